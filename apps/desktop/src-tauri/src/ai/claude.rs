@@ -1,5 +1,6 @@
 //! Anthropic Claude API provider for chart analysis
 
+use super::streaming::{read_sse_events, ChatDeltaSink};
 use super::{
     build_analysis_prompt, build_annotation_prompt, parse_annotation_response,
     build_enhanced_annotation_prompt, parse_enhanced_annotation_response,
@@ -7,7 +8,7 @@ use super::{
     AiError, AiErrorKind, ChartAnalysisResponse, ChartContext, AnnotationAnalysisResponse,
     EnhancedChartContext, EnhancedAnnotationAnalysisResponse,
     PortfolioInsightsContext, PortfolioInsightsResponse, ChatMessage, PortfolioChatResponse,
-    get_fallback, parse_retry_delay, calculate_backoff_delay, normalize_markdown_response,
+    get_fallback, parse_rate_limit_retry_after, calculate_backoff_delay, normalize_markdown_response,
     REQUEST_TIMEOUT_SECS, MAX_RETRIES, MAX_TOKENS, MAX_TOKENS_INSIGHTS, MAX_TOKENS_CHAT,
 };
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
@@ -67,14 +68,14 @@ struct ResponseContent {
     text: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy)]
 struct Usage {
     input_tokens: u32,
     output_tokens: u32,
 }
 
 /// Parse Claude API error response
-fn parse_error(status: u16, body: &str, model: &str) -> AiError {
+fn parse_error(status: u16, headers: &HeaderMap, body: &str, model: &str) -> AiError {
     let fallback = get_fallback("claude", model);
     let body_lower = body.to_lowercase();
 
@@ -83,7 +84,7 @@ fn parse_error(status: u16, body: &str, model: &str) -> AiError {
             if body_lower.contains("quota") || body_lower.contains("credit") {
                 AiError::quota_exceeded("Claude", model, fallback)
             } else {
-                let retry_after = parse_retry_delay(body);
+                let retry_after = parse_rate_limit_retry_after(headers, body);
                 AiError::rate_limit("Claude", model, retry_after)
             }
         }
@@ -184,8 +185,9 @@ pub async fn analyze(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -213,6 +215,8 @@ pub async fn analyze(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
         });
     }
 
@@ -292,8 +296,9 @@ pub async fn analyze_with_custom_prompt(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -317,6 +322,8 @@ pub async fn analyze_with_custom_prompt(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
         });
     }
 
@@ -402,8 +409,9 @@ pub async fn ocr_pdf(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -427,6 +435,8 @@ pub async fn ocr_pdf(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
         });
     }
 
@@ -506,8 +516,9 @@ pub async fn analyze_with_annotations(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -617,8 +628,9 @@ pub async fn analyze_enhanced(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -738,8 +750,9 @@ pub async fn analyze_portfolio(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -765,6 +778,8 @@ pub async fn analyze_portfolio(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
         });
     }
 
@@ -833,8 +848,9 @@ pub async fn analyze_opportunities(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -860,6 +876,8 @@ pub async fn analyze_opportunities(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
         });
     }
 
@@ -876,6 +894,74 @@ struct MultimodalChatRequest {
     messages: Vec<Message>,
 }
 
+/// Build the Messages API request body for a chat.
+/// Uses the multimodal format if any message has image attachments, otherwise text-only.
+fn chat_request_body(
+    model: &str,
+    messages: &[ChatMessage],
+    context: &PortfolioInsightsContext,
+) -> Result<serde_json::Value, AiError> {
+    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
+
+    let body = if has_images {
+        // Convert messages to multimodal Claude format
+        let claude_messages: Vec<Message> = messages
+            .iter()
+            .map(|m| {
+                let mut content: Vec<ContentBlock> = Vec::new();
+
+                // Add images first (if any)
+                for attachment in &m.attachments {
+                    content.push(ContentBlock::Image {
+                        source: ImageSource {
+                            source_type: "base64".to_string(),
+                            media_type: attachment.mime_type.clone(),
+                            data: attachment.data.clone(),
+                        },
+                    });
+                }
+
+                // Add text content
+                if !m.content.is_empty() {
+                    content.push(ContentBlock::Text {
+                        text: m.content.clone(),
+                    });
+                }
+
+                Message {
+                    role: m.role.clone(),
+                    content,
+                }
+            })
+            .collect();
+
+        serde_json::to_value(MultimodalChatRequest {
+            model: model.to_string(),
+            max_tokens: MAX_TOKENS_CHAT,
+            system: Some(build_chat_system_prompt(context)),
+            messages: claude_messages,
+        })
+    } else {
+        // Use simple text format for text-only messages
+        let claude_messages: Vec<TextMessage> = messages
+            .iter()
+            .map(|m| TextMessage {
+                role: m.role.clone(),
+                content: m.content.clone(),
+            })
+            .collect();
+
+        serde_json::to_value(TextMessagesRequest {
+            model: model.to_string(),
+            max_tokens: MAX_TOKENS_CHAT,
+            system: Some(build_chat_system_prompt(context)),
+            messages: claude_messages,
+        })
+    };
+
+    body.map_err(|e| AiError::other("Claude", model, &format!("JSON serialize error: {}", e)))
+}
+
 /// Chat with portfolio assistant using Claude
 /// Supports both text-only and multimodal (with images) messages
 pub async fn chat(
@@ -903,8 +989,7 @@ pub async fn chat(
         .build()
         .map_err(|e| AiError::network_error("Claude", model, &e.to_string()))?;
 
-    // Check if any message has image attachments
-    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
+    let request_body = chat_request_body(model, messages, context)?;
 
     let mut last_error = AiError::other("Claude", model, "No attempts made");
 
@@ -913,68 +998,7 @@ pub async fn chat(
             tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
         }
 
-        // Use multimodal format if we have images, otherwise use text-only
-        let response = if has_images {
-            // Convert messages to multimodal Claude format
-            let claude_messages: Vec<Message> = messages
-                .iter()
-                .map(|m| {
-                    let mut content: Vec<ContentBlock> = Vec::new();
-
-                    // Add images first (if any)
-                    for attachment in &m.attachments {
-                        content.push(ContentBlock::Image {
-                            source: ImageSource {
-                                source_type: "base64".to_string(),
-                                media_type: attachment.mime_type.clone(),
-                                data: attachment.data.clone(),
-                            },
-                        });
-                    }
-
-                    // Add text content
-                    if !m.content.is_empty() {
-                        content.push(ContentBlock::Text {
-                            text: m.content.clone(),
-                        });
-                    }
-
-                    Message {
-                        role: m.role.clone(),
-                        content,
-                    }
-                })
-                .collect();
-
-            let request_body = MultimodalChatRequest {
-                model: model.to_string(),
-                max_tokens: MAX_TOKENS_CHAT,
-                system: Some(build_chat_system_prompt(context)),
-                messages: claude_messages,
-            };
-
-            client.post(API_URL).json(&request_body).send().await
-        } else {
-            // Use simple text format for text-only messages
-            let claude_messages: Vec<TextMessage> = messages
-                .iter()
-                .map(|m| TextMessage {
-                    role: m.role.clone(),
-                    content: m.content.clone(),
-                })
-                .collect();
-
-            let request_body = TextMessagesRequest {
-                model: model.to_string(),
-                max_tokens: MAX_TOKENS_CHAT,
-                system: Some(build_chat_system_prompt(context)),
-                messages: claude_messages,
-            };
-
-            client.post(API_URL).json(&request_body).send().await
-        };
-
-        let response = match response {
+        let response = match client.post(API_URL).json(&request_body).send().await {
             Ok(resp) => resp,
             Err(e) => {
                 last_error = if e.is_timeout() {
@@ -994,8 +1018,9 @@ pub async fn chat(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -1019,6 +1044,171 @@ pub async fn chat(
             provider: "Claude".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.input_tokens + u.output_tokens),
+            prompt_tokens: data.usage.map(|u| u.input_tokens),
+            completion_tokens: data.usage.map(|u| u.output_tokens),
+            suggestions: Vec::new(),
+        });
+    }
+
+    Err(last_error)
+}
+
+
+/// Streaming event payload from the Messages API (only the fields we need)
+#[derive(Deserialize)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    delta: Option<StreamDelta>,
+    message: Option<StreamMessage>,
+    usage: Option<StreamUsage>,
+    error: Option<StreamError>,
+}
+
+#[derive(Deserialize)]
+struct StreamDelta {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamMessage {
+    usage: Option<StreamUsage>,
+}
+
+#[derive(Deserialize)]
+struct StreamUsage {
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct StreamError {
+    message: String,
+}
+
+/// Chat with portfolio assistant using Claude, streaming fragments to `on_delta`.
+pub async fn chat_stream<F>(
+    model: &str,
+    api_key: &str,
+    messages: &[ChatMessage],
+    context: &PortfolioInsightsContext,
+    mut on_delta: F,
+) -> Result<PortfolioChatResponse, AiError>
+where
+    F: ChatDeltaSink,
+{
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(api_key)
+            .map_err(|_| AiError::invalid_api_key("Claude", model))?,
+    );
+    headers.insert(
+        "anthropic-version",
+        HeaderValue::from_static("2023-06-01"),
+    );
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    // No total timeout: a long answer may stream for longer than REQUEST_TIMEOUT_SECS
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AiError::network_error("Claude", model, &e.to_string()))?;
+
+    let mut request_body = chat_request_body(model, messages, context)?;
+    request_body["stream"] = serde_json::Value::Bool(true);
+
+    let mut last_error = AiError::other("Claude", model, "No attempts made");
+
+    // Retries are only possible before the first token has been received
+    for attempt in 0..=MAX_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
+        }
+
+        let response = match client.post(API_URL).json(&request_body).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_error = if e.is_timeout() {
+                    AiError::network_error("Claude", model, "Zeitüberschreitung")
+                } else if e.is_connect() {
+                    AiError::network_error("Claude", model, "Verbindung fehlgeschlagen")
+                } else {
+                    AiError::network_error("Claude", model, &e.to_string())
+                };
+
+                if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                    continue;
+                }
+                return Err(last_error);
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
+
+            if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                continue;
+            }
+            return Err(last_error);
+        }
+
+        let mut response_text = String::new();
+        let mut input_tokens: Option<u32> = None;
+        let mut output_tokens: Option<u32> = None;
+        let mut stream_error: Option<String> = None;
+
+        read_sse_events(response, "Claude", model, |data| {
+            let Ok(event) = serde_json::from_str::<StreamEvent>(data) else {
+                return;
+            };
+            match event.event_type.as_str() {
+                "message_start" => {
+                    if let Some(usage) = event.message.and_then(|m| m.usage) {
+                        input_tokens = usage.input_tokens;
+                        output_tokens = usage.output_tokens;
+                    }
+                }
+                "content_block_delta" => {
+                    if let Some(text) = event.delta.and_then(|d| d.text) {
+                        on_delta(&text);
+                        response_text.push_str(&text);
+                    }
+                }
+                "message_delta" => {
+                    if let Some(tokens) = event.usage.and_then(|u| u.output_tokens) {
+                        output_tokens = Some(tokens);
+                    }
+                }
+                "error" => {
+                    stream_error = event.error.map(|e| e.message);
+                }
+                _ => {}
+            }
+        })
+        .await?;
+
+        if let Some(message) = stream_error {
+            return Err(AiError::server_error("Claude", model, &message));
+        }
+
+        let tokens_used = match (input_tokens, output_tokens) {
+            (None, None) => None,
+            (i, o) => Some(i.unwrap_or(0) + o.unwrap_or(0)),
+        };
+
+        return Ok(PortfolioChatResponse {
+            response: response_text,
+            provider: "Claude".to_string(),
+            model: model.to_string(),
+            tokens_used,
+            prompt_tokens: input_tokens,
+            completion_tokens: output_tokens,
             suggestions: Vec::new(),
         });
     }
@@ -1088,8 +1278,9 @@ pub async fn complete_text(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
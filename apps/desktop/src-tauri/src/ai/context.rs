@@ -844,6 +844,12 @@ pub fn load_provider_status_for_ai(conn: &Connection) -> Option<QuoteProviderSta
 
     let outdated_count = outdated.len();
 
+    let sync_errors = crate::commands::quotes::load_quote_sync_errors(conn)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| format!("{} ({}): {}", e.name, e.provider, e.error))
+        .collect();
+
     let quote_sync = QuoteSyncInfo {
         held_count,
         synced_today_count,
@@ -857,6 +863,7 @@ pub fn load_provider_status_for_ai(conn: &Connection) -> Option<QuoteProviderSta
         cannot_sync_count,
         missing_api_keys,
         issues,
+        sync_errors,
         quote_sync,
     })
 }
@@ -1,5 +1,6 @@
 //! Google Gemini API provider for chart analysis
 
+use super::streaming::{read_sse_events, ChatDeltaSink};
 use super::{
     build_analysis_prompt, build_annotation_prompt, parse_annotation_response,
     build_enhanced_annotation_prompt, parse_enhanced_annotation_response,
@@ -21,6 +22,13 @@ fn api_url(model: &str, api_key: &str) -> String {
     )
 }
 
+fn stream_api_url(model: &str, api_key: &str) -> String {
+    format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse&key={}",
+        model, api_key
+    )
+}
+
 #[derive(Serialize)]
 struct GenerateContentRequest {
     contents: Vec<Content>,
@@ -67,10 +75,14 @@ struct ResponsePart {
     text: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy)]
 struct UsageMetadata {
     #[serde(rename = "totalTokenCount")]
     total_token_count: Option<u32>,
+    #[serde(rename = "promptTokenCount")]
+    prompt_token_count: Option<u32>,
+    #[serde(rename = "candidatesTokenCount")]
+    candidates_token_count: Option<u32>,
 }
 
 /// Parse Gemini API error response
@@ -201,6 +213,8 @@ pub async fn analyze(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
         });
     }
 
@@ -297,6 +311,8 @@ pub async fn analyze_with_custom_prompt(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
         });
     }
 
@@ -394,6 +410,8 @@ pub async fn ocr_pdf(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
         });
     }
 
@@ -717,6 +735,8 @@ pub async fn analyze_portfolio(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
         });
     }
 
@@ -807,6 +827,8 @@ pub async fn analyze_opportunities(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
         });
     }
 
@@ -821,6 +843,77 @@ struct MultimodalChatRequest {
     system_instruction: Option<SystemInstruction>,
 }
 
+/// Build the generateContent request body for a chat.
+/// Uses the multimodal format if any message has image attachments, otherwise text-only.
+fn chat_request_body(
+    model: &str,
+    messages: &[ChatMessage],
+    context: &PortfolioInsightsContext,
+) -> Result<serde_json::Value, AiError> {
+    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
+
+    let body = if has_images {
+        // Convert messages to multimodal Gemini format
+        let gemini_contents: Vec<Content> = messages
+            .iter()
+            .map(|m| {
+                let mut parts: Vec<Part> = Vec::new();
+
+                // Add images first
+                for attachment in &m.attachments {
+                    parts.push(Part::InlineData {
+                        inline_data: InlineData {
+                            mime_type: attachment.mime_type.clone(),
+                            data: attachment.data.clone(),
+                        },
+                    });
+                }
+
+                // Add text content
+                if !m.content.is_empty() {
+                    parts.push(Part::Text { text: m.content.clone() });
+                }
+
+                Content {
+                    role: if m.role == "assistant" { "model".to_string() } else { m.role.clone() },
+                    parts,
+                }
+            })
+            .collect();
+
+        serde_json::to_value(MultimodalChatRequest {
+            contents: gemini_contents,
+            system_instruction: Some(SystemInstruction {
+                parts: vec![TextPart {
+                    text: build_chat_system_prompt(context),
+                }],
+            }),
+        })
+    } else {
+        // Use simple text format for text-only messages
+        let gemini_contents: Vec<TextContent> = messages
+            .iter()
+            .map(|m| TextContent {
+                role: if m.role == "assistant" { "model".to_string() } else { m.role.clone() },
+                parts: vec![TextPart {
+                    text: m.content.clone(),
+                }],
+            })
+            .collect();
+
+        serde_json::to_value(TextGenerateContentRequest {
+            contents: gemini_contents,
+            system_instruction: Some(SystemInstruction {
+                parts: vec![TextPart {
+                    text: build_chat_system_prompt(context),
+                }],
+            }),
+        })
+    };
+
+    body.map_err(|e| AiError::other("Gemini", model, &format!("JSON serialize error: {}", e)))
+}
+
 /// Chat with portfolio assistant using Gemini
 /// Supports both text-only and multimodal (with images) messages
 pub async fn chat(
@@ -839,9 +932,7 @@ pub async fn chat(
         .build()
         .map_err(|e| AiError::network_error("Gemini", model, &e.to_string()))?;
 
-    // Check if any message has image attachments
-    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
-
+    let request_body = chat_request_body(model, messages, context)?;
     let api_endpoint = api_url(model, api_key);
     let mut last_error = AiError::other("Gemini", model, "No attempts made");
 
@@ -850,71 +941,7 @@ pub async fn chat(
             tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
         }
 
-        // Use multimodal format if we have images, otherwise use text-only
-        let response = if has_images {
-            // Convert messages to multimodal Gemini format
-            let gemini_contents: Vec<Content> = messages
-                .iter()
-                .map(|m| {
-                    let mut parts: Vec<Part> = Vec::new();
-
-                    // Add images first
-                    for attachment in &m.attachments {
-                        parts.push(Part::InlineData {
-                            inline_data: InlineData {
-                                mime_type: attachment.mime_type.clone(),
-                                data: attachment.data.clone(),
-                            },
-                        });
-                    }
-
-                    // Add text content
-                    if !m.content.is_empty() {
-                        parts.push(Part::Text { text: m.content.clone() });
-                    }
-
-                    Content {
-                        role: if m.role == "assistant" { "model".to_string() } else { m.role.clone() },
-                        parts,
-                    }
-                })
-                .collect();
-
-            let request_body = MultimodalChatRequest {
-                contents: gemini_contents,
-                system_instruction: Some(SystemInstruction {
-                    parts: vec![TextPart {
-                        text: build_chat_system_prompt(context),
-                    }],
-                }),
-            };
-
-            client.post(&api_endpoint).json(&request_body).send().await
-        } else {
-            // Use simple text format for text-only messages
-            let gemini_contents: Vec<TextContent> = messages
-                .iter()
-                .map(|m| TextContent {
-                    role: if m.role == "assistant" { "model".to_string() } else { m.role.clone() },
-                    parts: vec![TextPart {
-                        text: m.content.clone(),
-                    }],
-                })
-                .collect();
-
-            let request_body = TextGenerateContentRequest {
-                contents: gemini_contents,
-                system_instruction: Some(SystemInstruction {
-                    parts: vec![TextPart {
-                        text: build_chat_system_prompt(context),
-                    }],
-                }),
-            };
-
-            client.post(&api_endpoint).json(&request_body).send().await
-        };
-
-        let response = match response {
+        let response = match client.post(&api_endpoint).json(&request_body).send().await {
             Ok(resp) => resp,
             Err(e) => {
                 last_error = if e.is_timeout() {
@@ -962,6 +989,112 @@ pub async fn chat(
             provider: "Gemini".to_string(),
             model: model.to_string(),
             tokens_used: data.usage_metadata.and_then(|u| u.total_token_count),
+            prompt_tokens: data.usage_metadata.and_then(|u| u.prompt_token_count),
+            completion_tokens: data.usage_metadata.and_then(|u| u.candidates_token_count),
+            suggestions: Vec::new(),
+        });
+    }
+
+    Err(last_error)
+}
+
+/// Chat with portfolio assistant using Gemini, streaming fragments to `on_delta`.
+pub async fn chat_stream<F>(
+    model: &str,
+    api_key: &str,
+    messages: &[ChatMessage],
+    context: &PortfolioInsightsContext,
+    mut on_delta: F,
+) -> Result<PortfolioChatResponse, AiError>
+where
+    F: ChatDeltaSink,
+{
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    // No total timeout: a long answer may stream for longer than REQUEST_TIMEOUT_SECS
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AiError::network_error("Gemini", model, &e.to_string()))?;
+
+    let request_body = chat_request_body(model, messages, context)?;
+    let api_endpoint = stream_api_url(model, api_key);
+    let mut last_error = AiError::other("Gemini", model, "No attempts made");
+
+    // Retries are only possible before the first token has been received
+    for attempt in 0..=MAX_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
+        }
+
+        let response = match client.post(&api_endpoint).json(&request_body).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_error = if e.is_timeout() {
+                    AiError::network_error("Gemini", model, "Zeitüberschreitung")
+                } else if e.is_connect() {
+                    AiError::network_error("Gemini", model, "Verbindung fehlgeschlagen")
+                } else {
+                    AiError::network_error("Gemini", model, &e.to_string())
+                };
+
+                if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                    continue;
+                }
+                return Err(last_error);
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            last_error = parse_error(status.as_u16(), &body, model);
+
+            if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                continue;
+            }
+            return Err(last_error);
+        }
+
+        let mut response_text = String::new();
+        let mut usage: Option<UsageMetadata> = None;
+
+        // Each event is a complete GenerateContentResponse holding the next fragment
+        read_sse_events(response, "Gemini", model, |data| {
+            let Ok(chunk) = serde_json::from_str::<GenerateContentResponse>(data) else {
+                return;
+            };
+
+            // Every chunk carries the running usage, the last one is final
+            if chunk.usage_metadata.is_some() {
+                usage = chunk.usage_metadata;
+            }
+
+            let text: String = chunk
+                .candidates
+                .and_then(|c| c.into_iter().next())
+                .and_then(|c| c.content)
+                .and_then(|c| c.parts)
+                .map(|parts| parts.into_iter().filter_map(|p| p.text).collect())
+                .unwrap_or_default();
+
+            if !text.is_empty() {
+                on_delta(&text);
+                response_text.push_str(&text);
+            }
+        })
+        .await?;
+
+        return Ok(PortfolioChatResponse {
+            response: response_text,
+            provider: "Gemini".to_string(),
+            model: model.to_string(),
+            tokens_used: usage.and_then(|u| u.total_token_count),
+            prompt_tokens: usage.and_then(|u| u.prompt_token_count),
+            completion_tokens: usage.and_then(|u| u.candidates_token_count),
             suggestions: Vec::new(),
         });
     }
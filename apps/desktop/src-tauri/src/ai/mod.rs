@@ -1,20 +1,24 @@
 //! AI-powered chart analysis module.
 //!
 //! Supports multiple providers: Claude (Anthropic), GPT-5 (OpenAI), Gemini (Google), Perplexity (Sonar)
+//! and local models via Ollama
 //!
 //! # Module Structure
 //!
 //! - `types`: All type definitions (requests, responses, errors, etc.)
 //! - `prompts`: Prompt building functions for different analysis types
 //! - `parsing`: Response parsing and utility functions
+//! - `streaming`: Server-Sent Events parsing for streamed chat responses
 //! - `context`: Portfolio context loading for AI analysis
 //! - `command_parser`: ChatBot command parsing and execution
 //! - `models`: Vision model registry and metadata
-//! - Provider implementations: `claude`, `openai`, `gemini`, `perplexity`
+//! - `usage`: Token usage accounting and monthly budgets
+//! - Provider implementations: `claude`, `openai`, `gemini`, `perplexity`, `ollama`
 
 // Provider implementations
 pub mod claude;
 pub mod gemini;
+pub mod ollama;
 pub mod openai;
 pub mod perplexity;
 
@@ -23,12 +27,14 @@ pub mod models;
 pub mod types;
 pub mod prompts;
 pub mod parsing;
+pub mod streaming;
 
 // Portfolio context and command parsing
 pub mod command_parser;
 pub mod context;
 pub mod normalizer;
 pub mod query_templates;
+pub mod usage;
 pub mod user_templates;
 
 // ============================================================================
@@ -84,6 +90,8 @@ pub use prompts::{
 
 pub use parsing::{
     parse_retry_delay,
+    parse_retry_after_headers,
+    parse_rate_limit_retry_after,
     parse_annotation_response,
     parse_enhanced_annotation_response,
     calculate_backoff_delay,
@@ -350,6 +358,11 @@ pub async fn list_gemini_models(api_key: &str) -> Result<Vec<AiModelInfo>> {
     Ok(models)
 }
 
+/// Fetch models installed on the local Ollama server
+pub async fn list_ollama_models(base_url: &str) -> Result<Vec<AiModelInfo>> {
+    ollama::list_models(base_url).await
+}
+
 /// Fetch available models from Perplexity API
 /// Note: Perplexity doesn't have a models endpoint, so we return hardcoded models
 pub async fn list_perplexity_models(_api_key: &str) -> Result<Vec<AiModelInfo>> {
@@ -148,6 +148,29 @@ pub const VISION_MODELS: &[VisionModel] = &[
         description: "Schnell + Web-Suche",
         provider: "perplexity",
     },
+
+    // -------------------------------------------------------------------------
+    // Ollama (local) - https://ollama.com/search?c=vision
+    // Runs offline, quality depends on the installed model size
+    // -------------------------------------------------------------------------
+    VisionModel {
+        id: "llama3.2-vision",
+        name: "Llama 3.2 Vision",
+        description: "Lokal, Vision (11B)",
+        provider: "ollama",
+    },
+    VisionModel {
+        id: "gemma3",
+        name: "Gemma 3",
+        description: "Lokal, Vision, kompakt",
+        provider: "ollama",
+    },
+    VisionModel {
+        id: "llava",
+        name: "LLaVA",
+        description: "Lokal, Vision",
+        provider: "ollama",
+    },
 ];
 
 // ============================================================================
@@ -203,6 +226,7 @@ pub const FALLBACK_CHAINS: &[(&str, &[&str])] = &[
     ("openai", &["gpt-5-mini", "gpt-4.1", "gpt-4o", "gpt-4o-mini"]),
     ("gemini", &["gemini-2.5-flash", "gemini-2.5-pro", "gemini-3-flash-preview", "gemini-3-pro-preview"]),
     ("perplexity", &["sonar-pro", "sonar"]),
+    ("ollama", &["llama3.2-vision", "gemma3", "llava"]),
 ];
 
 // ============================================================================
@@ -215,6 +239,7 @@ pub const DEFAULT_MODELS: &[(&str, &str)] = &[
     ("openai", "gpt-5-mini"),
     ("gemini", "gemini-2.5-flash"),
     ("perplexity", "sonar-pro"),
+    ("ollama", "llama3.2-vision"),
 ];
 
 // ============================================================================
@@ -264,11 +289,11 @@ pub fn is_valid_model(model: &str) -> bool {
 
 /// Check if a model has vision/image input support
 ///
-/// Returns true if the model is in the VISION_MODELS registry.
-/// This is the same as `is_valid_model` but with a more semantic name
-/// for use cases where you need to check image support specifically.
+/// Returns true if the model is in the VISION_MODELS registry. Ollama tags
+/// (`llava:latest`, `gemma3:12b`) are matched by their model name.
 pub fn has_vision_support(model: &str) -> bool {
-    VISION_MODELS.iter().any(|m| m.id == model)
+    let name = model.split_once(':').map_or(model, |(name, _)| name);
+    VISION_MODELS.iter().any(|m| m.id == name)
 }
 
 /// Get the provider for a model ID
@@ -327,6 +352,17 @@ mod tests {
         assert_eq!(get_default("claude"), "claude-sonnet-4-5-20250514");
         assert_eq!(get_default("openai"), "gpt-5-mini");
         assert_eq!(get_default("gemini"), "gemini-2.5-flash");
+        assert_eq!(get_default("ollama"), "llama3.2-vision");
+    }
+
+    #[test]
+    fn test_ollama_vision_models() {
+        let ollama_models = get_models_for_provider("ollama");
+        assert!(ollama_models.iter().any(|m| m.id == "llava"));
+        assert!(has_vision_support("llama3.2-vision"));
+        assert!(has_vision_support("llava:latest"));
+        assert!(has_vision_support("gemma3:12b"));
+        assert!(!has_vision_support("mistral:7b"));
     }
 
     #[test]
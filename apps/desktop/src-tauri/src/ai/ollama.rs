@@ -0,0 +1,326 @@
+//! Ollama provider for running the portfolio assistant locally
+//!
+//! Talks to a local Ollama server (`/api/chat`). No API key is needed and no
+//! data leaves the machine. The base URL defaults to `http://localhost:11434`
+//! and can be overridden via the `ai_ollama_base_url` key in `pp_settings`.
+
+use super::{
+    build_chat_system_prompt, calculate_backoff_delay, AiError, AiErrorKind, AiModelInfo,
+    ChatMessage as AiChatMessage, PortfolioChatResponse, PortfolioInsightsContext,
+    MAX_RETRIES, MAX_TOKENS_CHAT,
+};
+use crate::db;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default Ollama server address
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// `pp_settings` key holding a custom Ollama base URL
+pub const BASE_URL_SETTING_KEY: &str = "ai_ollama_base_url";
+
+/// Local models are much slower than hosted APIs, especially on CPU
+const OLLAMA_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    options: ChatOptions,
+}
+
+#[derive(Serialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+    /// Base64-encoded images (without data URL prefix), vision models only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ChatOptions {
+    num_predict: u32,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: Option<ResponseMessage>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    models: Vec<TagModel>,
+}
+
+#[derive(Deserialize)]
+struct TagModel {
+    name: String,
+    details: Option<TagModelDetails>,
+}
+
+#[derive(Deserialize)]
+struct TagModelDetails {
+    parameter_size: Option<String>,
+}
+
+/// Read the Ollama base URL from `pp_settings`, falling back to the default
+pub fn get_base_url(conn: &Connection) -> String {
+    conn.query_row(
+        "SELECT value FROM pp_settings WHERE key = ?",
+        params![BASE_URL_SETTING_KEY],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .map(|url| url.trim().trim_end_matches('/').to_string())
+    .filter(|url| !url.is_empty())
+    .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+}
+
+/// Load the configured base URL from the global database connection
+pub fn load_base_url() -> String {
+    db::get_connection()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(get_base_url))
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+}
+
+/// Parse Ollama error response
+fn parse_error(status: u16, body: &str, model: &str) -> AiError {
+    // Ollama returns {"error": "..."} for all failures
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(String::from))
+        .unwrap_or_else(|| body.chars().take(200).collect());
+
+    match status {
+        404 => {
+            let mut error = AiError::model_not_found("Ollama", model, None);
+            error.message = format!(
+                "Modell '{}' ist lokal nicht installiert. Installiere es mit `ollama pull {}`.",
+                model, model
+            );
+            error
+        }
+        500..=599 => AiError::server_error("Ollama", model, &message),
+        _ => AiError::other("Ollama", model, &format!("HTTP {}: {}", status, message)),
+    }
+}
+
+/// Check if error is retryable (the server may still be loading the model)
+fn is_retryable(err: &AiError) -> bool {
+    matches!(err.kind, AiErrorKind::ServerError)
+}
+
+fn build_client(model: &str) -> Result<reqwest::Client, AiError> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(OLLAMA_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AiError::network_error("Ollama", model, &e.to_string()))
+}
+
+fn build_chat_request(model: &str, messages: &[AiChatMessage], system_prompt: String) -> ChatRequest {
+    let mut ollama_messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: system_prompt,
+        images: Vec::new(),
+    }];
+
+    for m in messages {
+        ollama_messages.push(ChatMessage {
+            role: m.role.clone(),
+            content: m.content.clone(),
+            images: m.attachments.iter().map(|a| a.data.clone()).collect(),
+        });
+    }
+
+    ChatRequest {
+        model: model.to_string(),
+        messages: ollama_messages,
+        stream: false,
+        options: ChatOptions {
+            num_predict: MAX_TOKENS_CHAT,
+        },
+    }
+}
+
+/// Chat with the portfolio assistant using a local Ollama model
+pub async fn chat(
+    base_url: &str,
+    model: &str,
+    messages: &[AiChatMessage],
+    context: &PortfolioInsightsContext,
+) -> Result<PortfolioChatResponse, AiError> {
+    let client = build_client(model)?;
+    let url = format!("{}/api/chat", base_url.trim_end_matches('/'));
+    let request_body = build_chat_request(model, messages, build_chat_system_prompt(context));
+
+    let mut last_error = AiError::other("Ollama", model, "No attempts made");
+
+    for attempt in 0..=MAX_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
+        }
+
+        let response = match client.post(&url).json(&request_body).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                // A local server that is not running will not come up by retrying
+                return Err(if e.is_timeout() {
+                    AiError::network_error("Ollama", model, "Zeitüberschreitung")
+                } else if e.is_connect() {
+                    AiError::network_error(
+                        "Ollama",
+                        model,
+                        &format!("Ollama nicht erreichbar unter {}. Läuft `ollama serve`?", base_url),
+                    )
+                } else {
+                    AiError::network_error("Ollama", model, &e.to_string())
+                });
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            last_error = parse_error(status.as_u16(), &body, model);
+
+            if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                continue;
+            }
+            return Err(last_error);
+        }
+
+        let data: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| AiError::other("Ollama", model, &format!("JSON parse error: {}", e)))?;
+
+        return Ok(chat_response_from(data, model));
+    }
+
+    Err(last_error)
+}
+
+fn chat_response_from(data: ChatResponse, model: &str) -> PortfolioChatResponse {
+    let tokens_used = match (data.prompt_eval_count, data.eval_count) {
+        (None, None) => None,
+        (p, c) => Some(p.unwrap_or(0) + c.unwrap_or(0)),
+    };
+
+    PortfolioChatResponse {
+        response: data.message.map(|m| m.content).unwrap_or_default(),
+        provider: "Ollama".to_string(),
+        model: model.to_string(),
+        tokens_used,
+        prompt_tokens: data.prompt_eval_count,
+        completion_tokens: data.eval_count,
+        suggestions: Vec::new(),
+    }
+}
+
+/// List models installed on the local Ollama server
+pub async fn list_models(base_url: &str) -> anyhow::Result<Vec<AiModelInfo>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+
+    let response = client
+        .get(format!("{}/api/tags", base_url.trim_end_matches('/')))
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Ollama nicht erreichbar unter {}: {}", base_url, e))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Ollama API error: {}", response.status()));
+    }
+
+    let data: TagsResponse = response.json().await?;
+
+    Ok(data.models.into_iter().map(model_info_from).collect())
+}
+
+fn model_info_from(model: TagModel) -> AiModelInfo {
+    // Same check as the chat image validation, so listed vision models accept images
+    let supports_vision = super::has_vision_support(&model.name);
+    let description = match model.details.and_then(|d| d.parameter_size) {
+        Some(size) => format!("Lokal ({})", size),
+        None => "Lokal".to_string(),
+    };
+
+    AiModelInfo {
+        name: model.name.clone(),
+        id: model.name,
+        description,
+        supports_vision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ChatImageAttachment;
+
+    #[test]
+    fn test_get_base_url() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE pp_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);")
+            .unwrap();
+        assert_eq!(get_base_url(&conn), DEFAULT_BASE_URL);
+
+        conn.execute(
+            "INSERT INTO pp_settings (key, value) VALUES (?, ?)",
+            params![BASE_URL_SETTING_KEY, "http://192.168.1.10:11434/"],
+        )
+        .unwrap();
+        assert_eq!(get_base_url(&conn), "http://192.168.1.10:11434");
+    }
+
+    #[test]
+    fn test_chat_request_includes_images() {
+        let messages = vec![AiChatMessage {
+            role: "user".to_string(),
+            content: "Was siehst du?".to_string(),
+            attachments: vec![ChatImageAttachment {
+                data: "aGVsbG8=".to_string(),
+                mime_type: "image/png".to_string(),
+                filename: None,
+            }],
+        }];
+        let request = build_chat_request("llava", &messages, "System".to_string());
+        let json = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(json["stream"], false);
+        assert_eq!(json["messages"][0]["role"], "system");
+        assert!(json["messages"][0].get("images").is_none());
+        assert_eq!(json["messages"][1]["images"][0], "aGVsbG8=");
+    }
+
+    #[test]
+    fn test_chat_response_tokens() {
+        let data: ChatResponse = serde_json::from_str(
+            r#"{"model":"llava","message":{"role":"assistant","content":"Hallo"},"done":true,"prompt_eval_count":12,"eval_count":30}"#,
+        )
+        .unwrap();
+        let response = chat_response_from(data, "llava");
+        assert_eq!(response.response, "Hallo");
+        assert_eq!(response.tokens_used, Some(42));
+        assert_eq!(response.prompt_tokens, Some(12));
+    }
+
+    #[test]
+    fn test_parse_error_model_missing() {
+        let err = parse_error(404, r#"{"error":"model 'llava' not found"}"#, "llava");
+        assert_eq!(err.kind, AiErrorKind::ModelNotFound);
+        assert!(err.message.contains("ollama pull llava"));
+    }
+}
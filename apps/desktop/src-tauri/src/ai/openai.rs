@@ -1,5 +1,6 @@
 //! OpenAI GPT-5 Vision API provider for chart analysis
 
+use super::streaming::{read_sse_events, ChatDeltaSink};
 use super::{
     build_analysis_prompt, build_annotation_prompt, parse_annotation_response,
     build_enhanced_annotation_prompt, parse_enhanced_annotation_response,
@@ -8,7 +9,7 @@ use super::{
     EnhancedChartContext, EnhancedAnnotationAnalysisResponse,
     PortfolioInsightsContext, PortfolioInsightsResponse,
     ChatMessage as AiChatMessage, PortfolioChatResponse,
-    get_fallback, parse_retry_delay, calculate_backoff_delay, normalize_markdown_response,
+    get_fallback, parse_rate_limit_retry_after, calculate_backoff_delay, normalize_markdown_response,
     REQUEST_TIMEOUT_SECS, MAX_RETRIES, MAX_TOKENS, MAX_TOKENS_INSIGHTS, MAX_TOKENS_CHAT,
 };
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
@@ -81,9 +82,13 @@ struct ResponseMessage {
     content: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy)]
 struct Usage {
     total_tokens: u32,
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
 }
 
 // ============================================================================
@@ -144,13 +149,17 @@ struct ResponsesContent {
 }
 
 /// Usage stats from Responses API
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy)]
 struct ResponsesUsage {
     total_tokens: u32,
+    #[serde(default, rename = "input_tokens")]
+    prompt_tokens: u32,
+    #[serde(default, rename = "output_tokens")]
+    completion_tokens: u32,
 }
 
 /// Parse OpenAI API error response
-fn parse_error(status: u16, body: &str, model: &str) -> AiError {
+fn parse_error(status: u16, headers: &HeaderMap, body: &str, model: &str) -> AiError {
     let fallback = get_fallback("openai", model);
     let body_lower = body.to_lowercase();
 
@@ -161,7 +170,7 @@ fn parse_error(status: u16, body: &str, model: &str) -> AiError {
                body_lower.contains("exceeded") {
                 AiError::quota_exceeded("OpenAI", model, fallback)
             } else {
-                let retry_after = parse_retry_delay(body);
+                let retry_after = parse_rate_limit_retry_after(headers, body);
                 AiError::rate_limit("OpenAI", model, retry_after)
             }
         }
@@ -257,8 +266,9 @@ pub async fn analyze(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -286,6 +296,8 @@ pub async fn analyze(
             provider: "OpenAI".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -359,8 +371,9 @@ pub async fn analyze_with_custom_prompt(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -384,6 +397,8 @@ pub async fn analyze_with_custom_prompt(
             provider: "OpenAI".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -457,8 +472,9 @@ pub async fn analyze_with_annotations(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -561,8 +577,9 @@ pub async fn analyze_enhanced(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -699,8 +716,9 @@ pub async fn analyze_portfolio(
 
             let status = response.status();
             if !status.is_success() {
+                let headers = response.headers().clone();
                 let body = response.text().await.unwrap_or_default();
-                last_error = parse_error(status.as_u16(), &body, model);
+                last_error = parse_error(status.as_u16(), &headers, &body, model);
 
                 if attempt < MAX_RETRIES && is_retryable(&last_error) {
                     continue;
@@ -721,6 +739,8 @@ pub async fn analyze_portfolio(
                 provider: "OpenAI".to_string(),
                 model: model.to_string(),
                 tokens_used: data.usage.map(|u| u.total_tokens),
+                prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+                completion_tokens: data.usage.map(|u| u.completion_tokens),
             });
         }
 
@@ -754,8 +774,9 @@ pub async fn analyze_portfolio(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -781,6 +802,8 @@ pub async fn analyze_portfolio(
             provider: "OpenAI".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -846,8 +869,9 @@ pub async fn analyze_opportunities(
 
             let status = response.status();
             if !status.is_success() {
+                let headers = response.headers().clone();
                 let body = response.text().await.unwrap_or_default();
-                last_error = parse_error(status.as_u16(), &body, model);
+                last_error = parse_error(status.as_u16(), &headers, &body, model);
 
                 if attempt < MAX_RETRIES && is_retryable(&last_error) {
                     continue;
@@ -868,6 +892,8 @@ pub async fn analyze_opportunities(
                 provider: "OpenAI".to_string(),
                 model: model.to_string(),
                 tokens_used: data.usage.map(|u| u.total_tokens),
+                prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+                completion_tokens: data.usage.map(|u| u.completion_tokens),
             });
         }
 
@@ -901,8 +927,9 @@ pub async fn analyze_opportunities(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -928,6 +955,8 @@ pub async fn analyze_opportunities(
             provider: "OpenAI".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -949,6 +978,115 @@ struct MultimodalChatCompletionRequest {
     messages: Vec<MultimodalChatMessage>,
 }
 
+/// Build the request for a chat, returning the endpoint URL and JSON body.
+///
+/// GPT-5 uses the Responses API (text-only), older models the Chat Completions API
+/// (multimodal if any message has images, with web search for o3/o4).
+fn chat_request_body(
+    model: &str,
+    messages: &[AiChatMessage],
+    context: &PortfolioInsightsContext,
+) -> Result<(&'static str, serde_json::Value), AiError> {
+    let system_prompt = build_chat_system_prompt(context);
+    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
+
+    let (url, body) = if uses_responses_api(model) {
+        // Build messages for Responses API
+        let responses_messages: Vec<ResponsesMessage> = messages
+            .iter()
+            .map(|m| ResponsesMessage {
+                msg_type: "message".to_string(),
+                role: m.role.clone(),
+                content: m.content.clone(),
+            })
+            .collect();
+
+        let request_body = ResponsesApiRequest {
+            model: model.to_string(),
+            input: ResponsesInput::Messages(responses_messages),
+            instructions: Some(system_prompt),
+            max_output_tokens: Some(MAX_TOKENS_CHAT),
+        };
+        (RESPONSES_API_URL, serde_json::to_value(request_body))
+    } else if has_images {
+        // Build multimodal messages with images
+        let mut openai_messages: Vec<MultimodalChatMessage> = vec![
+            MultimodalChatMessage {
+                role: "system".to_string(),
+                content: vec![ContentPart::Text { text: system_prompt }],
+            }
+        ];
+
+        for m in messages {
+            let mut content: Vec<ContentPart> = Vec::new();
+
+            // Add images first
+            for attachment in &m.attachments {
+                content.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: format!("data:{};base64,{}", attachment.mime_type, attachment.data),
+                    },
+                });
+            }
+
+            // Add text content
+            if !m.content.is_empty() {
+                content.push(ContentPart::Text { text: m.content.clone() });
+            }
+
+            openai_messages.push(MultimodalChatMessage {
+                role: m.role.clone(),
+                content,
+            });
+        }
+
+        let request_body = MultimodalChatCompletionRequest {
+            model: model.to_string(),
+            max_tokens: MAX_TOKENS_CHAT,
+            messages: openai_messages,
+        };
+        (CHAT_API_URL, serde_json::to_value(request_body))
+    } else {
+        // Text-only messages
+        let mut openai_messages = vec![TextChatMessage {
+            role: "system".to_string(),
+            content: system_prompt,
+        }];
+
+        for m in messages {
+            openai_messages.push(TextChatMessage {
+                role: m.role.clone(),
+                content: m.content.clone(),
+            });
+        }
+
+        // Send request with or without web search tool
+        if supports_web_search(model) {
+            let request_body = TextChatWithToolsRequest {
+                model: model.to_string(),
+                max_tokens: MAX_TOKENS_CHAT,
+                messages: openai_messages,
+                tools: vec![WebSearchTool {
+                    tool_type: "web_search_preview".to_string(),
+                    search_context_size: Some("medium".to_string()),
+                }],
+            };
+            (CHAT_API_URL, serde_json::to_value(request_body))
+        } else {
+            let request_body = TextChatCompletionRequest {
+                model: model.to_string(),
+                max_tokens: MAX_TOKENS_CHAT,
+                messages: openai_messages,
+            };
+            (CHAT_API_URL, serde_json::to_value(request_body))
+        }
+    };
+
+    let body = body
+        .map_err(|e| AiError::other("OpenAI", model, &format!("JSON serialize error: {}", e)))?;
+    Ok((url, body))
+}
+
 /// Chat with portfolio assistant using OpenAI
 /// Supports both text-only and multimodal (with images) messages
 pub async fn chat(
@@ -972,12 +1110,7 @@ pub async fn chat(
         .build()
         .map_err(|e| AiError::network_error("OpenAI", model, &e.to_string()))?;
 
-    let system_prompt = build_chat_system_prompt(context);
-    let use_responses_api = uses_responses_api(model);
-    let use_web_search = supports_web_search(model);
-
-    // Check if any message has image attachments
-    let has_images = messages.iter().any(|m| !m.attachments.is_empty());
+    let (url, request_body) = chat_request_body(model, messages, context)?;
 
     let mut last_error = AiError::other("OpenAI", model, "No attempts made");
 
@@ -986,54 +1119,38 @@ pub async fn chat(
             tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
         }
 
-        // GPT-5 uses Responses API (text-only for now)
-        if use_responses_api {
-            // Build messages for Responses API
-            let responses_messages: Vec<ResponsesMessage> = messages
-                .iter()
-                .map(|m| ResponsesMessage {
-                    msg_type: "message".to_string(),
-                    role: m.role.clone(),
-                    content: m.content.clone(),
-                })
-                .collect();
-
-            let request_body = ResponsesApiRequest {
-                model: model.to_string(),
-                input: ResponsesInput::Messages(responses_messages),
-                instructions: Some(system_prompt.clone()),
-                max_output_tokens: Some(MAX_TOKENS_CHAT),
-            };
-
-            let response = match client.post(RESPONSES_API_URL).json(&request_body).send().await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    last_error = if e.is_timeout() {
-                        AiError::network_error("OpenAI", model, "Zeitüberschreitung")
-                    } else if e.is_connect() {
-                        AiError::network_error("OpenAI", model, "Verbindung fehlgeschlagen")
-                    } else {
-                        AiError::network_error("OpenAI", model, &e.to_string())
-                    };
-
-                    if attempt < MAX_RETRIES && is_retryable(&last_error) {
-                        continue;
-                    }
-                    return Err(last_error);
-                }
-            };
-
-            let status = response.status();
-            if !status.is_success() {
-                let body = response.text().await.unwrap_or_default();
-                last_error = parse_error(status.as_u16(), &body, model);
+        let response = match client.post(url).json(&request_body).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_error = if e.is_timeout() {
+                    AiError::network_error("OpenAI", model, "Zeitüberschreitung")
+                } else if e.is_connect() {
+                    AiError::network_error("OpenAI", model, "Verbindung fehlgeschlagen")
+                } else {
+                    AiError::network_error("OpenAI", model, &e.to_string())
+                };
 
                 if attempt < MAX_RETRIES && is_retryable(&last_error) {
                     continue;
                 }
                 return Err(last_error);
             }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
+
+            if attempt < MAX_RETRIES && is_retryable(&last_error) {
+                continue;
+            }
+            return Err(last_error);
+        }
 
+        // GPT-5 uses Responses API
+        if uses_responses_api(model) {
             let data: ResponsesApiResponse = response
                 .json()
                 .await
@@ -1046,87 +1163,113 @@ pub async fn chat(
                 provider: "OpenAI".to_string(),
                 model: model.to_string(),
                 tokens_used: data.usage.map(|u| u.total_tokens),
+                prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+                completion_tokens: data.usage.map(|u| u.completion_tokens),
                 suggestions: Vec::new(),
             });
         }
 
-        // GPT-4.x and older use Chat Completions API
-        let response = if has_images {
-            // Build multimodal messages with images
-            let mut openai_messages: Vec<MultimodalChatMessage> = vec![
-                MultimodalChatMessage {
-                    role: "system".to_string(),
-                    content: vec![ContentPart::Text { text: system_prompt.clone() }],
-                }
-            ];
-
-            for m in messages {
-                let mut content: Vec<ContentPart> = Vec::new();
-
-                // Add images first
-                for attachment in &m.attachments {
-                    content.push(ContentPart::ImageUrl {
-                        image_url: ImageUrl {
-                            url: format!("data:{};base64,{}", attachment.mime_type, attachment.data),
-                        },
-                    });
-                }
+        let data: ChatCompletionResponse = response
+            .json()
+            .await
+            .map_err(|e| AiError::other("OpenAI", model, &format!("JSON parse error: {}", e)))?;
 
-                // Add text content
-                if !m.content.is_empty() {
-                    content.push(ContentPart::Text { text: m.content.clone() });
-                }
+        let response_text = data
+            .choices
+            .first()
+            .and_then(|c| c.message.content.clone())
+            .unwrap_or_default();
 
-                openai_messages.push(MultimodalChatMessage {
-                    role: m.role.clone(),
-                    content,
-                });
-            }
+        return Ok(PortfolioChatResponse {
+            response: response_text,
+            provider: "OpenAI".to_string(),
+            model: model.to_string(),
+            tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
+            suggestions: Vec::new(),
+        });
+    }
 
-            let request_body = MultimodalChatCompletionRequest {
-                model: model.to_string(),
-                max_tokens: MAX_TOKENS_CHAT,
-                messages: openai_messages,
-            };
+    Err(last_error)
+}
 
-            client.post(CHAT_API_URL).json(&request_body).send().await
-        } else {
-            // Text-only messages
-            let mut openai_messages = vec![TextChatMessage {
-                role: "system".to_string(),
-                content: system_prompt.clone(),
-            }];
+/// Streaming event payload, covering both Chat Completions chunks and
+/// Responses API events (only the fields we need)
+#[derive(Deserialize)]
+struct StreamEvent {
+    /// Responses API event type (e.g. "response.output_text.delta")
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    /// Responses API text delta
+    delta: Option<serde_json::Value>,
+    /// Responses API final response (on "response.completed")
+    response: Option<StreamResponse>,
+    /// Chat Completions choices
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    /// Chat Completions usage (last chunk, with include_usage)
+    usage: Option<Usage>,
+}
 
-            for m in messages {
-                openai_messages.push(TextChatMessage {
-                    role: m.role.clone(),
-                    content: m.content.clone(),
-                });
-            }
+#[derive(Deserialize)]
+struct StreamResponse {
+    usage: Option<ResponsesUsage>,
+}
 
-            // Send request with or without web search tool
-            if use_web_search {
-                let request_body = TextChatWithToolsRequest {
-                    model: model.to_string(),
-                    max_tokens: MAX_TOKENS_CHAT,
-                    messages: openai_messages.clone(),
-                    tools: vec![WebSearchTool {
-                        tool_type: "web_search_preview".to_string(),
-                        search_context_size: Some("medium".to_string()),
-                    }],
-                };
-                client.post(CHAT_API_URL).json(&request_body).send().await
-            } else {
-                let request_body = TextChatCompletionRequest {
-                    model: model.to_string(),
-                    max_tokens: MAX_TOKENS_CHAT,
-                    messages: openai_messages.clone(),
-                };
-                client.post(CHAT_API_URL).json(&request_body).send().await
-            }
-        };
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: Option<StreamChoiceDelta>,
+}
 
-        let response = match response {
+#[derive(Deserialize)]
+struct StreamChoiceDelta {
+    content: Option<String>,
+}
+
+/// Chat with portfolio assistant using OpenAI, streaming fragments to `on_delta`.
+pub async fn chat_stream<F>(
+    model: &str,
+    api_key: &str,
+    messages: &[AiChatMessage],
+    context: &PortfolioInsightsContext,
+    mut on_delta: F,
+) -> Result<PortfolioChatResponse, AiError>
+where
+    F: ChatDeltaSink,
+{
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", api_key))
+            .map_err(|_| AiError::invalid_api_key("OpenAI", model))?,
+    );
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    // No total timeout: a long answer may stream for longer than REQUEST_TIMEOUT_SECS
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AiError::network_error("OpenAI", model, &e.to_string()))?;
+
+    let (url, mut request_body) = chat_request_body(model, messages, context)?;
+    request_body["stream"] = serde_json::Value::Bool(true);
+    if !uses_responses_api(model) {
+        // Chat Completions only reports usage in streams when asked to
+        request_body["stream_options"] = serde_json::json!({ "include_usage": true });
+    }
+
+    let mut last_error = AiError::other("OpenAI", model, "No attempts made");
+
+    // Retries are only possible before the first token has been received
+    for attempt in 0..=MAX_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(calculate_backoff_delay(attempt - 1)).await;
+        }
+
+        let response = match client.post(url).json(&request_body).send().await {
             Ok(resp) => resp,
             Err(e) => {
                 last_error = if e.is_timeout() {
@@ -1146,8 +1289,9 @@ pub async fn chat(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -1155,22 +1299,48 @@ pub async fn chat(
             return Err(last_error);
         }
 
-        let data: ChatCompletionResponse = response
-            .json()
-            .await
-            .map_err(|e| AiError::other("OpenAI", model, &format!("JSON parse error: {}", e)))?;
+        let mut response_text = String::new();
+        let mut usage: Option<(u32, u32, u32)> = None;
 
-        let response_text = data
-            .choices
-            .first()
-            .and_then(|c| c.message.content.clone())
-            .unwrap_or_default();
+        read_sse_events(response, "OpenAI", model, |data| {
+            let Ok(event) = serde_json::from_str::<StreamEvent>(data) else {
+                return;
+            };
+
+            let delta = match event.event_type.as_deref() {
+                Some("response.output_text.delta") => {
+                    event.delta.as_ref().and_then(|d| d.as_str()).map(str::to_string)
+                }
+                Some("response.completed") => {
+                    usage = event
+                        .response
+                        .and_then(|r| r.usage)
+                        .map(|u| (u.total_tokens, u.prompt_tokens, u.completion_tokens));
+                    None
+                }
+                Some(_) => None,
+                None => {
+                    if let Some(u) = event.usage {
+                        usage = Some((u.total_tokens, u.prompt_tokens, u.completion_tokens));
+                    }
+                    event.choices.into_iter().next().and_then(|c| c.delta).and_then(|d| d.content)
+                }
+            };
+
+            if let Some(text) = delta {
+                on_delta(&text);
+                response_text.push_str(&text);
+            }
+        })
+        .await?;
 
         return Ok(PortfolioChatResponse {
             response: response_text,
             provider: "OpenAI".to_string(),
             model: model.to_string(),
-            tokens_used: data.usage.map(|u| u.total_tokens),
+            tokens_used: usage.map(|(total, _, _)| total),
+            prompt_tokens: usage.map(|(_, prompt, _)| prompt),
+            completion_tokens: usage.map(|(_, _, completion)| completion),
             suggestions: Vec::new(),
         });
     }
@@ -1236,8 +1406,9 @@ pub async fn complete_text(
 
             let status = response.status();
             if !status.is_success() {
+                let headers = response.headers().clone();
                 let body = response.text().await.unwrap_or_default();
-                last_error = parse_error(status.as_u16(), &body, model);
+                last_error = parse_error(status.as_u16(), &headers, &body, model);
 
                 if attempt < MAX_RETRIES && is_retryable(&last_error) {
                     continue;
@@ -1283,8 +1454,9 @@ pub async fn complete_text(
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            last_error = parse_error(status.as_u16(), &body, model);
+            last_error = parse_error(status.as_u16(), &headers, &body, model);
 
             if attempt < MAX_RETRIES && is_retryable(&last_error) {
                 continue;
@@ -4,6 +4,7 @@
 //! for retry logic and markdown normalization.
 
 use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
 
 use crate::ai::types::{AnnotationAnalysisJson, EnhancedAnnotationAnalysisJson, RETRY_BASE_DELAY_MS};
 
@@ -33,6 +34,116 @@ pub fn parse_retry_delay(text: &str) -> Option<u32> {
     None
 }
 
+/// Parse retry delay from rate-limit response headers.
+///
+/// Checks, in order of precision:
+/// - `retry-after-ms` (OpenAI, milliseconds)
+/// - `retry-after` (seconds or HTTP date, sent by Anthropic and OpenAI)
+/// - `anthropic-ratelimit-{requests,tokens}-reset` (RFC 3339 timestamps)
+/// - `x-ratelimit-reset-{requests,tokens}` (OpenAI durations like "6m0s", "20ms")
+/// - `x-ratelimit-reset` (seconds or Unix timestamp)
+///
+/// Returns the delay in whole seconds (ceiled, minimum 1), or `None` if no
+/// usable header is present.
+pub fn parse_retry_after_headers(headers: &HeaderMap) -> Option<u32> {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let now = chrono::Utc::now();
+
+    if let Some(ms) = header_str("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some(secs_to_delay(ms / 1000.0));
+    }
+
+    if let Some(value) = header_str("retry-after") {
+        if let Ok(secs) = value.parse::<f64>() {
+            return Some(secs_to_delay(secs));
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let secs = (date.with_timezone(&chrono::Utc) - now).num_milliseconds() as f64 / 1000.0;
+            return Some(secs_to_delay(secs));
+        }
+    }
+
+    // Anthropic: reset timestamps per limit type; the longest one governs
+    let anthropic_reset = ["anthropic-ratelimit-requests-reset", "anthropic-ratelimit-tokens-reset"]
+        .iter()
+        .filter_map(|name| header_str(name))
+        .filter_map(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+        .map(|date| (date.with_timezone(&chrono::Utc) - now).num_milliseconds() as f64 / 1000.0)
+        .fold(None, |max: Option<f64>, secs| Some(max.map_or(secs, |m| m.max(secs))));
+    if let Some(secs) = anthropic_reset {
+        return Some(secs_to_delay(secs));
+    }
+
+    // OpenAI: reset durations per limit type; the longest one governs
+    let openai_reset = ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| header_str(name))
+        .filter_map(parse_duration_secs)
+        .fold(None, |max: Option<f64>, secs| Some(max.map_or(secs, |m| m.max(secs))));
+    if let Some(secs) = openai_reset {
+        return Some(secs_to_delay(secs));
+    }
+
+    if let Some(value) = header_str("x-ratelimit-reset").and_then(|v| v.parse::<f64>().ok()) {
+        // Large values are Unix timestamps rather than relative seconds
+        let secs = if value > 1_000_000_000.0 {
+            value - now.timestamp() as f64
+        } else {
+            value
+        };
+        return Some(secs_to_delay(secs));
+    }
+
+    None
+}
+
+/// Retry delay of a 429 response: rate-limit headers first, then the body
+pub fn parse_rate_limit_retry_after(headers: &HeaderMap, body: &str) -> Option<u32> {
+    parse_retry_after_headers(headers).or_else(|| parse_retry_delay(body))
+}
+
+/// Parse a Go-style duration string ("1s", "6m0s", "1h2m3.5s", "20ms") into seconds.
+fn parse_duration_secs(text: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = text.chars().peekable();
+    let mut found_unit = false;
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let value: f64 = number.parse().ok()?;
+        number.clear();
+        let factor = match c {
+            'h' => 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                0.001
+            }
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        total += value * factor;
+        found_unit = true;
+    }
+
+    if !number.is_empty() {
+        // Trailing number without unit is treated as seconds
+        total += number.parse::<f64>().ok()?;
+        found_unit = true;
+    }
+
+    found_unit.then_some(total)
+}
+
+/// Convert a (possibly fractional or negative) second count into a retry delay
+fn secs_to_delay(secs: f64) -> u32 {
+    secs.ceil().max(1.0) as u32
+}
+
 /// Parse JSON response from AI into structured annotations.
 /// Handles common AI quirks like markdown code blocks around JSON.
 pub fn parse_annotation_response(raw: &str) -> Result<AnnotationAnalysisJson> {
@@ -151,6 +262,76 @@ mod tests {
         assert_eq!(parse_retry_delay(text), None);
     }
 
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_seconds() {
+        let map = headers(&[("retry-after", "17")]);
+        assert_eq!(parse_retry_after_headers(&map), Some(17));
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_ms_takes_precedence() {
+        let map = headers(&[("retry-after", "2"), ("retry-after-ms", "1500")]);
+        assert_eq!(parse_retry_after_headers(&map), Some(2)); // 1.5s ceiled
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_anthropic_reset() {
+        let reset = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc3339();
+        let map = headers(&[("anthropic-ratelimit-tokens-reset", &reset)]);
+        let delay = parse_retry_after_headers(&map).unwrap();
+        assert!((29..=30).contains(&delay), "delay was {}", delay);
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_openai_reset_uses_longest() {
+        let map = headers(&[
+            ("x-ratelimit-reset-requests", "1s"),
+            ("x-ratelimit-reset-tokens", "6m0s"),
+        ]);
+        assert_eq!(parse_retry_after_headers(&map), Some(360));
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_generic_reset() {
+        let map = headers(&[("x-ratelimit-reset", "12")]);
+        assert_eq!(parse_retry_after_headers(&map), Some(12));
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers_none() {
+        let map = headers(&[("content-type", "application/json")]);
+        assert_eq!(parse_retry_after_headers(&map), None);
+        assert_eq!(parse_retry_after_headers(&headers(&[("retry-after", "soon")])), None);
+    }
+
+    #[test]
+    fn test_parse_rate_limit_retry_after_prefers_headers() {
+        let map = headers(&[("retry-after", "42")]);
+        assert_eq!(parse_rate_limit_retry_after(&map, r#"{"error": {"retryDelay": "4s"}}"#), Some(42));
+    }
+
+    #[test]
+    fn test_parse_rate_limit_retry_after_falls_back_to_body() {
+        assert_eq!(parse_rate_limit_retry_after(&HeaderMap::new(), "Please retry in 7 seconds"), Some(7));
+    }
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("20ms"), Some(0.02));
+        assert_eq!(parse_duration_secs("1h2m3s"), Some(3723.0));
+        assert_eq!(parse_duration_secs("1.5s"), Some(1.5));
+        assert_eq!(parse_duration_secs("abc"), None);
+        assert_eq!(parse_duration_secs(""), None);
+    }
+
     #[test]
     fn test_calculate_backoff_delay() {
         assert_eq!(calculate_backoff_delay(0), std::time::Duration::from_millis(1000));
@@ -63,9 +63,13 @@ struct ResponseMessage {
     content: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy)]
 struct Usage {
     total_tokens: u32,
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
 }
 
 /// Parse Perplexity API error response
@@ -204,6 +208,8 @@ pub async fn analyze(
             provider: "Perplexity".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -302,6 +308,8 @@ pub async fn analyze_with_custom_prompt(
             provider: "Perplexity".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -619,6 +627,8 @@ pub async fn analyze_portfolio(
             provider: "Perplexity".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -709,6 +719,8 @@ pub async fn analyze_opportunities(
             provider: "Perplexity".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
         });
     }
 
@@ -856,6 +868,8 @@ pub async fn chat(
             provider: "Perplexity".to_string(),
             model: model.to_string(),
             tokens_used: data.usage.map(|u| u.total_tokens),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens),
+            completion_tokens: data.usage.map(|u| u.completion_tokens),
             suggestions: Vec::new(),
         });
     }
@@ -346,6 +346,17 @@ pub fn build_portfolio_insights_prompt(ctx: &PortfolioInsightsContext) -> String
         .collect::<Vec<_>>()
         .join("\n");
 
+    // Concentration (Klumpenrisiko) as hard numbers
+    let values: Vec<f64> = ctx.holdings.iter().map(|h| h.current_value).collect();
+    let concentration = crate::performance::calculate_concentration_metrics(&values);
+    let concentration_str = format!(
+        "- HHI: {:.3} (effektive Anzahl Positionen: {:.1})\n- Top-5-Gewicht: {:.1}%, Top-10-Gewicht: {:.1}%",
+        concentration.hhi,
+        concentration.effective_holdings,
+        concentration.top5_weight,
+        concentration.top10_weight
+    );
+
     // Format currency allocation
     let currency_str = ctx
         .currency_allocation
@@ -401,6 +412,9 @@ pub fn build_portfolio_insights_prompt(ctx: &PortfolioInsightsContext) -> String
 **Top-Positionen:**
 {}
 
+**Konzentration:**
+{}
+
 **Holdings (Top 10 von {}):**
 {}
 
@@ -440,6 +454,7 @@ WICHTIG:
         perf_str,
         irr_str,
         top_positions_str,
+        concentration_str,
         ctx.holdings.len(),
         holdings_str,
         currency_str,
@@ -807,6 +822,20 @@ pub fn build_chat_system_prompt(ctx: &PortfolioInsightsContext) -> String {
                 ));
             }
 
+            // Last failed fetches (after retries) with the provider's error
+            if !status.sync_errors.is_empty() {
+                let errors_str = status.sync_errors.iter().take(5).cloned().collect::<Vec<_>>().join("\n- ");
+                let more_str = if status.sync_errors.len() > 5 {
+                    format!("\n- ... und {} weitere", status.sync_errors.len() - 5)
+                } else {
+                    String::new()
+                };
+                sections.push(format!(
+                    "=== LETZTE SYNC-FEHLER ===\nDer letzte Kursabruf ist trotz Wiederholungen fehlgeschlagen:\n- {}{}",
+                    errors_str, more_str
+                ));
+            }
+
             format!("\n\n{}", sections.join("\n\n"))
         }
         None => String::new(),
@@ -1098,6 +1127,7 @@ Format: EXCHANGE:SYMBOL
 | COINGECKO | coin_id | Zielwährung (EUR, USD) | Kryptowährungen |
 | KRAKEN | Symbol | Zielwährung | Krypto-Börsenpreise |
 | TRADINGVIEW | Symbol | Exchange (XETR, SIX) | Alternative zu Yahoo |
+| STOOQ | Symbol | Börsen-Suffix (.DE, .L, .hu) | Europäische Börsen ohne Yahoo-Abdeckung (EOD) |
 | ALPHAVANTAGE | Symbol | - | US-Aktien (API-Key nötig) |
 | TWELVEDATA | Symbol | - | Internationale Märkte |
 
@@ -0,0 +1,155 @@
+//! Server-Sent Events (SSE) helpers for streaming AI responses
+//!
+//! Claude, OpenAI and Gemini stream completions as `text/event-stream`.
+//! Each event carries a JSON payload on one or more `data:` lines and events
+//! are separated by a blank line. The payload itself contains the event type,
+//! so `event:` lines are not needed and ignored. [`ChatDeltaSink`] is the
+//! callback contract of the providers' `chat_stream` functions.
+
+use super::AiError;
+
+/// Sentinel OpenAI sends as the last Chat Completions event
+const OPENAI_DONE_SENTINEL: &str = "[DONE]";
+
+/// Callback of the streaming `chat_stream` functions of all providers.
+///
+/// Called once per text fragment, in order, as it arrives. Fragments are raw
+/// model output and concatenate to the text of the returned response, which
+/// additionally carries the token usage.
+pub trait ChatDeltaSink: FnMut(&str) {}
+
+impl<F: FnMut(&str)> ChatDeltaSink for F {}
+
+/// Incremental SSE parser that yields complete `data:` payloads.
+///
+/// Chunks from the network can split lines (and UTF-8 sequences) at arbitrary
+/// byte positions, so raw bytes are buffered until a full line is available.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    data: String,
+}
+
+impl SseParser {
+    /// Feed a chunk of bytes and return all payloads completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.process_line(line.trim_end_matches(['\n', '\r'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flush a trailing event when the stream ends without a final blank line
+    pub fn finish(&mut self) -> Option<String> {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            self.process_line(line.trim_end_matches(['\n', '\r']));
+        }
+        self.take_event()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        if line.is_empty() {
+            return self.take_event();
+        }
+        if let Some(value) = line.strip_prefix("data:") {
+            if !self.data.is_empty() {
+                self.data.push('\n');
+            }
+            self.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
+        None
+    }
+
+    fn take_event(&mut self) -> Option<String> {
+        if self.data.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.data))
+        }
+    }
+}
+
+/// Read an SSE response body to the end, calling `on_event` for every data payload.
+///
+/// OpenAI's `[DONE]` sentinel is filtered out. Network errors while reading the
+/// body are reported as `AiError::network_error` (no retry, since partial output
+/// has already been delivered).
+pub async fn read_sse_events<F>(
+    mut response: reqwest::Response,
+    provider: &str,
+    model: &str,
+    mut on_event: F,
+) -> Result<(), AiError>
+where
+    F: FnMut(&str),
+{
+    let mut parser = SseParser::default();
+
+    loop {
+        match response.chunk().await {
+            Ok(Some(bytes)) => {
+                for event in parser.feed(&bytes) {
+                    if event != OPENAI_DONE_SENTINEL {
+                        on_event(&event);
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(e) => return Err(AiError::network_error(provider, model, &e.to_string())),
+        }
+    }
+
+    if let Some(event) = parser.finish() {
+        if event != OPENAI_DONE_SENTINEL {
+            on_event(&event);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_single_event() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(b"event: delta\ndata: {\"a\":1}\n\n");
+        assert_eq!(events, vec!["{\"a\":1}".to_string()]);
+    }
+
+    #[test]
+    fn test_sse_parser_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b"data: {\"text\":\"Hal").is_empty());
+        assert!(parser.feed(b"lo\"}\r\n").is_empty());
+        let events = parser.feed(b"\r\ndata: [DONE]\n\n");
+        assert_eq!(events, vec!["{\"text\":\"Hallo\"}".to_string(), "[DONE]".to_string()]);
+    }
+
+    #[test]
+    fn test_sse_parser_split_utf8() {
+        let mut parser = SseParser::default();
+        let bytes = "data: Größe\n\n".as_bytes();
+        // Split inside the two-byte 'ö'
+        assert!(parser.feed(&bytes[..9]).is_empty());
+        assert_eq!(parser.feed(&bytes[9..]), vec!["Größe".to_string()]);
+    }
+
+    #[test]
+    fn test_sse_parser_multiline_data_and_finish() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b": keep-alive\ndata: line1\ndata: line2").is_empty());
+        assert_eq!(parser.finish(), Some("line1\nline2".to_string()));
+        assert_eq!(parser.finish(), None);
+    }
+}
@@ -188,6 +188,10 @@ pub struct ChartAnalysisResponse {
     pub provider: String,
     pub model: String,
     pub tokens_used: Option<u32>,
+    /// Input (prompt) tokens, if reported by the provider
+    pub prompt_tokens: Option<u32>,
+    /// Output (completion) tokens, if reported by the provider
+    pub completion_tokens: Option<u32>,
 }
 
 // ============================================================================
@@ -579,6 +583,8 @@ pub struct QuoteProviderStatusSummary {
     pub missing_api_keys: Vec<String>,
     /// Securities that cannot sync with reasons
     pub issues: Vec<String>,
+    /// Securities whose last quote fetch failed, with the final error
+    pub sync_errors: Vec<String>,
     /// Quote sync status
     pub quote_sync: QuoteSyncInfo,
 }
@@ -668,6 +674,10 @@ pub struct PortfolioInsightsResponse {
     pub provider: String,
     pub model: String,
     pub tokens_used: Option<u32>,
+    /// Input (prompt) tokens, if reported by the provider
+    pub prompt_tokens: Option<u32>,
+    /// Output (completion) tokens, if reported by the provider
+    pub completion_tokens: Option<u32>,
 }
 
 // ============================================================================
@@ -717,6 +727,10 @@ pub struct PortfolioChatResponse {
     pub provider: String,
     pub model: String,
     pub tokens_used: Option<u32>,
+    /// Input (prompt) tokens, if reported by the provider
+    pub prompt_tokens: Option<u32>,
+    /// Output (completion) tokens, if reported by the provider
+    pub completion_tokens: Option<u32>,
     /// Suggested actions that require user confirmation (watchlist modifications)
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub suggestions: Vec<ChatSuggestedAction>,
@@ -0,0 +1,308 @@
+//! AI token usage accounting and monthly budgets
+//!
+//! Every AI call that reports token usage is logged to `pp_ai_usage`.
+//! Users can set a monthly token budget per provider in `pp_ai_budget`;
+//! once it is used up, AI commands fail locally with `QuotaExceeded`
+//! instead of sending the request (and paying for it).
+
+use super::AiError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// A single AI call to be logged
+#[derive(Debug, Clone)]
+pub struct AiUsageEntry<'a> {
+    /// Provider key as used in requests ("claude", "openai", ...)
+    pub provider: &'a str,
+    pub model: &'a str,
+    /// Command that triggered the call (e.g. "chat_with_portfolio_assistant")
+    pub command: &'a str,
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub total_tokens: Option<u32>,
+}
+
+/// Aggregated usage of one provider for a month
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AiProviderUsage {
+    pub provider: String,
+    pub request_count: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// Configured monthly budget in tokens (None = unlimited)
+    pub monthly_budget_tokens: Option<i64>,
+    pub budget_exceeded: bool,
+}
+
+/// Usage summary for a month (YYYY-MM)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiUsageSummary {
+    pub month: String,
+    pub providers: Vec<AiProviderUsage>,
+    pub total_tokens: i64,
+}
+
+/// Current month in the format stored by SQLite's `datetime('now')` (UTC)
+pub fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+/// Log an AI call. Calls without any token information are still counted.
+pub fn record_usage(conn: &Connection, entry: &AiUsageEntry) -> Result<(), String> {
+    // Some providers only report the total, derive it if only the parts are known
+    let total = entry.total_tokens.or(match (entry.prompt_tokens, entry.completion_tokens) {
+        (None, None) => None,
+        (p, c) => Some(p.unwrap_or(0) + c.unwrap_or(0)),
+    });
+
+    conn.execute(
+        r#"
+        INSERT INTO pp_ai_usage (provider, model, command, prompt_tokens, completion_tokens, total_tokens)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+            entry.provider.to_lowercase(),
+            entry.model,
+            entry.command,
+            entry.prompt_tokens,
+            entry.completion_tokens,
+            total,
+        ],
+    )
+    .map_err(|e| format!("Fehler beim Speichern der AI-Nutzung: {}", e))?;
+
+    Ok(())
+}
+
+/// Total tokens used by a provider in the given month (YYYY-MM)
+pub fn get_monthly_tokens(conn: &Connection, provider: &str, month: &str) -> Result<i64, String> {
+    conn.query_row(
+        r#"
+        SELECT COALESCE(SUM(total_tokens), 0)
+        FROM pp_ai_usage
+        WHERE provider = ?1 AND strftime('%Y-%m', created_at) = ?2
+        "#,
+        params![provider.to_lowercase(), month],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Get the monthly token budget of a provider (None = unlimited)
+pub fn get_monthly_budget(conn: &Connection, provider: &str) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT monthly_token_limit FROM pp_ai_budget WHERE provider = ?1",
+        params![provider.to_lowercase()],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Set (or with `None` remove) the monthly token budget of a provider
+pub fn set_monthly_budget(conn: &Connection, provider: &str, limit: Option<i64>) -> Result<(), String> {
+    let provider = provider.to_lowercase();
+    match limit {
+        Some(limit) if limit < 0 => Err("Budget darf nicht negativ sein".into()),
+        Some(limit) => conn
+            .execute(
+                r#"
+                INSERT INTO pp_ai_budget (provider, monthly_token_limit, updated_at)
+                VALUES (?1, ?2, datetime('now'))
+                ON CONFLICT(provider) DO UPDATE SET
+                    monthly_token_limit = excluded.monthly_token_limit,
+                    updated_at = excluded.updated_at
+                "#,
+                params![provider, limit],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        None => conn
+            .execute("DELETE FROM pp_ai_budget WHERE provider = ?1", params![provider])
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Fail with `QuotaExceeded` if the provider's monthly budget is used up.
+/// Database errors are logged and never block the request.
+pub fn check_budget(conn: &Connection, provider: &str, model: &str, month: &str) -> Result<(), AiError> {
+    let limit = match get_monthly_budget(conn, provider) {
+        Ok(Some(limit)) => limit,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            log::warn!("Could not read AI budget for {}: {}", provider, e);
+            return Ok(());
+        }
+    };
+
+    let used = get_monthly_tokens(conn, provider, month).unwrap_or_else(|e| {
+        log::warn!("Could not read AI usage for {}: {}", provider, e);
+        0
+    });
+
+    if used >= limit {
+        let mut error = AiError::quota_exceeded(provider, model, None);
+        error.message = format!(
+            "Monatliches Token-Budget für {} erreicht ({} von {} Tokens). Erhöhe das Budget in den Einstellungen oder wechsle den Anbieter.",
+            provider, used, limit
+        );
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Per-provider usage totals for a month (YYYY-MM), including providers
+/// that only have a budget configured
+pub fn get_usage_summary(conn: &Connection, month: &str) -> Result<AiUsageSummary, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT p.provider,
+                   COUNT(u.id),
+                   COALESCE(SUM(u.prompt_tokens), 0),
+                   COALESCE(SUM(u.completion_tokens), 0),
+                   COALESCE(SUM(u.total_tokens), 0),
+                   b.monthly_token_limit
+            FROM (
+                SELECT provider FROM pp_ai_usage WHERE strftime('%Y-%m', created_at) = ?1
+                UNION
+                SELECT provider FROM pp_ai_budget
+            ) p
+            LEFT JOIN pp_ai_usage u
+                ON u.provider = p.provider AND strftime('%Y-%m', u.created_at) = ?1
+            LEFT JOIN pp_ai_budget b ON b.provider = p.provider
+            GROUP BY p.provider
+            ORDER BY p.provider
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let providers = stmt
+        .query_map(params![month], |row| {
+            let total_tokens: i64 = row.get(4)?;
+            let monthly_budget_tokens: Option<i64> = row.get(5)?;
+            Ok(AiProviderUsage {
+                provider: row.get(0)?,
+                request_count: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+                total_tokens,
+                monthly_budget_tokens,
+                budget_exceeded: monthly_budget_tokens.is_some_and(|limit| total_tokens >= limit),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let total_tokens = providers.iter().map(|p| p.total_tokens).sum();
+
+    Ok(AiUsageSummary {
+        month: month.to_string(),
+        providers,
+        total_tokens,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AiErrorKind;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_ai_usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                command TEXT NOT NULL,
+                prompt_tokens INTEGER,
+                completion_tokens INTEGER,
+                total_tokens INTEGER,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            CREATE TABLE pp_ai_budget (
+                provider TEXT PRIMARY KEY,
+                monthly_token_limit INTEGER NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn entry(provider: &str, prompt: Option<u32>, completion: Option<u32>, total: Option<u32>) -> AiUsageEntry<'_> {
+        AiUsageEntry {
+            provider,
+            model: "test-model",
+            command: "test",
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: total,
+        }
+    }
+
+    #[test]
+    fn test_usage_summary_per_provider() {
+        let conn = setup_db();
+        let month = current_month();
+        record_usage(&conn, &entry("claude", Some(100), Some(50), Some(150))).unwrap();
+        record_usage(&conn, &entry("Claude", Some(10), Some(5), None)).unwrap();
+        record_usage(&conn, &entry("openai", None, None, Some(200))).unwrap();
+        // Usage from another month is not counted
+        conn.execute(
+            "INSERT INTO pp_ai_usage (provider, model, command, total_tokens, created_at)
+             VALUES ('openai', 'm', 'test', 999, '2020-01-15 10:00:00')",
+            [],
+        )
+        .unwrap();
+
+        let summary = get_usage_summary(&conn, &month).unwrap();
+        assert_eq!(summary.total_tokens, 365);
+        assert_eq!(summary.providers.len(), 2);
+        let claude = &summary.providers[0];
+        assert_eq!(claude.provider, "claude");
+        assert_eq!(claude.request_count, 2);
+        assert_eq!(claude.prompt_tokens, 110);
+        assert_eq!(claude.completion_tokens, 55);
+        assert_eq!(claude.total_tokens, 165);
+        assert_eq!(summary.providers[1].total_tokens, 200);
+    }
+
+    #[test]
+    fn test_budget_blocks_when_exceeded() {
+        let conn = setup_db();
+        let month = current_month();
+
+        // No budget configured - never blocks
+        record_usage(&conn, &entry("claude", None, None, Some(1000))).unwrap();
+        assert!(check_budget(&conn, "claude", "m", &month).is_ok());
+
+        set_monthly_budget(&conn, "claude", Some(1500)).unwrap();
+        assert!(check_budget(&conn, "claude", "m", &month).is_ok());
+
+        record_usage(&conn, &entry("claude", None, None, Some(600))).unwrap();
+        let err = check_budget(&conn, "claude", "m", &month).unwrap_err();
+        assert_eq!(err.kind, AiErrorKind::QuotaExceeded);
+
+        // Other providers are not affected
+        assert!(check_budget(&conn, "openai", "m", &month).is_ok());
+
+        let summary = get_usage_summary(&conn, &month).unwrap();
+        assert!(summary.providers[0].budget_exceeded);
+        assert_eq!(summary.providers[0].monthly_budget_tokens, Some(1500));
+
+        // Removing the budget lifts the block
+        set_monthly_budget(&conn, "claude", None).unwrap();
+        assert!(check_budget(&conn, "claude", "m", &month).is_ok());
+        assert!(set_monthly_budget(&conn, "claude", Some(-1)).is_err());
+    }
+}
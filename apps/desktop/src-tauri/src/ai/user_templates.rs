@@ -53,78 +53,146 @@ pub struct UserTemplateInput {
 // SQL Validation (SECURITY CRITICAL!)
 // ============================================================================
 
-/// Validates user SQL before storage.
-/// Only SELECT statements are allowed, and dangerous keywords are blocked.
-pub fn validate_user_sql(sql: &str) -> Result<(), String> {
-    let sql_upper = sql.to_uppercase();
-    let sql_trimmed = sql_upper.trim();
-
-    // Only SELECT allowed
-    if !sql_trimmed.starts_with("SELECT") {
-        return Err("Nur SELECT-Statements sind erlaubt".into());
+/// Keywords that could modify data or schema, attach other databases or
+/// change connection settings. Matched as whole words only, so identifiers
+/// like `my_update_table` are still allowed. `REPLACE` is checked separately
+/// since `replace()` is a harmless string function.
+const FORBIDDEN_SQL_KEYWORDS: &[&str] = &[
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "DROP",
+    "ALTER",
+    "CREATE",
+    "TRUNCATE",
+    "UPSERT",
+    "ATTACH",
+    "DETACH",
+    "PRAGMA",
+    "VACUUM",
+    "REINDEX",
+    "ANALYZE", // Could be slow on large tables
+    "BEGIN",
+    "COMMIT",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "RELEASE",
+    "LOAD_EXTENSION",
+];
+
+/// Validates user template SQL before storage and execution.
+///
+/// Only a single read-only `SELECT` (optionally with a leading `WITH` clause)
+/// is accepted. String literals and comments are removed before the keyword
+/// check, so `WHERE type = 'DELETE'` is fine while `DELETE FROM ...` is not.
+/// This is a lightweight check - execution additionally relies on SQLite's
+/// own read-only detection (see `execute_user_template`).
+pub fn validate_user_template_sql(sql: &str) -> Result<(), String> {
+    let stripped = strip_sql_literals_and_comments(sql)?;
+    let words: Vec<String> = stripped
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_uppercase())
+        .collect();
+
+    // Only SELECT (or WITH ... SELECT) allowed
+    match words.first().map(String::as_str) {
+        Some("SELECT") => {}
+        Some("WITH") if words.iter().any(|w| w == "SELECT") => {}
+        _ => return Err("Nur SELECT-Statements sind erlaubt".into()),
     }
 
-    // Forbidden keywords that could modify data or schema
-    let forbidden = [
-        "INSERT",
-        "UPDATE",
-        "DELETE",
-        "DROP",
-        "ALTER",
-        "CREATE",
-        "TRUNCATE",
-        "REPLACE",
-        "ATTACH",
-        "DETACH",
-        "PRAGMA",
-        "VACUUM",
-        "REINDEX",
-        "ANALYZE", // Could be slow on large tables
-    ];
-
-    for kw in forbidden {
-        // Check for keyword as word boundary (not part of identifier)
-        // e.g., "SELECT * FROM my_update_table" should be allowed
-        let patterns = [
-            format!(" {} ", kw),
-            format!(" {}\n", kw),
-            format!(" {}\t", kw),
-            format!("\n{} ", kw),
-            format!("\t{} ", kw),
-            format!("({})", kw),
-            format!("({} ", kw),
-            format!(" {})", kw),
-        ];
-
-        for pattern in &patterns {
-            if sql_upper.contains(pattern) {
-                return Err(format!("Verbotenes Keyword: {}", kw));
-            }
-        }
+    if let Some(kw) = words
+        .iter()
+        .find(|w| FORBIDDEN_SQL_KEYWORDS.contains(&w.as_str()))
+    {
+        return Err(format!("Verbotenes Keyword: {}", kw));
+    }
 
-        // Also check at start/end
-        if sql_trimmed.starts_with(&format!("{} ", kw))
-            || sql_trimmed.starts_with(&format!("{}(", kw))
-        {
-            return Err(format!("Verbotenes Keyword: {}", kw));
-        }
+    // REPLACE INTO ... / INSERT OR REPLACE ... (statement start is covered above)
+    if words.windows(2).any(|pair| {
+        (pair[0] == "REPLACE" && pair[1] == "INTO") || (pair[0] == "OR" && pair[1] == "REPLACE")
+    }) {
+        return Err("Verbotenes Keyword: REPLACE".into());
     }
 
-    // No multiple statements (semicolon check)
-    // Allow semicolon only at the very end
-    let sql_no_trailing = sql.trim().trim_end_matches(';');
-    if sql_no_trailing.contains(';') {
+    // No multiple statements - a single trailing semicolon is allowed
+    let without_trailing = stripped.trim_end().trim_end_matches(';');
+    if without_trailing.contains(';') || stripped.matches(';').count() > 1 {
         return Err("Mehrere Statements sind nicht erlaubt".into());
     }
 
     // Basic syntax check - must have FROM clause
-    if !sql_upper.contains("FROM") {
+    if !words.iter().any(|w| w == "FROM") {
         return Err("SELECT muss eine FROM-Klausel enthalten".into());
     }
 
     Ok(())
 }
 
+/// Replace string literals, quoted identifiers and comments with a single
+/// space so that keyword and semicolon checks only see actual SQL tokens.
+fn strip_sql_literals_and_comments(sql: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                // Doubled quote ('') is an escaped quote inside the literal
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => {
+                            if chars.peek() == Some(&c) {
+                                chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                        Some(_) => {}
+                        None => return Err("Nicht geschlossenes Anführungszeichen im SQL".into()),
+                    }
+                }
+                result.push(' ');
+            }
+            '[' => {
+                // SQLite also accepts [identifier]
+                if !chars.by_ref().any(|ch| ch == ']') {
+                    return Err("Nicht geschlossene eckige Klammer im SQL".into());
+                }
+                result.push(' ');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for ch in chars.by_ref() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+                result.push('\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                let mut closed = false;
+                for ch in chars.by_ref() {
+                    if prev == '*' && ch == '/' {
+                        closed = true;
+                        break;
+                    }
+                    prev = ch;
+                }
+                if !closed {
+                    return Err("Nicht geschlossener Kommentar im SQL".into());
+                }
+                result.push(' ');
+            }
+            _ => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
 /// Generates a valid template_id from the name
 pub fn generate_template_id(name: &str) -> String {
     let cleaned: String = name
@@ -305,7 +373,7 @@ pub fn create_user_template(
     input: &UserTemplateInput,
 ) -> Result<UserTemplate, String> {
     // Validate SQL
-    validate_user_sql(&input.sql_query)?;
+    validate_user_template_sql(&input.sql_query)?;
 
     // Generate template_id
     let template_id = generate_template_id(&input.name);
@@ -375,7 +443,7 @@ pub fn update_user_template(
     input: &UserTemplateInput,
 ) -> Result<UserTemplate, String> {
     // Validate SQL
-    validate_user_sql(&input.sql_query)?;
+    validate_user_template_sql(&input.sql_query)?;
 
     // Get current template_id
     let current_template_id: String = conn
@@ -459,16 +527,27 @@ pub fn execute_user_template(
     params: &HashMap<String, String>,
 ) -> Result<QueryResult, String> {
     // Validate SQL again at execution time (paranoia check)
-    validate_user_sql(&template.sql_query)?;
+    validate_user_template_sql(&template.sql_query)?;
 
     // Substitute named parameters :param_name with ? placeholders
     let (sql, values) = substitute_named_params(&template.sql_query, &template.parameters, params)?;
 
+    // Sandbox: run inside a transaction that is always rolled back, so even a
+    // statement slipping past validation cannot persist any changes.
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("SQL-Fehler: {}", e))?;
+
     // Execute query
-    let mut stmt = conn
+    let mut stmt = tx
         .prepare(&sql)
         .map_err(|e| format!("SQL-Fehler: {}", e))?;
 
+    // SQLite's own check - rejects anything that could write to the database
+    if !stmt.readonly() {
+        return Err("Nur lesende Abfragen sind erlaubt".into());
+    }
+
     // Get column names
     let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
 
@@ -510,6 +589,9 @@ pub fn execute_user_template(
         }
         rows.push(row_map);
     }
+    drop(rows_iter);
+    drop(stmt);
+    tx.rollback().map_err(|e| format!("SQL-Fehler: {}", e))?;
 
     let row_count = rows.len();
 
@@ -665,23 +747,73 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_validate_user_sql_valid() {
-        assert!(validate_user_sql("SELECT * FROM pp_security").is_ok());
-        assert!(validate_user_sql("SELECT name, ticker FROM pp_security WHERE id = ?1").is_ok());
-        assert!(validate_user_sql(
+    fn test_validate_user_template_sql_valid() {
+        assert!(validate_user_template_sql("SELECT * FROM pp_security").is_ok());
+        assert!(validate_user_template_sql("SELECT name, ticker FROM pp_security WHERE id = ?1").is_ok());
+        assert!(validate_user_template_sql(
             "SELECT s.name FROM pp_security s JOIN pp_txn t ON s.id = t.security_id"
         )
         .is_ok());
+        assert!(validate_user_template_sql("SELECT replace(name, 'AG', '') FROM pp_security").is_ok());
     }
 
     #[test]
-    fn test_validate_user_sql_invalid() {
-        assert!(validate_user_sql("INSERT INTO pp_security (name) VALUES ('test')").is_err());
-        assert!(validate_user_sql("UPDATE pp_security SET name = 'test'").is_err());
-        assert!(validate_user_sql("DELETE FROM pp_security").is_err());
-        assert!(validate_user_sql("DROP TABLE pp_security").is_err());
-        assert!(validate_user_sql("SELECT * FROM pp_security; DROP TABLE pp_security").is_err());
-        assert!(validate_user_sql("PRAGMA table_info(pp_security)").is_err());
+    fn test_validate_user_template_sql_invalid() {
+        assert!(validate_user_template_sql("INSERT INTO pp_security (name) VALUES ('test')").is_err());
+        assert!(validate_user_template_sql("UPDATE pp_security SET name = 'test'").is_err());
+        assert!(validate_user_template_sql("DELETE FROM pp_security").is_err());
+        assert!(validate_user_template_sql("DROP TABLE pp_security").is_err());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security; DROP TABLE pp_security").is_err());
+        assert!(validate_user_template_sql("PRAGMA table_info(pp_security)").is_err());
+        assert!(validate_user_template_sql("REPLACE INTO pp_security (name) VALUES ('test')").is_err());
+        assert!(validate_user_template_sql("WITH t AS (SELECT 1) REPLACE INTO pp_security (id) SELECT * FROM t").is_err());
+    }
+
+    #[test]
+    fn test_validate_user_template_sql_literals_and_comments() {
+        // Keywords inside string literals or identifiers are fine
+        assert!(validate_user_template_sql("SELECT * FROM pp_txn WHERE txn_type = 'DELETE'").is_ok());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security WHERE name = 'A;B'").is_ok());
+        assert!(validate_user_template_sql("SELECT * FROM my_update_table").is_ok());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security;").is_ok());
+        assert!(validate_user_template_sql(
+            "WITH t AS (SELECT id FROM pp_security) SELECT * FROM t"
+        )
+        .is_ok());
+
+        // Comments must not hide a second statement
+        assert!(validate_user_template_sql("SELECT * FROM pp_security -- x\n; DELETE FROM pp_txn").is_err());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security /* */; ATTACH 'x.db' AS x").is_err());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security;;").is_err());
+        assert!(validate_user_template_sql("SELECT * FROM pp_security WHERE name = 'x").is_err());
+        assert!(validate_user_template_sql("SELECT load_extension('evil') FROM pp_security").is_err());
+        assert!(validate_user_template_sql("WITH x AS (DELETE FROM pp_txn) SELECT 1 FROM x").is_err());
+    }
+
+    #[test]
+    fn test_execute_user_template_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pp_security (id INTEGER PRIMARY KEY, name TEXT);
+             INSERT INTO pp_security (name) VALUES ('Apple'), ('SAP');",
+        )
+        .unwrap();
+
+        let template = UserTemplate {
+            id: 1,
+            template_id: "user_test".into(),
+            name: "Test".into(),
+            description: String::new(),
+            sql_query: "SELECT name FROM pp_security ORDER BY name".into(),
+            enabled: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+            parameters: Vec::new(),
+        };
+        let result = execute_user_template(&conn, &template, &HashMap::new()).unwrap();
+        assert_eq!(result.row_count, 2);
+        // Sandbox transaction must not be left open
+        assert!(conn.is_autocommit());
     }
 
     #[test]
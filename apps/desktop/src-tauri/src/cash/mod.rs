@@ -0,0 +1,499 @@
+//! Cash balance calculation
+//!
+//! Account balances at a date (optionally converted to base currency), the
+//! accounts linked to a portfolio, and the reconciliation of a computed balance
+//! against a broker statement. Shared by performance, net worth and reports.
+//!
+//! Balance = credits (DEPOSIT, INTEREST, DIVIDENDS, TAX_REFUND, FEES_REFUND,
+//! TRANSFER_IN, SELL) minus debits (REMOVAL, FEES, TAXES, INTEREST_CHARGE,
+//! TRANSFER_OUT, BUY) up to and including the date.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+const AMOUNT_SCALE: f64 = 100.0;
+
+/// Differences below half a cent count as reconciled
+const RECONCILE_TOLERANCE: f64 = 0.005;
+
+/// Get all account IDs linked to a portfolio
+///
+/// Includes:
+/// - The portfolio's reference_account_id
+/// - Any accounts connected via CrossEntry (Buy/Sell transactions)
+pub fn get_linked_account_ids(conn: &Connection, portfolio_id: i64) -> Result<Vec<i64>> {
+    let sql = r#"
+        SELECT DISTINCT account_id FROM (
+            -- Reference Account
+            SELECT reference_account_id as account_id
+            FROM pp_portfolio
+            WHERE id = ?1 AND reference_account_id IS NOT NULL
+
+            UNION
+
+            -- Accounts via CrossEntry (Buy/Sell link portfolio txn to account txn)
+            SELECT at.owner_id as account_id
+            FROM pp_txn pt
+            JOIN pp_cross_entry ce ON ce.portfolio_txn_id = pt.id
+            JOIN pp_txn at ON at.id = ce.account_txn_id
+            WHERE pt.owner_type = 'portfolio'
+              AND pt.owner_id = ?1
+              AND at.owner_type = 'account'
+        )
+        WHERE account_id IS NOT NULL
+    "#;
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([portfolio_id], |row| row.get::<_, i64>(0))?;
+
+    let mut account_ids = Vec::new();
+    for row in rows.flatten() {
+        account_ids.push(row);
+    }
+
+    log::debug!("Found {} linked accounts for portfolio {}", account_ids.len(), portfolio_id);
+    Ok(account_ids)
+}
+
+/// Calculate account cash balance at a specific date
+///
+/// Returns (balance in cents, currency)
+/// Balance = sum of all credits minus debits up to the given date
+pub fn get_account_balance_at_date(
+    conn: &Connection,
+    account_id: i64,
+    date: NaiveDate,
+) -> Result<(f64, String)> {
+    let sql = r#"
+        SELECT
+            a.currency,
+            COALESCE(SUM(
+                CASE
+                    -- Credits (money into account)
+                    WHEN t.txn_type IN ('DEPOSIT', 'INTEREST', 'DIVIDENDS', 'TAX_REFUND',
+                                        'FEES_REFUND', 'TRANSFER_IN', 'SELL') THEN t.amount
+                    -- Debits (money out of account)
+                    WHEN t.txn_type IN ('REMOVAL', 'FEES', 'TAXES', 'INTEREST_CHARGE',
+                                        'TRANSFER_OUT', 'BUY') THEN -t.amount
+                    ELSE 0
+                END
+            ), 0) as balance
+        FROM pp_account a
+        LEFT JOIN pp_txn t ON t.owner_type = 'account' AND t.owner_id = a.id
+            AND date(t.date) <= ?2
+        WHERE a.id = ?1
+        GROUP BY a.id
+    "#;
+
+    let result: (String, i64) = conn.query_row(sql, params![account_id, date.to_string()], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?.unwrap_or_else(|| "EUR".to_string()),
+            row.get::<_, i64>(1)?,
+        ))
+    }).map_err(|e| anyhow::anyhow!("Failed to get account balance: {}", e))?;
+
+    let balance_f = result.1 as f64 / AMOUNT_SCALE;
+    log::debug!("Account {} balance at {}: {:.2} {}", account_id, date, balance_f, result.0);
+
+    Ok((balance_f, result.0))
+}
+
+/// Get total cash balance from all linked accounts, converted to base currency
+///
+/// This is the sum of all account cash balances at a given date,
+/// with each account's balance converted to the portfolio's base currency.
+pub fn get_total_cash_balance_converted(
+    conn: &Connection,
+    portfolio_id: i64,
+    date: NaiveDate,
+    base_currency: &str,
+) -> Result<f64> {
+    get_total_cash_balance_at_rates(conn, portfolio_id, date, date, base_currency)
+}
+
+/// Get total cash balance from all linked accounts at `date`, converted with the rates of `rate_date`
+pub fn get_total_cash_balance_at_rates(
+    conn: &Connection,
+    portfolio_id: i64,
+    date: NaiveDate,
+    rate_date: NaiveDate,
+    base_currency: &str,
+) -> Result<f64> {
+    let account_ids = get_linked_account_ids(conn, portfolio_id)?;
+
+    if account_ids.is_empty() {
+        return Ok(0.0);
+    }
+
+    let mut total_cash = 0.0;
+
+    for account_id in account_ids {
+        let (_, _, balance_base) =
+            get_account_balance_at_rates(conn, account_id, date, rate_date, base_currency)?;
+        total_cash += balance_base;
+    }
+
+    log::debug!("Total cash balance for portfolio {} at {}: {:.2} {}",
+                portfolio_id, date, total_cash, base_currency);
+    Ok(total_cash)
+}
+
+/// Account cash balance at a date, also converted to base currency
+///
+/// Returns (balance, account currency, balance in base currency)
+pub fn get_account_balance_converted(
+    conn: &Connection,
+    account_id: i64,
+    date: NaiveDate,
+    base_currency: &str,
+) -> Result<(f64, String, f64)> {
+    get_account_balance_at_rates(conn, account_id, date, date, base_currency)
+}
+
+/// Account cash balance at `date`, converted with the rates of `rate_date`
+pub fn get_account_balance_at_rates(
+    conn: &Connection,
+    account_id: i64,
+    date: NaiveDate,
+    rate_date: NaiveDate,
+    base_currency: &str,
+) -> Result<(f64, String, f64)> {
+    use crate::currency;
+
+    let (balance, account_currency) = get_account_balance_at_date(conn, account_id, date)?;
+
+    // Convert to base currency if needed
+    let balance_base = if account_currency != base_currency && !account_currency.is_empty() {
+        currency::convert(conn, balance, &account_currency, base_currency, rate_date)
+            .unwrap_or(balance)
+    } else {
+        balance
+    };
+
+    Ok((balance, account_currency, balance_base))
+}
+
+/// Account transaction with its effect on the balance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationTransaction {
+    pub id: i64,
+    pub date: String,
+    pub txn_type: String,
+    /// Signed effect on the balance (credits positive, debits negative)
+    pub amount: f64,
+    pub security_name: Option<String>,
+    pub note: Option<String>,
+    /// Included in the balance at the reconciliation date
+    pub included: bool,
+}
+
+/// Computed account balance compared with a statement balance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountReconciliation {
+    pub account_id: i64,
+    pub currency: String,
+    pub as_of_date: String,
+    pub computed_balance: f64,
+    pub expected_balance: f64,
+    /// computed - expected
+    pub difference: f64,
+    pub reconciled: bool,
+    /// Transactions within ± window_days around the date
+    pub transactions: Vec<ReconciliationTransaction>,
+}
+
+/// Compare the computed balance of an account with an expected statement balance
+///
+/// Lists the account transactions within `window_days` before and after the
+/// date; a booking date off by a few days is the most common cause of a
+/// difference.
+pub fn reconcile_account(
+    conn: &Connection,
+    account_id: i64,
+    expected_balance: f64,
+    as_of_date: NaiveDate,
+    window_days: i64,
+) -> Result<AccountReconciliation> {
+    let (computed_balance, currency) = get_account_balance_at_date(conn, account_id, as_of_date)?;
+
+    let from = as_of_date - chrono::Duration::days(window_days);
+    let to = as_of_date + chrono::Duration::days(window_days);
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.id, t.date, t.txn_type, t.amount, s.name, t.note,
+               CASE
+                   WHEN t.txn_type IN ('DEPOSIT', 'INTEREST', 'DIVIDENDS', 'TAX_REFUND',
+                                       'FEES_REFUND', 'TRANSFER_IN', 'SELL') THEN 1
+                   WHEN t.txn_type IN ('REMOVAL', 'FEES', 'TAXES', 'INTEREST_CHARGE',
+                                       'TRANSFER_OUT', 'BUY') THEN -1
+                   ELSE 0
+               END as sign
+        FROM pp_txn t
+        LEFT JOIN pp_security s ON s.id = t.security_id
+        WHERE t.owner_type = 'account' AND t.owner_id = ?1
+          AND date(t.date) >= ?2 AND date(t.date) <= ?3
+        ORDER BY t.date, t.id
+        "#,
+    )?;
+    let rows = stmt.query_map(
+        params![account_id, from.to_string(), to.to_string()],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        },
+    )?;
+
+    let mut transactions = Vec::new();
+    for row in rows {
+        let (id, date, txn_type, amount, security_name, note, sign) = row?;
+        let included = crate::pp::parse_date_flexible(&date).is_some_and(|d| d <= as_of_date);
+        transactions.push(ReconciliationTransaction {
+            id,
+            date,
+            txn_type,
+            amount: (sign * amount) as f64 / AMOUNT_SCALE,
+            security_name,
+            note,
+            included,
+        });
+    }
+
+    let difference = computed_balance - expected_balance;
+    Ok(AccountReconciliation {
+        account_id,
+        currency,
+        as_of_date: as_of_date.to_string(),
+        computed_balance,
+        expected_balance,
+        difference,
+        reconciled: difference.abs() < RECONCILE_TOLERANCE,
+        transactions,
+    })
+}
+
+/// One account transaction with the balance after it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLedgerEntry {
+    pub id: i64,
+    pub date: String,
+    pub txn_type: String,
+    /// Signed effect on the balance (credits positive, debits negative)
+    pub amount: f64,
+    /// Running balance after this transaction
+    pub balance: f64,
+    pub security_name: Option<String>,
+    pub note: Option<String>,
+}
+
+/// All transactions of an account in date order with running balance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLedger {
+    pub account_id: i64,
+    pub account_name: String,
+    pub currency: String,
+    pub entries: Vec<AccountLedgerEntry>,
+    /// Balance after the last transaction
+    pub closing_balance: f64,
+}
+
+/// Build the passbook of an account: transactions by date with running balance
+///
+/// Uses the credit/debit signs of `get_account_balance_at_date`, so the balance
+/// of the last entry on a date equals the balance at that date.
+pub fn get_account_ledger(conn: &Connection, account_id: i64) -> Result<AccountLedger> {
+    let (account_name, currency): (String, String) = conn
+        .query_row(
+            "SELECT name, currency FROM pp_account WHERE id = ?1",
+            [account_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| anyhow::anyhow!("Konto {} nicht gefunden", account_id))?;
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.id, t.date, t.txn_type, t.amount, s.name, t.note,
+               CASE
+                   WHEN t.txn_type IN ('DEPOSIT', 'INTEREST', 'DIVIDENDS', 'TAX_REFUND',
+                                       'FEES_REFUND', 'TRANSFER_IN', 'SELL') THEN 1
+                   WHEN t.txn_type IN ('REMOVAL', 'FEES', 'TAXES', 'INTEREST_CHARGE',
+                                       'TRANSFER_OUT', 'BUY') THEN -1
+                   ELSE 0
+               END as sign
+        FROM pp_txn t
+        LEFT JOIN pp_security s ON s.id = t.security_id
+        WHERE t.owner_type = 'account' AND t.owner_id = ?1
+        ORDER BY date(t.date), t.date, t.id
+        "#,
+    )?;
+    let rows = stmt.query_map([account_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, i64>(6)?,
+        ))
+    })?;
+
+    // Sum in cents so the running balance does not drift
+    let mut balance: i64 = 0;
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, date, txn_type, amount, security_name, note, sign) = row?;
+        balance += sign * amount;
+        entries.push(AccountLedgerEntry {
+            id,
+            date,
+            txn_type,
+            amount: (sign * amount) as f64 / AMOUNT_SCALE,
+            balance: balance as f64 / AMOUNT_SCALE,
+            security_name,
+            note,
+        });
+    }
+
+    Ok(AccountLedger {
+        account_id,
+        account_name,
+        currency,
+        entries,
+        closing_balance: balance as f64 / AMOUNT_SCALE,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_e2e_account_balance_calculation() {
+        let conn = create_test_db();
+
+        // Create account
+        conn.execute(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'acc-1', 'Test Account', 'EUR')",
+            []
+        ).unwrap();
+
+        // Add transactions: DEPOSIT 1000, BUY 500, DIVIDENDS 50
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency) VALUES (1, 't1', 'account', 1, 'DEPOSIT', '2024-01-01', 100000, 'EUR')",
+            []
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency) VALUES (2, 't2', 'account', 1, 'BUY', '2024-01-15', 50000, 'EUR')",
+            []
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency) VALUES (3, 't3', 'account', 1, 'DIVIDENDS', '2024-06-01', 5000, 'EUR')",
+            []
+        ).unwrap();
+
+        // Test balance: 1000 - 500 + 50 = 550 EUR
+        let (balance, currency) = get_account_balance_at_date(
+            &conn, 1, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+        ).unwrap();
+
+        assert_eq!(currency, "EUR");
+        assert!((balance - 550.0).abs() < 0.01, "Expected 550, got {}", balance);
+    }
+
+    #[test]
+    fn test_e2e_linked_accounts() {
+        let conn = create_test_db();
+
+        // Create account and portfolio with reference
+        conn.execute(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'acc-1', 'Test Account', 'EUR')",
+            []
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'port-1', 'Test Portfolio', 1)",
+            []
+        ).unwrap();
+
+        // Test linked accounts
+        let linked = get_linked_account_ids(&conn, 1).unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0], 1);
+    }
+
+    #[test]
+    fn test_reconcile_account() {
+        let conn = create_test_db();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency)
+             VALUES (1, 't1', 'account', 1, 'DEPOSIT', '2024-01-01', 100000, 'EUR'),
+                    (2, 't2', 'account', 1, 'FEES', '2024-03-30', 250, 'EUR'),
+                    (3, 't3', 'account', 1, 'INTEREST', '2024-04-02', 1000, 'EUR'),
+                    (4, 't4', 'account', 1, 'REMOVAL', '2024-06-01', 20000, 'EUR');",
+        )
+        .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let result = reconcile_account(&conn, 1, 997.5, date, 7).unwrap();
+        assert!(result.reconciled);
+        assert!((result.computed_balance - 997.5).abs() < 1e-9);
+
+        // Statement already contains the interest booked two days later
+        let result = reconcile_account(&conn, 1, 1007.5, date, 7).unwrap();
+        assert!(!result.reconciled);
+        assert!((result.difference + 10.0).abs() < 1e-9);
+        assert_eq!(result.transactions.len(), 2);
+        assert!((result.transactions[0].amount + 2.5).abs() < 1e-9);
+        assert!(result.transactions[0].included);
+        assert!((result.transactions[1].amount - 10.0).abs() < 1e-9);
+        assert!(!result.transactions[1].included);
+    }
+
+    #[test]
+    fn test_account_ledger_running_balance() {
+        let conn = create_test_db();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency)
+             VALUES (1, 't1', 'account', 1, 'DEPOSIT', '2024-01-01', 100000, 'EUR'),
+                    (2, 't2', 'account', 1, 'DIVIDENDS', '2024-06-01', 5000, 'EUR'),
+                    (3, 't3', 'account', 1, 'BUY', '2024-01-15', 50000, 'EUR'),
+                    (4, 't4', 'account', 1, 'FEES', '2024-01-15', 250, 'EUR');",
+        )
+        .unwrap();
+
+        let ledger = get_account_ledger(&conn, 1).unwrap();
+        let ids: Vec<i64> = ledger.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 3, 4, 2]);
+        let balances: Vec<f64> = ledger.entries.iter().map(|e| e.balance).collect();
+        assert_eq!(balances, vec![1000.0, 500.0, 497.5, 547.5]);
+        assert!((ledger.entries[1].amount + 500.0).abs() < 1e-9);
+
+        let (balance, _) =
+            get_account_balance_at_date(&conn, 1, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap())
+                .unwrap();
+        assert!((ledger.closing_balance - balance).abs() < 1e-9);
+
+        assert!(get_account_ledger(&conn, 99).is_err());
+    }
+}
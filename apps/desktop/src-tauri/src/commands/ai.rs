@@ -6,17 +6,20 @@
 //! - Portfolio chat assistant with action commands
 
 use crate::ai::{
-    claude, gemini, openai, perplexity,
-    list_claude_models, list_openai_models, list_gemini_models, list_perplexity_models,
+    claude, gemini, ollama, openai, perplexity,
+    list_claude_models, list_openai_models, list_gemini_models, list_perplexity_models, list_ollama_models,
     get_model_upgrade, get_models_for_provider, has_vision_support, ModelInfo,
     AiModelInfo, AiError, ChartAnalysisRequest, ChartAnalysisResponse, AnnotationAnalysisResponse,
     EnhancedChartAnalysisRequest, EnhancedAnnotationAnalysisResponse,
-    PortfolioInsightsResponse, ChatMessage, PortfolioChatResponse, ChatSuggestedAction,
+    PortfolioInsightsContext, PortfolioInsightsResponse, ChatMessage, PortfolioChatResponse, ChatSuggestedAction,
     // Context loading from ai/context.rs
     load_portfolio_context,
     // Command parsing from ai/command_parser.rs
     parse_response_with_suggestions,
+    usage::{self, AiUsageEntry, AiUsageSummary},
 };
+use crate::db;
+use crate::commands::ai_helpers::answer_portfolio_value_locally;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use tauri::{command, AppHandle, Emitter};
@@ -37,6 +40,8 @@ pub async fn analyze_chart_with_ai(
         request.model.clone()
     };
 
+    check_ai_budget(&request.provider, &model)?;
+
     let result = match request.provider.as_str() {
         "claude" => claude::analyze(&request.image_base64, &model, &request.api_key, &request.context).await,
         "openai" => openai::analyze(&request.image_base64, &model, &request.api_key, &request.context).await,
@@ -45,6 +50,10 @@ pub async fn analyze_chart_with_ai(
         _ => Err(AiError::other("Unknown", &model, &format!("Unbekannter Anbieter: {}", request.provider))),
     };
 
+    if let Ok(response) = &result {
+        log_ai_usage(&request.provider, &response.model, "analyze_chart_with_ai", response.prompt_tokens, response.completion_tokens, response.tokens_used);
+    }
+
     // Convert AiError to JSON string for frontend parsing
     result.map_err(|e| {
         serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone())
@@ -70,6 +79,10 @@ pub async fn get_ai_models(
         "perplexity" => list_perplexity_models(&api_key)
             .await
             .map_err(|e| e.to_string()),
+        // Local server, no API key needed
+        "ollama" => list_ollama_models(&ollama::load_base_url())
+            .await
+            .map_err(|e| e.to_string()),
         _ => Err(format!("Unknown AI provider: {}", provider)),
     }
 }
@@ -135,6 +148,38 @@ pub async fn analyze_chart_enhanced(
     })
 }
 
+/// Set the base URL of the local Ollama server (`None` resets to the default)
+#[command]
+pub fn set_ollama_base_url(base_url: Option<String>) -> Result<(), String> {
+    let guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+
+    match base_url.map(|url| url.trim().trim_end_matches('/').to_string()).filter(|url| !url.is_empty()) {
+        Some(url) => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err("Die Ollama-URL muss mit http:// oder https:// beginnen".to_string());
+            }
+            conn.execute(
+                r#"
+                INSERT INTO pp_settings (key, value) VALUES (?, ?)
+                ON CONFLICT(key) DO UPDATE SET value = excluded.value
+                "#,
+                rusqlite::params![ollama::BASE_URL_SETTING_KEY, url],
+            )
+            .map_err(|e| format!("Failed to save Ollama URL: {}", e))?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM pp_settings WHERE key = ?",
+                rusqlite::params![ollama::BASE_URL_SETTING_KEY],
+            )
+            .map_err(|e| format!("Failed to reset Ollama URL: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Get vision-capable models for a provider from the centralized registry.
 ///
 /// Returns the list of vision models from the static registry.
@@ -208,6 +253,8 @@ pub async fn analyze_portfolio_with_ai(
         request.model.clone()
     };
 
+    check_ai_budget(&request.provider, &model)?;
+
     // Call the appropriate provider based on analysis type
     let result = match request.analysis_type.as_str() {
         "opportunities" => {
@@ -232,6 +279,10 @@ pub async fn analyze_portfolio_with_ai(
         }
     };
 
+    if let Ok(response) = &result {
+        log_ai_usage(&request.provider, &response.model, "analyze_portfolio_with_ai", response.prompt_tokens, response.completion_tokens, response.tokens_used);
+    }
+
     result.map_err(|e| {
         serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone())
     })
@@ -263,6 +314,147 @@ pub async fn chat_with_portfolio_assistant(
     _app: AppHandle,
     request: PortfolioChatRequest,
 ) -> Result<PortfolioChatResponse, String> {
+    // Deterministic questions are answered locally without an AI round-trip
+    if let Some(response) = answer_chat_locally(&request) {
+        return Ok(response);
+    }
+
+    let (model, context) = prepare_chat(&request)?;
+    check_ai_budget(&request.provider, &model)?;
+
+    // Call the appropriate provider
+    let result = match request.provider.as_str() {
+        "claude" => claude::chat(&model, &request.api_key, &request.messages, &context).await,
+        "openai" => openai::chat(&model, &request.api_key, &request.messages, &context).await,
+        "gemini" => gemini::chat(&model, &request.api_key, &request.messages, &context).await,
+        "perplexity" => perplexity::chat(&model, &request.api_key, &request.messages, &context).await,
+        "ollama" => ollama::chat(&ollama::load_base_url(), &model, &request.messages, &context).await,
+        _ => Err(AiError::other("Unknown", &model, &format!("Unbekannter Anbieter: {}", request.provider))),
+    };
+
+    match result {
+        Ok(response) => {
+            log_ai_usage(&request.provider, &response.model, "chat_with_portfolio_assistant", response.prompt_tokens, response.completion_tokens, response.tokens_used);
+            Ok(finalize_chat_response(response))
+        }
+        Err(e) => Err(serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone())),
+    }
+}
+
+/// Chat with the portfolio assistant, streaming the answer via events.
+///
+/// Emits `ai-chat-token` for each text fragment and a final `ai-chat-done`
+/// with token usage (or the error). The streamed text is raw model output; the
+/// returned response is the authoritative version with commands removed,
+/// query results appended and suggestions extracted.
+/// Perplexity and Ollama do not stream and emit their full answer as a single token.
+#[command]
+pub async fn chat_with_portfolio_assistant_stream(
+    app: AppHandle,
+    request: PortfolioChatRequest,
+    stream_id: String,
+) -> Result<PortfolioChatResponse, String> {
+    // Deterministic questions are answered locally without an AI round-trip
+    if let Some(response) = answer_chat_locally(&request) {
+        emit_ai_chat_token(&app, &stream_id, &response.response);
+        emit_ai_chat_done(&app, AiChatDonePayload {
+            stream_id,
+            provider: response.provider.clone(),
+            model: response.model.clone(),
+            tokens_used: None,
+            error: None,
+        });
+        return Ok(response);
+    }
+
+    let (model, context) = prepare_chat(&request)?;
+    if let Err(error) = check_ai_budget(&request.provider, &model) {
+        emit_ai_chat_done(&app, AiChatDonePayload {
+            stream_id,
+            provider: request.provider.clone(),
+            model,
+            tokens_used: None,
+            error: Some(error.clone()),
+        });
+        return Err(error);
+    }
+
+    let on_delta = |delta: &str| emit_ai_chat_token(&app, &stream_id, delta);
+
+    let result = match request.provider.as_str() {
+        "claude" => claude::chat_stream(&model, &request.api_key, &request.messages, &context, on_delta).await,
+        "openai" => openai::chat_stream(&model, &request.api_key, &request.messages, &context, on_delta).await,
+        "gemini" => gemini::chat_stream(&model, &request.api_key, &request.messages, &context, on_delta).await,
+        "perplexity" => perplexity::chat(&model, &request.api_key, &request.messages, &context)
+            .await
+            .inspect(|response| emit_ai_chat_token(&app, &stream_id, &response.response)),
+        "ollama" => ollama::chat(&ollama::load_base_url(), &model, &request.messages, &context)
+            .await
+            .inspect(|response| emit_ai_chat_token(&app, &stream_id, &response.response)),
+        _ => Err(AiError::other("Unknown", &model, &format!("Unbekannter Anbieter: {}", request.provider))),
+    };
+
+    match result {
+        Ok(response) => {
+            log_ai_usage(&request.provider, &response.model, "chat_with_portfolio_assistant_stream", response.prompt_tokens, response.completion_tokens, response.tokens_used);
+            emit_ai_chat_done(&app, AiChatDonePayload {
+                stream_id,
+                provider: response.provider.clone(),
+                model: response.model.clone(),
+                tokens_used: response.tokens_used,
+                error: None,
+            });
+            Ok(finalize_chat_response(response))
+        }
+        Err(e) => {
+            let error = serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone());
+            emit_ai_chat_done(&app, AiChatDonePayload {
+                stream_id,
+                provider: e.provider.clone(),
+                model: e.model.clone(),
+                tokens_used: None,
+                error: Some(error.clone()),
+            });
+            Err(error)
+        }
+    }
+}
+
+/// Answer the latest user message without the AI if it is a simple
+/// deterministic query (currently: portfolio value at a date).
+/// Returns `None` if the question should be sent to the AI.
+fn answer_chat_locally(request: &PortfolioChatRequest) -> Option<PortfolioChatResponse> {
+    let last = request.messages.last()?;
+    if last.role != "user" || !last.attachments.is_empty() {
+        return None;
+    }
+
+    match answer_portfolio_value_locally(&last.content) {
+        Ok(Some(result)) => {
+            log::info!("Answered portfolio value question locally for {}", result.date);
+            Some(PortfolioChatResponse {
+                response: result.message,
+                provider: "local".to_string(),
+                model: "rule-based".to_string(),
+                tokens_used: None,
+                prompt_tokens: None,
+                completion_tokens: None,
+                suggestions: Vec::new(),
+            })
+        }
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("Local portfolio value answer failed, falling back to AI: {}", e);
+            None
+        }
+    }
+}
+
+/// Resolve the model (auto-upgrading deprecated ones), check vision support
+/// and load the portfolio context for a chat request
+fn prepare_chat(
+    request: &PortfolioChatRequest,
+) -> Result<(String, PortfolioInsightsContext), String> {
     // Check if any message has image attachments
     let has_images = request.messages.iter().any(|m| !m.attachments.is_empty());
 
@@ -286,53 +478,109 @@ pub async fn chat_with_portfolio_assistant(
     // For chat, we always include technical signals (no progress events needed)
     let context = load_portfolio_context(&request.base_currency, request.user_name.clone(), true, None)?;
 
-    // Call the appropriate provider
-    let result = match request.provider.as_str() {
-        "claude" => claude::chat(&model, &request.api_key, &request.messages, &context).await,
-        "openai" => openai::chat(&model, &request.api_key, &request.messages, &context).await,
-        "gemini" => gemini::chat(&model, &request.api_key, &request.messages, &context).await,
-        "perplexity" => perplexity::chat(&model, &request.api_key, &request.messages, &context).await,
-        _ => Err(AiError::other("Unknown", &model, &format!("Unbekannter Anbieter: {}", request.provider))),
+    Ok((model, context))
+}
+
+/// Process a raw chat response using the secure suggestion-based command parser
+///
+/// SECURITY: This uses parse_response_with_suggestions which:
+/// - Returns watchlist modifications as SUGGESTIONS (not executed)
+/// - Only executes read-only queries (transactions, portfolio value)
+fn finalize_chat_response(mut response: PortfolioChatResponse) -> PortfolioChatResponse {
+    // Parse response and extract suggestions (watchlist commands NOT executed)
+    let parsed = parse_response_with_suggestions(response.response.clone());
+
+    // Update response with cleaned text
+    response.response = parsed.cleaned_response;
+
+    // Append query results if any (read-only queries are safe to execute)
+    if !parsed.query_results.is_empty() {
+        if response.response.trim().is_empty() || response.response.len() < 10 {
+            response.response = parsed.query_results.join("\n\n");
+        } else {
+            response.response = format!("{}\n\n{}", response.response, parsed.query_results.join("\n\n"));
+        }
+    }
+
+    // Convert suggestions to response format
+    // Frontend must display these and get user confirmation before executing
+    response.suggestions = parsed.suggestions
+        .into_iter()
+        .map(|s| ChatSuggestedAction {
+            action_type: s.action_type,
+            description: s.description,
+            payload: s.payload,
+        })
+        .collect();
+
+    response
+}
+
+// ============================================================================
+// Usage Accounting
+// ============================================================================
+
+/// Reject the request locally if the provider's monthly token budget is used up.
+/// Returns the JSON-serialized `AiError` like the provider calls do.
+fn check_ai_budget(provider: &str, model: &str) -> Result<(), String> {
+    let Ok(guard) = db::get_connection() else {
+        return Ok(());
+    };
+    let Some(conn) = guard.as_ref() else {
+        return Ok(());
     };
 
-    // Process the result using the secure suggestion-based command parser
-    // SECURITY: This uses parse_response_with_suggestions which:
-    // - Returns watchlist modifications as SUGGESTIONS (not executed)
-    // - Only executes read-only queries (transactions, portfolio value)
-    match result {
-        Ok(mut response) => {
-            // Parse response and extract suggestions (watchlist commands NOT executed)
-            let parsed = parse_response_with_suggestions(response.response.clone());
+    usage::check_budget(conn, provider, model, &usage::current_month())
+        .map_err(|e| serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone()))
+}
 
-            // Update response with cleaned text
-            response.response = parsed.cleaned_response;
+/// Log token usage of a successful AI call. Failures are only logged,
+/// accounting must never break the actual response.
+fn log_ai_usage(
+    provider: &str,
+    model: &str,
+    command: &str,
+    prompt_tokens: Option<u32>,
+    completion_tokens: Option<u32>,
+    total_tokens: Option<u32>,
+) {
+    let entry = AiUsageEntry {
+        provider,
+        model,
+        command,
+        prompt_tokens,
+        completion_tokens,
+        total_tokens,
+    };
 
-            // Append query results if any (read-only queries are safe to execute)
-            if !parsed.query_results.is_empty() {
-                if response.response.trim().is_empty() || response.response.len() < 10 {
-                    response.response = parsed.query_results.join("\n\n");
-                } else {
-                    response.response = format!("{}\n\n{}", response.response, parsed.query_results.join("\n\n"));
-                }
-            }
+    let result = db::get_connection()
+        .map_err(|e| e.to_string())
+        .and_then(|guard| match guard.as_ref() {
+            Some(conn) => usage::record_usage(conn, &entry),
+            None => Err("Database not initialized".to_string()),
+        });
 
-            // Convert suggestions to response format
-            // Frontend must display these and get user confirmation before executing
-            response.suggestions = parsed.suggestions
-                .into_iter()
-                .map(|s| ChatSuggestedAction {
-                    action_type: s.action_type,
-                    description: s.description,
-                    payload: s.payload,
-                })
-                .collect();
-
-            Ok(response)
-        }
-        Err(e) => Err(serde_json::to_string(&e).unwrap_or_else(|_| e.message.clone())),
+    if let Err(e) = result {
+        log::warn!("Failed to record AI usage: {}", e);
     }
 }
 
+/// Get per-provider AI token usage for the current month, including budgets
+#[command]
+pub fn get_ai_usage_summary() -> Result<AiUsageSummary, String> {
+    let guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+    usage::get_usage_summary(conn, &usage::current_month())
+}
+
+/// Set the monthly token budget for an AI provider (`None` removes the limit)
+#[command]
+pub fn set_ai_monthly_budget(provider: String, monthly_token_limit: Option<i64>) -> Result<(), String> {
+    let guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+    usage::set_monthly_budget(conn, &provider, monthly_token_limit)
+}
+
 // ============================================================================
 // Confirmed Action Execution (Security)
 // ============================================================================
@@ -373,7 +621,7 @@ pub async fn execute_confirmed_ai_action(
 
 use crate::ai::types::{TransactionCreateCommand, PortfolioTransferCommand, TransactionDeleteCommand};
 use crate::commands::crud::{create_transaction, delete_transaction, CreateTransactionRequest, TransactionUnitData};
-use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::events::{emit_ai_chat_done, emit_ai_chat_token, emit_data_changed, AiChatDonePayload, DataChangedPayload};
 
 /// Execute a confirmed transaction creation
 ///
@@ -413,10 +661,11 @@ pub fn execute_confirmed_transaction(
         note: cmd.note.clone(),
         units: None, // No units from AI commands
         reference_account_id: None, // Could be extended later
+        idempotency_key: None,
     };
 
     // Create the transaction
-    let result = create_transaction(app.clone(), request)
+    let result = create_transaction(app.clone(), request, None)
         .map_err(|e| format!("Fehler beim Erstellen der Transaktion: {}", e))?;
 
     // Emit data_changed event
@@ -461,9 +710,10 @@ pub fn execute_confirmed_portfolio_transfer(
         note: cmd.note.clone(),
         units: None,
         reference_account_id: None,
+        idempotency_key: None,
     };
 
-    let outbound_result = create_transaction(app.clone(), outbound_request)
+    let outbound_result = create_transaction(app.clone(), outbound_request, None)
         .map_err(|e| format!("Fehler bei Auslieferung: {}", e))?;
 
     // Create DELIVERY_INBOUND to target portfolio
@@ -479,9 +729,10 @@ pub fn execute_confirmed_portfolio_transfer(
         note: cmd.note.clone(),
         units: None,
         reference_account_id: None,
+        idempotency_key: None,
     };
 
-    let inbound_result = create_transaction(app.clone(), inbound_request)
+    let inbound_result = create_transaction(app.clone(), inbound_request, None)
         .map_err(|e| format!("Fehler bei Einlieferung: {}", e))?;
 
     // Emit data_changed event
@@ -506,7 +757,7 @@ pub fn execute_confirmed_transaction_delete(
         .map_err(|e| format!("Invalid delete payload: {}", e))?;
 
     // Delete the transaction
-    delete_transaction(app.clone(), cmd.transaction_id)?;
+    delete_transaction(app.clone(), cmd.transaction_id, None, None)?;
 
     Ok(format!(
         "Transaktion #{} erfolgreich gelöscht",
@@ -664,7 +915,7 @@ pub fn import_extracted_transactions(
         return Err("Keine Transaktionen zum Importieren".to_string());
     }
 
-    let mut imported_count = 0;
+    let mut imported_ids: Vec<i64> = Vec::new();
     let mut duplicates: Vec<String> = Vec::new();
     let mut errors: Vec<String> = Vec::new();
 
@@ -672,12 +923,17 @@ pub fn import_extracted_transactions(
         // Each transaction is imported separately to avoid holding DB connection
         // while calling create_transaction (which also needs the connection)
         match import_single_extracted_transaction(&app, txn, portfolio_id, delivery_mode) {
-            Ok(_) => imported_count += 1,
+            Ok(id) => imported_ids.push(id),
             Err(ImportSingleError::Duplicate(msg)) => duplicates.push(msg),
             Err(ImportSingleError::Other(msg)) => errors.push(format!("{}: {}", txn.date, msg)),
         }
     }
 
+    if let Err(e) = crate::commands::crud::rebuild_fifo_for_transactions(&imported_ids) {
+        errors.push(format!("FIFO-Neuberechnung fehlgeschlagen: {}", e));
+    }
+    let imported_count = imported_ids.len();
+
     // NEVER throw Err() - always return a result with imported_count, duplicates, and errors
     // The frontend will handle displaying appropriate messages for each case
     // This allows duplicates and errors to be shown as friendly chat messages
@@ -1002,9 +1258,11 @@ fn import_single_extracted_transaction(
         note,
         units: if units.is_empty() { None } else { Some(units) },
         reference_account_id: None,
+        idempotency_key: None,
     };
 
-    let result = create_transaction(app.clone(), request)
+    // FIFO lots are rebuilt once after all transactions are imported
+    let result = create_transaction(app.clone(), request, Some(true))
         .map_err(|e| ImportSingleError::Other(format!("Fehler: {}", e)))?;
 
     Ok(result.id)
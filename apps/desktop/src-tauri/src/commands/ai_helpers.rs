@@ -6,6 +6,8 @@
 use crate::db;
 use crate::quotes::{alphavantage, yahoo};
 use chrono::Utc;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use tauri::command;
@@ -411,23 +413,13 @@ pub fn ai_query_transactions(
         conditions.push(format!("strftime('%Y', t.date) = '{}'", y));
     }
 
-    // Transaction type filter (map DELIVERY to BUY/SELL for display)
+    // Transaction type filter (BUY/SELL include deliveries)
     if let Some(ref tt) = txn_type {
-        let tt_upper = tt.trim().to_uppercase();
-        match tt_upper.as_str() {
-            "BUY" | "KAUF" => {
-                conditions.push("t.txn_type IN ('BUY', 'DELIVERY_INBOUND')".to_string());
-            }
-            "SELL" | "VERKAUF" => {
-                conditions.push("t.txn_type IN ('SELL', 'DELIVERY_OUTBOUND')".to_string());
-            }
-            "DIVIDEND" | "DIVIDENDE" | "DIVIDENDS" => {
-                conditions.push("t.txn_type = 'DIVIDENDS'".to_string());
-            }
-            _ => {
-                conditions.push(format!("t.txn_type = '{}'", tt_upper));
-            }
-        }
+        let types: Vec<String> = super::data::txn_types_for_filter(tt)
+            .iter()
+            .map(|t| format!("'{}'", t))
+            .collect();
+        conditions.push(format!("t.txn_type IN ({})", types.join(", ")));
     }
 
     let where_clause = if conditions.is_empty() {
@@ -512,7 +504,7 @@ pub fn ai_query_transactions(
 // Helper Functions
 // ============================================================================
 
-fn find_or_create_watchlist(name: &str) -> Result<i64, String> {
+pub(crate) fn find_or_create_watchlist(name: &str) -> Result<i64, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
@@ -886,6 +878,185 @@ pub fn ai_query_portfolio_value(date: String) -> Result<AiPortfolioValueResult,
     })
 }
 
+// ============================================================================
+// Local Portfolio Value Intent (no AI round-trip)
+// ============================================================================
+
+/// Phrases that indicate a question about the total portfolio value
+const PORTFOLIO_VALUE_KEYWORDS: &[&str] = &[
+    "depotwert",
+    "portfoliowert",
+    "depotstand",
+    "wert des depots",
+    "wert meines depots",
+    "wert des portfolios",
+    "wert meines portfolios",
+    "stand das depot",
+    "stand mein depot",
+    "stand das portfolio",
+    "stand mein portfolio",
+    "portfolio value",
+    "value of my portfolio",
+    "value of the portfolio",
+    "portfolio worth",
+    "portfolio was worth",
+];
+
+/// Phrases that indicate the question needs more than a single value lookup
+const PORTFOLIO_VALUE_COMPLEX_MARKERS: &[&str] = &[
+    "warum", "wieso", "weshalb", "vergleich", "entwicklung", "prognose", "und wie", "und was",
+    "why", "compare", "comparison", "forecast", "development", "and how", "and what",
+];
+
+/// Questions longer than this are left to the AI
+const PORTFOLIO_VALUE_MAX_QUESTION_LEN: usize = 120;
+
+const MONTH_NAMES: &[(&str, u32)] = &[
+    ("januar", 1), ("january", 1), ("jan", 1),
+    ("februar", 2), ("february", 2), ("feb", 2),
+    ("märz", 3), ("maerz", 3), ("march", 3), ("mär", 3), ("mar", 3),
+    ("april", 4), ("apr", 4),
+    ("mai", 5), ("may", 5),
+    ("juni", 6), ("june", 6), ("jun", 6),
+    ("juli", 7), ("july", 7), ("jul", 7),
+    ("august", 8), ("aug", 8),
+    ("september", 9), ("sept", 9), ("sep", 9),
+    ("oktober", 10), ("october", 10), ("okt", 10), ("oct", 10),
+    ("november", 11), ("nov", 11),
+    ("dezember", 12), ("december", 12), ("dez", 12), ("dec", 12),
+];
+
+static RE_DATE_DE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(\d{1,2})\.(\d{1,2})\.(\d{4})\b").unwrap());
+static RE_DATE_ISO: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+static RE_DATE_DAY_MONTH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(\d{1,2})(?:\.|st|nd|rd|th)?\s+(?:of\s+)?([a-zä]+)\.?\s+(\d{4})\b").unwrap()
+});
+static RE_DATE_MONTH_DAY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b([a-z]+)\.?\s+(\d{1,2})(?:st|nd|rd|th)?,?\s+(\d{4})\b").unwrap()
+});
+static RE_YEAR_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:ende|jahresende|end of|year[- ]end)\s+(\d{4})\b").unwrap()
+});
+
+fn month_from_name(name: &str) -> Option<u32> {
+    MONTH_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, m)| *m)
+}
+
+/// Extract a date from a (lowercased) German or English question.
+fn parse_question_date(text: &str, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+    let ymd = |y: &str, m: u32, d: &str| {
+        chrono::NaiveDate::from_ymd_opt(y.parse().ok()?, m, d.parse().ok()?)
+    };
+
+    if let Some(c) = RE_DATE_ISO.captures(text) {
+        return ymd(&c[1], c[2].parse().ok()?, &c[3]);
+    }
+    if let Some(c) = RE_DATE_DE.captures(text) {
+        return ymd(&c[3], c[2].parse().ok()?, &c[1]);
+    }
+    if let Some(c) = RE_DATE_DAY_MONTH.captures(text) {
+        if let Some(month) = month_from_name(&c[2]) {
+            return ymd(&c[3], month, &c[1]);
+        }
+    }
+    if let Some(c) = RE_DATE_MONTH_DAY.captures(text) {
+        if let Some(month) = month_from_name(&c[1]) {
+            return ymd(&c[3], month, &c[2]);
+        }
+    }
+    if let Some(c) = RE_YEAR_END.captures(text) {
+        return ymd(&c[1], 12, "31");
+    }
+    if text.contains("vorgestern") {
+        return today.pred_opt()?.pred_opt();
+    }
+    if text.contains("gestern") || text.contains("yesterday") {
+        return today.pred_opt();
+    }
+    if text.contains("heute") || text.contains("today") || text.contains("aktuell") || text.contains("current") {
+        return Some(today);
+    }
+    None
+}
+
+/// Recognize simple "portfolio value at date X" questions without an AI call.
+///
+/// Returns the requested date if the question clearly asks only for the total
+/// portfolio value at one date (e.g. "Depotwert am 04.04.2025?",
+/// "What was my portfolio value on April 4, 2025?"). Returns `None` for
+/// anything else, in which case the question should go to the AI.
+pub fn parse_portfolio_value_intent(
+    question: &str,
+    today: chrono::NaiveDate,
+) -> Option<chrono::NaiveDate> {
+    let text = question.trim().to_lowercase();
+    if text.is_empty() || text.chars().count() > PORTFOLIO_VALUE_MAX_QUESTION_LEN {
+        return None;
+    }
+    if !PORTFOLIO_VALUE_KEYWORDS.iter().any(|k| text.contains(k)) {
+        return None;
+    }
+    if PORTFOLIO_VALUE_COMPLEX_MARKERS.iter().any(|k| text.contains(k)) {
+        return None;
+    }
+    parse_question_date(&text, today)
+}
+
+/// Answer a portfolio value question locally if it matches a known pattern.
+///
+/// Returns `Ok(None)` if the question is not a simple portfolio value query,
+/// so the caller can fall back to the AI. Works without an API key.
+pub fn answer_portfolio_value_locally(question: &str) -> Result<Option<AiPortfolioValueResult>, String> {
+    use crate::performance::get_portfolio_value_at_date_with_currency;
+
+    let today = chrono::Local::now().date_naive();
+    let Some(date) = parse_portfolio_value_intent(question, today) else {
+        return Ok(None);
+    };
+
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let date_display = date.format("%d.%m.%Y").to_string();
+
+    if date > today {
+        return Ok(Some(AiPortfolioValueResult {
+            date: date_str,
+            value: 0.0,
+            currency: "EUR".to_string(),
+            found: false,
+            message: format!("Datum {} liegt in der Zukunft.", date_display),
+        }));
+    }
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let currency = crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let value = get_portfolio_value_at_date_with_currency(conn, None, date)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(AiPortfolioValueResult {
+        date: date_str,
+        value,
+        currency: currency.clone(),
+        found: true,
+        message: format!("Depotwert am {}: {:.2} {}", date_display, value, currency),
+    }))
+}
+
+/// Try to answer a portfolio value question without the AI.
+/// Returns `None` if the question should be sent to the AI instead.
+#[command]
+pub fn ai_answer_portfolio_value_locally(question: String) -> Result<Option<AiPortfolioValueResult>, String> {
+    answer_portfolio_value_locally(&question)
+}
+
 // ============================================================================
 // API Key Management Commands
 // ============================================================================
@@ -999,7 +1170,7 @@ use crate::ai::user_templates::{
     execute_user_template as execute_template_impl,
     get_all_user_templates as get_templates_impl,
     update_user_template as update_template_impl,
-    validate_user_sql, UserTemplate, UserTemplateInput,
+    validate_user_template_sql, UserTemplate, UserTemplateInput,
 };
 
 /// Get all user-defined query templates
@@ -1051,7 +1222,7 @@ pub struct UserTemplateTestResult {
 #[command]
 pub fn test_user_template(template: UserTemplateInput) -> Result<UserTemplateTestResult, String> {
     // First validate the SQL
-    if let Err(e) = validate_user_sql(&template.sql_query) {
+    if let Err(e) = validate_user_template_sql(&template.sql_query) {
         return Ok(UserTemplateTestResult {
             success: false,
             row_count: 0,
@@ -1107,3 +1278,62 @@ pub fn get_query_templates_prompt() -> Result<String, String> {
     let conn = guard.as_ref().ok_or("Database not initialized")?;
     Ok(crate::ai::query_templates::get_templates_for_prompt_with_user_templates(conn))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 15).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn test_portfolio_value_intent_german_formats() {
+        assert_eq!(parse_portfolio_value_intent("Depotwert am 04.04.2025", today()), date(2025, 4, 4));
+        assert_eq!(
+            parse_portfolio_value_intent("Wie hoch stand das Depot am 4. April 2025?", today()),
+            date(2025, 4, 4)
+        );
+        assert_eq!(
+            parse_portfolio_value_intent("Wie war mein Depotwert Ende 2024?", today()),
+            date(2024, 12, 31)
+        );
+        assert_eq!(parse_portfolio_value_intent("Depotwert gestern?", today()), date(2025, 6, 14));
+    }
+
+    #[test]
+    fn test_portfolio_value_intent_english_formats() {
+        assert_eq!(
+            parse_portfolio_value_intent("What was my portfolio value on April 4, 2025?", today()),
+            date(2025, 4, 4)
+        );
+        assert_eq!(
+            parse_portfolio_value_intent("Portfolio value 2025-01-31", today()),
+            date(2025, 1, 31)
+        );
+        assert_eq!(
+            parse_portfolio_value_intent("portfolio worth on the 3rd of march 2025", today()),
+            date(2025, 3, 3)
+        );
+    }
+
+    #[test]
+    fn test_portfolio_value_intent_rejects_other_questions() {
+        // No value keyword
+        assert_eq!(parse_portfolio_value_intent("Was habe ich am 04.04.2025 gekauft?", today()), None);
+        // No date
+        assert_eq!(parse_portfolio_value_intent("Wie berechnet sich der Depotwert?", today()), None);
+        // Needs reasoning beyond a lookup
+        assert_eq!(
+            parse_portfolio_value_intent("Warum war der Depotwert am 04.04.2025 so niedrig?", today()),
+            None
+        );
+        // Invalid date
+        assert_eq!(parse_portfolio_value_intent("Depotwert am 31.02.2025", today()), None);
+    }
+}
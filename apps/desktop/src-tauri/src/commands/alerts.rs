@@ -5,8 +5,11 @@
 //! 2. Allocation Alerts - Alerts when portfolio allocation deviates from targets
 
 use crate::db;
+use crate::events;
+use rusqlite::{Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use std::collections::HashMap;
+use tauri::{command, AppHandle};
 use uuid::Uuid;
 
 // ============================================================================
@@ -31,6 +34,10 @@ pub struct PriceAlert {
     pub last_triggered_price: Option<f64>,
     pub note: Option<String>,
     pub created_at: String,
+    /// Price captured at creation, basis for percentage alerts
+    pub reference_price: Option<f64>,
+    /// Highest price seen since creation (trailing stop)
+    pub high_water_mark: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +66,8 @@ pub struct TriggeredAlert {
     pub alert: PriceAlert,
     pub current_price: f64,
     pub trigger_reason: String,
+    /// Reference price for percentage alerts
+    pub reference_price: Option<f64>,
 }
 
 // ============================================================================
@@ -84,7 +93,7 @@ pub fn get_price_alerts(security_id: Option<i64>) -> Result<Vec<PriceAlert>, Str
                     a.alert_type, a.target_value, a.target_value_2,
                     a.is_active, a.is_triggered, a.trigger_count,
                     a.last_triggered_at, a.last_triggered_price,
-                    a.note, a.created_at
+                    a.note, a.created_at, a.reference_price, a.high_water_mark
                 FROM pp_price_alert a
                 LEFT JOIN pp_security s ON s.id = a.security_id
                 WHERE a.security_id = ?1
@@ -110,7 +119,7 @@ pub fn get_price_alerts(security_id: Option<i64>) -> Result<Vec<PriceAlert>, Str
                     a.alert_type, a.target_value, a.target_value_2,
                     a.is_active, a.is_triggered, a.trigger_count,
                     a.last_triggered_at, a.last_triggered_price,
-                    a.note, a.created_at
+                    a.note, a.created_at, a.reference_price, a.high_water_mark
                 FROM pp_price_alert a
                 LEFT JOIN pp_security s ON s.id = a.security_id
                 ORDER BY a.created_at DESC
@@ -147,7 +156,7 @@ pub fn get_active_alerts() -> Result<Vec<PriceAlert>, String> {
                 a.alert_type, a.target_value, a.target_value_2,
                 a.is_active, a.is_triggered, a.trigger_count,
                 a.last_triggered_at, a.last_triggered_price,
-                a.note, a.created_at
+                a.note, a.created_at, a.reference_price, a.high_water_mark
             FROM pp_price_alert a
             LEFT JOIN pp_security s ON s.id = a.security_id
             WHERE a.is_active = 1
@@ -172,14 +181,44 @@ pub fn create_price_alert(request: CreateAlertRequest) -> Result<PriceAlert, Str
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    // Percentage and trailing-stop alerts store the percentage as target value and
+    // are measured against the price at creation time
+    let relative = matches!(request.alert_type.as_str(), "price_pct_change" | "trailing_stop");
+    if request.alert_type == "price_pct_change"
+        && (request.target_value == 0.0 || request.target_value <= -100.0)
+    {
+        return Err("Prozentuale Veränderung muss ungleich 0 und größer als -100% sein".to_string());
+    }
+    if request.alert_type == "trailing_stop"
+        && (request.target_value <= 0.0 || request.target_value >= 100.0)
+    {
+        return Err("Trailing-Stop muss zwischen 0 und 100% liegen".to_string());
+    }
+    let reference_price = if relative {
+        let price: Option<f64> = conn
+            .query_row(
+                "SELECT value / 100000000.0 FROM pp_latest_price WHERE security_id = ?1",
+                [request.security_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        Some(price.ok_or_else(|| {
+            "Kein aktueller Kurs für das Wertpapier vorhanden".to_string()
+        })?)
+    } else {
+        None
+    };
+
     let uuid = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
         r#"
         INSERT INTO pp_price_alert (
-            uuid, security_id, alert_type, target_value, target_value_2, note, created_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            uuid, security_id, alert_type, target_value, target_value_2, reference_price,
+            high_water_mark, note, created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
         "#,
         rusqlite::params![
             uuid,
@@ -187,6 +226,8 @@ pub fn create_price_alert(request: CreateAlertRequest) -> Result<PriceAlert, Str
             request.alert_type,
             request.target_value,
             request.target_value_2,
+            reference_price,
+            reference_price.filter(|_| request.alert_type == "trailing_stop"),
             request.note,
             now
         ],
@@ -206,7 +247,7 @@ pub fn create_price_alert(request: CreateAlertRequest) -> Result<PriceAlert, Str
                 a.alert_type, a.target_value, a.target_value_2,
                 a.is_active, a.is_triggered, a.trigger_count,
                 a.last_triggered_at, a.last_triggered_price,
-                a.note, a.created_at
+                a.note, a.created_at, a.reference_price, a.high_water_mark
             FROM pp_price_alert a
             LEFT JOIN pp_security s ON s.id = a.security_id
             WHERE a.id = ?1
@@ -277,7 +318,7 @@ pub fn update_price_alert(request: UpdateAlertRequest) -> Result<PriceAlert, Str
                 a.alert_type, a.target_value, a.target_value_2,
                 a.is_active, a.is_triggered, a.trigger_count,
                 a.last_triggered_at, a.last_triggered_price,
-                a.note, a.created_at
+                a.note, a.created_at, a.reference_price, a.high_water_mark
             FROM pp_price_alert a
             LEFT JOIN pp_security s ON s.id = a.security_id
             WHERE a.id = ?1
@@ -329,7 +370,7 @@ pub fn toggle_price_alert(id: i64) -> Result<PriceAlert, String> {
                 a.alert_type, a.target_value, a.target_value_2,
                 a.is_active, a.is_triggered, a.trigger_count,
                 a.last_triggered_at, a.last_triggered_price,
-                a.note, a.created_at
+                a.note, a.created_at, a.reference_price, a.high_water_mark
             FROM pp_price_alert a
             LEFT JOIN pp_security s ON s.id = a.security_id
             WHERE a.id = ?1
@@ -368,8 +409,8 @@ pub fn check_price_alerts() -> Result<Vec<TriggeredAlert>, String> {
                 a.alert_type, a.target_value, a.target_value_2,
                 a.is_active, a.is_triggered, a.trigger_count,
                 a.last_triggered_at, a.last_triggered_price,
-                a.note, a.created_at,
-                lp.value as current_price
+                a.note, a.created_at, a.reference_price, a.high_water_mark,
+                lp.value / 100000000.0 as current_price
             FROM pp_price_alert a
             LEFT JOIN pp_security s ON s.id = a.security_id
             LEFT JOIN pp_latest_price lp ON lp.security_id = a.security_id
@@ -380,24 +421,8 @@ pub fn check_price_alerts() -> Result<Vec<TriggeredAlert>, String> {
 
     let rows = stmt
         .query_map([], |row| {
-            let alert = PriceAlert {
-                id: row.get(0)?,
-                uuid: row.get(1)?,
-                security_id: row.get(2)?,
-                security_name: row.get(3)?,
-                security_ticker: row.get(4)?,
-                alert_type: row.get(5)?,
-                target_value: row.get(6)?,
-                target_value_2: row.get(7)?,
-                is_active: row.get::<_, i32>(8)? != 0,
-                is_triggered: row.get::<_, i32>(9)? != 0,
-                trigger_count: row.get(10)?,
-                last_triggered_at: row.get(11)?,
-                last_triggered_price: row.get(12)?,
-                note: row.get(13)?,
-                created_at: row.get(14)?,
-            };
-            let current_price: f64 = row.get(15)?;
+            let alert = map_alert_row(row)?;
+            let current_price: f64 = row.get(17)?;
             Ok((alert, current_price))
         })
         .map_err(|e| e.to_string())?;
@@ -407,14 +432,24 @@ pub fn check_price_alerts() -> Result<Vec<TriggeredAlert>, String> {
         .map_err(|e| e.to_string())?;
 
     // Check each alert
-    for (alert, current_price) in alerts_with_prices {
-        let (is_triggered, reason) = check_alert_condition(&alert, current_price);
+    for (mut alert, current_price) in alerts_with_prices {
+        if ratchet_high_water_mark(&mut alert, current_price) {
+            conn.execute(
+                "UPDATE pp_price_alert SET high_water_mark = ?1 WHERE id = ?2",
+                rusqlite::params![alert.high_water_mark, alert.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        let indicators = load_market_indicators(conn, &alert).map_err(|e| e.to_string())?;
+        let (is_triggered, reason) = check_alert_condition(&alert, current_price, &indicators);
 
         if is_triggered {
             triggered.push(TriggeredAlert {
                 alert: alert.clone(),
                 current_price,
                 trigger_reason: reason,
+                reference_price: alert.reference_price,
             });
 
             // Update alert as triggered
@@ -475,10 +510,126 @@ fn map_alert_row(row: &rusqlite::Row) -> rusqlite::Result<PriceAlert> {
         last_triggered_price: row.get(12)?,
         note: row.get(13)?,
         created_at: row.get(14)?,
+        reference_price: row.get(15)?,
+        high_water_mark: row.get(16)?,
     })
 }
 
-fn check_alert_condition(alert: &PriceAlert, current_price: f64) -> (bool, String) {
+/// Default RSI period when the alert does not specify one in target_value_2
+const DEFAULT_RSI_PERIOD: usize = 14;
+
+/// Number of previous trading days averaged for volume-spike alerts
+const VOLUME_AVERAGE_DAYS: usize = 20;
+
+/// Indicator values needed to evaluate RSI and volume alerts
+#[derive(Debug, Clone, Default)]
+struct MarketIndicators {
+    rsi: Option<f64>,
+    /// Latest volume divided by the average of the previous 20 days
+    volume_ratio: Option<f64>,
+}
+
+/// Load the indicators an alert type depends on from pp_price
+fn load_market_indicators(
+    conn: &Connection,
+    alert: &PriceAlert,
+) -> rusqlite::Result<MarketIndicators> {
+    let mut indicators = MarketIndicators::default();
+
+    match alert.alert_type.as_str() {
+        "rsi_above" | "rsi_below" => {
+            let period = alert
+                .target_value_2
+                .filter(|p| *p >= 2.0)
+                .map_or(DEFAULT_RSI_PERIOD, |p| p as usize);
+            // Enough history for Wilder's smoothing to settle
+            let mut stmt = conn.prepare(
+                "SELECT value / 100000000.0 FROM pp_price WHERE security_id = ?1 ORDER BY date DESC LIMIT ?2",
+            )?;
+            let mut closes = stmt
+                .query_map(
+                    rusqlite::params![alert.security_id, (period * 10) as i64],
+                    |row| row.get::<_, f64>(0),
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            closes.reverse();
+            indicators.rsi = calculate_rsi(&closes, period);
+        }
+        "volume_spike" => {
+            let mut stmt = conn.prepare(
+                "SELECT volume FROM pp_price WHERE security_id = ?1 AND volume IS NOT NULL ORDER BY date DESC LIMIT ?2",
+            )?;
+            let volumes = stmt
+                .query_map(
+                    rusqlite::params![alert.security_id, (VOLUME_AVERAGE_DAYS + 1) as i64],
+                    |row| row.get::<_, i64>(0),
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            indicators.volume_ratio = calculate_volume_ratio(&volumes);
+        }
+        _ => {}
+    }
+
+    Ok(indicators)
+}
+
+/// RSI of the last close using Wilder's smoothing (same as the chart indicator)
+fn calculate_rsi(closes: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || closes.len() < period + 1 {
+        return None;
+    }
+
+    let changes: Vec<f64> = closes.windows(2).map(|w| w[1] - w[0]).collect();
+    let gain = |c: &f64| c.max(0.0);
+    let loss = |c: &f64| (-c).max(0.0);
+
+    let mut avg_gain = changes[..period].iter().map(gain).sum::<f64>() / period as f64;
+    let mut avg_loss = changes[..period].iter().map(loss).sum::<f64>() / period as f64;
+    for change in &changes[period..] {
+        avg_gain = (avg_gain * (period - 1) as f64 + gain(change)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + loss(change)) / period as f64;
+    }
+
+    if avg_loss == 0.0 {
+        return Some(100.0);
+    }
+    Some(100.0 - 100.0 / (1.0 + avg_gain / avg_loss))
+}
+
+/// Ratio of the newest volume to the average of the following (older) days
+/// Expects volumes ordered newest first and a full 20-day window
+fn calculate_volume_ratio(volumes: &[i64]) -> Option<f64> {
+    let (current, previous) = volumes.split_first()?;
+    if previous.len() < VOLUME_AVERAGE_DAYS {
+        return None;
+    }
+    let average = previous.iter().sum::<i64>() as f64 / previous.len() as f64;
+    if average <= 0.0 {
+        return None;
+    }
+    Some(*current as f64 / average)
+}
+
+/// Raise the high-water mark of a trailing-stop alert to the current price
+/// Returns true if the mark changed and needs to be persisted
+fn ratchet_high_water_mark(alert: &mut PriceAlert, current_price: f64) -> bool {
+    if alert.alert_type != "trailing_stop" {
+        return false;
+    }
+    match alert.high_water_mark {
+        Some(high) if high >= current_price => false,
+        _ => {
+            alert.high_water_mark = Some(current_price);
+            true
+        }
+    }
+}
+
+fn check_alert_condition(
+    alert: &PriceAlert,
+    current_price: f64,
+    indicators: &MarketIndicators,
+) -> (bool, String) {
     match alert.alert_type.as_str() {
         "price_above" => {
             let triggered = current_price > alert.target_value;
@@ -538,13 +689,71 @@ fn check_alert_condition(alert: &PriceAlert, current_price: f64) -> (bool, Strin
             );
             (triggered, reason)
         }
-        // RSI alerts would need additional calculation - for now just return false
-        "rsi_above" | "rsi_below" => (
-            false,
-            "RSI-Alerts benötigen Indikator-Berechnung".to_string(),
-        ),
+        "price_pct_change" => {
+            // target_value holds the percentage, negative values alert on drops
+            let Some(reference) = alert.reference_price.filter(|r| *r > 0.0) else {
+                return (false, "Kein Referenzkurs vorhanden".to_string());
+            };
+            let threshold = reference * (1.0 + alert.target_value / 100.0);
+            let change = (current_price / reference - 1.0) * 100.0;
+            if alert.target_value < 0.0 {
+                let triggered = current_price <= threshold;
+                let reason = format!(
+                    "Kurs ({:.2}) ist um {:.2}% gegenüber Referenz {:.2} gefallen (Schwelle {:.2})",
+                    current_price, -change, reference, threshold
+                );
+                (triggered, reason)
+            } else {
+                let triggered = current_price >= threshold;
+                let reason = format!(
+                    "Kurs ({:.2}) ist um {:.2}% gegenüber Referenz {:.2} gestiegen (Schwelle {:.2})",
+                    current_price, change, reference, threshold
+                );
+                (triggered, reason)
+            }
+        }
+        "trailing_stop" => {
+            // target_value holds the distance below the high-water mark in percent
+            let Some(high) = alert.high_water_mark.filter(|h| *h > 0.0) else {
+                return (false, "Kein Höchstkurs vorhanden".to_string());
+            };
+            let stop = high * (1.0 - alert.target_value / 100.0);
+            let triggered = current_price <= stop;
+            let reason = format!(
+                "Kurs ({:.2}) liegt {:.2}% unter Höchstkurs {:.2} (Trailing-Stop {:.2})",
+                current_price,
+                (1.0 - current_price / high) * 100.0,
+                high,
+                stop
+            );
+            (triggered, reason)
+        }
+        "rsi_above" | "rsi_below" => {
+            let Some(rsi) = indicators.rsi else {
+                return (false, "Zu wenig Kursdaten für RSI".to_string());
+            };
+            if alert.alert_type == "rsi_above" {
+                let triggered = rsi > alert.target_value;
+                (triggered, format!("RSI ({:.1}) liegt über {:.1}", rsi, alert.target_value))
+            } else {
+                let triggered = rsi < alert.target_value;
+                (triggered, format!("RSI ({:.1}) liegt unter {:.1}", rsi, alert.target_value))
+            }
+        }
+        "volume_spike" => {
+            // target_value is the multiple of the 20-day average volume
+            let Some(ratio) = indicators.volume_ratio else {
+                return (false, "Zu wenig Volumendaten".to_string());
+            };
+            let triggered = ratio >= alert.target_value;
+            let reason = format!(
+                "Volumen beträgt das {:.1}-fache des 20-Tage-Durchschnitts (Schwelle {:.1})",
+                ratio, alert.target_value
+            );
+            (triggered, reason)
+        }
         // Pattern/divergence alerts are triggered by signal detection, not price
-        "volume_spike" | "divergence" | "pattern_detected" => (
+        "divergence" | "pattern_detected" => (
             false,
             "Dieser Alert-Typ wird durch Signal-Erkennung ausgelöst".to_string(),
         ),
@@ -610,6 +819,10 @@ pub struct AllocationAlert {
     pub security_id: Option<i64>,
     /// Associated classification ID if applicable
     pub classification_id: Option<i64>,
+    /// Allocation target that is breached
+    pub target_id: i64,
+    /// Threshold of the target as decimal
+    pub threshold: f64,
 }
 
 /// Alert count summary for badge display
@@ -629,6 +842,14 @@ pub fn get_allocation_targets(portfolio_id: i64) -> Result<Vec<AllocationTarget>
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    load_allocation_targets(conn, Some(portfolio_id))
+}
+
+/// Load allocation targets of one portfolio or of all portfolios
+fn load_allocation_targets(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+) -> Result<Vec<AllocationTarget>, String> {
     let mut stmt = conn
         .prepare(
             r#"
@@ -649,8 +870,8 @@ pub fn get_allocation_targets(portfolio_id: i64) -> Result<Vec<AllocationTarget>
             LEFT JOIN pp_security s ON s.id = t.security_id
             LEFT JOIN pp_taxonomy tax ON tax.id = t.taxonomy_id
             LEFT JOIN pp_classification c ON c.id = t.classification_id
-            WHERE t.portfolio_id = ?
-            ORDER BY t.target_weight DESC
+            WHERE ?1 IS NULL OR t.portfolio_id = ?1
+            ORDER BY t.portfolio_id, t.target_weight DESC
             "#,
         )
         .map_err(|e| e.to_string())?;
@@ -780,60 +1001,18 @@ pub fn get_allocation_alerts(portfolio_id: Option<i64>) -> Result<Vec<Allocation
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    compute_allocation_alerts(conn, portfolio_id)
+}
+
+/// Compare current weights against the allocation targets
+fn compute_allocation_alerts(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+) -> Result<Vec<AllocationAlert>, String> {
     let mut alerts = Vec::new();
 
     // Get all allocation targets (optionally filtered by portfolio)
-    let targets: Vec<AllocationTarget> = if let Some(pid) = portfolio_id {
-        get_allocation_targets(pid)?
-    } else {
-        // Get all targets for all portfolios
-        let mut stmt = conn
-            .prepare(
-                r#"
-                SELECT
-                    t.id,
-                    t.portfolio_id,
-                    t.security_id,
-                    s.name as security_name,
-                    t.taxonomy_id,
-                    tax.name as taxonomy_name,
-                    t.classification_id,
-                    c.name as classification_name,
-                    t.target_weight,
-                    t.threshold,
-                    t.created_at,
-                    t.updated_at
-                FROM pp_allocation_target t
-                LEFT JOIN pp_security s ON s.id = t.security_id
-                LEFT JOIN pp_taxonomy tax ON tax.id = t.taxonomy_id
-                LEFT JOIN pp_classification c ON c.id = t.classification_id
-                ORDER BY t.portfolio_id, t.target_weight DESC
-                "#,
-            )
-            .map_err(|e| e.to_string())?;
-
-        let rows = stmt
-            .query_map([], |row| {
-                Ok(AllocationTarget {
-                    id: row.get(0)?,
-                    portfolio_id: row.get(1)?,
-                    security_id: row.get(2)?,
-                    security_name: row.get(3)?,
-                    taxonomy_id: row.get(4)?,
-                    taxonomy_name: row.get(5)?,
-                    classification_id: row.get(6)?,
-                    classification_name: row.get(7)?,
-                    target_weight: row.get(8)?,
-                    threshold: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-
-        rows.collect::<Result<Vec<_>, _>>()
-            .map_err(|e| e.to_string())?
-    };
+    let targets = load_allocation_targets(conn, portfolio_id)?;
 
     // Get total portfolio value
     let total_value: f64 = conn
@@ -953,6 +1132,8 @@ pub fn get_allocation_alerts(portfolio_id: Option<i64>) -> Result<Vec<Allocation
                 severity: severity.to_string(),
                 security_id: target.security_id,
                 classification_id: target.classification_id,
+                target_id: target.id,
+                threshold: target.threshold,
             });
         }
     }
@@ -991,3 +1172,273 @@ pub fn get_allocation_alert_count(portfolio_id: Option<i64>) -> Result<Allocatio
         warning,
     })
 }
+
+/// Evaluate all allocation targets and notify about new or worsened breaches
+///
+/// Emits an `allocation-alert` event per breach. A breach is only reported again
+/// after it cleared or worsened (see `record_drift_breaches`). Returns the reported alerts.
+#[command]
+pub fn check_allocation_drift(app: AppHandle) -> Result<Vec<AllocationAlert>, String> {
+    let notify = {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        let alerts = compute_allocation_alerts(conn, None)?;
+        record_drift_breaches(conn, &alerts)?
+    };
+
+    for alert in &notify {
+        events::emit_allocation_alert(&app, alert);
+    }
+
+    Ok(notify)
+}
+
+/// Compare breaches with the stored snapshot and return those to report
+///
+/// A breach is reported if it is new, changed direction, escalated to critical or
+/// grew by at least the target's threshold since it was last reported.
+/// Snapshots of targets that are no longer breached are removed.
+fn record_drift_breaches(
+    conn: &Connection,
+    alerts: &[AllocationAlert],
+) -> Result<Vec<AllocationAlert>, String> {
+    let mut stmt = conn
+        .prepare("SELECT target_id, deviation, severity FROM pp_allocation_alert_state")
+        .map_err(|e| e.to_string())?;
+    let mut snapshots: HashMap<i64, (f64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut notify = Vec::new();
+
+    for alert in alerts {
+        let worsened = match snapshots.remove(&alert.target_id) {
+            None => true,
+            Some((deviation, severity)) => {
+                deviation.signum() != alert.deviation.signum()
+                    || (severity != "critical" && alert.severity == "critical")
+                    || alert.deviation.abs() - deviation.abs() >= alert.threshold
+            }
+        };
+
+        if worsened {
+            conn.execute(
+                r#"
+                INSERT OR REPLACE INTO pp_allocation_alert_state (target_id, deviation, severity, alerted_at)
+                VALUES (?, ?, ?, ?)
+                "#,
+                rusqlite::params![alert.target_id, alert.deviation, alert.severity, now],
+            )
+            .map_err(|e| e.to_string())?;
+            notify.push(alert.clone());
+        }
+    }
+
+    // Remaining snapshots belong to breaches that cleared
+    for target_id in snapshots.keys() {
+        conn.execute(
+            "DELETE FROM pp_allocation_alert_state WHERE target_id = ?",
+            [target_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(notify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_pct_change_condition() {
+        let mut alert = PriceAlert {
+            id: 1,
+            uuid: "a1".to_string(),
+            security_id: 1,
+            security_name: None,
+            security_ticker: None,
+            alert_type: "price_pct_change".to_string(),
+            target_value: -10.0,
+            target_value_2: None,
+            is_active: true,
+            is_triggered: false,
+            trigger_count: 0,
+            last_triggered_at: None,
+            last_triggered_price: None,
+            note: None,
+            created_at: "2024-01-01".to_string(),
+            reference_price: Some(200.0),
+            high_water_mark: None,
+        };
+        let none = MarketIndicators::default();
+
+        // Drop of 10% from 200 triggers at 180
+        assert!(!check_alert_condition(&alert, 181.0, &none).0);
+        let (triggered, reason) = check_alert_condition(&alert, 180.0, &none);
+        assert!(triggered);
+        assert!(reason.contains("200.00"));
+
+        // Rise of 5% from 200 triggers at 210
+        alert.target_value = 5.0;
+        assert!(!check_alert_condition(&alert, 209.0, &none).0);
+        assert!(check_alert_condition(&alert, 210.0, &none).0);
+
+        alert.reference_price = None;
+        assert!(!check_alert_condition(&alert, 500.0, &none).0);
+    }
+
+    #[test]
+    fn test_rsi_and_volume_indicators() {
+        // Only gains: RSI saturates at 100
+        let rising: Vec<f64> = (0..20).map(|i| 100.0 + i as f64).collect();
+        assert_eq!(calculate_rsi(&rising, 14), Some(100.0));
+        assert_eq!(calculate_rsi(&rising[..14], 14), None);
+
+        // Equal alternating gains and losses: RSI near 50
+        let flat: Vec<f64> = (0..30).map(|i| if i % 2 == 0 { 100.0 } else { 101.0 }).collect();
+        let rsi = calculate_rsi(&flat, 14).unwrap();
+        assert!((rsi - 50.0).abs() < 5.0);
+
+        // Newest volume 3x the 20-day average
+        let mut volumes = vec![3000];
+        volumes.extend(std::iter::repeat(1000).take(20));
+        assert_eq!(calculate_volume_ratio(&volumes), Some(3.0));
+        assert_eq!(calculate_volume_ratio(&volumes[..10]), None);
+
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR')",
+            [],
+        )
+        .unwrap();
+        for (i, volume) in volumes.iter().rev().enumerate() {
+            conn.execute(
+                "INSERT INTO pp_price (security_id, date, value, volume) VALUES (1, ?1, ?2, ?3)",
+                rusqlite::params![
+                    format!("2024-01-{:02}", i + 1),
+                    (100 + i as i64) * 100_000_000,
+                    volume
+                ],
+            )
+            .unwrap();
+        }
+
+        let mut alert = PriceAlert {
+            id: 1,
+            uuid: "a1".to_string(),
+            security_id: 1,
+            security_name: None,
+            security_ticker: None,
+            alert_type: "volume_spike".to_string(),
+            target_value: 2.0,
+            target_value_2: None,
+            is_active: true,
+            is_triggered: false,
+            trigger_count: 0,
+            last_triggered_at: None,
+            last_triggered_price: None,
+            note: None,
+            created_at: "2024-01-01".to_string(),
+            reference_price: None,
+            high_water_mark: None,
+        };
+        let indicators = load_market_indicators(&conn, &alert).unwrap();
+        assert!(check_alert_condition(&alert, 120.0, &indicators).0);
+
+        alert.alert_type = "rsi_above".to_string();
+        alert.target_value = 70.0;
+        let indicators = load_market_indicators(&conn, &alert).unwrap();
+        assert_eq!(indicators.rsi, Some(100.0));
+        assert!(check_alert_condition(&alert, 120.0, &indicators).0);
+    }
+
+    #[test]
+    fn test_trailing_stop_ratchets() {
+        let mut alert = PriceAlert {
+            id: 1,
+            uuid: "a1".to_string(),
+            security_id: 1,
+            security_name: None,
+            security_ticker: None,
+            alert_type: "trailing_stop".to_string(),
+            target_value: 10.0,
+            target_value_2: None,
+            is_active: true,
+            is_triggered: false,
+            trigger_count: 0,
+            last_triggered_at: None,
+            last_triggered_price: None,
+            note: None,
+            created_at: "2024-01-01".to_string(),
+            reference_price: Some(100.0),
+            high_water_mark: Some(100.0),
+        };
+
+        // Rising prices move the mark up, falling prices leave it
+        assert!(ratchet_high_water_mark(&mut alert, 120.0));
+        assert!(!ratchet_high_water_mark(&mut alert, 110.0));
+        assert_eq!(alert.high_water_mark, Some(120.0));
+        let none = MarketIndicators::default();
+
+        // Stop is 10% below the high of 120
+        assert!(!check_alert_condition(&alert, 109.0, &none).0);
+        assert!(check_alert_condition(&alert, 108.0, &none).0);
+    }
+
+    #[test]
+    fn test_record_drift_breaches() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR'), (2, 's2', 'Anleihe', 'EUR');
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-01-02', 10000000000), (2, '2024-01-02', 10000000000);
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 0, 'EUR', 1, 7000000000),
+                    ('t2', 'portfolio', 1, 'BUY', '2024-01-01', 0, 'EUR', 2, 3000000000);
+             INSERT INTO pp_allocation_target (id, portfolio_id, security_id, target_weight, threshold)
+             VALUES (1, 1, 1, 0.6, 0.05), (2, 1, 2, 0.4, 0.05);",
+        )
+        .unwrap();
+
+        let check = |conn: &Connection| {
+            let alerts = compute_allocation_alerts(conn, None).unwrap();
+            record_drift_breaches(conn, &alerts).unwrap()
+        };
+
+        // 70/30 vs 60/40: both targets breached, reported once
+        let first = check(&conn);
+        assert_eq!(first.len(), 2);
+        assert!(check(&conn).is_empty());
+
+        // Worsens to 80/20: reported again
+        conn.execute(
+            "UPDATE pp_latest_price SET value = 17000000000 WHERE security_id = 1",
+            [],
+        )
+        .unwrap();
+        let worse = check(&conn);
+        assert_eq!(worse.len(), 2);
+        assert!(worse.iter().all(|a| a.severity == "critical"));
+
+        // Back within the band: snapshot cleared, next breach fires again
+        conn.execute(
+            "UPDATE pp_latest_price SET value = 6500000000 WHERE security_id = 1",
+            [],
+        )
+        .unwrap();
+        assert!(check(&conn).is_empty());
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_allocation_alert_state", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -346,11 +346,64 @@ pub fn set_security_attribute(request: SetAttributeValueRequest) -> Result<(), S
         )
         .map_err(|e| format!("Attribute type not found: {}", e))?;
 
+    set_attribute_value(conn, request.security_id, &attr_uuid, &request.value)
+}
+
+/// Set the same attribute value on many securities in one transaction
+///
+/// Returns the number of updated securities. If one security does not exist,
+/// nothing is changed.
+#[command]
+pub fn set_security_attribute_bulk(
+    attribute_type_id: i64,
+    value: String,
+    security_ids: Vec<i64>,
+) -> Result<usize, String> {
+    let guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+
+    set_attribute_value_bulk(conn, attribute_type_id, &value, &security_ids)
+}
+
+fn set_attribute_value_bulk(
+    conn: &rusqlite::Connection,
+    attribute_type_id: i64,
+    value: &str,
+    security_ids: &[i64],
+) -> Result<usize, String> {
+    let attr_uuid: String = conn
+        .query_row(
+            "SELECT uuid FROM pp_attribute_type WHERE id = ?",
+            [attribute_type_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Attribute type not found: {}", e))?;
+
+    let mut ids = security_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for &security_id in &ids {
+        set_attribute_value(&tx, security_id, &attr_uuid, value)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(ids.len())
+}
+
+/// Upsert one attribute value in the attributes JSON of a security
+fn set_attribute_value(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    attr_uuid: &str,
+    value: &str,
+) -> Result<(), String> {
     // Get current attributes
     let attrs_json: Option<String> = conn
         .query_row(
             "SELECT attributes FROM pp_security WHERE id = ?",
-            [request.security_id],
+            [security_id],
             |row| row.get(0),
         )
         .map_err(|e| format!("Security not found: {}", e))?;
@@ -360,13 +413,13 @@ pub fn set_security_attribute(request: SetAttributeValueRequest) -> Result<(), S
         .unwrap_or_default();
 
     // Set the value
-    attrs.insert(attr_uuid, request.value);
+    attrs.insert(attr_uuid.to_string(), value.to_string());
 
     // Save back
     let new_json = serde_json::to_string(&attrs).map_err(|e| e.to_string())?;
     conn.execute(
         "UPDATE pp_security SET attributes = ? WHERE id = ?",
-        rusqlite::params![new_json, request.security_id],
+        rusqlite::params![new_json, security_id],
     )
     .map_err(|e| e.to_string())?;
 
@@ -416,8 +469,15 @@ pub fn remove_security_attribute(security_id: i64, attribute_type_id: i64) -> Re
 }
 
 /// Get all securities with their attribute values for a specific attribute type
+///
+/// With `value` only securities whose value matches are returned
+/// (`match_mode` "contains" by default, or "exact"; both case-insensitive).
 #[command]
-pub fn get_securities_by_attribute(attribute_type_id: i64) -> Result<Vec<(i64, String, Option<String>)>, String> {
+pub fn get_securities_by_attribute(
+    attribute_type_id: i64,
+    value: Option<String>,
+    match_mode: Option<AttributeMatchMode>,
+) -> Result<Vec<(i64, String, Option<String>)>, String> {
     let guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = guard.as_ref().ok_or("Database not initialized")?;
 
@@ -430,29 +490,232 @@ pub fn get_securities_by_attribute(attribute_type_id: i64) -> Result<Vec<(i64, S
         )
         .map_err(|e| format!("Attribute type not found: {}", e))?;
 
-    // Get all securities with this attribute
+    let mode = match_mode.unwrap_or_default();
+    let result = load_security_attributes(conn)?
+        .into_iter()
+        .map(|(id, name, _, attrs)| (id, name, attrs.get(&attr_uuid).cloned()))
+        .filter(|(_, _, attr_value)| match &value {
+            Some(needle) => attr_value
+                .as_deref()
+                .is_some_and(|v| mode.matches(v, needle)),
+            None => true,
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// How an attribute filter compares values (case-insensitive)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeMatchMode {
+    Exact,
+    #[default]
+    Contains,
+}
+
+impl AttributeMatchMode {
+    fn matches(self, value: &str, needle: &str) -> bool {
+        let value = value.trim().to_lowercase();
+        let needle = needle.trim().to_lowercase();
+        match self {
+            Self::Exact => value == needle,
+            Self::Contains => value.contains(&needle),
+        }
+    }
+}
+
+/// One condition of `search_securities_by_attributes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeFilter {
+    pub attribute_type_id: i64,
+    pub value: String,
+    pub match_mode: Option<AttributeMatchMode>,
+}
+
+/// A security matching all filters, with all its attribute values
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityAttributeMatch {
+    pub security_id: i64,
+    pub security_name: String,
+    pub isin: Option<String>,
+    pub attributes: Vec<AttributeValue>,
+}
+
+/// Find securities matching all attribute filters (AND)
+///
+/// Returns every attribute value of the matches for a faceted browse.
+/// Without filters all active securities are returned.
+#[command]
+pub fn search_securities_by_attributes(filters: Vec<AttributeFilter>) -> Result<Vec<SecurityAttributeMatch>, String> {
+    let guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+
+    filter_securities_by_attributes(conn, &filters)
+}
+
+fn filter_securities_by_attributes(
+    conn: &rusqlite::Connection,
+    filters: &[AttributeFilter],
+) -> Result<Vec<SecurityAttributeMatch>, String> {
+    // Attribute types for securities: (id, uuid, name, data_type)
     let mut stmt = conn
-        .prepare("SELECT id, name, attributes FROM pp_security WHERE is_retired = 0 ORDER BY name")
+        .prepare("SELECT id, uuid, name, data_type FROM pp_attribute_type WHERE target = 'security' ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let types: Vec<(i64, String, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let conditions = filters
+        .iter()
+        .map(|filter| {
+            types
+                .iter()
+                .find(|(id, ..)| *id == filter.attribute_type_id)
+                .map(|(_, uuid, ..)| (uuid, filter))
+                .ok_or_else(|| format!("Attribute type not found: {}", filter.attribute_type_id))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let result = load_security_attributes(conn)?
+        .into_iter()
+        .filter(|(_, _, _, attrs)| {
+            conditions.iter().all(|(uuid, filter)| {
+                attrs.get(*uuid).is_some_and(|v| {
+                    filter.match_mode.unwrap_or_default().matches(v, &filter.value)
+                })
+            })
+        })
+        .map(|(security_id, security_name, isin, attrs)| SecurityAttributeMatch {
+            security_id,
+            security_name,
+            isin,
+            attributes: types
+                .iter()
+                .filter_map(|(id, uuid, name, data_type)| {
+                    attrs.get(uuid).map(|value| AttributeValue {
+                        attribute_type_id: *id,
+                        attribute_type_name: name.clone(),
+                        attribute_type_uuid: uuid.clone(),
+                        data_type: data_type.clone(),
+                        value: Some(value.clone()),
+                    })
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// Security with its parsed attributes: (id, name, isin, uuid -> value)
+type SecurityAttributes = (i64, String, Option<String>, std::collections::HashMap<String, String>);
+
+/// Active securities with their parsed attributes
+fn load_security_attributes(conn: &rusqlite::Connection) -> Result<Vec<SecurityAttributes>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name, isin, attributes FROM pp_security WHERE is_retired = 0 ORDER BY name")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
         .query_map([], |row| {
             let id: i64 = row.get(0)?;
             let name: String = row.get(1)?;
-            let attrs_json: Option<String> = row.get(2)?;
-            Ok((id, name, attrs_json))
+            let isin: Option<String> = row.get(2)?;
+            let attrs_json: Option<String> = row.get(3)?;
+            Ok((id, name, isin, attrs_json))
         })
         .map_err(|e| e.to_string())?;
 
-    let result: Vec<(i64, String, Option<String>)> = rows
+    Ok(rows
         .filter_map(|r| r.ok())
-        .map(|(id, name, attrs_json)| {
-            let value = attrs_json
-                .and_then(|json| serde_json::from_str::<std::collections::HashMap<String, String>>(&json).ok())
-                .and_then(|attrs| attrs.get(&attr_uuid).cloned());
-            (id, name, value)
+        .map(|(id, name, isin, attrs_json)| {
+            let attrs = attrs_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            (id, name, isin, attrs)
         })
-        .collect();
+        .collect())
+}
 
-    Ok(result)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_securities_by_attributes() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO pp_attribute_type (id, uuid, name, target) VALUES (1, 'sector', 'Sektor', 'security'), (2, 'risk', 'Risiko', 'security');
+            INSERT INTO pp_security (id, uuid, name, currency, attributes) VALUES
+                (1, 's1', 'Apple', 'USD', '{"sector":"Technology","risk":"High"}'),
+                (2, 's2', 'SAP', 'EUR', '{"sector":"Tech Software","risk":"Medium"}'),
+                (3, 's3', 'Allianz', 'EUR', '{"sector":"Financials","risk":"High"}'),
+                (4, 's4', 'Anleihe', 'EUR', NULL);
+            "#,
+        )
+        .unwrap();
+        let filter = |id: i64, value: &str, match_mode: Option<AttributeMatchMode>| AttributeFilter {
+            attribute_type_id: id,
+            value: value.to_string(),
+            match_mode,
+        };
+        let ids = |filters: &[AttributeFilter]| -> Vec<i64> {
+            filter_securities_by_attributes(&conn, filters)
+                .unwrap()
+                .iter()
+                .map(|m| m.security_id)
+                .collect()
+        };
+
+        assert_eq!(ids(&[filter(1, "tech", None)]), [1, 2]);
+        assert_eq!(ids(&[filter(1, "tech", Some(AttributeMatchMode::Exact))]), Vec::<i64>::new());
+        assert_eq!(ids(&[filter(1, "tech", None), filter(2, "high", None)]), [1]);
+        assert_eq!(ids(&[]).len(), 4);
+        assert!(filter_securities_by_attributes(&conn, &[filter(9, "x", None)]).is_err());
+
+        let matches = filter_securities_by_attributes(&conn, &[filter(2, "HIGH", Some(AttributeMatchMode::Exact))]).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].security_name, "Allianz");
+        let names: Vec<&str> = matches[0].attributes.iter().map(|a| a.attribute_type_name.as_str()).collect();
+        assert_eq!(names, ["Risiko", "Sektor"]);
+    }
+
+    #[test]
+    fn test_set_attribute_value_bulk() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO pp_attribute_type (id, uuid, name, target) VALUES (1, 'sector', 'Sektor', 'security'), (2, 'risk', 'Risiko', 'security');
+            INSERT INTO pp_security (id, uuid, name, currency, attributes) VALUES
+                (1, 's1', 'Apple', 'USD', '{"risk":"High"}'),
+                (2, 's2', 'SAP', 'EUR', NULL),
+                (3, 's3', 'Allianz', 'EUR', NULL);
+            "#,
+        )
+        .unwrap();
+        let attrs = |id: i64| -> std::collections::HashMap<String, String> {
+            let json: Option<String> = conn
+                .query_row("SELECT attributes FROM pp_security WHERE id = ?", [id], |row| row.get(0))
+                .unwrap();
+            json.map(|j| serde_json::from_str(&j).unwrap()).unwrap_or_default()
+        };
+
+        assert_eq!(set_attribute_value_bulk(&conn, 1, "Tech", &[1, 2, 2]).unwrap(), 2);
+        assert_eq!(attrs(1)["sector"], "Tech");
+        assert_eq!(attrs(1)["risk"], "High");
+        assert_eq!(attrs(2)["sector"], "Tech");
+
+        // Unknown security rolls back the whole batch
+        assert!(set_attribute_value_bulk(&conn, 1, "Finance", &[3, 99]).is_err());
+        assert!(attrs(3).is_empty());
+        assert!(set_attribute_value_bulk(&conn, 9, "x", &[1]).is_err());
+    }
 }
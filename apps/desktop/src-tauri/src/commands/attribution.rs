@@ -0,0 +1,520 @@
+//! Performance attribution commands for Tauri
+//!
+//! Splits the active return of a portfolio against a benchmark into allocation,
+//! selection and interaction effects per classification of a taxonomy
+//! (single-period Brinson-Hood-Beebower):
+//!
+//! ```text
+//! Allocation  = (w_p - w_b) × r_b
+//! Selection   = w_b × (r_p - r_b)
+//! Interaction = (w_p - w_b) × (r_p - r_b)
+//! ```
+//!
+//! Portfolio weights and returns come from the current positions and their
+//! TTWROR (`performance::calculate_position_performance`). Benchmark weights
+//! are entered per classification or derived from the taxonomy assignments of
+//! the benchmark security.
+
+use crate::currency;
+use crate::db;
+use crate::performance;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::command;
+
+/// Bucket id for the part without classification
+const UNCLASSIFIED_ID: i64 = 0;
+
+/// Name of the bucket for the part without classification
+const UNCLASSIFIED_NAME: &str = "Nicht klassifiziert";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Benchmark weight of a classification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributionWeight {
+    pub classification_id: i64,
+    pub classification_name: Option<String>,
+    /// Weight as decimal (0.0 - 1.0)
+    pub weight: f64,
+    /// Benchmark return of the classification in percent (None = total benchmark return)
+    pub benchmark_return: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAttributionWeightsRequest {
+    pub benchmark_id: i64,
+    pub taxonomy_id: i64,
+    pub weights: Vec<AttributionWeight>,
+}
+
+/// Attribution of one classification (returns and effects in percent)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributionBucket {
+    pub classification_id: i64,
+    pub classification_name: String,
+    pub portfolio_weight: f64,
+    pub benchmark_weight: f64,
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    pub allocation_effect: f64,
+    pub selection_effect: f64,
+    pub interaction_effect: f64,
+    pub total_effect: f64,
+}
+
+/// Attribution result (returns and effects in percent)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributionResult {
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    pub active_return: f64,
+    pub allocation_effect: f64,
+    pub selection_effect: f64,
+    pub interaction_effect: f64,
+    /// "manual" (entered weights) or "assignments" (taxonomy of the benchmark security)
+    pub weight_source: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub buckets: Vec<AttributionBucket>,
+}
+
+/// Weights and returns of one bucket as decimals
+#[derive(Debug, Clone, Default)]
+struct BucketInput {
+    name: String,
+    portfolio_weight: f64,
+    portfolio_return: f64,
+    benchmark_weight: f64,
+    benchmark_return: f64,
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the entered benchmark weights for a taxonomy
+#[command]
+pub fn get_attribution_weights(
+    benchmark_id: i64,
+    taxonomy_id: i64,
+) -> Result<Vec<AttributionWeight>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    load_attribution_weights(conn, benchmark_id, taxonomy_id).map_err(|e| e.to_string())
+}
+
+/// Replace the benchmark weights for a taxonomy
+///
+/// Weights are decimals and must not add up to more than 100%.
+#[command]
+pub fn set_attribution_weights(request: SetAttributionWeightsRequest) -> Result<(), String> {
+    let mut conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_mut()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    if request
+        .weights
+        .iter()
+        .any(|w| !(0.0..=1.0).contains(&w.weight))
+    {
+        return Err("Gewichtungen müssen zwischen 0 und 100% liegen".to_string());
+    }
+    let total: f64 = request.weights.iter().map(|w| w.weight).sum();
+    if total > 1.0 + 1e-9 {
+        return Err(format!(
+            "Summe der Gewichtungen ({:.2}%) überschreitet 100%",
+            total * 100.0
+        ));
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"
+        DELETE FROM pp_attribution_weight
+        WHERE benchmark_id = ?1
+          AND classification_id IN (SELECT id FROM pp_classification WHERE taxonomy_id = ?2)
+        "#,
+        params![request.benchmark_id, request.taxonomy_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    for w in request.weights.iter().filter(|w| w.weight > 0.0) {
+        let in_taxonomy: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM pp_classification WHERE id = ?1 AND taxonomy_id = ?2)",
+                params![w.classification_id, request.taxonomy_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !in_taxonomy {
+            return Err(format!(
+                "Klassifizierung {} gehört nicht zur Taxonomie",
+                w.classification_id
+            ));
+        }
+
+        tx.execute(
+            "INSERT INTO pp_attribution_weight (benchmark_id, classification_id, weight, benchmark_return)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![request.benchmark_id, w.classification_id, w.weight, w.benchmark_return],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Brinson-Hood-Beebower attribution of a portfolio against a benchmark by taxonomy
+#[command]
+pub fn calculate_attribution(
+    portfolio_id: Option<i64>,
+    benchmark_id: i64,
+    taxonomy_id: i64,
+) -> Result<AttributionResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let today = chrono::Utc::now().date_naive();
+    compute_attribution(conn, portfolio_id, benchmark_id, taxonomy_id, today)
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn load_attribution_weights(
+    conn: &Connection,
+    benchmark_id: i64,
+    taxonomy_id: i64,
+) -> rusqlite::Result<Vec<AttributionWeight>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT w.classification_id, c.name, w.weight, w.benchmark_return
+        FROM pp_attribution_weight w
+        JOIN pp_classification c ON c.id = w.classification_id
+        WHERE w.benchmark_id = ?1 AND c.taxonomy_id = ?2
+        ORDER BY w.weight DESC
+        "#,
+    )?;
+    let rows = stmt.query_map(params![benchmark_id, taxonomy_id], |row| {
+        Ok(AttributionWeight {
+            classification_id: row.get(0)?,
+            classification_name: row.get(1)?,
+            weight: row.get(2)?,
+            benchmark_return: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Classification weights (decimal) of a security within a taxonomy
+fn security_classifications(
+    conn: &Connection,
+    security_id: i64,
+    taxonomy_id: i64,
+) -> rusqlite::Result<Vec<(i64, String, f64)>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT c.id, c.name, a.weight
+        FROM pp_classification_assignment a
+        JOIN pp_classification c ON c.id = a.classification_id
+        JOIN pp_security s ON s.uuid = a.vehicle_uuid
+        WHERE a.vehicle_type = 'security' AND s.id = ?1 AND c.taxonomy_id = ?2
+        "#,
+    )?;
+    let rows = stmt.query_map(params![security_id, taxonomy_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)? as f64 / 10000.0,
+        ))
+    })?;
+    rows.collect()
+}
+
+/// Price return of the benchmark security between two dates (decimal)
+fn benchmark_price_return(
+    conn: &Connection,
+    security_id: i64,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+) -> rusqlite::Result<Option<f64>> {
+    // Price at or before the start, otherwise the first one after it
+    let start_price: Option<i64> = conn
+        .query_row(
+            r#"
+            SELECT value FROM pp_price
+            WHERE security_id = ?1
+            ORDER BY CASE WHEN date(date) <= ?2 THEN 0 ELSE 1 END,
+                     CASE WHEN date(date) <= ?2 THEN date END DESC,
+                     date ASC
+            LIMIT 1
+            "#,
+            params![security_id, start.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let end_price: Option<i64> = conn
+        .query_row(
+            "SELECT value FROM pp_price WHERE security_id = ?1 AND date(date) <= ?2 ORDER BY date DESC LIMIT 1",
+            params![security_id, end.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(match (start_price, end_price) {
+        (Some(s), Some(e)) if s > 0 => Some(e as f64 / s as f64 - 1.0),
+        _ => None,
+    })
+}
+
+fn compute_attribution(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    benchmark_id: i64,
+    taxonomy_id: i64,
+    end_date: chrono::NaiveDate,
+) -> Result<AttributionResult, String> {
+    let benchmark_security_id: i64 = conn
+        .query_row(
+            "SELECT security_id FROM pp_benchmark WHERE id = ?1",
+            [benchmark_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Benchmark {} nicht gefunden", benchmark_id))?;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let holdings = performance::get_holdings_at_date(conn, portfolio_id, end_date, &base_currency)
+        .map_err(|e| e.to_string())?;
+
+    // Portfolio side: value-weighted position returns per classification
+    let mut buckets: BTreeMap<i64, BucketInput> = BTreeMap::new();
+    let mut total_value = 0.0;
+    let mut start_date = end_date;
+    for holding in &holdings {
+        let position = performance::calculate_position_performance(
+            conn,
+            holding.security_id,
+            portfolio_id,
+            end_date,
+        )
+        .map_err(|e| e.to_string())?;
+        let value = position.current_value;
+        if value <= 0.0 {
+            continue;
+        }
+        total_value += value;
+        if let Some(d) = crate::pp::parse_date_flexible(&position.start_date) {
+            start_date = start_date.min(d);
+        }
+
+        let classifications = security_classifications(conn, holding.security_id, taxonomy_id)
+            .map_err(|e| e.to_string())?;
+        let remainder = (1.0 - classifications.iter().map(|(_, _, w)| w).sum::<f64>()).max(0.0);
+        let unclassified = (UNCLASSIFIED_ID, UNCLASSIFIED_NAME.to_string(), remainder);
+        for (id, name, weight) in classifications
+            .into_iter()
+            .chain(std::iter::once(unclassified))
+        {
+            if weight <= 0.0 {
+                continue;
+            }
+            let bucket = buckets.entry(id).or_insert_with(|| BucketInput {
+                name,
+                ..Default::default()
+            });
+            // Accumulate value and value × return, normalized below
+            bucket.portfolio_weight += value * weight;
+            bucket.portfolio_return += value * weight * position.ttwror / 100.0;
+        }
+    }
+
+    if total_value <= 0.0 {
+        return Err("Keine Positionen mit Wert im Depot".to_string());
+    }
+    for bucket in buckets.values_mut() {
+        if bucket.portfolio_weight > 0.0 {
+            bucket.portfolio_return /= bucket.portfolio_weight;
+        }
+        bucket.portfolio_weight /= total_value;
+    }
+
+    // Benchmark side: entered weights, otherwise the benchmark's own assignments
+    let total_benchmark_return =
+        benchmark_price_return(conn, benchmark_security_id, start_date, end_date)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Keine Kursdaten für den Benchmark im Zeitraum".to_string())?;
+
+    let manual =
+        load_attribution_weights(conn, benchmark_id, taxonomy_id).map_err(|e| e.to_string())?;
+    let weight_source = if manual.is_empty() {
+        "assignments"
+    } else {
+        "manual"
+    };
+    let benchmark_weights: Vec<(i64, String, f64, Option<f64>)> = if manual.is_empty() {
+        security_classifications(conn, benchmark_security_id, taxonomy_id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(id, name, weight)| (id, name, weight, None))
+            .collect()
+    } else {
+        manual
+            .into_iter()
+            .map(|w| {
+                (
+                    w.classification_id,
+                    w.classification_name.unwrap_or_default(),
+                    w.weight,
+                    w.benchmark_return.map(|r| r / 100.0),
+                )
+            })
+            .collect()
+    };
+
+    let assigned: f64 = benchmark_weights.iter().map(|(_, _, w, _)| w).sum();
+    let remainder = (
+        UNCLASSIFIED_ID,
+        UNCLASSIFIED_NAME.to_string(),
+        (1.0 - assigned).max(0.0),
+        None,
+    );
+    for (id, name, weight, bucket_return) in benchmark_weights
+        .into_iter()
+        .chain(std::iter::once(remainder))
+    {
+        if weight <= 0.0 {
+            continue;
+        }
+        let bucket = buckets.entry(id).or_insert_with(|| BucketInput {
+            name,
+            ..Default::default()
+        });
+        bucket.benchmark_weight = weight;
+        bucket.benchmark_return = bucket_return.unwrap_or(total_benchmark_return);
+    }
+    // Buckets only held in the portfolio are measured against the total benchmark return
+    for bucket in buckets.values_mut().filter(|b| b.benchmark_weight == 0.0) {
+        bucket.benchmark_return = total_benchmark_return;
+    }
+
+    let mut result = brinson_attribution(buckets);
+    result.weight_source = weight_source.to_string();
+    result.start_date = start_date.to_string();
+    result.end_date = end_date.to_string();
+    Ok(result)
+}
+
+/// Single-period Brinson-Hood-Beebower decomposition
+///
+/// The sum of all effects equals the active return Σ w_p·r_p − Σ w_b·r_b.
+fn brinson_attribution(buckets: BTreeMap<i64, BucketInput>) -> AttributionResult {
+    let mut result = AttributionResult {
+        portfolio_return: 0.0,
+        benchmark_return: 0.0,
+        active_return: 0.0,
+        allocation_effect: 0.0,
+        selection_effect: 0.0,
+        interaction_effect: 0.0,
+        weight_source: String::new(),
+        start_date: String::new(),
+        end_date: String::new(),
+        buckets: Vec::new(),
+    };
+
+    for (id, b) in buckets {
+        let active_weight = b.portfolio_weight - b.benchmark_weight;
+        let allocation = active_weight * b.benchmark_return;
+        let selection = b.benchmark_weight * (b.portfolio_return - b.benchmark_return);
+        let interaction = active_weight * (b.portfolio_return - b.benchmark_return);
+
+        result.portfolio_return += b.portfolio_weight * b.portfolio_return * 100.0;
+        result.benchmark_return += b.benchmark_weight * b.benchmark_return * 100.0;
+        result.allocation_effect += allocation * 100.0;
+        result.selection_effect += selection * 100.0;
+        result.interaction_effect += interaction * 100.0;
+
+        result.buckets.push(AttributionBucket {
+            classification_id: id,
+            classification_name: b.name,
+            portfolio_weight: b.portfolio_weight,
+            benchmark_weight: b.benchmark_weight,
+            portfolio_return: b.portfolio_return * 100.0,
+            benchmark_return: b.benchmark_return * 100.0,
+            allocation_effect: allocation * 100.0,
+            selection_effect: selection * 100.0,
+            interaction_effect: interaction * 100.0,
+            total_effect: (allocation + selection + interaction) * 100.0,
+        });
+    }
+
+    result.active_return = result.portfolio_return - result.benchmark_return;
+    result.buckets.sort_by(|a, b| {
+        b.total_effect
+            .abs()
+            .partial_cmp(&a.total_effect.abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brinson_attribution() {
+        let mut buckets = BTreeMap::new();
+        // Overweight equities that beat their benchmark, underweight bonds
+        buckets.insert(
+            1,
+            BucketInput {
+                name: "Aktien".to_string(),
+                portfolio_weight: 0.8,
+                portfolio_return: 0.12,
+                benchmark_weight: 0.6,
+                benchmark_return: 0.10,
+            },
+        );
+        buckets.insert(
+            2,
+            BucketInput {
+                name: "Anleihen".to_string(),
+                portfolio_weight: 0.2,
+                portfolio_return: 0.02,
+                benchmark_weight: 0.4,
+                benchmark_return: 0.03,
+            },
+        );
+
+        let result = brinson_attribution(buckets);
+
+        // Portfolio 10.0%, benchmark 7.2%
+        assert!((result.portfolio_return - 10.0).abs() < 1e-9);
+        assert!((result.benchmark_return - 7.2).abs() < 1e-9);
+        // Allocation: 0.2 × 10% − 0.2 × 3% = 1.4pp
+        assert!((result.allocation_effect - 1.4).abs() < 1e-9);
+        // Selection: 0.6 × 2% + 0.4 × −1% = 0.8pp
+        assert!((result.selection_effect - 0.8).abs() < 1e-9);
+        // Interaction: 0.2 × 2% + (−0.2) × (−1%) = 0.6pp
+        assert!((result.interaction_effect - 0.6).abs() < 1e-9);
+
+        let sum = result.allocation_effect + result.selection_effect + result.interaction_effect;
+        assert!((sum - result.active_return).abs() < 1e-9);
+        assert_eq!(result.buckets[0].classification_name, "Aktien");
+    }
+}
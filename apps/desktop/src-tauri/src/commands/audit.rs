@@ -0,0 +1,209 @@
+//! Audit log of mutating commands
+//!
+//! CRUD, corporate-action, trash, import, investment-plan, rebalancing,
+//! base-currency and restore commands call `record_audit` with the command
+//! name, the changed entity and a short summary. The entry is written on the
+//! same connection (inside the command's DB transaction where there is one),
+//! so a rolled-back change leaves no entry. Logging failures never fail the
+//! command itself.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Default and maximum number of entries returned by `get_audit_log`
+const DEFAULT_AUDIT_LIMIT: i64 = 200;
+const MAX_AUDIT_LIMIT: i64 = 5000;
+
+/// One recorded change
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub timestamp: String,
+    pub command: String,
+    /// security, account, portfolio, transaction, corporate_action, import,
+    /// investment_plan, settings, database
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    pub summary: String,
+}
+
+/// Filter for `get_audit_log`; all fields optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i64>,
+    pub command: Option<String>,
+    /// Inclusive, YYYY-MM-DD
+    pub from: Option<String>,
+    /// Inclusive, YYYY-MM-DD
+    pub to: Option<String>,
+    /// Case-insensitive substring of the summary
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Write an audit entry; errors are logged, not returned
+pub(crate) fn record_audit(
+    conn: &rusqlite::Connection,
+    command: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    summary: &str,
+) {
+    if let Err(e) = conn.execute(
+        "INSERT INTO pp_audit_log (command, entity_type, entity_id, summary) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![command, entity_type, entity_id, summary],
+    ) {
+        log::warn!("Audit log: failed to record {} on {}: {}", command, entity_type, e);
+    }
+}
+
+fn load_audit_log(
+    conn: &rusqlite::Connection,
+    filter: &AuditLogFilter,
+) -> Result<Vec<AuditLogEntry>, String> {
+    let mut conditions: Vec<&str> = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(entity_type) = &filter.entity_type {
+        conditions.push("entity_type = ?");
+        params.push(Box::new(entity_type.clone()));
+    }
+    if let Some(entity_id) = filter.entity_id {
+        conditions.push("entity_id = ?");
+        params.push(Box::new(entity_id));
+    }
+    if let Some(command) = &filter.command {
+        conditions.push("command = ?");
+        params.push(Box::new(command.clone()));
+    }
+    if let Some(from) = &filter.from {
+        conditions.push("date(timestamp) >= date(?)");
+        params.push(Box::new(from.clone()));
+    }
+    if let Some(to) = &filter.to {
+        conditions.push("date(timestamp) <= date(?)");
+        params.push(Box::new(to.clone()));
+    }
+    if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        conditions.push("summary LIKE ? ESCAPE '\\'");
+        let escaped = search
+            .trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        params.push(Box::new(format!("%{}%", escaped)));
+    }
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    params.push(Box::new(limit));
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let sql = format!(
+        "SELECT id, timestamp, command, entity_type, entity_id, summary FROM pp_audit_log {} ORDER BY id DESC LIMIT ?",
+        where_clause
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let entries = stmt
+        .query_map(param_refs.as_slice(), |row| {
+            Ok(AuditLogEntry {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                command: row.get(2)?,
+                entity_type: row.get(3)?,
+                entity_id: row.get(4)?,
+                summary: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(entries)
+}
+
+/// Browse the audit log, newest first
+#[command]
+pub fn get_audit_log(filter: Option<AuditLogFilter>) -> Result<Vec<AuditLogEntry>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    load_audit_log(conn, &filter.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_filter() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+
+        record_audit(&conn, "create_security", "security", Some(1), "Wertpapier Apple angelegt");
+        record_audit(&conn, "create_transaction", "transaction", Some(5), "BUY 2024-01-02 100_50% EUR");
+        record_audit(&conn, "delete_transaction", "transaction", Some(5), "Buchung gelöscht");
+        conn.execute(
+            "UPDATE pp_audit_log SET timestamp = '2024-01-01 10:00:00' WHERE command = 'create_security'",
+            [],
+        )
+        .unwrap();
+
+        let all = load_audit_log(&conn, &AuditLogFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].command, "delete_transaction");
+
+        let txn = load_audit_log(
+            &conn,
+            &AuditLogFilter {
+                entity_type: Some("transaction".into()),
+                entity_id: Some(5),
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(txn.len(), 1);
+        assert_eq!(txn[0].command, "delete_transaction");
+
+        let old = load_audit_log(
+            &conn,
+            &AuditLogFilter {
+                to: Some("2024-01-01".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].entity_id, Some(1));
+
+        // LIKE wildcards in the search are matched literally
+        let search = |s: &str| {
+            load_audit_log(
+                &conn,
+                &AuditLogFilter {
+                    search: Some(s.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .len()
+        };
+        assert_eq!(search("apple"), 1);
+        assert_eq!(search("0_5"), 1);
+        assert_eq!(search("%"), 1);
+        assert_eq!(search("1_5"), 0);
+    }
+}
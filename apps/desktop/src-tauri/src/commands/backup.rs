@@ -0,0 +1,229 @@
+//! Database backup, restore and maintenance commands for Tauri
+//!
+//! Backups are a one-click safety net before `delete_all_data` or a large
+//! import; `optimize_database` shrinks the database afterwards.
+
+use crate::commands::audit::record_audit;
+use crate::db;
+use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::security;
+use serde::Serialize;
+use tauri::{command, AppHandle};
+
+/// File extensions accepted for database backups
+const BACKUP_EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3"];
+
+/// Result of a backup or restore
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackupResult {
+    pub path: String,
+    pub size_bytes: u64,
+    pub schema_version: i32,
+}
+
+/// Save a snapshot of the database to `path`
+#[command]
+pub fn backup_database(path: String) -> Result<DatabaseBackupResult, String> {
+    let validated_path = security::validate_file_path_with_extension(&path, Some(BACKUP_EXTENSIONS))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    db::backup_database(conn, &validated_path)
+        .map_err(|e| format!("Sicherung fehlgeschlagen: {}", e))?;
+
+    let size_bytes = std::fs::metadata(&validated_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    log::info!("Database backup written to {}", validated_path.display());
+
+    Ok(DatabaseBackupResult {
+        path,
+        size_bytes,
+        schema_version: db::SCHEMA_VERSION,
+    })
+}
+
+/// Replace the database with a backup created by `backup_database`
+///
+/// The file is validated first (SQLite integrity, portfolio tables, schema not
+/// newer than this app); on failure the current database stays untouched.
+#[command]
+pub fn restore_database(path: String, app: AppHandle) -> Result<DatabaseBackupResult, String> {
+    let validated_path = security::validate_file_path_with_extension(&path, Some(BACKUP_EXTENSIONS))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let schema_version = db::validate_database_file(&validated_path)
+        .map_err(|e| format!("Ungültige Sicherung: {}", e))?;
+    let size_bytes = std::fs::metadata(&validated_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    {
+        let mut conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        db::restore_database(&mut conn_guard, &validated_path)
+            .map_err(|e| format!("Wiederherstellung fehlgeschlagen: {}", e))?;
+        // Recorded in the restored database, the replaced one is gone
+        if let Some(conn) = conn_guard.as_ref() {
+            record_audit(
+                conn,
+                "restore_database",
+                "database",
+                None,
+                &format!("Datenbank aus {} wiederhergestellt", validated_path.display()),
+            );
+        }
+    }
+    log::info!("Database restored from {}", validated_path.display());
+
+    emit_data_changed(
+        &app,
+        DataChangedPayload {
+            entity: "database".to_string(),
+            action: "restored".to_string(),
+            security_ids: None,
+            portfolio_ids: None,
+        },
+    );
+
+    Ok(DatabaseBackupResult {
+        path,
+        size_bytes,
+        schema_version,
+    })
+}
+
+/// Result of `optimize_database`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseOptimizationResult {
+    pub before: db::DatabaseSizes,
+    pub after: db::DatabaseSizes,
+    pub saved_bytes: u64,
+}
+
+/// Checkpoint the WAL file and compact the database (`VACUUM`)
+#[command]
+pub fn optimize_database() -> Result<DatabaseOptimizationResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let (before, after) = db::optimize_database(conn)
+        .map_err(|e| format!("Optimierung fehlgeschlagen: {}", e))?;
+    log::info!(
+        "Database optimized: {} -> {} bytes",
+        before.total(),
+        after.total()
+    );
+
+    Ok(DatabaseOptimizationResult {
+        before,
+        after,
+        saved_bytes: before.total().saturating_sub(after.total()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn count_securities(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM pp_security", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("pp_backup_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("portfolio.db");
+        let backup_path = dir.join("backup.db");
+        let newer_path = dir.join("newer.db");
+        let invalid_path = dir.join("invalid.db");
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch("PRAGMA journal_mode=WAL;").unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO pp_security (uuid, name, currency) VALUES ('s1', 'Aktie', 'EUR')",
+            [],
+        )
+        .unwrap();
+
+        db::backup_database(&conn, &backup_path).unwrap();
+        assert_eq!(
+            db::validate_database_file(&backup_path).unwrap(),
+            db::SCHEMA_VERSION
+        );
+
+        conn.execute(
+            "INSERT INTO pp_security (uuid, name, currency) VALUES ('s2', 'Fonds', 'EUR')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(count_securities(&conn), 2);
+
+        // Rejected files leave the database untouched
+        db::backup_database(&conn, &newer_path).unwrap();
+        Connection::open(&newer_path)
+            .unwrap()
+            .execute(
+                "INSERT INTO pp_schema_version (version, description) VALUES (?1, 'newer')",
+                [db::SCHEMA_VERSION + 1],
+            )
+            .unwrap();
+        std::fs::write(&invalid_path, b"no database").unwrap();
+
+        let mut slot = Some(conn);
+        assert!(db::restore_database(&mut slot, &newer_path).is_err());
+        assert!(db::restore_database(&mut slot, &invalid_path).is_err());
+        assert_eq!(count_securities(slot.as_ref().unwrap()), 2);
+
+        db::restore_database(&mut slot, &backup_path).unwrap();
+        let restored = count_securities(slot.as_ref().unwrap());
+        drop(slot);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(restored, 1);
+    }
+
+    #[test]
+    fn test_optimize_database() {
+        let dir = std::env::temp_dir().join(format!("pp_optimize_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let conn = Connection::open(dir.join("portfolio.db")).unwrap();
+        conn.execute_batch("PRAGMA journal_mode=WAL;").unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            r#"
+            WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i < 20000)
+            INSERT INTO pp_price (security_id, date, value)
+            SELECT 1, date('2000-01-01', '+' || i || ' days'), i FROM n
+            "#,
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM pp_price", []).unwrap();
+
+        let (before, after) = db::optimize_database(&conn).unwrap();
+        drop(conn);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(before.wal_bytes > 0);
+        assert_eq!(after.wal_bytes, 0);
+        assert!(after.total() < before.total());
+    }
+}
@@ -3,6 +3,8 @@
 //! Compare portfolio performance against benchmark securities.
 
 use crate::db;
+use crate::quotes::{self, ProviderType};
+use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
@@ -39,6 +41,8 @@ pub struct BenchmarkDataPoint {
     pub portfolio_return: f64,
     pub benchmark_value: f64,
     pub benchmark_return: f64,
+    /// Cumulative outperformance in percentage points (portfolio_return - benchmark_return)
+    pub outperformance: f64,
 }
 
 /// Ensure benchmark table exists
@@ -87,9 +91,173 @@ pub fn get_benchmarks() -> Result<Vec<BenchmarkData>, String> {
     benchmarks.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
-/// Add a security as a benchmark
+/// Common indices that can be added as benchmark by symbol or name: (symbol, name, currency)
+const KNOWN_INDICES: &[(&str, &str, &str)] = &[
+    ("^GSPC", "S&P 500", "USD"),
+    ("^NDX", "Nasdaq 100", "USD"),
+    ("^DJI", "Dow Jones Industrial Average", "USD"),
+    ("^GDAXI", "DAX", "EUR"),
+    ("^MDAXI", "MDAX", "EUR"),
+    ("^STOXX50E", "EURO STOXX 50", "EUR"),
+    ("^SSMI", "SMI", "CHF"),
+    ("^ATX", "ATX", "EUR"),
+    ("^FTSE", "FTSE 100", "GBP"),
+    ("^N225", "Nikkei 225", "JPY"),
+];
+
+/// Years of history fetched for a new benchmark without start date
+const DEFAULT_HISTORY_YEARS: i32 = 10;
+
+/// Benchmark security resolved from a ticker, ISIN or index name
+#[derive(Debug, Clone, PartialEq)]
+struct BenchmarkSymbol {
+    symbol: String,
+    name: String,
+    currency: String,
+    isin: Option<String>,
+}
+
+/// Look up a known index by symbol or name (case-insensitive)
+fn find_known_index(input: &str) -> Option<BenchmarkSymbol> {
+    KNOWN_INDICES
+        .iter()
+        .find(|(symbol, name, _)| symbol.eq_ignore_ascii_case(input) || name.eq_ignore_ascii_case(input))
+        .map(|(symbol, name, currency)| BenchmarkSymbol {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            currency: currency.to_string(),
+            isin: None,
+        })
+}
+
+/// Resolve a ticker, ISIN or index name to a Yahoo symbol with name and currency
+async fn resolve_benchmark_symbol(input: &str) -> Result<BenchmarkSymbol, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Benchmark symbol is empty".to_string());
+    }
+
+    if let Some(known) = find_known_index(input) {
+        return Ok(known);
+    }
+
+    let isin = crate::commands::crud::validate_isin(&input.to_uppercase()).then(|| input.to_uppercase());
+    let symbol = match isin {
+        Some(ref isin) => quotes::yahoo::search(isin)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .next()
+            .map(|result| result.symbol)
+            .ok_or_else(|| format!("No ticker found for ISIN {}", isin))?,
+        None => input.to_string(),
+    };
+
+    let quote = quotes::yahoo::fetch_quote(&symbol, false)
+        .await
+        .map_err(|e| format!("Unknown benchmark symbol {}: {}", symbol, e))?;
+
+    Ok(BenchmarkSymbol {
+        name: quote.name.unwrap_or_else(|| symbol.clone()),
+        currency: quote.currency.unwrap_or_else(|| "EUR".to_string()),
+        symbol,
+        isin,
+    })
+}
+
+/// Find the security for a benchmark symbol or create a synthetic one
+fn find_or_create_benchmark_security(
+    conn: &rusqlite::Connection,
+    benchmark: &BenchmarkSymbol,
+) -> Result<i64, String> {
+    use rusqlite::OptionalExtension;
+
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM pp_security
+             WHERE ticker = ?1 COLLATE NOCASE OR (?2 IS NOT NULL AND isin = ?2)
+             ORDER BY is_retired, id LIMIT 1",
+            rusqlite::params![benchmark.symbol, benchmark.isin],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    conn.execute(
+        "INSERT INTO pp_security (uuid, name, currency, isin, ticker, feed, note, is_retired, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 'YAHOO', 'Benchmark', 0, datetime('now'))",
+        rusqlite::params![
+            uuid::Uuid::new_v4().to_string(),
+            benchmark.name,
+            benchmark.currency,
+            benchmark.isin,
+            benchmark.symbol,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Add a benchmark
+///
+/// Either an existing security (`security_id`) or a `symbol`: a Yahoo ticker, an ISIN
+/// or a common index like ^GSPC / "DAX". For a symbol the security is created if needed
+/// and its price history is fetched from Yahoo starting at `start_date`.
 #[command]
-pub fn add_benchmark(security_id: i64, start_date: Option<String>) -> Result<BenchmarkData, String> {
+pub async fn add_benchmark(
+    security_id: Option<i64>,
+    symbol: Option<String>,
+    start_date: Option<String>,
+) -> Result<BenchmarkData, String> {
+    let security = match (security_id, symbol) {
+        (Some(id), _) => BenchmarkSecurity::Existing(id),
+        (None, Some(symbol)) => {
+            let benchmark = resolve_benchmark_symbol(&symbol).await?;
+
+            let today = chrono::Local::now().date_naive();
+            let from = match start_date.as_deref() {
+                Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                    .map_err(|e| format!("Invalid start date: {}", e))?,
+                None => today
+                    .with_year(today.year() - DEFAULT_HISTORY_YEARS)
+                    .unwrap_or(today),
+            };
+            let history = quotes::fetch_historical_quotes_with_options(
+                &benchmark.symbol,
+                ProviderType::Yahoo,
+                from,
+                today,
+                None,
+                None,
+            )
+            .await
+            .map_err(|e| format!("Failed to fetch prices for {}: {}", benchmark.symbol, e))?;
+            if history.is_empty() {
+                return Err(format!("No prices found for {}", benchmark.symbol));
+            }
+
+            BenchmarkSecurity::Fetched(benchmark, history)
+        }
+        (None, None) => return Err("Either security_id or symbol is required".to_string()),
+    };
+
+    add_benchmark_to_db(security, start_date)
+}
+
+/// Security of a new benchmark
+enum BenchmarkSecurity {
+    Existing(i64),
+    Fetched(BenchmarkSymbol, Vec<quotes::Quote>),
+}
+
+fn add_benchmark_to_db(
+    security: BenchmarkSecurity,
+    start_date: Option<String>,
+) -> Result<BenchmarkData, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
@@ -97,6 +265,22 @@ pub fn add_benchmark(security_id: i64, start_date: Option<String>) -> Result<Ben
 
     ensure_tables(conn)?;
 
+    let security_id = match security {
+        BenchmarkSecurity::Existing(id) => id,
+        BenchmarkSecurity::Fetched(benchmark, history) => {
+            let id = find_or_create_benchmark_security(conn, &benchmark)?;
+            crate::commands::quotes::insert_historical_quotes(conn, id, &history)
+                .map_err(|e| e.to_string())?;
+            log::info!(
+                "Benchmark {} ({}): saved {} prices",
+                benchmark.name,
+                benchmark.symbol,
+                history.len()
+            );
+            id
+        }
+    };
+
     // Get earliest date for this security if not specified
     let start = match start_date {
         Some(d) => d,
@@ -233,12 +417,16 @@ pub fn compare_to_benchmark(
 }
 
 /// Get time series data for benchmark comparison chart
+///
+/// With `normalize`, both series are indices rebased to 100 on the first common date,
+/// chained from the flow-adjusted daily returns also used for Beta/Alpha.
 #[command]
 pub fn get_benchmark_comparison_data(
     portfolio_id: Option<i64>,
     benchmark_id: i64,
     start_date: String,
     end_date: String,
+    normalize: Option<bool>,
 ) -> Result<Vec<BenchmarkDataPoint>, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
@@ -252,6 +440,34 @@ pub fn get_benchmark_comparison_data(
         |row| row.get(0)
     ).map_err(|e| format!("Benchmark not found: {}", e))?;
 
+    if normalize.unwrap_or(false) {
+        let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid start date: {}", e))?;
+        let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid end date: {}", e))?;
+
+        let series = crate::performance::calculate_rebased_benchmark_series(
+            conn,
+            portfolio_id,
+            benchmark_security_id,
+            start,
+            end,
+        )
+        .map_err(|e| e.to_string())?;
+
+        return Ok(series
+            .into_iter()
+            .map(|(date, portfolio_index, benchmark_index)| BenchmarkDataPoint {
+                date: date.to_string(),
+                portfolio_value: portfolio_index,
+                portfolio_return: portfolio_index - 100.0,
+                benchmark_value: benchmark_index,
+                benchmark_return: benchmark_index - 100.0,
+                outperformance: portfolio_index - benchmark_index,
+            })
+            .collect());
+    }
+
     // Get data
     let benchmark_prices = get_price_series(conn, benchmark_security_id, &start_date, &end_date)?;
     let portfolio_values = get_portfolio_values(conn, portfolio_id, &start_date, &end_date)?;
@@ -285,12 +501,16 @@ pub fn get_benchmark_comparison_data(
         last_benchmark = benchmark_value;
         last_portfolio = portfolio_value;
 
+        let portfolio_return = ((portfolio_value / portfolio_base) - 1.0) * 100.0;
+        let benchmark_return = ((benchmark_value / benchmark_base) - 1.0) * 100.0;
+
         data_points.push(BenchmarkDataPoint {
             date,
             portfolio_value,
-            portfolio_return: ((portfolio_value / portfolio_base) - 1.0) * 100.0,
+            portfolio_return,
             benchmark_value,
-            benchmark_return: ((benchmark_value / benchmark_base) - 1.0) * 100.0,
+            benchmark_return,
+            outperformance: portfolio_return - benchmark_return,
         });
     }
 
@@ -525,3 +745,49 @@ fn calculate_max_drawdown(series: &[(String, f64)]) -> f64 {
 
     max_drawdown * 100.0  // As percentage
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_known_index() {
+        assert_eq!(find_known_index("^gdaxi").unwrap().name, "DAX");
+        assert_eq!(find_known_index("S&P 500").unwrap().symbol, "^GSPC");
+        assert!(find_known_index("AAPL").is_none());
+    }
+
+    #[test]
+    fn test_find_or_create_benchmark_security() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+
+        let dax = find_known_index("DAX").unwrap();
+        let id = find_or_create_benchmark_security(&conn, &dax).unwrap();
+        assert_eq!(find_or_create_benchmark_security(&conn, &dax).unwrap(), id);
+
+        let (name, currency, feed): (String, String, String) = conn
+            .query_row(
+                "SELECT name, currency, feed FROM pp_security WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((name.as_str(), currency.as_str(), feed.as_str()), ("DAX", "EUR", "YAHOO"));
+
+        // An existing security with the ISIN is reused
+        conn.execute(
+            "INSERT INTO pp_security (uuid, name, currency, isin) VALUES ('s1', 'iShares MSCI World', 'EUR', 'IE00B4L5Y983')",
+            [],
+        )
+        .unwrap();
+        let etf = BenchmarkSymbol {
+            symbol: "EUNL.DE".to_string(),
+            name: "iShares Core MSCI World".to_string(),
+            currency: "EUR".to_string(),
+            isin: Some("IE00B4L5Y983".to_string()),
+        };
+        let etf_id = find_or_create_benchmark_security(&conn, &etf).unwrap();
+        assert_eq!(etf_id, conn.last_insert_rowid());
+    }
+}
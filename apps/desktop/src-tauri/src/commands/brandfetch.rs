@@ -8,8 +8,37 @@
 //! URL format: https://cdn.brandfetch.io/{domain}/h/64/w/64/icon?c={client_id}
 
 use base64::Engine;
+use futures::stream::{FuturesUnordered, StreamExt};
 use serde::Serialize;
-use tauri::command;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::Semaphore;
+
+/// Parallel logo downloads in `reload_all_logos`
+const MAX_CONCURRENT_LOGO_REQUESTS: usize = 4;
+
+/// Pause after each download before its slot is freed (Brandfetch rate limit)
+const LOGO_REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Background colors for monogram fallback logos (picked by name hash)
+const MONOGRAM_COLORS: &[&str] = &[
+    "#2563eb", "#7c3aed", "#db2777", "#dc2626", "#ea580c", "#ca8a04", "#16a34a", "#0d9488",
+    "#0891b2", "#4f46e5", "#9333ea", "#475569",
+];
+
+/// Data URL prefix of SVG logos (monogram fallbacks)
+const SVG_DATA_URL_PREFIX: &str = "data:image/svg+xml;base64,";
+
+/// `pp_settings` key: maximum logo cache size in bytes ("0" = unlimited)
+pub const LOGO_CACHE_MAX_BYTES_KEY: &str = "logo_cache_max_bytes";
+
+/// `pp_settings` key: maximum number of cached logos ("0" = unlimited)
+pub const LOGO_CACHE_MAX_COUNT_KEY: &str = "logo_cache_max_count";
+
+/// Default cache size limit (logos are a few KB each)
+const DEFAULT_LOGO_CACHE_MAX_BYTES: u64 = 20 * 1024 * 1024;
 
 /// ETF provider domain mappings (detected by keyword in name)
 const ETF_PROVIDERS: &[(&str, &str)] = &[
@@ -212,6 +241,8 @@ pub struct LogoResult {
     pub security_id: i64,
     pub logo_url: Option<String>,
     pub domain: Option<String>,
+    /// Logo already in the local cache (no download needed)
+    pub cached: bool,
 }
 
 /// Detect if security is an ETF based on name and return provider domain
@@ -406,19 +437,14 @@ pub fn get_security_logo_url(
             security_id,
             logo_url: None,
             domain: None,
+            cached: false,
         };
     }
 
-    // Get domain using intelligent detection
+    // Get domain using intelligent detection; no domain means no Brandfetch logo
     let domain = match get_domain_for_security(&name, ticker.as_deref(), isin.as_deref()) {
         Some(d) => d,
-        None => {
-            return LogoResult {
-                security_id,
-                logo_url: None,
-                domain: None,
-            };
-        }
+        None => return fallback_logo_result(security_id, &name),
     };
 
     // Build the CDN URL
@@ -427,6 +453,7 @@ pub fn get_security_logo_url(
     LogoResult {
         security_id,
         logo_url: Some(logo_url),
+        cached: is_logo_cached(domain.clone()),
         domain: Some(domain),
     }
 }
@@ -441,7 +468,10 @@ pub fn get_logo_urls_batch(
     securities
         .into_iter()
         .map(|(security_id, ticker, name, isin)| {
-            let domain = get_domain_for_security(&name, ticker.as_deref(), isin.as_deref());
+            let Some(domain) = get_domain_for_security(&name, ticker.as_deref(), isin.as_deref()) else {
+                return fallback_logo_result(security_id, &name);
+            };
+            let domain = Some(domain);
             // Only build CDN URL if API key is provided
             let logo_url = if client_id.is_empty() {
                 None
@@ -452,6 +482,7 @@ pub fn get_logo_urls_batch(
             LogoResult {
                 security_id,
                 logo_url,
+                cached: domain.clone().is_some_and(is_logo_cached),
                 domain,
             }
         })
@@ -470,6 +501,109 @@ pub async fn fetch_security_logo(
     get_security_logo_url(client_id, security_id, ticker, name, None)
 }
 
+// Monogram fallback logos
+
+/// Initials for a monogram: first letters of the first two words of the cleaned name
+fn monogram_initials(name: &str) -> String {
+    let cleaned = clean_company_name(name);
+    let source = if cleaned.is_empty() { name.to_lowercase() } else { cleaned };
+    let initials: String = source
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+/// Deterministic background color (FNV-1a hash of the name)
+fn monogram_color(name: &str) -> &'static str {
+    let hash = name
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    MONOGRAM_COLORS[(hash % MONOGRAM_COLORS.len() as u64) as usize]
+}
+
+/// 64x64 SVG with the initials on a colored rounded square
+fn render_monogram_svg(name: &str) -> String {
+    let initials = monogram_initials(name);
+    let font_size = if initials.chars().count() > 1 { 26 } else { 32 };
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="64" viewBox="0 0 64 64"><rect width="64" height="64" rx="12" fill="{}"/><text x="32" y="32" dy="0.35em" text-anchor="middle" font-family="Helvetica, Arial, sans-serif" font-size="{}" font-weight="600" fill="#ffffff">{}</text></svg>"##,
+        monogram_color(name),
+        font_size,
+        initials
+    )
+}
+
+/// Cache key of the fallback logo for securities without a logo domain
+fn fallback_logo_key(security_id: i64) -> String {
+    format!("fallback-{}", security_id)
+}
+
+/// Render a monogram for `name` and store it in the logo cache under `key`
+fn store_monogram(key: &str, name: &str) -> Result<String, String> {
+    let svg = render_monogram_svg(name);
+    let data_url = format!(
+        "{}{}",
+        SVG_DATA_URL_PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(svg)
+    );
+    save_logo_to_cache(key.to_string(), data_url.clone())?;
+    Ok(data_url)
+}
+
+/// Monogram logo for a security without a Brandfetch domain (created once, then cached)
+fn fallback_logo_result(security_id: i64, name: &str) -> LogoResult {
+    let key = fallback_logo_key(security_id);
+    let cached = is_logo_cached(key.clone()) || store_monogram(&key, name).is_ok();
+
+    LogoResult {
+        security_id,
+        logo_url: None,
+        domain: cached.then_some(key),
+        cached,
+    }
+}
+
+/// Generate a monogram logo (initials on a color derived from the name)
+///
+/// Stored in the logo cache as SVG; `domain` of the result is the cache key
+/// for `get_cached_logo_data`. Replaces an existing fallback logo.
+#[command]
+pub fn generate_fallback_logo(security_id: i64) -> Result<LogoResult, String> {
+    let name: String = {
+        let conn_guard = crate::db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+        conn.query_row(
+            "SELECT name FROM pp_security WHERE id = ?1",
+            [security_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Wertpapier {} nicht gefunden", security_id))?
+    };
+
+    let key = fallback_logo_key(security_id);
+    let data_url = store_monogram(&key, &name)?;
+
+    Ok(LogoResult {
+        security_id,
+        logo_url: Some(data_url),
+        domain: Some(key),
+        cached: true,
+    })
+}
+
 /// Get the cache directory for logos
 fn get_logo_cache_dir() -> Result<std::path::PathBuf, String> {
     let data_dir = dirs::data_dir()
@@ -486,40 +620,51 @@ fn get_logo_cache_dir() -> Result<std::path::PathBuf, String> {
     Ok(cache_dir)
 }
 
+/// Cached logo file for a domain (PNG from Brandfetch or SVG monogram) with its MIME type
+fn find_cached_logo(domain: &str) -> Option<(std::path::PathBuf, &'static str)> {
+    let cache_dir = get_logo_cache_dir().ok()?;
+    let stem = domain.replace('.', "_");
+    [("png", "image/png"), ("svg", "image/svg+xml")]
+        .into_iter()
+        .map(|(ext, mime)| (cache_dir.join(format!("{}.{}", stem, ext)), mime))
+        .find(|(path, _)| path.exists())
+}
+
 /// Check if a logo is cached locally for a domain
 #[command]
 pub fn is_logo_cached(domain: String) -> bool {
-    if let Ok(cache_dir) = get_logo_cache_dir() {
-        let file_name = domain.replace('.', "_") + ".png";
-        let path = cache_dir.join(&file_name);
-        path.exists()
-    } else {
-        false
-    }
+    find_cached_logo(&domain).is_some()
 }
 
 /// Get cached logo as base64 data URL
 #[command]
 pub fn get_cached_logo_data(domain: String) -> Option<String> {
-    let cache_dir = get_logo_cache_dir().ok()?;
-    let file_name = domain.replace('.', "_") + ".png";
-    let path = cache_dir.join(&file_name);
-
-    if path.exists() {
-        let data = std::fs::read(&path).ok()?;
-        let base64_str = base64::engine::general_purpose::STANDARD.encode(&data);
-        Some(format!("data:image/png;base64,{}", base64_str))
-    } else {
-        None
-    }
+    let (path, mime) = find_cached_logo(&domain)?;
+    let data = std::fs::read(&path).ok()?;
+    // The modification time is the last access for the LRU eviction
+    let _ = std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+    let base64_str = base64::engine::general_purpose::STANDARD.encode(&data);
+    Some(format!("data:{};base64,{}", mime, base64_str))
 }
 
 /// Save logo to local cache (receives base64 data from frontend)
+///
+/// SVG data URLs are stored as `.svg`, everything else as `.png`; the other
+/// format is removed so a downloaded logo replaces a monogram.
 #[command]
 pub fn save_logo_to_cache(domain: String, base64_data: String) -> Result<String, String> {
     let cache_dir = get_logo_cache_dir()?;
-    let file_name = domain.replace('.', "_") + ".png";
-    let path = cache_dir.join(&file_name);
+    let stem = domain.replace('.', "_");
+    let (ext, other_ext) = if base64_data.starts_with(SVG_DATA_URL_PREFIX) {
+        ("svg", "png")
+    } else {
+        ("png", "svg")
+    };
+    let path = cache_dir.join(format!("{}.{}", stem, ext));
+    let _ = std::fs::remove_file(cache_dir.join(format!("{}.{}", stem, other_ext)));
 
     // Extract base64 data (remove "data:image/png;base64," prefix if present)
     let data_str = if let Some(pos) = base64_data.find(",") {
@@ -535,6 +680,7 @@ pub fn save_logo_to_cache(domain: String, base64_data: String) -> Result<String,
     // Write to file
     std::fs::write(&path, &bytes)
         .map_err(|e| format!("Failed to write logo file: {}", e))?;
+    enforce_logo_cache_limits(&cache_dir, Some(&path));
 
     Ok(path.to_string_lossy().to_string())
 }
@@ -553,10 +699,10 @@ pub fn clear_logo_cache() -> Result<u32, String> {
         if let Ok(entries) = std::fs::read_dir(&cache_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.extension().map_or(false, |ext| ext == "png") {
-                    if std::fs::remove_file(&path).is_ok() {
-                        count += 1;
-                    }
+                if path.extension().is_some_and(|ext| ext == "png" || ext == "svg")
+                    && std::fs::remove_file(&path).is_ok()
+                {
+                    count += 1;
                 }
             }
         }
@@ -565,6 +711,171 @@ pub fn clear_logo_cache() -> Result<u32, String> {
     Ok(count)
 }
 
+// Logo cache size limit (LRU eviction)
+
+/// Configured limits of the logo cache (`None` = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogoCacheLimits {
+    max_bytes: Option<u64>,
+    max_count: Option<u64>,
+}
+
+/// Size of the logo cache and its configured limits
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoCacheStats {
+    pub count: u64,
+    pub total_bytes: u64,
+    pub max_bytes: Option<u64>,
+    pub max_count: Option<u64>,
+}
+
+/// Read the cache limits from `pp_settings`; missing keys use the defaults
+fn load_logo_cache_limits(conn: &rusqlite::Connection) -> LogoCacheLimits {
+    let setting = |key: &str| -> Option<u64> {
+        conn.query_row("SELECT value FROM pp_settings WHERE key = ?1", [key], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+    };
+
+    LogoCacheLimits {
+        max_bytes: setting(LOGO_CACHE_MAX_BYTES_KEY)
+            .or(Some(DEFAULT_LOGO_CACHE_MAX_BYTES))
+            .filter(|v| *v > 0),
+        max_count: setting(LOGO_CACHE_MAX_COUNT_KEY).filter(|v| *v > 0),
+    }
+}
+
+fn current_logo_cache_limits() -> LogoCacheLimits {
+    crate::db::get_connection()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(load_logo_cache_limits))
+        .unwrap_or(LogoCacheLimits {
+            max_bytes: Some(DEFAULT_LOGO_CACHE_MAX_BYTES),
+            max_count: None,
+        })
+}
+
+/// Cached logo files with size and last access, least recently used first
+fn list_cached_logos(cache_dir: &std::path::Path) -> Vec<(std::path::PathBuf, u64, std::time::SystemTime)> {
+    let mut files: Vec<_> = std::fs::read_dir(cache_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "png" || ext == "svg")
+        })
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let accessed = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+            Some((entry.path(), meta.len(), accessed))
+        })
+        .collect();
+    files.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+    files
+}
+
+/// Delete least recently used logos until the cache is within `limits`
+///
+/// `keep` (the logo just written) is never deleted. Returns the number of
+/// deleted files.
+fn evict_logo_cache(
+    cache_dir: &std::path::Path,
+    limits: LogoCacheLimits,
+    keep: Option<&std::path::Path>,
+) -> u32 {
+    let files = list_cached_logos(cache_dir);
+    let mut count = files.len() as u64;
+    let mut total_bytes: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let over = |count: u64, bytes: u64| {
+        limits.max_count.is_some_and(|max| count > max) || limits.max_bytes.is_some_and(|max| bytes > max)
+    };
+
+    let mut evicted = 0;
+    for (path, size, _) in files {
+        if !over(count, total_bytes) {
+            break;
+        }
+        if Some(path.as_path()) == keep {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            count -= 1;
+            total_bytes -= size;
+            evicted += 1;
+        }
+    }
+    evicted
+}
+
+fn enforce_logo_cache_limits(cache_dir: &std::path::Path, keep: Option<&std::path::Path>) {
+    let evicted = evict_logo_cache(cache_dir, current_logo_cache_limits(), keep);
+    if evicted > 0 {
+        log::info!("Logo cache: evicted {} least recently used logos", evicted);
+    }
+}
+
+fn logo_cache_stats(cache_dir: &std::path::Path, limits: LogoCacheLimits) -> LogoCacheStats {
+    let files = list_cached_logos(cache_dir);
+    LogoCacheStats {
+        count: files.len() as u64,
+        total_bytes: files.iter().map(|(_, size, _)| size).sum(),
+        max_bytes: limits.max_bytes,
+        max_count: limits.max_count,
+    }
+}
+
+/// Number and total size of cached logos with the configured limits
+#[command]
+pub fn get_logo_cache_stats() -> Result<LogoCacheStats, String> {
+    let cache_dir = get_logo_cache_dir()?;
+    Ok(logo_cache_stats(&cache_dir, current_logo_cache_limits()))
+}
+
+/// Set the logo cache limits and prune the cache right away
+///
+/// `None` resets a limit to its default (20 MB, no count limit), `0` disables it.
+#[command]
+pub fn set_logo_cache_limits(
+    max_bytes: Option<u64>,
+    max_count: Option<u64>,
+) -> Result<LogoCacheStats, String> {
+    let limits = {
+        let conn_guard = crate::db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        for (key, value) in [
+            (LOGO_CACHE_MAX_BYTES_KEY, max_bytes),
+            (LOGO_CACHE_MAX_COUNT_KEY, max_count),
+        ] {
+            match value {
+                Some(value) => conn.execute(
+                    r#"
+                    INSERT INTO pp_settings (key, value) VALUES (?1, ?2)
+                    ON CONFLICT(key) DO UPDATE SET value = excluded.value
+                    "#,
+                    rusqlite::params![key, value.to_string()],
+                ),
+                None => conn.execute("DELETE FROM pp_settings WHERE key = ?1", [key]),
+            }
+            .map_err(|e| format!("Failed to save logo cache limit: {}", e))?;
+        }
+
+        load_logo_cache_limits(conn)
+    };
+
+    let cache_dir = get_logo_cache_dir()?;
+    evict_logo_cache(&cache_dir, limits, None);
+    Ok(logo_cache_stats(&cache_dir, limits))
+}
+
 #[command]
 pub async fn fetch_logos_batch(
     client_id: String,
@@ -578,86 +889,174 @@ pub async fn fetch_logos_batch(
     get_logo_urls_batch(client_id, securities_with_isin)
 }
 
-/// Reload all logos: clears cache and downloads fresh logos for all securities
+/// Download logos for all securities
+///
+/// Domains already in the cache are skipped unless `force` is set, which
+/// clears the cache first. Downloads run with bounded concurrency and a pause
+/// per request (429 responses are retried with backoff); every finished
+/// domain emits a `logo-reload-progress` event.
 #[command]
 pub async fn reload_all_logos(
+    app: AppHandle,
     client_id: String,
     securities: Vec<(i64, Option<String>, String)>, // (id, ticker, name)
+    force: Option<bool>,
 ) -> Result<ReloadResult, String> {
     if client_id.is_empty() {
         return Err("Client ID erforderlich".to_string());
     }
 
-    // Get unique domains
-    let mut domains: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for (_, ticker, name) in &securities {
-        if let Some(domain) = get_domain_for_security(name, ticker.as_deref(), None) {
-            domains.insert(domain);
-        }
-    }
-
-    let total_domains = domains.len() as u32;
+    let domain_names = collect_logo_domains(&securities);
+    let total_domains = domain_names.len() as u32;
 
-    // Clear existing cache
-    let cleared = clear_logo_cache().unwrap_or(0);
+    let cleared = if force.unwrap_or(false) {
+        clear_logo_cache().unwrap_or(0)
+    } else {
+        0
+    };
+    let (cached, to_download) = split_cached_domains(
+        domain_names.keys().cloned().collect(),
+        |d| is_logo_cached(d.to_string()),
+    );
 
-    // Download all logos
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
+        .timeout(Duration::from_secs(10))
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_LOGO_REQUESTS));
 
-    let mut downloaded = 0u32;
-    let mut failed = 0u32;
-
-    for domain in domains {
+    let mut pending = FuturesUnordered::new();
+    for domain in to_download {
+        let client = client.clone();
+        let limit = limit.clone();
         let url = build_logo_url(&domain, &client_id);
+        pending.push(async move {
+            let _permit = limit.acquire().await;
+            let outcome = download_logo(&client, &url, &domain).await;
+            tokio::time::sleep(LOGO_REQUEST_INTERVAL).await;
+            (domain, outcome)
+        });
+    }
 
-        match client.get(&url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.bytes().await {
-                        Ok(bytes) => {
-                            // Save to cache
-                            let cache_dir = match get_logo_cache_dir() {
-                                Ok(dir) => dir,
-                                Err(_) => {
-                                    failed += 1;
-                                    continue;
-                                }
-                            };
-                            let file_name = domain.replace('.', "_") + ".png";
-                            let path = cache_dir.join(&file_name);
-
-                            if std::fs::write(&path, &bytes).is_ok() {
-                                downloaded += 1;
-                                log::info!("Downloaded logo for {}", domain);
-                            } else {
-                                failed += 1;
-                            }
-                        }
-                        Err(_) => {
-                            failed += 1;
-                        }
+    let total = pending.len();
+    let mut result = ReloadResult {
+        cleared,
+        downloaded: 0,
+        cached: cached.len() as u32,
+        not_found: 0,
+        failed: 0,
+        total_domains,
+        failures: Vec::new(),
+    };
+    let mut current = 0;
+    while let Some((domain, outcome)) = pending.next().await {
+        current += 1;
+        let status = match outcome {
+            LogoDownload::Downloaded => {
+                result.downloaded += 1;
+                "downloaded"
+            }
+            LogoDownload::NotFound => {
+                // Brandfetch has no logo: cache a monogram instead of leaving a blank
+                if let Some(name) = domain_names.get(&domain) {
+                    if let Err(e) = store_monogram(&domain, name) {
+                        log::warn!("Failed to store fallback logo for {}: {}", domain, e);
                     }
-                } else {
-                    // 404 etc. - domain not found, not a real error
-                    log::debug!("No logo found for {}: {}", domain, response.status());
                 }
+                result.not_found += 1;
+                "not_found"
             }
-            Err(e) => {
-                log::warn!("Failed to fetch logo for {}: {}", domain, e);
-                failed += 1;
+            LogoDownload::Failed(reason) => {
+                log::warn!("Failed to fetch logo for {}: {}", domain, reason);
+                result.failed += 1;
+                result.failures.push(LogoFailure {
+                    domain: domain.clone(),
+                    reason,
+                });
+                "failed"
             }
+        };
+        let _ = app.emit(
+            "logo-reload-progress",
+            LogoReloadProgress {
+                current,
+                total,
+                domain,
+                status: status.to_string(),
+            },
+        );
+    }
+
+    if let Ok(cache_dir) = get_logo_cache_dir() {
+        enforce_logo_cache_limits(&cache_dir, None);
+    }
+
+    log::info!(
+        "Logo reload: {} downloaded, {} cached, {} not found, {} failed",
+        result.downloaded,
+        result.cached,
+        result.not_found,
+        result.failed
+    );
+    Ok(result)
+}
+
+/// Unique logo domains of the securities (sorted) with the first security name per domain
+fn collect_logo_domains(securities: &[(i64, Option<String>, String)]) -> BTreeMap<String, String> {
+    let mut domains = BTreeMap::new();
+    for (_, ticker, name) in securities {
+        if let Some(domain) = get_domain_for_security(name, ticker.as_deref(), None) {
+            domains.entry(domain).or_insert_with(|| name.clone());
         }
     }
+    domains
+}
 
-    Ok(ReloadResult {
-        cleared,
-        downloaded,
-        failed,
-        total_domains,
-    })
+/// Split domains into (already cached, to download)
+fn split_cached_domains(
+    domains: BTreeSet<String>,
+    is_cached: impl Fn(&str) -> bool,
+) -> (Vec<String>, Vec<String>) {
+    domains.into_iter().partition(|d| is_cached(d))
+}
+
+enum LogoDownload {
+    Downloaded,
+    /// 404 etc. - Brandfetch has no logo for the domain, not a real error
+    NotFound,
+    Failed(String),
+}
+
+async fn download_logo(client: &reqwest::Client, url: &str, domain: &str) -> LogoDownload {
+    let response = match crate::quotes::retry::send_with_retry(client.get(url)).await {
+        Ok(response) => response,
+        Err(e) => return LogoDownload::Failed(e.to_string()),
+    };
+
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return LogoDownload::Failed(format!("HTTP {}", status));
+    }
+    if !status.is_success() {
+        log::debug!("No logo found for {}: {}", domain, status);
+        return LogoDownload::NotFound;
+    }
+
+    let bytes = match response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return LogoDownload::Failed(e.to_string()),
+    };
+    let path = match get_logo_cache_dir() {
+        Ok(dir) => dir.join(domain.replace('.', "_") + ".png"),
+        Err(e) => return LogoDownload::Failed(e),
+    };
+    match std::fs::write(&path, &bytes) {
+        Ok(()) => {
+            log::info!("Downloaded logo for {}", domain);
+            LogoDownload::Downloaded
+        }
+        Err(e) => LogoDownload::Failed(format!("Failed to write logo file: {}", e)),
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -665,8 +1064,32 @@ pub async fn reload_all_logos(
 pub struct ReloadResult {
     pub cleared: u32,
     pub downloaded: u32,
+    /// Skipped because already cached
+    pub cached: u32,
+    /// Brandfetch has no logo for the domain
+    pub not_found: u32,
     pub failed: u32,
     pub total_domains: u32,
+    pub failures: Vec<LogoFailure>,
+}
+
+/// A logo download that failed (network, rate limit, file system)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoFailure {
+    pub domain: String,
+    pub reason: String,
+}
+
+/// Progress of `reload_all_logos` (event `logo-reload-progress`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoReloadProgress {
+    pub current: usize,
+    pub total: usize,
+    pub domain: String,
+    /// `downloaded`, `not_found` or `failed`
+    pub status: String,
 }
 
 #[cfg(test)]
@@ -697,6 +1120,95 @@ mod tests {
         assert_eq!(detect_etf_provider("Amundis NASDAQ 100"), Some("amundi.com"));
     }
 
+    #[test]
+    fn test_reload_skips_cached_domains() {
+        let securities = vec![
+            (1, Some("AAPL".to_string()), "Apple Inc.".to_string()),
+            (2, None, "Apple Inc. (Xetra)".to_string()),
+            (3, None, "iShares Core MSCI World".to_string()),
+            (4, None, "Bitcoin".to_string()),
+        ];
+        let domains = collect_logo_domains(&securities);
+        assert_eq!(domains.len(), 2);
+
+        let (cached, to_download) =
+            split_cached_domains(domains.into_keys().collect(), |d| d == "blackrock.com");
+        assert_eq!(cached, vec!["blackrock.com".to_string()]);
+        assert_eq!(to_download, vec!["apple.com".to_string()]);
+    }
+
+    #[test]
+    fn test_monogram_fallback() {
+        assert_eq!(monogram_initials("Vanguard FTSE All-World UCITS ETF"), "VF");
+        assert_eq!(monogram_initials("Apple Inc."), "A");
+        assert_eq!(monogram_initials("4,5% Bundesanleihe 2030"), "4B");
+        assert_eq!(monogram_initials("   "), "?");
+
+        // Same name, same color; case does not matter
+        assert_eq!(monogram_color("Xtrackers DAX"), monogram_color("XTRACKERS DAX"));
+        assert!(MONOGRAM_COLORS.contains(&monogram_color("Bundesanleihe")));
+
+        let svg = render_monogram_svg("Siemens Energy AG");
+        assert!(svg.starts_with("<svg") && svg.contains(">SE</text>"));
+        assert!(svg.contains(monogram_color("Siemens Energy AG")));
+    }
+
+    #[test]
+    fn test_logo_cache_eviction() {
+        let dir = std::env::temp_dir().join(format!("pp_logo_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // a (oldest) .. d (newest), 100 bytes each
+        let now = std::time::SystemTime::now();
+        for (i, name) in ["a_com.png", "b_com.png", "c_com.svg", "d_com.png"].iter().enumerate() {
+            let path = dir.join(name);
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(100 - i as u64))
+                .unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let unlimited = LogoCacheLimits { max_bytes: None, max_count: None };
+        assert_eq!(evict_logo_cache(&dir, unlimited, None), 0);
+
+        // Count limit; the oldest file is protected as `keep`
+        let keep = dir.join("a_com.png");
+        let by_count = LogoCacheLimits { max_bytes: None, max_count: Some(3) };
+        assert_eq!(evict_logo_cache(&dir, by_count, Some(&keep)), 1);
+        assert!(keep.exists() && !dir.join("b_com.png").exists());
+
+        let by_size = LogoCacheLimits { max_bytes: Some(150), max_count: None };
+        assert_eq!(evict_logo_cache(&dir, by_size, None), 2);
+        let stats = logo_cache_stats(&dir, by_size);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.total_bytes, 100);
+    }
+
+    #[test]
+    fn test_load_logo_cache_limits() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        assert_eq!(
+            load_logo_cache_limits(&conn),
+            LogoCacheLimits { max_bytes: Some(DEFAULT_LOGO_CACHE_MAX_BYTES), max_count: None }
+        );
+
+        conn.execute_batch(
+            "INSERT INTO pp_settings (key, value) VALUES ('logo_cache_max_bytes', '0'), ('logo_cache_max_count', '500');",
+        )
+        .unwrap();
+        assert_eq!(
+            load_logo_cache_limits(&conn),
+            LogoCacheLimits { max_bytes: None, max_count: Some(500) }
+        );
+    }
+
     #[test]
     fn test_domain_exceptions() {
         assert_eq!(check_domain_exception("Alphabet Inc."), Some("google.com"));
@@ -792,6 +792,53 @@ pub fn compare_portfolios(portfolio_ids: Vec<i64>) -> Result<PortfolioComparison
     })
 }
 
+/// Get risk metrics (volatility, Sharpe, drawdown, ...) for a whole consortium
+#[tauri::command]
+pub fn get_consortium_risk_metrics(
+    consortium_id: i64,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    benchmark_id: Option<i64>,
+    risk_free_rate: Option<f64>,
+    horizon_days: Option<u32>,
+) -> Result<performance::RiskMetrics, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let portfolio_ids_str: String = conn
+        .query_row(
+            "SELECT portfolio_ids FROM pp_consortium WHERE id = ?1",
+            params![consortium_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Consortium not found: {}", e))?;
+
+    let portfolio_ids: Vec<i64> = serde_json::from_str(&portfolio_ids_str).unwrap_or_default();
+
+    let (combined_start, combined_end) = get_combined_date_range(conn, &portfolio_ids);
+
+    let start = start_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or(combined_start);
+
+    let end = end_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or(combined_end);
+
+    performance::calculate_risk_metrics_for_portfolios(
+        conn,
+        &portfolio_ids,
+        start,
+        end,
+        benchmark_id,
+        risk_free_rate,
+        horizon_days,
+    )
+    .map_err(|e| e.to_string())
+}
+
 /// Get historical performance data for a consortium (for charts)
 #[tauri::command]
 pub fn get_consortium_history(
@@ -3,6 +3,7 @@
 //! Handles stock splits, mergers, spin-offs, and other corporate events
 //! that affect share counts and cost basis.
 
+use crate::commands::audit::record_audit;
 use crate::db;
 use crate::pp::common::shares;
 use serde::{Deserialize, Serialize};
@@ -73,8 +74,9 @@ pub struct ApplySpinOffRequest {
     pub source_security_id: i64,
     pub target_security_id: i64,
     pub effective_date: String,
-    /// Cost basis allocation to new security (0.0 - 1.0)
-    pub cost_allocation: f64,
+    /// Cost basis allocated to the new security in percent (0 - 100).
+    /// Defaults to the fair market value ratio of both securities' prices.
+    pub cost_allocation_percent: Option<f64>,
     /// Shares of new security per source share
     pub share_ratio: f64,
     pub note: Option<String>,
@@ -106,9 +108,29 @@ pub struct MergerPreview {
     pub share_ratio: f64,
     pub cash_per_share: f64,
     pub affected_portfolios: Vec<MergerAffectedPortfolio>,
+    /// Conversion of each open FIFO lot
+    pub lots: Vec<MergerLotConversion>,
+    /// Price of the target security used for cash-in-lieu
+    pub target_price: Option<f64>,
     pub total_source_shares: f64,
     pub total_target_shares: f64,
     pub total_cash: f64,
+    pub total_cash_in_lieu: f64,
+}
+
+/// Conversion of a single FIFO lot under the exchange ratio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergerLotConversion {
+    pub lot_id: i64,
+    pub portfolio_id: i64,
+    pub purchase_date: String,
+    pub source_shares: f64,
+    pub target_shares: f64,
+    /// Cost basis carried over to the target security
+    pub gross_cost_basis: f64,
+    pub net_cost_basis: f64,
+    pub currency: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +141,9 @@ pub struct MergerAffectedPortfolio {
     pub source_shares: f64,
     pub target_shares: f64,
     pub cash_received: f64,
+    /// Fractional target shares settled in cash
+    pub fractional_shares: f64,
+    pub cash_in_lieu: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -159,6 +184,461 @@ pub struct CorporateActionResult {
     pub fifo_lots_adjusted: i64,
     /// Number of prices adjusted
     pub prices_adjusted: i64,
+    /// Recorded corporate action (for undo of mergers and spin-offs)
+    pub action_id: Option<i64>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Open FIFO lot held at the date of a corporate action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenLot {
+    lot_id: i64,
+    portfolio_id: i64,
+    purchase_date: String,
+    /// Remaining shares (scale 10^8)
+    shares: i64,
+    /// Remaining cost basis including fees/taxes (cents)
+    gross_amount: i64,
+    /// Remaining cost basis excluding fees/taxes (cents)
+    net_amount: i64,
+    currency: String,
+}
+
+/// Open FIFO lots of a security bought up to the effective date
+fn load_open_lots(
+    conn: &rusqlite::Connection,
+    source_security_id: i64,
+    effective_date: &str,
+) -> Result<Vec<OpenLot>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, portfolio_id, purchase_date, original_shares, remaining_shares,
+                   gross_amount, net_amount, currency
+            FROM pp_fifo_lot
+            WHERE security_id = ? AND remaining_shares > 0 AND purchase_date <= ?
+            ORDER BY portfolio_id, purchase_date, id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let lots = stmt
+        .query_map(rusqlite::params![source_security_id, effective_date], |row| {
+            let original_shares: i64 = row.get(3)?;
+            let remaining_shares: i64 = row.get(4)?;
+            // Cost basis of the remaining part of the lot
+            let remaining = |amount: i64| {
+                if original_shares == 0 {
+                    0
+                } else {
+                    ((remaining_shares as i128 * amount as i128) / original_shares as i128) as i64
+                }
+            };
+            Ok(OpenLot {
+                lot_id: row.get(0)?,
+                portfolio_id: row.get(1)?,
+                purchase_date: row.get(2)?,
+                shares: remaining_shares,
+                gross_amount: remaining(row.get(5)?),
+                net_amount: remaining(row.get(6)?),
+                currency: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(lots)
+}
+
+/// Transaction booked by a corporate action on its effective date
+struct ActionTxn<'a> {
+    owner_type: &'a str,
+    owner_id: i64,
+    security_id: i64,
+    txn_type: &'a str,
+    /// Amount in cents (net cost basis for deliveries)
+    amount: i64,
+    currency: &'a str,
+    shares: Option<i64>,
+    note: &'a str,
+    /// Purchase date of the lot a delivery replaces (keeps the holding period)
+    acquisition_date: Option<&'a str>,
+}
+
+fn insert_action_txn(
+    conn: &rusqlite::Connection,
+    import_id: i64,
+    date: &str,
+    txn: &ActionTxn,
+) -> Result<i64, String> {
+    conn.execute(
+        r#"
+        INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note,
+                            acquisition_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        rusqlite::params![
+            import_id,
+            uuid::Uuid::new_v4().to_string(),
+            txn.owner_type,
+            txn.owner_id,
+            txn.security_id,
+            txn.txn_type,
+            date,
+            txn.amount,
+            txn.currency,
+            txn.shares,
+            txn.note,
+            txn.acquisition_date
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Book a delivery whose FIFO lot gets the given gross cost basis.
+///
+/// The amount is the net basis, the fees/taxes part is stored as FEE unit, so
+/// a FIFO rebuild creates the lot with the same gross/net amounts.
+fn insert_delivery_with_basis(
+    conn: &rusqlite::Connection,
+    import_id: i64,
+    date: &str,
+    txn: &ActionTxn,
+    gross_amount: i64,
+) -> Result<i64, String> {
+    let txn_id = insert_action_txn(conn, import_id, date, txn)?;
+    let fees = gross_amount - txn.amount;
+    if fees > 0 {
+        conn.execute(
+            "INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency) VALUES (?1, 'FEE', ?2, ?3)",
+            rusqlite::params![txn_id, fees, txn.currency],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(txn_id)
+}
+
+/// Cost basis of a parent lot split between parent and spun-off security
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LotBasisSplit {
+    retained_gross: i64,
+    retained_net: i64,
+    allocated_gross: i64,
+    allocated_net: i64,
+}
+
+fn split_lot_basis(gross_amount: i64, net_amount: i64, allocation: f64) -> LotBasisSplit {
+    let allocated_gross = (gross_amount as f64 * allocation).round() as i64;
+    let allocated_net = (net_amount as f64 * allocation).round() as i64;
+    LotBasisSplit {
+        retained_gross: gross_amount - allocated_gross,
+        retained_net: net_amount - allocated_net,
+        allocated_gross,
+        allocated_net,
+    }
+}
+
+/// Share of the cost basis for the spun-off security by fair market value:
+/// value of the new shares per parent share relative to the combined value.
+fn fair_value_allocation(parent_price: f64, child_price: f64, share_ratio: f64) -> Option<f64> {
+    let child_value = child_price * share_ratio;
+    let total = parent_price + child_value;
+    (total > 0.0 && child_value >= 0.0).then(|| child_value / total)
+}
+
+/// Shares of the new security for a lot under an exchange ratio (scale 10^8)
+fn convert_lot_shares(source_shares: i64, share_ratio: f64) -> i64 {
+    (source_shares as f64 * share_ratio).round() as i64
+}
+
+/// Fractional part of a target position that is settled in cash (scale 10^8)
+fn fractional_merger_shares(target_shares: i64) -> i64 {
+    target_shares % 100_000_000
+}
+
+/// Latest price of a security on or before a date
+fn price_on_or_before(conn: &rusqlite::Connection, security_id: i64, date: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT value FROM pp_price WHERE security_id = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+        rusqlite::params![security_id, date],
+        |row| row.get::<_, i64>(0),
+    )
+    .ok()
+    .map(crate::pp::common::prices::to_decimal)
+}
+
+/// Applied merger or spin-off to record for undo
+struct AppliedAction<'a> {
+    /// MERGER or SPINOFF
+    action_type: &'a str,
+    security_id: i64,
+    successor_security_id: i64,
+    effective_date: &'a str,
+    note: Option<&'a str>,
+    /// Transactions booked by the action
+    created_txn_ids: &'a [i64],
+    /// Open lots of the source security before the action
+    lots: &'a [OpenLot],
+}
+
+/// Store the action in `pp_corporate_action` with a snapshot for undo
+fn record_applied_action(conn: &rusqlite::Connection, action: &AppliedAction) -> Result<i64, String> {
+    conn.execute(
+        r#"
+        INSERT INTO pp_corporate_action
+            (security_id, action_type, effective_date, successor_security_id, source, is_applied, is_confirmed, note)
+        VALUES (?1, ?2, ?3, ?4, 'USER', 1, 1, ?5)
+        "#,
+        rusqlite::params![
+            action.security_id,
+            action.action_type,
+            action.effective_date,
+            action.successor_security_id,
+            action.note
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let action_id = conn.last_insert_rowid();
+
+    conn.execute(
+        "INSERT INTO pp_corporate_action_snapshot (action_id, txn_ids, lots) VALUES (?1, ?2, ?3)",
+        rusqlite::params![
+            action_id,
+            serde_json::to_string(action.created_txn_ids).map_err(|e| e.to_string())?,
+            serde_json::to_string(action.lots).map_err(|e| e.to_string())?
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(action_id)
+}
+
+/// Remove the transactions of an applied action and rebuild the lots.
+///
+/// The restored lots of the source security are checked against the snapshot
+/// taken when the action was applied.
+fn undo_applied_action(
+    conn: &rusqlite::Connection,
+    action_id: i64,
+    expected_type: &str,
+) -> Result<CorporateActionResult, String> {
+    let (action_type, security_id, successor_id, effective_date, is_applied): (String, i64, Option<i64>, String, bool) = conn
+        .query_row(
+            "SELECT action_type, security_id, successor_security_id, effective_date, is_applied FROM pp_corporate_action WHERE id = ?",
+            [action_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get::<_, i32>(4)? == 1)),
+        )
+        .map_err(|e| format!("Corporate action not found: {}", e))?;
+
+    if action_type != expected_type {
+        return Err(format!("Corporate action {} is a {}, not a {}", action_id, action_type, expected_type));
+    }
+    if !is_applied {
+        return Err(format!("Corporate action {} is not applied", action_id));
+    }
+
+    let (txn_ids_json, lots_json): (String, String) = conn
+        .query_row(
+            "SELECT txn_ids, lots FROM pp_corporate_action_snapshot WHERE action_id = ?",
+            [action_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| format!("No snapshot stored for corporate action {}, cannot undo", action_id))?;
+    let txn_ids: Vec<i64> = serde_json::from_str(&txn_ids_json).map_err(|e| e.to_string())?;
+    let snapshot_lots: Vec<OpenLot> = serde_json::from_str(&lots_json).map_err(|e| e.to_string())?;
+
+    // Later portfolio transactions (e.g. a sale of the merger target) build on
+    // the action's deliveries; removing them would corrupt the lots
+    let dependent: Vec<i64> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id FROM pp_txn
+                 WHERE security_id IN (?1, ?2) AND owner_type = 'portfolio'
+                   AND date(date) >= date(?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map(
+                rusqlite::params![security_id, successor_id.unwrap_or(security_id), effective_date],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids.into_iter().filter(|id| !txn_ids.contains(id)).collect()
+    };
+    if !dependent.is_empty() {
+        return Err(format!(
+            "Corporate action {} cannot be undone: {} later transaction(s) depend on it (ids {:?})",
+            action_id,
+            dependent.len(),
+            dependent
+        ));
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    for txn_id in &txn_ids {
+        conn.execute(
+            "DELETE FROM pp_cross_entry WHERE portfolio_txn_id = ?1 OR account_txn_id = ?1",
+            [txn_id],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM pp_txn_unit WHERE txn_id = ?", [txn_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM pp_txn WHERE id = ?", [txn_id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    for id in std::iter::once(security_id).chain(successor_id) {
+        crate::fifo::build_fifo_lots(conn, id).map_err(|e| e.to_string())?;
+    }
+
+    let restored = load_open_lots(conn, security_id, &effective_date)?;
+    let totals = |lots: &[OpenLot]| {
+        lots.iter()
+            .fold((0i64, 0i64), |(shares, gross), l| (shares + l.shares, gross + l.gross_amount))
+    };
+    if totals(&restored) != totals(&snapshot_lots) {
+        // Dropping `tx` rolls the deletions back
+        return Err(format!(
+            "Corporate action {} cannot be undone: restored lots differ from snapshot ({:?} vs {:?})",
+            action_id,
+            totals(&restored),
+            totals(&snapshot_lots)
+        ));
+    }
+
+    conn.execute(
+        "UPDATE pp_corporate_action SET is_applied = 0 WHERE id = ?",
+        [action_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM pp_corporate_action_snapshot WHERE action_id = ?", [action_id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(CorporateActionResult {
+        success: true,
+        message: format!("{} undone ({} lots restored)", action_type, restored.len()),
+        transactions_adjusted: txn_ids.len() as i64,
+        fifo_lots_adjusted: restored.len() as i64,
+        prices_adjusted: 0,
+        action_id: Some(action_id),
+    })
+}
+
+/// Confirm an ISIN change and merge the successor security into the old one.
+///
+/// The old security keeps its id (lots, watchlists, plans) and takes over the
+/// new ISIN. Everything referencing an already existing successor is moved
+/// over (see `duplicates::repoint_security_references`); from the effective
+/// date on the successor's prices win.
+/// The emptied successor is retired.
+fn merge_isin_change(conn: &rusqlite::Connection, action_id: i64) -> Result<CorporateActionResult, String> {
+    let (action_type, security_id, successor_id, effective_date, new_isin, is_applied): (
+        String,
+        i64,
+        Option<i64>,
+        String,
+        Option<String>,
+        bool,
+    ) = conn
+        .query_row(
+            "SELECT action_type, security_id, successor_security_id, effective_date, new_identifier, is_applied
+             FROM pp_corporate_action WHERE id = ?",
+            [action_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get::<_, i32>(5)? == 1)),
+        )
+        .map_err(|e| format!("Corporate action not found: {}", e))?;
+
+    if action_type != "ISIN_CHANGE" {
+        return Err(format!("Corporate action {} is a {}, not an ISIN_CHANGE", action_id, action_type));
+    }
+    if is_applied {
+        return Err(format!("Corporate action {} is already applied", action_id));
+    }
+    let new_isin = new_isin
+        .map(|isin| isin.trim().to_uppercase())
+        .filter(|isin| !isin.is_empty())
+        .ok_or_else(|| format!("Corporate action {} has no new ISIN", action_id))?;
+
+    // All-or-nothing: a failure halfway must not leave half-moved transactions
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut transactions_moved = 0;
+    let mut prices_moved = 0;
+
+    if let Some(successor_id) = successor_id.filter(|id| *id != security_id) {
+        // From the effective date on the successor's prices win; everything
+        // else is repointed like a duplicate merge (the old security wins)
+        let replaced = conn
+            .execute(
+                "INSERT OR REPLACE INTO pp_price (security_id, date, value, source)
+                 SELECT ?1, date, value, source FROM pp_price WHERE security_id = ?2 AND date >= ?3",
+                rusqlite::params![security_id, successor_id, effective_date],
+            )
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR REPLACE INTO pp_latest_price (security_id, date, value, high, low, volume, updated_at)
+             SELECT ?1, n.date, n.value, n.high, n.low, n.volume, n.updated_at
+             FROM pp_latest_price n
+             WHERE n.security_id = ?2
+               AND n.date >= COALESCE((SELECT date FROM pp_latest_price WHERE security_id = ?1), '')",
+            [security_id, successor_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let (transactions, prices) =
+            crate::commands::duplicates::repoint_security_references(conn, security_id, successor_id)?;
+        transactions_moved = transactions;
+        prices_moved = replaced + prices;
+
+        // The successor still holds the new ISIN; clear it before retiring
+        conn.execute(
+            "UPDATE pp_security SET is_retired = 1, isin = NULL,
+                    note = TRIM(COALESCE(note, '') || ' ' || ?2), updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            rusqlite::params![successor_id, format!("Merged into security {} (ISIN change {})", security_id, new_isin)],
+        )
+        .map_err(|e| e.to_string())?;
+
+        crate::fifo::build_fifo_lots(conn, successor_id).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute(
+        "UPDATE pp_security SET isin = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![new_isin, security_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    crate::fifo::build_fifo_lots(conn, security_id).map_err(|e| e.to_string())?;
+    let lots: i64 = conn
+        .query_row("SELECT COUNT(*) FROM pp_fifo_lot WHERE security_id = ?", [security_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE pp_corporate_action SET is_applied = 1, is_confirmed = 1, successor_security_id = ?2 WHERE id = ?1",
+        rusqlite::params![action_id, successor_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(CorporateActionResult {
+        success: true,
+        message: format!(
+            "ISIN changed to {} ({} transactions, {} prices merged)",
+            new_isin, transactions_moved, prices_moved
+        ),
+        transactions_adjusted: transactions_moved as i64,
+        fifo_lots_adjusted: lots,
+        prices_adjusted: prices_moved as i64,
+        action_id: Some(action_id),
+    })
 }
 
 // ============================================================================
@@ -358,6 +838,17 @@ pub fn apply_stock_split(request: ApplyStockSplitRequest) -> Result<CorporateAct
         );
     }
 
+    record_audit(
+        conn,
+        "apply_stock_split",
+        "security",
+        Some(request.security_id),
+        &format!(
+            "Aktiensplit {}:{} zum {} ({} Buchungen angepasst)",
+            request.ratio_from, request.ratio_to, request.effective_date, transactions_adjusted
+        ),
+    );
+
     Ok(CorporateActionResult {
         success: true,
         message: format!(
@@ -367,6 +858,7 @@ pub fn apply_stock_split(request: ApplyStockSplitRequest) -> Result<CorporateAct
         transactions_adjusted,
         fifo_lots_adjusted,
         prices_adjusted,
+        action_id: None,
     })
 }
 
@@ -391,6 +883,11 @@ pub fn undo_stock_split(
 }
 
 /// Apply a spin-off (create holdings in new security from existing holdings)
+///
+/// Each parent lot is split: the retained part stays on the parent security,
+/// the allocated part becomes a lot of the spun-off security. Both are booked
+/// as deliveries so FIFO rebuilds keep the allocation; the new lots keep the
+/// original purchase dates.
 #[command]
 pub fn apply_spin_off(request: ApplySpinOffRequest) -> Result<CorporateActionResult, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
@@ -398,129 +895,131 @@ pub fn apply_spin_off(request: ApplySpinOffRequest) -> Result<CorporateActionRes
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
-    // Get current holdings in source security
-    let mut stmt = conn
-        .prepare(
-            r#"
-            SELECT owner_id, SUM(CASE
-                WHEN txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN shares
-                WHEN txn_type IN ('SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND') THEN -shares
-                ELSE 0
-            END) as net_shares
-            FROM pp_txn
-            WHERE security_id = ? AND owner_type = 'portfolio' AND date <= ?
-            GROUP BY owner_id
-            HAVING net_shares > 0
-            "#,
-        )
-        .map_err(|e| e.to_string())?;
+    let cost_allocation = match request.cost_allocation_percent {
+        Some(percent) if (0.0..=100.0).contains(&percent) => percent / 100.0,
+        Some(percent) => return Err(format!("Invalid cost allocation: {}%", percent)),
+        None => {
+            let parent_price = price_on_or_before(conn, request.source_security_id, &request.effective_date);
+            let child_price = price_on_or_before(conn, request.target_security_id, &request.effective_date);
+            match (parent_price, child_price) {
+                (Some(parent), Some(child)) => fair_value_allocation(parent, child, request.share_ratio)
+                    .ok_or_else(|| "Cannot derive cost allocation from prices".to_string())?,
+                _ => {
+                    return Err(format!(
+                        "No prices on {} for a fair value allocation, please specify the cost allocation",
+                        request.effective_date
+                    ))
+                }
+            }
+        }
+    };
 
-    let holdings: Vec<(i64, i64)> = stmt
-        .query_map(
-            rusqlite::params![request.source_security_id, request.effective_date],
-            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
-        )
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+    // All-or-nothing: bookings, lots and the undo snapshot are written together
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    // Make sure the parent lots reflect all transactions
+    crate::fifo::build_fifo_lots(conn, request.source_security_id).map_err(|e| e.to_string())?;
+    let parent_lots = load_open_lots(conn, request.source_security_id, &request.effective_date)?;
 
     let import_id: i64 = conn
         .query_row("SELECT id FROM pp_import ORDER BY id DESC LIMIT 1", [], |r| r.get(0))
         .unwrap_or(1);
 
-    let mut transactions_created = 0i64;
-
-    for (portfolio_id, source_shares) in holdings {
-        // Calculate new shares based on ratio
-        let new_shares = (shares::to_decimal(source_shares) * request.share_ratio * 100_000_000.0) as i64;
-
-        if new_shares > 0 {
-            // Create DELIVERY_INBOUND transaction for new security
-            let uuid = uuid::Uuid::new_v4().to_string();
-            let _ = conn.execute(
-                r#"
-                INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-                VALUES (?, ?, 'portfolio', ?, ?, 'DELIVERY_INBOUND', ?, 0, 'EUR', ?, ?)
-                "#,
-                rusqlite::params![
-                    import_id,
-                    uuid,
-                    portfolio_id,
-                    request.target_security_id,
-                    request.effective_date,
-                    new_shares,
-                    request.note.as_deref().unwrap_or("Spin-off")
-                ],
-            );
-            transactions_created += 1;
-
-            // Optionally adjust cost basis (create FIFO lot with allocated cost)
-            if request.cost_allocation > 0.0 {
-                // Get original cost basis from source security FIFO lots
-                let source_cost: i64 = conn
-                    .query_row(
-                        "SELECT COALESCE(SUM(gross_amount), 0) FROM pp_fifo_lot WHERE security_id = ? AND portfolio_id = ?",
-                        rusqlite::params![request.source_security_id, portfolio_id],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-
-                let allocated_cost = (source_cost as f64 * request.cost_allocation) as i64;
-                // net_amount is same as gross for spin-offs (no fees)
-                let net_cost = allocated_cost;
-
-                // Get the transaction ID we just created
-                let txn_id: i64 = conn
-                    .query_row(
-                        "SELECT id FROM pp_txn WHERE uuid = ?",
-                        rusqlite::params![uuid],
-                        |row| row.get(0),
-                    )
-                    .map_err(|e| e.to_string())?;
-
-                let _ = conn.execute(
-                    r#"
-                    INSERT INTO pp_fifo_lot (security_id, portfolio_id, purchase_txn_id, purchase_date, original_shares, remaining_shares, gross_amount, net_amount, currency)
-                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'EUR')
-                    "#,
-                    rusqlite::params![
-                        request.target_security_id,
-                        portfolio_id,
-                        txn_id,
-                        request.effective_date,
-                        new_shares,
-                        new_shares,
-                        allocated_cost,
-                        net_cost
-                    ],
-                );
-
-                // Reduce cost basis in source security FIFO lots proportionally
-                let reduction_factor = 1.0 - request.cost_allocation;
-                let _ = conn.execute(
-                    r#"
-                    UPDATE pp_fifo_lot
-                    SET gross_amount = CAST(gross_amount * ? AS INTEGER),
-                        net_amount = CAST(net_amount * ? AS INTEGER)
-                    WHERE security_id = ? AND portfolio_id = ?
-                    "#,
-                    rusqlite::params![
-                        reduction_factor,
-                        reduction_factor,
-                        request.source_security_id,
-                        portfolio_id
-                    ],
-                );
+    let note = request.note.as_deref().unwrap_or("Spin-off");
+    let mut portfolio_ids: Vec<i64> = parent_lots.iter().map(|l| l.portfolio_id).collect();
+    portfolio_ids.dedup();
+
+    let mut created_txn_ids = Vec::new();
+    let mut fifo_lots_adjusted = 0i64;
+
+    for portfolio_id in portfolio_ids {
+        let lots: Vec<&OpenLot> = parent_lots.iter().filter(|l| l.portfolio_id == portfolio_id).collect();
+
+        // 1. Deliver the parent lots out at their full cost basis...
+        created_txn_ids.push(insert_action_txn(conn, import_id, &request.effective_date, &ActionTxn {
+            owner_type: "portfolio",
+            owner_id: portfolio_id,
+            security_id: request.source_security_id,
+            txn_type: "DELIVERY_OUTBOUND",
+            amount: lots.iter().map(|l| l.net_amount).sum(),
+            currency: &lots[0].currency,
+            shares: Some(lots.iter().map(|l| l.shares).sum()),
+            note,
+            acquisition_date: None,
+        })?);
+
+        for lot in lots {
+            let split = split_lot_basis(lot.gross_amount, lot.net_amount, cost_allocation);
+
+            // 2. ...and back in with the retained cost basis
+            created_txn_ids.push(insert_delivery_with_basis(conn, import_id, &request.effective_date, &ActionTxn {
+                owner_type: "portfolio",
+                owner_id: portfolio_id,
+                security_id: request.source_security_id,
+                txn_type: "DELIVERY_INBOUND",
+                amount: split.retained_net,
+                currency: &lot.currency,
+                shares: Some(lot.shares),
+                note,
+                acquisition_date: Some(&lot.purchase_date),
+            }, split.retained_gross)?);
+
+            // 3. New lot of the spun-off security with the allocated cost basis
+            let new_shares = convert_lot_shares(lot.shares, request.share_ratio);
+            if new_shares > 0 {
+                created_txn_ids.push(insert_delivery_with_basis(conn, import_id, &request.effective_date, &ActionTxn {
+                    owner_type: "portfolio",
+                    owner_id: portfolio_id,
+                    security_id: request.target_security_id,
+                    txn_type: "DELIVERY_INBOUND",
+                    amount: split.allocated_net,
+                    currency: &lot.currency,
+                    shares: Some(new_shares),
+                    note,
+                    acquisition_date: Some(&lot.purchase_date),
+                }, split.allocated_gross)?);
             }
+            fifo_lots_adjusted += 1;
         }
     }
 
+    // Rebuild FIFO lots for both securities from the new deliveries
+    for security_id in [request.source_security_id, request.target_security_id] {
+        crate::fifo::build_fifo_lots(conn, security_id).map_err(|e| e.to_string())?;
+    }
+
+    let action_id = record_applied_action(conn, &AppliedAction {
+        action_type: "SPINOFF",
+        security_id: request.source_security_id,
+        successor_security_id: request.target_security_id,
+        effective_date: &request.effective_date,
+        note: request.note.as_deref(),
+        created_txn_ids: &created_txn_ids,
+        lots: &parent_lots,
+    })?;
+
+    record_audit(
+        conn,
+        "apply_spin_off",
+        "corporate_action",
+        Some(action_id),
+        &format!(
+            "Spin-off von Wertpapier {} nach {} zum {}",
+            request.source_security_id, request.target_security_id, request.effective_date
+        ),
+    );
+    tx.commit().map_err(|e| e.to_string())?;
+
     Ok(CorporateActionResult {
         success: true,
-        message: "Spin-off applied successfully".to_string(),
-        transactions_adjusted: transactions_created,
-        fifo_lots_adjusted: transactions_created, // Same count for lots created
+        message: format!(
+            "Spin-off applied successfully ({:.1}% cost basis allocated)",
+            cost_allocation * 100.0
+        ),
+        transactions_adjusted: created_txn_ids.len() as i64,
+        fifo_lots_adjusted,
         prices_adjusted: 0,
+        action_id: Some(action_id),
     })
 }
 
@@ -620,20 +1119,46 @@ pub fn preview_merger(
         })
         .map_err(|e| e.to_string())?;
 
+    let source_lots = load_open_lots(conn, source_security_id, &effective_date)?;
+    let target_price = price_on_or_before(conn, target_security_id, &effective_date);
+
     let mut affected_portfolios = Vec::new();
+    let mut lots = Vec::new();
     let mut total_source_shares = 0.0;
     let mut total_target_shares = 0.0;
     let mut total_cash = 0.0;
+    let mut total_cash_in_lieu = 0.0;
 
     for row in rows.flatten() {
         let (portfolio_id, portfolio_name, shares_raw) = row;
         let source_shares = shares::to_decimal(shares_raw);
-        let target_shares = source_shares * share_ratio;
         let cash_received = source_shares * cash_per_share;
 
+        // Per-lot conversion keeps the cost basis of each lot
+        let mut target_shares_raw = 0;
+        for lot in source_lots.iter().filter(|l| l.portfolio_id == portfolio_id) {
+            let lot_target_shares = convert_lot_shares(lot.shares, share_ratio);
+            target_shares_raw += lot_target_shares;
+            lots.push(MergerLotConversion {
+                lot_id: lot.lot_id,
+                portfolio_id,
+                purchase_date: lot.purchase_date.clone(),
+                source_shares: shares::to_decimal(lot.shares),
+                target_shares: shares::to_decimal(lot_target_shares),
+                gross_cost_basis: lot.gross_amount as f64 / 100.0,
+                net_cost_basis: lot.net_amount as f64 / 100.0,
+                currency: lot.currency.clone(),
+            });
+        }
+
+        let target_shares = shares::to_decimal(target_shares_raw);
+        let fractional_shares = shares::to_decimal(fractional_merger_shares(target_shares_raw));
+        let cash_in_lieu = fractional_shares * target_price.unwrap_or(0.0);
+
         total_source_shares += source_shares;
         total_target_shares += target_shares;
         total_cash += cash_received;
+        total_cash_in_lieu += cash_in_lieu;
 
         affected_portfolios.push(MergerAffectedPortfolio {
             portfolio_id,
@@ -641,6 +1166,8 @@ pub fn preview_merger(
             source_shares,
             target_shares,
             cash_received,
+            fractional_shares,
+            cash_in_lieu,
         });
     }
 
@@ -651,15 +1178,19 @@ pub fn preview_merger(
         share_ratio,
         cash_per_share,
         affected_portfolios,
+        lots,
+        target_price,
         total_source_shares,
         total_target_shares,
         total_cash,
+        total_cash_in_lieu,
     })
 }
 
 /// Apply a merger/acquisition
-/// Creates SELL for source and BUY for target (stock component)
-/// Plus cash dividend if cash component exists
+/// Delivers the source lots out and the converted lots in, each lot with its
+/// original gross/net cost basis and purchase date. Fractional target shares
+/// are sold for cash (cash-in-lieu), a cash component is booked as dividend.
 #[command]
 pub fn apply_merger(request: ApplyMergerRequest) -> Result<CorporateActionResult, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
@@ -667,31 +1198,12 @@ pub fn apply_merger(request: ApplyMergerRequest) -> Result<CorporateActionResult
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
-    // Get holdings in source security
-    let mut stmt = conn
-        .prepare(
-            r#"
-            SELECT owner_id, SUM(CASE
-                WHEN txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN shares
-                WHEN txn_type IN ('SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND') THEN -shares
-                ELSE 0
-            END) as net_shares
-            FROM pp_txn
-            WHERE security_id = ? AND owner_type = 'portfolio' AND date <= ?
-            GROUP BY owner_id
-            HAVING net_shares > 0
-            "#,
-        )
-        .map_err(|e| e.to_string())?;
+    // All-or-nothing: bookings, lots and the undo snapshot are written together
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
 
-    let holdings: Vec<(i64, i64)> = stmt
-        .query_map(
-            rusqlite::params![request.source_security_id, request.effective_date],
-            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
-        )
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+    // Make sure the source lots reflect all transactions
+    crate::fifo::build_fifo_lots(conn, request.source_security_id).map_err(|e| e.to_string())?;
+    let source_lots = load_open_lots(conn, request.source_security_id, &request.effective_date)?;
 
     let import_id: i64 = conn
         .query_row("SELECT id FROM pp_import ORDER BY id DESC LIMIT 1", [], |r| r.get(0))
@@ -706,144 +1218,452 @@ pub fn apply_merger(request: ApplyMergerRequest) -> Result<CorporateActionResult
         )
         .unwrap_or_else(|_| "EUR".to_string());
 
-    let cash_currency = request.cash_currency.unwrap_or_else(|| source_currency.clone());
+    let target_currency: String = conn
+        .query_row(
+            "SELECT COALESCE(currency, 'EUR') FROM pp_security WHERE id = ?",
+            [request.target_security_id],
+            |row| row.get(0),
+        )
+        .unwrap_or_else(|_| "EUR".to_string());
+
+    let target_price = price_on_or_before(conn, request.target_security_id, &request.effective_date);
+    let cash_currency = request.cash_currency.clone().unwrap_or_else(|| source_currency.clone());
+
+    let mut portfolio_ids: Vec<i64> = source_lots.iter().map(|l| l.portfolio_id).collect();
+    portfolio_ids.dedup();
 
-    let mut transactions_created = 0i64;
+    let mut created_txn_ids = Vec::new();
     let mut fifo_lots_adjusted = 0i64;
 
-    for (portfolio_id, source_shares_raw) in holdings {
-        let source_shares = shares::to_decimal(source_shares_raw);
-        let target_shares_raw = (source_shares * request.share_ratio * 100_000_000.0) as i64;
-        let cash_amount = (source_shares * request.cash_per_share * 100.0) as i64;
+    for portfolio_id in portfolio_ids {
+        let lots: Vec<&OpenLot> = source_lots.iter().filter(|l| l.portfolio_id == portfolio_id).collect();
+        let source_shares_raw: i64 = lots.iter().map(|l| l.shares).sum();
+        let source_net: i64 = lots.iter().map(|l| l.net_amount).sum();
+        let cash_amount = (shares::to_decimal(source_shares_raw) * request.cash_per_share * 100.0) as i64;
+
+        // 1. Create DELIVERY_OUTBOUND for source shares (merger exchange, no gain realized)
+        created_txn_ids.push(insert_action_txn(conn, import_id, &request.effective_date, &ActionTxn {
+            owner_type: "portfolio",
+            owner_id: portfolio_id,
+            security_id: request.source_security_id,
+            txn_type: "DELIVERY_OUTBOUND",
+            amount: source_net,
+            currency: &source_currency,
+            shares: Some(source_shares_raw),
+            note: request.note.as_deref().unwrap_or("Merger - shares exchanged"),
+            acquisition_date: None,
+        })?);
+
+        // 2. One DELIVERY_INBOUND per lot, so FIFO rebuilds keep each lot's cost basis
+        let mut target_shares_raw = 0i64;
+        for lot in &lots {
+            let lot_target_shares = convert_lot_shares(lot.shares, request.share_ratio);
+            if lot_target_shares <= 0 {
+                continue;
+            }
+            target_shares_raw += lot_target_shares;
+
+            created_txn_ids.push(insert_delivery_with_basis(conn, import_id, &request.effective_date, &ActionTxn {
+                owner_type: "portfolio",
+                owner_id: portfolio_id,
+                security_id: request.target_security_id,
+                txn_type: "DELIVERY_INBOUND",
+                amount: lot.net_amount,
+                currency: &lot.currency,
+                shares: Some(lot_target_shares),
+                note: request.note.as_deref().unwrap_or("Merger - shares received"),
+                acquisition_date: Some(&lot.purchase_date),
+            }, lot.gross_amount)?);
+            fifo_lots_adjusted += 1;
+        }
 
-        // Get cost basis from source security
-        let source_cost: i64 = conn
+        // Get reference account for this portfolio
+        let account_id: Option<i64> = conn
             .query_row(
-                "SELECT COALESCE(SUM(gross_amount), 0) FROM pp_fifo_lot WHERE security_id = ? AND portfolio_id = ? AND remaining_shares > 0",
-                rusqlite::params![request.source_security_id, portfolio_id],
+                "SELECT reference_account_id FROM pp_portfolio WHERE id = ?",
+                [portfolio_id],
                 |row| row.get(0),
             )
-            .unwrap_or(0);
+            .ok()
+            .flatten();
 
-        // 1. Create DELIVERY_OUTBOUND for source shares (merger exchange)
-        let sell_uuid = uuid::Uuid::new_v4().to_string();
-        let _ = conn.execute(
-            r#"
-            INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-            VALUES (?, ?, 'portfolio', ?, ?, 'DELIVERY_OUTBOUND', ?, ?, ?, ?, ?)
-            "#,
-            rusqlite::params![
-                import_id,
-                sell_uuid,
-                portfolio_id,
-                request.source_security_id,
-                request.effective_date,
-                source_cost, // Use cost basis as amount
-                source_currency,
-                source_shares_raw,
-                request.note.as_deref().unwrap_or("Merger - shares exchanged")
-            ],
-        );
-        transactions_created += 1;
-
-        // 2. Create DELIVERY_INBOUND for target shares (stock component)
-        if target_shares_raw > 0 {
-            let buy_uuid = uuid::Uuid::new_v4().to_string();
-            let _ = conn.execute(
-                r#"
-                INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-                VALUES (?, ?, 'portfolio', ?, ?, 'DELIVERY_INBOUND', ?, ?, ?, ?, ?)
-                "#,
-                rusqlite::params![
-                    import_id,
-                    buy_uuid,
-                    portfolio_id,
-                    request.target_security_id,
-                    request.effective_date,
-                    source_cost, // Transfer cost basis to new shares
-                    source_currency,
-                    target_shares_raw,
-                    request.note.as_deref().unwrap_or("Merger - shares received")
-                ],
-            );
-            transactions_created += 1;
-
-            // Create FIFO lot for target shares with transferred cost basis
-            let txn_id: i64 = conn
-                .query_row(
-                    "SELECT id FROM pp_txn WHERE uuid = ?",
-                    rusqlite::params![buy_uuid],
-                    |row| row.get(0),
+        // 3. Sell fractional target shares for cash (cash-in-lieu)
+        let fractional_raw = fractional_merger_shares(target_shares_raw);
+        if let (Some(acc_id), Some(price)) = (account_id, target_price) {
+            let cash_in_lieu = (shares::to_decimal(fractional_raw) * price * 100.0).round() as i64;
+            if fractional_raw > 0 && cash_in_lieu > 0 {
+                let note = "Merger - cash in lieu of fractional shares";
+                let sale = ActionTxn {
+                    owner_type: "portfolio",
+                    owner_id: portfolio_id,
+                    security_id: request.target_security_id,
+                    txn_type: "SELL",
+                    amount: cash_in_lieu,
+                    currency: &target_currency,
+                    shares: Some(fractional_raw),
+                    note,
+                    acquisition_date: None,
+                };
+                let portfolio_txn_id = insert_action_txn(conn, import_id, &request.effective_date, &sale)?;
+                let account_txn_id = insert_action_txn(conn, import_id, &request.effective_date, &ActionTxn {
+                    owner_type: "account",
+                    owner_id: acc_id,
+                    ..sale
+                })?;
+                conn.execute(
+                    "INSERT INTO pp_cross_entry (entry_type, portfolio_txn_id, account_txn_id)
+                     VALUES ('BUY_SELL', ?1, ?2)",
+                    [portfolio_txn_id, account_txn_id],
                 )
                 .map_err(|e| e.to_string())?;
-
-            let _ = conn.execute(
-                r#"
-                INSERT INTO pp_fifo_lot (security_id, portfolio_id, purchase_txn_id, purchase_date, original_shares, remaining_shares, gross_amount, net_amount, currency)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-                "#,
-                rusqlite::params![
-                    request.target_security_id,
-                    portfolio_id,
-                    txn_id,
-                    request.effective_date,
-                    target_shares_raw,
-                    target_shares_raw,
-                    source_cost,
-                    source_cost,
-                    source_currency
-                ],
-            );
-            fifo_lots_adjusted += 1;
+                created_txn_ids.extend([portfolio_txn_id, account_txn_id]);
+            }
         }
 
-        // 3. Create cash dividend transaction if cash component exists
-        if cash_amount > 0 {
-            let dividend_uuid = uuid::Uuid::new_v4().to_string();
-
-            // Get reference account for this portfolio
-            let account_id: Option<i64> = conn
-                .query_row(
-                    "SELECT reference_account_id FROM pp_portfolio WHERE id = ?",
-                    [portfolio_id],
-                    |row| row.get(0),
-                )
-                .ok();
-
-            if let Some(acc_id) = account_id {
-                let _ = conn.execute(
-                    r#"
-                    INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, note)
-                    VALUES (?, ?, 'account', ?, ?, 'DIVIDENDS', ?, ?, ?, ?)
-                    "#,
-                    rusqlite::params![
-                        import_id,
-                        dividend_uuid,
-                        acc_id,
-                        request.source_security_id,
-                        request.effective_date,
-                        cash_amount,
-                        cash_currency,
-                        format!("Merger cash component - {}", request.note.as_deref().unwrap_or(""))
-                    ],
-                );
-                transactions_created += 1;
-            }
+        // 4. Create cash dividend transaction if cash component exists
+        if let (Some(acc_id), true) = (account_id, cash_amount > 0) {
+            created_txn_ids.push(insert_action_txn(conn, import_id, &request.effective_date, &ActionTxn {
+                owner_type: "account",
+                owner_id: acc_id,
+                security_id: request.source_security_id,
+                txn_type: "DIVIDENDS",
+                amount: cash_amount,
+                currency: &cash_currency,
+                shares: None,
+                note: &format!("Merger cash component - {}", request.note.as_deref().unwrap_or("")),
+                acquisition_date: None,
+            })?);
         }
+    }
 
-        // 4. Mark source FIFO lots as consumed
-        let _ = conn.execute(
-            "UPDATE pp_fifo_lot SET remaining_shares = 0 WHERE security_id = ? AND portfolio_id = ? AND remaining_shares > 0",
-            rusqlite::params![request.source_security_id, portfolio_id],
-        );
-        fifo_lots_adjusted += 1;
+    // Rebuild FIFO lots: source lots are consumed, target lots carry the cost basis
+    for security_id in [request.source_security_id, request.target_security_id] {
+        crate::fifo::build_fifo_lots(conn, security_id).map_err(|e| e.to_string())?;
     }
 
+    let action_id = record_applied_action(conn, &AppliedAction {
+        action_type: "MERGER",
+        security_id: request.source_security_id,
+        successor_security_id: request.target_security_id,
+        effective_date: &request.effective_date,
+        note: request.note.as_deref(),
+        created_txn_ids: &created_txn_ids,
+        lots: &source_lots,
+    })?;
+
+    record_audit(
+        conn,
+        "apply_merger",
+        "corporate_action",
+        Some(action_id),
+        &format!(
+            "Fusion von Wertpapier {} in {} zum {}",
+            request.source_security_id, request.target_security_id, request.effective_date
+        ),
+    );
+    tx.commit().map_err(|e| e.to_string())?;
+
     Ok(CorporateActionResult {
         success: true,
         message: "Merger applied successfully".to_string(),
-        transactions_adjusted: transactions_created,
+        transactions_adjusted: created_txn_ids.len() as i64,
         fifo_lots_adjusted,
         prices_adjusted: 0,
+        action_id: Some(action_id),
     })
 }
+
+/// Undo an applied merger (restores the source lots)
+#[command]
+pub fn undo_merger(action_id: i64) -> Result<CorporateActionResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let result = undo_applied_action(conn, action_id, "MERGER")?;
+    record_audit(conn, "undo_merger", "corporate_action", Some(action_id), "Fusion rückgängig gemacht");
+    Ok(result)
+}
+
+/// Undo an applied spin-off (restores the parent lots and their cost basis)
+#[command]
+pub fn undo_spin_off(action_id: i64) -> Result<CorporateActionResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let result = undo_applied_action(conn, action_id, "SPINOFF")?;
+    record_audit(conn, "undo_spin_off", "corporate_action", Some(action_id), "Spin-off rückgängig gemacht");
+    Ok(result)
+}
+
+/// Confirm a detected ISIN change and merge the old and new security
+#[command]
+pub fn confirm_isin_change(action_id: i64) -> Result<CorporateActionResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let result = merge_isin_change(conn, action_id)?;
+    record_audit(conn, "confirm_isin_change", "corporate_action", Some(action_id), "ISIN-Änderung bestätigt");
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_lot_shares() {
+        // 0.75 new shares per old share: 10 -> 7.5
+        let target = convert_lot_shares(10 * 100_000_000, 0.75);
+        assert_eq!(target, 750_000_000);
+        assert_eq!(fractional_merger_shares(target), 50_000_000);
+
+        // Fractions of several lots add up before cash-in-lieu
+        let total = convert_lot_shares(3 * 100_000_000, 0.5) + convert_lot_shares(100_000_000, 0.5);
+        assert_eq!(total, 200_000_000);
+        assert_eq!(fractional_merger_shares(total), 0);
+    }
+
+    #[test]
+    fn test_spin_off_cost_allocation() {
+        // Parent 90 €, 1 new share at 10 € per parent share -> 10% to the spin-off
+        let allocation = fair_value_allocation(90.0, 10.0, 1.0).unwrap();
+        assert!((allocation - 0.10).abs() < 1e-9);
+        // 0.5 new shares at 20 € -> same value split
+        assert!((fair_value_allocation(90.0, 20.0, 0.5).unwrap() - 0.10).abs() < 1e-9);
+        assert_eq!(fair_value_allocation(0.0, 0.0, 1.0), None);
+
+        let split = split_lot_basis(100_050, 100_000, 0.10);
+        assert_eq!(split.allocated_gross, 10_005);
+        assert_eq!(split.allocated_net, 10_000);
+        assert_eq!(split.retained_gross + split.allocated_gross, 100_050);
+        assert_eq!(split.retained_net, 90_000);
+    }
+
+    fn setup_action_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_txn (
+                id INTEGER PRIMARY KEY, import_id INTEGER, uuid TEXT, owner_type TEXT, owner_id INTEGER,
+                security_id INTEGER, txn_type TEXT, date TEXT, amount INTEGER, currency TEXT,
+                shares INTEGER, note TEXT, cross_entry_id INTEGER, acquisition_date TEXT
+            );
+            CREATE TABLE pp_txn_unit (txn_id INTEGER, unit_type TEXT, amount INTEGER, currency TEXT);
+            CREATE TABLE pp_cross_entry (
+                id INTEGER PRIMARY KEY, entry_type TEXT, portfolio_txn_id INTEGER,
+                account_txn_id INTEGER, from_txn_id INTEGER
+            );
+            CREATE TABLE pp_fifo_lot (
+                id INTEGER PRIMARY KEY, security_id INTEGER, portfolio_id INTEGER, purchase_txn_id INTEGER,
+                purchase_date TEXT, original_shares INTEGER, remaining_shares INTEGER,
+                gross_amount INTEGER, net_amount INTEGER, currency TEXT
+            );
+            CREATE TABLE pp_fifo_consumption (
+                id INTEGER PRIMARY KEY, lot_id INTEGER, sale_txn_id INTEGER, shares_consumed INTEGER,
+                gross_amount INTEGER, net_amount INTEGER, fx_gain INTEGER, price_gain INTEGER
+            );
+            CREATE TABLE pp_corporate_action (
+                id INTEGER PRIMARY KEY, security_id INTEGER, action_type TEXT, effective_date TEXT,
+                successor_security_id INTEGER, source TEXT, is_applied INTEGER, is_confirmed INTEGER, note TEXT
+            );
+            CREATE TABLE pp_corporate_action_snapshot (action_id INTEGER PRIMARY KEY, txn_ids TEXT, lots TEXT);
+
+            -- 10 shares for 1000 € + 5 € fees
+            INSERT INTO pp_txn (id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares)
+            VALUES (1, 'buy-1', 'portfolio', 1, 1, 'BUY', '2020-01-15', 100000, 'EUR', 1000000000);
+            INSERT INTO pp_txn_unit VALUES (1, 'FEE', 500, 'EUR');
+            "#,
+        )
+        .unwrap();
+        crate::fifo::build_fifo_lots(&conn, 1).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_undo_restores_source_lots() {
+        let conn = setup_action_db();
+        let lots = load_open_lots(&conn, 1, "2024-06-01").unwrap();
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].gross_amount, 100_500);
+
+        // Exchange into security 2 with carried cost basis
+        let lot = &lots[0];
+        let out_id = insert_action_txn(&conn, 1, "2024-06-01", &ActionTxn {
+            owner_type: "portfolio",
+            owner_id: 1,
+            security_id: 1,
+            txn_type: "DELIVERY_OUTBOUND",
+            amount: lot.net_amount,
+            currency: "EUR",
+            shares: Some(lot.shares),
+            note: "Merger",
+            acquisition_date: None,
+        })
+        .unwrap();
+        let in_id = insert_delivery_with_basis(&conn, 1, "2024-06-01", &ActionTxn {
+            owner_type: "portfolio",
+            owner_id: 1,
+            security_id: 2,
+            txn_type: "DELIVERY_INBOUND",
+            amount: lot.net_amount,
+            currency: "EUR",
+            shares: Some(convert_lot_shares(lot.shares, 0.75)),
+            note: "Merger",
+            acquisition_date: Some(&lot.purchase_date),
+        }, lot.gross_amount)
+        .unwrap();
+        crate::fifo::build_fifo_lots(&conn, 1).unwrap();
+        crate::fifo::build_fifo_lots(&conn, 2).unwrap();
+
+        let target_lots = load_open_lots(&conn, 2, "2024-06-01").unwrap();
+        assert_eq!(target_lots[0].gross_amount, 100_500);
+        // Holding period continues from the original purchase
+        assert_eq!(target_lots[0].purchase_date, "2020-01-15");
+        assert_eq!(target_lots[0].net_amount, 100_000);
+        assert!(load_open_lots(&conn, 1, "2024-06-01").unwrap().is_empty());
+
+        let action_id = record_applied_action(&conn, &AppliedAction {
+            action_type: "MERGER",
+            security_id: 1,
+            successor_security_id: 2,
+            effective_date: "2024-06-01",
+            note: None,
+            created_txn_ids: &[out_id, in_id],
+            lots: &lots,
+        })
+        .unwrap();
+
+        // Wrong command for the action type
+        assert!(undo_applied_action(&conn, action_id, "SPINOFF").is_err());
+
+        // A later sale of the merger target blocks the undo and leaves everything in place
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares)
+             VALUES (99, 'sell-2', 'portfolio', 1, 2, 'SELL', '2024-07-01', 50000, 'EUR', 300000000)",
+            [],
+        )
+        .unwrap();
+        let err = undo_applied_action(&conn, action_id, "MERGER").unwrap_err();
+        assert!(err.contains("later transaction"), "{}", err);
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_txn WHERE id IN (?1, ?2)", [out_id, in_id], |r| r.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2);
+        conn.execute("DELETE FROM pp_txn WHERE id = 99", []).unwrap();
+
+        let result = undo_applied_action(&conn, action_id, "MERGER").unwrap();
+        assert_eq!(result.transactions_adjusted, 2);
+
+        let restored = load_open_lots(&conn, 1, "2024-06-01").unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].shares, 1_000_000_000);
+        assert_eq!(restored[0].gross_amount, 100_500);
+        assert!(load_open_lots(&conn, 2, "2024-06-01").unwrap().is_empty());
+
+        // Already undone
+        assert!(undo_applied_action(&conn, action_id, "MERGER").is_err());
+    }
+
+    #[test]
+    fn test_merge_isin_change() {
+        let conn = setup_action_db();
+        conn.execute_batch(
+            r#"
+            ALTER TABLE pp_corporate_action ADD COLUMN old_identifier TEXT;
+            ALTER TABLE pp_corporate_action ADD COLUMN new_identifier TEXT;
+            CREATE TABLE pp_security (
+                id INTEGER PRIMARY KEY, uuid TEXT, name TEXT, isin TEXT, is_retired INTEGER DEFAULT 0, note TEXT,
+                updated_at TEXT
+            );
+            CREATE TABLE pp_security_event (id INTEGER PRIMARY KEY, security_id INTEGER, date TEXT);
+            CREATE TABLE pp_watchlist_security (watchlist_id INTEGER, security_id INTEGER, UNIQUE(watchlist_id, security_id));
+            CREATE TABLE pp_classification_assignment (classification_id INTEGER, vehicle_type TEXT, vehicle_uuid TEXT);
+            CREATE TABLE pp_price (security_id INTEGER, date TEXT, value INTEGER, source TEXT, UNIQUE(security_id, date));
+            CREATE TABLE pp_latest_price (
+                security_id INTEGER PRIMARY KEY, date TEXT, value INTEGER, high INTEGER, low INTEGER,
+                volume INTEGER, updated_at TEXT
+            );
+            INSERT INTO pp_security (id, uuid, name, isin) VALUES (1, 's1', 'Old', 'DE0006483001'), (2, 's2', 'New', 'IE00BZ12WP82');
+            INSERT INTO pp_watchlist_security VALUES (1, 1), (1, 2), (2, 2);
+            INSERT INTO pp_classification_assignment VALUES (7, 'security', 's2');
+            INSERT INTO pp_price (security_id, date, value) VALUES (1, '2023-02-28', 90), (1, '2023-03-01', 91), (2, '2023-03-01', 95), (2, '2023-03-02', 96);
+            INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2023-03-01', 91), (2, '2023-03-02', 96);
+
+            -- Later purchase was booked on the new security
+            INSERT INTO pp_txn (id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares)
+            VALUES (2, 'buy-2', 'portfolio', 1, 2, 'BUY', '2023-04-01', 50000, 'EUR', 500000000);
+
+            INSERT INTO pp_corporate_action
+                (id, security_id, action_type, effective_date, successor_security_id, source, is_applied, is_confirmed,
+                 old_identifier, new_identifier)
+            VALUES (1, 1, 'ISIN_CHANGE', '2023-03-01', 2, 'DETECTED', 0, 0, 'DE0006483001', ' ie00bz12wp82');
+            "#,
+        )
+        .unwrap();
+        crate::fifo::build_fifo_lots(&conn, 2).unwrap();
+
+        let result = merge_isin_change(&conn, 1).unwrap();
+        assert_eq!(result.transactions_adjusted, 1);
+        assert_eq!(result.fifo_lots_adjusted, 2);
+
+        let lots = load_open_lots(&conn, 1, "2024-01-01").unwrap();
+        assert_eq!(lots.iter().map(|l| l.shares).sum::<i64>(), 1_500_000_000);
+        assert!(load_open_lots(&conn, 2, "2024-01-01").unwrap().is_empty());
+
+        // Successor prices win from the effective date on
+        let prices: Vec<(String, i64)> = conn
+            .prepare("SELECT date, value FROM pp_price WHERE security_id = 1 ORDER BY date")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            prices,
+            vec![
+                ("2023-02-28".to_string(), 90),
+                ("2023-03-01".to_string(), 95),
+                ("2023-03-02".to_string(), 96)
+            ]
+        );
+        let latest: i64 = conn
+            .query_row("SELECT value FROM pp_latest_price WHERE security_id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(latest, 96);
+
+        let (isin, retired): (String, i32) = conn
+            .query_row("SELECT isin, (SELECT is_retired FROM pp_security WHERE id = 2) FROM pp_security WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(isin, "IE00BZ12WP82");
+        assert_eq!(retired, 1);
+
+        // Other references follow the old security
+        let watchlists: Vec<i64> = conn
+            .prepare("SELECT watchlist_id FROM pp_watchlist_security WHERE security_id = 1 ORDER BY watchlist_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(watchlists, vec![1, 2]);
+        let classified: String = conn
+            .query_row("SELECT vehicle_uuid FROM pp_classification_assignment", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(classified, "s1");
+        let successor: i64 = conn
+            .query_row("SELECT successor_security_id FROM pp_corporate_action WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(successor, 2);
+
+        // Already applied
+        assert!(merge_isin_change(&conn, 1).is_err());
+    }
+}
@@ -1,10 +1,14 @@
 //! CRUD commands for managing securities, accounts, portfolios, and transactions.
 
+use crate::commands::audit::record_audit;
 use crate::db;
-use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::events::{
+    emit_data_changed, emit_transactions_changed, DataChangedPayload, TransactionsChangedPayload,
+};
 use crate::quotes::{self, yahoo, ProviderType};
 use chrono::{Datelike, NaiveDate};
-use rusqlite::params;
+use std::collections::{BTreeSet, HashMap};
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use tauri::{command, AppHandle};
 use uuid::Uuid;
@@ -50,6 +54,12 @@ pub struct UpdateSecurityRequest {
     pub is_retired: Option<bool>,
     pub attributes: Option<std::collections::HashMap<String, String>>,
     pub properties: Option<std::collections::HashMap<String, String>>,
+    /// ISO country code of the issuer (for withholding tax), "" = derive from ISIN
+    pub domicile: Option<String>,
+    /// Investment fund type for the Teilfreistellung (EQUITY, MIXED, ...), "" = no fund
+    pub fund_type: Option<String>,
+    /// Share (Aktie) whose sales belong to the stock loss pot
+    pub is_stock: Option<bool>,
 }
 
 /// Security data returned after create/update
@@ -75,7 +85,7 @@ pub struct SecurityResult {
 }
 
 /// Validate ISIN checksum (ISO 7812)
-fn validate_isin(isin: &str) -> bool {
+pub(crate) fn validate_isin(isin: &str) -> bool {
     if isin.len() != 12 {
         return false;
     }
@@ -178,6 +188,7 @@ pub fn create_security(data: CreateSecurityRequest) -> Result<SecurityResult, St
     .map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid();
+    record_audit(conn, "create_security", "security", Some(id), &format!("Wertpapier {} angelegt", data.name));
 
     Ok(SecurityResult {
         id,
@@ -414,6 +425,13 @@ fn create_security_internal(data: &CreateSecurityRequest) -> Result<SecurityResu
     .map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid();
+    record_audit(
+        conn,
+        "create_security_with_history",
+        "security",
+        Some(id),
+        &format!("Wertpapier {} angelegt", data.name),
+    );
 
     Ok(SecurityResult {
         id,
@@ -611,6 +629,38 @@ pub fn update_security(id: i64, data: UpdateSecurityRequest) -> Result<SecurityR
         None => current_properties,
     };
 
+    if let Some(ref domicile) = data.domicile {
+        let domicile = domicile.trim().to_uppercase();
+        if !domicile.is_empty() && (domicile.len() != 2 || !domicile.chars().all(|c| c.is_ascii_alphabetic())) {
+            return Err(format!("Ungültiger Ländercode: {}", domicile));
+        }
+        conn.execute(
+            "UPDATE pp_security SET domicile = ?1 WHERE id = ?2",
+            params![if domicile.is_empty() { None } else { Some(domicile) }, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(ref fund_type) = data.fund_type {
+        let fund_type = fund_type.trim().to_uppercase();
+        if !fund_type.is_empty() && crate::tax::vorabpauschale::teilfreistellung_rate(&fund_type).is_none() {
+            return Err(format!("Unbekannter Fondstyp: {}", fund_type));
+        }
+        conn.execute(
+            "UPDATE pp_security SET fund_type = ?1 WHERE id = ?2",
+            params![if fund_type.is_empty() { None } else { Some(fund_type) }, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(is_stock) = data.is_stock {
+        conn.execute(
+            "UPDATE pp_security SET is_stock = ?1 WHERE id = ?2",
+            params![is_stock, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
     conn.execute(
         r#"
         UPDATE pp_security
@@ -622,6 +672,7 @@ pub fn update_security(id: i64, data: UpdateSecurityRequest) -> Result<SecurityR
         params![name, currency, target_currency, isin, wkn, ticker, feed, feed_url, latest_feed, latest_feed_url, note, is_retired, attributes, properties, now, id],
     )
     .map_err(|e| e.to_string())?;
+    record_audit(conn, "update_security", "security", Some(id), &format!("Wertpapier {} geändert", name));
 
     Ok(SecurityResult {
         id,
@@ -706,6 +757,7 @@ pub fn delete_security(id: i64) -> Result<(), String> {
     if rows == 0 {
         return Err(format!("Security with id {} not found", id));
     }
+    record_audit(conn, "delete_security", "security", Some(id), "Wertpapier gelöscht");
 
     Ok(())
 }
@@ -861,6 +913,7 @@ pub fn create_account(data: CreateAccountRequest) -> Result<AccountResult, Strin
     .map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid();
+    record_audit(conn, "create_account", "account", Some(id), &format!("Konto {} angelegt", data.name));
 
     Ok(AccountResult {
         id,
@@ -907,6 +960,7 @@ pub fn update_account(id: i64, data: UpdateAccountRequest) -> Result<AccountResu
         params![name, currency, note, is_retired, now, id],
     )
     .map_err(|e| e.to_string())?;
+    record_audit(conn, "update_account", "account", Some(id), &format!("Konto {} geändert", name));
 
     Ok(AccountResult {
         id,
@@ -965,6 +1019,7 @@ pub fn delete_account(id: i64) -> Result<(), String> {
     if rows == 0 {
         return Err(format!("Account with id {} not found", id));
     }
+    record_audit(conn, "delete_account", "account", Some(id), "Konto gelöscht");
 
     Ok(())
 }
@@ -1040,6 +1095,7 @@ pub fn create_pp_portfolio_new(data: CreatePortfolioRequest) -> Result<Portfolio
     .map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid();
+    record_audit(conn, "create_pp_portfolio_new", "portfolio", Some(id), &format!("Depot {} angelegt", data.name));
 
     Ok(PortfolioResult {
         id,
@@ -1101,6 +1157,7 @@ pub fn update_pp_portfolio(id: i64, data: UpdatePortfolioRequest) -> Result<Port
         params![name, reference_account_id, note, is_retired, now, id],
     )
     .map_err(|e| e.to_string())?;
+    record_audit(conn, "update_pp_portfolio", "portfolio", Some(id), &format!("Depot {} geändert", name));
 
     Ok(PortfolioResult {
         id,
@@ -1159,6 +1216,7 @@ pub fn delete_pp_portfolio(id: i64) -> Result<(), String> {
     if rows == 0 {
         return Err(format!("Portfolio with id {} not found", id));
     }
+    record_audit(conn, "delete_pp_portfolio", "portfolio", Some(id), "Depot gelöscht");
 
     Ok(())
 }
@@ -1195,6 +1253,8 @@ pub struct CreateTransactionRequest {
     pub units: Option<Vec<TransactionUnitData>>,
     // For portfolio BUY/SELL: reference account for the cash transaction
     pub reference_account_id: Option<i64>,
+    // Client-generated key: a retried create returns the existing transaction
+    pub idempotency_key: Option<String>,
 }
 
 /// Transaction data returned after create/update
@@ -1219,18 +1279,210 @@ pub struct TransactionResult {
 const SHARES_SCALE: f64 = 100_000_000.0;
 const AMOUNT_SCALE: f64 = 100.0;
 
+/// Columns read by `transaction_result_from_row`
+const TRANSACTION_RESULT_COLUMNS: &str =
+    "id, uuid, owner_type, owner_id, txn_type, date, amount, currency, shares, security_id, note, cross_entry_id";
+
+fn transaction_result_from_row(row: &rusqlite::Row) -> rusqlite::Result<TransactionResult> {
+    let amount_cents: i64 = row.get(6)?;
+    let shares_raw: Option<i64> = row.get(8)?;
+
+    Ok(TransactionResult {
+        id: row.get(0)?,
+        uuid: row.get(1)?,
+        owner_type: row.get(2)?,
+        owner_id: row.get(3)?,
+        txn_type: row.get(4)?,
+        date: row.get(5)?,
+        amount: amount_cents as f64 / AMOUNT_SCALE,
+        currency: row.get(7)?,
+        shares: shares_raw.map(|s| s as f64 / SHARES_SCALE),
+        security_id: row.get(9)?,
+        note: row.get(10)?,
+        cross_entry_id: row.get(11)?,
+    })
+}
+
+/// Transaction created earlier with the same idempotency key
+fn find_transaction_by_idempotency_key(
+    conn: &rusqlite::Connection,
+    key: &str,
+) -> Result<Option<TransactionResult>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM pp_txn WHERE idempotency_key = ?1",
+            TRANSACTION_RESULT_COLUMNS
+        ),
+        params![key],
+        transaction_result_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Whether a transaction with this idempotency key already exists
+pub(crate) fn idempotency_key_exists(conn: &rusqlite::Connection, key: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pp_txn WHERE idempotency_key = ?1)",
+        params![key],
+        |row| row.get(0),
+    )
+}
+
+/// Stable idempotency keys for bulk imports, derived from the transaction content
+///
+/// Identical rows within one import get consecutive occurrence numbers, so all
+/// of them are imported while importing the same data again skips them.
+pub(crate) struct ImportIdempotencyKeys {
+    source: &'static str,
+    seen: HashMap<String, usize>,
+}
+
+impl ImportIdempotencyKeys {
+    pub(crate) fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Key for the next transaction with this content
+    pub(crate) fn next(&mut self, content: &str) -> String {
+        use sha1::{Digest, Sha1};
+
+        let occurrence = self.seen.entry(content.to_string()).or_insert(0);
+        *occurrence += 1;
+
+        let mut hasher = Sha1::new();
+        hasher.update(content.as_bytes());
+        hasher.update(format!("#{}", occurrence).as_bytes());
+        let digest: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("{}:{}", self.source, digest)
+    }
+}
+
+/// Portfolios and securities whose derived data depends on changed transactions
+#[derive(Debug, Default)]
+pub(crate) struct AffectedByTransactions {
+    pub(crate) portfolio_ids: BTreeSet<i64>,
+    pub(crate) security_ids: BTreeSet<i64>,
+}
+
+impl AffectedByTransactions {
+    /// Add the portfolio transactions among `txn_ids` (in their current state)
+    pub(crate) fn add(&mut self, conn: &rusqlite::Connection, txn_ids: &[i64]) -> Result<(), String> {
+        if txn_ids.is_empty() {
+            return Ok(());
+        }
+        let placeholders = txn_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT owner_id, security_id FROM pp_txn WHERE owner_type = 'portfolio' AND id IN ({})",
+            placeholders
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(txn_ids), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (portfolio_id, security_id) = row.map_err(|e| e.to_string())?;
+            self.portfolio_ids.insert(portfolio_id);
+            self.security_ids.extend(security_id);
+        }
+        Ok(())
+    }
+}
+
+/// Rebuild the FIFO lots of the securities touched by the given transactions
+///
+/// For bulk callers that created transactions with `skip_fifo_rebuild`.
+/// Returns the rebuilt security IDs.
+pub(crate) fn rebuild_fifo_for_transactions(txn_ids: &[i64]) -> Result<Vec<i64>, String> {
+    if txn_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let mut affected = AffectedByTransactions::default();
+    affected.add(conn, txn_ids)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for security_id in &affected.security_ids {
+        crate::fifo::build_fifo_lots(&tx, *security_id).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(affected.security_ids.into_iter().collect())
+}
+
+/// Refresh data derived from transactions, commit and emit `transactions-changed`
+///
+/// Rebuilds the FIFO lots of the affected securities (only those) in the same
+/// DB transaction as the change and drops the invested capital cache, so cost
+/// basis and valuations never use stale data. Bulk callers can skip the FIFO
+/// rebuild and run `rebuild_fifo_lots(security_ids)` once at the end.
+pub(crate) fn transactions_changed(
+    app: &AppHandle,
+    tx: rusqlite::Transaction,
+    action: &str,
+    affected: &AffectedByTransactions,
+    rebuild_fifo: bool,
+) -> Result<(), String> {
+    if rebuild_fifo {
+        for security_id in &affected.security_ids {
+            crate::fifo::build_fifo_lots(&tx, *security_id).map_err(|e| {
+                format!("Failed to rebuild FIFO lots for security {}: {}", security_id, e)
+            })?;
+        }
+    }
+    crate::commands::data::invalidate_invested_capital_cache(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    emit_transactions_changed(
+        app,
+        TransactionsChangedPayload {
+            action: action.to_string(),
+            portfolio_ids: affected.portfolio_ids.iter().copied().collect(),
+            security_ids: affected.security_ids.iter().copied().collect(),
+        },
+    );
+    Ok(())
+}
+
 /// Create a new transaction
 /// For portfolio BUY/SELL, also creates a matching account transaction and cross-entry
 #[command]
 pub fn create_transaction(
     app: AppHandle,
     data: CreateTransactionRequest,
+    skip_fifo_rebuild: Option<bool>,
 ) -> Result<TransactionResult, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    // Changes and FIFO rebuild are committed together (rolled back on error)
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let conn: &rusqlite::Connection = &tx;
+
+    // Retry or double-submit: return the transaction created the first time
+    if let Some(key) = &data.idempotency_key {
+        if let Some(existing) = find_transaction_by_idempotency_key(conn, key)? {
+            log::info!("create_transaction: key {} already used by transaction {}", key, existing.id);
+            return Ok(existing);
+        }
+    }
+
     // Validate owner exists
     let owner_exists: bool = if data.owner_type == "account" {
         conn.query_row(
@@ -1343,8 +1595,8 @@ pub fn create_transaction(
     // Create the main transaction
     conn.execute(
         r#"
-        INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, shares, security_id, note, cross_entry_id, updated_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+        INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, shares, security_id, note, cross_entry_id, updated_at, idempotency_key)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
         "#,
         params![
             uuid,
@@ -1359,6 +1611,7 @@ pub fn create_transaction(
             data.note,
             cross_entry_id,
             now,
+            data.idempotency_key,
         ],
     )
     .map_err(|e| e.to_string())?;
@@ -1396,15 +1649,28 @@ pub fn create_transaction(
         }
     }
 
-    // Rebuild FIFO lots if this affects a security in a portfolio
-    if data.owner_type == "portfolio" && data.security_id.is_some() {
-        let security_id = data.security_id.unwrap();
-        if let Err(e) = crate::fifo::build_fifo_lots(conn, security_id) {
-            log::warn!("Failed to rebuild FIFO lots: {}", e);
-        }
-    }
+    record_audit(
+        conn,
+        "create_transaction",
+        "transaction",
+        Some(id),
+        &format!(
+            "{} {} {:.2} {}{}",
+            data.txn_type,
+            data.date,
+            data.amount as f64 / AMOUNT_SCALE,
+            data.currency,
+            data.shares
+                .map(|s| format!(", {} Stück", s as f64 / SHARES_SCALE))
+                .unwrap_or_default()
+        ),
+    );
 
-    // Emit data changed event for frontend refresh
+    // Rebuild FIFO lots and caches, notify the frontend
+    let mut affected = AffectedByTransactions::default();
+    affected.add(conn, &[id])?;
+    let rebuild_fifo = !skip_fifo_rebuild.unwrap_or(false);
+    transactions_changed(&app, tx, "created", &affected, rebuild_fifo)?;
     emit_data_changed(&app, DataChangedPayload::transaction("created", data.security_id));
 
     Ok(TransactionResult {
@@ -1424,14 +1690,24 @@ pub fn create_transaction(
 }
 
 /// Delete a transaction
-/// Also deletes linked cross-entry and account transaction if applicable
+/// Also deletes linked cross-entry and account transaction if applicable.
+/// Moves them to the trash (`restore_transaction`) unless `permanent` is set.
 #[command]
-pub fn delete_transaction(app: AppHandle, id: i64) -> Result<(), String> {
+pub fn delete_transaction(
+    app: AppHandle,
+    id: i64,
+    skip_fifo_rebuild: Option<bool>,
+    permanent: Option<bool>,
+) -> Result<(), String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    // Changes and FIFO rebuild are committed together (rolled back on error)
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let conn: &rusqlite::Connection = &tx;
+
     // Get transaction info for FIFO rebuild
     let txn_info: Option<(String, i64, Option<i64>, Option<i64>)> = conn
         .query_row(
@@ -1441,9 +1717,27 @@ pub fn delete_transaction(app: AppHandle, id: i64) -> Result<(), String> {
         )
         .ok();
 
-    let (owner_type, _owner_id, security_id, cross_entry_id) =
+    let (_owner_type, _owner_id, security_id, cross_entry_id) =
         txn_info.ok_or_else(|| format!("Transaction with id {} not found", id))?;
 
+    let mut affected = AffectedByTransactions::default();
+    affected.add(conn, &[id])?;
+
+    if !permanent.unwrap_or(false) {
+        let mut trashed = vec![id];
+        if let Some(ce_id) = cross_entry_id {
+            let linked: Vec<i64> = conn
+                .prepare("SELECT id FROM pp_txn WHERE cross_entry_id = ?1 AND id != ?2")
+                .and_then(|mut stmt| {
+                    stmt.query_map(params![ce_id, id], |row| row.get(0))?
+                        .collect::<Result<_, _>>()
+                })
+                .map_err(|e| e.to_string())?;
+            trashed.extend(linked);
+        }
+        crate::commands::trash::store_in_trash(conn, &trashed)?;
+    }
+
     // Delete transaction units first
     conn.execute("DELETE FROM pp_txn_unit WHERE txn_id = ?1", params![id])
         .map_err(|e| e.to_string())?;
@@ -1470,6 +1764,8 @@ pub fn delete_transaction(app: AppHandle, id: i64) -> Result<(), String> {
 
         // Delete the other transaction's units and the transaction itself
         if let Some(other_id) = other_txn_id {
+            // Deleting the account side of a BUY/SELL also removes the portfolio side
+            affected.add(conn, &[other_id])?;
             conn.execute("DELETE FROM pp_txn_unit WHERE txn_id = ?1", params![other_id])
                 .map_err(|e| e.to_string())?;
             conn.execute("DELETE FROM pp_txn WHERE id = ?1", params![other_id])
@@ -1485,15 +1781,21 @@ pub fn delete_transaction(app: AppHandle, id: i64) -> Result<(), String> {
     conn.execute("DELETE FROM pp_txn WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
 
-    // Rebuild FIFO lots if this was a portfolio transaction with a security
-    if owner_type == "portfolio" && security_id.is_some() {
-        let sec_id = security_id.unwrap();
-        if let Err(e) = crate::fifo::build_fifo_lots(conn, sec_id) {
-            log::warn!("Failed to rebuild FIFO lots: {}", e);
-        }
-    }
+    record_audit(
+        conn,
+        "delete_transaction",
+        "transaction",
+        Some(id),
+        if permanent.unwrap_or(false) {
+            "Buchung endgültig gelöscht"
+        } else {
+            "Buchung in den Papierkorb verschoben"
+        },
+    );
 
-    // Emit data changed event for frontend refresh
+    // Rebuild FIFO lots and caches, notify the frontend
+    let rebuild_fifo = !skip_fifo_rebuild.unwrap_or(false);
+    transactions_changed(&app, tx, "deleted", &affected, rebuild_fifo)?;
     emit_data_changed(&app, DataChangedPayload::transaction("deleted", security_id));
 
     Ok(())
@@ -1514,8 +1816,13 @@ pub struct BulkDeleteResult {
 /// Delete multiple transactions at once
 /// Automatically deletes linked cross-entry transactions (e.g., account side of BUY/SELL)
 /// Rebuilds FIFO cost basis once per affected security
+/// Moves them to the trash (`restore_transaction`) unless `permanent` is set.
 #[command]
-pub fn delete_transactions_bulk(app: AppHandle, ids: Vec<i64>) -> Result<BulkDeleteResult, String> {
+pub fn delete_transactions_bulk(
+    app: AppHandle,
+    ids: Vec<i64>,
+    permanent: Option<bool>,
+) -> Result<BulkDeleteResult, String> {
     if ids.is_empty() {
         return Ok(BulkDeleteResult {
             deleted_count: 0,
@@ -1529,41 +1836,31 @@ pub fn delete_transactions_bulk(app: AppHandle, ids: Vec<i64>) -> Result<BulkDel
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    // Changes and FIFO rebuild are committed together (rolled back on error)
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let conn: &rusqlite::Connection = &tx;
+
     // 1. Gather info for all selected transactions
     let placeholders: String = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
     let query = format!(
-        "SELECT owner_type, security_id, cross_entry_id FROM pp_txn WHERE id IN ({})",
+        "SELECT cross_entry_id FROM pp_txn WHERE id IN ({})",
         placeholders
     );
 
     let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
     let params: Vec<&dyn rusqlite::ToSql> = ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
 
-    struct TxnInfo {
-        owner_type: String,
-        security_id: Option<i64>,
-        cross_entry_id: Option<i64>,
-    }
-
-    let txn_infos: Vec<TxnInfo> = stmt
-        .query_map(params.as_slice(), |row| {
-            Ok(TxnInfo {
-                owner_type: row.get(0)?,
-                security_id: row.get(1)?,
-                cross_entry_id: row.get(2)?,
-            })
-        })
+    let txn_cross_entries: Vec<Option<i64>> = stmt
+        .query_map(params.as_slice(), |row| row.get(0))
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
+    drop(stmt);
 
-    let selected_count = txn_infos.len();
+    let selected_count = txn_cross_entries.len();
 
     // 2. Collect all cross-entry IDs and find linked transactions
-    let cross_entry_ids: Vec<i64> = txn_infos
-        .iter()
-        .filter_map(|t| t.cross_entry_id)
-        .collect();
+    let cross_entry_ids: Vec<i64> = txn_cross_entries.iter().flatten().copied().collect();
 
     let mut all_ids: std::collections::HashSet<i64> = ids.iter().copied().collect();
     let mut linked_ids: std::collections::HashSet<i64> = std::collections::HashSet::new();
@@ -1624,37 +1921,16 @@ pub fn delete_transactions_bulk(app: AppHandle, ids: Vec<i64>) -> Result<BulkDel
         }
     }
 
-    // 3. Collect affected securities for FIFO rebuild (portfolio transactions with security_id)
-    let mut affected_securities: std::collections::HashSet<i64> = txn_infos
-        .iter()
-        .filter(|t| t.owner_type == "portfolio")
-        .filter_map(|t| t.security_id)
-        .collect();
-
-    // Also get security_ids from linked transactions
-    if !linked_ids.is_empty() {
-        let linked_placeholders: String = linked_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        let linked_query = format!(
-            "SELECT security_id FROM pp_txn WHERE id IN ({}) AND owner_type = 'portfolio' AND security_id IS NOT NULL",
-            linked_placeholders
-        );
-        let mut linked_stmt = conn.prepare(&linked_query).map_err(|e| e.to_string())?;
-        let linked_params: Vec<&dyn rusqlite::ToSql> = linked_ids
-            .iter()
-            .map(|id| id as &dyn rusqlite::ToSql)
-            .collect();
-
-        let linked_sec_ids = linked_stmt
-            .query_map(linked_params.as_slice(), |row| row.get::<_, i64>(0))
-            .map_err(|e| e.to_string())?;
+    // 3. Collect affected portfolios and securities (including linked transactions)
+    let all_ids_vec: Vec<i64> = all_ids.iter().copied().collect();
+    let mut affected = AffectedByTransactions::default();
+    affected.add(conn, &all_ids_vec)?;
 
-        for sec_id in linked_sec_ids.filter_map(|r| r.ok()) {
-            affected_securities.insert(sec_id);
-        }
+    if !permanent.unwrap_or(false) {
+        crate::commands::trash::store_in_trash(conn, &all_ids_vec)?;
     }
 
     // 4. Delete in correct order
-    let all_ids_vec: Vec<i64> = all_ids.iter().copied().collect();
     let all_placeholders: String = all_ids_vec.iter().map(|_| "?").collect::<Vec<_>>().join(",");
     let all_params: Vec<&dyn rusqlite::ToSql> = all_ids_vec
         .iter()
@@ -1683,15 +1959,25 @@ pub fn delete_transactions_bulk(app: AppHandle, ids: Vec<i64>) -> Result<BulkDel
     conn.execute(&delete_txn_query, all_params.as_slice())
         .map_err(|e| e.to_string())?;
 
-    // 5. Rebuild FIFO lots once per affected security
-    let affected_securities_vec: Vec<i64> = affected_securities.iter().copied().collect();
-    for sec_id in &affected_securities_vec {
-        if let Err(e) = crate::fifo::build_fifo_lots(conn, *sec_id) {
-            log::warn!("Failed to rebuild FIFO lots for security {}: {}", sec_id, e);
-        }
-    }
+    record_audit(
+        conn,
+        "delete_transactions_bulk",
+        "transaction",
+        None,
+        &format!(
+            "{} Buchungen {} (+{} verknüpfte), IDs {:?}",
+            selected_count,
+            if permanent.unwrap_or(false) { "endgültig gelöscht" } else { "in den Papierkorb verschoben" },
+            linked_ids.len(),
+            ids
+        ),
+    );
+
+    // 5. Rebuild FIFO lots once per affected security and caches
+    transactions_changed(&app, tx, "bulk_deleted", &affected, true)?;
 
     // 6. Emit single event
+    let affected_securities_vec: Vec<i64> = affected.security_ids.iter().copied().collect();
     let security_ids_option = if affected_securities_vec.is_empty() {
         None
     } else {
@@ -1739,12 +2025,17 @@ pub fn update_transaction(
     app: AppHandle,
     id: i64,
     data: UpdateTransactionRequest,
+    skip_fifo_rebuild: Option<bool>,
 ) -> Result<(), String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    // Changes and FIFO rebuild are committed together (rolled back on error)
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let conn: &rusqlite::Connection = &tx;
+
     // Verify transaction exists and get current info for FIFO rebuild
     let txn_info: Option<(String, i64, Option<i64>, Option<i64>)> = conn
         .query_row(
@@ -1757,6 +2048,26 @@ pub fn update_transaction(
     let (old_owner_type, old_owner_id, old_security_id, cross_entry_id) =
         txn_info.ok_or_else(|| format!("Transaction with id {} not found", id))?;
 
+    let changed_fields: Vec<&str> = [
+        ("date", data.date.is_some()),
+        ("amount", data.amount.is_some()),
+        ("shares", data.shares.is_some()),
+        ("note", data.note.is_some()),
+        ("fee", data.fee_amount.is_some()),
+        ("tax", data.tax_amount.is_some()),
+        ("owner", data.owner_type.is_some() || data.owner_id.is_some()),
+        ("type", data.txn_type.is_some()),
+        ("security", data.security_id.is_some()),
+        ("currency", data.currency.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, changed)| changed.then_some(field))
+    .collect();
+
+    // State before the update, so a moved transaction refreshes its old portfolio/security too
+    let mut affected = AffectedByTransactions::default();
+    affected.add(conn, &[id])?;
+
     // Determine new values (use new if provided, else keep old)
     let new_owner_type = data.owner_type.clone().unwrap_or_else(|| old_owner_type.clone());
     let new_owner_id = data.owner_id.unwrap_or(old_owner_id);
@@ -1806,6 +2117,9 @@ pub fn update_transaction(
         }
     }
 
+    // Same format as create_transaction: the invested capital cache compares updated_at as text
+    let now = chrono::Utc::now().to_rfc3339();
+
     // Build update query dynamically
     let mut updates = Vec::new();
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -1849,7 +2163,8 @@ pub fn update_transaction(
     }
 
     // Always update updated_at
-    updates.push("updated_at = datetime('now')");
+    updates.push("updated_at = ?");
+    params_vec.push(Box::new(now.clone()));
 
     if !updates.is_empty() {
         let sql = format!(
@@ -1932,7 +2247,8 @@ pub fn update_transaction(
                 other_updates.push("amount = ?");
                 other_params.push(Box::new(amount));
             }
-            other_updates.push("updated_at = datetime('now')");
+            other_updates.push("updated_at = ?");
+            other_params.push(Box::new(now.clone()));
 
             if !other_updates.is_empty() {
                 let sql = format!(
@@ -1948,29 +2264,21 @@ pub fn update_transaction(
         }
     }
 
-    // Rebuild FIFO lots for affected securities
-    // If security changed, rebuild for both old and new
-    let security_changed = old_security_id != new_security_id;
-
-    // Rebuild FIFO lots for old security if it was a portfolio transaction
-    if old_owner_type == "portfolio" && old_security_id.is_some() {
-        let sec_id = old_security_id.unwrap();
-        if let Err(e) = crate::fifo::build_fifo_lots(conn, sec_id) {
-            log::warn!("Failed to rebuild FIFO lots for old security: {}", e);
-        }
-    }
+    record_audit(
+        conn,
+        "update_transaction",
+        "transaction",
+        Some(id),
+        &format!("Buchung geändert: {}", changed_fields.join(", ")),
+    );
 
-    // Rebuild FIFO lots for new security if changed and is portfolio transaction
-    if security_changed && new_owner_type == "portfolio" && new_security_id.is_some() {
-        let sec_id = new_security_id.unwrap();
-        if let Err(e) = crate::fifo::build_fifo_lots(conn, sec_id) {
-            log::warn!("Failed to rebuild FIFO lots for new security: {}", e);
-        }
-    }
+    // Rebuild FIFO lots and caches for old and new state, notify the frontend
+    affected.add(conn, &[id])?;
+    let rebuild_fifo = !skip_fifo_rebuild.unwrap_or(false);
+    transactions_changed(&app, tx, "updated", &affected, rebuild_fifo)?;
 
-    // Emit data changed event for frontend refresh
     // Include both old and new security if changed
-    let affected_security = if security_changed {
+    let affected_security = if old_security_id != new_security_id {
         new_security_id.or(old_security_id)
     } else {
         new_security_id
@@ -1989,31 +2297,9 @@ pub fn get_transaction(id: i64) -> Result<TransactionResult, String> {
         .ok_or_else(|| "Database not initialized".to_string())?;
 
     conn.query_row(
-        r#"
-        SELECT id, uuid, owner_type, owner_id, txn_type, date, amount, currency, shares, security_id, note, cross_entry_id
-        FROM pp_txn
-        WHERE id = ?1
-        "#,
+        &format!("SELECT {} FROM pp_txn WHERE id = ?1", TRANSACTION_RESULT_COLUMNS),
         params![id],
-        |row| {
-            let amount_cents: i64 = row.get(6)?;
-            let shares_raw: Option<i64> = row.get(8)?;
-
-            Ok(TransactionResult {
-                id: row.get(0)?,
-                uuid: row.get(1)?,
-                owner_type: row.get(2)?,
-                owner_id: row.get(3)?,
-                txn_type: row.get(4)?,
-                date: row.get(5)?,
-                amount: amount_cents as f64 / AMOUNT_SCALE,
-                currency: row.get(7)?,
-                shares: shares_raw.map(|s| s as f64 / SHARES_SCALE),
-                security_id: row.get(9)?,
-                note: row.get(10)?,
-                cross_entry_id: row.get(11)?,
-            })
-        },
+        transaction_result_from_row,
     )
     .map_err(|e| format!("Transaction not found: {}", e))
 }
@@ -2043,6 +2329,7 @@ pub fn delete_all_data() -> Result<(), String> {
         "pp_txn_unit",
         "pp_cross_entry",
         "pp_txn",
+        "pp_txn_trash",
         "pp_price",
         "pp_latest_price",
         "pp_exchange_rate",
@@ -2086,6 +2373,10 @@ pub fn delete_all_data() -> Result<(), String> {
     conn.execute("DELETE FROM sqlite_sequence", []).ok();
 
     log::info!("All data deleted from database");
+    record_audit(conn, "delete_all_data", "database", None, "Alle Daten gelöscht");
+
+    // Give the freed pages back to the file system
+    db::optimize_after_bulk_change(conn, "deleting all data");
     Ok(())
 }
 
@@ -2106,4 +2397,69 @@ mod tests {
         assert!(!validate_isin("US03783310055")); // Too long
         assert!(!validate_isin("123456789012")); // Invalid country code
     }
+
+    #[test]
+    fn test_affected_by_transactions() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (5, 's5', 'Apple', 'USD'), (6, 's6', 'SAP', 'EUR');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id)
+             VALUES (1, 't1', 'portfolio', 10, 'BUY', '2024-01-02', 100, 'EUR', 5),
+                    (2, 't2', 'account', 20, 'BUY', '2024-01-02', 100, 'EUR', 5),
+                    (3, 't3', 'account', 20, 'DEPOSIT', '2024-01-03', 100, 'EUR', NULL),
+                    (4, 't4', 'portfolio', 11, 'DELIVERY_INBOUND', '2024-01-04', 100, 'EUR', 6);",
+        )
+        .unwrap();
+
+        // Account transactions do not affect portfolios or FIFO lots
+        let mut affected = AffectedByTransactions::default();
+        affected.add(&conn, &[2, 3]).unwrap();
+        assert!(affected.portfolio_ids.is_empty());
+        assert!(affected.security_ids.is_empty());
+
+        affected.add(&conn, &[1, 2, 4]).unwrap();
+        assert_eq!(affected.portfolio_ids.iter().copied().collect::<Vec<_>>(), vec![10, 11]);
+        assert_eq!(affected.security_ids.iter().copied().collect::<Vec<_>>(), vec![5, 6]);
+    }
+
+    #[test]
+    fn test_idempotency_keys() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+
+        // Identical rows within an import get distinct, reproducible keys
+        let mut keys = ImportIdempotencyKeys::new("csv");
+        let first = keys.next("1|BUY|2024-01-02|100");
+        let second = keys.next("1|BUY|2024-01-02|100");
+        assert_ne!(first, second);
+        assert!(first.starts_with("csv:"));
+        let mut again = ImportIdempotencyKeys::new("csv");
+        assert_eq!(again.next("1|BUY|2024-01-02|100"), first);
+
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, idempotency_key)
+             VALUES (1, 't1', 'account', 1, 'DEPOSIT', '2024-01-02', 100, 'EUR', ?1)",
+            params![first],
+        )
+        .unwrap();
+        assert!(idempotency_key_exists(&conn, &first).unwrap());
+        assert!(!idempotency_key_exists(&conn, &second).unwrap());
+        assert_eq!(find_transaction_by_idempotency_key(&conn, &first).unwrap().unwrap().id, 1);
+        assert!(find_transaction_by_idempotency_key(&conn, &second).unwrap().is_none());
+
+        // The key is unique, transactions without a key are not affected
+        let duplicate = conn.execute(
+            "INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, idempotency_key)
+             VALUES ('t2', 'account', 1, 'DEPOSIT', '2024-01-02', 100, 'EUR', ?1)",
+            params![first],
+        );
+        assert!(duplicate.is_err());
+        conn.execute_batch(
+            "INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency)
+             VALUES ('t3', 'account', 1, 'DEPOSIT', '2024-01-02', 100, 'EUR'),
+                    ('t4', 'account', 1, 'DEPOSIT', '2024-01-02', 100, 'EUR');",
+        )
+        .unwrap();
+    }
 }
@@ -1,13 +1,14 @@
 //! CSV import and export commands for Tauri
 
+use crate::commands::audit::record_audit;
 use crate::db;
-use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::events::{emit_data_changed, emit_export_progress, DataChangedPayload, ExportProgressPayload};
 use crate::pp::common::{prices, shares};
 use crate::security;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use tauri::{command, AppHandle};
 
 // ============================================================================
@@ -183,6 +184,172 @@ pub fn export_transactions_csv(
     })
 }
 
+/// Rows between two progress events of the JSON Lines export
+const JSONL_PROGRESS_INTERVAL: usize = 1000;
+
+/// One transaction of the JSON Lines export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionJsonLine {
+    pub uuid: String,
+    pub date: String,
+    pub txn_type: String,
+    pub owner_type: String,
+    pub owner_id: i64,
+    pub owner_name: Option<String>,
+    pub security_id: Option<i64>,
+    pub security_name: Option<String>,
+    pub isin: Option<String>,
+    pub shares: Option<f64>,
+    pub amount: f64,
+    pub currency: String,
+    pub fees: f64,
+    pub taxes: f64,
+    pub note: Option<String>,
+}
+
+/// Export transactions as JSON Lines (one JSON object per line)
+///
+/// Rows are streamed from the database into a buffered file writer, so memory
+/// stays flat for any number of transactions. Emits `export-progress` every
+/// 1000 rows.
+#[command]
+pub async fn export_transactions_jsonl(
+    app: AppHandle,
+    path: String,
+    owner_type: Option<String>,
+    owner_id: Option<i64>,
+) -> Result<CsvExportResult, String> {
+    // SECURITY: Validate path (defense-in-depth)
+    let validated_path = security::validate_file_path_with_extension(&path, Some(&["jsonl", "json"]))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    // Run the export in a separate thread to keep the UI responsive
+    tokio::task::spawn_blocking(move || {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        let file = File::create(&validated_path).map_err(|e| e.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        let rows_exported = write_transactions_jsonl(
+            conn,
+            &mut writer,
+            owner_type.as_deref(),
+            owner_id,
+            |rows_written, total_rows| {
+                emit_export_progress(
+                    &app,
+                    ExportProgressPayload {
+                        export: "transactions-jsonl".to_string(),
+                        rows_written,
+                        total_rows,
+                        done: rows_written == total_rows,
+                    },
+                );
+            },
+        )
+        .map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+
+        Ok(CsvExportResult {
+            path: validated_path.to_string_lossy().to_string(),
+            rows_exported,
+        })
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Stream transactions as JSON Lines into `writer`
+///
+/// Calls `on_progress(rows_written, total_rows)` every `JSONL_PROGRESS_INTERVAL`
+/// rows and once at the end. Returns the number of rows written.
+fn write_transactions_jsonl<W: Write>(
+    conn: &rusqlite::Connection,
+    writer: &mut W,
+    owner_type: Option<&str>,
+    owner_id: Option<i64>,
+    mut on_progress: impl FnMut(usize, usize),
+) -> anyhow::Result<usize> {
+    let mut filter = String::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(ot) = owner_type {
+        filter.push_str(" AND t.owner_type = ?");
+        params.push(Box::new(ot.to_string()));
+    }
+    if let Some(oid) = owner_id {
+        filter.push_str(" AND t.owner_id = ?");
+        params.push(Box::new(oid));
+    }
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+    let total_rows: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM pp_txn t WHERE 1=1{}", filter),
+        params_refs.as_slice(),
+        |row| row.get(0),
+    )?;
+    let total_rows = total_rows as usize;
+
+    let query = format!(
+        r#"
+        SELECT
+            t.uuid, t.date, t.txn_type, t.owner_type, t.owner_id,
+            CASE t.owner_type
+                WHEN 'portfolio' THEN (SELECT name FROM pp_portfolio WHERE id = t.owner_id)
+                WHEN 'account' THEN (SELECT name FROM pp_account WHERE id = t.owner_id)
+            END as owner_name,
+            t.security_id, s.name, s.isin, t.shares, t.amount, t.currency, t.note,
+            (SELECT COALESCE(SUM(amount), 0) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'FEE') as fees,
+            (SELECT COALESCE(SUM(amount), 0) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX') as taxes
+        FROM pp_txn t
+        LEFT JOIN pp_security s ON s.id = t.security_id
+        WHERE 1=1{}
+        ORDER BY t.date, t.id
+        "#,
+        filter
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = stmt.query(params_refs.as_slice())?;
+
+    let mut rows_written = 0;
+    while let Some(row) = rows.next()? {
+        let line = TransactionJsonLine {
+            uuid: row.get(0)?,
+            date: row.get(1)?,
+            txn_type: row.get(2)?,
+            owner_type: row.get(3)?,
+            owner_id: row.get(4)?,
+            owner_name: row.get(5)?,
+            security_id: row.get(6)?,
+            security_name: row.get(7)?,
+            isin: row.get(8)?,
+            shares: row.get::<_, Option<i64>>(9)?.map(shares::to_decimal),
+            amount: row.get::<_, i64>(10)? as f64 / 100.0,
+            currency: row.get(11)?,
+            note: row.get::<_, Option<String>>(12)?.filter(|n| !n.is_empty()),
+            fees: row.get::<_, i64>(13)? as f64 / 100.0,
+            taxes: row.get::<_, i64>(14)? as f64 / 100.0,
+        };
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+
+        rows_written += 1;
+        if rows_written % JSONL_PROGRESS_INTERVAL == 0 {
+            on_progress(rows_written, total_rows);
+        }
+    }
+
+    if rows_written % JSONL_PROGRESS_INTERVAL != 0 || rows_written == 0 {
+        on_progress(rows_written, total_rows);
+    }
+
+    Ok(rows_written)
+}
+
 /// Export holdings to CSV
 #[command]
 pub fn export_holdings_csv(path: String) -> Result<CsvExportResult, String> {
@@ -577,9 +744,21 @@ pub fn import_transactions_csv(
         .query_row("SELECT id FROM pp_import ORDER BY id DESC LIMIT 1", [], |r| r.get(0))
         .unwrap_or(1);
 
+    // Rows imported before (same portfolio and content) are skipped
+    let mut idempotency_keys = crate::commands::crud::ImportIdempotencyKeys::new("csv");
+
     for (line_num, line) in lines.iter().enumerate().skip(1) {
         let values: Vec<&str> = line.split(delim).collect();
 
+        let idempotency_key = idempotency_keys.next(&format!("{}|{}", portfolio_id, line.trim()));
+        if crate::commands::crud::idempotency_key_exists(conn, &idempotency_key)
+            .map_err(|e| e.to_string())?
+        {
+            errors.push(format!("Zeile {}: Bereits importiert", line_num + 1));
+            skipped += 1;
+            continue;
+        }
+
         // Parse date
         let date = mapping
             .date
@@ -664,8 +843,8 @@ pub fn import_transactions_csv(
         // Insert transaction
         let result = conn.execute(
             r#"
-            INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-            VALUES (?, ?, 'portfolio', ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note, idempotency_key)
+            VALUES (?, ?, 'portfolio', ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             rusqlite::params![
                 import_id,
@@ -677,7 +856,8 @@ pub fn import_transactions_csv(
                 amount,
                 currency,
                 shares,
-                note
+                note,
+                idempotency_key
             ],
         );
 
@@ -725,6 +905,17 @@ pub fn import_transactions_csv(
         log::info!("CSV Import: Rebuilt FIFO lots for {} securities", affected_security_ids.len());
     }
 
+    record_audit(
+        conn,
+        "import_transactions_csv",
+        "import",
+        None,
+        &format!(
+            "CSV-Import {} in Depot {}: {} Buchungen importiert, {} übersprungen",
+            path, portfolio_id, imported, skipped
+        ),
+    );
+
     // Emit data changed event for frontend refresh
     emit_data_changed(
         &app,
@@ -816,6 +1007,14 @@ pub fn import_prices_csv(
         );
     }
 
+    record_audit(
+        conn,
+        "import_prices_csv",
+        "security",
+        Some(security_id),
+        &format!("CSV-Import {}: {} Kurse importiert, {} übersprungen", path, imported, skipped),
+    );
+
     Ok(CsvImportResult {
         rows_imported: imported,
         rows_skipped: skipped,
@@ -823,10 +1022,177 @@ pub fn import_prices_csv(
     })
 }
 
+/// Constituent of a fund parsed from a factsheet CSV
+#[derive(Debug, Clone, PartialEq)]
+struct FundHoldingRow {
+    name: String,
+    isin: Option<String>,
+    region: Option<String>,
+    sector: Option<String>,
+    /// Percent of fund assets
+    weight: f64,
+}
+
+/// Import the constituents of a fund from a factsheet CSV
+///
+/// Columns are detected by their header (name, ISIN, region/country, sector, weight in %);
+/// lines before the header are skipped. Replaces the previously imported constituents.
+#[command]
+pub fn import_fund_holdings_csv(
+    path: String,
+    security_id: i64,
+    delimiter: Option<char>,
+) -> Result<CsvImportResult, String> {
+    // SECURITY: Validate path (defense-in-depth)
+    let validated_path = security::validate_file_path_with_extension(&path, Some(&["csv", "txt"]))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let content = std::fs::read(&validated_path).map_err(|e| e.to_string())?;
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<&str> = content.lines().collect();
+
+    let (rows, mut result) = parse_fund_holdings(&lines, delimiter)?;
+
+    let mut conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_mut()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM pp_fund_holdings WHERE fund_security_id = ?",
+        [security_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for row in &rows {
+        tx.execute(
+            "INSERT INTO pp_fund_holdings (fund_security_id, name, isin, region, sector, weight) VALUES (?, ?, ?, ?, ?, ?)",
+            rusqlite::params![security_id, row.name, row.isin, row.region, row.sector, row.weight],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    record_audit(
+        &tx,
+        "import_fund_holdings_csv",
+        "security",
+        Some(security_id),
+        &format!("Fondsbestandteile aus {} importiert ({} Positionen)", path, rows.len()),
+    );
+    tx.commit().map_err(|e| e.to_string())?;
+
+    result.rows_imported = rows.len();
+    Ok(result)
+}
+
+/// Parse factsheet lines into fund constituents
+fn parse_fund_holdings(
+    lines: &[&str],
+    delimiter: Option<char>,
+) -> Result<(Vec<FundHoldingRow>, CsvImportResult), String> {
+    // Factsheets often start with a few lines of fund information before the header
+    let header = lines.iter().take(30).enumerate().find_map(|(index, line)| {
+        let delim = delimiter.unwrap_or_else(|| detect_delimiter(line));
+        let headers: Vec<String> = split_csv_line(line, delim)
+            .into_iter()
+            .map(|h| h.trim().to_lowercase())
+            .collect();
+        let name = find_column(&headers, &["name", "bezeichnung", "emittent", "holding"])?;
+        let weight = find_column(&headers, &["gewichtung", "gewicht", "weight", "anteil", "%"])?;
+        Some((index, delim, name, weight, headers))
+    });
+    let Some((header_index, delim, name_col, weight_col, headers)) = header else {
+        return Err("Keine Kopfzeile mit Name und Gewichtung gefunden".to_string());
+    };
+    let isin_col = find_column(&headers, &["isin"]);
+    let region_col = find_column(&headers, &["region", "land", "standort", "country", "location"]);
+    let sector_col = find_column(&headers, &["sektor", "sector", "branche"]);
+
+    let mut rows = Vec::new();
+    let mut result = CsvImportResult {
+        rows_imported: 0,
+        rows_skipped: 0,
+        errors: Vec::new(),
+    };
+
+    for (line_num, line) in lines.iter().enumerate().skip(header_index + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let values = split_csv_line(line, delim);
+        let field = |col: Option<usize>| {
+            col.and_then(|c| values.get(c))
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty() && *v != "-")
+                .map(str::to_string)
+        };
+
+        let name = field(Some(name_col));
+        let weight = field(Some(weight_col)).and_then(|v| parse_decimal(v.trim_end_matches('%')));
+        let (Some(name), Some(weight)) = (name, weight) else {
+            // Disclaimer lines at the end of the file have no weight
+            result.errors.push(format!("Zeile {}: Ungültige Daten", line_num + 1));
+            result.rows_skipped += 1;
+            continue;
+        };
+
+        rows.push(FundHoldingRow {
+            name,
+            isin: field(isin_col),
+            region: field(region_col),
+            sector: field(sector_col),
+            weight,
+        });
+    }
+
+    let total_weight: f64 = rows.iter().map(|r| r.weight).sum();
+    if total_weight > 100.5 {
+        result.errors.push(format!(
+            "Summe der Gewichtungen beträgt {:.2} % (über 100 %)",
+            total_weight
+        ));
+    }
+
+    Ok((rows, result))
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
+/// Index of the first header matching an alias exactly, otherwise containing it
+fn find_column(headers: &[String], aliases: &[&str]) -> Option<usize> {
+    aliases
+        .iter()
+        .find_map(|alias| headers.iter().position(|h| h == alias))
+        .or_else(|| {
+            aliases
+                .iter()
+                .find_map(|alias| headers.iter().position(|h| h.contains(alias)))
+        })
+}
+
+/// Split a CSV line, honoring quoted fields (RFC 4180)
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
 /// Escape a string for CSV output (RFC 4180 compliant)
 /// - If the value contains delimiter, quotes, or newlines, wrap in quotes
 /// - Double any existing quotes
@@ -924,7 +1290,7 @@ fn map_transaction_type(s: &str) -> String {
     }
 }
 
-fn find_or_create_security(
+pub(crate) fn find_or_create_security(
     conn: &rusqlite::Connection,
     isin: &str,
     name: Option<&str>,
@@ -1230,3 +1596,100 @@ fn extract_json_from_response(response: &str) -> String {
 
     response.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fund_holdings() {
+        let lines = [
+            "iShares Core MSCI World UCITS ETF",
+            "Fondsvermögen per,\"02.Jan.2024\"",
+            "",
+            "Emittententicker,Name,Sektor,Anlageklasse,Gewichtung (%),Standort",
+            "AAPL,\"APPLE INC\",IT,Aktien,\"4,85\",Vereinigte Staaten",
+            "NESN,NESTLE SA,Basiskonsumgüter,Aktien,\"0,75\",Schweiz",
+            "EUR,EUR CASH,Cash und/oder Derivate,Cash,\"0,10\",-",
+            "",
+            "\"Die Angaben dienen nur zu Informationszwecken.\"",
+        ];
+
+        let (rows, result) = parse_fund_holdings(&lines, None).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            FundHoldingRow {
+                name: "APPLE INC".to_string(),
+                isin: None,
+                region: Some("Vereinigte Staaten".to_string()),
+                sector: Some("IT".to_string()),
+                weight: 4.85,
+            }
+        );
+        assert_eq!(rows[2].region, None);
+        assert_eq!(result.rows_skipped, 1);
+
+        assert!(parse_fund_holdings(&["a;b", "1;2"], None).is_err());
+    }
+
+    #[test]
+    fn test_write_transactions_jsonl() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency, isin) VALUES (1, 's1', 'Aktie', 'EUR', 'DE0001234567');",
+        )
+        .unwrap();
+        for i in 0..2500 {
+            conn.execute(
+                "INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+                 VALUES (?1, 'portfolio', 1, 'BUY', '2024-01-02', 10050, 'EUR', 1, 150000000)",
+                [format!("t{}", i)],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, note)
+             VALUES ('d1', 'account', 1, 'DEPOSIT', '2024-01-01', 500000, 'EUR', 'Start')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency) VALUES (1, 'FEE', 50, 'EUR')",
+            [],
+        )
+        .unwrap();
+
+        let mut output = Vec::new();
+        let mut progress = Vec::new();
+        let rows = write_transactions_jsonl(&conn, &mut output, None, None, |written, total| {
+            progress.push((written, total))
+        })
+        .unwrap();
+
+        assert_eq!(rows, 2501);
+        assert_eq!(progress, [(1000, 2501), (2000, 2501), (2501, 2501)]);
+
+        let lines: Vec<TransactionJsonLine> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2501);
+        assert_eq!(lines[0].txn_type, "DEPOSIT");
+        assert_eq!(lines[0].owner_name.as_deref(), Some("Giro"));
+        assert_eq!(lines[0].note.as_deref(), Some("Start"));
+        assert_eq!(lines[1].isin.as_deref(), Some("DE0001234567"));
+        assert_eq!(lines[1].shares, Some(1.5));
+        assert!((lines[1].amount - 100.5).abs() < 1e-9);
+        assert!((lines[1].fees - 0.5).abs() < 1e-9);
+
+        let mut filtered = Vec::new();
+        let rows = write_transactions_jsonl(&conn, &mut filtered, Some("account"), Some(1), |_, _| {})
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+}
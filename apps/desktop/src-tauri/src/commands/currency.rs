@@ -1,10 +1,15 @@
 //! Currency conversion commands for Tauri
 
+use crate::commands::audit::record_audit;
+use crate::commands::data::invalidate_invested_capital_cache;
+use crate::commands::quotes::save_exchange_rates_to_db;
 use crate::currency;
 use crate::db;
+use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::quotes::ecb;
 use chrono::NaiveDate;
 use serde::Serialize;
-use tauri::command;
+use tauri::{command, AppHandle};
 
 /// Exchange rate result
 #[derive(Debug, Clone, Serialize)]
@@ -117,6 +122,117 @@ pub fn get_base_currency() -> Result<String, String> {
     currency::get_base_currency(conn).map_err(|e| e.to_string())
 }
 
+/// Result of a base currency change
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseCurrencyChange {
+    pub old_currency: String,
+    pub new_currency: String,
+    /// Exchange rates fetched from the ECB to cover the new base
+    pub fetched_rates: usize,
+}
+
+/// Change the base currency
+///
+/// All valuations convert on the fly, so only the setting and the cached
+/// invested capital history change. Rates from every used currency to the new
+/// base must exist back to the first transaction; missing ones are fetched from
+/// the ECB. If they are still missing, the base currency is left unchanged.
+#[command]
+pub async fn set_base_currency(currency: String, app: AppHandle) -> Result<BaseCurrencyChange, String> {
+    let new_currency = currency::normalize_currency_code(&currency).map_err(|e| e.to_string())?;
+
+    let (old_currency, since, missing) = {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+        let old_currency = currency::get_base_currency(conn).map_err(|e| e.to_string())?;
+        let since = first_transaction_date(conn);
+        let missing = currency::currencies_missing_rates(conn, &new_currency, since)
+            .map_err(|e| e.to_string())?;
+        (old_currency, since, missing)
+    };
+
+    if old_currency == new_currency {
+        return Ok(BaseCurrencyChange {
+            old_currency,
+            new_currency,
+            fetched_rates: 0,
+        });
+    }
+
+    let mut fetched_rates = 0;
+    if !missing.is_empty() {
+        let today = chrono::Utc::now().date_naive();
+        let rates: Vec<_> = ecb::fetch_historical_rates(since, today)
+            .await
+            .map_err(|e| format!("Wechselkurse konnten nicht geladen werden: {}", e))?
+            .into_values()
+            .flatten()
+            .collect();
+        save_exchange_rates_to_db(&rates).map_err(|e| e.to_string())?;
+        fetched_rates = rates.len();
+    }
+
+    {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        let missing = currency::currencies_missing_rates(conn, &new_currency, since)
+            .map_err(|e| e.to_string())?;
+        if !missing.is_empty() {
+            return Err(format!(
+                "Keine Wechselkurse nach {} ab {} für: {}",
+                new_currency,
+                since,
+                missing.join(", ")
+            ));
+        }
+
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        currency::set_base_currency(&tx, &new_currency).map_err(|e| e.to_string())?;
+        invalidate_invested_capital_cache(&tx).map_err(|e| e.to_string())?;
+        crate::fifo::store_all_fx_gains(&tx).map_err(|e| e.to_string())?;
+        record_audit(
+            &tx,
+            "set_base_currency",
+            "settings",
+            None,
+            &format!("Basiswährung von {} auf {} geändert", old_currency, new_currency),
+        );
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    log::info!("Base currency changed from {} to {}", old_currency, new_currency);
+
+    emit_data_changed(
+        &app,
+        DataChangedPayload {
+            entity: "settings".to_string(),
+            action: "base_currency_changed".to_string(),
+            security_ids: None,
+            portfolio_ids: None,
+        },
+    );
+
+    Ok(BaseCurrencyChange {
+        old_currency,
+        new_currency,
+        fetched_rates,
+    })
+}
+
+/// Date of the first transaction (today without transactions)
+fn first_transaction_date(conn: &rusqlite::Connection) -> NaiveDate {
+    conn.query_row("SELECT MIN(date(date)) FROM pp_txn", [], |row| row.get::<_, Option<String>>(0))
+        .ok()
+        .flatten()
+        .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| chrono::Utc::now().date_naive())
+}
+
 /// Get all holdings converted to base currency
 #[command]
 pub fn get_holdings_in_base_currency() -> Result<f64, String> {
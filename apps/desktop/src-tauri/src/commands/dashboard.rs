@@ -21,6 +21,7 @@ pub enum WidgetType {
     Alerts,
     Chart,
     Benchmark,
+    YieldOnCost,
 }
 
 // Note: Widget size and labels are defined in the frontend (TypeScript)
@@ -222,6 +223,18 @@ pub fn get_available_widgets() -> Result<Vec<WidgetDefinition>, String> {
             max_height: 4,
             configurable: true,
         },
+        WidgetDefinition {
+            widget_type: WidgetType::YieldOnCost,
+            label: "Yield on Cost".to_string(),
+            description: "Jährliche Dividenden im Verhältnis zum Einstandswert".to_string(),
+            default_width: 2,
+            default_height: 1,
+            min_width: 1,
+            min_height: 1,
+            max_width: 4,
+            max_height: 2,
+            configurable: true,
+        },
     ];
 
     Ok(widgets)
@@ -434,3 +447,140 @@ pub fn create_default_dashboard_layout() -> Result<DashboardLayout, String> {
 
     Ok(DashboardLayout { id, ..layout })
 }
+
+/// Export a dashboard layout as JSON to import it on another machine
+#[tauri::command]
+pub fn export_dashboard_layout(layout_id: i64) -> Result<String, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    export_dashboard_layout_internal(conn, layout_id)
+}
+
+/// Import a dashboard layout exported by `export_dashboard_layout`
+///
+/// Always creates a new, non-default layout.
+#[tauri::command]
+pub fn import_dashboard_layout(json: String) -> Result<DashboardLayout, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    import_dashboard_layout_internal(conn, &json)
+}
+
+fn export_dashboard_layout_internal(
+    conn: &rusqlite::Connection,
+    layout_id: i64,
+) -> Result<String, String> {
+    let layout = conn
+        .query_row(
+            "SELECT id, name, columns, widgets_json, is_default FROM pp_widget_layout WHERE id = ?1",
+            [layout_id],
+            |row| {
+                let widgets_json: String = row.get(3)?;
+                Ok(DashboardLayout {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    columns: row.get(2)?,
+                    widgets: serde_json::from_str(&widgets_json).unwrap_or_default(),
+                    is_default: row.get::<_, i64>(4)? == 1,
+                })
+            },
+        )
+        .map_err(|_| format!("Layout {} nicht gefunden", layout_id))?;
+
+    serde_json::to_string_pretty(&layout).map_err(|e| format!("JSON error: {}", e))
+}
+
+fn import_dashboard_layout_internal(
+    conn: &rusqlite::Connection,
+    json: &str,
+) -> Result<DashboardLayout, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Ungültiges JSON: {}", e))?;
+
+    // Check widget types first so unknown widgets get a readable error
+    // instead of a serde enum message
+    let available: Vec<serde_json::Value> = get_available_widgets()?
+        .into_iter()
+        .filter_map(|w| serde_json::to_value(w.widget_type).ok())
+        .collect();
+    let widgets = value
+        .get("widgets")
+        .and_then(|w| w.as_array())
+        .ok_or_else(|| "Layout enthält keine Widgets".to_string())?;
+    let unknown: Vec<String> = widgets
+        .iter()
+        .map(|w| w.get("widget_type").cloned().unwrap_or_default())
+        .filter(|t| !available.contains(t))
+        .map(|t| t.to_string())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!("Unbekannte Widget-Typen: {}", unknown.join(", ")));
+    }
+
+    let imported: DashboardLayout =
+        serde_json::from_value(value).map_err(|e| format!("Ungültiges Layout: {}", e))?;
+    if imported.columns == 0 {
+        return Err("Layout muss mindestens eine Spalte haben".to_string());
+    }
+
+    let layout = DashboardLayout {
+        id: 0,
+        is_default: false,
+        ..imported
+    };
+    let id = save_dashboard_layout_internal(conn, &layout)?;
+
+    Ok(DashboardLayout { id, ..layout })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_export_import_dashboard_layout() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+
+        let layout = DashboardLayout {
+            id: 0,
+            name: "Dividenden".to_string(),
+            columns: 6,
+            widgets: vec![WidgetConfig {
+                id: "yoc".to_string(),
+                widget_type: WidgetType::YieldOnCost,
+                title: None,
+                position: Position { x: 0, y: 0 },
+                size: Size { width: 2, height: 1 },
+                settings: serde_json::json!({ "portfolioId": 1 }),
+            }],
+            is_default: true,
+        };
+        let id = save_dashboard_layout_internal(&conn, &layout).unwrap();
+
+        let json = export_dashboard_layout_internal(&conn, id).unwrap();
+        let imported = import_dashboard_layout_internal(&conn, &json).unwrap();
+        assert_ne!(imported.id, id);
+        assert!(!imported.is_default);
+        assert_eq!(imported.name, "Dividenden");
+        assert_eq!(imported.widgets.len(), 1);
+        assert_eq!(imported.widgets[0].settings["portfolioId"], 1);
+
+        let unknown = json.replace("yield_on_cost", "crypto_ticker");
+        let err = import_dashboard_layout_internal(&conn, &unknown).unwrap_err();
+        assert!(err.contains("crypto_ticker"));
+
+        assert!(export_dashboard_layout_internal(&conn, 999).is_err());
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_widget_layout", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
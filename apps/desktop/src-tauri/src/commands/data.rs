@@ -12,6 +12,7 @@
 use crate::currency;
 use crate::db;
 use crate::pp::common::{prices, shares};
+use crate::pp::parse_date_flexible;
 // SSOT reference: These constants define the canonical holdings calculation logic
 #[allow(unused_imports)]
 use crate::pp::{HOLDINGS_ADD_TYPES, HOLDINGS_REMOVE_TYPES, HOLDINGS_SUM_SQL};
@@ -218,6 +219,41 @@ pub fn get_accounts(import_id: Option<i64>) -> Result<Vec<AccountData>, String>
     Ok(accounts)
 }
 
+/// Reconcile the computed balance of an account with a statement balance
+///
+/// Returns the difference and the account transactions within `window_days`
+/// (default 7) around the date.
+#[command]
+pub fn reconcile_account(
+    account_id: i64,
+    expected_balance: f64,
+    as_of_date: String,
+    window_days: Option<i64>,
+) -> Result<crate::cash::AccountReconciliation, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let date = parse_date_flexible(&as_of_date)
+        .ok_or_else(|| format!("Ungültiges Datumsformat: {}. Erwartet: YYYY-MM-DD", as_of_date))?;
+    let window_days = window_days.unwrap_or(7).clamp(0, 365);
+
+    crate::cash::reconcile_account(conn, account_id, expected_balance, date, window_days)
+        .map_err(|e| e.to_string())
+}
+
+/// All transactions of an account in date order with running balance (passbook)
+#[command]
+pub fn get_account_ledger(account_id: i64) -> Result<crate::cash::AccountLedger, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    crate::cash::get_account_ledger(conn, account_id).map_err(|e| e.to_string())
+}
+
 /// Portfolio data for frontend display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -306,20 +342,181 @@ pub struct TransactionData {
     pub has_forex: bool,
 }
 
-/// Get transactions with optional filters
+/// Filters for `get_transactions`
+///
+/// Mirrors the filter shape of the chat assistant's `ai_query_transactions`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TransactionFilter {
+    pub owner_type: Option<String>,
+    pub owner_id: Option<i64>,
+    pub security_id: Option<i64>,
+    pub txn_type: Option<String>,
+    pub year: Option<i32>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// Full-text search over note, security name, ISIN, WKN and ticker
+    pub search: Option<String>,
+}
+
+/// A page of transactions with the total number of matching rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionPage {
+    pub transactions: Vec<TransactionData>,
+    pub total_count: i64,
+}
+
+/// Transaction types matched by a txn_type filter
+///
+/// BUY/SELL include deliveries, German names are accepted (e.g. "Kauf", "Dividende").
+pub(crate) fn txn_types_for_filter(txn_type: &str) -> Vec<String> {
+    let tt_upper = txn_type.trim().to_uppercase();
+    let types: &[&str] = match tt_upper.as_str() {
+        "BUY" | "KAUF" => &["BUY", "DELIVERY_INBOUND"],
+        "SELL" | "VERKAUF" => &["SELL", "DELIVERY_OUTBOUND"],
+        "DIVIDEND" | "DIVIDENDE" | "DIVIDENDS" => &["DIVIDENDS"],
+        _ => return vec![tt_upper],
+    };
+    types.iter().map(|t| t.to_string()).collect()
+}
+
+/// Get transactions with optional filters and pagination
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub fn get_transactions(
     owner_type: Option<String>,
     owner_id: Option<i64>,
     security_id: Option<i64>,
+    txn_type: Option<String>,
+    year: Option<i32>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<TransactionPage, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let filter = TransactionFilter {
+        owner_type,
+        owner_id,
+        security_id,
+        txn_type,
+        year,
+        start_date,
+        end_date,
+        search: None,
+    };
+
+    query_transactions(conn, &filter, limit, offset)
+}
+
+/// Search transactions by note text and security name, ISIN, WKN or ticker
+///
+/// Every word of the query must match (as word prefix) in one of these fields.
+#[command]
+pub fn search_transactions(
+    query: String,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<TransactionData>, String> {
+) -> Result<TransactionPage, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    let filter = TransactionFilter {
+        search: Some(query),
+        ..Default::default()
+    };
+
+    query_transactions(conn, &filter, Some(limit.unwrap_or(50)), offset)
+}
+
+/// Build an FTS5 match expression with every word as prefix term
+///
+/// Words are quoted so FTS5 operators in user input are matched literally.
+fn fts_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"*", w))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn query_transactions(
+    conn: &rusqlite::Connection,
+    filter: &TransactionFilter,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<TransactionPage, String> {
+    let mut where_clause = String::from(" WHERE 1=1");
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(ref ot) = filter.owner_type {
+        where_clause.push_str(&format!(" AND t.owner_type = ?{}", params_vec.len() + 1));
+        params_vec.push(Box::new(ot.clone()));
+    }
+
+    if let Some(oid) = filter.owner_id {
+        where_clause.push_str(&format!(" AND t.owner_id = ?{}", params_vec.len() + 1));
+        params_vec.push(Box::new(oid));
+    }
+
+    if let Some(sid) = filter.security_id {
+        where_clause.push_str(&format!(" AND t.security_id = ?{}", params_vec.len() + 1));
+        params_vec.push(Box::new(sid));
+    }
+
+    if let Some(ref tt) = filter.txn_type {
+        let placeholders: Vec<String> = txn_types_for_filter(tt)
+            .into_iter()
+            .map(|t| {
+                params_vec.push(Box::new(t));
+                format!("?{}", params_vec.len())
+            })
+            .collect();
+        where_clause.push_str(&format!(" AND t.txn_type IN ({})", placeholders.join(", ")));
+    }
+
+    if let Some(y) = filter.year {
+        where_clause.push_str(&format!(" AND strftime('%Y', t.date) = ?{}", params_vec.len() + 1));
+        params_vec.push(Box::new(format!("{:04}", y)));
+    }
+
+    if let Some(ref search) = filter.search {
+        let Some(match_query) = fts_match_query(search) else {
+            return Ok(TransactionPage {
+                transactions: Vec::new(),
+                total_count: 0,
+            });
+        };
+        where_clause.push_str(&format!(
+            " AND t.id IN (SELECT rowid FROM pp_txn_fts WHERE pp_txn_fts MATCH ?{})",
+            params_vec.len() + 1
+        ));
+        params_vec.push(Box::new(match_query));
+    }
+
+    for (date, op) in [(&filter.start_date, ">="), (&filter.end_date, "<=")] {
+        if let Some(ref d) = date {
+            let parsed = parse_date_flexible(d)
+                .ok_or_else(|| format!("Ungültiges Datumsformat: {}. Erwartet: YYYY-MM-DD", d))?;
+            where_clause.push_str(&format!(" AND date(t.date) {} ?{}", op, params_vec.len() + 1));
+            params_vec.push(Box::new(parsed.to_string()));
+        }
+    }
+
+    // Total count of matching rows (filters only reference pp_txn)
+    let count_sql = format!("SELECT COUNT(*) FROM pp_txn t{}", where_clause);
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let total_count: i64 = conn
+        .query_row(&count_sql, params_refs.as_slice(), |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
     let mut sql = String::from(
         "SELECT t.id, t.uuid, t.owner_type, t.owner_id,
                 CASE WHEN t.owner_type = 'account' THEN a.name ELSE p.name END as owner_name,
@@ -331,28 +528,12 @@ pub fn get_transactions(
          FROM pp_txn t
          LEFT JOIN pp_account a ON t.owner_type = 'account' AND a.id = t.owner_id
          LEFT JOIN pp_portfolio p ON t.owner_type = 'portfolio' AND p.id = t.owner_id
-         LEFT JOIN pp_security s ON s.id = t.security_id
-         WHERE 1=1",
+         LEFT JOIN pp_security s ON s.id = t.security_id",
     );
+    sql.push_str(&where_clause);
 
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(ref ot) = owner_type {
-        sql.push_str(&format!(" AND t.owner_type = ?{}", params_vec.len() + 1));
-        params_vec.push(Box::new(ot.clone()));
-    }
-
-    if let Some(oid) = owner_id {
-        sql.push_str(&format!(" AND t.owner_id = ?{}", params_vec.len() + 1));
-        params_vec.push(Box::new(oid));
-    }
-
-    if let Some(sid) = security_id {
-        sql.push_str(&format!(" AND t.security_id = ?{}", params_vec.len() + 1));
-        params_vec.push(Box::new(sid));
-    }
-
-    sql.push_str(" ORDER BY t.date DESC");
+    // Tie-break on id so pages are stable
+    sql.push_str(" ORDER BY t.date DESC, t.id DESC");
 
     if let Some(l) = limit {
         sql.push_str(&format!(" LIMIT ?{}", params_vec.len() + 1));
@@ -360,6 +541,10 @@ pub fn get_transactions(
     }
 
     if let Some(o) = offset {
+        // SQLite requires a LIMIT before OFFSET
+        if limit.is_none() {
+            sql.push_str(" LIMIT -1");
+        }
         sql.push_str(&format!(" OFFSET ?{}", params_vec.len() + 1));
         params_vec.push(Box::new(o));
     }
@@ -402,7 +587,10 @@ pub fn get_transactions(
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(transactions)
+    Ok(TransactionPage {
+        transactions,
+        total_count,
+    })
 }
 
 /// Price history data
@@ -419,12 +607,25 @@ pub struct PriceData {
 pub struct PriceDataWithOutliers {
     pub date: String,
     pub value: f64,
-    /// Whether this price is detected as an outlier (>75% daily change)
+    /// Whether this price is detected as an outlier
     pub is_outlier: bool,
-    /// Percentage change from previous day (None for first data point)
+    /// Why the price was flagged (None if not an outlier)
+    pub reason: Option<OutlierReason>,
+    /// Percentage change from previous day, or from the day before a spike
+    /// (None for first data point)
     pub change_percent: Option<f64>,
 }
 
+/// Reason a price was flagged as outlier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierReason {
+    /// Single-day spike: jumps away from the previous price and back to the next
+    Spike,
+    /// Change from the previous day exceeds the threshold
+    DailyMove,
+}
+
 /// Summary of outliers in price data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -447,6 +648,7 @@ pub struct OutlierInfo {
     pub value: f64,
     pub previous_value: f64,
     pub change_percent: f64,
+    pub reason: OutlierReason,
 }
 
 /// Price history with outlier analysis
@@ -457,24 +659,34 @@ pub struct PriceHistoryWithOutliers {
     pub summary: OutlierSummary,
 }
 
-/// Threshold for outlier detection: 30% daily change
+/// Default threshold for outlier detection: 30% daily change
 const OUTLIER_THRESHOLD_PERCENT: f64 = 30.0;
 
+/// Resolve the caller's percent jump threshold (default: OUTLIER_THRESHOLD_PERCENT)
+fn outlier_threshold(percent_jump_threshold: Option<f64>) -> Result<f64, String> {
+    match percent_jump_threshold {
+        None => Ok(OUTLIER_THRESHOLD_PERCENT),
+        Some(t) if t.is_finite() && t > 0.0 => Ok(t),
+        Some(t) => Err(format!("Ungültiger Schwellenwert für Ausreißer: {}", t)),
+    }
+}
+
 /// Detect outliers in a list of prices (sorted by date)
 /// An outlier is detected when:
-/// 1. Price changes more than OUTLIER_THRESHOLD_PERCENT from previous day, OR
-/// 2. Price is a "spike" - deviates significantly from BOTH previous AND next day
-///    (typical for Yahoo data errors that appear as single-day spikes)
-fn detect_outliers(prices: &[PriceData]) -> PriceHistoryWithOutliers {
+/// 1. Price changes more than `threshold_percent` from previous day, OR
+/// 2. Price is a "spike" - deviates by more than half the threshold from BOTH previous
+///    AND next day (typical for Yahoo data errors that appear as single-day spikes)
+///
+/// The day after a spike is compared with the price before the spike, so the
+/// return to normal is not flagged as well.
+fn detect_outliers(prices: &[PriceData], threshold_percent: f64) -> PriceHistoryWithOutliers {
     let mut result_prices = Vec::with_capacity(prices.len());
     let mut outliers = Vec::new();
+    let mut reference: Option<&PriceData> = None;
 
     for (i, price) in prices.iter().enumerate() {
-        let (is_outlier, change_percent, prev_value) = if i == 0 {
-            (false, None, None)
-        } else {
-            let prev = &prices[i - 1];
-            if prev.value > 0.0 {
+        let (reason, change_percent, prev_value) = match reference {
+            Some(prev) if prev.value > 0.0 => {
                 let change_from_prev = ((price.value - prev.value) / prev.value) * 100.0;
 
                 // Check if this is a spike (deviates from both neighbors)
@@ -484,8 +696,8 @@ fn detect_outliers(prices: &[PriceData]) -> PriceHistoryWithOutliers {
                         let change_to_next = ((next.value - price.value) / price.value) * 100.0;
                         // It's a spike if it jumps up/down from prev AND jumps back to next
                         // (both changes in opposite directions and significant)
-                        let prev_significant = change_from_prev.abs() > OUTLIER_THRESHOLD_PERCENT / 2.0;
-                        let next_significant = change_to_next.abs() > OUTLIER_THRESHOLD_PERCENT / 2.0;
+                        let prev_significant = change_from_prev.abs() > threshold_percent / 2.0;
+                        let next_significant = change_to_next.abs() > threshold_percent / 2.0;
                         let opposite_directions = (change_from_prev > 0.0) != (change_to_next > 0.0);
                         prev_significant && next_significant && opposite_directions
                     } else {
@@ -495,27 +707,38 @@ fn detect_outliers(prices: &[PriceData]) -> PriceHistoryWithOutliers {
                     false
                 };
 
-                // Outlier if: large single-day change OR spike pattern
-                let is_outlier = change_from_prev.abs() > OUTLIER_THRESHOLD_PERCENT || is_spike;
-                (is_outlier, Some(change_from_prev), Some(prev.value))
-            } else {
-                (false, None, None)
+                // Outlier if: spike pattern OR large single-day change
+                let reason = if is_spike {
+                    Some(OutlierReason::Spike)
+                } else if change_from_prev.abs() > threshold_percent {
+                    Some(OutlierReason::DailyMove)
+                } else {
+                    None
+                };
+                (reason, Some(change_from_prev), Some(prev.value))
             }
+            _ => (None, None, None),
         };
 
-        if is_outlier {
+        if reason != Some(OutlierReason::Spike) {
+            reference = Some(price);
+        }
+
+        if let Some(reason) = reason {
             outliers.push(OutlierInfo {
                 date: price.date.clone(),
                 value: price.value,
                 previous_value: prev_value.unwrap_or(0.0),
                 change_percent: change_percent.unwrap_or(0.0),
+                reason,
             });
         }
 
         result_prices.push(PriceDataWithOutliers {
             date: price.date.clone(),
             value: price.value,
-            is_outlier,
+            is_outlier: reason.is_some(),
+            reason,
             change_percent,
         });
     }
@@ -561,6 +784,15 @@ pub fn get_price_history(
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    load_price_history(conn, security_id, start_date, end_date)
+}
+
+fn load_price_history(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<PriceData>, String> {
     let mut sql =
         String::from("SELECT date, value FROM pp_price WHERE security_id = ?1");
 
@@ -597,17 +829,21 @@ pub fn get_price_history(
 ///
 /// Returns all prices with outlier flags and a summary of detected outliers.
 /// Use this for charts where you want to visually mark outliers.
+/// `percent_jump_threshold` tunes the sensitivity (default 30% daily change).
 #[command]
 pub fn get_price_history_with_outliers(
     security_id: i64,
     start_date: Option<String>,
     end_date: Option<String>,
+    percent_jump_threshold: Option<f64>,
 ) -> Result<PriceHistoryWithOutliers, String> {
+    let threshold = outlier_threshold(percent_jump_threshold)?;
+
     // First get the raw price history
     let prices = get_price_history(security_id, start_date, end_date)?;
 
     // Then detect outliers
-    let result = detect_outliers(&prices);
+    let result = detect_outliers(&prices, threshold);
 
     // Log warning if outliers found
     if result.summary.outlier_count > 0 {
@@ -619,11 +855,12 @@ pub fn get_price_history_with_outliers(
         );
         for outlier in &result.summary.outliers {
             log::warn!(
-                "  Outlier on {}: {:.2} -> {:.2} ({:+.1}%)",
+                "  Outlier on {}: {:.2} -> {:.2} ({:+.1}%, {:?})",
                 outlier.date,
                 outlier.previous_value,
                 outlier.value,
-                outlier.change_percent
+                outlier.change_percent,
+                outlier.reason
             );
         }
     }
@@ -640,12 +877,15 @@ pub fn get_price_history_filtered(
     security_id: i64,
     start_date: Option<String>,
     end_date: Option<String>,
+    percent_jump_threshold: Option<f64>,
 ) -> Result<FilteredPriceHistory, String> {
+    let threshold = outlier_threshold(percent_jump_threshold)?;
+
     // First get the raw price history
     let prices = get_price_history(security_id, start_date, end_date)?;
 
     // Detect outliers
-    let with_outliers = detect_outliers(&prices);
+    let with_outliers = detect_outliers(&prices, threshold);
 
     // Filter out outliers
     let filtered_prices = filter_outliers(&with_outliers.prices);
@@ -666,6 +906,172 @@ pub struct FilteredPriceHistory {
     pub summary: OutlierSummary,
 }
 
+/// Delete detected outliers from the stored price history of a security
+///
+/// Uses the same detection as `get_price_history_with_outliers` over the full history.
+/// Returns the deleted prices.
+#[command]
+pub fn delete_price_outliers(
+    security_id: i64,
+    percent_jump_threshold: Option<f64>,
+) -> Result<Vec<OutlierInfo>, String> {
+    let threshold = outlier_threshold(percent_jump_threshold)?;
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    delete_outlier_prices(conn, security_id, threshold)
+}
+
+fn delete_outlier_prices(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    threshold: f64,
+) -> Result<Vec<OutlierInfo>, String> {
+    let prices = load_price_history(conn, security_id, None, None)?;
+    let outliers = detect_outliers(&prices, threshold).summary.outliers;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare("DELETE FROM pp_price WHERE security_id = ?1 AND date = ?2")
+            .map_err(|e| e.to_string())?;
+        for outlier in &outliers {
+            stmt.execute(params![security_id, outlier.date])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if !outliers.is_empty() {
+        log::info!(
+            "Deleted {} outlier price(s) of security {}",
+            outliers.len(),
+            security_id
+        );
+    }
+
+    Ok(outliers)
+}
+
+/// Interpolation method for backfilling price gaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapFillMethod {
+    /// Carry the last real price forward
+    LastValue,
+    /// Linear interpolation between the surrounding real prices
+    Linear,
+}
+
+/// Result of backfilling price gaps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillResult {
+    /// Number of interpolated prices inserted
+    pub inserted: usize,
+    /// Number of previously interpolated prices that were replaced
+    pub removed: usize,
+}
+
+/// Marker in pp_price.source for synthetic backfilled prices
+const INTERPOLATED_SOURCE: &str = "interpolated";
+
+/// Fill missing trading days (Mon-Fri) between the first and last real price
+///
+/// Inserted prices are marked with `source = 'interpolated'`; they are excluded from
+/// exports and replaced by real prices on the next quote sync. Running the command
+/// again recomputes all interpolated prices of the security.
+#[command]
+pub fn backfill_price_gaps(
+    security_id: i64,
+    method: GapFillMethod,
+) -> Result<BackfillResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    backfill_gaps(conn, security_id, method)
+}
+
+fn backfill_gaps(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    method: GapFillMethod,
+) -> Result<BackfillResult, String> {
+    use chrono::{Datelike, NaiveDate, Weekday};
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let removed = tx
+        .execute(
+            "DELETE FROM pp_price WHERE security_id = ?1 AND source = ?2",
+            params![security_id, INTERPOLATED_SOURCE],
+        )
+        .map_err(|e| e.to_string())?;
+
+    let real_prices: Vec<(NaiveDate, i64)> = {
+        let mut stmt = tx
+            .prepare("SELECT date, value FROM pp_price WHERE security_id = ?1 ORDER BY date")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([security_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.flatten()
+            .filter_map(|(date, value)| parse_date_flexible(&date).map(|d| (d, value)))
+            .collect()
+    };
+
+    let mut inserted = 0;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO pp_price (security_id, date, value, source)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )
+            .map_err(|e| e.to_string())?;
+
+        for pair in real_prices.windows(2) {
+            let (start, start_value) = pair[0];
+            let (end, end_value) = pair[1];
+            let span = (end - start).num_days() as f64;
+
+            for date in start.iter_days().skip(1).take_while(|d| *d < end) {
+                if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                    continue;
+                }
+                let value = match method {
+                    GapFillMethod::LastValue => start_value,
+                    GapFillMethod::Linear => {
+                        let fraction = (date - start).num_days() as f64 / span;
+                        start_value + ((end_value - start_value) as f64 * fraction).round() as i64
+                    }
+                };
+                inserted += stmt
+                    .execute(params![security_id, date.to_string(), value, INTERPOLATED_SOURCE])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Backfilled {} price gap(s) of security {} ({:?}, {} replaced)",
+        inserted,
+        security_id,
+        method,
+        removed
+    );
+
+    Ok(BackfillResult { inserted, removed })
+}
+
 /// Holdings data (current position in a portfolio)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -775,9 +1181,23 @@ pub fn get_holdings(portfolio_id: i64) -> Result<Vec<HoldingData>, String> {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PortfolioHolding {
+    pub portfolio_id: i64,
     pub portfolio_name: String,
     pub shares: f64,
     pub value: Option<f64>,
+    /// Einstandswert of the FIFO lots in this portfolio
+    pub cost_basis: f64,
+}
+
+/// How `get_all_holdings` groups positions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HoldingsGroupBy {
+    /// One row per ISIN across all portfolios
+    #[default]
+    Security,
+    /// One row per ISIN and portfolio
+    Portfolio,
 }
 
 /// Holdings aggregated by ISIN across all portfolios
@@ -802,20 +1222,36 @@ pub struct AggregatedHolding {
     pub gain_loss_percent: Option<f64>,
     /// ΣDiv Seit (total dividends received for this position)
     pub dividends_total: f64,
+    /// Per-portfolio breakdown (combined view only)
     pub portfolios: Vec<PortfolioHolding>,
     pub custom_logo: Option<String>,
+    /// Set when grouped by portfolio
+    pub portfolio_id: Option<i64>,
+    pub portfolio_name: Option<String>,
 }
 
 /// Get all holdings aggregated by ISIN across all portfolios
 /// Uses transaction sums (like Portfolio Performance's PortfolioSnapshot.java)
 /// NOT FIFO lots - FIFO is only for cost basis calculation!
+///
+/// `group_by: "portfolio"` returns one row per ISIN and portfolio instead
+/// (dividends split by the shares currently held in each portfolio).
 #[command]
-pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
+pub fn get_all_holdings(group_by: Option<HoldingsGroupBy>) -> Result<Vec<AggregatedHolding>, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    load_all_holdings(conn, group_by.unwrap_or_default())
+}
+
+fn load_all_holdings(
+    conn: &rusqlite::Connection,
+    group_by: HoldingsGroupBy,
+) -> Result<Vec<AggregatedHolding>, String> {
+    let by_portfolio = group_by == HoldingsGroupBy::Portfolio;
+
     // Get base currency for value conversion
     let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
     let today = Utc::now().date_naive();
@@ -825,7 +1261,13 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
     // then aggregate by ISIN. This ensures correct values when multiple securities
     // share the same ISIN but have different prices or currencies.
     // shares = SUM(BUY/TRANSFER_IN/DELIVERY_INBOUND) - SUM(SELL/TRANSFER_OUT/DELIVERY_OUTBOUND)
-    let security_holdings_sql = "
+    let (portfolio_columns, portfolio_group) = if by_portfolio {
+        ("p.id, p.name", ", p.id")
+    } else {
+        ("NULL, NULL", "")
+    };
+    let security_holdings_sql = format!(
+        "
         SELECT
             s.id as security_id,
             COALESCE(s.isin, s.uuid) as identifier,
@@ -837,16 +1279,19 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
                 WHEN t.txn_type IN ('SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND') THEN -t.shares
                 ELSE 0
             END) as net_shares,
-            lp.value as latest_price
+            lp.value as latest_price,
+            {}
         FROM pp_txn t
         JOIN pp_portfolio p ON p.id = t.owner_id AND t.owner_type = 'portfolio'
         JOIN pp_security s ON s.id = t.security_id
         LEFT JOIN pp_latest_price lp ON lp.security_id = s.id
         WHERE t.shares IS NOT NULL
-        GROUP BY s.id
+        GROUP BY s.id{}
         HAVING net_shares > 0
         ORDER BY net_shares * COALESCE(lp.value, 0) DESC
-    ";
+    ",
+        portfolio_columns, portfolio_group
+    );
 
     // Structure to hold security-level data before ISIN aggregation
     struct SecurityHolding {
@@ -858,11 +1303,13 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
         shares: f64,
         price: Option<f64>,
         value_in_base: Option<f64>,
+        portfolio_id: Option<i64>,
+        portfolio_name: Option<String>,
     }
 
     let mut security_holdings: Vec<SecurityHolding> = Vec::new();
     {
-        let mut stmt = conn.prepare(security_holdings_sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&security_holdings_sql).map_err(|e| e.to_string())?;
         let rows = stmt
             .query_map([], |row| {
                 Ok((
@@ -873,12 +1320,24 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
                     row.get::<_, Option<String>>(4)?, // custom_logo
                     row.get::<_, i64>(5)?,      // net_shares
                     row.get::<_, Option<i64>>(6)?, // latest_price
+                    row.get::<_, Option<i64>>(7)?, // portfolio_id
+                    row.get::<_, Option<String>>(8)?, // portfolio_name
                 ))
             })
             .map_err(|e| e.to_string())?;
 
         for row in rows.flatten() {
-            let (security_id, identifier, name, security_currency, custom_logo, shares_raw, price_raw) = row;
+            let (
+                security_id,
+                identifier,
+                name,
+                security_currency,
+                custom_logo,
+                shares_raw,
+                price_raw,
+                portfolio_id,
+                portfolio_name,
+            ) = row;
             let shares = shares::to_decimal(shares_raw);
             let price = price_raw.map(|p| {
                 let price_decimal = prices::to_decimal(p);
@@ -919,6 +1378,8 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
                 shares,
                 price,
                 value_in_base,
+                portfolio_id,
+                portfolio_name,
             });
         }
     }
@@ -926,8 +1387,13 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
     // STEP 2: Get cost basis from FIFO lots using SINGLE SOURCE OF TRUTH
     // Uses fifo::get_cost_basis_by_security_converted() which converts each lot individually
     // WICHTIG: Nicht GROUP BY verwenden! Securities können Lots in verschiedenen Währungen haben!
-    let cost_basis_map = crate::fifo::get_cost_basis_by_security_converted(conn, &base_currency)
-        .unwrap_or_default();
+    let portfolio_cost_basis_map =
+        crate::fifo::get_cost_basis_by_security_portfolio_converted(conn, &base_currency)
+            .unwrap_or_default();
+    let mut cost_basis_map: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+    for ((identifier, _), cost) in &portfolio_cost_basis_map {
+        *cost_basis_map.entry(identifier.clone()).or_insert(0.0) += cost;
+    }
 
     // STEP 3: Get dividend sums per security (ΣDiv Seit)
     // Sum all DIVIDENDS transactions for each security across all accounts
@@ -962,13 +1428,20 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
         }
     }
 
-    // STEP 4: Aggregate security holdings by ISIN
+    // Dividends are booked on accounts, not portfolios: per-portfolio rows get
+    // their share of the total by the shares currently held
+    let mut shares_by_identifier: std::collections::HashMap<&str, f64> = std::collections::HashMap::new();
+    for holding in &security_holdings {
+        *shares_by_identifier.entry(holding.identifier.as_str()).or_insert(0.0) += holding.shares;
+    }
+
+    // STEP 4: Aggregate security holdings by ISIN (and portfolio)
     // Group by identifier and sum shares/values (values already converted to base currency)
-    let mut isin_groups: std::collections::HashMap<String, Vec<&SecurityHolding>> =
+    let mut isin_groups: std::collections::HashMap<(String, Option<i64>), Vec<&SecurityHolding>> =
         std::collections::HashMap::new();
     for holding in &security_holdings {
         isin_groups
-            .entry(holding.identifier.clone())
+            .entry((holding.identifier.clone(), holding.portfolio_id))
             .or_default()
             .push(holding);
     }
@@ -976,7 +1449,7 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
     // Build aggregated holdings from groups
     let mut holdings: Vec<AggregatedHolding> = isin_groups
         .into_iter()
-        .map(|(identifier, group)| {
+        .map(|((identifier, portfolio_id), group)| {
             // Sum shares and values across all securities with this ISIN
             let total_shares: f64 = group.iter().map(|h| h.shares).sum();
             let total_value: Option<f64> = {
@@ -1002,7 +1475,13 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
             };
 
             // Get cost basis from FIFO map (already converted to base currency by SSOT function)
-            let cost_basis = cost_basis_map.get(&identifier).copied().unwrap_or(0.0);
+            let cost_basis = match portfolio_id {
+                Some(portfolio_id) => portfolio_cost_basis_map
+                    .get(&(identifier.clone(), portfolio_id))
+                    .copied(),
+                None => cost_basis_map.get(&identifier).copied(),
+            }
+            .unwrap_or(0.0);
 
             // Calculate purchase price per share (Einstandskurs)
             let purchase_price = if total_shares > 0.0 {
@@ -1024,12 +1503,18 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
                 .cloned()
                 .unwrap_or((0, base_currency.clone()));
             let div_raw = div_cents as f64 / 100.0;
-            let dividends_total = if div_currency == base_currency {
+            let dividends_all_portfolios = if div_currency == base_currency {
                 div_raw
             } else {
                 currency::convert(conn, div_raw, &div_currency, &base_currency, today)
                     .unwrap_or(div_raw)
             };
+            let dividends_total = match shares_by_identifier.get(identifier.as_str()) {
+                Some(&all_shares) if portfolio_id.is_some() && all_shares > 0.0 => {
+                    dividends_all_portfolios * total_shares / all_shares
+                }
+                _ => dividends_all_portfolios,
+            };
 
             AggregatedHolding {
                 isin: identifier,
@@ -1046,6 +1531,8 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
                 dividends_total,
                 portfolios: Vec::new(),
                 custom_logo: primary.custom_logo.clone(),
+                portfolio_id,
+                portfolio_name: primary.portfolio_name.clone(),
             }
         })
         .collect();
@@ -1058,10 +1545,16 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
+    // Rows grouped by portfolio are their own breakdown
+    if by_portfolio {
+        return Ok(holdings);
+    }
+
     // STEP 5: Get per-portfolio breakdown using transaction sums (NOT FIFO lots!)
     let portfolio_sql = "
         SELECT
             COALESCE(s.isin, s.uuid) as identifier,
+            p.id as portfolio_id,
             p.name as portfolio_name,
             SUM(CASE
                 WHEN t.txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN t.shares
@@ -1082,8 +1575,9 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
         .query_map([], |row| {
             Ok((
                 row.get::<_, String>(0)?, // identifier
-                row.get::<_, String>(1)?, // portfolio_name
-                row.get::<_, i64>(2)?,    // net_shares
+                row.get::<_, i64>(1)?,    // portfolio_id
+                row.get::<_, String>(2)?, // portfolio_name
+                row.get::<_, i64>(3)?,    // net_shares
             ))
         })
         .map_err(|e| e.to_string())?
@@ -1094,7 +1588,7 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
     let mut portfolio_map: std::collections::HashMap<String, Vec<PortfolioHolding>> =
         std::collections::HashMap::new();
 
-    for (identifier, portfolio_name, shares_raw) in portfolio_rows {
+    for (identifier, portfolio_id, portfolio_name, shares_raw) in portfolio_rows {
         let shares = shares::to_decimal(shares_raw);
         let entry = portfolio_map.entry(identifier.clone()).or_default();
 
@@ -1119,10 +1613,16 @@ pub fn get_all_holdings() -> Result<Vec<AggregatedHolding>, String> {
             None
         };
 
+        let cost_basis = portfolio_cost_basis_map
+            .get(&(identifier, portfolio_id))
+            .copied()
+            .unwrap_or(0.0);
         entry.push(PortfolioHolding {
+            portfolio_id,
             portfolio_name,
             shares,
             value,
+            cost_basis,
         });
     }
 
@@ -1595,73 +2095,249 @@ pub fn get_invested_capital_history() -> Result<Vec<PortfolioValuePoint>, String
     let current_cost_basis = crate::fifo::get_total_cost_basis_converted(conn, None, &base_currency)
         .unwrap_or(0.0);
 
-    // STEP 3: Build approximate history using cumulative transactions
+    // STEP 3: Build approximate history using cumulative transactions (cached)
     // Then scale to match the correct current value
-    let txn_sql = r#"
+    let mut history = load_invested_capital_raw_history(conn, &base_currency, today)?;
+    let cumulative = history.last().map(|p| p.value).unwrap_or(0.0);
+
+    // STEP 4: Scale the history so the last value matches the correct FIFO cost basis
+    if !history.is_empty() && cumulative > 0.0 {
+        let scale_factor = current_cost_basis / cumulative;
+        for point in &mut history {
+            point.value *= scale_factor;
+        }
+    }
+
+    Ok(history)
+}
+
+/// `pp_settings` key holding the cache key of `pp_invested_capital_cache`
+const INVESTED_CAPITAL_CACHE_SETTING: &str = "invested_capital_cache_key";
+
+/// Transaction filter shared by the invested capital history and its cache key
+const INVESTED_CAPITAL_TXN_FILTER: &str = "t.owner_type = 'portfolio'
+          AND t.txn_type IN ('BUY', 'SELL', 'DELIVERY_INBOUND', 'DELIVERY_OUTBOUND')
+          AND t.amount IS NOT NULL";
+
+/// Identifies the transaction state a cached invested capital history was built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvestedCapitalCacheKey {
+    base_currency: String,
+    /// Amounts are converted with today's rates, so a cache is only valid for one day
+    computed_on: String,
+    txn_count: i64,
+    max_updated_at: Option<String>,
+}
+
+/// Unscaled cumulative invested capital per transaction date.
+///
+/// The result is cached in `pp_invested_capital_cache`. If only new transactions
+/// were added since (newer `updated_at`, no edits or deletions), only the tail
+/// starting at the earliest new transaction date is recomputed. Any other
+/// change, a new day or a different base currency trigger a full rebuild.
+///
+/// `updated_at` is compared as text, so writers must use RFC 3339 like
+/// `create_transaction`; transaction CRUD additionally drops the cache via
+/// `invalidate_invested_capital_cache`.
+fn load_invested_capital_raw_history(
+    conn: &rusqlite::Connection,
+    base_currency: &str,
+    today: chrono::NaiveDate,
+) -> Result<Vec<PortfolioValuePoint>, String> {
+    let (txn_count, max_updated_at): (i64, Option<String>) = conn
+        .query_row(
+            &format!("SELECT COUNT(*), MAX(t.updated_at) FROM pp_txn t WHERE {}", INVESTED_CAPITAL_TXN_FILTER),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let key = InvestedCapitalCacheKey {
+        base_currency: base_currency.to_string(),
+        computed_on: today.to_string(),
+        txn_count,
+        max_updated_at,
+    };
+
+    let cached_key: Option<InvestedCapitalCacheKey> = conn
+        .query_row(
+            "SELECT value FROM pp_settings WHERE key = ?1",
+            params![INVESTED_CAPITAL_CACHE_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+
+    // Cache hit: nothing changed
+    if cached_key.as_ref() == Some(&key) {
+        return load_invested_capital_cache(conn, None);
+    }
+
+    // Only new transactions since the cache was built? Then recompute from their earliest date.
+    let tail_from: Option<String> = cached_key
+        .filter(|cached| {
+            cached.base_currency == key.base_currency
+                && cached.computed_on == key.computed_on
+                && cached.max_updated_at.is_some()
+        })
+        .and_then(|cached| {
+            let (changed, min_date): (i64, Option<String>) = conn
+                .query_row(
+                    &format!(
+                        "SELECT COUNT(*), MIN(date(t.date)) FROM pp_txn t WHERE {} AND t.updated_at > ?1",
+                        INVESTED_CAPITAL_TXN_FILTER
+                    ),
+                    params![cached.max_updated_at],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .ok()?;
+            // Edits or deletions change the count differently than pure inserts
+            if changed > 0 && key.txn_count - cached.txn_count == changed {
+                min_date
+            } else {
+                None
+            }
+        });
+
+    let mut history = match &tail_from {
+        Some(from) => load_invested_capital_cache(conn, Some(from))?,
+        None => Vec::new(),
+    };
+    let start = history.last().map(|p| p.value).unwrap_or(0.0);
+    let tail = compute_invested_capital_points(conn, base_currency, today, tail_from.as_deref(), start)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    match &tail_from {
+        Some(from) => tx.execute("DELETE FROM pp_invested_capital_cache WHERE date >= ?1", params![from]),
+        None => tx.execute("DELETE FROM pp_invested_capital_cache", []),
+    }
+    .map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare("INSERT INTO pp_invested_capital_cache (date, value) VALUES (?1, ?2)")
+            .map_err(|e| e.to_string())?;
+        for point in &tail {
+            stmt.execute(params![point.date, point.value]).map_err(|e| e.to_string())?;
+        }
+    }
+    let key_json = serde_json::to_string(&key).map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO pp_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![INVESTED_CAPITAL_CACHE_SETTING, key_json],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    history.extend(tail);
+    Ok(history)
+}
+
+/// Drop the invested capital cache so the next request rebuilds it
+///
+/// Needed for changes the cache key cannot detect (e.g. an edit and a deletion
+/// with an unchanged count).
+pub(crate) fn invalidate_invested_capital_cache(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM pp_settings WHERE key = ?1",
+        params![INVESTED_CAPITAL_CACHE_SETTING],
+    )?;
+    conn.execute("DELETE FROM pp_invested_capital_cache", [])?;
+    Ok(())
+}
+
+/// Cached points, optionally only those before `before_date`
+fn load_invested_capital_cache(
+    conn: &rusqlite::Connection,
+    before_date: Option<&str>,
+) -> Result<Vec<PortfolioValuePoint>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, value FROM pp_invested_capital_cache
+             WHERE ?1 IS NULL OR date < ?1
+             ORDER BY date",
+        )
+        .map_err(|e| e.to_string())?;
+    let points = stmt
+        .query_map(params![before_date], |row| {
+            Ok(PortfolioValuePoint {
+                date: row.get(0)?,
+                value: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(points)
+}
+
+/// Cumulative invested capital per date, starting at `from_date` with `start` as carry-over
+fn compute_invested_capital_points(
+    conn: &rusqlite::Connection,
+    base_currency: &str,
+    today: chrono::NaiveDate,
+    from_date: Option<&str>,
+    start: f64,
+) -> Result<Vec<PortfolioValuePoint>, String> {
+    let txn_sql = format!(
+        r#"
         SELECT
             date(t.date) as txn_date,
             t.txn_type,
             t.amount,
             t.currency
         FROM pp_txn t
-        WHERE t.owner_type = 'portfolio'
-          AND t.txn_type IN ('BUY', 'SELL', 'DELIVERY_INBOUND', 'DELIVERY_OUTBOUND')
-          AND t.amount IS NOT NULL
+        WHERE {}
+          AND (?1 IS NULL OR date(t.date) >= ?1)
         ORDER BY txn_date, t.id
-    "#;
+    "#,
+        INVESTED_CAPITAL_TXN_FILTER
+    );
 
-    let mut cumulative = 0.0;
+    let mut cumulative = start;
     let mut history: Vec<PortfolioValuePoint> = Vec::new();
 
-    {
-        let mut stmt = conn.prepare(txn_sql).map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map([], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, i64>(2)?,
-                    row.get::<_, Option<String>>(3)?.unwrap_or_default(),
-                ))
-            })
-            .map_err(|e| e.to_string())?;
-
-        for row in rows.flatten() {
-            let (date, txn_type, amount_cents, txn_currency) = row;
-            let amount = amount_cents as f64 / 100.0;
+    let mut stmt = conn.prepare(&txn_sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
 
-            // Currency conversion
-            let amount_in_base = if !txn_currency.is_empty() && txn_currency != base_currency {
-                currency::convert(conn, amount, &txn_currency, &base_currency, today)
-                    .unwrap_or(amount)
-            } else {
-                amount
-            };
+    for row in rows.flatten() {
+        let (date, txn_type, amount_cents, txn_currency) = row;
+        let amount = amount_cents as f64 / 100.0;
 
-            let is_buy = txn_type == "BUY" || txn_type == "DELIVERY_INBOUND";
-            if is_buy {
-                cumulative += amount_in_base;
-            } else {
-                cumulative -= amount_in_base;
-            }
+        // Currency conversion
+        let amount_in_base = if !txn_currency.is_empty() && txn_currency != base_currency {
+            currency::convert(conn, amount, &txn_currency, base_currency, today)
+                .unwrap_or(amount)
+        } else {
+            amount
+        };
 
-            // Update or add entry
-            if let Some(last) = history.last_mut() {
-                if last.date == date {
-                    last.value = cumulative;
-                    continue;
-                }
-            }
-            history.push(PortfolioValuePoint { date, value: cumulative });
+        let is_buy = txn_type == "BUY" || txn_type == "DELIVERY_INBOUND";
+        if is_buy {
+            cumulative += amount_in_base;
+        } else {
+            cumulative -= amount_in_base;
         }
-    }
 
-    // STEP 4: Scale the history so the last value matches the correct FIFO cost basis
-    if !history.is_empty() && cumulative > 0.0 {
-        let scale_factor = current_cost_basis / cumulative;
-        for point in &mut history {
-            point.value *= scale_factor;
+        // Update or add entry
+        if let Some(last) = history.last_mut() {
+            if last.date == date {
+                last.value = cumulative;
+                continue;
+            }
         }
+        history.push(PortfolioValuePoint { date, value: cumulative });
     }
 
     Ok(history)
@@ -1931,3 +2607,332 @@ pub fn get_fifo_cost_basis_history(security_id: i64) -> Result<SecurityChartData
         trades,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn setup_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE pp_invested_capital_cache (date TEXT PRIMARY KEY, value REAL NOT NULL);
+            CREATE TABLE pp_txn (
+                id INTEGER PRIMARY KEY, owner_type TEXT, txn_type TEXT, date TEXT,
+                amount INTEGER, currency TEXT, updated_at TEXT
+            );
+            INSERT INTO pp_txn VALUES
+                (1, 'portfolio', 'BUY', '2024-01-10', 100000, 'EUR', '2024-01-10 09:00:00'),
+                (2, 'portfolio', 'BUY', '2024-02-10', 50000, 'EUR', '2024-02-10 09:00:00'),
+                (3, 'account', 'DEPOSIT', '2024-01-01', 999900, 'EUR', '2024-01-01 09:00:00');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn values(points: &[PortfolioValuePoint]) -> Vec<(String, f64)> {
+        points.iter().map(|p| (p.date.clone(), p.value)).collect()
+    }
+
+    #[test]
+    fn test_invested_capital_cache_recomputes_tail_only() {
+        let conn = setup_db();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+        let history = load_invested_capital_raw_history(&conn, "EUR", today).unwrap();
+        assert_eq!(
+            values(&history),
+            vec![("2024-01-10".to_string(), 1000.0), ("2024-02-10".to_string(), 1500.0)]
+        );
+
+        // Unchanged transactions: served from the cache
+        conn.execute("UPDATE pp_invested_capital_cache SET value = 1001.0 WHERE date = '2024-01-10'", [])
+            .unwrap();
+        let cached = load_invested_capital_raw_history(&conn, "EUR", today).unwrap();
+        assert_eq!(cached[0].value, 1001.0);
+
+        // New transaction: only points from its date on are recomputed
+        conn.execute(
+            "INSERT INTO pp_txn VALUES (4, 'portfolio', 'SELL', '2024-02-10', 20000, 'EUR', '2024-03-01 09:00:00')",
+            [],
+        )
+        .unwrap();
+        let tail = load_invested_capital_raw_history(&conn, "EUR", today).unwrap();
+        assert_eq!(
+            values(&tail),
+            vec![("2024-01-10".to_string(), 1001.0), ("2024-02-10".to_string(), 1301.0)]
+        );
+
+        // Deletions force a full rebuild
+        conn.execute("DELETE FROM pp_txn WHERE id = 4", []).unwrap();
+        let rebuilt = load_invested_capital_raw_history(&conn, "EUR", today).unwrap();
+        assert_eq!(values(&rebuilt), values(&history));
+    }
+
+    #[test]
+    fn test_query_transactions_filters_and_pages() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (5, 's5', 'Apple', 'USD');
+             INSERT INTO pp_account (id, uuid, name) VALUES (20, 'a20', 'Konto');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (10, 'p10', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id)
+             VALUES (1, 't1', 'portfolio', 10, 'BUY', '2023-06-01', 100, 'EUR', 5),
+                    (2, 't2', 'portfolio', 10, 'DELIVERY_INBOUND', '2024-01-02', 100, 'EUR', 5),
+                    (3, 't3', 'portfolio', 10, 'SELL', '2024-02-01', 100, 'EUR', 5),
+                    (4, 't4', 'account', 20, 'DEPOSIT', '2024-01-03', 100, 'EUR', NULL),
+                    (5, 't5', 'portfolio', 10, 'BUY', '2024-03-01', 100, 'EUR', 5);",
+        )
+        .unwrap();
+
+        let ids = |page: &TransactionPage| page.transactions.iter().map(|t| t.id).collect::<Vec<_>>();
+
+        // BUY includes deliveries, newest first
+        let filter = TransactionFilter {
+            txn_type: Some("Kauf".to_string()),
+            ..Default::default()
+        };
+        let page = query_transactions(&conn, &filter, Some(2), None).unwrap();
+        assert_eq!(ids(&page), vec![5, 2]);
+        assert_eq!(page.total_count, 3);
+
+        let page = query_transactions(&conn, &filter, Some(2), Some(2)).unwrap();
+        assert_eq!(ids(&page), vec![1]);
+        assert_eq!(page.total_count, 3);
+
+        let filter = TransactionFilter {
+            owner_type: Some("portfolio".to_string()),
+            start_date: Some("2024-01-01".to_string()),
+            end_date: Some("2024-02-15".to_string()),
+            ..Default::default()
+        };
+        let page = query_transactions(&conn, &filter, None, None).unwrap();
+        assert_eq!(ids(&page), vec![3, 2]);
+        assert_eq!(page.total_count, 2);
+
+        let filter = TransactionFilter {
+            year: Some(2023),
+            ..Default::default()
+        };
+        assert_eq!(ids(&query_transactions(&conn, &filter, None, None).unwrap()), vec![1]);
+
+        let filter = TransactionFilter {
+            end_date: Some("kein Datum".to_string()),
+            ..Default::default()
+        };
+        assert!(query_transactions(&conn, &filter, None, None).is_err());
+    }
+
+    #[test]
+    fn test_delete_price_outliers() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (5, 's5', 'Apple', 'USD');
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                (5, '2024-01-01', 10000000000), (5, '2024-01-02', 10100000000),
+                (5, '2024-01-03', 25000000000), (5, '2024-01-04', 10200000000),
+                (5, '2024-01-05', 12000000000), (5, '2024-01-08', 12100000000);",
+        )
+        .unwrap();
+
+        // The spike is flagged but not the return to normal after it,
+        // the 18% move only with a lower threshold
+        let prices = load_price_history(&conn, 5, None, None).unwrap();
+        let reasons = |threshold| {
+            detect_outliers(&prices, threshold)
+                .summary
+                .outliers
+                .iter()
+                .map(|o| (o.date.clone(), o.reason))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(reasons(30.0), vec![("2024-01-03".to_string(), OutlierReason::Spike)]);
+        assert_eq!(
+            reasons(15.0),
+            vec![
+                ("2024-01-03".to_string(), OutlierReason::Spike),
+                ("2024-01-05".to_string(), OutlierReason::DailyMove),
+            ]
+        );
+        assert!(outlier_threshold(Some(0.0)).is_err());
+
+        let deleted = delete_outlier_prices(&conn, 5, 30.0).unwrap();
+        assert_eq!(deleted.len(), 1);
+        let remaining = load_price_history(&conn, 5, None, None).unwrap();
+        assert_eq!(remaining.len(), 5);
+        assert!(remaining.iter().all(|p| p.date != "2024-01-03"));
+    }
+
+    #[test]
+    fn test_backfill_price_gaps() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        // Thu, Fri, then Wed: Mon and Tue are missing, the weekend is skipped
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (5, 's5', 'Apple', 'USD');
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                (5, '2024-01-04', 10000000000), (5, '2024-01-05', 10000000000),
+                (5, '2024-01-10', 10500000000);",
+        )
+        .unwrap();
+
+        let interpolated = |conn: &rusqlite::Connection| {
+            let mut stmt = conn
+                .prepare("SELECT date, value FROM pp_price WHERE source = 'interpolated' ORDER BY date")
+                .unwrap();
+            stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+                .unwrap()
+                .flatten()
+                .collect::<Vec<_>>()
+        };
+
+        let result = backfill_gaps(&conn, 5, GapFillMethod::LastValue).unwrap();
+        assert_eq!((result.inserted, result.removed), (2, 0));
+        assert_eq!(
+            interpolated(&conn),
+            vec![
+                ("2024-01-08".to_string(), 10000000000),
+                ("2024-01-09".to_string(), 10000000000)
+            ]
+        );
+
+        // Recomputed with linear interpolation (Mon = 3/5, Tue = 4/5 of the way)
+        let result = backfill_gaps(&conn, 5, GapFillMethod::Linear).unwrap();
+        assert_eq!((result.inserted, result.removed), (2, 2));
+        assert_eq!(
+            interpolated(&conn),
+            vec![
+                ("2024-01-08".to_string(), 10300000000),
+                ("2024-01-09".to_string(), 10400000000)
+            ]
+        );
+
+        // A real price replaces the interpolated one
+        conn.execute(
+            "INSERT OR IGNORE INTO pp_price (security_id, date, value) VALUES (5, '2024-01-08', 10100000000)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(interpolated(&conn), vec![("2024-01-09".to_string(), 10400000000)]);
+        let value: i64 = conn
+            .query_row("SELECT value FROM pp_price WHERE date = '2024-01-08'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, 10100000000);
+    }
+
+    #[test]
+    fn test_search_transactions_fts() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency, isin, wkn, ticker)
+             VALUES (5, 's5', 'Nestlé S.A.', 'CHF', 'CH0038863350', 'A0Q4DC', 'NESN.SW'),
+                    (6, 's6', 'Apple Inc.', 'USD', 'US0378331005', '865985', 'AAPL');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (10, 'p10', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, note)
+             VALUES (1, 't1', 'portfolio', 10, 'BUY', '2024-01-02', 100, 'CHF', 5, NULL),
+                    (2, 't2', 'portfolio', 10, 'BUY', '2024-01-03', 100, 'USD', 6, 'Sparplan Januar'),
+                    (3, 't3', 'portfolio', 10, 'SELL', '2024-02-01', 100, 'USD', 6, 'Teilverkauf');",
+        )
+        .unwrap();
+
+        let search = |query: &str| {
+            let filter = TransactionFilter {
+                search: Some(query.to_string()),
+                ..Default::default()
+            };
+            let page = query_transactions(&conn, &filter, None, None).unwrap();
+            page.transactions.iter().map(|t| t.id).collect::<Vec<_>>()
+        };
+
+        // Diacritics are ignored, words match as prefix
+        assert_eq!(search("nestle"), vec![1]);
+        assert_eq!(search("CH0038"), vec![1]);
+        assert_eq!(search("apple spar"), vec![2]);
+        assert_eq!(search("AAPL"), vec![3, 2]);
+        assert!(search("\"OR*").is_empty());
+        assert!(search("  ").is_empty());
+
+        // Index follows note and security changes
+        conn.execute("UPDATE pp_txn SET note = 'Dividende Schweiz' WHERE id = 1", []).unwrap();
+        conn.execute("UPDATE pp_security SET ticker = 'NESN' WHERE id = 5", []).unwrap();
+        assert_eq!(search("schweiz nesn"), vec![1]);
+        conn.execute("DELETE FROM pp_txn WHERE id = 1", []).unwrap();
+        assert!(search("schweiz").is_empty());
+    }
+
+    #[test]
+    fn test_all_holdings_grouped_by_portfolio() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, isin, name, currency) VALUES (1, 's1', 'IE00B4L5Y983', 'World ETF', 'EUR');
+             INSERT INTO pp_account (id, uuid, name) VALUES (1, 'a1', 'Konto A'), (2, 'a2', 'Konto B');
+             INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'p1', 'Depot A', 1), (2, 'p2', 'Depot B', 2);
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-06-01', 10000000000);
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 't1', 'portfolio', 1, 'BUY', '2024-01-10', 80000, 'EUR', 1, 1000000000),
+                    (2, 't2', 'portfolio', 2, 'BUY', '2024-02-10', 45000, 'EUR', 1, 500000000),
+                    (3, 't3', 'account', 2, 'DIVIDENDS', '2024-05-01', 1200, 'EUR', 1, NULL);",
+        )
+        .unwrap();
+        crate::fifo::build_all_fifo_lots(&conn).unwrap();
+
+        let combined = load_all_holdings(&conn, HoldingsGroupBy::Security).unwrap();
+        assert_eq!(combined.len(), 1);
+        assert_eq!(combined[0].total_shares, 15.0);
+        assert_eq!(combined[0].cost_basis, 1250.0);
+        assert_eq!(combined[0].dividends_total, 12.0);
+        assert!(combined[0].portfolio_id.is_none());
+        let mut breakdown: Vec<(i64, f64, f64)> = combined[0]
+            .portfolios
+            .iter()
+            .map(|p| (p.portfolio_id, p.shares, p.cost_basis))
+            .collect();
+        breakdown.sort_by_key(|b| b.0);
+        assert_eq!(breakdown, vec![(1, 10.0, 800.0), (2, 5.0, 450.0)]);
+
+        let mut rows = load_all_holdings(&conn, HoldingsGroupBy::Portfolio).unwrap();
+        rows.sort_by_key(|h| h.portfolio_id);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].portfolio_name.as_deref(), Some("Depot A"));
+        assert_eq!((rows[0].total_shares, rows[0].cost_basis), (10.0, 800.0));
+        assert_eq!(rows[0].current_value, Some(1000.0));
+        assert_eq!(rows[0].dividends_total, 8.0);
+        assert_eq!((rows[1].total_shares, rows[1].cost_basis), (5.0, 450.0));
+        assert_eq!(rows[1].current_value, Some(500.0));
+        assert_eq!(rows[1].dividends_total, 4.0);
+        assert!(rows.iter().all(|h| h.portfolios.is_empty()));
+    }
+
+    #[test]
+    fn test_portfolio_dividends_with_shared_reference_account() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, isin, name, currency) VALUES (1, 's1', 'DE0007164600', 'SAP', 'EUR');
+             INSERT INTO pp_account (id, uuid, name) VALUES (1, 'a1', 'Verrechnungskonto');
+             INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'p1', 'Depot A', 1), (2, 'p2', 'Depot B', 1);
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 't1', 'portfolio', 1, 'BUY', '2024-01-10', 30000, 'EUR', 1, 300000000),
+                    (2, 't2', 'portfolio', 2, 'BUY', '2024-02-10', 10000, 'EUR', 1, 100000000),
+                    (3, 't3', 'account', 1, 'DIVIDENDS', '2024-05-01', 2000, 'EUR', 1, NULL);",
+        )
+        .unwrap();
+        crate::fifo::build_all_fifo_lots(&conn).unwrap();
+
+        let mut rows = load_all_holdings(&conn, HoldingsGroupBy::Portfolio).unwrap();
+        rows.sort_by_key(|h| h.portfolio_id);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].dividends_total, 15.0);
+        assert_eq!(rows[1].dividends_total, 5.0);
+
+        let combined = load_all_holdings(&conn, HoldingsGroupBy::Security).unwrap();
+        assert_eq!(combined[0].dividends_total, 20.0);
+    }
+}
@@ -170,6 +170,61 @@ fn get_month_name(month: u32) -> String {
     .to_string()
 }
 
+/// A booked dividend payment (net of taxes)
+struct DividendPayment {
+    date: String,
+    security_id: i64,
+    security_name: String,
+    security_isin: Option<String>,
+    amount: f64,
+    currency: String,
+    /// Shares the dividend was paid for (PP format × 10^8)
+    shares: Option<i64>,
+}
+
+/// Load dividend payments in a date range, ordered by date
+fn load_dividend_payments(
+    conn: &rusqlite::Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<DividendPayment>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT
+                t.date,
+                s.id,
+                s.name,
+                s.isin,
+                (t.amount - COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0)) / 100.0 as net_amount,
+                t.currency,
+                t.shares
+            FROM pp_txn t
+            JOIN pp_security s ON s.id = t.security_id
+            WHERE t.txn_type = 'DIVIDENDS'
+              AND t.date >= ?1 AND t.date <= ?2
+            ORDER BY t.date
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([start_date, end_date], |row| {
+            Ok(DividendPayment {
+                date: row.get(0)?,
+                security_id: row.get(1)?,
+                security_name: row.get(2)?,
+                security_isin: row.get(3)?,
+                amount: row.get(4)?,
+                currency: row.get(5)?,
+                shares: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.flatten().collect())
+}
+
 // ============================================================================
 // Commands
 // ============================================================================
@@ -209,43 +264,21 @@ pub fn get_dividend_calendar(year: i32, month: Option<u32>) -> Result<Vec<MonthC
     };
 
     // Get actual dividends
-    let mut stmt = conn
-        .prepare(
-            r#"
-            SELECT
-                t.date,
-                s.id,
-                s.name,
-                s.isin,
-                (t.amount - COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0)) / 100.0 as net_amount,
-                t.currency
-            FROM pp_txn t
-            JOIN pp_security s ON s.id = t.security_id
-            WHERE t.txn_type = 'DIVIDENDS'
-              AND t.date >= ?1 AND t.date <= ?2
-            ORDER BY t.date
-            "#,
-        )
-        .map_err(|e| e.to_string())?;
-
-    let rows = stmt
-        .query_map([&start_date, &end_date], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                row.get::<_, f64>(4)?,
-                row.get::<_, String>(5)?,
-            ))
-        })
-        .map_err(|e| e.to_string())?;
+    let payments = load_dividend_payments(conn, &start_date, &end_date)?;
 
     // Group by month
     let mut month_data: HashMap<u32, MonthCalendarData> = HashMap::new();
 
-    for row in rows.flatten() {
-        let (date, security_id, security_name, isin, amount, currency) = row;
+    for payment in payments {
+        let DividendPayment {
+            date,
+            security_id,
+            security_name,
+            security_isin: isin,
+            amount,
+            currency,
+            ..
+        } = payment;
         let parsed_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok();
         let m = parsed_date.map(|d| d.month()).unwrap_or(1);
 
@@ -284,6 +317,10 @@ pub fn get_dividend_patterns() -> Result<Vec<DividendPattern>, String> {
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    load_dividend_patterns(conn)
+}
+
+fn load_dividend_patterns(conn: &rusqlite::Connection) -> Result<Vec<DividendPattern>, String> {
     // Get securities with dividend history (last 3 years)
     let three_years_ago = chrono::Utc::now()
         .date_naive()
@@ -430,6 +467,15 @@ pub fn estimate_annual_dividends(year: Option<i32>) -> Result<DividendForecast,
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    build_dividend_forecast(conn, year, None)
+}
+
+/// Dividend forecast for the holdings of one portfolio (or all)
+fn build_dividend_forecast(
+    conn: &rusqlite::Connection,
+    year: Option<i32>,
+    portfolio_id: Option<i64>,
+) -> Result<DividendForecast, String> {
     let forecast_year = year.unwrap_or_else(|| chrono::Utc::now().date_naive().year());
     let current_month = chrono::Utc::now().date_naive().month();
 
@@ -442,7 +488,7 @@ pub fn estimate_annual_dividends(year: Option<i32>) -> Result<DividendForecast,
         .unwrap_or_else(|_| "EUR".to_string());
 
     // Get patterns
-    let patterns = get_dividend_patterns()?;
+    let patterns = load_dividend_patterns(conn)?;
 
     // Get current holdings
     let mut holdings_stmt = conn
@@ -455,6 +501,7 @@ pub fn estimate_annual_dividends(year: Option<i32>) -> Result<DividendForecast,
             END) as total_shares
             FROM pp_txn
             WHERE owner_type = 'portfolio'
+              AND (?1 IS NULL OR owner_id = ?1)
             GROUP BY security_id
             HAVING total_shares > 0
             "#,
@@ -462,7 +509,7 @@ pub fn estimate_annual_dividends(year: Option<i32>) -> Result<DividendForecast,
         .map_err(|e| e.to_string())?;
 
     let holdings: HashMap<i64, f64> = holdings_stmt
-        .query_map([], |row| Ok((row.get::<_, i64>(0)?, shares::to_decimal(row.get::<_, i64>(1)?))))
+        .query_map([portfolio_id], |row| Ok((row.get::<_, i64>(0)?, shares::to_decimal(row.get::<_, i64>(1)?))))
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
@@ -643,6 +690,58 @@ pub fn get_portfolio_dividend_yield() -> Result<f64, String> {
     Ok((total_dividends / total_value) * 100.0)
 }
 
+/// Yield on cost: expected annual dividends relative to the cost basis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YieldOnCost {
+    pub portfolio_id: Option<i64>,
+    pub currency: String,
+    /// Expected dividends for a full year at current holdings (base currency)
+    pub annual_dividends: f64,
+    /// FIFO cost basis of the open positions (base currency)
+    pub cost_basis: f64,
+    /// In percent, `None` without cost basis
+    pub yield_on_cost: Option<f64>,
+}
+
+/// Get yield on cost (annual dividends / cost basis) for a portfolio or all
+#[command]
+pub fn get_yield_on_cost(portfolio_id: Option<i64>) -> Result<YieldOnCost, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    calculate_yield_on_cost(conn, portfolio_id)
+}
+
+fn calculate_yield_on_cost(conn: &rusqlite::Connection, portfolio_id: Option<i64>) -> Result<YieldOnCost, String> {
+    let forecast = build_dividend_forecast(conn, None, portfolio_id)?;
+    let today = chrono::Utc::now().date_naive();
+
+    // Every expected payment counts, including those already received this year
+    let annual_dividends: f64 = forecast
+        .by_security
+        .iter()
+        .map(|security| {
+            let amount: f64 = security.expected_payments.iter().map(|p| p.estimated_amount).sum();
+            crate::currency::convert(conn, amount, &security.pattern.currency, &forecast.currency, today)
+                .unwrap_or(amount)
+        })
+        .sum();
+
+    let cost_basis = crate::fifo::get_total_cost_basis_converted(conn, portfolio_id, &forecast.currency)?;
+    let yield_on_cost = (cost_basis > 0.0).then(|| annual_dividends / cost_basis * 100.0);
+
+    Ok(YieldOnCost {
+        portfolio_id,
+        currency: forecast.currency,
+        annual_dividends,
+        cost_basis,
+        yield_on_cost,
+    })
+}
+
 // ============================================================================
 // Ex-Dividend Types
 // ============================================================================
@@ -1225,3 +1324,449 @@ pub fn get_enhanced_dividend_calendar(
 
     Ok(result)
 }
+
+// ============================================================================
+// DRIP Simulation
+// ============================================================================
+
+/// A dividend reinvested in the DRIP simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DripReinvestment {
+    pub date: String,
+    pub security_id: i64,
+    pub security_name: String,
+    /// Dividend cash incl. dividends on already reinvested shares (security currency)
+    pub dividend_amount: f64,
+    pub price: f64,
+    pub shares_bought: f64,
+    pub currency: String,
+}
+
+/// Actual vs. DRIP position of a security
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DripSecurityResult {
+    pub security_id: i64,
+    pub security_name: String,
+    pub actual_shares: f64,
+    /// Shares that would have been bought with the dividends
+    pub additional_shares: f64,
+    pub latest_price: Option<f64>,
+    pub currency: String,
+    /// Current value of the actual shares (base currency)
+    pub actual_value: f64,
+    /// Current value incl. reinvested shares (base currency)
+    pub drip_value: f64,
+}
+
+/// Result of the "what if all dividends had been reinvested" simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DripSimulationResult {
+    pub portfolio_id: Option<i64>,
+    pub start_date: String,
+    pub currency: String,
+    /// Current value of the actual holdings
+    pub actual_value: f64,
+    /// Hypothetical current value with all dividends reinvested
+    pub drip_value: f64,
+    pub difference: f64,
+    pub difference_percent: f64,
+    /// Dividends that could not be reinvested (no price on or before the payment date)
+    pub skipped_dividends: usize,
+    pub securities: Vec<DripSecurityResult>,
+    pub reinvestments: Vec<DripReinvestment>,
+}
+
+/// Dividend per share of a security on a payment date (security currency)
+struct DripDividend {
+    date: NaiveDate,
+    security_id: i64,
+    dividend_per_share: f64,
+}
+
+/// A single reinvestment of the simulation
+struct DripStep {
+    /// Index into the simulated dividends
+    dividend: usize,
+    cash: f64,
+    price: f64,
+    shares_bought: f64,
+}
+
+/// Outcome of the DRIP simulation
+struct DripOutcome {
+    additional_shares: HashMap<i64, f64>,
+    steps: Vec<DripStep>,
+    skipped: usize,
+}
+
+/// Shares held at a date from chronological (date, share delta) movements
+fn shares_at(movements: Option<&Vec<(NaiveDate, f64)>>, date: NaiveDate) -> f64 {
+    movements
+        .map(|m| m.iter().take_while(|(d, _)| *d <= date).map(|(_, s)| s).sum())
+        .unwrap_or(0.0)
+}
+
+/// Walk the dividends chronologically and reinvest each one at that day's price.
+///
+/// Dividends are paid on the actual plus the already reinvested shares (compounding).
+/// When actual shares are sold, the reinvested shares are reduced proportionally,
+/// as a DRIP position would be sold together with the original position.
+fn run_drip_simulation(
+    dividends: &[DripDividend],
+    holdings: &HashMap<i64, Vec<(NaiveDate, f64)>>,
+    as_of: NaiveDate,
+    price_at: impl Fn(i64, NaiveDate) -> Option<f64>,
+) -> DripOutcome {
+    let mut additional_shares: HashMap<i64, f64> = HashMap::new();
+    let mut last_actual: HashMap<i64, f64> = HashMap::new();
+    let mut steps = Vec::new();
+    let mut skipped = 0;
+
+    let mut apply_sales = |additional: &mut HashMap<i64, f64>, security_id: i64, actual: f64| {
+        if let Some(prev) = last_actual.insert(security_id, actual) {
+            if actual < prev && prev > 0.0 {
+                if let Some(extra) = additional.get_mut(&security_id) {
+                    *extra *= actual.max(0.0) / prev;
+                }
+            }
+        }
+    };
+
+    for (index, dividend) in dividends.iter().enumerate() {
+        let actual = shares_at(holdings.get(&dividend.security_id), dividend.date);
+        apply_sales(&mut additional_shares, dividend.security_id, actual);
+
+        let extra = additional_shares.get(&dividend.security_id).copied().unwrap_or(0.0);
+        let held = actual + extra;
+        if held <= 0.0 {
+            continue;
+        }
+
+        let Some(price) = price_at(dividend.security_id, dividend.date).filter(|p| *p > 0.0) else {
+            skipped += 1;
+            continue;
+        };
+
+        let cash = held * dividend.dividend_per_share;
+        let shares_bought = cash / price;
+        *additional_shares.entry(dividend.security_id).or_insert(0.0) += shares_bought;
+
+        steps.push(DripStep {
+            dividend: index,
+            cash,
+            price,
+            shares_bought,
+        });
+    }
+
+    // Sales after the last dividend
+    let security_ids: Vec<i64> = additional_shares.keys().copied().collect();
+    for security_id in security_ids {
+        let actual = shares_at(holdings.get(&security_id), as_of);
+        apply_sales(&mut additional_shares, security_id, actual);
+    }
+
+    DripOutcome {
+        additional_shares,
+        steps,
+        skipped,
+    }
+}
+
+/// Simulate dividend reinvestment (DRIP) since `start_date`.
+///
+/// Every dividend is reinvested in fractional shares of the paying security at
+/// that day's price (`pp_price`). Returns the hypothetical current value
+/// compared to the actual holdings.
+#[command]
+pub fn simulate_drip(portfolio_id: Option<i64>, start_date: String) -> Result<DripSimulationResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
+        .map_err(|_| format!("Ungültiges Startdatum: {}", start_date))?;
+    let today = chrono::Utc::now().date_naive();
+    let base_currency = crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    // Security names and currencies
+    let securities: HashMap<i64, (String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name, COALESCE(currency, ?1) FROM pp_security")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&base_currency], |row| {
+                Ok((row.get::<_, i64>(0)?, (row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+
+    // Share movements of the portfolio (and of all portfolios for dividends without shares)
+    let mut holdings: HashMap<i64, Vec<(NaiveDate, f64)>> = HashMap::new();
+    let mut all_holdings: HashMap<i64, Vec<(NaiveDate, f64)>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT t.owner_id, t.security_id, date(t.date),
+                    CASE
+                        WHEN t.txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN t.shares
+                        WHEN t.txn_type IN ('SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND') THEN -t.shares
+                        ELSE 0
+                    END
+                FROM pp_txn t
+                WHERE t.owner_type = 'portfolio' AND t.security_id IS NOT NULL AND t.shares IS NOT NULL
+                ORDER BY t.date
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        for (owner_id, security_id, date, delta) in rows.flatten() {
+            let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+                continue;
+            };
+            let movement = (date, shares::to_decimal(delta));
+            all_holdings.entry(security_id).or_default().push(movement);
+            if portfolio_id.map_or(true, |pid| pid == owner_id) {
+                holdings.entry(security_id).or_default().push(movement);
+            }
+        }
+    }
+
+    // Dividend per share, payments of the same security and day are combined
+    let payments = load_dividend_payments(conn, &start.to_string(), &today.to_string())?;
+    let mut grouped: Vec<(NaiveDate, i64, f64, Option<f64>)> = Vec::new();
+    for payment in payments {
+        let Some(date) = payment
+            .date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        let security_currency = securities
+            .get(&payment.security_id)
+            .map(|(_, c)| c.as_str())
+            .unwrap_or(&payment.currency);
+        let amount = crate::currency::convert(conn, payment.amount, &payment.currency, security_currency, date)
+            .unwrap_or(payment.amount);
+        let paid_shares = payment.shares.filter(|s| *s > 0).map(shares::to_decimal);
+
+        match grouped.last_mut() {
+            Some((d, sec, total, total_shares)) if *d == date && *sec == payment.security_id => {
+                *total += amount;
+                *total_shares = total_shares.zip(paid_shares).map(|(a, b)| a + b);
+            }
+            _ => grouped.push((date, payment.security_id, amount, paid_shares)),
+        }
+    }
+
+    let dividends: Vec<DripDividend> = grouped
+        .into_iter()
+        .filter_map(|(date, security_id, amount, paid_shares)| {
+            let per_share_base = paid_shares.unwrap_or_else(|| shares_at(all_holdings.get(&security_id), date));
+            (per_share_base > 0.0).then(|| DripDividend {
+                date,
+                security_id,
+                dividend_per_share: amount / per_share_base,
+            })
+        })
+        .collect();
+
+    let outcome = run_drip_simulation(&dividends, &holdings, today, |security_id, date| {
+        conn.query_row(
+            "SELECT value FROM pp_price WHERE security_id = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+            rusqlite::params![security_id, date.to_string()],
+            |row| row.get::<_, i64>(0),
+        )
+        .ok()
+        .map(|v| v as f64 / 100_000_000.0)
+    });
+
+    let name_and_currency = |security_id: i64| {
+        securities
+            .get(&security_id)
+            .cloned()
+            .unwrap_or_else(|| (format!("#{}", security_id), base_currency.clone()))
+    };
+
+    let reinvestments: Vec<DripReinvestment> = outcome
+        .steps
+        .iter()
+        .map(|step| {
+            let dividend = &dividends[step.dividend];
+            let (security_name, currency) = name_and_currency(dividend.security_id);
+            DripReinvestment {
+                date: dividend.date.to_string(),
+                security_id: dividend.security_id,
+                security_name,
+                dividend_amount: step.cash,
+                price: step.price,
+                shares_bought: step.shares_bought,
+                currency,
+            }
+        })
+        .collect();
+
+    // Current values of actual and reinvested shares
+    let mut security_results = Vec::new();
+    for (&security_id, movements) in &holdings {
+        let actual_shares = shares_at(Some(movements), today);
+        let additional_shares = outcome.additional_shares.get(&security_id).copied().unwrap_or(0.0);
+        if actual_shares <= 0.0 && additional_shares <= 0.0 {
+            continue;
+        }
+
+        let (security_name, currency) = name_and_currency(security_id);
+        let latest_price: Option<f64> = conn
+            .query_row(
+                r#"
+                SELECT COALESCE(
+                    (SELECT value FROM pp_latest_price WHERE security_id = ?1),
+                    (SELECT value FROM pp_price WHERE security_id = ?1 ORDER BY date DESC LIMIT 1)
+                )
+                "#,
+                [security_id],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .ok()
+            .flatten()
+            .map(|v| v as f64 / 100_000_000.0);
+
+        let to_base = |value: f64| {
+            crate::currency::convert(conn, value, &currency, &base_currency, today).unwrap_or(value)
+        };
+        let price = latest_price.unwrap_or(0.0);
+
+        security_results.push(DripSecurityResult {
+            security_id,
+            security_name,
+            actual_shares,
+            additional_shares,
+            latest_price,
+            actual_value: to_base(actual_shares * price),
+            drip_value: to_base((actual_shares + additional_shares) * price),
+            currency,
+        });
+    }
+    security_results.sort_by(|a, b| a.security_name.cmp(&b.security_name));
+
+    let actual_value: f64 = security_results.iter().map(|s| s.actual_value).sum();
+    let drip_value: f64 = security_results.iter().map(|s| s.drip_value).sum();
+    let difference = drip_value - actual_value;
+
+    Ok(DripSimulationResult {
+        portfolio_id,
+        start_date: start.to_string(),
+        currency: base_currency,
+        actual_value,
+        drip_value,
+        difference,
+        difference_percent: if actual_value > 0.0 {
+            difference / actual_value * 100.0
+        } else {
+            0.0
+        },
+        skipped_dividends: outcome.skipped,
+        securities: security_results,
+        reinvestments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_calculate_yield_on_cost() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        let today = chrono::Utc::now().date_naive();
+        let quarter = |n: u32| (today - chrono::Months::new(3 * n)).to_string();
+        conn.execute_batch(&format!(
+            r#"
+            INSERT INTO pp_import (id, file_path, version, base_currency) VALUES (1, 'a.portfolio', 1, 'EUR');
+            INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Allianz', 'EUR');
+            INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Konto', 'EUR');
+            INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'p1', 'Depot', 1), (2, 'p2', 'Depot 2', 1);
+            INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares) VALUES
+                (1, 't1', 'portfolio', 1, 'BUY', '{start}', 200000, 'EUR', 1, 10000000000),
+                (2, 't2', 'account', 1, 'DIVIDENDS', '{q1}', 5000, 'EUR', 1, 10000000000),
+                (3, 't3', 'account', 1, 'DIVIDENDS', '{q2}', 5000, 'EUR', 1, 10000000000);
+            INSERT INTO pp_fifo_lot (security_id, portfolio_id, purchase_txn_id, purchase_date, original_shares, remaining_shares, gross_amount, net_amount, currency)
+            VALUES (1, 1, 1, '{start}', 10000000000, 10000000000, 200000, 200000, 'EUR');
+            "#,
+            start = quarter(3),
+            q1 = quarter(1),
+            q2 = quarter(2),
+        ))
+        .unwrap();
+
+        let result = calculate_yield_on_cost(&conn, Some(1)).unwrap();
+        // Two payments a year of 0.50 per share on 100 shares
+        assert_eq!(result.cost_basis, 2000.0);
+        assert!((result.annual_dividends - 100.0).abs() < 1e-9);
+        assert!((result.yield_on_cost.unwrap() - 5.0).abs() < 1e-9);
+
+        // Portfolio without holdings
+        let empty = calculate_yield_on_cost(&conn, Some(2)).unwrap();
+        assert_eq!(empty.annual_dividends, 0.0);
+        assert_eq!(empty.yield_on_cost, None);
+    }
+
+    #[test]
+    fn test_drip_simulation_compounds_dividends() {
+        // 100 shares, two dividends of 1.00 per share at a price of 50
+        let holdings = HashMap::from([(1, vec![(date(2023, 1, 2), 100.0)])]);
+        let dividends = vec![
+            DripDividend { date: date(2023, 6, 1), security_id: 1, dividend_per_share: 1.0 },
+            DripDividend { date: date(2023, 12, 1), security_id: 1, dividend_per_share: 1.0 },
+        ];
+
+        let outcome = run_drip_simulation(&dividends, &holdings, date(2024, 1, 1), |_, _| Some(50.0));
+
+        // 100 € -> 2 shares, then 102 € -> 2.04 shares
+        assert_eq!(outcome.steps.len(), 2);
+        assert!((outcome.steps[1].cash - 102.0).abs() < 1e-9);
+        assert!((outcome.additional_shares[&1] - 4.04).abs() < 1e-9);
+        assert_eq!(outcome.skipped, 0);
+    }
+
+    #[test]
+    fn test_drip_simulation_sales_and_missing_prices() {
+        // Half of the position is sold after the first dividend
+        let holdings = HashMap::from([(1, vec![(date(2023, 1, 2), 100.0), (date(2023, 7, 1), -50.0)])]);
+        let dividends = vec![
+            DripDividend { date: date(2023, 6, 1), security_id: 1, dividend_per_share: 1.0 },
+            DripDividend { date: date(2023, 12, 1), security_id: 1, dividend_per_share: 1.0 },
+        ];
+
+        let outcome = run_drip_simulation(&dividends, &holdings, date(2024, 1, 1), |_, d| {
+            (d < date(2023, 12, 1)).then_some(50.0)
+        });
+
+        // 2 shares bought, halved by the sale, second dividend has no price
+        assert_eq!(outcome.steps.len(), 1);
+        assert_eq!(outcome.skipped, 1);
+        assert!((outcome.additional_shares[&1] - 1.0).abs() < 1e-9);
+    }
+}
@@ -1,14 +1,18 @@
-//! DivvyDiary export functionality
+//! DivvyDiary export and import functionality
 //!
 //! Uploads portfolio holdings and transaction history to DivvyDiary
 //! API endpoint: https://api.divvydiary.com/portfolios/{id}/import
 //!
+//! Imports the holdings of DivvyDiary portfolios as securities (matched by ISIN)
+//! and optionally into a watchlist
+//! API endpoint: https://api.divvydiary.com/portfolios/{id}
+//!
 //! Based on Portfolio Performance's DivvyDiaryUploader.java
 
 use crate::db;
 use crate::fifo;
 use chrono::{NaiveDate, NaiveDateTime};
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
@@ -93,6 +97,54 @@ pub struct DivvyDiaryExportResult {
     pub activities_count: i32,
 }
 
+/// Holding of a DivvyDiary portfolio (import)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DivvyDiaryHolding {
+    isin: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    wkn: Option<String>,
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    quantity: f64,
+}
+
+/// Portfolio details from DivvyDiary (import)
+#[derive(Debug, Clone, Deserialize)]
+struct DivvyDiaryPortfolioDetails {
+    #[serde(default, alias = "holdings")]
+    securities: Vec<DivvyDiaryHolding>,
+}
+
+/// Imported security
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivvyDiaryImportedSecurity {
+    pub security_id: i64,
+    pub isin: String,
+    pub name: String,
+    /// Quantity held in DivvyDiary (no transactions are created)
+    pub quantity: f64,
+    pub created: bool,
+}
+
+/// Import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivvyDiaryImportResult {
+    pub message: String,
+    pub securities_created: i32,
+    pub securities_updated: i32,
+    pub watchlist_id: Option<i64>,
+    pub watchlist_added: i32,
+    pub securities: Vec<DivvyDiaryImportedSecurity>,
+}
+
 /// Get available DivvyDiary portfolios for the user
 #[command]
 pub async fn get_divvydiary_portfolios(
@@ -188,6 +240,182 @@ pub async fn upload_to_divvydiary(
     })
 }
 
+/// Import holdings from DivvyDiary
+///
+/// divvydiary_portfolio_id: None = alle DivvyDiary-Portfolios
+/// watchlist_name: Wertpapiere zusätzlich in diese Watchlist übernehmen (wird bei Bedarf angelegt)
+#[command]
+pub async fn import_from_divvydiary(
+    api_key: String,
+    divvydiary_portfolio_id: Option<String>,
+    watchlist_name: Option<String>,
+) -> Result<DivvyDiaryImportResult, String> {
+    let portfolio_ids = match divvydiary_portfolio_id {
+        Some(id) => vec![id],
+        None => get_divvydiary_portfolios(api_key.clone())
+            .await?
+            .into_iter()
+            .map(|p| p.id)
+            .collect(),
+    };
+
+    let client = reqwest::Client::new();
+    let mut holdings: Vec<DivvyDiaryHolding> = Vec::new();
+    for portfolio_id in portfolio_ids {
+        let response = client
+            .get(format!("{}/portfolios/{}", DIVVYDIARY_API_BASE, portfolio_id))
+            .header("X-API-Key", &api_key)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| format!("Verbindungsfehler: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "DivvyDiary API Fehler: {} - Bitte API-Key überprüfen",
+                response.status()
+            ));
+        }
+
+        let body = response.text().await.map_err(|e| format!("Fehler beim Lesen der Antwort: {}", e))?;
+        let details: DivvyDiaryPortfolioDetails = serde_json::from_str(&body)
+            .map_err(|e| format!("Ungültige API-Antwort: {} - Body: {}", e, &body[..body.len().min(200)]))?;
+        holdings.extend(details.securities);
+    }
+
+    if holdings.iter().all(|h| h.isin.trim().is_empty()) {
+        return Err("Keine Wertpapiere mit ISIN in DivvyDiary gefunden".to_string());
+    }
+
+    let watchlist_id = match watchlist_name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => Some(super::ai_helpers::find_or_create_watchlist(name)?),
+        _ => None,
+    };
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Datenbank nicht initialisiert".to_string())?;
+
+    apply_divvydiary_holdings(conn, &holdings, watchlist_id).map_err(|e| e.to_string())
+}
+
+/// Create or update securities for the DivvyDiary holdings and fill the watchlist
+///
+/// Holdings of several portfolios are merged by ISIN. Existing securities only get
+/// missing master data (WKN, ticker, placeholder name) filled in.
+fn apply_divvydiary_holdings(
+    conn: &rusqlite::Connection,
+    holdings: &[DivvyDiaryHolding],
+    watchlist_id: Option<i64>,
+) -> rusqlite::Result<DivvyDiaryImportResult> {
+    let import_id: i64 = conn
+        .query_row("SELECT id FROM pp_import ORDER BY id DESC LIMIT 1", [], |r| r.get(0))
+        .unwrap_or(1);
+
+    let mut merged: Vec<DivvyDiaryHolding> = Vec::new();
+    for holding in holdings {
+        let isin = holding.isin.trim().to_uppercase();
+        if isin.is_empty() {
+            continue;
+        }
+        match merged.iter_mut().find(|h| h.isin == isin) {
+            Some(existing) => existing.quantity += holding.quantity,
+            None => merged.push(DivvyDiaryHolding {
+                isin,
+                ..holding.clone()
+            }),
+        }
+    }
+
+    let mut securities = Vec::with_capacity(merged.len());
+    let mut securities_created = 0;
+    let mut securities_updated = 0;
+    let mut watchlist_added = 0;
+
+    for holding in merged {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM pp_security WHERE isin = ?",
+                [&holding.isin],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let name = holding.name.clone().unwrap_or_else(|| holding.isin.clone());
+        let security_id = match existing {
+            Some(id) => {
+                let changed = conn.execute(
+                    r#"
+                    UPDATE pp_security SET
+                        name = CASE WHEN name = isin AND ?2 IS NOT NULL THEN ?2 ELSE name END,
+                        wkn = COALESCE(wkn, ?3),
+                        ticker = COALESCE(ticker, ?4),
+                        updated_at = datetime('now')
+                    WHERE id = ?1
+                      AND ((name = isin AND ?2 IS NOT NULL)
+                           OR (wkn IS NULL AND ?3 IS NOT NULL)
+                           OR (ticker IS NULL AND ?4 IS NOT NULL))
+                    "#,
+                    params![id, holding.name, holding.wkn, holding.symbol],
+                )?;
+                if changed > 0 {
+                    securities_updated += 1;
+                }
+                id
+            }
+            None => {
+                let currency = holding.currency.as_deref().unwrap_or("EUR");
+                let Some(id) = super::csv::find_or_create_security(
+                    conn,
+                    &holding.isin,
+                    holding.name.as_deref(),
+                    currency,
+                    import_id,
+                )?
+                else {
+                    continue;
+                };
+                conn.execute(
+                    "UPDATE pp_security SET wkn = ?2, ticker = ?3 WHERE id = ?1",
+                    params![id, holding.wkn, holding.symbol],
+                )?;
+                securities_created += 1;
+                id
+            }
+        };
+
+        if let Some(watchlist_id) = watchlist_id {
+            watchlist_added += conn.execute(
+                "INSERT OR IGNORE INTO pp_watchlist_security (watchlist_id, security_id) VALUES (?, ?)",
+                params![watchlist_id, security_id],
+            )? as i32;
+        }
+
+        securities.push(DivvyDiaryImportedSecurity {
+            security_id,
+            isin: holding.isin,
+            name,
+            quantity: holding.quantity,
+            created: existing.is_none(),
+        });
+    }
+
+    Ok(DivvyDiaryImportResult {
+        message: format!(
+            "Import erfolgreich: {} Wertpapiere ({} neu, {} aktualisiert)",
+            securities.len(),
+            securities_created,
+            securities_updated
+        ),
+        securities_created,
+        securities_updated,
+        watchlist_id,
+        watchlist_added,
+        securities,
+    })
+}
+
 /// Build the payload for DivvyDiary from local database
 /// portfolio_id: None = alle Portfolios, Some(id) = einzelnes Portfolio
 fn build_payload(
@@ -434,3 +662,60 @@ fn format_datetime(date: &str) -> String {
         format!("{}T12:00:00.000Z", &date[..10])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(isin: &str, name: Option<&str>, quantity: f64) -> DivvyDiaryHolding {
+        DivvyDiaryHolding {
+            isin: isin.to_string(),
+            name: name.map(str::to_string),
+            wkn: Some("A0B1C2".to_string()),
+            symbol: None,
+            currency: Some("USD".to_string()),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_apply_divvydiary_holdings() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_import (id, file_path, version, base_currency) VALUES (1, 'test.portfolio', 1, 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency, isin) VALUES (1, 's1', 'US0378331005', 'USD', 'US0378331005');
+             INSERT INTO pp_watchlist (id, name) VALUES (1, 'DivvyDiary');",
+        )
+        .unwrap();
+
+        let holdings = vec![
+            holding("us0378331005", Some("Apple"), 5.0),
+            holding("US5949181045", Some("Microsoft"), 2.0),
+            holding("US0378331005", Some("Apple"), 3.0),
+            holding("", None, 1.0),
+        ];
+        let result = apply_divvydiary_holdings(&conn, &holdings, Some(1)).unwrap();
+
+        assert_eq!(result.securities.len(), 2);
+        assert_eq!(result.securities_created, 1);
+        assert_eq!(result.securities_updated, 1);
+        assert_eq!(result.watchlist_added, 2);
+        assert!((result.securities[0].quantity - 8.0).abs() < 1e-9);
+
+        // Placeholder name replaced, WKN filled in
+        let (name, wkn): (String, String) = conn
+            .query_row("SELECT name, wkn FROM pp_security WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(name, "Apple");
+        assert_eq!(wkn, "A0B1C2");
+
+        // Second run changes nothing
+        let again = apply_divvydiary_holdings(&conn, &holdings, Some(1)).unwrap();
+        assert_eq!(again.securities_created, 0);
+        assert_eq!(again.securities_updated, 0);
+        assert_eq!(again.watchlist_added, 0);
+    }
+}
@@ -0,0 +1,439 @@
+//! Duplicate security commands for Tauri
+//!
+//! Repeated imports can leave several `pp_security` rows with the same ISIN
+//! (different UUIDs). `find_duplicate_securities` lists them per ISIN and
+//! `merge_securities` moves everything onto one security and deletes the rest.
+
+use crate::commands::audit::record_audit;
+use crate::db;
+use crate::events::{emit_data_changed, DataChangedPayload};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{command, AppHandle};
+
+/// Columns referencing `pp_security.id` that are repointed on merge.
+///
+/// Rows that would violate a unique constraint (e.g. a price on a date the
+/// kept security already has) are dropped; the kept security wins.
+const SECURITY_REFERENCES: &[(&str, &str)] = &[
+    ("pp_txn", "security_id"),
+    ("pp_price", "security_id"),
+    ("pp_security_event", "security_id"),
+    ("pp_watchlist_security", "security_id"),
+    ("pp_investment_plan", "security_id"),
+    ("pp_benchmark", "security_id"),
+    ("pp_corporate_action", "security_id"),
+    ("pp_corporate_action", "successor_security_id"),
+    ("pp_chart_annotation", "security_id"),
+    ("pp_chart_drawing", "security_id"),
+    ("pp_price_alert", "security_id"),
+    ("pp_pattern_history", "security_id"),
+    ("pp_allocation_target", "security_id"),
+    ("pp_fund_holdings", "fund_security_id"),
+    ("pp_ex_dividend", "security_id"),
+    ("pp_intraday_price", "security_id"),
+    ("pp_symbol_mapping", "security_id"),
+    ("pp_quote_sync_error", "security_id"),
+];
+
+/// Quote settings taken over from a duplicate when the kept security has none
+const FILLED_COLUMNS: &[&str] = &[
+    "wkn",
+    "ticker",
+    "feed",
+    "feed_url",
+    "latest_feed",
+    "latest_feed_url",
+];
+
+/// One security of a duplicate cluster
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSecurity {
+    pub id: i64,
+    pub uuid: String,
+    pub name: String,
+    pub currency: String,
+    pub ticker: Option<String>,
+    pub is_retired: bool,
+    pub transaction_count: i64,
+    pub price_count: i64,
+}
+
+/// Securities sharing one ISIN
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSecurityCluster {
+    pub isin: String,
+    /// Security with the most transactions (then prices, then lowest id)
+    pub suggested_keep_id: i64,
+    pub securities: Vec<DuplicateSecurity>,
+}
+
+/// Result of `merge_securities`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSecuritiesResult {
+    pub kept_id: i64,
+    pub merged_ids: Vec<i64>,
+    pub transactions_moved: usize,
+    pub prices_moved: usize,
+    pub fifo_lots: i64,
+}
+
+/// Find securities that share an ISIN (ignoring case and whitespace)
+#[command]
+pub fn find_duplicate_securities() -> Result<Vec<DuplicateSecurityCluster>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    load_duplicate_securities(conn).map_err(|e| e.to_string())
+}
+
+/// Merge `merge_ids` into `keep_id` and delete them
+///
+/// Transactions, prices, events, watchlist entries, classification
+/// assignments and other references move to the kept security in one
+/// database transaction; FIFO lots are rebuilt afterwards.
+#[command]
+pub fn merge_securities(
+    keep_id: i64,
+    merge_ids: Vec<i64>,
+    app: AppHandle,
+) -> Result<MergeSecuritiesResult, String> {
+    let result = {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        let result = merge_security_rows(conn, keep_id, &merge_ids)?;
+        record_audit(
+            conn,
+            "merge_securities",
+            "security",
+            Some(keep_id),
+            &format!("Wertpapiere {:?} zusammengeführt", result.merged_ids),
+        );
+        result
+    };
+
+    let mut security_ids = vec![keep_id];
+    security_ids.extend(&result.merged_ids);
+    emit_data_changed(
+        &app,
+        DataChangedPayload {
+            entity: "security".to_string(),
+            action: "merged".to_string(),
+            security_ids: Some(security_ids),
+            portfolio_ids: None,
+        },
+    );
+
+    Ok(result)
+}
+
+fn load_duplicate_securities(conn: &Connection) -> rusqlite::Result<Vec<DuplicateSecurityCluster>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT UPPER(TRIM(s.isin)), s.id, s.uuid, s.name, s.currency, s.ticker, s.is_retired,
+               (SELECT COUNT(*) FROM pp_txn t WHERE t.security_id = s.id),
+               (SELECT COUNT(*) FROM pp_price p WHERE p.security_id = s.id)
+        FROM pp_security s
+        WHERE UPPER(TRIM(s.isin)) IN (
+            SELECT UPPER(TRIM(isin)) FROM pp_security
+            WHERE isin IS NOT NULL AND TRIM(isin) != ''
+            GROUP BY UPPER(TRIM(isin))
+            HAVING COUNT(*) > 1
+        )
+        ORDER BY UPPER(TRIM(s.isin)), s.id
+        "#,
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            DuplicateSecurity {
+                id: row.get(1)?,
+                uuid: row.get(2)?,
+                name: row.get(3)?,
+                currency: row.get(4)?,
+                ticker: row.get(5)?,
+                is_retired: row.get::<_, i64>(6)? == 1,
+                transaction_count: row.get(7)?,
+                price_count: row.get(8)?,
+            },
+        ))
+    })?;
+
+    let mut clusters: Vec<DuplicateSecurityCluster> = Vec::new();
+    for row in rows {
+        let (isin, security) = row?;
+        match clusters.last_mut() {
+            Some(cluster) if cluster.isin == isin => cluster.securities.push(security),
+            _ => clusters.push(DuplicateSecurityCluster {
+                isin,
+                suggested_keep_id: security.id,
+                securities: vec![security],
+            }),
+        }
+    }
+
+    for cluster in &mut clusters {
+        if let Some(best) = cluster.securities.iter().max_by(|a, b| {
+            (a.transaction_count, a.price_count, -a.id).cmp(&(
+                b.transaction_count,
+                b.price_count,
+                -b.id,
+            ))
+        }) {
+            cluster.suggested_keep_id = best.id;
+        }
+    }
+
+    Ok(clusters)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )
+}
+
+/// Repoint everything that references security `from_id` onto `keep_id`.
+///
+/// Covers [`SECURITY_REFERENCES`], the latest price (the more recent one wins)
+/// and classification assignments. Rows the kept security already has are
+/// dropped. Returns the number of moved transactions and prices. Shared by the
+/// duplicate merge and the ISIN change of corporate actions.
+pub(crate) fn repoint_security_references(
+    conn: &Connection,
+    keep_id: i64,
+    from_id: i64,
+) -> Result<(usize, usize), String> {
+    let mut transactions_moved = 0;
+    let mut prices_moved = 0;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO pp_latest_price (security_id, date, value, high, low, volume, updated_at)
+         SELECT ?1, n.date, n.value, n.high, n.low, n.volume, n.updated_at
+         FROM pp_latest_price n
+         WHERE n.security_id = ?2
+           AND n.date > COALESCE((SELECT date FROM pp_latest_price WHERE security_id = ?1), '')",
+        [keep_id, from_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM pp_latest_price WHERE security_id = ?1", [from_id])
+        .map_err(|e| e.to_string())?;
+
+    for (table, column) in SECURITY_REFERENCES {
+        if !has_column(conn, table, column).map_err(|e| e.to_string())? {
+            continue;
+        }
+        let moved = conn
+            .execute(
+                &format!("UPDATE OR IGNORE {table} SET {column} = ?1 WHERE {column} = ?2"),
+                [keep_id, from_id],
+            )
+            .map_err(|e| e.to_string())?;
+        conn.execute(&format!("DELETE FROM {table} WHERE {column} = ?1"), [from_id])
+            .map_err(|e| e.to_string())?;
+        match *table {
+            "pp_txn" => transactions_moved += moved,
+            "pp_price" => prices_moved += moved,
+            _ => {}
+        }
+    }
+
+    // Classification assignments reference the security by UUID
+    conn.execute(
+        r#"
+        UPDATE pp_classification_assignment
+        SET vehicle_uuid = (SELECT uuid FROM pp_security WHERE id = ?1)
+        WHERE vehicle_type = 'security'
+          AND vehicle_uuid = (SELECT uuid FROM pp_security WHERE id = ?2)
+          AND classification_id NOT IN (
+              SELECT classification_id FROM pp_classification_assignment
+              WHERE vehicle_type = 'security'
+                AND vehicle_uuid = (SELECT uuid FROM pp_security WHERE id = ?1)
+          )
+        "#,
+        [keep_id, from_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM pp_classification_assignment
+         WHERE vehicle_type = 'security' AND vehicle_uuid = (SELECT uuid FROM pp_security WHERE id = ?1)",
+        [from_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok((transactions_moved, prices_moved))
+}
+
+fn merge_security_rows(
+    conn: &Connection,
+    keep_id: i64,
+    merge_ids: &[i64],
+) -> Result<MergeSecuritiesResult, String> {
+    let mut merge_ids: Vec<i64> = merge_ids
+        .iter()
+        .copied()
+        .filter(|id| *id != keep_id)
+        .collect();
+    merge_ids.sort_unstable();
+    merge_ids.dedup();
+    if merge_ids.is_empty() {
+        return Err("Keine Wertpapiere zum Zusammenführen angegeben".to_string());
+    }
+
+    let isin_of = |id: i64| -> Result<Option<String>, String> {
+        conn.query_row(
+            "SELECT UPPER(TRIM(isin)) FROM pp_security WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Wertpapier {} nicht gefunden", id))
+    };
+    let keep_isin = isin_of(keep_id)?.filter(|isin| !isin.is_empty());
+    for &id in &merge_ids {
+        if keep_isin.is_none() || isin_of(id)? != keep_isin {
+            return Err(format!(
+                "Wertpapier {} hat nicht dieselbe ISIN wie Wertpapier {}",
+                id, keep_id
+            ));
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut transactions_moved = 0;
+    let mut prices_moved = 0;
+
+    for &id in &merge_ids {
+        let (transactions, prices) = repoint_security_references(&tx, keep_id, id)?;
+        transactions_moved += transactions;
+        prices_moved += prices;
+
+        for column in FILLED_COLUMNS {
+            tx.execute(
+                &format!(
+                    "UPDATE pp_security SET {column} = (SELECT {column} FROM pp_security WHERE id = ?2)
+                     WHERE id = ?1 AND COALESCE({column}, '') = ''"
+                ),
+                [keep_id, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        // No transactions left, so this only clears the duplicate's lots
+        crate::fifo::build_fifo_lots(&tx, id).map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM pp_security WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.execute(
+        "UPDATE pp_security SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        [keep_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::fifo::build_fifo_lots(&tx, keep_id).map_err(|e| e.to_string())?;
+    let fifo_lots: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM pp_fifo_lot WHERE security_id = ?1",
+            [keep_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Merged securities {:?} into {} ({} transactions, {} prices)",
+        merge_ids,
+        keep_id,
+        transactions_moved,
+        prices_moved
+    );
+
+    Ok(MergeSecuritiesResult {
+        kept_id: keep_id,
+        merged_ids: merge_ids,
+        transactions_moved,
+        prices_moved,
+        fifo_lots,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_merge_duplicates() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency, isin, ticker) VALUES
+                 (1, 's1', 'Apple', 'USD', 'US0378331005', NULL),
+                 (2, 's2', 'Apple Inc.', 'USD', ' us0378331005', 'AAPL'),
+                 (3, 's3', 'Microsoft', 'USD', 'US5949181045', NULL);
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-02', 10000, 'USD', 1, 100000000),
+                    ('t2', 'portfolio', 1, 'BUY', '2024-02-01', 20000, 'USD', 2, 100000000);
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                 (1, '2024-01-02', 10000000000), (2, '2024-01-02', 99900000000), (2, '2024-01-03', 10100000000);
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-01-02', 10000000000), (2, '2024-06-28', 20000000000);
+             INSERT INTO pp_watchlist (id, name) VALUES (1, 'Tech');
+             INSERT INTO pp_watchlist_security (watchlist_id, security_id) VALUES (1, 1), (1, 2);
+             INSERT INTO pp_taxonomy (id, uuid, name) VALUES (1, 'tx1', 'Branchen');
+             INSERT INTO pp_classification (id, uuid, taxonomy_id, name) VALUES (1, 'c1', 1, 'IT');
+             INSERT INTO pp_classification_assignment (classification_id, vehicle_type, vehicle_uuid) VALUES (1, 'security', 's2');",
+        )
+        .unwrap();
+
+        let clusters = load_duplicate_securities(&conn).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].isin, "US0378331005");
+        assert_eq!(clusters[0].securities.len(), 2);
+
+        // Different ISIN is rejected
+        assert!(merge_security_rows(&conn, 1, &[3]).is_err());
+
+        let result = merge_security_rows(&conn, 1, &[2]).unwrap();
+        assert_eq!(result.merged_ids, vec![2]);
+        assert_eq!(result.transactions_moved, 1);
+        assert_eq!(result.prices_moved, 1);
+        assert_eq!(result.fifo_lots, 2);
+
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM pp_security WHERE id = 2"), 0);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM pp_txn WHERE security_id = 1"),
+            2
+        );
+        // The kept price wins on the same date
+        assert_eq!(
+            count("SELECT value FROM pp_price WHERE security_id = 1 AND date = '2024-01-02'"),
+            10000000000
+        );
+        assert_eq!(
+            count("SELECT value FROM pp_latest_price WHERE security_id = 1"),
+            20000000000
+        );
+        assert_eq!(count("SELECT COUNT(*) FROM pp_watchlist_security"), 1);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM pp_classification_assignment WHERE vehicle_uuid = 's1'"),
+            1
+        );
+        let ticker: String = conn
+            .query_row("SELECT ticker FROM pp_security WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(ticker, "AAPL");
+        assert!(load_duplicate_securities(&conn).unwrap().is_empty());
+    }
+}
@@ -1,16 +1,18 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use crate::db;
 use crate::pp::{
-    common::Money,
+    common::{ForexInfo, Money},
+    taxonomy::{Classification, ClassificationAssignment, Taxonomy},
     transaction::{
-        AccountTransaction, AccountTransactionType, PortfolioTransaction,
-        PortfolioTransactionType,
+        AccountTransaction, AccountTransactionType, CrossEntry, PortfolioTransaction,
+        PortfolioTransactionType, TransactionUnit, UnitType,
     },
-    Account, Client, LatestPrice, Portfolio, PriceEntry, Security,
+    Account, Client, LatestPrice, Portfolio, PriceEntry, Security, Watchlist,
 };
-use crate::protobuf;
+use crate::protobuf::{self, PpFileInfo};
 use crate::security;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
@@ -76,10 +78,13 @@ pub fn get_file_extension() -> String {
 
 /// Validate a portfolio file without fully loading it
 ///
+/// Reports format, version, encryption and unsupported data blocks, so an
+/// import can be rejected up front with a clear reason.
+///
 /// # Security
 /// Path is validated to prevent directory traversal and access to unauthorized locations.
 #[command]
-pub async fn validate_portfolio_file(path: String) -> Result<bool, String> {
+pub async fn validate_portfolio_file(path: String) -> Result<PpFileInfo, String> {
     // SECURITY: Validate the file path
     let validated_path = security::validate_file_path_with_extension(&path, Some(&["portfolio"]))?;
 
@@ -87,10 +92,8 @@ pub async fn validate_portfolio_file(path: String) -> Result<bool, String> {
         return Err("File does not exist".to_string());
     }
 
-    match protobuf::parse_portfolio_file(&validated_path) {
-        Ok(_) => Ok(true),
-        Err(e) => Err(format!("Invalid portfolio file: {}", e)),
-    }
+    protobuf::inspect_portfolio_file(&validated_path)
+        .map_err(|e| format!("Invalid portfolio file: {}", e))
 }
 
 /// Export portfolio statistics as JSON
@@ -120,18 +123,61 @@ pub struct PortfolioStats {
 
 /// Export database to a .portfolio file
 ///
+/// `format` is "protobuf" (default, current PP builds) or "xml" (zipped XML for
+/// older PP builds and other tools).
+///
 /// # Security
 /// Path is validated to prevent directory traversal and access to unauthorized locations.
 #[command]
-pub async fn export_database_to_portfolio(path: String) -> Result<ExportResult, String> {
+pub async fn export_database_to_portfolio(
+    path: String,
+    format: Option<String>,
+) -> Result<ExportResult, String> {
     // SECURITY: Validate the file path
     let path_buf = security::validate_file_path_with_extension(&path, Some(&["portfolio"]))?;
 
+    let xml = match format.as_deref().unwrap_or("protobuf") {
+        "protobuf" => false,
+        "xml" => true,
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    let client = load_client_from_db(conn).map_err(|e| e.to_string())?;
+
+    // Write to file
+    if xml {
+        crate::pp::xml::write_xml_file(&path_buf, &client).map_err(|e| e.to_string())?;
+    } else {
+        protobuf::write_portfolio_file(&path_buf, &client).map_err(|e| e.to_string())?;
+    }
+
+    Ok(ExportResult {
+        path: path_buf.to_string_lossy().to_string(),
+        securities_count: client.securities.len(),
+        accounts_count: client.accounts.len(),
+        portfolios_count: client.portfolios.len(),
+        transactions_count: client.accounts.iter().map(|a| a.transactions.len()).sum::<usize>()
+            + client.portfolios.iter().map(|p| p.transactions.len()).sum::<usize>(),
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub path: String,
+    pub securities_count: usize,
+    pub accounts_count: usize,
+    pub portfolios_count: usize,
+    pub transactions_count: usize,
+}
+
+/// Rebuild the PP client model from the database
+pub(crate) fn load_client_from_db(conn: &rusqlite::Connection) -> Result<Client, rusqlite::Error> {
     // Get base currency from import
     let base_currency: String = conn
         .query_row(
@@ -143,39 +189,38 @@ pub async fn export_database_to_portfolio(path: String) -> Result<ExportResult,
 
     let mut client = Client::new(&base_currency);
 
-    // Load securities
-    let securities = load_securities_from_db(conn).map_err(|e| e.to_string())?;
-    let securities_count = securities.len();
-    client.securities = securities;
-
-    // Load accounts with transactions
-    let accounts = load_accounts_from_db(conn).map_err(|e| e.to_string())?;
-    let accounts_count = accounts.len();
-    client.accounts = accounts;
+    // Units and cross-entries are shared by account and portfolio transactions
+    let extras = TxnExtras {
+        units: load_txn_units(conn)?,
+        cross_entries: load_cross_entries(conn)?,
+    };
 
-    // Load portfolios with transactions
-    let portfolios = load_portfolios_from_db(conn).map_err(|e| e.to_string())?;
-    let portfolios_count = portfolios.len();
-    client.portfolios = portfolios;
+    client.securities = load_securities_from_db(conn)?;
+    client.accounts = load_accounts_from_db(conn, &extras)?;
+    client.portfolios = load_portfolios_from_db(conn, &extras)?;
+    client.taxonomies = load_taxonomies_from_db(conn)?;
+    client.watchlists = load_watchlists_from_db(conn)?;
 
-    // Write to file
-    protobuf::write_portfolio_file(&path_buf, &client).map_err(|e| e.to_string())?;
+    Ok(client)
+}
 
-    Ok(ExportResult {
-        path: path_buf.to_string_lossy().to_string(),
-        securities_count,
-        accounts_count,
-        portfolios_count,
-    })
+/// Parse a JSON attribute map as stored by the import
+fn parse_json_map(json: Option<String>) -> HashMap<String, String> {
+    json.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ExportResult {
-    pub path: String,
-    pub securities_count: usize,
-    pub accounts_count: usize,
-    pub portfolios_count: usize,
+/// Parse a transaction date; imported rows carry a time, manual ones may not
+fn parse_txn_date(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            s.get(..10)
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
 }
 
 /// Load securities from database
@@ -184,46 +229,45 @@ fn load_securities_from_db(conn: &rusqlite::Connection) -> Result<Vec<Security>,
 
     let mut stmt = conn.prepare(
         r#"
-        SELECT uuid, name, currency, isin, wkn, ticker, feed, feed_url, is_retired
+        SELECT id, uuid, name, currency, target_currency, online_id, isin, wkn, ticker, calendar,
+               feed, feed_url, latest_feed, latest_feed_url, is_retired, note, updated_at,
+               attributes, properties
         FROM pp_security
         ORDER BY name
         "#,
     )?;
 
     let rows = stmt.query_map([], |row| {
-        Ok((
-            row.get::<_, String>(0)?,
-            row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
-            row.get::<_, Option<String>>(3)?,
-            row.get::<_, Option<String>>(4)?,
-            row.get::<_, Option<String>>(5)?,
-            row.get::<_, Option<String>>(6)?,
-            row.get::<_, Option<String>>(7)?,
-            row.get::<_, bool>(8)?,
-        ))
+        let mut sec = Security::new(row.get(1)?, row.get(2)?, row.get(3)?);
+        sec.target_currency = row.get(4)?;
+        sec.online_id = row.get(5)?;
+        sec.isin = row.get(6)?;
+        sec.wkn = row.get(7)?;
+        sec.ticker = row.get(8)?;
+        sec.calendar = row.get(9)?;
+        sec.feed = row.get(10)?;
+        sec.feed_url = row.get(11)?;
+        sec.latest_feed = row.get(12)?;
+        sec.latest_feed_url = row.get(13)?;
+        sec.is_retired = row.get(14)?;
+        sec.note = row.get(15)?;
+        sec.updated_at = row.get(16)?;
+        sec.attributes = parse_json_map(row.get(17)?);
+        sec.properties = parse_json_map(row.get(18)?);
+        Ok((row.get::<_, i64>(0)?, sec))
     })?;
 
+    // Interpolated prices are synthetic and not exported
+    let mut price_stmt = conn.prepare(
+        "SELECT date, value FROM pp_price
+         WHERE security_id = ? AND source IS NOT 'interpolated'
+         ORDER BY date",
+    )?;
+
     for row in rows.flatten() {
-        let (uuid, name, currency, isin, wkn, ticker, feed, feed_url, is_retired) = row;
-        let mut sec = Security::new(uuid.clone(), name, currency);
-        sec.isin = isin;
-        sec.wkn = wkn;
-        sec.ticker = ticker;
-        sec.feed = feed;
-        sec.feed_url = feed_url;
-        sec.is_retired = is_retired;
+        let (sec_id, mut sec) = row;
 
         // Load prices for this security
-        let sec_id: i64 = conn.query_row(
-            "SELECT id FROM pp_security WHERE uuid = ?",
-            [&uuid],
-            |row| row.get(0),
-        )?;
-
-        let mut price_stmt = conn.prepare(
-            "SELECT date, value FROM pp_price WHERE security_id = ? ORDER BY date",
-        )?;
         let price_rows = price_stmt.query_map([sec_id], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
@@ -255,15 +299,66 @@ fn load_securities_from_db(conn: &rusqlite::Connection) -> Result<Vec<Security>,
     Ok(securities)
 }
 
-/// Load accounts from database
-fn load_accounts_from_db(conn: &rusqlite::Connection) -> Result<Vec<Account>, rusqlite::Error> {
-    let mut accounts = Vec::new();
+/// Cross-entry row: type plus the UUIDs of the linked transactions
+struct CrossEntryRow {
+    entry_type: String,
+    portfolio_txn: Option<String>,
+    account_txn: Option<String>,
+    from_txn: Option<String>,
+    to_txn: Option<String>,
+}
+
+impl CrossEntryRow {
+    /// Cross-entry as seen from one of its transactions
+    fn for_transaction(&self, own_uuid: &str) -> Option<CrossEntry> {
+        match self.entry_type.as_str() {
+            "BUY_SELL" => {
+                let (portfolio, account) = (self.portfolio_txn.clone()?, self.account_txn.clone()?);
+                let other = if portfolio == own_uuid { account } else { portfolio };
+                Some(CrossEntry::buy_sell(own_uuid.to_string(), other))
+            }
+            "ACCOUNT_TRANSFER" => Some(CrossEntry::account_transfer(
+                self.from_txn.clone()?,
+                self.to_txn.clone()?,
+            )),
+            "PORTFOLIO_TRANSFER" => Some(CrossEntry::portfolio_transfer(
+                self.from_txn.clone()?,
+                self.to_txn.clone()?,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Per-transaction data shared by account and portfolio transactions
+struct TxnExtras {
+    units: HashMap<i64, Vec<TransactionUnit>>,
+    cross_entries: HashMap<i64, CrossEntryRow>,
+}
+
+impl TxnExtras {
+    fn units(&self, txn_id: i64) -> Vec<TransactionUnit> {
+        self.units.get(&txn_id).cloned().unwrap_or_default()
+    }
+
+    fn cross_entry(&self, cross_entry_id: Option<i64>, own_uuid: &str) -> Option<CrossEntry> {
+        cross_entry_id
+            .and_then(|id| self.cross_entries.get(&id))
+            .and_then(|row| row.for_transaction(own_uuid))
+    }
+}
+
+/// Load all transaction units (fees, taxes, gross values) grouped by transaction
+fn load_txn_units(
+    conn: &rusqlite::Connection,
+) -> Result<HashMap<i64, Vec<TransactionUnit>>, rusqlite::Error> {
+    let mut units: HashMap<i64, Vec<TransactionUnit>> = HashMap::new();
 
     let mut stmt = conn.prepare(
         r#"
-        SELECT id, uuid, name, currency, is_retired
-        FROM pp_account
-        ORDER BY name
+        SELECT txn_id, unit_type, amount, currency, forex_amount, forex_currency, exchange_rate
+        FROM pp_txn_unit
+        ORDER BY id
         "#,
     )?;
 
@@ -271,20 +366,95 @@ fn load_accounts_from_db(conn: &rusqlite::Connection) -> Result<Vec<Account>, ru
         Ok((
             row.get::<_, i64>(0)?,
             row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
+            row.get::<_, i64>(2)?,
             row.get::<_, String>(3)?,
-            row.get::<_, bool>(4)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<f64>>(6)?,
+        ))
+    })?;
+
+    for row in rows.flatten() {
+        let (txn_id, unit_type, amount, currency, forex_amount, forex_currency, exchange_rate) = row;
+        let Some(unit_type) = UnitType::from_str(&unit_type) else {
+            continue;
+        };
+
+        let mut unit = TransactionUnit::new(unit_type, Money::new(amount, currency));
+        if let (Some(fx_amount), Some(fx_currency), Some(rate)) =
+            (forex_amount, forex_currency, exchange_rate)
+        {
+            unit = unit.with_forex(ForexInfo {
+                amount: Money::new(fx_amount, fx_currency),
+                exchange_rate: rate,
+            });
+        }
+        units.entry(txn_id).or_default().push(unit);
+    }
+
+    Ok(units)
+}
+
+/// Load all cross-entries with the UUIDs of their transactions
+fn load_cross_entries(
+    conn: &rusqlite::Connection,
+) -> Result<HashMap<i64, CrossEntryRow>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT ce.id, ce.entry_type, pt.uuid, at.uuid, ft.uuid, tt.uuid
+        FROM pp_cross_entry ce
+        LEFT JOIN pp_txn pt ON pt.id = ce.portfolio_txn_id
+        LEFT JOIN pp_txn at ON at.id = ce.account_txn_id
+        LEFT JOIN pp_txn ft ON ft.id = ce.from_txn_id
+        LEFT JOIN pp_txn tt ON tt.id = ce.to_txn_id
+        "#,
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            CrossEntryRow {
+                entry_type: row.get(1)?,
+                portfolio_txn: row.get(2)?,
+                account_txn: row.get(3)?,
+                from_txn: row.get(4)?,
+                to_txn: row.get(5)?,
+            },
         ))
     })?;
 
+    Ok(rows.flatten().collect())
+}
+
+/// Load accounts from database
+fn load_accounts_from_db(
+    conn: &rusqlite::Connection,
+    extras: &TxnExtras,
+) -> Result<Vec<Account>, rusqlite::Error> {
+    let mut accounts = Vec::new();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, uuid, name, currency, is_retired, note, updated_at, attributes
+        FROM pp_account
+        ORDER BY name
+        "#,
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let mut acc = Account::new(row.get(1)?, row.get(2)?, row.get(3)?);
+        acc.is_retired = row.get(4)?;
+        acc.note = row.get(5)?;
+        acc.updated_at = row.get(6)?;
+        acc.attributes = parse_json_map(row.get(7)?);
+        Ok((row.get::<_, i64>(0)?, acc))
+    })?;
+
     for row in rows.flatten() {
-        let (id, uuid, name, currency, is_retired) = row;
-        let mut acc = Account::new(uuid.clone(), name, currency);
-        acc.is_retired = is_retired;
+        let (id, mut acc) = row;
 
         // Load transactions for this account
-        let txns = load_account_transactions(conn, id)?;
-        acc.transactions = txns;
+        acc.transactions = load_account_transactions(conn, id, extras)?;
 
         accounts.push(acc);
     }
@@ -296,35 +466,47 @@ fn load_accounts_from_db(conn: &rusqlite::Connection) -> Result<Vec<Account>, ru
 fn load_account_transactions(
     conn: &rusqlite::Connection,
     account_id: i64,
+    extras: &TxnExtras,
 ) -> Result<Vec<AccountTransaction>, rusqlite::Error> {
     let mut txns = Vec::new();
 
     let mut stmt = conn.prepare(
         r#"
-        SELECT t.uuid, t.txn_type, t.date, t.amount, t.currency, t.shares, t.note,
-               s.uuid as security_uuid
+        SELECT t.id, t.uuid, t.txn_type, t.date, t.amount, t.currency, t.shares, t.note,
+               s.uuid as security_uuid, t.source, t.updated_at, t.cross_entry_id,
+               oa.uuid as other_account_uuid, t.other_updated_at
         FROM pp_txn t
         LEFT JOIN pp_security s ON s.id = t.security_id
+        LEFT JOIN pp_account oa ON oa.id = t.other_account_id
         WHERE t.owner_type = 'account' AND t.owner_id = ?
-        ORDER BY t.date
+        ORDER BY t.date, t.id
         "#,
     )?;
 
     let rows = stmt.query_map([account_id], |row| {
         Ok((
-            row.get::<_, String>(0)?,
+            row.get::<_, i64>(0)?,
             row.get::<_, String>(1)?,
             row.get::<_, String>(2)?,
-            row.get::<_, i64>(3)?,
-            row.get::<_, String>(4)?,
-            row.get::<_, Option<i64>>(5)?,
-            row.get::<_, Option<String>>(6)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, Option<i64>>(6)?,
             row.get::<_, Option<String>>(7)?,
+            row.get::<_, Option<String>>(8)?,
+            (
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+                row.get::<_, Option<i64>>(11)?,
+                row.get::<_, Option<String>>(12)?,
+                row.get::<_, Option<String>>(13)?,
+            ),
         ))
     })?;
 
     for row in rows.flatten() {
-        let (uuid, txn_type, date_str, amount, currency, shares, note, security_uuid) = row;
+        let (id, uuid, txn_type, date_str, amount, currency, shares, note, security_uuid, rest) = row;
+        let (source, updated_at, cross_entry_id, other_account_uuid, other_updated_at) = rest;
 
         let tx_type = match txn_type.as_str() {
             "DEPOSIT" => AccountTransactionType::Deposit,
@@ -343,8 +525,7 @@ fn load_account_transactions(
             _ => continue,
         };
 
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d") {
-            let datetime = date.and_hms_opt(0, 0, 0).unwrap();
+        if let Some(datetime) = parse_txn_date(&date_str) {
             let mut tx = AccountTransaction::new(
                 uuid,
                 datetime,
@@ -354,6 +535,12 @@ fn load_account_transactions(
             tx.shares = shares;
             tx.note = note;
             tx.security_uuid = security_uuid;
+            tx.source = source;
+            tx.updated_at = updated_at;
+            tx.other_account_uuid = other_account_uuid;
+            tx.other_updated_at = other_updated_at;
+            tx.units = extras.units(id);
+            tx.cross_entry = extras.cross_entry(cross_entry_id, &tx.uuid);
             txns.push(tx);
         }
     }
@@ -362,12 +549,16 @@ fn load_account_transactions(
 }
 
 /// Load portfolios from database
-fn load_portfolios_from_db(conn: &rusqlite::Connection) -> Result<Vec<Portfolio>, rusqlite::Error> {
+fn load_portfolios_from_db(
+    conn: &rusqlite::Connection,
+    extras: &TxnExtras,
+) -> Result<Vec<Portfolio>, rusqlite::Error> {
     let mut portfolios = Vec::new();
 
     let mut stmt = conn.prepare(
         r#"
-        SELECT p.id, p.uuid, p.name, p.is_retired, a.uuid as ref_account_uuid
+        SELECT p.id, p.uuid, p.name, p.is_retired, a.uuid as ref_account_uuid,
+               p.note, p.updated_at, p.attributes
         FROM pp_portfolio p
         LEFT JOIN pp_account a ON a.id = p.reference_account_id
         ORDER BY p.name
@@ -375,24 +566,20 @@ fn load_portfolios_from_db(conn: &rusqlite::Connection) -> Result<Vec<Portfolio>
     )?;
 
     let rows = stmt.query_map([], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,
-            row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
-            row.get::<_, bool>(3)?,
-            row.get::<_, Option<String>>(4)?,
-        ))
+        let mut port = Portfolio::new(row.get(1)?, row.get(2)?);
+        port.is_retired = row.get(3)?;
+        port.reference_account_uuid = row.get(4)?;
+        port.note = row.get(5)?;
+        port.updated_at = row.get(6)?;
+        port.attributes = parse_json_map(row.get(7)?);
+        Ok((row.get::<_, i64>(0)?, port))
     })?;
 
     for row in rows.flatten() {
-        let (id, uuid, name, is_retired, ref_account_uuid) = row;
-        let mut port = Portfolio::new(uuid.clone(), name);
-        port.is_retired = is_retired;
-        port.reference_account_uuid = ref_account_uuid;
+        let (id, mut port) = row;
 
         // Load transactions for this portfolio
-        let txns = load_portfolio_transactions(conn, id)?;
-        port.transactions = txns;
+        port.transactions = load_portfolio_transactions(conn, id, extras)?;
 
         portfolios.push(port);
     }
@@ -404,35 +591,47 @@ fn load_portfolios_from_db(conn: &rusqlite::Connection) -> Result<Vec<Portfolio>
 fn load_portfolio_transactions(
     conn: &rusqlite::Connection,
     portfolio_id: i64,
+    extras: &TxnExtras,
 ) -> Result<Vec<PortfolioTransaction>, rusqlite::Error> {
     let mut txns = Vec::new();
 
     let mut stmt = conn.prepare(
         r#"
-        SELECT t.uuid, t.txn_type, t.date, t.amount, t.currency, t.shares, t.note,
-               s.uuid as security_uuid
+        SELECT t.id, t.uuid, t.txn_type, t.date, t.amount, t.currency, t.shares, t.note,
+               s.uuid as security_uuid, t.source, t.updated_at, t.cross_entry_id,
+               op.uuid as other_portfolio_uuid, t.other_updated_at
         FROM pp_txn t
         LEFT JOIN pp_security s ON s.id = t.security_id
+        LEFT JOIN pp_portfolio op ON op.id = t.other_portfolio_id
         WHERE t.owner_type = 'portfolio' AND t.owner_id = ?
-        ORDER BY t.date
+        ORDER BY t.date, t.id
         "#,
     )?;
 
     let rows = stmt.query_map([portfolio_id], |row| {
         Ok((
-            row.get::<_, String>(0)?,
+            row.get::<_, i64>(0)?,
             row.get::<_, String>(1)?,
             row.get::<_, String>(2)?,
-            row.get::<_, i64>(3)?,
-            row.get::<_, String>(4)?,
-            row.get::<_, i64>(5)?,
-            row.get::<_, Option<String>>(6)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, i64>(6)?,
             row.get::<_, Option<String>>(7)?,
+            row.get::<_, Option<String>>(8)?,
+            (
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+                row.get::<_, Option<i64>>(11)?,
+                row.get::<_, Option<String>>(12)?,
+                row.get::<_, Option<String>>(13)?,
+            ),
         ))
     })?;
 
     for row in rows.flatten() {
-        let (uuid, txn_type, date_str, amount, currency, shares, note, security_uuid) = row;
+        let (id, uuid, txn_type, date_str, amount, currency, shares, note, security_uuid, rest) = row;
+        let (source, updated_at, cross_entry_id, other_portfolio_uuid, other_updated_at) = rest;
 
         let tx_type = match txn_type.as_str() {
             "BUY" => PortfolioTransactionType::Buy,
@@ -444,8 +643,7 @@ fn load_portfolio_transactions(
             _ => continue,
         };
 
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d") {
-            let datetime = date.and_hms_opt(0, 0, 0).unwrap();
+        if let Some(datetime) = parse_txn_date(&date_str) {
             let mut tx = PortfolioTransaction::new(
                 uuid,
                 datetime,
@@ -455,6 +653,12 @@ fn load_portfolio_transactions(
             );
             tx.note = note;
             tx.security_uuid = security_uuid;
+            tx.source = source;
+            tx.updated_at = updated_at;
+            tx.other_portfolio_uuid = other_portfolio_uuid;
+            tx.other_updated_at = other_updated_at;
+            tx.units = extras.units(id);
+            tx.cross_entry = extras.cross_entry(cross_entry_id, &tx.uuid);
             txns.push(tx);
         }
     }
@@ -462,6 +666,135 @@ fn load_portfolio_transactions(
     Ok(txns)
 }
 
+/// Classification row as stored in pp_classification
+struct ClassificationRow {
+    id: i64,
+    parent_id: Option<i64>,
+    classification: Classification,
+}
+
+/// Load taxonomies with their classification trees and assignments
+fn load_taxonomies_from_db(conn: &rusqlite::Connection) -> Result<Vec<Taxonomy>, rusqlite::Error> {
+    let mut assignments: HashMap<i64, Vec<ClassificationAssignment>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT classification_id, vehicle_type, vehicle_uuid, weight, rank
+            FROM pp_classification_assignment
+            ORDER BY id
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                ClassificationAssignment {
+                    vehicle_class: row.get(1)?,
+                    vehicle_uuid: row.get(2)?,
+                    weight: row.get(3)?,
+                    rank: row.get(4)?,
+                },
+            ))
+        })?;
+        for (class_id, assignment) in rows.flatten() {
+            assignments.entry(class_id).or_default().push(assignment);
+        }
+    }
+
+    let mut taxonomies = Vec::new();
+    let mut tax_stmt = conn.prepare("SELECT id, uuid, name, source FROM pp_taxonomy ORDER BY id")?;
+    let tax_rows = tax_stmt.query_map([], |row| {
+        let mut taxonomy = Taxonomy::new(row.get(1)?, row.get(2)?);
+        taxonomy.source = row.get(3)?;
+        Ok((row.get::<_, i64>(0)?, taxonomy))
+    })?;
+
+    let mut class_stmt = conn.prepare(
+        r#"
+        SELECT id, parent_id, uuid, name, color, weight, rank
+        FROM pp_classification
+        WHERE taxonomy_id = ?
+        ORDER BY rank, id
+        "#,
+    )?;
+
+    for (taxonomy_id, mut taxonomy) in tax_rows.flatten() {
+        let class_rows = class_stmt.query_map([taxonomy_id], |row| {
+            let mut classification = Classification::new(row.get(2)?, row.get(3)?);
+            classification.color = row.get(4)?;
+            classification.weight = row.get(5)?;
+            classification.rank = row.get(6)?;
+            Ok(ClassificationRow {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                classification,
+            })
+        })?;
+        let mut rows: Vec<ClassificationRow> = class_rows.flatten().collect();
+        for row in rows.iter_mut() {
+            row.classification.assignments = assignments.remove(&row.id).unwrap_or_default();
+        }
+
+        taxonomy.root = rows
+            .iter()
+            .position(|r| r.parent_id.is_none())
+            .map(|idx| {
+                let root = rows.remove(idx);
+                build_classification_tree(root, &mut rows)
+            });
+        taxonomies.push(taxonomy);
+    }
+
+    Ok(taxonomies)
+}
+
+/// Attach all descendants of `node` from `rows`
+fn build_classification_tree(node: ClassificationRow, rows: &mut Vec<ClassificationRow>) -> Classification {
+    let mut classification = node.classification;
+    let mut idx = 0;
+    let mut children = Vec::new();
+    while idx < rows.len() {
+        if rows[idx].parent_id == Some(node.id) {
+            children.push(rows.remove(idx));
+        } else {
+            idx += 1;
+        }
+    }
+    for child in children {
+        let child = build_classification_tree(child, rows);
+        classification.children.push(child);
+    }
+    classification
+}
+
+/// Load watchlists with their securities
+fn load_watchlists_from_db(conn: &rusqlite::Connection) -> Result<Vec<Watchlist>, rusqlite::Error> {
+    let mut watchlists = Vec::new();
+
+    let mut stmt = conn.prepare("SELECT id, name FROM pp_watchlist ORDER BY id")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+
+    let mut sec_stmt = conn.prepare(
+        r#"
+        SELECT s.uuid
+        FROM pp_watchlist_security ws
+        JOIN pp_security s ON s.id = ws.security_id
+        WHERE ws.watchlist_id = ?
+        ORDER BY s.name
+        "#,
+    )?;
+
+    for (id, name) in rows.flatten() {
+        let mut watchlist = Watchlist::new(name);
+        watchlist.security_uuids = sec_stmt
+            .query_map([id], |row| row.get::<_, String>(0))?
+            .flatten()
+            .collect();
+        watchlists.push(watchlist);
+    }
+
+    Ok(watchlists)
+}
+
 /// Read a file as base64 for chat attachments
 ///
 /// # Security
@@ -563,3 +896,236 @@ pub async fn read_image_as_base64(path: String) -> Result<FileBase64Result, Stri
         filename,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pp::transaction::TransactionUnit;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn datetime(y: i32, m: u32, d: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap()
+    }
+
+    fn eur(amount: i64) -> Money {
+        Money::new(amount, "EUR")
+    }
+
+    /// Client with one transaction of each kind that needs special handling
+    fn sample_client() -> Client {
+        let mut client = Client::new("EUR");
+
+        let mut world = Security::new("sec-1".into(), "MSCI World".into(), "EUR".into());
+        world.isin = Some("IE00B4L5Y983".into());
+        world.prices.push(PriceEntry::new(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(), 8_000_000_000));
+        world.prices.push(PriceEntry::new(NaiveDate::from_ymd_opt(2024, 1, 11).unwrap(), 8_100_000_000));
+        let apple = Security::new("sec-2".into(), "Apple".into(), "USD".into());
+        client.securities = vec![world, apple];
+
+        let mut cash = Account::new("acc-1".into(), "Verrechnungskonto".into(), "EUR".into());
+        let mut savings = Account::new("acc-2".into(), "Tagesgeld".into(), "EUR".into());
+        let mut depot = Portfolio::new("port-1".into(), "Depot".into());
+        depot.reference_account_uuid = Some("acc-1".into());
+        let mut depot2 = Portfolio::new("port-2".into(), "Zweitdepot".into());
+
+        cash.transactions.push(AccountTransaction::new(
+            "dep-1".into(),
+            datetime(2024, 1, 2),
+            AccountTransactionType::Deposit,
+            eur(1_000_000),
+        ));
+
+        // Buy with fee: portfolio and account side linked by a cross-entry
+        let mut buy = PortfolioTransaction::new(
+            "buy-p".into(),
+            datetime(2024, 1, 10),
+            PortfolioTransactionType::Buy,
+            eur(80_500),
+            1_000_000_000,
+        );
+        buy.security_uuid = Some("sec-1".into());
+        buy.units.push(TransactionUnit::fee(eur(500)));
+        buy.cross_entry = Some(CrossEntry::buy_sell("buy-p".into(), "buy-a".into()));
+        let mut buy_cash = AccountTransaction::new(
+            "buy-a".into(),
+            datetime(2024, 1, 10),
+            AccountTransactionType::Buy,
+            eur(80_500),
+        );
+        buy_cash.security_uuid = Some("sec-1".into());
+        buy_cash.units.push(TransactionUnit::fee(eur(500)));
+        buy_cash.cross_entry = Some(CrossEntry::buy_sell("buy-a".into(), "buy-p".into()));
+        depot.transactions.push(buy);
+        cash.transactions.push(buy_cash);
+
+        // Dividend in USD with withholding tax
+        let mut dividend = AccountTransaction::new(
+            "div-1".into(),
+            datetime(2024, 2, 15),
+            AccountTransactionType::Dividends,
+            eur(850),
+        );
+        dividend.security_uuid = Some("sec-2".into());
+        dividend.units.push(TransactionUnit::tax(eur(150)).with_forex(ForexInfo {
+            amount: Money::new(165, "USD"),
+            exchange_rate: 1.1,
+        }));
+        cash.transactions.push(dividend);
+
+        // Cash transfer between accounts
+        let transfer = CrossEntry::account_transfer("tr-1".into(), "tr-1-in".into());
+        let mut out = AccountTransaction::new(
+            "tr-1".into(),
+            datetime(2024, 3, 1),
+            AccountTransactionType::TransferOut,
+            eur(20_000),
+        );
+        out.other_account_uuid = Some("acc-2".into());
+        out.cross_entry = Some(transfer.clone());
+        let mut incoming = AccountTransaction::new(
+            "tr-1-in".into(),
+            datetime(2024, 3, 1),
+            AccountTransactionType::TransferIn,
+            eur(20_000),
+        );
+        incoming.cross_entry = Some(transfer);
+        cash.transactions.push(out);
+        savings.transactions.push(incoming);
+
+        // Security transfer between portfolios
+        let transfer = CrossEntry::portfolio_transfer("st-1-out".into(), "st-1".into());
+        let mut st_out = PortfolioTransaction::new(
+            "st-1-out".into(),
+            datetime(2024, 4, 1),
+            PortfolioTransactionType::TransferOut,
+            eur(40_000),
+            500_000_000,
+        );
+        st_out.security_uuid = Some("sec-1".into());
+        st_out.cross_entry = Some(transfer.clone());
+        let mut st_in = st_out.clone();
+        st_in.uuid = "st-1".into();
+        st_in.transaction_type = PortfolioTransactionType::TransferIn;
+        st_in.cross_entry = Some(transfer);
+        depot.transactions.push(st_out);
+        depot2.transactions.push(st_in);
+
+        client.accounts = vec![cash, savings];
+        client.portfolios = vec![depot, depot2];
+
+        let mut equities = Classification::new("cls-equity".into(), "Aktien".into());
+        equities.assignments.push(ClassificationAssignment::security("sec-1".into(), 10000));
+        let mut root = Classification::new("cls-root".into(), "Asset Allocation".into());
+        root.children.push(equities);
+        root.children.push(Classification::new("cls-bonds".into(), "Anleihen".into()));
+        let mut taxonomy = Taxonomy::new("tax-1".into(), "Asset Allocation".into());
+        taxonomy.root = Some(root);
+        client.taxonomies.push(taxonomy);
+
+        let mut watchlist = Watchlist::new("Beobachten");
+        watchlist.security_uuids.push("sec-2".into());
+        client.watchlists.push(watchlist);
+
+        client
+    }
+
+    fn transaction_count(client: &Client) -> usize {
+        client.accounts.iter().map(|a| a.transactions.len()).sum::<usize>()
+            + client.portfolios.iter().map(|p| p.transactions.len()).sum::<usize>()
+    }
+
+    /// Order-independent checksum over everything the export must preserve
+    fn checksum(client: &Client) -> u64 {
+        let mut lines = Vec::new();
+        for sec in &client.securities {
+            lines.push(format!("S|{}|{}|{:?}|{:?}", sec.uuid, sec.currency, sec.isin, sec.prices));
+        }
+        for acc in &client.accounts {
+            for t in &acc.transactions {
+                lines.push(format!(
+                    "A|{}|{}|{:?}|{}|{:?}|{:?}|{:?}|{:?}|{:?}",
+                    acc.uuid, t.uuid, t.transaction_type, t.date, t.amount, t.shares,
+                    t.security_uuid, t.units, t.cross_entry
+                ));
+            }
+        }
+        for port in &client.portfolios {
+            for t in &port.transactions {
+                lines.push(format!(
+                    "P|{}|{}|{:?}|{}|{:?}|{}|{:?}|{:?}|{:?}",
+                    port.uuid, t.uuid, t.transaction_type, t.date, t.amount, t.shares,
+                    t.security_uuid, t.units, t.cross_entry
+                ));
+            }
+        }
+        for tax in &client.taxonomies {
+            for c in tax.all_classifications() {
+                let assigned: Vec<_> = c.assignments.iter().map(|a| (&a.vehicle_uuid, a.weight)).collect();
+                lines.push(format!("T|{}|{}|{}|{:?}", tax.id, c.id, c.children.len(), assigned));
+            }
+        }
+        for wl in &client.watchlists {
+            lines.push(format!("W|{}|{:?}", wl.name, wl.security_uuids));
+        }
+        lines.sort();
+
+        let mut hasher = DefaultHasher::new();
+        lines.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn import(client: &Client) -> rusqlite::Connection {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        crate::commands::import::save_client(&mut conn, "test.portfolio", client, |_| {}).unwrap();
+        conn
+    }
+
+    fn db_transaction_count(conn: &rusqlite::Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM pp_txn", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_txn_date() {
+        assert_eq!(parse_txn_date("2024-01-10 09:30:00"), Some(datetime(2024, 1, 10)));
+        assert_eq!(parse_txn_date("2024-01-10T09:30:00"), Some(datetime(2024, 1, 10)));
+        assert_eq!(
+            parse_txn_date("2024-01-10"),
+            NaiveDate::from_ymd_opt(2024, 1, 10).unwrap().and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(parse_txn_date("invalid"), None);
+    }
+
+    #[test]
+    fn test_export_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("pp_export_roundtrip_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sample_path = dir.join("sample.portfolio");
+        let export_path = dir.join("export.portfolio");
+
+        // Import a sample file
+        protobuf::write_portfolio_file(&sample_path, &sample_client()).unwrap();
+        let original = protobuf::parse_portfolio_file(&sample_path).unwrap();
+        let conn = import(&original);
+        assert_eq!(transaction_count(&original), 8);
+        assert_eq!(db_transaction_count(&conn), 8);
+
+        // Export and re-import
+        let exported = load_client_from_db(&conn).unwrap();
+        protobuf::write_portfolio_file(&export_path, &exported).unwrap();
+        let reparsed = protobuf::parse_portfolio_file(&export_path).unwrap();
+        let reimported = import(&reparsed);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(transaction_count(&reparsed), transaction_count(&original));
+        assert_eq!(db_transaction_count(&reimported), db_transaction_count(&conn));
+        assert_eq!(reparsed.securities.len(), 2);
+        assert_eq!(reparsed.taxonomies.len(), 1);
+        assert_eq!(reparsed.watchlists[0].security_uuids, vec!["sec-2".to_string()]);
+        assert_eq!(checksum(&reparsed), checksum(&original));
+    }
+}
@@ -0,0 +1,328 @@
+//! Ghostfolio export functionality
+//!
+//! Writes accounts and activities in Ghostfolio's JSON import format
+//! (the file created by "Export" in Ghostfolio and accepted by "Import").
+//!
+//! Mapping:
+//! - Accounts → Ghostfolio accounts (balance = current cash balance)
+//! - Portfolio BUY/SELL (and deliveries) → BUY/SELL of the linked account
+//! - DIVIDENDS → DIVIDEND, FEES → FEE, INTEREST → INTEREST
+//!
+//! Securities are referenced by ticker (data source YAHOO) or, without ticker,
+//! by ISIN as manual asset.
+
+use crate::cash;
+use crate::db;
+use crate::fifo;
+use crate::security;
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Export file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostfolioExport {
+    pub meta: GhostfolioMeta,
+    pub accounts: Vec<GhostfolioAccount>,
+    pub activities: Vec<GhostfolioActivity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostfolioMeta {
+    pub date: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhostfolioAccount {
+    pub id: String,
+    pub name: String,
+    pub currency: String,
+    pub balance: f64,
+    pub is_excluded: bool,
+    pub comment: Option<String>,
+    pub platform_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhostfolioActivity {
+    pub account_id: Option<String>,
+    pub comment: Option<String>,
+    pub currency: String,
+    pub data_source: String,
+    pub date: String,
+    pub fee: f64,
+    pub quantity: f64,
+    pub symbol: String,
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    pub unit_price: f64,
+}
+
+/// Export result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhostfolioExportResult {
+    pub path: String,
+    pub accounts_exported: usize,
+    pub activities_exported: usize,
+}
+
+/// Export accounts and transactions as Ghostfolio import JSON
+#[command]
+pub fn export_ghostfolio_json(path: String) -> Result<GhostfolioExportResult, String> {
+    // SECURITY: Validate path (defense-in-depth)
+    let validated_path = security::validate_file_path_with_extension(&path, Some(&["json"]))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let today = chrono::Utc::now().date_naive();
+    let export = build_ghostfolio_export(conn, today).map_err(|e| e.to_string())?;
+
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&validated_path, json).map_err(|e| e.to_string())?;
+
+    Ok(GhostfolioExportResult {
+        path: validated_path.to_string_lossy().to_string(),
+        accounts_exported: export.accounts.len(),
+        activities_exported: export.activities.len(),
+    })
+}
+
+/// Build the Ghostfolio export from the database
+fn build_ghostfolio_export(conn: &Connection, today: NaiveDate) -> anyhow::Result<GhostfolioExport> {
+    let mut accounts = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT id, uuid, name, note FROM pp_account WHERE is_retired = 0 ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, uuid, name, note) = row?;
+            let (balance, currency) = cash::get_account_balance_at_date(conn, id, today)?;
+            accounts.push(GhostfolioAccount {
+                id: uuid,
+                name,
+                currency,
+                balance: round2(balance),
+                is_excluded: false,
+                comment: note,
+                platform_id: None,
+            });
+        }
+    }
+
+    // Portfolio transactions are booked on the account of the cross entry,
+    // otherwise on the reference account of the portfolio
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.owner_type, t.txn_type, t.date, t.amount, t.currency, t.shares, t.note,
+               s.name, s.isin, s.ticker,
+               COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'FEE'), 0),
+               COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0),
+               CASE WHEN t.owner_type = 'account'
+                   THEN (SELECT uuid FROM pp_account WHERE id = t.owner_id)
+                   ELSE COALESCE(
+                       (SELECT a.uuid FROM pp_cross_entry ce
+                        JOIN pp_txn at ON at.id = ce.account_txn_id
+                        JOIN pp_account a ON a.id = at.owner_id
+                        WHERE ce.portfolio_txn_id = t.id),
+                       (SELECT a.uuid FROM pp_portfolio p
+                        JOIN pp_account a ON a.id = p.reference_account_id
+                        WHERE p.id = t.owner_id))
+               END as account_uuid
+        FROM pp_txn t
+        LEFT JOIN pp_security s ON s.id = t.security_id
+        WHERE (t.owner_type = 'portfolio'
+               AND t.txn_type IN ('BUY', 'SELL', 'DELIVERY_INBOUND', 'DELIVERY_OUTBOUND'))
+           OR (t.owner_type = 'account' AND t.txn_type IN ('DIVIDENDS', 'FEES', 'INTEREST'))
+        ORDER BY t.date, t.id
+        "#,
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<i64>>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+            row.get::<_, Option<String>>(8)?,
+            row.get::<_, Option<String>>(9)?,
+            row.get::<_, i64>(10)?,
+            row.get::<_, i64>(11)?,
+            row.get::<_, Option<String>>(12)?,
+        ))
+    })?;
+
+    let mut activities = Vec::new();
+    for row in rows {
+        let (
+            owner_type,
+            txn_type,
+            date,
+            amount,
+            currency,
+            shares,
+            note,
+            name,
+            isin,
+            ticker,
+            fees,
+            taxes,
+            account_id,
+        ) = row?;
+
+        let amount = amount as f64 / fifo::AMOUNT_SCALE as f64;
+        let costs = (fees + taxes) as f64 / fifo::AMOUNT_SCALE as f64;
+        let quantity = shares.unwrap_or(0) as f64 / fifo::SHARES_SCALE as f64;
+        let asset = asset_reference(ticker, isin, name);
+
+        let (activity_type, data_source, symbol, quantity, unit_price, fee) =
+            match (owner_type.as_str(), txn_type.as_str()) {
+                ("portfolio", "BUY" | "DELIVERY_INBOUND") => {
+                    let Some((data_source, symbol)) = asset else { continue };
+                    if quantity <= 0.0 {
+                        continue;
+                    }
+                    ("BUY", data_source, symbol, quantity, (amount - costs) / quantity, costs)
+                }
+                ("portfolio", "SELL" | "DELIVERY_OUTBOUND") => {
+                    let Some((data_source, symbol)) = asset else { continue };
+                    if quantity <= 0.0 {
+                        continue;
+                    }
+                    ("SELL", data_source, symbol, quantity, (amount + costs) / quantity, costs)
+                }
+                ("account", "DIVIDENDS") => {
+                    let Some((data_source, symbol)) = asset else { continue };
+                    // Dividend per share from the gross amount (net + taxes + fees)
+                    let quantity = if quantity > 0.0 { quantity } else { 1.0 };
+                    ("DIVIDEND", data_source, symbol, quantity, (amount + costs) / quantity, costs)
+                }
+                ("account", "FEES") => {
+                    let (data_source, symbol) =
+                        asset.unwrap_or_else(|| ("MANUAL".to_string(), "Gebühren".to_string()));
+                    ("FEE", data_source, symbol, 1.0, 0.0, amount)
+                }
+                ("account", "INTEREST") => {
+                    ("INTEREST", "MANUAL".to_string(), "Zinsen".to_string(), 1.0, amount, 0.0)
+                }
+                _ => continue,
+            };
+
+        activities.push(GhostfolioActivity {
+            account_id,
+            comment: note.filter(|n| !n.is_empty()),
+            currency,
+            data_source,
+            date: format!("{}T00:00:00.000Z", &date[..date.len().min(10)]),
+            fee: round2(fee),
+            quantity,
+            symbol,
+            activity_type: activity_type.to_string(),
+            unit_price: unit_price.max(0.0),
+        });
+    }
+
+    Ok(GhostfolioExport {
+        meta: GhostfolioMeta {
+            date: chrono::Utc::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        accounts,
+        activities,
+    })
+}
+
+/// Data source and symbol of a security: ticker (Yahoo), otherwise ISIN or name as manual asset
+fn asset_reference(
+    ticker: Option<String>,
+    isin: Option<String>,
+    name: Option<String>,
+) -> Option<(String, String)> {
+    let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+    if let Some(ticker) = non_empty(ticker) {
+        return Some(("YAHOO".to_string(), ticker));
+    }
+    non_empty(isin)
+        .or_else(|| non_empty(name))
+        .map(|symbol| ("MANUAL".to_string(), symbol))
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_ghostfolio_export() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'acc-1', 'Verrechnungskonto', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'p1', 'Depot', 1);
+             INSERT INTO pp_security (id, uuid, name, currency, isin, ticker) VALUES
+                (1, 's1', 'Apple', 'EUR', 'US0378331005', 'AAPL'),
+                (2, 's2', 'Fonds', 'EUR', 'DE0001234567', NULL);
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 't1', 'account', 1, 'DEPOSIT', '2024-01-01', 200000, 'EUR', NULL, NULL),
+                    (2, 't2', 'portfolio', 1, 'BUY', '2024-01-02', 100500, 'EUR', 1, 1000000000),
+                    (3, 't3', 'account', 1, 'BUY', '2024-01-02', 100500, 'EUR', 1, 1000000000),
+                    (4, 't4', 'account', 1, 'DIVIDENDS', '2024-06-01', 1500, 'EUR', 1, 1000000000),
+                    (5, 't5', 'portfolio', 1, 'DELIVERY_INBOUND', '2024-07-01', 5000, 'EUR', 2, 100000000),
+                    (6, 't6', 'account', 1, 'FEES', '2024-12-31', 1200, 'EUR', NULL, NULL);
+             INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency) VALUES
+                (2, 'FEE', 500, 'EUR'), (4, 'TAX', 500, 'EUR');",
+        )
+        .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let export = build_ghostfolio_export(&conn, today).unwrap();
+
+        assert_eq!(export.accounts.len(), 1);
+        // 2000 - 1005 + 15 - 12
+        assert!((export.accounts[0].balance - 998.0).abs() < 1e-9);
+
+        let types: Vec<&str> = export.activities.iter().map(|a| a.activity_type.as_str()).collect();
+        assert_eq!(types, ["BUY", "DIVIDEND", "BUY", "FEE"]);
+
+        let buy = &export.activities[0];
+        assert_eq!(buy.account_id.as_deref(), Some("acc-1"));
+        assert_eq!((buy.data_source.as_str(), buy.symbol.as_str()), ("YAHOO", "AAPL"));
+        assert_eq!(buy.date, "2024-01-02T00:00:00.000Z");
+        assert!((buy.quantity - 10.0).abs() < 1e-9);
+        assert!((buy.unit_price - 100.0).abs() < 1e-9);
+        assert!((buy.fee - 5.0).abs() < 1e-9);
+
+        // Gross dividend 20 EUR on 10 shares
+        assert!((export.activities[1].unit_price - 2.0).abs() < 1e-9);
+
+        // Without ticker the ISIN is used as manual asset
+        let delivery = &export.activities[2];
+        assert_eq!((delivery.data_source.as_str(), delivery.symbol.as_str()), ("MANUAL", "DE0001234567"));
+
+        let fee = &export.activities[3];
+        assert!((fee.fee - 12.0).abs() < 1e-9);
+        assert_eq!(fee.unit_price, 0.0);
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides Tauri commands for importing PP files (protobuf binary format)
 //! into the SQLite database with full data fidelity.
 
+use crate::commands::audit::record_audit;
 use crate::db;
 use crate::pp::{
     self, Account, Classification, Client, Dashboard, InvestmentPlan, Portfolio, Security,
@@ -13,7 +14,8 @@ use crate::pp::{
 use crate::protobuf;
 use crate::quotes::ExchangeRate;
 use anyhow::Result;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use tauri::{command, AppHandle, Emitter};
 
@@ -28,6 +30,10 @@ pub struct ImportProgress {
     pub total: Option<usize>,
 }
 
+/// Imports with at least this many transactions and prices are followed by
+/// `db::optimize_database` to keep the WAL file from growing
+const OPTIMIZE_AFTER_IMPORT_ROWS: usize = 10_000;
+
 /// Result of a successful import
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -42,11 +48,24 @@ pub struct ImportResult {
     pub transactions_count: usize,
     pub prices_count: usize,
     pub warnings: Vec<String>,
+    /// Set for merge imports
+    pub merge: Option<MergeSummary>,
 }
 
 /// Import a Portfolio Performance XML file into the database
+///
+/// With `merge` the file is merged into the existing data instead: only new
+/// securities, transactions and prices are added (see `merge_client`).
+///
+/// Password-protected files need `password`; without it the error starts with
+/// `PASSWORD_REQUIRED`, with a wrong one with `INCORRECT_PASSWORD`.
 #[command]
-pub async fn import_pp_file(path: String, app: AppHandle) -> Result<ImportResult, String> {
+pub async fn import_pp_file(
+    path: String,
+    app: AppHandle,
+    merge: Option<bool>,
+    password: Option<String>,
+) -> Result<ImportResult, String> {
     // SECURITY: Validate path before processing (defense-in-depth)
     let path_buf = crate::security::validate_file_path_with_extension(&path, Some(&["portfolio"]))
         .map_err(|e| format!("Invalid file path: {}", e))?;
@@ -79,9 +98,26 @@ pub async fn import_pp_file(path: String, app: AppHandle) -> Result<ImportResult
         },
     );
 
-    // Parse protobuf to Client
-    let client = protobuf::parse_portfolio_file(&path_buf)
-        .map_err(|e| format!("Failed to parse portfolio file: {}", e))?;
+    // Reject unsupported files (encrypted, XML, ...) before the full parse
+    let info = protobuf::inspect_portfolio_file(&path_buf)
+        .map_err(|e| format!("Failed to read portfolio file: {}", e))?;
+    if !info.importable {
+        return Err(format!("Unsupported portfolio file: {}", info.issues.join("; ")));
+    }
+
+    // Parse protobuf to Client (decrypting it first if needed)
+    let client = protobuf::parse_portfolio_file_with_password(&path_buf, password.as_deref())
+        .map_err(|e| {
+            let message = e.to_string();
+            // Keep the error code at the start so the frontend can prompt for the password
+            if message.starts_with(protobuf::encryption::PASSWORD_REQUIRED)
+                || message.starts_with(protobuf::encryption::INCORRECT_PASSWORD)
+            {
+                message
+            } else {
+                format!("Failed to parse portfolio file: {}", e)
+            }
+        })?;
 
     // Emit progress: saving
     let _ = app.emit(
@@ -96,8 +132,16 @@ pub async fn import_pp_file(path: String, app: AppHandle) -> Result<ImportResult
     );
 
     // Save to database
-    let result =
-        save_client_to_db(&path, &client, &app).map_err(|e| format!("Failed to save to database: {}", e))?;
+    let result = save_client_to_db(&path, &client, &app, merge.unwrap_or(false))
+        .map_err(|e| format!("Failed to save to database: {}", e))?;
+
+    if let Ok(conn_guard) = db::get_connection() {
+        if let Some(conn) = conn_guard.as_ref() {
+            if result.transactions_count + result.prices_count >= OPTIMIZE_AFTER_IMPORT_ROWS {
+                db::optimize_after_bulk_change(conn, "large import");
+            }
+        }
+    }
 
     // Fetch exchange rates from ECB (non-blocking, errors are logged but don't fail import)
     let _ = app.emit(
@@ -139,13 +183,33 @@ pub async fn import_pp_file(path: String, app: AppHandle) -> Result<ImportResult
     Ok(result)
 }
 
-/// Save the parsed Client to the database
-fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<ImportResult> {
+/// Save (or merge) the parsed Client to the database
+fn save_client_to_db(path: &str, client: &Client, app: &AppHandle, merge: bool) -> Result<ImportResult> {
     let mut conn_guard = db::get_connection()?;
     let conn = conn_guard
         .as_mut()
         .ok_or_else(|| anyhow::anyhow!("Database not initialized"))?;
 
+    let on_progress = |progress| {
+        let _ = app.emit("import-progress", progress);
+    };
+    if merge {
+        merge_client(conn, path, client, on_progress)
+    } else {
+        save_client(conn, path, client, on_progress)
+    }
+}
+
+/// Save the parsed Client on the given connection, reporting progress via `on_progress`
+pub(crate) fn save_client<F>(
+    conn: &mut rusqlite::Connection,
+    path: &str,
+    client: &Client,
+    on_progress: F,
+) -> Result<ImportResult>
+where
+    F: Fn(ImportProgress),
+{
     // Start transaction for atomic import
     let tx = conn.transaction()?;
 
@@ -183,16 +247,13 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
     let mut warnings = Vec::new();
 
     // Import securities
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "securities".to_string(),
-            message: "Importing securities...".to_string(),
-            percent: 35,
-            current: Some(0),
-            total: Some(client.securities.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "securities".to_string(),
+        message: "Importing securities...".to_string(),
+        percent: 35,
+        current: Some(0),
+        total: Some(client.securities.len()),
+    });
 
     for (i, security) in client.securities.iter().enumerate() {
         if let Err(e) = insert_security(&tx, import_id, security) {
@@ -200,117 +261,93 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
         }
 
         if i % 10 == 0 {
-            let _ = app.emit(
-                "import-progress",
-                ImportProgress {
-                    stage: "securities".to_string(),
-                    message: format!("Importing security: {}", security.name),
-                    percent: 35 + (i * 15 / client.securities.len().max(1)) as u32,
-                    current: Some(i),
-                    total: Some(client.securities.len()),
-                },
-            );
+            on_progress(ImportProgress {
+                stage: "securities".to_string(),
+                message: format!("Importing security: {}", security.name),
+                percent: 35 + (i * 15 / client.securities.len().max(1)) as u32,
+                current: Some(i),
+                total: Some(client.securities.len()),
+            });
         }
     }
 
     // Import accounts
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "accounts".to_string(),
-            message: "Importing accounts...".to_string(),
-            percent: 50,
-            current: Some(0),
-            total: Some(client.accounts.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "accounts".to_string(),
+        message: "Importing accounts...".to_string(),
+        percent: 50,
+        current: Some(0),
+        total: Some(client.accounts.len()),
+    });
 
     for (i, account) in client.accounts.iter().enumerate() {
         if let Err(e) = insert_account(&tx, import_id, account) {
             warnings.push(format!("Account {}: {}", account.name, e));
         }
 
-        let _ = app.emit(
-            "import-progress",
-            ImportProgress {
-                stage: "accounts".to_string(),
-                message: format!("Importing account: {}", account.name),
-                percent: 50 + (i * 10 / client.accounts.len().max(1)) as u32,
-                current: Some(i),
-                total: Some(client.accounts.len()),
-            },
-        );
+        on_progress(ImportProgress {
+            stage: "accounts".to_string(),
+            message: format!("Importing account: {}", account.name),
+            percent: 50 + (i * 10 / client.accounts.len().max(1)) as u32,
+            current: Some(i),
+            total: Some(client.accounts.len()),
+        });
     }
 
     // Import portfolios
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "portfolios".to_string(),
-            message: "Importing portfolios...".to_string(),
-            percent: 60,
-            current: Some(0),
-            total: Some(client.portfolios.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "portfolios".to_string(),
+        message: "Importing portfolios...".to_string(),
+        percent: 60,
+        current: Some(0),
+        total: Some(client.portfolios.len()),
+    });
 
     for (i, portfolio) in client.portfolios.iter().enumerate() {
         if let Err(e) = insert_portfolio(&tx, import_id, portfolio) {
             warnings.push(format!("Portfolio {}: {}", portfolio.name, e));
         }
 
-        let _ = app.emit(
-            "import-progress",
-            ImportProgress {
-                stage: "portfolios".to_string(),
-                message: format!("Importing portfolio: {}", portfolio.name),
-                percent: 60 + (i * 10 / client.portfolios.len().max(1)) as u32,
-                current: Some(i),
-                total: Some(client.portfolios.len()),
-            },
-        );
+        on_progress(ImportProgress {
+            stage: "portfolios".to_string(),
+            message: format!("Importing portfolio: {}", portfolio.name),
+            percent: 60 + (i * 10 / client.portfolios.len().max(1)) as u32,
+            current: Some(i),
+            total: Some(client.portfolios.len()),
+        });
     }
 
     // Import taxonomies
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "taxonomies".to_string(),
-            message: "Importing taxonomies...".to_string(),
-            percent: 70,
-            current: Some(0),
-            total: Some(client.taxonomies.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "taxonomies".to_string(),
+        message: "Importing taxonomies...".to_string(),
+        percent: 70,
+        current: Some(0),
+        total: Some(client.taxonomies.len()),
+    });
 
     for (i, taxonomy) in client.taxonomies.iter().enumerate() {
         if let Err(e) = insert_taxonomy(&tx, import_id, taxonomy) {
             warnings.push(format!("Taxonomy {}: {}", taxonomy.name, e));
         }
 
-        let _ = app.emit(
-            "import-progress",
-            ImportProgress {
-                stage: "taxonomies".to_string(),
-                message: format!("Importing taxonomy: {}", taxonomy.name),
-                percent: 70 + (i * 10 / client.taxonomies.len().max(1)) as u32,
-                current: Some(i),
-                total: Some(client.taxonomies.len()),
-            },
-        );
+        on_progress(ImportProgress {
+            stage: "taxonomies".to_string(),
+            message: format!("Importing taxonomy: {}", taxonomy.name),
+            percent: 70 + (i * 10 / client.taxonomies.len().max(1)) as u32,
+            current: Some(i),
+            total: Some(client.taxonomies.len()),
+        });
     }
 
     // Import watchlists
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "watchlists".to_string(),
-            message: "Importing watchlists...".to_string(),
-            percent: 80,
-            current: Some(0),
-            total: Some(client.watchlists.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "watchlists".to_string(),
+        message: "Importing watchlists...".to_string(),
+        percent: 80,
+        current: Some(0),
+        total: Some(client.watchlists.len()),
+    });
 
     for watchlist in &client.watchlists {
         if let Err(e) = insert_watchlist(&tx, import_id, watchlist) {
@@ -319,16 +356,13 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
     }
 
     // Import investment plans
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "plans".to_string(),
-            message: "Importing investment plans...".to_string(),
-            percent: 81,
-            current: Some(0),
-            total: Some(client.plans.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "plans".to_string(),
+        message: "Importing investment plans...".to_string(),
+        percent: 81,
+        current: Some(0),
+        total: Some(client.plans.len()),
+    });
 
     for (i, plan) in client.plans.iter().enumerate() {
         if let Err(e) = insert_investment_plan(&tx, import_id, plan) {
@@ -336,46 +370,37 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
         }
 
         if i % 5 == 0 {
-            let _ = app.emit(
-                "import-progress",
-                ImportProgress {
-                    stage: "plans".to_string(),
-                    message: format!("Importing plan: {}", plan.name),
-                    percent: 81,
-                    current: Some(i),
-                    total: Some(client.plans.len()),
-                },
-            );
+            on_progress(ImportProgress {
+                stage: "plans".to_string(),
+                message: format!("Importing plan: {}", plan.name),
+                percent: 81,
+                current: Some(i),
+                total: Some(client.plans.len()),
+            });
         }
     }
 
     // Import client properties
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "properties".to_string(),
-            message: "Importing properties...".to_string(),
-            percent: 82,
-            current: None,
-            total: None,
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "properties".to_string(),
+        message: "Importing properties...".to_string(),
+        percent: 82,
+        current: None,
+        total: None,
+    });
 
     if let Err(e) = insert_client_properties(&tx, import_id, client) {
         warnings.push(format!("Client properties: {}", e));
     }
 
     // Import dashboards
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "dashboards".to_string(),
-            message: "Importing dashboards...".to_string(),
-            percent: 83,
-            current: Some(0),
-            total: Some(client.dashboards.len()),
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "dashboards".to_string(),
+        message: "Importing dashboards...".to_string(),
+        percent: 83,
+        current: Some(0),
+        total: Some(client.dashboards.len()),
+    });
 
     for (i, dashboard) in client.dashboards.iter().enumerate() {
         if let Err(e) = insert_dashboard(&tx, import_id, dashboard) {
@@ -383,67 +408,69 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
         }
 
         if i % 2 == 0 {
-            let _ = app.emit(
-                "import-progress",
-                ImportProgress {
-                    stage: "dashboards".to_string(),
-                    message: format!("Importing dashboard: {}", dashboard.name),
-                    percent: 83,
-                    current: Some(i),
-                    total: Some(client.dashboards.len()),
-                },
-            );
+            on_progress(ImportProgress {
+                stage: "dashboards".to_string(),
+                message: format!("Importing dashboard: {}", dashboard.name),
+                percent: 83,
+                current: Some(i),
+                total: Some(client.dashboards.len()),
+            });
         }
     }
 
     // Import settings
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "settings".to_string(),
-            message: "Importing settings...".to_string(),
-            percent: 84,
-            current: None,
-            total: None,
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "settings".to_string(),
+        message: "Importing settings...".to_string(),
+        percent: 84,
+        current: None,
+        total: None,
+    });
 
     if let Err(e) = insert_settings(&tx, import_id, client) {
         warnings.push(format!("Settings: {}", e));
     }
 
     // Link cross-entries
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "linking".to_string(),
-            message: "Linking cross-entries...".to_string(),
-            percent: 85,
-            current: None,
-            total: None,
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "linking".to_string(),
+        message: "Linking cross-entries...".to_string(),
+        percent: 85,
+        current: None,
+        total: None,
+    });
 
     if let Err(e) = link_cross_entries(&tx, client) {
         warnings.push(format!("Cross-entries: {}", e));
     }
 
     // Build FIFO lots for cost basis calculation
-    let _ = app.emit(
-        "import-progress",
-        ImportProgress {
-            stage: "fifo".to_string(),
-            message: "Building FIFO lots...".to_string(),
-            percent: 92,
-            current: None,
-            total: None,
-        },
-    );
+    on_progress(ImportProgress {
+        stage: "fifo".to_string(),
+        message: "Building FIFO lots...".to_string(),
+        percent: 92,
+        current: None,
+        total: None,
+    });
 
     if let Err(e) = crate::fifo::build_all_fifo_lots(&tx) {
         warnings.push(format!("FIFO calculation: {}", e));
     }
 
+    record_audit(
+        &tx,
+        "import_pp_file",
+        "import",
+        Some(import_id),
+        &format!(
+            "Datei importiert {}: {} Wertpapiere, {} Buchungen, {} Kurse",
+            path,
+            client.securities.len(),
+            total_transactions,
+            total_prices
+        ),
+    );
+
     // Commit transaction
     tx.commit()?;
 
@@ -458,6 +485,353 @@ fn save_client_to_db(path: &str, client: &Client, app: &AppHandle) -> Result<Imp
         transactions_count: total_transactions,
         prices_count: total_prices,
         warnings,
+        merge: None,
+    })
+}
+
+/// Counts of a merge import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSummary {
+    /// Securities found in the database by UUID or ISIN
+    pub securities_matched: usize,
+    pub securities_added: usize,
+    pub accounts_matched: usize,
+    pub accounts_added: usize,
+    pub portfolios_matched: usize,
+    pub portfolios_added: usize,
+    pub transactions_added: usize,
+    /// Transactions whose UUID already exists
+    pub transactions_skipped: usize,
+    pub prices_added: usize,
+    /// Prices for dates that already have a price
+    pub prices_skipped: usize,
+}
+
+/// Find a security of the file in the database, first by UUID, then by ISIN
+fn find_existing_security(
+    tx: &rusqlite::Transaction,
+    security: &Security,
+) -> Result<Option<(i64, String)>> {
+    let by_uuid = tx
+        .query_row(
+            "SELECT id, uuid FROM pp_security WHERE uuid = ?1",
+            params![security.uuid],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if by_uuid.is_some() {
+        return Ok(by_uuid);
+    }
+
+    match security.isin.as_deref().map(str::trim).filter(|isin| !isin.is_empty()) {
+        Some(isin) => Ok(tx
+            .query_row(
+                "SELECT id, uuid FROM pp_security WHERE isin = ?1 ORDER BY id LIMIT 1",
+                params![isin],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?),
+        None => Ok(None),
+    }
+}
+
+fn find_id_by_uuid(tx: &rusqlite::Transaction, table: &str, uuid: &str) -> Result<Option<i64>> {
+    Ok(tx
+        .query_row(
+            &format!("SELECT id FROM {} WHERE uuid = ?1", table),
+            params![uuid],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Merge a parsed Client into the existing data
+///
+/// Securities are matched by UUID or ISIN, accounts and portfolios by UUID.
+/// Only transactions with unknown UUIDs and prices for new dates are inserted
+/// and a newer latest price replaces the stored one; existing records and the
+/// base currency are left untouched.
+pub(crate) fn merge_client<F>(
+    conn: &mut rusqlite::Connection,
+    path: &str,
+    client: &Client,
+    on_progress: F,
+) -> Result<ImportResult>
+where
+    F: Fn(ImportProgress),
+{
+    let tx = conn.transaction()?;
+
+    let total_transactions: usize = client
+        .accounts
+        .iter()
+        .map(|a| a.transactions.len())
+        .sum::<usize>()
+        + client
+            .portfolios
+            .iter()
+            .map(|p| p.transactions.len())
+            .sum::<usize>();
+    let total_prices: usize = client.securities.iter().map(|s| s.prices.len()).sum();
+
+    // The latest import row defines the base currency; a merge keeps the current one
+    let base_currency = crate::currency::get_base_currency(&tx).unwrap_or_else(|_| client.base_currency.clone());
+    tx.execute(
+        "INSERT INTO pp_import (file_path, version, base_currency, securities_count, accounts_count, portfolios_count, transactions_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            path,
+            client.version,
+            base_currency,
+            client.securities.len(),
+            client.accounts.len(),
+            client.portfolios.len(),
+            total_transactions,
+        ],
+    )?;
+    let import_id = tx.last_insert_rowid();
+
+    let mut summary = MergeSummary::default();
+    let mut warnings = Vec::new();
+
+    // Securities: file UUID -> UUID in the database (differs for ISIN matches)
+    on_progress(ImportProgress {
+        stage: "securities".to_string(),
+        message: "Merging securities...".to_string(),
+        percent: 35,
+        current: Some(0),
+        total: Some(client.securities.len()),
+    });
+
+    let mut security_uuids: HashMap<String, String> = HashMap::new();
+    for security in &client.securities {
+        match find_existing_security(&tx, security)? {
+            Some((security_id, db_uuid)) => {
+                summary.securities_matched += 1;
+                for price in &security.prices {
+                    let inserted = tx.execute(
+                        "INSERT OR IGNORE INTO pp_price (security_id, date, value) VALUES (?1, ?2, ?3)",
+                        params![security_id, price.date.to_string(), price.value],
+                    )?;
+                    if inserted > 0 {
+                        summary.prices_added += 1;
+                    } else {
+                        summary.prices_skipped += 1;
+                    }
+                }
+                // A newer latest price from the file wins
+                if let Some(latest) = security.latest.as_ref().filter(|l| l.value.is_some()) {
+                    if let Some(date) = latest.date {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO pp_latest_price (security_id, date, value, high, low, volume)
+                             SELECT ?1, ?2, ?3, ?4, ?5, ?6
+                             WHERE ?2 > COALESCE((SELECT date FROM pp_latest_price WHERE security_id = ?1), '')",
+                            params![security_id, date.to_string(), latest.value, latest.high, latest.low, latest.volume],
+                        )?;
+                    }
+                }
+                security_uuids.insert(security.uuid.clone(), db_uuid);
+            }
+            None => match insert_security(&tx, import_id, security) {
+                Ok(_) => {
+                    summary.securities_added += 1;
+                    summary.prices_added += security.prices.len();
+                }
+                Err(e) => warnings.push(format!("Security {}: {}", security.name, e)),
+            },
+        }
+    }
+
+    let remap_security = |uuid: &Option<String>| {
+        uuid.as_ref()
+            .map(|u| security_uuids.get(u).cloned().unwrap_or_else(|| u.clone()))
+    };
+
+    // New transactions only, used to link their cross-entries afterwards
+    let mut delta = Client::new(&client.base_currency);
+
+    on_progress(ImportProgress {
+        stage: "accounts".to_string(),
+        message: "Merging accounts...".to_string(),
+        percent: 50,
+        current: Some(0),
+        total: Some(client.accounts.len()),
+    });
+
+    for account in &client.accounts {
+        let mut new_account = Account {
+            transactions: Vec::new(),
+            ..account.clone()
+        };
+        let account_id = match find_id_by_uuid(&tx, "pp_account", &account.uuid)? {
+            Some(id) => {
+                summary.accounts_matched += 1;
+                id
+            }
+            None => match insert_account(&tx, import_id, &new_account) {
+                Ok(id) => {
+                    summary.accounts_added += 1;
+                    id
+                }
+                Err(e) => {
+                    warnings.push(format!("Account {}: {}", account.name, e));
+                    continue;
+                }
+            },
+        };
+
+        for txn in &account.transactions {
+            if find_id_by_uuid(&tx, "pp_txn", &txn.uuid)?.is_some() {
+                summary.transactions_skipped += 1;
+                continue;
+            }
+            let mut txn = txn.clone();
+            txn.security_uuid = remap_security(&txn.security_uuid);
+            match insert_account_transaction(&tx, account_id, &txn) {
+                Ok(_) => {
+                    summary.transactions_added += 1;
+                    new_account.transactions.push(txn);
+                }
+                Err(e) => warnings.push(format!("Transaction {}: {}", txn.uuid, e)),
+            }
+        }
+        delta.accounts.push(new_account);
+    }
+
+    on_progress(ImportProgress {
+        stage: "portfolios".to_string(),
+        message: "Merging portfolios...".to_string(),
+        percent: 60,
+        current: Some(0),
+        total: Some(client.portfolios.len()),
+    });
+
+    for portfolio in &client.portfolios {
+        let mut new_portfolio = Portfolio {
+            transactions: Vec::new(),
+            ..portfolio.clone()
+        };
+        let portfolio_id = match find_id_by_uuid(&tx, "pp_portfolio", &portfolio.uuid)? {
+            Some(id) => {
+                summary.portfolios_matched += 1;
+                id
+            }
+            None => match insert_portfolio(&tx, import_id, &new_portfolio) {
+                Ok(id) => {
+                    summary.portfolios_added += 1;
+                    id
+                }
+                Err(e) => {
+                    warnings.push(format!("Portfolio {}: {}", portfolio.name, e));
+                    continue;
+                }
+            },
+        };
+
+        for txn in &portfolio.transactions {
+            if find_id_by_uuid(&tx, "pp_txn", &txn.uuid)?.is_some() {
+                summary.transactions_skipped += 1;
+                continue;
+            }
+            let mut txn = txn.clone();
+            txn.security_uuid = remap_security(&txn.security_uuid);
+            match insert_portfolio_transaction(&tx, portfolio_id, &txn) {
+                Ok(_) => {
+                    summary.transactions_added += 1;
+                    new_portfolio.transactions.push(txn);
+                }
+                Err(e) => warnings.push(format!("Transaction {}: {}", txn.uuid, e)),
+            }
+        }
+        delta.portfolios.push(new_portfolio);
+    }
+
+    // Taxonomies and watchlists are only added if they are new
+    on_progress(ImportProgress {
+        stage: "taxonomies".to_string(),
+        message: "Merging taxonomies and watchlists...".to_string(),
+        percent: 70,
+        current: None,
+        total: None,
+    });
+
+    for taxonomy in &client.taxonomies {
+        if find_id_by_uuid(&tx, "pp_taxonomy", &taxonomy.id)?.is_none() {
+            if let Err(e) = insert_taxonomy(&tx, import_id, taxonomy) {
+                warnings.push(format!("Taxonomy {}: {}", taxonomy.name, e));
+            }
+        }
+    }
+
+    for watchlist in &client.watchlists {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pp_watchlist WHERE name = ?1)",
+            params![watchlist.name],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            let mut watchlist = watchlist.clone();
+            watchlist.security_uuids = watchlist
+                .security_uuids
+                .iter()
+                .map(|u| security_uuids.get(u).cloned().unwrap_or_else(|| u.clone()))
+                .collect();
+            if let Err(e) = insert_watchlist(&tx, import_id, &watchlist) {
+                warnings.push(format!("Watchlist {}: {}", watchlist.name, e));
+            }
+        }
+    }
+
+    on_progress(ImportProgress {
+        stage: "linking".to_string(),
+        message: "Linking cross-entries...".to_string(),
+        percent: 85,
+        current: None,
+        total: None,
+    });
+
+    if let Err(e) = link_cross_entries(&tx, &delta) {
+        warnings.push(format!("Cross-entries: {}", e));
+    }
+
+    on_progress(ImportProgress {
+        stage: "fifo".to_string(),
+        message: "Building FIFO lots...".to_string(),
+        percent: 92,
+        current: None,
+        total: None,
+    });
+
+    if let Err(e) = crate::fifo::build_all_fifo_lots(&tx) {
+        warnings.push(format!("FIFO calculation: {}", e));
+    }
+
+    record_audit(
+        &tx,
+        "import_pp_file",
+        "import",
+        Some(import_id),
+        &format!(
+            "Datei zusammengeführt {}: {} neue Wertpapiere, {} neue Buchungen, {} neue Kurse",
+            path, summary.securities_added, summary.transactions_added, summary.prices_added
+        ),
+    );
+
+    tx.commit()?;
+
+    Ok(ImportResult {
+        import_id,
+        file_path: path.to_string(),
+        version: client.version,
+        base_currency: client.base_currency.clone(),
+        securities_count: client.securities.len(),
+        accounts_count: client.accounts.len(),
+        portfolios_count: client.portfolios.len(),
+        transactions_count: total_transactions,
+        prices_count: total_prices,
+        warnings,
+        merge: Some(summary),
     })
 }
 
@@ -1432,6 +1806,7 @@ pub fn delete_import(import_id: i64) -> Result<(), String> {
     // Delete will cascade to all related tables
     conn.execute("DELETE FROM pp_import WHERE id = ?1", params![import_id])
         .map_err(|e| e.to_string())?;
+    record_audit(conn, "delete_import", "import", Some(import_id), "Import gelöscht");
 
     Ok(())
 }
@@ -1482,21 +1857,36 @@ fn parse_split_ratio(details: &str) -> (i32, i32) {
 }
 
 /// Rebuild all FIFO lots from transactions
-/// Call this after fixing FIFO calculation logic to recalculate cost basis
+/// Call this after fixing FIFO calculation logic to recalculate cost basis.
+/// With `security_ids` only those securities are rebuilt (e.g. once after a
+/// bulk import that created transactions with `skip_fifo_rebuild`).
 #[command]
-pub fn rebuild_fifo_lots() -> Result<RebuildFifoResult, String> {
+pub fn rebuild_fifo_lots(security_ids: Option<Vec<i64>>) -> Result<RebuildFifoResult, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
-    // Count securities before rebuild
-    let security_count: i64 = conn
-        .query_row("SELECT COUNT(DISTINCT id) FROM pp_security", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    // Rebuild FIFO lots for all securities
-    crate::fifo::build_all_fifo_lots(conn).map_err(|e| e.to_string())?;
+    let security_count: i64 = match &security_ids {
+        Some(ids) => {
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            for security_id in ids {
+                crate::fifo::build_fifo_lots(&tx, *security_id).map_err(|e| e.to_string())?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+            ids.len() as i64
+        }
+        None => {
+            // Count securities before rebuild
+            let count = conn
+                .query_row("SELECT COUNT(DISTINCT id) FROM pp_security", [], |row| row.get(0))
+                .unwrap_or(0);
+
+            // Rebuild FIFO lots for all securities
+            crate::fifo::build_all_fifo_lots(conn).map_err(|e| e.to_string())?;
+            count
+        }
+    };
 
     // Count lots after rebuild
     let lot_count: i64 = conn
@@ -1516,3 +1906,119 @@ pub struct RebuildFifoResult {
     pub securities_processed: usize,
     pub lots_created: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pp::common::{LatestPrice, Money, PriceEntry};
+    use crate::pp::AccountTransactionType;
+    use chrono::NaiveDate;
+
+    fn deposit(uuid: &str, day: u32, amount: i64) -> AccountTransaction {
+        AccountTransaction::new(
+            uuid.into(),
+            NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            AccountTransactionType::Deposit,
+            Money::new(amount, "EUR"),
+        )
+    }
+
+    fn client(security_uuid: &str, prices: &[u32], deposits: &[(&str, u32)]) -> Client {
+        let mut client = Client::new("EUR");
+        let mut security = Security::new(security_uuid.into(), "MSCI World".into(), "EUR".into());
+        security.isin = Some("IE00B4L5Y983".into());
+        for day in prices {
+            let date = NaiveDate::from_ymd_opt(2024, 1, *day).unwrap();
+            security.prices.push(PriceEntry::new(date, 8_000_000_000));
+        }
+        client.securities.push(security);
+
+        let mut account = Account::new("acc-1".into(), "Verrechnungskonto".into(), "EUR".into());
+        for (uuid, day) in deposits {
+            account.transactions.push(deposit(uuid, *day, 10_000));
+        }
+        client.accounts.push(account);
+        client
+    }
+
+    fn count(conn: &rusqlite::Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_merge_client() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        save_client(&mut conn, "first.portfolio", &client("sec-1", &[1, 2], &[("dep-1", 1)]), |_| {})
+            .unwrap();
+
+        // Same security under another UUID, one known and one new transaction
+        let update = client("sec-other", &[2, 3], &[("dep-1", 1), ("dep-2", 5)]);
+        let result = merge_client(&mut conn, "second.portfolio", &update, |_| {}).unwrap();
+        let summary = result.merge.unwrap();
+
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+        assert_eq!(summary.securities_matched, 1);
+        assert_eq!(summary.securities_added, 0);
+        assert_eq!(summary.accounts_matched, 1);
+        assert_eq!(summary.transactions_added, 1);
+        assert_eq!(summary.transactions_skipped, 1);
+        assert_eq!(summary.prices_added, 1);
+        assert_eq!(summary.prices_skipped, 1);
+
+        assert_eq!(count(&conn, "pp_security"), 1);
+        assert_eq!(count(&conn, "pp_account"), 1);
+        assert_eq!(count(&conn, "pp_txn"), 2);
+        assert_eq!(count(&conn, "pp_price"), 3);
+
+        // Merging the same file again changes nothing
+        let again = merge_client(&mut conn, "second.portfolio", &update, |_| {}).unwrap();
+        let summary = again.merge.unwrap();
+        assert_eq!(summary.transactions_added, 0);
+        assert_eq!(summary.transactions_skipped, 2);
+        assert_eq!(summary.prices_added, 0);
+        assert_eq!(count(&conn, "pp_txn"), 2);
+        // Import and both merges are audited in their transactions
+        assert_eq!(count(&conn, "pp_audit_log"), 3);
+    }
+
+    #[test]
+    fn test_merge_client_keeps_base_currency_and_updates_latest_price() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        let mut first = client("sec-1", &[1], &[]);
+        first.securities[0].latest = Some(LatestPrice {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1),
+            value: Some(8_000_000_000),
+            high: None,
+            low: None,
+            volume: None,
+        });
+        save_client(&mut conn, "first.portfolio", &first, |_| {}).unwrap();
+
+        let mut update = first.clone();
+        update.base_currency = "USD".to_string();
+        update.securities[0].latest = Some(LatestPrice {
+            date: NaiveDate::from_ymd_opt(2024, 2, 1),
+            value: Some(9_000_000_000),
+            high: None,
+            low: None,
+            volume: None,
+        });
+        merge_client(&mut conn, "second.portfolio", &update, |_| {}).unwrap();
+
+        assert_eq!(crate::currency::get_base_currency(&conn).unwrap(), "EUR");
+        let latest: (String, i64) = conn
+            .query_row("SELECT date, value FROM pp_latest_price", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(latest, ("2024-02-01".to_string(), 9_000_000_000));
+
+        // An older latest price does not overwrite the stored one
+        merge_client(&mut conn, "first.portfolio", &first, |_| {}).unwrap();
+        let value: i64 = conn
+            .query_row("SELECT value FROM pp_latest_price", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, 9_000_000_000);
+    }
+}
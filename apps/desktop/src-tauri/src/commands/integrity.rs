@@ -0,0 +1,364 @@
+//! Data integrity check commands for Tauri
+//!
+//! Finds inconsistencies that lead to wrong cash, IRR or FIFO numbers:
+//! broken cross-entries, BUY/SELL without account side, transactions of
+//! missing securities, negative positions, unlinked accounts and prices of
+//! deleted securities. Read-only; nothing is repaired automatically.
+
+use crate::db;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::command;
+
+/// Maximum number of listed issues per category (count stays exact)
+const MAX_ISSUES_PER_CATEGORY: usize = 100;
+
+/// A single integrity problem
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    /// "transaction", "cross_entry", "account", "security", "price", "fifo_lot"
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub message: String,
+}
+
+/// Issues of one check
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityCategory {
+    pub category: String,
+    pub description: String,
+    /// "error" (wrong numbers) or "warning" (possibly intended)
+    pub severity: String,
+    pub count: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+/// Result of the integrity check
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub checked_at: String,
+    pub issue_count: usize,
+    /// Only categories with issues
+    pub categories: Vec<IntegrityCategory>,
+}
+
+/// Run all integrity checks on the database
+#[command]
+pub fn run_integrity_check() -> Result<IntegrityReport, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    check_integrity(conn).map_err(|e| e.to_string())
+}
+
+fn check_integrity(conn: &Connection) -> rusqlite::Result<IntegrityReport> {
+    let checks = [
+        (
+            "orphaned_cross_entries",
+            "Verknüpfungen, die auf gelöschte oder fehlende Buchungen zeigen",
+            "error",
+            check_orphaned_cross_entries(conn)?,
+        ),
+        (
+            "missing_cross_entries",
+            "Käufe/Verkäufe im Depot ohne Gegenbuchung auf einem Konto",
+            "warning",
+            check_missing_cross_entries(conn)?,
+        ),
+        (
+            "missing_securities",
+            "Buchungen, deren Wertpapier fehlt",
+            "error",
+            check_missing_securities(conn)?,
+        ),
+        (
+            "negative_positions",
+            "Bestände, die zu einem Zeitpunkt negativ werden",
+            "error",
+            check_negative_positions(conn)?,
+        ),
+        (
+            "unlinked_accounts",
+            "Konten ohne verknüpftes Depot",
+            "warning",
+            check_unlinked_accounts(conn)?,
+        ),
+        (
+            "orphaned_prices",
+            "Kurse gelöschter Wertpapiere",
+            "error",
+            check_orphaned_prices(conn)?,
+        ),
+    ];
+
+    let categories: Vec<IntegrityCategory> = checks
+        .into_iter()
+        .filter(|(_, _, _, issues)| !issues.is_empty())
+        .map(|(category, description, severity, mut issues)| {
+            let count = issues.len();
+            issues.truncate(MAX_ISSUES_PER_CATEGORY);
+            IntegrityCategory {
+                category: category.to_string(),
+                description: description.to_string(),
+                severity: severity.to_string(),
+                count,
+                issues,
+            }
+        })
+        .collect();
+
+    Ok(IntegrityReport {
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        issue_count: categories.iter().map(|c| c.count).sum(),
+        categories,
+    })
+}
+
+/// Run a query returning (entity_id, message) rows as issues
+fn collect_issues(
+    conn: &Connection,
+    entity_type: &str,
+    sql: &str,
+) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(IntegrityIssue {
+            entity_type: entity_type.to_string(),
+            entity_id: row.get(0)?,
+            message: row.get(1)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn check_orphaned_cross_entries(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    collect_issues(
+        conn,
+        "cross_entry",
+        r#"
+        SELECT ce.id,
+               ce.entry_type || ': ' ||
+               CASE
+                   WHEN ce.entry_type = 'BUY_SELL' AND (ce.portfolio_txn_id IS NULL OR ce.account_txn_id IS NULL)
+                       THEN 'Depot- oder Kontobuchung nicht gesetzt'
+                   WHEN ce.entry_type != 'BUY_SELL' AND (ce.from_txn_id IS NULL OR ce.to_txn_id IS NULL)
+                       THEN 'Quell- oder Zielbuchung nicht gesetzt'
+                   ELSE 'verweist auf fehlende Buchung'
+               END
+        FROM pp_cross_entry ce
+        WHERE (ce.entry_type = 'BUY_SELL' AND (ce.portfolio_txn_id IS NULL OR ce.account_txn_id IS NULL))
+           OR (ce.entry_type != 'BUY_SELL' AND (ce.from_txn_id IS NULL OR ce.to_txn_id IS NULL))
+           OR (ce.portfolio_txn_id IS NOT NULL AND ce.portfolio_txn_id NOT IN (SELECT id FROM pp_txn))
+           OR (ce.account_txn_id IS NOT NULL AND ce.account_txn_id NOT IN (SELECT id FROM pp_txn))
+           OR (ce.from_txn_id IS NOT NULL AND ce.from_txn_id NOT IN (SELECT id FROM pp_txn))
+           OR (ce.to_txn_id IS NOT NULL AND ce.to_txn_id NOT IN (SELECT id FROM pp_txn))
+        ORDER BY ce.id
+        "#,
+    )
+}
+
+fn check_missing_cross_entries(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    collect_issues(
+        conn,
+        "transaction",
+        r#"
+        SELECT t.id, t.txn_type || ' vom ' || date(t.date) || ' ohne Kontobuchung'
+        FROM pp_txn t
+        WHERE t.owner_type = 'portfolio'
+          AND t.txn_type IN ('BUY', 'SELL')
+          AND NOT EXISTS (
+              SELECT 1 FROM pp_cross_entry ce
+              JOIN pp_txn at ON at.id = ce.account_txn_id
+              WHERE ce.portfolio_txn_id = t.id
+          )
+        ORDER BY t.date, t.id
+        "#,
+    )
+}
+
+fn check_missing_securities(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    collect_issues(
+        conn,
+        "transaction",
+        r#"
+        SELECT t.id,
+               CASE WHEN t.security_id IS NULL
+                   THEN t.txn_type || ' vom ' || date(t.date) || ' ohne Wertpapier'
+                   ELSE t.txn_type || ' vom ' || date(t.date) || ': Wertpapier ' || t.security_id || ' existiert nicht'
+               END
+        FROM pp_txn t
+        WHERE (t.security_id IS NOT NULL AND t.security_id NOT IN (SELECT id FROM pp_security))
+           OR (t.security_id IS NULL AND t.owner_type = 'portfolio')
+        ORDER BY t.date, t.id
+        "#,
+    )
+}
+
+/// Replays all portfolio transactions per (portfolio, security) in date order and
+/// reports the first date the position drops below zero, plus negative FIFO lots
+fn check_negative_positions(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.id, t.owner_id, t.security_id, date(t.date), t.txn_type, t.shares,
+               COALESCE(s.name, 'Wertpapier ' || t.security_id), COALESCE(p.name, 'Depot ' || t.owner_id)
+        FROM pp_txn t
+        LEFT JOIN pp_security s ON s.id = t.security_id
+        LEFT JOIN pp_portfolio p ON p.id = t.owner_id
+        WHERE t.owner_type = 'portfolio'
+          AND t.security_id IS NOT NULL
+          AND t.shares IS NOT NULL
+          AND t.txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND',
+                             'SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND')
+        ORDER BY date(t.date),
+                 CASE WHEN t.txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN 0 ELSE 1 END,
+                 t.id
+        "#,
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, i64>(5)?,
+            row.get::<_, String>(6)?,
+            row.get::<_, String>(7)?,
+        ))
+    })?;
+
+    let mut positions: HashMap<(i64, i64), i64> = HashMap::new();
+    let mut reported: std::collections::HashSet<(i64, i64)> = std::collections::HashSet::new();
+    let mut issues = Vec::new();
+
+    for row in rows {
+        let (txn_id, portfolio_id, security_id, date, txn_type, shares, security, portfolio) = row?;
+        let key = (portfolio_id, security_id);
+        let position = positions.entry(key).or_insert(0);
+        match txn_type.as_str() {
+            "BUY" | "TRANSFER_IN" | "DELIVERY_INBOUND" => *position += shares,
+            _ => *position -= shares,
+        }
+        if *position < 0 && reported.insert(key) {
+            issues.push(IntegrityIssue {
+                entity_type: "transaction".to_string(),
+                entity_id: txn_id,
+                message: format!(
+                    "{} in {}: Bestand am {} negativ ({:.4} Stück)",
+                    security,
+                    portfolio,
+                    date,
+                    *position as f64 / crate::fifo::SHARES_SCALE as f64
+                ),
+            });
+        }
+    }
+
+    issues.extend(collect_issues(
+        conn,
+        "fifo_lot",
+        r#"
+        SELECT id, 'FIFO-Lot mit negativem Restbestand (Wertpapier ' || security_id || ')'
+        FROM pp_fifo_lot
+        WHERE remaining_shares < 0
+        ORDER BY id
+        "#,
+    )?);
+
+    Ok(issues)
+}
+
+fn check_unlinked_accounts(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    collect_issues(
+        conn,
+        "account",
+        r#"
+        SELECT a.id, a.name || ' ist keinem Depot zugeordnet'
+        FROM pp_account a
+        WHERE a.is_retired = 0
+          AND a.id NOT IN (
+              SELECT reference_account_id FROM pp_portfolio WHERE reference_account_id IS NOT NULL
+          )
+          AND NOT EXISTS (
+              SELECT 1 FROM pp_cross_entry ce
+              JOIN pp_txn at ON at.id = ce.account_txn_id
+              WHERE ce.entry_type = 'BUY_SELL' AND at.owner_type = 'account' AND at.owner_id = a.id
+          )
+        ORDER BY a.name
+        "#,
+    )
+}
+
+fn check_orphaned_prices(conn: &Connection) -> rusqlite::Result<Vec<IntegrityIssue>> {
+    collect_issues(
+        conn,
+        "price",
+        r#"
+        SELECT security_id, COUNT(*) || ' Kurse für gelöschtes Wertpapier ' || security_id
+        FROM pp_price
+        WHERE security_id NOT IN (SELECT id FROM pp_security)
+        GROUP BY security_id
+        UNION ALL
+        SELECT security_id, 'Aktueller Kurs für gelöschtes Wertpapier ' || security_id
+        FROM pp_latest_price
+        WHERE security_id NOT IN (SELECT id FROM pp_security)
+        "#,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_integrity() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Verrechnung', 'EUR'), (2, 'a2', 'Tagesgeld', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'p1', 'Depot', 1);
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 't1', 'portfolio', 1, 'BUY', '2024-01-02', 100000, 'EUR', 1, 1000000000),
+                    (2, 't2', 'account', 1, 'BUY', '2024-01-02', 100000, 'EUR', 1, 1000000000),
+                    (3, 't3', 'portfolio', 1, 'SELL', '2024-02-01', 150000, 'EUR', 1, 1500000000),
+                    (4, 't4', 'portfolio', 1, 'BUY', '2024-03-01', 10000, 'EUR', 99, 100000000);
+             INSERT INTO pp_cross_entry (id, uuid, entry_type, portfolio_txn_id, account_txn_id)
+             VALUES (1, 'c1', 'BUY_SELL', 1, 2), (2, 'c2', 'BUY_SELL', 3, 42);
+             INSERT INTO pp_price (security_id, date, value) VALUES (99, '2024-03-01', 10000000000);",
+        )
+        .unwrap();
+
+        let report = check_integrity(&conn).unwrap();
+        let category = |name: &str| report.categories.iter().find(|c| c.category == name);
+
+        let orphaned = category("orphaned_cross_entries").unwrap();
+        assert_eq!(orphaned.count, 1);
+        assert_eq!(orphaned.issues[0].entity_id, 2);
+
+        // SELL (dangling account side) and BUY of the missing security
+        let missing = category("missing_cross_entries").unwrap();
+        let ids: Vec<i64> = missing.issues.iter().map(|i| i.entity_id).collect();
+        assert_eq!(ids, [3, 4]);
+
+        assert_eq!(category("missing_securities").unwrap().issues[0].entity_id, 4);
+
+        let negative = category("negative_positions").unwrap();
+        assert_eq!(negative.count, 1);
+        assert_eq!(negative.issues[0].entity_id, 3);
+
+        let unlinked = category("unlinked_accounts").unwrap();
+        assert_eq!(unlinked.count, 1);
+        assert_eq!(unlinked.issues[0].entity_id, 2);
+
+        assert_eq!(category("orphaned_prices").unwrap().issues[0].entity_id, 99);
+        assert_eq!(report.issue_count, 7);
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! Manage recurring investment plans for automated purchases.
 
+use crate::commands::audit::record_audit;
 use crate::db;
-use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::events::{
+    emit_data_changed, emit_investment_plans_executed, DataChangedPayload, ExecutedPlanSummary,
+    InvestmentPlansExecutedPayload,
+};
 use chrono::{Datelike, NaiveDate};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use tauri::{command, AppHandle};
 
 /// Plan interval
@@ -94,6 +100,44 @@ pub struct InvestmentPlanExecution {
     pub transaction_id: i64,
 }
 
+/// Savings plan to simulate (nothing is stored)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePlanRequest {
+    pub security_id: i64,
+    pub interval: String,
+    pub amount: i64,  // cents
+    pub day_of_month: i32,
+    /// First execution (YYYY-MM-DD), default today
+    pub start_date: Option<String>,
+    /// Yearly increase of the contribution in percent (None = flat contributions)
+    pub contribution_growth_rate: Option<f64>,
+}
+
+/// One simulated execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedExecution {
+    pub date: String,
+    pub amount: i64,  // cents
+    pub shares: i64,  // scaled
+    pub total_invested: i64,  // cents
+    pub total_shares: i64,  // scaled
+}
+
+/// Result of a plan simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanSimulation {
+    /// Assumed price for all executions: average of the last 12 months of prices (scaled)
+    pub assumed_price: i64,
+    pub executions: Vec<SimulatedExecution>,
+    pub total_invested: i64,  // cents
+    pub total_shares: i64,  // scaled
+    /// Value of the shares at the assumed price (cents)
+    pub projected_value: i64,
+}
+
 /// Ensure investment_plans table exists
 fn ensure_tables(conn: &rusqlite::Connection) -> Result<(), String> {
     conn.execute(
@@ -110,6 +154,7 @@ fn ensure_tables(conn: &rusqlite::Connection) -> Result<(), String> {
             start_date TEXT NOT NULL,
             end_date TEXT,
             is_active INTEGER NOT NULL DEFAULT 1,
+            auto_generate INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY (security_id) REFERENCES pp_security(id),
             FOREIGN KEY (account_id) REFERENCES pp_account(id),
@@ -134,6 +179,22 @@ fn ensure_tables(conn: &rusqlite::Connection) -> Result<(), String> {
         [],
     ).map_err(|e| e.to_string())?;
 
+    // The schema migration only adds this column if the table already existed at startup
+    let has_transactions_column = conn
+        .prepare("SELECT transactions FROM pp_investment_plan LIMIT 0")
+        .is_ok();
+    if !has_transactions_column {
+        conn.execute("ALTER TABLE pp_investment_plan ADD COLUMN transactions TEXT", [])
+            .map_err(|e| e.to_string())?;
+    }
+    let has_auto_generate_column = conn
+        .prepare("SELECT auto_generate FROM pp_investment_plan LIMIT 0")
+        .is_ok();
+    if !has_auto_generate_column {
+        conn.execute("ALTER TABLE pp_investment_plan ADD COLUMN auto_generate INTEGER NOT NULL DEFAULT 0", [])
+            .map_err(|e| e.to_string())?;
+    }
+
     Ok(())
 }
 
@@ -152,7 +213,12 @@ pub fn get_investment_plans() -> Result<Vec<InvestmentPlanData>, String> {
             p.id, p.name, p.security_id, s.name, p.account_id, a.name,
             p.portfolio_id, pf.name, p.interval, p.amount, a.currency,
             p.day_of_month, p.start_date, p.end_date, p.is_active,
-            (SELECT MAX(date) FROM pp_plan_execution WHERE plan_id = p.id) as last_exec,
+            (SELECT MAX(d) FROM (
+                SELECT MAX(date) AS d FROM pp_plan_execution WHERE plan_id = p.id
+                UNION ALL
+                SELECT MAX(substr(t.date, 1, 10)) FROM pp_txn t
+                WHERE t.uuid IN (SELECT value FROM json_each(COALESCE(p.transactions, '[]')))
+            )) as last_exec,
             (SELECT COALESCE(SUM(amount), 0) FROM pp_plan_execution WHERE plan_id = p.id) as total,
             (SELECT COUNT(*) FROM pp_plan_execution WHERE plan_id = p.id) as exec_count
          FROM pp_investment_plan p
@@ -172,7 +238,7 @@ pub fn get_investment_plans() -> Result<Vec<InvestmentPlanData>, String> {
 
         // Calculate next execution
         let next_execution = if is_active {
-            calculate_next_execution(&interval, day_of_month, last_execution.as_deref(), end_date.as_deref())
+            calculate_next_execution(&interval, day_of_month, &start_date, last_execution.as_deref(), end_date.as_deref())
         } else {
             None
         };
@@ -213,12 +279,21 @@ pub fn get_investment_plan(id: i64) -> Result<InvestmentPlanData, String> {
 
     ensure_tables(conn)?;
 
+    load_plan(conn, id)
+}
+
+fn load_plan(conn: &Connection, id: i64) -> Result<InvestmentPlanData, String> {
     conn.query_row(
         "SELECT
             p.id, p.name, p.security_id, s.name, p.account_id, a.name,
             p.portfolio_id, pf.name, p.interval, p.amount, a.currency,
             p.day_of_month, p.start_date, p.end_date, p.is_active,
-            (SELECT MAX(date) FROM pp_plan_execution WHERE plan_id = p.id) as last_exec,
+            (SELECT MAX(d) FROM (
+                SELECT MAX(date) AS d FROM pp_plan_execution WHERE plan_id = p.id
+                UNION ALL
+                SELECT MAX(substr(t.date, 1, 10)) FROM pp_txn t
+                WHERE t.uuid IN (SELECT value FROM json_each(COALESCE(p.transactions, '[]')))
+            )) as last_exec,
             (SELECT COALESCE(SUM(amount), 0) FROM pp_plan_execution WHERE plan_id = p.id) as total,
             (SELECT COUNT(*) FROM pp_plan_execution WHERE plan_id = p.id) as exec_count
          FROM pp_investment_plan p
@@ -236,7 +311,7 @@ pub fn get_investment_plan(id: i64) -> Result<InvestmentPlanData, String> {
             let day_of_month: i32 = row.get(11)?;
 
             let next_execution = if is_active {
-                calculate_next_execution(&interval, day_of_month, last_execution.as_deref(), end_date.as_deref())
+                calculate_next_execution(&interval, day_of_month, &start_date, last_execution.as_deref(), end_date.as_deref())
             } else {
                 None
             };
@@ -284,8 +359,8 @@ pub fn create_investment_plan(data: CreateInvestmentPlanRequest) -> Result<Inves
     ).map_err(|e| format!("Security not found: {}", e))?;
 
     conn.execute(
-        "INSERT INTO pp_investment_plan (import_id, name, security_id, account_id, portfolio_id, interval, amount, day_of_month, start_date, end_date, is_active)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1)",
+        "INSERT INTO pp_investment_plan (import_id, name, security_id, account_id, portfolio_id, interval, amount, day_of_month, start_date, end_date, is_active, auto_generate)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, 1)",
         rusqlite::params![
             import_id,
             data.name,
@@ -419,39 +494,75 @@ pub fn execute_investment_plan(
     date: String,
     price: Option<i64>,
 ) -> Result<InvestmentPlanExecution, String> {
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard
-        .as_ref()
-        .ok_or_else(|| "Database not initialized".to_string())?;
+    let execution = {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        ensure_tables(conn)?;
+
+        // Get plan details
+        let plan = load_plan(conn, plan_id)?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let execution = execute_plan(conn, &plan, &date, price)?;
+
+        // Rebuild FIFO lots for affected security
+        if let Err(e) = crate::fifo::build_fifo_lots(conn, plan.security_id) {
+            log::warn!("Failed to rebuild FIFO lots for security {}: {}", plan.security_id, e);
+        }
+        record_audit(
+            conn,
+            "execute_investment_plan",
+            "investment_plan",
+            Some(plan.id),
+            &format!("Sparplan {} am {} ausgeführt", plan.name, date),
+        );
+        tx.commit().map_err(|e| e.to_string())?;
+        (execution, plan.security_id)
+    };
 
-    // Get plan details
-    let plan = get_investment_plan(plan_id)?;
+    // Emit data changed event for frontend refresh
+    emit_data_changed(&app, DataChangedPayload::investment_plan_executed(execution.1));
+
+    Ok(execution.0)
+}
 
-    // Get price (from parameter or latest price)
+/// Create the buy transactions of one plan execution and record it
+///
+/// Without a price, the price at or before the date is used (latest price as fallback).
+fn execute_plan(
+    conn: &Connection,
+    plan: &InvestmentPlanData,
+    date: &str,
+    price: Option<i64>,
+) -> Result<InvestmentPlanExecution, String> {
+    // Get price (from parameter, price history or latest price)
     let exec_price = match price {
         Some(p) => p,
-        None => {
-            conn.query_row(
-                "SELECT value FROM pp_latest_price WHERE security_id = ?1",
-                [plan.security_id],
-                |row| row.get::<_, i64>(0)
-            ).map_err(|_| "No price available for security".to_string())?
-        }
+        None => conn
+            .query_row(
+                "SELECT value FROM pp_price WHERE security_id = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+                rusqlite::params![plan.security_id, date],
+                |row| row.get::<_, i64>(0),
+            )
+            .or_else(|_| {
+                conn.query_row(
+                    "SELECT value FROM pp_latest_price WHERE security_id = ?1",
+                    [plan.security_id],
+                    |row| row.get::<_, i64>(0),
+                )
+            })
+            .map_err(|_| "No price available for security".to_string())?,
     };
 
-    // Calculate shares: amount / price (both scaled)
-    // amount is in cents (×10²), price is ×10⁸
-    // shares should be ×10⁸
-    let shares = if exec_price > 0 {
-        (plan.amount as i128 * 100_000_000 / (exec_price as i128 / 100)) as i64
-    } else {
-        return Err("Price cannot be zero".to_string());
-    };
+    let shares = shares_for_amount(plan.amount, exec_price)
+        .ok_or_else(|| "Price cannot be zero".to_string())?;
 
     // Get import_id
-    let import_id: i64 = conn.query_row(
+    let import_id: Option<i64> = conn.query_row(
         "SELECT import_id FROM pp_investment_plan WHERE id = ?1",
-        [plan_id],
+        [plan.id],
         |row| row.get(0)
     ).map_err(|e| e.to_string())?;
 
@@ -497,32 +608,37 @@ pub fn execute_investment_plan(
 
     // Create cross entry
     conn.execute(
-        "INSERT INTO pp_cross_entry (entry_type, portfolio_txn_id, account_txn_id)
-         VALUES ('BUY_SELL', ?1, ?2)",
-        [portfolio_txn_id, account_txn_id],
+        "INSERT INTO pp_cross_entry (uuid, entry_type, portfolio_txn_id, account_txn_id)
+         VALUES (?1, 'BUY_SELL', ?2, ?3)",
+        rusqlite::params![uuid::Uuid::new_v4().to_string(), portfolio_txn_id, account_txn_id],
+    ).map_err(|e| e.to_string())?;
+
+    // Link the generated transaction to the plan (JSON array of UUIDs, as in PP)
+    let transactions: Option<String> = conn.query_row(
+        "SELECT transactions FROM pp_investment_plan WHERE id = ?1",
+        [plan.id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    let mut linked: Vec<String> = transactions
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    linked.push(txn_uuid);
+    conn.execute(
+        "UPDATE pp_investment_plan SET transactions = ?1 WHERE id = ?2",
+        rusqlite::params![serde_json::to_string(&linked).map_err(|e| e.to_string())?, plan.id],
     ).map_err(|e| e.to_string())?;
 
     // Record execution
     conn.execute(
         "INSERT INTO pp_plan_execution (plan_id, date, shares, price, amount, fees, transaction_id)
          VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)",
-        rusqlite::params![plan_id, date, shares, exec_price, plan.amount, portfolio_txn_id],
+        rusqlite::params![plan.id, date, shares, exec_price, plan.amount, portfolio_txn_id],
     ).map_err(|e| e.to_string())?;
 
-    let exec_id = conn.last_insert_rowid();
-
-    // Rebuild FIFO lots for affected security
-    if let Err(e) = crate::fifo::build_fifo_lots(conn, plan.security_id) {
-        log::warn!("Failed to rebuild FIFO lots for security {}: {}", plan.security_id, e);
-    }
-
-    // Emit data changed event for frontend refresh
-    emit_data_changed(&app, DataChangedPayload::investment_plan_executed(plan.security_id));
-
     Ok(InvestmentPlanExecution {
-        id: exec_id,
-        plan_id,
-        date,
+        id: conn.last_insert_rowid(),
+        plan_id: plan.id,
+        date: date.to_string(),
         shares,
         price: exec_price,
         amount: plan.amount,
@@ -531,6 +647,230 @@ pub fn execute_investment_plan(
     })
 }
 
+/// Shares bought for an amount at a price
+///
+/// amount is in cents (×10²), price is ×10⁸, shares are ×10⁸:
+/// amount / 10² / (price / 10⁸) × 10⁸ = amount × 10¹⁴ / price
+fn shares_for_amount(amount: i64, price: i64) -> Option<i64> {
+    if price <= 0 {
+        return None;
+    }
+    Some((amount as i128 * 100_000_000_000_000 / price as i128) as i64)
+}
+
+/// Simulate a savings plan over a number of years without creating transactions
+///
+/// Uses the same schedule and share calculation as the real execution. All executions
+/// are priced at the average price of the last 12 months (latest price as fallback).
+#[command]
+pub fn simulate_investment_plan(config: SimulatePlanRequest, years: u32) -> Result<PlanSimulation, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start = match &config.start_date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date format: {}", e))?,
+        None => chrono::Local::now().date_naive(),
+    };
+
+    let assumed_price: i64 = conn
+        .query_row(
+            "SELECT CAST(AVG(value) AS INTEGER) FROM pp_price WHERE security_id = ?1 AND date > date(?2, '-1 year') AND date <= ?2",
+            rusqlite::params![config.security_id, start.to_string()],
+            |row| row.get::<_, Option<i64>>(0),
+        )
+        .ok()
+        .flatten()
+        .or_else(|| {
+            conn.query_row(
+                "SELECT value FROM pp_latest_price WHERE security_id = ?1",
+                [config.security_id],
+                |row| row.get(0),
+            )
+            .ok()
+        })
+        .ok_or_else(|| "No price available for security".to_string())?;
+
+    simulate_plan(&config, start, years, assumed_price)
+}
+
+fn simulate_plan(
+    config: &SimulatePlanRequest,
+    start: NaiveDate,
+    years: u32,
+    assumed_price: i64,
+) -> Result<PlanSimulation, String> {
+    if config.amount <= 0 {
+        return Err("Amount must be positive".to_string());
+    }
+    let end = start
+        .checked_add_months(chrono::Months::new(years * 12))
+        .ok_or_else(|| "Simulation period too long".to_string())?;
+    let growth = config.contribution_growth_rate.unwrap_or(0.0) / 100.0;
+
+    let mut executions = Vec::new();
+    let mut total_invested = 0i64;
+    let mut total_shares = 0i64;
+    let mut date = Some(start);
+
+    while let Some(current) = date.filter(|d| *d < end) {
+        // Contributions increase once per full plan year
+        let plan_year = current.years_since(start).unwrap_or(0) as i32;
+        let amount = (config.amount as f64 * (1.0 + growth).powi(plan_year)).round() as i64;
+        let shares = shares_for_amount(amount, assumed_price)
+            .ok_or_else(|| "Price cannot be zero".to_string())?;
+
+        total_invested += amount;
+        total_shares += shares;
+        executions.push(SimulatedExecution {
+            date: current.to_string(),
+            amount,
+            shares,
+            total_invested,
+            total_shares,
+        });
+
+        date = advance_execution_date(&config.interval, config.day_of_month, current);
+        if date.is_none() {
+            return Err(format!("Unknown interval: {}", config.interval));
+        }
+    }
+
+    let projected_value =
+        (total_shares as i128 * assumed_price as i128 / 100_000_000_000_000) as i64;
+
+    Ok(PlanSimulation {
+        assumed_price,
+        executions,
+        total_invested,
+        total_shares,
+        projected_value,
+    })
+}
+
+/// Execute all active plans whose next execution date has passed
+///
+/// Only plans with `auto_generate` are run (plans imported from PP keep
+/// their own setting). Missed periods are caught up, one execution per due date. Emits an
+/// `investment-plans-executed` event if anything was executed.
+#[command]
+pub fn run_due_investment_plans(app: AppHandle) -> Result<Vec<InvestmentPlanExecution>, String> {
+    let today = chrono::Local::now().date_naive();
+
+    let (executions, summaries) = {
+        let mut conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_mut()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+
+        ensure_tables(conn)?;
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let result = run_due_plans(&tx, today)?;
+        for summary in &result.1 {
+            record_audit(
+                &tx,
+                "run_due_investment_plans",
+                "investment_plan",
+                Some(summary.plan_id),
+                &format!("Sparplan {} automatisch ausgeführt: {}", summary.name, summary.dates.join(", ")),
+            );
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        result
+    };
+
+    if !summaries.is_empty() {
+        let security_ids: BTreeSet<i64> = summaries.iter().map(|s| s.security_id).collect();
+        for security_id in security_ids {
+            emit_data_changed(&app, DataChangedPayload::investment_plan_executed(security_id));
+        }
+        emit_investment_plans_executed(
+            &app,
+            InvestmentPlansExecutedPayload {
+                execution_count: executions.len(),
+                plans: summaries,
+            },
+        );
+    }
+
+    Ok(executions)
+}
+
+/// Execute all due plans up to `today` (see `run_due_investment_plans`)
+fn run_due_plans(
+    conn: &Connection,
+    today: NaiveDate,
+) -> Result<(Vec<InvestmentPlanExecution>, Vec<ExecutedPlanSummary>), String> {
+    let plan_ids: Vec<i64> = conn
+        .prepare("SELECT id FROM pp_investment_plan WHERE is_active = 1 AND auto_generate = 1 ORDER BY id")
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+        .map_err(|e| e.to_string())?;
+
+    let mut executions = Vec::new();
+    let mut summaries = Vec::new();
+
+    for plan_id in plan_ids {
+        let plan = load_plan(conn, plan_id)?;
+        let mut plan_executions = Vec::new();
+
+        for date in due_execution_dates(&plan, today) {
+            match execute_plan(conn, &plan, &date.to_string(), None) {
+                Ok(execution) => plan_executions.push(execution),
+                Err(e) => {
+                    // Stop this plan at the first failure, remaining periods are caught up later
+                    log::warn!("Investment plan {} not executed on {}: {}", plan.name, date, e);
+                    break;
+                }
+            }
+        }
+
+        if plan_executions.is_empty() {
+            continue;
+        }
+
+        if let Err(e) = crate::fifo::build_fifo_lots(conn, plan.security_id) {
+            log::warn!("Failed to rebuild FIFO lots for security {}: {}", plan.security_id, e);
+        }
+
+        summaries.push(ExecutedPlanSummary {
+            plan_id: plan.id,
+            name: plan.name.clone(),
+            security_id: plan.security_id,
+            dates: plan_executions.iter().map(|e| e.date.clone()).collect(),
+            amount: plan_executions.iter().map(|e| e.amount).sum(),
+        });
+        executions.extend(plan_executions);
+    }
+
+    Ok((executions, summaries))
+}
+
+/// All execution dates of a plan from its next execution up to `today`
+fn due_execution_dates(plan: &InvestmentPlanData, today: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut next = plan
+        .next_execution
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+
+    while let Some(date) = next.filter(|d| *d <= today) {
+        dates.push(date);
+        next = calculate_next_execution(
+            &plan.interval,
+            plan.day_of_month,
+            &plan.start_date,
+            Some(&date.to_string()),
+            plan.end_date.as_deref(),
+        )
+        .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
+    }
+
+    dates
+}
+
 /// Get plans due for execution on a date
 #[command]
 pub fn get_plans_due_for_execution(date: String) -> Result<Vec<InvestmentPlanData>, String> {
@@ -557,19 +897,39 @@ pub fn get_plans_due_for_execution(date: String) -> Result<Vec<InvestmentPlanDat
 }
 
 /// Calculate next execution date
+///
+/// `last_execution` is the newest recorded execution or linked transaction
+/// (imported plans have no execution rows). Without one the plan starts on
+/// its start date.
 fn calculate_next_execution(
     interval: &str,
     day_of_month: i32,
+    start_date: &str,
     last_execution: Option<&str>,
     end_date: Option<&str>,
 ) -> Option<String> {
-    let today = chrono::Local::now().date_naive();
-
-    let base_date = match last_execution {
-        Some(last) => NaiveDate::parse_from_str(last, "%Y-%m-%d").ok()?,
-        None => today,
+    let next = match last_execution {
+        Some(last) => {
+            let base_date = NaiveDate::parse_from_str(last, "%Y-%m-%d").ok()?;
+            advance_execution_date(interval, day_of_month, base_date)?
+        }
+        None => NaiveDate::parse_from_str(start_date, "%Y-%m-%d").ok()?,
     };
 
+    // Check end date
+    if let Some(end) = end_date {
+        if let Ok(end_d) = NaiveDate::parse_from_str(end, "%Y-%m-%d") {
+            if next > end_d {
+                return None;
+            }
+        }
+    }
+
+    Some(next.format("%Y-%m-%d").to_string())
+}
+
+/// Execution date following `base_date`
+fn advance_execution_date(interval: &str, day_of_month: i32, base_date: NaiveDate) -> Option<NaiveDate> {
     let next = match interval {
         "WEEKLY" => base_date + chrono::Duration::days(7),
         "BIWEEKLY" => base_date + chrono::Duration::days(14),
@@ -602,16 +962,7 @@ fn calculate_next_execution(
         _ => return None,
     };
 
-    // Check end date
-    if let Some(end) = end_date {
-        if let Ok(end_d) = NaiveDate::parse_from_str(end, "%Y-%m-%d") {
-            if next > end_d {
-                return None;
-            }
-        }
-    }
-
-    Some(next.format("%Y-%m-%d").to_string())
+    Some(next)
 }
 
 fn days_in_month(year: i32, month: u32) -> u32 {
@@ -628,3 +979,109 @@ fn days_in_month(year: i32, month: u32) -> u32 {
         _ => 30,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_plan() {
+        let config = SimulatePlanRequest {
+            security_id: 1,
+            interval: "QUARTERLY".to_string(),
+            amount: 10000,
+            day_of_month: 1,
+            start_date: None,
+            contribution_growth_rate: Some(10.0),
+        };
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        // 2 years quarterly at 50 EUR: 4 × 100 EUR, then 4 × 110 EUR
+        let simulation = simulate_plan(&config, start, 2, 5_000_000_000).unwrap();
+        assert_eq!(simulation.executions.len(), 8);
+        assert_eq!(simulation.executions[3].amount, 10000);
+        assert_eq!(simulation.executions[4].date, "2025-01-01");
+        assert_eq!(simulation.executions[4].amount, 11000);
+        assert_eq!(simulation.total_invested, 84000);
+        assert_eq!(simulation.total_shares, 1_680_000_000);
+        assert_eq!(simulation.projected_value, 84000);
+
+        let flat = SimulatePlanRequest {
+            contribution_growth_rate: None,
+            ..config
+        };
+        assert_eq!(simulate_plan(&flat, start, 2, 5_000_000_000).unwrap().total_invested, 80000);
+    }
+
+    #[test]
+    fn test_run_due_plans_catches_up() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        ensure_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'ETF', 'EUR');
+             INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Konto', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_price (security_id, date, value) VALUES (1, '2024-01-02', 5000000000), (1, '2024-02-01', 10000000000);
+             INSERT INTO pp_investment_plan (id, name, security_id, account_id, portfolio_id, interval, amount, day_of_month, start_date, auto_generate)
+             VALUES (1, 'ETF-Sparplan', 1, 1, 1, 'MONTHLY', 10000, 15, '2024-01-15', 1);",
+        )
+        .unwrap();
+
+        // Three missed months
+        let today = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let (executions, summaries) = run_due_plans(&conn, today).unwrap();
+        let dates: Vec<&str> = executions.iter().map(|e| e.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-01-15", "2024-02-15", "2024-03-15"]);
+        // Historical price of the execution date: 100 EUR at 50 EUR = 2 shares
+        assert_eq!(executions[0].shares, 200_000_000);
+        assert_eq!(executions[1].shares, 100_000_000);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].amount, 30000);
+
+        let linked: String = conn
+            .query_row("SELECT transactions FROM pp_investment_plan WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Vec<String>>(&linked).unwrap().len(), 3);
+
+        // Nothing due anymore
+        let (executions, _) = run_due_plans(&conn, today).unwrap();
+        assert!(executions.is_empty());
+        assert_eq!(
+            load_plan(&conn, 1).unwrap().next_execution.as_deref(),
+            Some("2024-04-15")
+        );
+    }
+
+    #[test]
+    fn test_run_due_plans_continues_imported_plan() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        ensure_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'ETF', 'EUR');
+             INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Konto', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_price (security_id, date, value) VALUES (1, '2024-01-02', 5000000000);
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 'pp-1', 'portfolio', 1, 'BUY', '2024-01-15T00:00', 10000, 'EUR', 1, 200000000),
+                    (2, 'pp-2', 'portfolio', 1, 'BUY', '2024-02-15T00:00', 10000, 'EUR', 1, 200000000);
+             INSERT INTO pp_investment_plan (id, name, security_id, account_id, portfolio_id, interval, amount, day_of_month, start_date, auto_generate, transactions)
+             VALUES (1, 'Importiert', 1, 1, 1, 'MONTHLY', 10000, 15, '2024-01-15', 1, '["pp-1","pp-2"]'),
+                    (2, 'Manuell', 1, 1, 1, 'MONTHLY', 10000, 15, '2024-01-15', 0, NULL);"#,
+        )
+        .unwrap();
+
+        // Imported executions are not replayed, the manual-only plan is skipped
+        let today = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let (executions, summaries) = run_due_plans(&conn, today).unwrap();
+        let dates: Vec<&str> = executions.iter().map(|e| e.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-03-15"]);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].plan_id, 1);
+        let buys: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_txn WHERE txn_type = 'BUY'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(buys, 4);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod ai;
 pub mod ai_helpers;
+pub mod audit;
 pub mod alerts;
+pub mod attribution;
 pub mod attributes;
+pub mod backup;
 pub mod chat;
 pub mod consortium;
 pub mod dashboard;
@@ -16,8 +19,11 @@ pub mod data;
 pub mod divvydiary;
 pub mod dividends;
 pub mod drawings;
+pub mod duplicates;
 pub mod file;
+pub mod ghostfolio;
 pub mod import;
+pub mod integrity;
 pub mod investment_plans;
 pub mod patterns;
 pub mod pdf_export;
@@ -29,5 +35,6 @@ pub mod quotes;
 pub mod rebalancing;
 pub mod reports;
 pub mod taxonomy;
+pub mod trash;
 pub mod validation;
 pub mod watchlist;
@@ -2,11 +2,11 @@
 //!
 //! This module provides commands to:
 //! - Save detected patterns to the database
-//! - Evaluate pattern outcomes after 5/10 days
+//! - Evaluate pattern outcomes after 5/10 trading days (also after every price sync)
 //! - Get pattern statistics and success rates
 
 use anyhow::Result;
-use rusqlite::params;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
 use crate::db::get_connection;
@@ -111,27 +111,40 @@ pub async fn save_pattern_detection(pattern: PatternDetection) -> Result<i64, St
     Ok(id)
 }
 
-/// Evaluate pending patterns that are old enough (5+ days).
-/// Compares the price at detection with current/later prices to determine success.
+/// Evaluate pending patterns that are old enough (5+ trading days).
+/// Compares the price at detection with later prices to determine success.
 #[tauri::command]
 pub async fn evaluate_pattern_outcomes() -> Result<PatternEvaluationResult, String> {
     let guard = get_connection().map_err(|e| e.to_string())?;
     let conn = guard.as_ref().ok_or("Database not initialized")?;
 
-    // Get pending patterns that are at least 5 days old
-    let mut stmt = conn
-        .prepare(
-            r#"
-            SELECT ph.id, ph.security_id, ph.detected_at, ph.price_at_detection,
-                   ph.predicted_direction
-            FROM pp_pattern_history ph
-            WHERE ph.actual_outcome = 'pending'
-              AND date(ph.detected_at) <= date('now', '-5 days')
-            "#,
-        )
-        .map_err(|e| e.to_string())?;
+    evaluate_pending_patterns(conn).map_err(|e| e.to_string())
+}
+
+/// Trading days after detection used to decide the outcome
+const OUTCOME_DAYS: i64 = 5;
 
-    let patterns: Vec<(i64, i64, String, f64, String)> = stmt
+/// Trading days after detection for the longer-term price change
+const FOLLOW_UP_DAYS: i64 = 10;
+
+/// Fill the 5/10 trading day prices of tracked patterns from `pp_price` and
+/// decide the outcome once the 5-day price exists.
+///
+/// Called after every price sync, so outcomes are resolved without user action.
+/// Trading days are the price rows stored after the detection date. Patterns that
+/// already have an outcome are revisited until their 10-day price is known.
+pub fn evaluate_pending_patterns(conn: &Connection) -> Result<PatternEvaluationResult> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT ph.id, ph.security_id, ph.detected_at, ph.price_at_detection,
+               ph.predicted_direction, ph.actual_outcome
+        FROM pp_pattern_history ph
+        WHERE (ph.actual_outcome = 'pending' AND ph.predicted_direction != 'neutral')
+           OR ph.price_after_10d IS NULL
+        "#,
+    )?;
+
+    let patterns: Vec<(i64, i64, String, f64, String, Option<String>)> = stmt
         .query_map([], |row| {
             Ok((
                 row.get(0)?,
@@ -139,89 +152,81 @@ pub async fn evaluate_pattern_outcomes() -> Result<PatternEvaluationResult, Stri
                 row.get(2)?,
                 row.get(3)?,
                 row.get(4)?,
+                row.get(5)?,
             ))
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<rusqlite::Result<_>>()?;
 
     let mut successes = 0i64;
     let mut failures = 0i64;
     let now = chrono::Utc::now().format("%Y-%m-%d").to_string();
 
-    for (id, security_id, detected_at, price_at_detection, predicted_direction) in &patterns {
-        // Get price 5 days after detection
-        let price_5d: Option<f64> = conn
-            .query_row(
-                r#"
-                SELECT value / 100000000.0
-                FROM pp_price
-                WHERE security_id = ? AND date >= date(?, '+5 days')
-                ORDER BY date ASC
-                LIMIT 1
-                "#,
-                params![security_id, detected_at],
-                |row| row.get(0),
-            )
-            .ok();
+    for (id, security_id, detected_at, price_at_detection, predicted_direction, outcome) in
+        &patterns
+    {
+        // Nth trading day after detection
+        let price_after = |days: i64| -> Result<Option<f64>> {
+            Ok(conn
+                .query_row(
+                    r#"
+                    SELECT value / 100000000.0
+                    FROM pp_price
+                    WHERE security_id = ? AND date > date(?)
+                    ORDER BY date ASC
+                    LIMIT 1 OFFSET ?
+                    "#,
+                    params![security_id, detected_at, days - 1],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        };
 
-        // Get price 10 days after detection
-        let price_10d: Option<f64> = conn
-            .query_row(
-                r#"
-                SELECT value / 100000000.0
-                FROM pp_price
-                WHERE security_id = ? AND date >= date(?, '+10 days')
-                ORDER BY date ASC
-                LIMIT 1
-                "#,
-                params![security_id, detected_at],
-                |row| row.get(0),
-            )
-            .ok();
+        let price_5d = price_after(OUTCOME_DAYS)?;
+        let Some(price_5d) = price_5d else {
+            continue; // Not enough data yet
+        };
+        let price_10d = price_after(FOLLOW_UP_DAYS)?;
 
         // Calculate price changes
-        let change_5d = price_5d.map(|p| ((p - price_at_detection) / price_at_detection) * 100.0);
-        let change_10d = price_10d.map(|p| ((p - price_at_detection) / price_at_detection) * 100.0);
+        let change = |p: f64| ((p - price_at_detection) / price_at_detection) * 100.0;
+        let change_5d = change(price_5d);
+        let change_10d = price_10d.map(change);
 
         // Determine outcome based on predicted direction and actual price movement
-        let outcome = if let Some(change) = change_5d {
-            let is_success = match predicted_direction.as_str() {
-                "bullish" => change > 1.0, // At least 1% gain for bullish
-                "bearish" => change < -1.0, // At least 1% drop for bearish
-                _ => false, // Neutral patterns are not evaluated
-            };
-
-            if predicted_direction == "neutral" {
-                None // Don't evaluate neutral patterns
-            } else if is_success {
-                successes += 1;
-                Some("success")
-            } else {
-                failures += 1;
-                Some("failure")
-            }
-        } else {
-            None // Not enough data yet
+        let outcome = match outcome.as_deref() {
+            Some("pending") | None => match predicted_direction.as_str() {
+                "bullish" | "bearish" => {
+                    let is_success = if predicted_direction == "bullish" {
+                        change_5d > 1.0 // At least 1% gain for bullish
+                    } else {
+                        change_5d < -1.0 // At least 1% drop for bearish
+                    };
+                    if is_success {
+                        successes += 1;
+                        "success"
+                    } else {
+                        failures += 1;
+                        "failure"
+                    }
+                }
+                _ => "pending", // Neutral patterns are not evaluated
+            },
+            Some(decided) => decided,
         };
 
-        // Update the pattern record
-        if let Some(outcome) = outcome {
-            conn.execute(
-                r#"
-                UPDATE pp_pattern_history
-                SET actual_outcome = ?,
-                    price_after_5d = ?,
-                    price_after_10d = ?,
-                    price_change_5d_percent = ?,
-                    price_change_10d_percent = ?,
-                    evaluated_at = ?
-                WHERE id = ?
-                "#,
-                params![outcome, price_5d, price_10d, change_5d, change_10d, now, id],
-            )
-            .map_err(|e| e.to_string())?;
-        }
+        conn.execute(
+            r#"
+            UPDATE pp_pattern_history
+            SET actual_outcome = ?,
+                price_after_5d = ?,
+                price_after_10d = ?,
+                price_change_5d_percent = ?,
+                price_change_10d_percent = ?,
+                evaluated_at = ?
+            WHERE id = ?
+            "#,
+            params![outcome, price_5d, price_10d, change_5d, change_10d, now, id],
+        )?;
     }
 
     Ok(PatternEvaluationResult {
@@ -331,3 +336,55 @@ pub async fn get_pattern_history(security_id: i64) -> Result<Vec<PatternHistory>
 
     Ok(history)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_pending_patterns() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_pattern_history (id, security_id, pattern_type, detected_at, price_at_detection, predicted_direction, actual_outcome)
+             VALUES (1, 1, 'hammer', '2024-01-01', 100.0, 'bullish', 'pending'),
+                    (2, 1, 'shooting_star', '2024-01-01', 100.0, 'bearish', 'pending');",
+        )
+        .unwrap();
+        let add_prices = |from: i64, to: i64| {
+            for day in from..=to {
+                conn.execute(
+                    "INSERT INTO pp_price (security_id, date, value) VALUES (1, ?1, ?2)",
+                    params![format!("2024-01-{:02}", day + 1), (100 + day) * 100_000_000],
+                )
+                .unwrap();
+            }
+        };
+
+        // Only 4 trading days after detection: nothing decided
+        add_prices(1, 4);
+        let result = evaluate_pending_patterns(&conn).unwrap();
+        assert_eq!(result.patterns_evaluated, 0);
+
+        // 5th trading day at 105: bullish succeeds, bearish fails
+        add_prices(5, 5);
+        let result = evaluate_pending_patterns(&conn).unwrap();
+        assert_eq!((result.successes, result.failures), (1, 1));
+
+        // 10-day price filled later without counting the outcome again
+        add_prices(6, 10);
+        let result = evaluate_pending_patterns(&conn).unwrap();
+        assert_eq!(result.patterns_evaluated, 0);
+        let (outcome, price_10d, change_10d): (String, f64, f64) = conn
+            .query_row(
+                "SELECT actual_outcome, price_after_10d, price_change_10d_percent FROM pp_pattern_history WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(outcome, "success");
+        assert_eq!(price_10d, 110.0);
+        assert!((change_10d - 10.0).abs() < 1e-9);
+    }
+}
@@ -418,7 +418,7 @@ pub fn export_performance_pdf(
         .map_err(|e| e.to_string())?;
     let irr_result = performance::calculate_irr(&cash_flows, current_value, end)
         .map_err(|e| e.to_string())?;
-    let risk_metrics = performance::calculate_risk_metrics(conn, portfolio_id, start, end, None, None).ok();
+    let risk_metrics = performance::calculate_risk_metrics(conn, portfolio_id, start, end, None, None, None).ok();
 
     // Section: Performance Kennzahlen
     y = draw_section_header(&current_layer, &font_bold, y, "Performance Kennzahlen");
@@ -733,3 +733,80 @@ pub fn export_tax_report_pdf(
         pages: 1,
     })
 }
+
+/// Export the German tax report as Anlage KAP PDF
+#[command]
+pub fn export_anlage_kap_pdf(
+    year: i32,
+    path: String,
+) -> Result<PdfExportResult, String> {
+    let validated_path = crate::security::validate_file_path_with_extension(&path, Some(&["pdf"]))
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let export = crate::tax::anlage_kap::load_anlage_kap_export(conn, year)?;
+
+    let (doc, page1, layer1) = create_pdf("Anlage KAP");
+    let font = get_font(&doc);
+    let font_bold = get_font_bold(&doc);
+    let current_layer = doc.get_page(page1).get_layer(layer1);
+
+    // Header
+    let mut y = draw_header(&current_layer, &font_bold, &font,
+        &format!("Anlage KAP {}", year), Some("Einkünfte aus Kapitalvermögen"));
+
+    // Section: Form lines
+    y = draw_section_header(&current_layer, &font_bold, y, "Zeilen der Anlage KAP");
+
+    let columns = [
+        ("Zeile", MARGIN_LEFT + 2.0),
+        ("Bezeichnung", MARGIN_LEFT + 20.0),
+        ("Betrag", 150.0),
+    ];
+    y = draw_table_header(&current_layer, &font_bold, y, &columns);
+
+    for (i, line) in export.lines.iter().enumerate() {
+        if i % 2 == 1 {
+            draw_rect(&current_layer, MARGIN_LEFT, y - 4.0, CONTENT_WIDTH, 8.0, COLOR_ROW_ALT);
+        }
+        add_text(&current_layer, &font_bold, Mm(MARGIN_LEFT + 2.0), Mm(y - 2.0), 9.0, &line.zeile.to_string());
+        add_text(&current_layer, &font, Mm(MARGIN_LEFT + 20.0), Mm(y - 2.0), 9.0, &truncate_text(&line.label, 60));
+        add_text(&current_layer, &font, Mm(150.0), Mm(y - 2.0), 9.0, &format_currency(line.amount, &export.currency));
+        y -= 8.0;
+    }
+
+    // Summary box
+    y -= 15.0;
+    draw_rect(&current_layer, MARGIN_LEFT, y - 15.0, CONTENT_WIDTH, 20.0, COLOR_HEADER_BG);
+    add_text(&current_layer, &font, Mm(MARGIN_LEFT + 5.0), Mm(y - 2.0), 9.0, "Bereits gezahlte Steuern:");
+    add_text(&current_layer, &font, Mm(150.0), Mm(y - 2.0), 9.0,
+        &format_currency(export.tax_already_paid, &export.currency));
+    add_text_colored(&current_layer, &font_bold, Mm(MARGIN_LEFT + 5.0), Mm(y - 10.0), 10.0,
+        "Voraussichtliche Nachzahlung:", COLOR_PRIMARY);
+    add_text_colored(&current_layer, &font_bold, Mm(150.0), Mm(y - 10.0), 10.0,
+        &format_currency(export.remaining_tax_liability, &export.currency), COLOR_NEGATIVE);
+
+    // Disclaimer
+    y -= 35.0;
+    draw_rect(&current_layer, MARGIN_LEFT, y - 15.0, CONTENT_WIDTH, 20.0, (0.99, 0.95, 0.90)); // Light orange
+    add_text_colored(&current_layer, &font_bold, Mm(MARGIN_LEFT + 5.0), Mm(y - 2.0), 8.0, "Hinweis:", (0.8, 0.5, 0.0));
+    add_text_colored(&current_layer, &font, Mm(MARGIN_LEFT + 5.0), Mm(y - 10.0), 7.5,
+        "Ausfüllhilfe ohne Gewähr - bitte mit den Jahressteuerbescheinigungen abgleichen.", COLOR_TEXT);
+
+    // Footer
+    draw_footer(&current_layer, &font, 1, 1);
+
+    // Save
+    let file = File::create(&validated_path).map_err(|e| format!("Failed to create file: {}", e))?;
+    doc.save(&mut BufWriter::new(file)).map_err(|e| format!("Failed to save PDF: {}", e))?;
+
+    Ok(PdfExportResult {
+        success: true,
+        path: validated_path.to_string_lossy().to_string(),
+        pages: 1,
+    })
+}
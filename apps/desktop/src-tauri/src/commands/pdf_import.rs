@@ -2,14 +2,16 @@
 //!
 //! Tauri commands for importing bank statements from PDF files.
 
+use crate::commands::audit::record_audit;
 use crate::db;
 use crate::events::{emit_data_changed, DataChangedPayload};
+use crate::pdf_import::provenance::TransactionProvenance;
 use crate::pdf_import::{
     extract_pdf_text, parse_pdf, parse_pdf_content, ParsedTransaction, ParsedTransactionType,
     ParseResult,
 };
 use serde::{Deserialize, Serialize};
-use tauri::{command, AppHandle};
+use tauri::{command, AppHandle, Emitter};
 
 /// Format date with optional time for database storage
 fn format_datetime(txn: &ParsedTransaction) -> String {
@@ -54,6 +56,10 @@ pub struct PdfImportPreview {
     pub new_securities: Vec<SecurityMatch>,
     pub matched_securities: Vec<SecurityMatch>,
     pub potential_duplicates: Vec<PotentialDuplicate>,
+    /// True if the text was read via OCR because the PDF has no usable text layer
+    pub ocr_used: bool,
+    /// Per-transaction field confidence and source lines for the review
+    pub provenance: Vec<TransactionProvenance>,
 }
 
 /// Potential duplicate transaction
@@ -146,9 +152,24 @@ pub fn get_supported_banks() -> Vec<SupportedBank> {
     ]
 }
 
+/// Minimum number of non-whitespace characters before a text layer counts as usable
+const MIN_TEXT_LAYER_CHARS: usize = 100;
+
 /// Preview PDF import without making changes
+///
+/// If the text layer is (nearly) empty, the PDF is most likely scanned and OCR is used
+/// automatically, provided OCR credentials and the user's upload consent are passed.
+/// `ocr_used` in the result tells whether that happened.
 #[command]
-pub async fn preview_pdf_import(pdf_path: String) -> Result<PdfImportPreview, String> {
+pub async fn preview_pdf_import(
+    pdf_path: String,
+    ocr_provider: Option<String>,
+    ocr_model: Option<String>,
+    ocr_api_key: Option<String>,
+    ocr_consent_given: Option<bool>,
+) -> Result<PdfImportPreview, String> {
+    use crate::pdf_import::ocr::ocr_pdf;
+
     // SECURITY: Validate path (defense-in-depth)
     let validated_path = crate::security::validate_file_path_with_extension(&pdf_path, Some(&["pdf"]))
         .map_err(|e| format!("Invalid file path: {}", e))?;
@@ -156,22 +177,81 @@ pub async fn preview_pdf_import(pdf_path: String) -> Result<PdfImportPreview, St
 
     log::info!("PDF Import: Starting preview for {}", validated_path_str);
 
-    // Run blocking PDF parsing in a separate thread to not block the main thread
-    let result = tokio::task::spawn_blocking(move || {
-        preview_pdf_import_sync(&validated_path_str)
-    })
-    .await
-    .map_err(|e| format!("PDF preview task failed: {}", e))?;
+    // Run blocking PDF extraction in a separate thread to not block the main thread
+    let path = validated_path_str.clone();
+    let extracted_text = tokio::task::spawn_blocking(move || extract_pdf_text(&path))
+        .await
+        .map_err(|e| format!("PDF preview task failed: {}", e))??;
+
+    let ocr_options = auto_ocr_options(
+        &extracted_text,
+        ocr_provider,
+        ocr_model,
+        ocr_api_key,
+        ocr_consent_given,
+    )?;
+
+    let (content, ocr_used) = match ocr_options {
+        Some(options) => {
+            log::info!(
+                "PDF Import: Text layer too small, falling back to OCR via {}",
+                options.provider
+            );
+            let ocr_result = ocr_pdf(&validated_path_str, options, None).await?;
+            (ocr_result.full_text, true)
+        }
+        None => (extracted_text, false),
+    };
 
-    result
+    tokio::task::spawn_blocking(move || build_preview(&content, ocr_used))
+        .await
+        .map_err(|e| format!("PDF preview task failed: {}", e))?
 }
 
-/// Synchronous PDF preview implementation (runs in blocking thread)
-fn preview_pdf_import_sync(pdf_path: &str) -> Result<PdfImportPreview, String> {
-    log::info!("PDF Import: Starting sync preview for {}", pdf_path);
+/// Decide whether the extracted text needs OCR and whether OCR can be used
+///
+/// Returns `Ok(None)` if the text layer is usable. For scanned PDFs OCR options are
+/// returned when credentials, consent and the required tools are present, otherwise
+/// an error explains how to enable OCR.
+fn auto_ocr_options(
+    extracted_text: &str,
+    provider: Option<String>,
+    model: Option<String>,
+    api_key: Option<String>,
+    consent_given: Option<bool>,
+) -> Result<Option<crate::pdf_import::ocr::OcrOptions>, String> {
+    use crate::pdf_import::ocr::{is_pdftoppm_available, should_use_ocr_fallback, supports_direct_pdf, OcrOptions};
+
+    if !should_use_ocr_fallback(extracted_text, MIN_TEXT_LAYER_CHARS) {
+        return Ok(None);
+    }
+
+    let api_key = api_key.filter(|key| !key.trim().is_empty());
+    match (provider, model, api_key) {
+        // SECURITY: OCR uploads the PDF to an external AI service, only with explicit consent
+        (Some(provider), Some(model), Some(api_key))
+            if consent_given == Some(true)
+                && (supports_direct_pdf(&provider) || is_pdftoppm_available()) =>
+        {
+            Ok(Some(OcrOptions {
+                provider,
+                model,
+                api_key,
+            }))
+        }
+        _ => Err(
+            "Das PDF enthält kaum Text und ist vermutlich gescannt. \
+             Aktiviere die OCR-Texterkennung (KI-Provider mit API-Key und Zustimmung zum Upload), \
+             um es zu importieren."
+                .to_string(),
+        ),
+    }
+}
 
-    // Parse the PDF
-    let result = match parse_pdf(&pdf_path) {
+/// Build the import preview from extracted text (runs in blocking thread)
+fn build_preview(content: &str, ocr_used: bool) -> Result<PdfImportPreview, String> {
+    // Parse the content
+    let result = match parse_pdf_content(content) {
         Ok(r) => {
             log::info!("PDF Import: Successfully parsed PDF, found {} transactions", r.transactions.len());
             r
@@ -225,7 +305,7 @@ fn preview_pdf_import_sync(pdf_path: &str) -> Result<PdfImportPreview, String> {
     }
 
     // Convert ParseWarning to strings for backward compatibility
-    let warnings: Vec<String> = result.warnings.iter().map(|w| {
+    let mut warnings: Vec<String> = result.warnings.iter().map(|w| {
         format!("[{}] {}: {} (Wert: '{}')",
             match w.severity {
                 crate::pdf_import::WarningSeverity::Info => "Info",
@@ -237,6 +317,12 @@ fn preview_pdf_import_sync(pdf_path: &str) -> Result<PdfImportPreview, String> {
             w.raw_value
         )
     }).collect();
+    if ocr_used {
+        warnings.insert(
+            0,
+            "[Info] OCR: Kein Textlayer gefunden, der Text wurde per Texterkennung gelesen. Bitte Werte prüfen.".to_string(),
+        );
+    }
 
     // Check for potential duplicates
     let mut potential_duplicates = Vec::new();
@@ -313,6 +399,8 @@ fn preview_pdf_import_sync(pdf_path: &str) -> Result<PdfImportPreview, String> {
         new_securities,
         matched_securities,
         potential_duplicates,
+        ocr_used,
+        provenance: result.provenance,
     })
 }
 
@@ -373,7 +461,27 @@ fn import_pdf_transactions_sync(
     fee_overrides: std::collections::HashMap<usize, f64>,
 ) -> Result<PdfImportResult, String> {
     let result = parse_pdf(pdf_path)?;
+    import_parse_result(
+        result,
+        portfolio_id,
+        account_id,
+        create_missing_securities,
+        skip_duplicates,
+        type_overrides,
+        fee_overrides,
+    )
+}
 
+/// Import already parsed transactions
+fn import_parse_result(
+    result: ParseResult,
+    portfolio_id: i64,
+    account_id: i64,
+    create_missing_securities: bool,
+    skip_duplicates: bool,
+    type_overrides: std::collections::HashMap<usize, String>,
+    fee_overrides: std::collections::HashMap<usize, f64>,
+) -> Result<PdfImportResult, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
@@ -396,6 +504,14 @@ fn import_pdf_transactions_sync(
         )
         .map_err(|e| format!("Portfolio not found: {}", e))?;
 
+    // Transactions imported before (same target and content) are always skipped
+    let mut idempotency_keys = crate::commands::crud::ImportIdempotencyKeys::new("pdf");
+    // Duplicate check only against transactions that existed before this import,
+    // so identical orders within the file are all imported
+    let last_existing_txn_id: i64 = conn
+        .query_row("SELECT COALESCE(MAX(id), 0) FROM pp_txn", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
     for (idx, txn) in result.transactions.iter().enumerate() {
         // Check for type override
         let effective_type = if let Some(override_type) = type_overrides.get(&idx) {
@@ -415,6 +531,29 @@ fn import_pdf_transactions_sync(
             txn.txn_type
         };
 
+        let idempotency_key = idempotency_keys.next(&format!(
+            "{}|{}|{:?}|{}|{}|{}|{}|{:?}|{}",
+            portfolio_id,
+            account_id,
+            effective_type,
+            format_datetime(txn),
+            txn.isin.as_deref().unwrap_or_default(),
+            txn.security_name.as_deref().unwrap_or_default(),
+            (txn.net_amount * 100.0).round() as i64,
+            txn.shares,
+            txn.currency,
+        ));
+        if crate::commands::crud::idempotency_key_exists(conn, &idempotency_key)
+            .map_err(|e| e.to_string())?
+        {
+            warnings.push(format!(
+                "Transaktion vom {} übersprungen (bereits importiert)",
+                txn.date
+            ));
+            transactions_skipped += 1;
+            continue;
+        }
+
         // Find or create security
         let security_id: Option<i64> = if let Some(isin) = &txn.isin {
             let existing: Option<i64> = conn
@@ -480,7 +619,7 @@ fn import_pdf_transactions_sync(
                     let type_placeholders = txn_types
                         .iter()
                         .enumerate()
-                        .map(|(i, _)| format!("?{}", i + 4))
+                        .map(|(i, _)| format!("?{}", i + 5))
                         .collect::<Vec<_>>()
                         .join(", ");
 
@@ -490,6 +629,7 @@ fn import_pdf_transactions_sync(
                         WHERE security_id = ?1
                           AND date LIKE ?2
                           AND ABS(amount - ?3) <= 1
+                          AND id <= ?4
                           AND txn_type IN ({})
                         LIMIT 1
                         "#,
@@ -497,7 +637,7 @@ fn import_pdf_transactions_sync(
                     );
 
                     let mut params: Vec<&dyn rusqlite::ToSql> =
-                        vec![&sec_id, &date_pattern, &amount_cents];
+                        vec![&sec_id, &date_pattern, &amount_cents, &last_existing_txn_id];
                     for t in &txn_types {
                         params.push(t);
                     }
@@ -552,8 +692,8 @@ fn import_pdf_transactions_sync(
 
             // Insert portfolio transaction
             conn.execute(
-                "INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-                 VALUES (?1, ?2, 'portfolio', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                "INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note, idempotency_key)
+                 VALUES (?1, ?2, 'portfolio', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 rusqlite::params![
                     import_id,
                     uuid,
@@ -565,6 +705,7 @@ fn import_pdf_transactions_sync(
                     txn.currency,
                     shares_scaled,
                     txn.note,
+                    idempotency_key,
                 ],
             ).map_err(|e| {
                 errors.push(format!("Failed to insert portfolio transaction: {}", e));
@@ -646,8 +787,8 @@ fn import_pdf_transactions_sync(
             };
 
             conn.execute(
-                "INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note)
-                 VALUES (?1, ?2, 'account', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                "INSERT INTO pp_txn (import_id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares, note, idempotency_key)
+                 VALUES (?1, ?2, 'account', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 rusqlite::params![
                     import_id,
                     uuid,
@@ -659,6 +800,7 @@ fn import_pdf_transactions_sync(
                     txn.currency,
                     shares_scaled,
                     txn.note,
+                    idempotency_key,
                 ],
             ).map_err(|e| {
                 errors.push(format!("Failed to insert account transaction: {}", e));
@@ -695,6 +837,17 @@ fn import_pdf_transactions_sync(
         );
     }
 
+    record_audit(
+        conn,
+        "import_pdf_transactions",
+        "import",
+        Some(import_id),
+        &format!(
+            "PDF-Import ({}) in Depot {}: {} Buchungen importiert, {} übersprungen, {} Wertpapiere angelegt",
+            result.bank, portfolio_id, transactions_imported, transactions_skipped, securities_created
+        ),
+    );
+
     Ok(PdfImportResult {
         success: errors.is_empty(),
         bank: result.bank,
@@ -706,6 +859,223 @@ fn import_pdf_transactions_sync(
     })
 }
 
+// ============================================================================
+// Batch Import
+// ============================================================================
+
+/// Key identifying the same transaction across statements: (date, type, ISIN, shares, amount)
+type TransactionKey = (chrono::NaiveDate, ParsedTransactionType, Option<String>, Option<i64>, i64);
+
+/// Dedup key of a parsed transaction (shares ×10^8, amount in cents)
+pub(crate) fn transaction_key(txn: &ParsedTransaction) -> TransactionKey {
+    (
+        txn.date,
+        txn.txn_type,
+        txn.isin.clone(),
+        txn.shares.map(|s| (s * 100_000_000.0).round() as i64),
+        (txn.net_amount * 100.0).round() as i64,
+    )
+}
+
+/// Drop transactions already contained in an earlier file of the batch
+///
+/// Identical transactions within one file are kept (two equal orders on the
+/// same day); a later file only loses as many of them as earlier files had.
+/// Transactions already in the database are skipped by `import_parse_result`.
+/// Returns the number of dropped transactions and records this file's keys.
+fn remove_batch_duplicates(
+    result: &mut ParseResult,
+    seen: &mut std::collections::HashMap<TransactionKey, usize>,
+    warnings: &mut Vec<String>,
+) -> i32 {
+    let mut skipped = 0;
+    let mut keep = Vec::with_capacity(result.transactions.len());
+    let mut in_file: std::collections::HashMap<TransactionKey, usize> = std::collections::HashMap::new();
+
+    for txn in result.transactions.drain(..) {
+        let key = transaction_key(&txn);
+        let earlier = seen.get(&key).copied().unwrap_or(0);
+        let count = in_file.entry(key).or_insert(0);
+        *count += 1;
+        if *count > earlier {
+            keep.push(txn);
+        } else {
+            warnings.push(format!(
+                "Transaktion vom {} übersprungen (bereits in einer anderen Datei: {})",
+                txn.date,
+                txn.security_name.as_deref().or(txn.isin.as_deref()).unwrap_or("Unbekannt")
+            ));
+            skipped += 1;
+        }
+    }
+
+    for (key, count) in in_file {
+        let entry = seen.entry(key).or_insert(0);
+        *entry = (*entry).max(count);
+    }
+
+    result.transactions = keep;
+    // Provenance indices no longer match the filtered list
+    result.provenance.clear();
+    skipped
+}
+
+/// Result of one file in a batch import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfBatchFileResult {
+    pub path: String,
+    /// Detected bank, None if the file could not be parsed
+    pub bank: Option<String>,
+    pub transactions_imported: i32,
+    pub transactions_skipped: i32,
+    pub securities_created: i32,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Batch import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfBatchImportResult {
+    pub files: Vec<PdfBatchFileResult>,
+    pub transactions_imported: i32,
+    pub transactions_skipped: i32,
+    pub files_failed: i32,
+}
+
+/// Progress of a batch import (Event `pdf-batch-progress`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfBatchProgress {
+    /// Number of files already processed
+    pub current: usize,
+    pub total: usize,
+    pub path: String,
+    pub success: bool,
+}
+
+/// Import several PDF statements at once
+///
+/// Files are processed in order; transactions that appear in more than one file
+/// (same date, ISIN, shares and amount) are imported only once. A failing file
+/// does not abort the batch.
+#[command]
+pub async fn import_pdf_batch(
+    app: AppHandle,
+    paths: Vec<String>,
+    portfolio_id: i64,
+    account_id: i64,
+    create_missing_securities: bool,
+    skip_duplicates: Option<bool>,
+) -> Result<PdfBatchImportResult, String> {
+    let skip_duplicates = skip_duplicates.unwrap_or(true);
+    let progress_app = app.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let total = paths.len();
+        let mut seen = std::collections::HashMap::new();
+        let mut files = Vec::with_capacity(total);
+
+        for (idx, path) in paths.into_iter().enumerate() {
+            let file_result = import_batch_file(
+                &path,
+                portfolio_id,
+                account_id,
+                create_missing_securities,
+                skip_duplicates,
+                &mut seen,
+            );
+
+            let _ = progress_app.emit(
+                "pdf-batch-progress",
+                PdfBatchProgress {
+                    current: idx + 1,
+                    total,
+                    path: path.clone(),
+                    success: file_result.errors.is_empty(),
+                },
+            );
+            files.push(file_result);
+        }
+
+        PdfBatchImportResult {
+            transactions_imported: files.iter().map(|f| f.transactions_imported).sum(),
+            transactions_skipped: files.iter().map(|f| f.transactions_skipped).sum(),
+            files_failed: files.iter().filter(|f| !f.errors.is_empty()).count() as i32,
+            files,
+        }
+    })
+    .await
+    .map_err(|e| format!("PDF batch import task failed: {}", e))?;
+
+    if result.transactions_imported > 0 {
+        emit_data_changed(&app, DataChangedPayload::import(vec![]));
+    }
+
+    Ok(result)
+}
+
+/// Import one file of a batch; errors are reported in the file result
+fn import_batch_file(
+    path: &str,
+    portfolio_id: i64,
+    account_id: i64,
+    create_missing_securities: bool,
+    skip_duplicates: bool,
+    seen: &mut std::collections::HashMap<TransactionKey, usize>,
+) -> PdfBatchFileResult {
+    let mut file_result = PdfBatchFileResult {
+        path: path.to_string(),
+        bank: None,
+        transactions_imported: 0,
+        transactions_skipped: 0,
+        securities_created: 0,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    // SECURITY: Validate path (defense-in-depth)
+    let parsed = crate::security::validate_file_path_with_extension(path, Some(&["pdf"]))
+        .map_err(|e| format!("Invalid file path: {}", e))
+        .and_then(|validated| parse_pdf(&validated.to_string_lossy()));
+    let mut result = match parsed {
+        Ok(r) => r,
+        Err(e) => {
+            log::warn!("PDF Batch Import: Failed to parse {}: {}", path, e);
+            file_result.errors.push(e);
+            return file_result;
+        }
+    };
+
+    file_result.bank = Some(result.bank.clone());
+    let batch_skipped = remove_batch_duplicates(&mut result, seen, &mut file_result.warnings);
+
+    match import_parse_result(
+        result,
+        portfolio_id,
+        account_id,
+        create_missing_securities,
+        skip_duplicates,
+        std::collections::HashMap::new(),
+        std::collections::HashMap::new(),
+    ) {
+        Ok(imported) => {
+            file_result.transactions_imported = imported.transactions_imported;
+            file_result.transactions_skipped = imported.transactions_skipped + batch_skipped;
+            file_result.securities_created = imported.securities_created;
+            file_result.errors.extend(imported.errors);
+            file_result.warnings.extend(imported.warnings);
+        }
+        Err(e) => {
+            file_result.transactions_skipped = batch_skipped;
+            file_result.errors.push(e);
+        }
+    }
+
+    file_result
+}
+
 /// Extract raw text from PDF for debugging/custom parsing
 #[command]
 pub fn extract_pdf_raw_text(pdf_path: String) -> Result<String, String> {
@@ -836,7 +1206,8 @@ pub async fn preview_pdf_import_with_ocr(
     let extracted_text = extract_pdf_text(&validated_path_str)?;
 
     // Check if we should use OCR fallback
-    let content = if use_ocr && should_use_ocr_fallback(&extracted_text, 100) {
+    let ocr_used = use_ocr && should_use_ocr_fallback(&extracted_text, MIN_TEXT_LAYER_CHARS);
+    let content = if ocr_used {
         log::info!("PDF Import: Text extraction yielded too little content, using OCR fallback");
 
         // SECURITY: Require explicit consent for external data upload
@@ -871,134 +1242,100 @@ pub async fn preview_pdf_import_with_ocr(
         extracted_text
     };
 
-    // Parse the content
-    let result = parse_pdf_content(&content)?;
-
-    // Rest of the preview logic (same as preview_pdf_import)
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard
-        .as_ref()
-        .ok_or_else(|| "Database not initialized".to_string())?;
-
-    let mut new_securities = Vec::new();
-    let mut matched_securities = Vec::new();
-    let mut seen_isins = std::collections::HashSet::new();
-
-    for txn in &result.transactions {
-        if let Some(isin) = &txn.isin {
-            if seen_isins.contains(isin) {
-                continue;
-            }
-            seen_isins.insert(isin.clone());
+    build_preview(&content, ocr_used)
+}
 
-            let existing: Option<(i64, String)> = conn
-                .query_row(
-                    "SELECT id, name FROM pp_security WHERE isin = ?1 LIMIT 1",
-                    [isin],
-                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
-                )
-                .ok();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let security_match = SecurityMatch {
-                isin: Some(isin.clone()),
-                wkn: txn.wkn.clone(),
-                name: txn.security_name.clone(),
-                existing_id: existing.as_ref().map(|(id, _)| *id),
-                existing_name: existing.map(|(_, name)| name),
-            };
+    fn ocr_args(consent: bool) -> (Option<String>, Option<String>, Option<String>, Option<bool>) {
+        (
+            Some("claude".to_string()),
+            Some("claude-sonnet".to_string()),
+            Some("sk-test".to_string()),
+            Some(consent),
+        )
+    }
 
-            if security_match.existing_id.is_some() {
-                matched_securities.push(security_match);
-            } else {
-                new_securities.push(security_match);
-            }
+    fn parsed(date: &str, isin: &str, shares: f64, net_amount: f64) -> ParsedTransaction {
+        ParsedTransaction {
+            date: chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            time: None,
+            txn_type: ParsedTransactionType::Buy,
+            security_name: None,
+            isin: Some(isin.to_string()),
+            wkn: None,
+            shares: Some(shares),
+            price_per_share: None,
+            gross_amount: net_amount,
+            fees: 0.0,
+            taxes: 0.0,
+            net_amount,
+            currency: "EUR".to_string(),
+            note: None,
+            exchange_rate: None,
+            forex_currency: None,
         }
     }
 
-    let warnings: Vec<String> = result.warnings.iter().map(|w| {
-        format!("[{}] {}: {} (Wert: '{}')",
-            match w.severity {
-                crate::pdf_import::WarningSeverity::Info => "Info",
-                crate::pdf_import::WarningSeverity::Warning => "Warnung",
-                crate::pdf_import::WarningSeverity::Error => "Fehler",
-            },
-            w.field,
-            w.message,
-            w.raw_value
-        )
-    }).collect();
-
-    let mut potential_duplicates = Vec::new();
-    for (idx, txn) in result.transactions.iter().enumerate() {
-        if let Some(isin) = &txn.isin {
-            let security_id: Option<i64> = conn
-                .query_row(
-                    "SELECT id FROM pp_security WHERE isin = ?1 LIMIT 1",
-                    [isin],
-                    |row| row.get(0),
-                )
-                .ok();
-
-            if let Some(sec_id) = security_id {
-                let amount_cents = (txn.net_amount * 100.0).round() as i64;
-                // Get all possible DB types (original + delivery mode variant)
-                let txn_types = get_duplicate_check_types(txn.txn_type);
-                if txn_types.is_empty() {
-                    continue; // Skip non-portfolio transactions (Dividend, etc.)
-                }
-
-                // Use LIKE for date comparison to handle seconds mismatch
-                // Check for multiple type variants (BUY and DELIVERY_INBOUND, etc.)
-                let date_pattern = format!("{}%", format_datetime(txn));
-                let type_placeholders = txn_types
-                    .iter()
-                    .enumerate()
-                    .map(|(i, _)| format!("?{}", i + 4))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                let sql = format!(
-                    r#"
-                    SELECT id FROM pp_txn
-                    WHERE security_id = ?1
-                      AND date LIKE ?2
-                      AND ABS(amount - ?3) <= 1
-                      AND txn_type IN ({})
-                    LIMIT 1
-                    "#,
-                    type_placeholders
-                );
-
-                let mut params: Vec<&dyn rusqlite::ToSql> =
-                    vec![&sec_id, &date_pattern, &amount_cents];
-                for t in &txn_types {
-                    params.push(t);
-                }
-
-                let existing: Option<i64> = conn
-                    .query_row(&sql, params.as_slice(), |row| row.get(0))
-                    .ok();
-
-                if let Some(existing_id) = existing {
-                    potential_duplicates.push(PotentialDuplicate {
-                        transaction_index: idx,
-                        existing_txn_id: existing_id,
-                        date: format_datetime(txn),
-                        amount: txn.net_amount,
-                        security_name: txn.security_name.clone(),
-                        txn_type: txn_types[0].to_string(), // Use first type for display
-                    });
-                }
-            }
+    fn parse_result(transactions: Vec<ParsedTransaction>) -> ParseResult {
+        ParseResult {
+            bank: "DKB".to_string(),
+            transactions,
+            warnings: Vec::new(),
+            raw_text: None,
+            provenance: Vec::new(),
         }
     }
 
-    Ok(PdfImportPreview {
-        bank: result.bank,
-        transactions: result.transactions,
-        warnings,
-        new_securities,
-        matched_securities,
-        potential_duplicates,
-    })
+    #[test]
+    fn test_remove_batch_duplicates() {
+        let mut seen = std::collections::HashMap::new();
+        let mut warnings = Vec::new();
+
+        let mut january = parse_result(vec![
+            parsed("2024-01-15", "IE00B4L5Y983", 1.5, 120.0),
+            parsed("2024-01-15", "IE00B4L5Y983", 1.5, 120.0),
+        ]);
+        // Two identical orders within one file are both kept
+        assert_eq!(remove_batch_duplicates(&mut january, &mut seen, &mut warnings), 0);
+        assert_eq!(january.transactions.len(), 2);
+
+        // Overlapping statement: both January executions are dropped, February kept
+        let mut sell = parsed("2024-01-15", "IE00B4L5Y983", 1.5, 120.0);
+        sell.txn_type = ParsedTransactionType::Sell;
+        let mut february = parse_result(vec![
+            parsed("2024-01-15", "IE00B4L5Y983", 1.5, 120.0),
+            parsed("2024-01-15", "IE00B4L5Y983", 1.5, 120.0),
+            parsed("2024-02-15", "IE00B4L5Y983", 1.5, 120.0),
+            parsed("2024-01-15", "IE00B4L5Y983", 1.4, 120.0),
+            // A sell with the same figures is a different transaction
+            sell,
+        ]);
+        assert_eq!(remove_batch_duplicates(&mut february, &mut seen, &mut warnings), 2);
+        assert_eq!(february.transactions.len(), 3);
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_auto_ocr_options() {
+        let text_layer = "Wertpapier Abrechnung Kauf ".repeat(10);
+        let (provider, model, key, consent) = ocr_args(true);
+        assert!(auto_ocr_options(&text_layer, provider, model, key, consent)
+            .unwrap()
+            .is_none());
+
+        // Scanned PDF with credentials and consent falls back to OCR
+        let (provider, model, key, consent) = ocr_args(true);
+        let options = auto_ocr_options(" \n ", provider, model, key, consent)
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.provider, "claude");
+
+        // Without consent or credentials the user gets a hint instead
+        let (provider, model, key, consent) = ocr_args(false);
+        assert!(auto_ocr_options("", provider, model, key, consent).is_err());
+        assert!(auto_ocr_options("", None, None, None, None).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 //! Performance calculation commands for Tauri
 
+use crate::currency;
 use crate::db;
 use crate::performance;
 use crate::pp::parse_date_flexible; // SSOT: centralized date parsing
@@ -45,6 +46,22 @@ pub struct PeriodReturnData {
     pub return_rate: f64,
 }
 
+/// Holding of a security at a specific date
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldingAtDateData {
+    pub security_id: i64,
+    pub name: String,
+    pub isin: Option<String>,
+    pub currency: String,
+    pub shares: f64,
+    pub price: Option<f64>,
+    pub price_date: Option<String>,
+    pub value: Option<f64>,
+    pub value_base: Option<f64>,
+    pub base_currency: String,
+}
+
 /// Calculate performance metrics for a portfolio
 #[command]
 pub fn calculate_performance(
@@ -120,6 +137,8 @@ pub fn calculate_performance(
 }
 
 /// Get period returns for detailed analysis
+///
+/// Returns the sub-periods between cash flows that are chained into the TTWROR.
 #[command]
 pub fn get_period_returns(
     portfolio_id: Option<i64>,
@@ -161,6 +180,24 @@ pub fn get_period_returns(
     Ok(periods)
 }
 
+/// Get the standard return table (YTD, 1M, 3M, 6M, 1Y, 3Y, 5Y, since inception)
+///
+/// Unlike `get_period_returns` (sub-periods between cash flows), every entry is a
+/// TTWROR over a fixed window ending at the latest valuation date.
+#[command]
+pub fn get_standard_period_returns(
+    portfolio_id: Option<i64>,
+) -> Result<Vec<performance::StandardPeriodReturn>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let today = chrono::Utc::now().date_naive();
+    performance::calculate_standard_period_returns(conn, portfolio_id, today)
+        .map_err(|e| e.to_string())
+}
+
 /// Helper: Get first transaction date
 fn get_first_transaction_date(
     conn: &rusqlite::Connection,
@@ -202,7 +239,7 @@ fn get_cash_flows_for_irr(
 
 /// Calculate risk metrics for a portfolio
 ///
-/// Returns Sharpe, Sortino, Max Drawdown, Volatility, Beta/Alpha
+/// Returns Sharpe, Sortino, Max Drawdown, Volatility, Beta/Alpha, VaR
 #[command]
 pub fn calculate_risk_metrics(
     portfolio_id: Option<i64>,
@@ -210,6 +247,7 @@ pub fn calculate_risk_metrics(
     end_date: Option<String>,
     benchmark_id: Option<i64>,
     risk_free_rate: Option<f64>,
+    horizon_days: Option<u32>,
 ) -> Result<performance::RiskMetrics, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
@@ -228,6 +266,154 @@ pub fn calculate_risk_metrics(
             end - chrono::Duration::days(365)
         });
 
-    performance::calculate_risk_metrics(conn, portfolio_id, start, end, benchmark_id, risk_free_rate)
+    performance::calculate_risk_metrics(conn, portfolio_id, start, end, benchmark_id, risk_free_rate, horizon_days)
+        .map_err(|e| e.to_string())
+}
+
+/// Get concentration metrics (HHI, top-5/top-10 weight, effective number of holdings)
+///
+/// Based on the current position values in base currency.
+#[command]
+pub fn get_concentration_metrics(
+    portfolio_id: Option<i64>,
+) -> Result<performance::ConcentrationMetrics, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let today = chrono::Utc::now().date_naive();
+
+    let values: Vec<f64> = performance::get_holdings_at_date(conn, portfolio_id, today, &base_currency)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|h| h.value_base)
+        .collect();
+
+    Ok(performance::calculate_concentration_metrics(&values))
+}
+
+/// Get the total net worth: all portfolios plus all cash accounts in base currency
+///
+/// Includes the securities/cash split and a breakdown by holding currency.
+#[command]
+pub fn get_total_net_worth() -> Result<performance::NetWorth, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let today = chrono::Utc::now().date_naive();
+
+    performance::calculate_net_worth(conn, today, &base_currency).map_err(|e| e.to_string())
+}
+
+/// Get the net worth (all portfolios + all cash accounts) for each price date in a range
+#[command]
+pub fn get_net_worth_history(
+    start: String,
+    end: String,
+) -> Result<Vec<performance::NetWorthPoint>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start_date = parse_date_flexible(&start)
+        .ok_or_else(|| format!("Ungültiges Datumsformat: {}. Erwartet: YYYY-MM-DD", start))?;
+    let end_date = parse_date_flexible(&end)
+        .ok_or_else(|| format!("Ungültiges Datumsformat: {}. Erwartet: YYYY-MM-DD", end))?;
+    if start_date > end_date {
+        return Err("Startdatum liegt nach dem Enddatum".to_string());
+    }
+
+    performance::calculate_net_worth_history(conn, start_date, end_date).map_err(|e| e.to_string())
+}
+
+/// Calculate TTWROR and IRR of a single position (security), optionally per portfolio
+///
+/// Cash flows are the position's own purchases, sales and dividends; the period
+/// runs from the first transaction until today.
+#[command]
+pub fn calculate_position_performance(
+    security_id: i64,
+    portfolio_id: Option<i64>,
+) -> Result<performance::PositionPerformance, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let today = chrono::Utc::now().date_naive();
+    performance::calculate_position_performance(conn, security_id, portfolio_id, today)
+        .map_err(|e| e.to_string())
+}
+
+/// Compare portfolio TTWROR with and without currency effects
+///
+/// The constant-FX return converts everything at the start-date rates, the
+/// difference to the regular TTWROR is the currency contribution.
+#[command]
+pub fn calculate_currency_effect(
+    portfolio_id: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<performance::CurrencyEffect, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start = start_date
+        .and_then(|s| parse_date_flexible(&s))
+        .unwrap_or_else(|| {
+            get_first_transaction_date(conn, portfolio_id)
+                .unwrap_or_else(|| NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())
+        });
+    let end = end_date
+        .and_then(|s| parse_date_flexible(&s))
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    performance::calculate_currency_effect(conn, portfolio_id, start, end)
         .map_err(|e| e.to_string())
 }
+
+/// Get holdings as of a specific date (point-in-time snapshot)
+///
+/// Without portfolio_id, holdings of all portfolios are summed per security.
+#[command]
+pub fn get_holdings_at_date(
+    portfolio_id: Option<i64>,
+    date: String,
+) -> Result<Vec<HoldingAtDateData>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let date = parse_date_flexible(&date)
+        .ok_or_else(|| format!("Ungültiges Datumsformat: {}. Erwartet: YYYY-MM-DD", date))?;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    let holdings = performance::get_holdings_at_date(conn, portfolio_id, date, &base_currency)
+        .map_err(|e| e.to_string())?;
+
+    Ok(holdings
+        .into_iter()
+        .map(|h| HoldingAtDateData {
+            security_id: h.security_id,
+            name: h.name,
+            isin: h.isin,
+            currency: h.currency,
+            shares: h.shares,
+            price: h.price,
+            price_date: h.price_date.map(|d| d.to_string()),
+            value: h.value,
+            value_base: h.value_base,
+            base_currency: base_currency.clone(),
+        })
+        .collect())
+}
@@ -5,15 +5,19 @@
 //! - fetch_historical_prices: Historische Kurse abrufen
 //! - sync_security_prices: Kurse in DB speichern
 //! - sync_all_prices: Alle Securities aktualisieren
+//! - fetch_intraday_quotes: Intraday-Kurse für den Live-Chart (7 Tage Aufbewahrung)
 //! - fetch_exchange_rates: EZB Wechselkurse abrufen
 
+use crate::currency;
 use crate::db;
+use crate::events::{emit_price_updated, PriceUpdatedPayload};
+use crate::pp::HOLDINGS_SUM_SQL;
 use crate::quotes::{self, alphavantage, ecb, tradingview, yahoo, ExchangeRate, LatestQuote, ProviderType, Quote, QuoteResult};
 use futures::stream::{self, StreamExt};
 use chrono::NaiveDate;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use tauri::{command, AppHandle, Emitter};
 
 /// Legacy-Format für Rückwärtskompatibilität
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,8 +66,10 @@ pub struct ApiKeys {
 }
 
 /// Kurse für Securities aus der DB abrufen und aktualisieren
+/// Jeder gespeicherte Kurs sendet ein `price-updated` Event.
 #[command]
 pub async fn sync_security_prices(
+    app: AppHandle,
     security_ids: Vec<i64>,
     api_keys: Option<ApiKeys>,
 ) -> Result<Vec<QuoteResult>, String> {
@@ -106,28 +112,52 @@ pub async fn sync_security_prices(
     let results = quotes::fetch_all_quotes(requests).await;
 
     // Ergebnisse in DB speichern
+    let mut saved: Vec<(i64, &LatestQuote)> = Vec::new();
     for result in &results {
         if result.success {
             if let Some(ref latest) = result.latest {
-                if let Err(e) = save_quote_to_db(result.security_id, latest) {
-                    log::error!(
+                match save_quote_to_db(result.security_id, latest) {
+                    Ok(()) => saved.push((result.security_id, latest)),
+                    Err(e) => log::error!(
                         "Failed to save quote for security {}: {}",
                         result.security_id,
                         e
-                    );
+                    ),
                 }
             }
         }
     }
+    emit_prices_updated(&app, &saved);
+    evaluate_patterns_after_sync(saved.len());
+
+    let failed: Vec<&QuoteResult> = results.iter().filter(|r| !r.success).collect();
+    if let Err(e) = save_sync_errors_to_db(&failed) {
+        log::error!("Failed to save quote sync errors: {}", e);
+    }
 
     Ok(results)
 }
 
+/// Fortschritt der Kurssynchronisation (Event `quote-sync-progress`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteSyncProgress {
+    /// Anzahl bereits abgeschlossener Abfragen
+    pub current: usize,
+    pub total: usize,
+    pub symbol: String,
+    pub success: bool,
+}
+
 /// Alle Securities synchronisieren
+/// Kurse werden parallel abgerufen (siehe `quotes::fetch_all_quotes_concurrent`),
+/// jeder abgeschlossene Abruf sendet ein `quote-sync-progress` Event,
+/// jeder gespeicherte Kurs ein `price-updated` Event.
 /// @param only_held - wenn true, werden nur Wertpapiere mit Bestand synchronisiert
 /// @param api_keys - optionale API Keys für verschiedene Provider
 #[command]
 pub async fn sync_all_prices(
+    app: AppHandle,
     only_held: Option<bool>,
     api_keys: Option<ApiKeys>,
 ) -> Result<SyncResult, String> {
@@ -218,36 +248,60 @@ pub async fn sync_all_prices(
 
     log::info!("Fetching quotes for {} securities (skipped {})", requests.len(), skipped);
 
-    let results = quotes::fetch_all_quotes(requests).await;
+    let fetch_total = requests.len();
+    let mut completed = 0;
+    let results = quotes::fetch_all_quotes_concurrent(requests, |result| {
+        completed += 1;
+        let _ = app.emit(
+            "quote-sync-progress",
+            QuoteSyncProgress {
+                current: completed,
+                total: fetch_total,
+                symbol: result.symbol.clone(),
+                success: result.success,
+            },
+        );
+    })
+    .await;
 
     let mut success_count = 0;
     let mut error_count = 0;
     let mut errors: Vec<String> = Vec::new();
 
-    for result in &results {
-        if result.success {
-            if let Some(ref latest) = result.latest {
-                match save_quote_to_db(result.security_id, latest) {
-                    Ok(_) => {
-                        log::info!("Saved quote for {}: {}", result.symbol, latest.quote.close);
-                        success_count += 1;
-                    }
-                    Err(e) => {
-                        log::error!("Failed to save quote for {}: {}", result.symbol, e);
-                        error_count += 1;
-                        errors.push(format!("{}: {}", result.symbol, e));
-                    }
-                }
-            }
-        } else {
-            error_count += 1;
-            if let Some(ref err) = result.error {
-                log::error!("Quote fetch error for {}: {}", result.symbol, err);
-                errors.push(format!("{}: {}", result.symbol, err));
-            }
+    let fetched: Vec<(i64, &LatestQuote)> = results
+        .iter()
+        .filter(|r| r.success)
+        .filter_map(|r| r.latest.as_ref().map(|latest| (r.security_id, latest)))
+        .collect();
+
+    // Alle Kurse in einer Transaktion speichern
+    match save_quotes_to_db(&fetched) {
+        Ok(()) => {
+            log::info!("Saved {} quotes", fetched.len());
+            success_count = fetched.len();
+            emit_prices_updated(&app, &fetched);
+            evaluate_patterns_after_sync(fetched.len());
+        }
+        Err(e) => {
+            log::error!("Failed to save quotes: {}", e);
+            error_count += fetched.len();
+            errors.push(format!("Speichern fehlgeschlagen: {}", e));
         }
     }
 
+    // Fehler erst nach allen Wiederholungen (siehe `quotes::retry`) als fehlgeschlagen speichern
+    let failed: Vec<&QuoteResult> = results.iter().filter(|r| !r.success).collect();
+    for result in &failed {
+        error_count += 1;
+        if let Some(ref err) = result.error {
+            log::error!("Quote fetch error for {}: {}", result.symbol, err);
+            errors.push(format!("{}: {}", result.symbol, err));
+        }
+    }
+    if let Err(e) = save_sync_errors_to_db(&failed) {
+        log::error!("Failed to save quote sync errors: {}", e);
+    }
+
     log::info!("Sync complete: {} success, {} errors", success_count, error_count);
 
     Ok(SyncResult {
@@ -258,6 +312,101 @@ pub async fn sync_all_prices(
     })
 }
 
+/// Intraday-Kurse werden nach dieser Anzahl Tage gelöscht
+const INTRADAY_RETENTION_DAYS: i64 = 7;
+
+/// Einzelner Intraday-Kurs für den Live-Chart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntradayPrice {
+    pub timestamp: String,
+    pub price: f64,
+}
+
+/// Intraday-Kurse abrufen und in `pp_intraday_price` speichern
+/// Ohne `security_ids` werden alle Wertpapiere aus Watchlists abgerufen.
+/// Intraday-Kurse dienen nur dem Live-Chart; `pp_price` (EOD) bleibt die
+/// Grundlage für Performance-Berechnungen. Einträge älter als
+/// `INTRADAY_RETENTION_DAYS` werden dabei gelöscht.
+#[command]
+pub async fn fetch_intraday_quotes(security_ids: Option<Vec<i64>>) -> Result<SyncResult, String> {
+    let ids = match security_ids {
+        Some(ids) => ids,
+        None => get_watched_security_ids().map_err(|e| e.to_string())?,
+    };
+    let securities = get_securities_for_sync(ids).map_err(|e| e.to_string())?;
+    let total = securities.len();
+
+    let results: Vec<(i64, String, anyhow::Result<Vec<quotes::IntradayQuote>>)> = stream::iter(securities)
+        .map(|s| async move {
+            // Intraday always follows the provider for current quotes
+            let (feed, feed_url) = match s.latest_feed.as_deref().filter(|f| !f.is_empty()) {
+                Some(latest_feed) => (latest_feed.to_string(), s.latest_feed_url.clone()),
+                None => (s.feed.clone(), s.feed_url.clone()),
+            };
+            let symbol = s.ticker.clone().or(s.isin.clone()).unwrap_or_else(|| s.name.clone());
+            let result = match ProviderType::from_str(&feed) {
+                Some(provider) => quotes::fetch_intraday_quotes(&symbol, provider, feed_url.as_deref()).await,
+                None => Err(anyhow::anyhow!("Unknown provider '{}'", feed)),
+            };
+            (s.id, symbol, result)
+        })
+        .buffer_unordered(quotes::MAX_CONCURRENT_QUOTE_REQUESTS)
+        .collect()
+        .await;
+
+    let mut fetched: Vec<(i64, Vec<quotes::IntradayQuote>)> = Vec::new();
+    let mut error_messages: Vec<String> = Vec::new();
+    for (security_id, symbol, result) in results {
+        match result {
+            Ok(points) => fetched.push((security_id, points)),
+            Err(e) => {
+                log::warn!("Intraday fetch failed for {}: {}", symbol, e);
+                error_messages.push(format!("{}: {}", symbol, e));
+            }
+        }
+    }
+
+    let mut conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (security_id, points) in &fetched {
+        save_intraday_quotes(&tx, *security_id, points).map_err(|e| e.to_string())?;
+    }
+    prune_intraday_prices(&tx, chrono::Utc::now()).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(SyncResult {
+        total,
+        success: fetched.len(),
+        errors: error_messages.len(),
+        error_messages,
+    })
+}
+
+/// Gespeicherte Intraday-Kurse einer Security (aufsteigend nach Zeit)
+#[command]
+pub fn get_intraday_prices(security_id: i64) -> Result<Vec<IntradayPrice>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
+
+    let mut stmt = conn
+        .prepare("SELECT timestamp, value FROM pp_intraday_price WHERE security_id = ? ORDER BY timestamp")
+        .map_err(|e| e.to_string())?;
+    let prices = stmt
+        .query_map(params![security_id], |row| {
+            Ok(IntradayPrice {
+                timestamp: row.get(0)?,
+                price: quotes::price_from_db(row.get(1)?),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(prices)
+}
+
 /// Historische Kurse abrufen
 #[command]
 pub async fn fetch_historical_prices(
@@ -303,12 +452,13 @@ pub async fn fetch_historical_prices(
         .map_err(|e| e.to_string())?;
 
     // Optional: Historische Kurse in DB speichern
-    if let Err(e) = save_historical_quotes_to_db(security_id, &quotes) {
-        log::warn!(
+    match save_historical_quotes_to_db(security_id, &quotes) {
+        Ok(()) => evaluate_patterns_after_sync(quotes.len()),
+        Err(e) => log::warn!(
             "Failed to save historical quotes for security {}: {}",
             security_id,
             e
-        );
+        ),
     }
 
     Ok(quotes)
@@ -360,6 +510,118 @@ pub async fn fetch_historical_exchange_rates(
     Ok(all_rates)
 }
 
+/// Ergebnis des Wechselkurs-Backfills
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateBackfillResult {
+    /// Fremdwährungen aus Wertpapieren und Konten (EUR/X-Paare)
+    pub currencies: Vec<String>,
+    pub from: String,
+    pub to: String,
+    pub fetched: usize,
+    /// Neu gespeicherte Kurse (vorhandene bleiben unverändert)
+    pub inserted: usize,
+    /// Währungen ohne EZB-Kurse
+    pub unsupported: Vec<String>,
+}
+
+/// Tägliche EZB-Kurse für alle verwendeten Währungen ab der ersten Buchung nachladen
+///
+/// Währungen kommen aus `pp_security.currency` und `pp_account.currency`
+/// (GBX/GBp als GBP). Nur fehlende Tage werden in `pp_exchange_rate` ergänzt.
+#[command]
+pub async fn backfill_exchange_rates() -> Result<ExchangeRateBackfillResult, String> {
+    let today = chrono::Utc::now().date_naive();
+    let (currencies, from) = {
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| "Database not initialized".to_string())?;
+        let from: Option<String> = conn
+            .query_row("SELECT MIN(date(date)) FROM pp_txn", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        let from = from
+            .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+            .unwrap_or(today);
+        (used_foreign_currencies(conn).map_err(|e| e.to_string())?, from)
+    };
+
+    let mut result = ExchangeRateBackfillResult {
+        currencies: currencies.clone(),
+        from: from.to_string(),
+        to: today.to_string(),
+        fetched: 0,
+        inserted: 0,
+        unsupported: Vec::new(),
+    };
+    if currencies.is_empty() {
+        return Ok(result);
+    }
+
+    let rates: Vec<ExchangeRate> = ecb::fetch_historical_rates(from, today)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_values()
+        .flatten()
+        .filter(|rate| rate.base == "EUR" && currencies.contains(&rate.target))
+        .collect();
+    result.fetched = rates.len();
+    result.unsupported = currencies
+        .iter()
+        .filter(|c| !rates.iter().any(|rate| &rate.target == *c))
+        .cloned()
+        .collect();
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+    result.inserted = insert_missing_exchange_rates(conn, &rates).map_err(|e| e.to_string())?;
+    log::info!(
+        "Backfilled {} exchange rates for {:?} since {}",
+        result.inserted,
+        currencies,
+        from
+    );
+
+    Ok(result)
+}
+
+/// Nicht-EUR-Währungen von Wertpapieren und Konten (GBX/GBp als GBP)
+fn used_foreign_currencies(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT currency FROM pp_security WHERE currency IS NOT NULL
+         UNION SELECT currency FROM pp_account WHERE currency IS NOT NULL",
+    )?;
+    let mut currencies: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|c| if c == "GBX" || c == "GBp" { "GBP".to_string() } else { c })
+        .filter(|c| c != "EUR")
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+    Ok(currencies)
+}
+
+/// EUR/X-Kurse speichern, ohne vorhandene Tage zu überschreiben
+fn insert_missing_exchange_rates(conn: &rusqlite::Connection, rates: &[ExchangeRate]) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut inserted = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for rate in rates.iter().filter(|rate| rate.base == "EUR") {
+            inserted += stmt.execute(params![rate.base, rate.target, rate.date.to_string(), rate.rate.to_string()])?;
+        }
+    }
+    tx.commit()?;
+    Ok(inserted)
+}
+
 /// Verfügbare Quote Provider abrufen
 /// Gibt alle Provider zurück, die verwendet werden können
 /// Provider, die einen API-Key benötigen, werden nur zurückgegeben wenn der Key vorhanden ist
@@ -395,6 +657,12 @@ pub async fn get_available_quote_providers(api_keys: Option<ApiKeys>) -> Result<
             requires_api_key: false,
             supports_historical: true,
         },
+        QuoteProvider {
+            id: "STOOQ".to_string(),
+            name: "Stooq (EOD)".to_string(),
+            requires_api_key: false,
+            supports_historical: true,
+        },
     ];
 
     // Add Finnhub only if API key is provided
@@ -466,6 +734,9 @@ pub async fn search_external_securities(
     let mut providers_used: Vec<String> = Vec::new();
     let mut errors: Vec<String> = Vec::new();
 
+    // Yahoo doesn't return ISINs, but an ISIN query only matches that security
+    let query_isin = Some(query.to_uppercase()).filter(|q| crate::commands::crud::validate_isin(q));
+
     // 1. Search Yahoo Finance (no API key needed, global coverage)
     match yahoo::search(query).await {
         Ok(results) => {
@@ -474,7 +745,7 @@ pub async fn search_external_securities(
                 all_results.push(ExternalSecuritySearchResult {
                     symbol: r.symbol.clone(),
                     name: r.name,
-                    isin: None, // Yahoo doesn't return ISIN
+                    isin: query_isin.clone(),
                     wkn: None,
                     security_type: Some(r.security_type),
                     currency: None, // Currency determined by exchange
@@ -550,6 +821,102 @@ pub async fn search_external_securities(
     })
 }
 
+// ============== ISIN Enrichment ==============
+
+/// Metadata suggested for an ISIN by `enrich_security_from_isin`
+///
+/// Nothing is saved; the user confirms the values and applies them via
+/// `update_security` (or `create_security`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityEnrichment {
+    pub isin: String,
+    pub name: Option<String>,
+    pub ticker: Option<String>,
+    pub exchange: Option<String>,
+    pub currency: Option<String>,
+    pub security_type: Option<String>,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    /// Issuer country from the ISIN prefix (`domicile` of the security)
+    pub domicile: Option<String>,
+    /// Quote provider for the suggested ticker
+    pub feed: Option<String>,
+    /// All listings found for the ISIN, the first one is suggested
+    pub candidates: Vec<ExternalSecuritySearchResult>,
+    pub errors: Vec<String>,
+}
+
+/// Look up name, ticker, exchange, currency and country for an ISIN
+///
+/// Searches Yahoo Finance for the ISIN and reads the currency from the quote
+/// of the first listing.
+#[command]
+pub async fn enrich_security_from_isin(isin: String) -> Result<SecurityEnrichment, String> {
+    let isin = isin.trim().to_uppercase();
+    if !crate::commands::crud::validate_isin(&isin) {
+        return Err(format!("Ungültige ISIN: {}", isin));
+    }
+
+    let results = yahoo::search(&isin)
+        .await
+        .map_err(|e| format!("Yahoo Finance: {}", e))?;
+    let mut enrichment = build_security_enrichment(&isin, results);
+
+    if let Some(ticker) = enrichment.ticker.clone() {
+        match yahoo::fetch_quote(&ticker, false).await {
+            Ok(quote) => {
+                enrichment.currency = quote.currency;
+                if enrichment.name.is_none() {
+                    enrichment.name = quote.name;
+                }
+            }
+            Err(e) => {
+                log::warn!("Yahoo quote for {} failed: {}", ticker, e);
+                enrichment.errors.push(format!("Währung nicht ermittelbar: {}", e));
+            }
+        }
+    } else {
+        enrichment.errors.push("Kein Wertpapier zur ISIN gefunden".to_string());
+    }
+
+    Ok(enrichment)
+}
+
+fn build_security_enrichment(isin: &str, results: Vec<yahoo::YahooSearchResult>) -> SecurityEnrichment {
+    let best = results.first().cloned();
+    let candidates = results
+        .into_iter()
+        .map(|r| ExternalSecuritySearchResult {
+            symbol: r.symbol,
+            name: r.name,
+            isin: Some(isin.to_string()),
+            wkn: None,
+            security_type: Some(r.security_type),
+            currency: None,
+            region: Some(r.exchange),
+            provider: "YAHOO".to_string(),
+            provider_id: None,
+        })
+        .collect();
+
+    let non_empty = |s: String| Some(s).filter(|s| !s.trim().is_empty());
+    SecurityEnrichment {
+        isin: isin.to_string(),
+        name: best.as_ref().and_then(|b| non_empty(b.name.clone())),
+        ticker: best.as_ref().map(|b| b.symbol.clone()),
+        exchange: best.as_ref().and_then(|b| non_empty(b.exchange.clone())),
+        currency: None,
+        security_type: best.as_ref().map(|b| b.security_type.clone()),
+        sector: best.as_ref().and_then(|b| b.sector.clone()),
+        industry: best.as_ref().and_then(|b| b.industry.clone()),
+        domicile: crate::tax::withholding::security_country(None, Some(isin)),
+        feed: best.as_ref().map(|_| "YAHOO".to_string()),
+        candidates,
+        errors: Vec::new(),
+    }
+}
+
 // ============== Hilfsstrukturen ==============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -611,6 +978,24 @@ fn get_securities_for_sync(ids: Vec<i64>) -> anyhow::Result<Vec<SecurityInfo>> {
     Ok(securities)
 }
 
+/// Alle Securities, die in mindestens einer Watchlist stehen
+fn get_watched_security_ids() -> anyhow::Result<Vec<i64>> {
+    let conn_guard = db::get_connection()?;
+    let conn = conn_guard.as_ref().ok_or(anyhow::anyhow!("DB not initialized"))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT ws.security_id
+         FROM pp_watchlist_security ws
+         JOIN pp_security s ON s.id = ws.security_id
+         WHERE s.is_retired = 0",
+    )?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+
+    Ok(ids)
+}
+
 fn get_all_securities_for_sync(only_held: bool) -> anyhow::Result<Vec<SecurityInfo>> {
     let conn_guard = db::get_connection()?;
     let conn = conn_guard.as_ref().ok_or(anyhow::anyhow!("DB not initialized"))?;
@@ -697,6 +1082,113 @@ fn save_quote_to_db(security_id: i64, quote: &LatestQuote) -> anyhow::Result<()>
     let conn_guard = db::get_connection()?;
     let conn = conn_guard.as_ref().ok_or(anyhow::anyhow!("DB not initialized"))?;
 
+    save_quote(conn, security_id, quote)
+}
+
+/// `price-updated` Events für gespeicherte Kurse senden
+fn emit_prices_updated(app: &AppHandle, saved: &[(i64, &LatestQuote)]) {
+    if saved.is_empty() {
+        return;
+    }
+
+    let payloads = match db::get_connection() {
+        Ok(conn_guard) => match conn_guard.as_ref() {
+            Some(conn) => price_updated_payloads(conn, saved),
+            None => return,
+        },
+        Err(e) => {
+            log::warn!("Failed to build price-updated events: {}", e);
+            return;
+        }
+    };
+
+    for payload in payloads {
+        emit_price_updated(app, payload);
+    }
+}
+
+/// Ausstehende Muster-Auswertungen mit den neuen Kursen abschließen
+fn evaluate_patterns_after_sync(saved_count: usize) {
+    if saved_count == 0 {
+        return;
+    }
+
+    let result = db::get_connection().and_then(|conn_guard| match conn_guard.as_ref() {
+        Some(conn) => super::patterns::evaluate_pending_patterns(conn),
+        None => Err(anyhow::anyhow!("DB not initialized")),
+    });
+    match result {
+        Ok(r) if r.patterns_evaluated > 0 => log::info!(
+            "Evaluated {} pattern outcomes ({} success, {} failure)",
+            r.patterns_evaluated,
+            r.successes,
+            r.failures
+        ),
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to evaluate pattern outcomes: {}", e),
+    }
+}
+
+/// Event-Payloads inkl. Umrechnung in die Basiswährung erzeugen
+fn price_updated_payloads(
+    conn: &rusqlite::Connection,
+    saved: &[(i64, &LatestQuote)],
+) -> Vec<PriceUpdatedPayload> {
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    saved
+        .iter()
+        .map(|(security_id, quote)| {
+            let security_currency: String = conn
+                .query_row(
+                    "SELECT currency FROM pp_security WHERE id = ?",
+                    params![security_id],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| base_currency.clone());
+            // GBX/GBp correction
+            let (close, convert_currency) = if security_currency == "GBX" || security_currency == "GBp" {
+                (quote.quote.close / 100.0, "GBP")
+            } else {
+                (quote.quote.close, security_currency.as_str())
+            };
+            let price_base =
+                currency::convert(conn, close, convert_currency, &base_currency, quote.quote.date).ok();
+
+            PriceUpdatedPayload {
+                security_id: *security_id,
+                date: quote.quote.date.to_string(),
+                price: quote.quote.close,
+                currency: security_currency,
+                price_base,
+                base_currency: base_currency.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Mehrere Kurse in einer einzigen Transaktion speichern
+fn save_quotes_to_db(quotes: &[(i64, &LatestQuote)]) -> anyhow::Result<()> {
+    if quotes.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn_guard = db::get_connection()?;
+    let conn = conn_guard.as_mut().ok_or(anyhow::anyhow!("DB not initialized"))?;
+
+    let tx = conn.transaction()?;
+    for (security_id, quote) in quotes {
+        save_quote(&tx, *security_id, quote)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Latest Price und historischen Kurs für eine Security schreiben
+fn save_quote(conn: &rusqlite::Connection, security_id: i64, quote: &LatestQuote) -> anyhow::Result<()> {
     let price_value = quotes::price_to_db(quote.quote.close);
     let high_value = quote.quote.high.map(quotes::price_to_db);
     let low_value = quote.quote.low.map(quotes::price_to_db);
@@ -719,26 +1211,114 @@ fn save_quote_to_db(security_id: i64, quote: &LatestQuote) -> anyhow::Result<()>
 
     // Auch in historische Preise einfügen
     conn.execute(
-        "INSERT OR REPLACE INTO pp_price (security_id, date, value)
-         VALUES (?, ?, ?)",
-        params![security_id, quote.quote.date.to_string(), price_value],
+        "INSERT OR REPLACE INTO pp_price (security_id, date, value, volume)
+         VALUES (?, ?, ?, ?)",
+        params![security_id, quote.quote.date.to_string(), price_value, quote.quote.volume],
+    )?;
+
+    // Erfolgreicher Abruf löscht einen früheren Sync-Fehler
+    conn.execute(
+        "DELETE FROM pp_quote_sync_error WHERE security_id = ?",
+        params![security_id],
     )?;
 
     Ok(())
 }
 
-fn save_historical_quotes_to_db(security_id: i64, quotes: &[Quote]) -> anyhow::Result<()> {
+/// Intraday-Kurse einer Security schreiben (gleicher Zeitstempel wird überschrieben)
+fn save_intraday_quotes(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    points: &[quotes::IntradayQuote],
+) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO pp_intraday_price (security_id, timestamp, value) VALUES (?, ?, ?)",
+    )?;
+    for point in points {
+        stmt.execute(params![
+            security_id,
+            point.timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            quotes::price_to_db(point.price),
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// Intraday-Kurse älter als `INTRADAY_RETENTION_DAYS` löschen
+fn prune_intraday_prices(
+    conn: &rusqlite::Connection,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<usize> {
+    let cutoff = now - chrono::Duration::days(INTRADAY_RETENTION_DAYS);
+    let deleted = conn.execute(
+        "DELETE FROM pp_intraday_price WHERE timestamp < ?",
+        params![cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string()],
+    )?;
+
+    Ok(deleted)
+}
+
+/// Endgültig fehlgeschlagene Kursabrufe in einer Transaktion speichern
+fn save_sync_errors_to_db(results: &[&QuoteResult]) -> anyhow::Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+
     let mut conn_guard = db::get_connection()?;
     let conn = conn_guard.as_mut().ok_or(anyhow::anyhow!("DB not initialized"))?;
 
     let tx = conn.transaction()?;
+    for result in results {
+        save_sync_error(&tx, result)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Letzten Fehler eines Kursabrufs für eine Security schreiben
+fn save_sync_error(conn: &rusqlite::Connection, result: &QuoteResult) -> anyhow::Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO pp_quote_sync_error (security_id, provider, error, failed_at)
+        VALUES (?, ?, ?, datetime('now'))
+        ON CONFLICT(security_id) DO UPDATE SET
+            provider = excluded.provider,
+            error = excluded.error,
+            failed_at = excluded.failed_at
+        "#,
+        params![
+            result.security_id,
+            result.provider,
+            result.error.as_deref().unwrap_or("Unbekannter Fehler"),
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn save_historical_quotes_to_db(security_id: i64, quotes: &[Quote]) -> anyhow::Result<()> {
+    let conn_guard = db::get_connection()?;
+    let conn = conn_guard.as_ref().ok_or(anyhow::anyhow!("DB not initialized"))?;
+
+    insert_historical_quotes(conn, security_id, quotes)
+}
+
+/// Historische Kurse in pp_price schreiben (vorhandene Tage werden überschrieben)
+pub(crate) fn insert_historical_quotes(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    quotes: &[Quote],
+) -> anyhow::Result<()> {
+    let tx = conn.unchecked_transaction()?;
 
     for quote in quotes {
         let price_value = quotes::price_to_db(quote.close);
         tx.execute(
-            "INSERT OR REPLACE INTO pp_price (security_id, date, value)
-             VALUES (?, ?, ?)",
-            params![security_id, quote.date.to_string(), price_value],
+            "INSERT OR REPLACE INTO pp_price (security_id, date, value, volume)
+             VALUES (?, ?, ?, ?)",
+            params![security_id, quote.date.to_string(), price_value, quote.volume],
         )?;
     }
 
@@ -749,7 +1329,7 @@ fn save_historical_quotes_to_db(security_id: i64, quotes: &[Quote]) -> anyhow::R
 /// Save exchange rates to database
 /// NOTE: Only EUR/X rates should be stored! The currency module auto-inverts when needed.
 /// See CLAUDE.md "Bekannte Fallen" #18 - storing X/EUR rates causes calculation errors!
-fn save_exchange_rates_to_db(rates: &[ExchangeRate]) -> anyhow::Result<()> {
+pub(crate) fn save_exchange_rates_to_db(rates: &[ExchangeRate]) -> anyhow::Result<()> {
     let mut conn_guard = db::get_connection()?;
     let conn = conn_guard.as_mut().ok_or(anyhow::anyhow!("DB not initialized"))?;
 
@@ -1145,8 +1725,17 @@ pub fn detect_splits_by_price_heuristic(
 
             // Check if this matches a known split ratio
             if let Some((num, denom, confidence)) = match_split_ratio(factor, min_conf) {
-                // Check if already recorded
-                if is_split_already_recorded(*sec_id, date_after)? {
+                // Check if already recorded (inline: the connection is already locked)
+                let recorded: i64 = conn
+                    .query_row(
+                        "SELECT COUNT(*) FROM pp_corporate_action
+                         WHERE security_id = ?1 AND effective_date = ?2
+                         AND action_type IN ('STOCK_SPLIT', 'REVERSE_SPLIT')",
+                        params![sec_id, date_after],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| e.to_string())?;
+                if recorded > 0 {
                     continue;
                 }
 
@@ -1164,8 +1753,32 @@ pub fn detect_splits_by_price_heuristic(
                     continue;
                 }
 
+                // A large (special) dividend around the same date causes a similar drop
+                let dividends = load_dividends_near(conn, *sec_id, date_after)?;
+                let dividend_check = check_dividend_explains_drop(price_before, price_after, &dividends);
+                let confidence = match &dividend_check {
+                    Some(check) => confidence * (1.0 - check.explained_share),
+                    None => confidence,
+                };
+                if confidence < min_conf {
+                    log::info!(
+                        "Heuristic: Ignoring {}:{} split candidate for {} on {} ({})",
+                        num, denom, sec_name, date_after,
+                        dividend_check.as_ref().map(|c| c.reason.as_str()).unwrap_or("")
+                    );
+                    continue;
+                }
+
                 // Save to database with lower confidence (heuristic detection)
-                save_heuristic_split_to_db(*sec_id, date_after, num, denom, confidence, price_before, price_after)?;
+                save_heuristic_split_to_db(
+                    conn,
+                    *sec_id,
+                    date_after,
+                    (num, denom),
+                    confidence,
+                    (price_before, price_after),
+                    dividend_check.as_ref().map(|c| c.reason.as_str()),
+                )?;
                 new_count += 1;
 
                 all_splits.push(HeuristicSplitResult {
@@ -1215,23 +1828,99 @@ fn match_split_ratio(factor: f64, min_confidence: f64) -> Option<(i32, i32, f64)
     None
 }
 
+/// Dividend of a security close to a split candidate
+struct NearbyDividend {
+    date: String,
+    /// Dividend per share in security currency
+    amount: f64,
+}
+
+/// Outcome of cross-checking a split candidate against dividends
+struct DividendCheck {
+    /// Share of the price drop explained by the dividend (0.0 - 1.0)
+    explained_share: f64,
+    reason: String,
+}
+
+/// Days around the split date in which a dividend ex-date counts as matching
+const DIVIDEND_MATCH_WINDOW_DAYS: i64 = 3;
+
+/// Load dividends (pp_security_event) within `DIVIDEND_MATCH_WINDOW_DAYS` of `date`
+fn load_dividends_near(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    date: &str,
+) -> Result<Vec<NearbyDividend>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.date, e.amount
+             FROM pp_security_event e
+             JOIN pp_security s ON s.id = e.security_id
+             WHERE e.security_id = ?1
+               AND e.event_type = 'DIVIDEND_PAYMENT'
+               AND e.amount IS NOT NULL
+               AND (e.amount_currency IS NULL OR e.amount_currency = s.currency)
+               AND ABS(julianday(e.date) - julianday(?2)) <= ?3",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let dividends = stmt
+        .query_map(params![security_id, date, DIVIDEND_MATCH_WINDOW_DAYS], |row| {
+            Ok(NearbyDividend {
+                date: row.get(0)?,
+                amount: row.get::<_, i64>(1)? as f64 / crate::pp::AMOUNT_FACTOR as f64,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(dividends)
+}
+
+/// Check how much of a price drop is explained by nearby dividends
+fn check_dividend_explains_drop(
+    price_before: f64,
+    price_after: f64,
+    dividends: &[NearbyDividend],
+) -> Option<DividendCheck> {
+    let gap = price_before - price_after;
+    let total: f64 = dividends.iter().map(|d| d.amount).sum();
+    if gap <= 0.0 || total <= 0.0 {
+        return None;
+    }
+
+    let explained_share = (total / gap).min(1.0);
+    let dates: Vec<&str> = dividends.iter().map(|d| d.date.as_str()).collect();
+    Some(DividendCheck {
+        explained_share,
+        reason: format!(
+            "Dividend {:.2} on {} explains {:.0}% of the price drop {:.2}",
+            total,
+            dates.join(", "),
+            explained_share * 100.0,
+            gap
+        ),
+    })
+}
+
 /// Save a heuristically detected split to database
 fn save_heuristic_split_to_db(
+    conn: &rusqlite::Connection,
     security_id: i64,
     date: &str,
-    numerator: i32,
-    denominator: i32,
+    (numerator, denominator): (i32, i32),
     confidence: f64,
-    price_before: f64,
-    price_after: f64,
+    (price_before, price_after): (f64, f64),
+    dividend_reason: Option<&str>,
 ) -> Result<(), String> {
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
-
-    let note = format!(
+    let mut note = format!(
         "Heuristically detected: price {:.2} → {:.2} (factor {:.3})",
         price_before, price_after, price_after / price_before
     );
+    if let Some(reason) = dividend_reason {
+        note.push_str(&format!("; {} - confidence lowered", reason));
+    }
 
     conn.execute(
         "INSERT INTO pp_corporate_action
@@ -1253,6 +1942,159 @@ fn save_heuristic_split_to_db(
     Ok(())
 }
 
+// ============== ISIN Change Detection ==============
+
+/// A detected ISIN change of a security
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsinChangeResult {
+    pub action_id: i64,
+    pub security_id: i64,
+    pub security_name: String,
+    pub old_isin: String,
+    pub new_isin: String,
+    pub effective_date: String,
+    pub confidence: f64,
+    /// Existing security that already carries the new ISIN (merged on confirmation)
+    pub successor_security_id: Option<i64>,
+    pub successor_name: Option<String>,
+}
+
+/// Detect ISIN changes by comparing the stored ISIN with the one reported
+/// for the ticker by the quote provider
+#[command]
+pub async fn detect_isin_changes(
+    only_held: Option<bool>,
+) -> Result<Vec<IsinChangeResult>, String> {
+    let securities = get_all_securities_for_sync(only_held.unwrap_or(true))
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+
+    for security in securities {
+        let (Some(ticker), Some(old_isin)) = (security.ticker.as_deref(), security.isin.as_deref()) else {
+            continue;
+        };
+
+        let (new_isin, confidence) = match tradingview::fetch_isin(ticker).await {
+            Ok(Some(found)) => found,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!("Failed to look up ISIN for {}: {}", security.name, e);
+                continue;
+            }
+        };
+
+        if !is_isin_change(old_isin, &new_isin) {
+            continue;
+        }
+
+        let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+        let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
+        let today = chrono::Utc::now().date_naive().to_string();
+        if let Some(result) = save_isin_change(
+            conn,
+            (security.id, &security.name),
+            (old_isin, &new_isin),
+            confidence,
+            &today,
+        )? {
+            results.push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Whether the provider ISIN is a valid, different ISIN
+fn is_isin_change(stored: &str, reported: &str) -> bool {
+    let reported = reported.trim().to_uppercase();
+    stored.trim().to_uppercase() != reported && crate::commands::crud::validate_isin(&reported)
+}
+
+/// Record a detected ISIN change (skipped if already recorded)
+///
+/// If another security already carries the new ISIN it is linked as
+/// successor, and its first price date is used as effective date.
+fn save_isin_change(
+    conn: &rusqlite::Connection,
+    (security_id, security_name): (i64, &str),
+    (old_isin, new_isin): (&str, &str),
+    confidence: f64,
+    today: &str,
+) -> Result<Option<IsinChangeResult>, String> {
+    let new_isin = new_isin.trim().to_uppercase();
+    let new_isin = new_isin.as_str();
+    let recorded: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pp_corporate_action
+             WHERE security_id = ?1 AND action_type = 'ISIN_CHANGE' AND new_identifier = ?2",
+            params![security_id, new_isin],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if recorded > 0 {
+        return Ok(None);
+    }
+
+    let successor: Option<(i64, String)> = conn
+        .query_row(
+            "SELECT id, name FROM pp_security WHERE UPPER(isin) = ?1 AND id != ?2 ORDER BY id LIMIT 1",
+            params![new_isin, security_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+
+    let effective_date = successor
+        .as_ref()
+        .and_then(|(id, _)| {
+            conn.query_row(
+                "SELECT MIN(date) FROM pp_price WHERE security_id = ?1",
+                params![id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten()
+        })
+        .unwrap_or_else(|| today.to_string());
+
+    let note = match &successor {
+        Some((_, name)) => format!("ISIN {} → {} (existing security: {})", old_isin, new_isin, name),
+        None => format!("ISIN {} → {}", old_isin, new_isin),
+    };
+
+    conn.execute(
+        "INSERT INTO pp_corporate_action
+         (security_id, action_type, effective_date, old_identifier, new_identifier,
+          successor_security_id, source, confidence, note)
+         VALUES (?1, 'ISIN_CHANGE', ?2, ?3, ?4, ?5, 'DETECTED', ?6, ?7)",
+        params![
+            security_id,
+            effective_date,
+            old_isin,
+            new_isin,
+            successor.as_ref().map(|(id, _)| *id),
+            confidence,
+            note,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    log::info!("Detected ISIN change for {}: {}", security_name, note);
+
+    Ok(Some(IsinChangeResult {
+        action_id: conn.last_insert_rowid(),
+        security_id,
+        security_name: security_name.to_string(),
+        old_isin: old_isin.to_string(),
+        new_isin: new_isin.to_string(),
+        effective_date,
+        confidence,
+        successor_security_id: successor.as_ref().map(|(id, _)| *id),
+        successor_name: successor.map(|(_, name)| name),
+    }))
+}
+
 // ============================================================================
 // Provider Status
 // ============================================================================
@@ -1277,6 +2119,19 @@ pub struct ProviderStatus {
     pub cannot_sync: Vec<SecurityProviderInfo>,
     /// Quote sync status for today
     pub quote_status: QuoteSyncStatus,
+    /// Securities whose last quote fetch failed (after all retries)
+    pub sync_errors: Vec<QuoteSyncError>,
+}
+
+/// Last failed quote fetch of a security
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteSyncError {
+    pub id: i64,
+    pub name: String,
+    pub provider: String,
+    pub error: String,
+    pub failed_at: String,
 }
 
 /// Status of quote synchronization for held securities
@@ -1459,6 +2314,7 @@ pub fn get_provider_status(api_keys: Option<ApiKeys>) -> Result<ProviderStatus,
 
     // Query quote sync status for held securities
     let quote_status = get_quote_sync_status(conn)?;
+    let sync_errors = load_quote_sync_errors(conn)?;
 
     Ok(ProviderStatus {
         total_securities,
@@ -1469,9 +2325,41 @@ pub fn get_provider_status(api_keys: Option<ApiKeys>) -> Result<ProviderStatus,
         by_provider,
         cannot_sync,
         quote_status,
+        sync_errors,
     })
 }
 
+/// Load the last failed quote fetch per (non-retired) security, newest first
+pub fn load_quote_sync_errors(conn: &rusqlite::Connection) -> Result<Vec<QuoteSyncError>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT s.id, s.name, e.provider, e.error, e.failed_at
+            FROM pp_quote_sync_error e
+            JOIN pp_security s ON s.id = e.security_id
+            WHERE s.is_retired = 0
+            ORDER BY e.failed_at DESC, s.name
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let errors = stmt
+        .query_map([], |row| {
+            Ok(QuoteSyncError {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                provider: row.get(2)?,
+                error: row.get(3)?,
+                failed_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(errors)
+}
+
 /// Get quote sync status for held securities
 fn get_quote_sync_status(conn: &rusqlite::Connection) -> Result<QuoteSyncStatus, String> {
     let today = chrono::Utc::now().date_naive();
@@ -1551,6 +2439,83 @@ fn get_quote_sync_status(conn: &rusqlite::Connection) -> Result<QuoteSyncStatus,
     })
 }
 
+/// Held security whose latest price is older than the requested age
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleSecurity {
+    pub id: i64,
+    pub name: String,
+    pub isin: Option<String>,
+    pub ticker: Option<String>,
+    /// Provider used for current quotes (latest_feed, falling back to feed)
+    pub feed: Option<String>,
+    pub last_quote_date: Option<String>,
+    /// Days since the latest price (None = no price at all)
+    pub days_stale: Option<i64>,
+}
+
+/// Get currently held securities whose latest price is older than `max_age_days`
+/// (or missing), oldest first. Used to badge the holdings table.
+#[command]
+pub fn get_stale_securities(max_age_days: u32) -> Result<Vec<StaleSecurity>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
+
+    let today = chrono::Utc::now().date_naive();
+    load_stale_securities(conn, today, max_age_days)
+}
+
+fn load_stale_securities(
+    conn: &rusqlite::Connection,
+    today: NaiveDate,
+    max_age_days: u32,
+) -> Result<Vec<StaleSecurity>, String> {
+    let sql = format!(
+        r#"
+        SELECT
+            s.id,
+            s.name,
+            s.isin,
+            s.ticker,
+            COALESCE(NULLIF(s.latest_feed, ''), NULLIF(s.feed, '')) as feed,
+            lp.date as last_quote_date,
+            CAST(julianday(?1) - julianday(lp.date) AS INTEGER) as days_stale
+        FROM pp_security s
+        JOIN (
+            SELECT t.security_id
+            FROM pp_txn t
+            WHERE t.owner_type = 'portfolio' AND t.shares IS NOT NULL
+            GROUP BY t.security_id
+            HAVING {} > 0
+        ) h ON h.security_id = s.id
+        LEFT JOIN pp_latest_price lp ON lp.security_id = s.id
+        WHERE s.is_retired = 0
+          AND (lp.date IS NULL OR julianday(?1) - julianday(lp.date) > ?2)
+        ORDER BY days_stale IS NOT NULL, days_stale DESC, s.name
+        "#,
+        HOLDINGS_SUM_SQL
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let securities = stmt
+        .query_map(params![today.to_string(), max_age_days], |row| {
+            Ok(StaleSecurity {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                isin: row.get(2)?,
+                ticker: row.get(3)?,
+                feed: row.get(4)?,
+                last_quote_date: row.get(5)?,
+                days_stale: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(securities)
+}
+
 // ============================================================================
 // Quote Provider Suggestions
 // ============================================================================
@@ -3043,6 +4008,35 @@ pub fn apply_quote_assistant_suggestion(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_build_security_enrichment() {
+        let result = |symbol: &str, exchange: &str| yahoo::YahooSearchResult {
+            symbol: symbol.to_string(),
+            name: "Apple Inc.".to_string(),
+            exchange: exchange.to_string(),
+            security_type: "Equity".to_string(),
+            sector: Some("Technology".to_string()),
+            industry: None,
+        };
+
+        let enrichment = build_security_enrichment(
+            "US0378331005",
+            vec![result("AAPL", "NMS"), result("APC.DE", "GER")],
+        );
+        assert_eq!(enrichment.name.as_deref(), Some("Apple Inc."));
+        assert_eq!(enrichment.ticker.as_deref(), Some("AAPL"));
+        assert_eq!(enrichment.exchange.as_deref(), Some("NMS"));
+        assert_eq!(enrichment.sector.as_deref(), Some("Technology"));
+        assert_eq!(enrichment.domicile.as_deref(), Some("US"));
+        assert_eq!(enrichment.feed.as_deref(), Some("YAHOO"));
+        assert_eq!(enrichment.candidates.len(), 2);
+        assert_eq!(enrichment.candidates[1].isin.as_deref(), Some("US0378331005"));
+
+        let empty = build_security_enrichment("IE00B4L5Y983", vec![]);
+        assert!(empty.ticker.is_none() && empty.feed.is_none());
+        assert_eq!(empty.domicile.as_deref(), Some("IE"));
+    }
+
     #[test]
     fn test_known_symbol_fix() {
         // Commodities
@@ -3103,6 +4097,237 @@ mod tests {
         assert_eq!(get_known_symbol_fix("AAPL", "YAHOO"), None);
     }
 
+    #[test]
+    fn test_backfill_exchange_rate_helpers() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (uuid, name, currency) VALUES ('s1', 'Apple', 'USD'), ('s2', 'BP', 'GBX'), ('s3', 'SAP', 'EUR');
+             INSERT INTO pp_account (uuid, name, currency) VALUES ('a1', 'Konto', 'EUR'), ('a2', 'Konto USD', 'USD');
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate) VALUES ('EUR', 'USD', '2024-01-02', '1.5');",
+        )
+        .unwrap();
+
+        assert_eq!(used_foreign_currencies(&conn).unwrap(), ["GBP", "USD"]);
+
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let rate = |target: &str, d: u32, rate: f64| ExchangeRate {
+            base: "EUR".into(),
+            target: target.into(),
+            date: date(d),
+            rate,
+        };
+        let rates = [rate("USD", 2, 1.1), rate("USD", 3, 1.2), rate("GBP", 2, 0.86)];
+        assert_eq!(insert_missing_exchange_rates(&conn, &rates).unwrap(), 2);
+
+        // Existing rates are kept
+        assert_eq!(currency::get_exchange_rate(&conn, "EUR", "USD", date(2)).unwrap(), 1.5);
+        assert_eq!(currency::get_exchange_rate(&conn, "EUR", "USD", date(3)).unwrap(), 1.2);
+    }
+
+    #[test]
+    fn test_sync_error_cleared_by_successful_quote() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_security (id INTEGER PRIMARY KEY, name TEXT NOT NULL, is_retired INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE pp_latest_price (
+                security_id INTEGER PRIMARY KEY, date TEXT, value INTEGER,
+                high INTEGER, low INTEGER, volume INTEGER, updated_at TEXT
+            );
+            CREATE TABLE pp_price (security_id INTEGER, date TEXT, value INTEGER, volume INTEGER, PRIMARY KEY (security_id, date));
+            CREATE TABLE pp_quote_sync_error (
+                security_id INTEGER PRIMARY KEY,
+                provider TEXT NOT NULL,
+                error TEXT NOT NULL,
+                failed_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            INSERT INTO pp_security (id, name) VALUES (1, 'SAP'), (2, 'Alt AG');
+            UPDATE pp_security SET is_retired = 1 WHERE id = 2;
+            "#,
+        )
+        .unwrap();
+
+        let failed = QuoteResult::error(1, "SAP.DE".into(), "YAHOO", "Yahoo API error: 429 Too Many Requests".into());
+        save_sync_error(&conn, &failed).unwrap();
+        save_sync_error(&conn, &QuoteResult::error(2, "ALT".into(), "YAHOO", "404".into())).unwrap();
+
+        // Retired securities are not reported
+        let errors = load_quote_sync_errors(&conn).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].name, "SAP");
+        assert_eq!(errors[0].error, "Yahoo API error: 429 Too Many Requests");
+
+        let quote = LatestQuote {
+            symbol: "SAP.DE".into(),
+            name: None,
+            currency: None,
+            quote: Quote {
+                date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                close: 150.0,
+                high: None,
+                low: None,
+                open: None,
+                volume: None,
+            },
+        };
+        save_quote(&conn, 1, &quote).unwrap();
+        assert!(load_quote_sync_errors(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_stale_securities_only_held() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_security (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, isin TEXT, ticker TEXT,
+                feed TEXT, latest_feed TEXT, is_retired INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE pp_txn (security_id INTEGER, owner_type TEXT, txn_type TEXT, shares INTEGER);
+            CREATE TABLE pp_latest_price (security_id INTEGER PRIMARY KEY, date TEXT);
+            INSERT INTO pp_security (id, name, isin, feed, latest_feed) VALUES
+                (1, 'Aktuell', 'DE0001', 'YAHOO', NULL),
+                (2, 'Veraltet', 'DE0002', 'YAHOO', 'STOOQ'),
+                (3, 'Ohne Kurs', 'DE0003', 'YAHOO', ''),
+                (4, 'Verkauft', 'DE0004', 'YAHOO', NULL);
+            INSERT INTO pp_txn VALUES
+                (1, 'portfolio', 'BUY', 100), (2, 'portfolio', 'BUY', 100),
+                (3, 'portfolio', 'BUY', 100), (4, 'portfolio', 'BUY', 100),
+                (4, 'portfolio', 'SELL', 100);
+            INSERT INTO pp_latest_price VALUES
+                (1, '2024-06-09'), (2, '2024-06-01'), (4, '2024-01-01');
+            "#,
+        )
+        .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let stale = load_stale_securities(&conn, today, 3).unwrap();
+
+        assert_eq!(stale.len(), 2);
+        // Securities without any price come first
+        assert_eq!(stale[0].name, "Ohne Kurs");
+        assert_eq!(stale[0].days_stale, None);
+        assert_eq!(stale[0].feed.as_deref(), Some("YAHOO"));
+        assert_eq!(stale[1].name, "Veraltet");
+        assert_eq!(stale[1].days_stale, Some(9));
+        assert_eq!(stale[1].feed.as_deref(), Some("STOOQ"));
+        assert_eq!(stale[1].isin.as_deref(), Some("DE0002"));
+    }
+
+    #[test]
+    fn test_intraday_prices_saved_and_pruned() {
+        use chrono::TimeZone;
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pp_intraday_price (
+                security_id INTEGER NOT NULL, timestamp TEXT NOT NULL, value INTEGER NOT NULL,
+                PRIMARY KEY (security_id, timestamp)
+            );",
+        )
+        .unwrap();
+
+        let now = chrono::Utc.with_ymd_and_hms(2024, 6, 10, 12, 0, 0).unwrap();
+        let point = |days_ago: i64, minutes: i64, price: f64| quotes::IntradayQuote {
+            timestamp: now - chrono::Duration::days(days_ago) + chrono::Duration::minutes(minutes),
+            price,
+            volume: None,
+        };
+
+        // Several points per day are kept, the same timestamp is overwritten
+        save_intraday_quotes(&conn, 1, &[point(0, 0, 10.0), point(0, 5, 10.5), point(8, 0, 9.0)]).unwrap();
+        save_intraday_quotes(&conn, 1, &[point(0, 5, 10.75)]).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM pp_intraday_price", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 3);
+
+        assert_eq!(prune_intraday_prices(&conn, now).unwrap(), 1);
+        let latest: i64 = conn
+            .query_row("SELECT value FROM pp_intraday_price ORDER BY timestamp DESC LIMIT 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(latest, quotes::price_to_db(10.75));
+    }
+
+    #[test]
+    fn test_dividend_explains_split_candidate() {
+        let dividend = |amount: f64| NearbyDividend {
+            date: "2024-05-02".to_string(),
+            amount,
+        };
+
+        // 100 → 50 with a special dividend of 48: almost fully explained
+        let check = check_dividend_explains_drop(100.0, 50.0, &[dividend(48.0)]).unwrap();
+        assert!((check.explained_share - 0.96).abs() < 1e-9);
+        assert!(check.reason.contains("2024-05-02"));
+
+        // A regular dividend barely explains a 2:1 drop
+        let check = check_dividend_explains_drop(100.0, 50.0, &[dividend(2.5)]).unwrap();
+        assert!((check.explained_share - 0.05).abs() < 1e-9);
+
+        // Explained share is capped, no dividends means no adjustment
+        assert_eq!(check_dividend_explains_drop(100.0, 50.0, &[dividend(80.0)]).unwrap().explained_share, 1.0);
+        assert!(check_dividend_explains_drop(100.0, 50.0, &[]).is_none());
+        assert!(check_dividend_explains_drop(50.0, 100.0, &[dividend(10.0)]).is_none());
+    }
+
+    #[test]
+    fn test_load_dividends_near() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_security (id INTEGER PRIMARY KEY, currency TEXT);
+            CREATE TABLE pp_security_event (
+                security_id INTEGER, event_type TEXT, date TEXT, amount INTEGER, amount_currency TEXT
+            );
+            INSERT INTO pp_security VALUES (1, 'EUR');
+            INSERT INTO pp_security_event VALUES
+                (1, 'DIVIDEND_PAYMENT', '2024-05-01', 4800, 'EUR'),
+                (1, 'DIVIDEND_PAYMENT', '2024-05-01', 100, 'USD'),
+                (1, 'DIVIDEND_PAYMENT', '2024-03-01', 250, 'EUR'),
+                (1, 'STOCK_SPLIT', '2024-05-02', NULL, NULL);
+            "#,
+        )
+        .unwrap();
+
+        let dividends = load_dividends_near(&conn, 1, "2024-05-02").unwrap();
+        assert_eq!(dividends.len(), 1);
+        assert_eq!(dividends[0].amount, 48.0);
+    }
+
+    #[test]
+    fn test_save_isin_change_links_successor() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pp_security (id INTEGER PRIMARY KEY, name TEXT, isin TEXT);
+             CREATE TABLE pp_price (security_id INTEGER, date TEXT, value INTEGER);
+             CREATE TABLE pp_corporate_action (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, security_id INTEGER, action_type TEXT,
+                 effective_date TEXT, old_identifier TEXT, new_identifier TEXT,
+                 successor_security_id INTEGER, source TEXT, confidence REAL, note TEXT
+             );
+             INSERT INTO pp_security VALUES (1, 'Linde alt', 'DE0006483001');
+             INSERT INTO pp_security VALUES (2, 'Linde neu', 'IE00BZ12WP82');
+             INSERT INTO pp_price VALUES (2, '2023-03-01', 100), (2, '2023-03-02', 101);",
+        )
+        .unwrap();
+
+        assert!(is_isin_change("DE0006483001", "ie00bz12wp82"));
+        assert!(!is_isin_change("DE0006483001", "DE0006483001"));
+        assert!(!is_isin_change("DE0006483001", "IE00BZ12WP83"));
+
+        let result = save_isin_change(&conn, (1, "Linde alt"), ("DE0006483001", " ie00bz12wp82 "), 0.9, "2024-05-01")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.new_isin, "IE00BZ12WP82");
+        assert_eq!(result.successor_security_id, Some(2));
+        assert_eq!(result.effective_date, "2023-03-01");
+
+        // Second detection run does not record it again
+        assert!(save_isin_change(&conn, (1, "Linde alt"), ("DE0006483001", "IE00BZ12WP82"), 0.9, "2024-05-02")
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn test_get_suffixes_for_currency() {
         assert_eq!(get_suffixes_for_currency("EUR"), vec![".DE", ".PA", ".AS", ".MI", ".MC"]);
@@ -3176,4 +4401,48 @@ mod tests {
         assert!(conf >= 0.5);
         assert!(conf <= 0.85);
     }
+
+    #[test]
+    fn test_price_updated_payloads_convert_to_base_currency() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_import (file_path, version, base_currency) VALUES ('test', 68, 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 'sec-1', 'Apple', 'USD');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (2, 'sec-2', 'SAP', 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (3, 'sec-3', 'Vodafone', 'GBX');",
+        )
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        currency::store_rate(&conn, "EUR", "USD", date, 1.25).unwrap();
+        currency::store_rate(&conn, "EUR", "GBP", date, 0.8).unwrap();
+
+        let quote = |close| LatestQuote {
+            symbol: "X".to_string(),
+            name: None,
+            currency: None,
+            quote: Quote {
+                date,
+                close,
+                high: None,
+                low: None,
+                open: None,
+                volume: None,
+            },
+        };
+        let apple = quote(200.0);
+        let sap = quote(180.0);
+        let vodafone = quote(7000.0);
+
+        let payloads = price_updated_payloads(&conn, &[(1, &apple), (2, &sap), (3, &vodafone)]);
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[0].currency, "USD");
+        assert_eq!(payloads[0].date, "2024-06-03");
+        assert!((payloads[0].price_base.unwrap() - 160.0).abs() < 1e-9);
+        assert_eq!(payloads[1].price_base, Some(180.0));
+        assert_eq!(payloads[1].base_currency, "EUR");
+        // Pence: 7000 GBX = 70 GBP = 87.50 EUR
+        assert_eq!(payloads[2].price, 7000.0);
+        assert!((payloads[2].price_base.unwrap() - 87.5).abs() < 1e-9);
+    }
 }
@@ -3,9 +3,11 @@
 //! Calculate and execute portfolio rebalancing based on target allocations.
 
 use crate::ai::{claude, gemini, openai, perplexity, AiError, get_model_upgrade};
+use crate::commands::audit::record_audit;
 use crate::db;
 use crate::events::{emit_data_changed, DataChangedPayload};
 use crate::pp::common::{prices, shares};
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
 use tauri::{command, AppHandle};
 
@@ -30,6 +32,9 @@ pub struct RebalanceAction {
     pub target_weight: f64,
     pub current_value: f64,
     pub target_value: f64,
+    /// Estimated FIFO gain of a SELL (security currency)
+    #[serde(default)]
+    pub realized_gain: Option<f64>,
 }
 
 /// Rebalancing preview result
@@ -41,6 +46,19 @@ pub struct RebalancePreview {
     pub actions: Vec<RebalanceAction>,
     pub deviation_before: f64,
     pub deviation_after: f64,
+    pub tax_estimate: RebalanceTaxEstimate,
+}
+
+/// Estimated German tax on the gains realized by the suggested sells
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RebalanceTaxEstimate {
+    /// Sum of the FIFO gains and losses of all sells
+    pub realized_gain: f64,
+    /// Taxable gain after Teilfreistellung, loss offsetting and Sparer-Pauschbetrag
+    pub taxable_gain: f64,
+    pub freistellung_used: f64,
+    /// Abgeltungssteuer + Soli + Kirchensteuer
+    pub estimated_tax: f64,
 }
 
 /// Target with current state
@@ -116,11 +134,24 @@ struct AiTargetJson {
 }
 
 /// Preview rebalancing actions
+///
+/// With `tax_aware` the sells are chosen to limit realized gains: positions
+/// whose FIFO lots would realize a loss are sold to target, winners only as
+/// far as the gain stays within the remaining Sparer-Pauschbetrag, otherwise
+/// they keep up to `tolerance` percentage points (default 2) of overweight.
+/// Buys are funded from new cash and the sells; if that is not enough all
+/// buys are scaled down evenly.
+///
+/// With `buy_only` nothing is sold: the new cash goes to the most
+/// underweight positions first (takes precedence over `tax_aware`).
 #[command]
 pub fn preview_rebalance(
     portfolio_id: i64,
     targets: Vec<RebalanceTarget>,
     new_cash: Option<f64>,
+    tax_aware: Option<bool>,
+    tolerance: Option<f64>,
+    buy_only: Option<bool>,
 ) -> Result<RebalancePreview, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
@@ -174,6 +205,11 @@ pub fn preview_rebalance(
         return Err("Portfolio has no value".to_string());
     }
 
+    let buy_only = buy_only.unwrap_or(false);
+    if buy_only && new_cash.unwrap_or(0.0) <= 0.0 {
+        return Err("Buy-only rebalancing needs new cash".to_string());
+    }
+
     // Map targets by security
     let mut target_map: std::collections::HashMap<i64, f64> = std::collections::HashMap::new();
     for target in &targets {
@@ -184,9 +220,8 @@ pub fn preview_rebalance(
 
     // Calculate current weights and deviations
     let mut targets_with_current = Vec::new();
-    let mut actions = Vec::new();
+    let mut rows: Vec<RebalanceRow> = Vec::new();
     let mut deviation_before = 0.0;
-    let mut deviation_after = 0.0;
 
     for (security_id, name, isin, _currency, shares, price) in &holdings {
         let current_value = shares * price;
@@ -210,42 +245,16 @@ pub fn preview_rebalance(
             difference,
         });
 
-        // Calculate action
-        if difference.abs() > 1.0 {  // Ignore tiny differences
-            let shares_diff = if *price > 0.0 { difference / price } else { 0.0 };
-
-            if difference > 0.0 {
-                actions.push(RebalanceAction {
-                    security_id: *security_id,
-                    security_name: name.clone(),
-                    isin: isin.clone(),
-                    action: "BUY".to_string(),
-                    shares: shares_diff,
-                    amount: difference,
-                    current_weight,
-                    target_weight,
-                    current_value,
-                    target_value,
-                });
-            } else {
-                actions.push(RebalanceAction {
-                    security_id: *security_id,
-                    security_name: name.clone(),
-                    isin: isin.clone(),
-                    action: "SELL".to_string(),
-                    shares: shares_diff.abs(),
-                    amount: difference.abs(),
-                    current_weight,
-                    target_weight,
-                    current_value,
-                    target_value,
-                });
-            }
-        }
-
-        // Calculate post-rebalance deviation (should be ~0 for matched targets)
-        let new_weight = target_weight;
-        deviation_after += (new_weight - target_weight).abs();
+        rows.push(RebalanceRow {
+            security_id: *security_id,
+            name: name.clone(),
+            isin: isin.clone(),
+            price: *price,
+            current_weight,
+            target_weight,
+            current_value,
+            target_value,
+        });
     }
 
     // Add targets for securities not currently held
@@ -267,8 +276,6 @@ pub fn preview_rebalance(
                     );
 
                     if let Ok((name, isin, price)) = sec_info {
-                        let shares = if price > 0.0 { target_value / price } else { 0.0 };
-
                         targets_with_current.push(RebalanceTargetWithCurrent {
                             security_id: Some(security_id),
                             security_name: Some(name.clone()),
@@ -281,13 +288,11 @@ pub fn preview_rebalance(
                             difference: target_value,
                         });
 
-                        actions.push(RebalanceAction {
+                        rows.push(RebalanceRow {
                             security_id,
-                            security_name: name,
+                            name,
                             isin,
-                            action: "BUY".to_string(),
-                            shares,
-                            amount: target_value,
+                            price,
                             current_weight: 0.0,
                             target_weight: target.target_weight,
                             current_value: 0.0,
@@ -299,6 +304,82 @@ pub fn preview_rebalance(
         }
     }
 
+    // FIFO lots of all positions with something to sell
+    let mut positions = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let lots = if row.current_value > row.target_value {
+            load_sale_lots(conn, portfolio_id, row.security_id)?
+        } else {
+            Vec::new()
+        };
+        let (fund_type, is_stock): (Option<String>, bool) = conn
+            .query_row(
+                "SELECT fund_type, is_stock FROM pp_security WHERE id = ?1",
+                [row.security_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap_or((None, false));
+        positions.push(RebalancePosition {
+            current_value: row.current_value,
+            target_value: row.target_value,
+            price: row.price,
+            lots,
+            teilfreistellung: crate::tax::teilfreistellung_for(fund_type.as_deref()),
+            is_stock: crate::tax::is_stock_for(is_stock, fund_type.as_deref()),
+        });
+    }
+
+    let settings = crate::tax::load_tax_settings(conn, chrono::Local::now().year());
+    let freistellung_remaining = (settings.freistellung_limit - settings.freistellung_used).max(0.0);
+
+    let trades = if buy_only {
+        plan_buy_only_trades(&positions, new_cash.unwrap_or(0.0))
+    } else if tax_aware.unwrap_or(false) {
+        let band = tolerance.unwrap_or(2.0).max(0.0) / 100.0 * total_value;
+        plan_tax_aware_trades(&positions, new_cash.unwrap_or(0.0), band, freistellung_remaining)
+    } else {
+        positions
+            .iter()
+            .map(|p| p.target_value - p.current_value)
+            .collect()
+    };
+
+    let mut actions = Vec::new();
+    let mut sales = Vec::new();
+    let mut deviation_after = 0.0;
+
+    for ((row, position), trade) in rows.iter().zip(&positions).zip(&trades) {
+        let new_weight = (row.current_value + trade) / total_value * 100.0;
+        deviation_after += (new_weight - row.target_weight).abs();
+
+        if trade.abs() <= 1.0 {  // Ignore tiny differences
+            continue;
+        }
+
+        let shares = if row.price > 0.0 { trade.abs() / row.price } else { 0.0 };
+        let realized_gain = if *trade < 0.0 {
+            let sale = position.sale_gain(shares);
+            sales.push(sale.clone());
+            Some(sale.gain)
+        } else {
+            None
+        };
+
+        actions.push(RebalanceAction {
+            security_id: row.security_id,
+            security_name: row.name.clone(),
+            isin: row.isin.clone(),
+            action: if *trade > 0.0 { "BUY" } else { "SELL" }.to_string(),
+            shares,
+            amount: trade.abs(),
+            current_weight: row.current_weight,
+            target_weight: row.target_weight,
+            current_value: row.current_value,
+            target_value: row.target_value,
+            realized_gain,
+        });
+    }
+
     // Sort actions: sells first, then buys
     actions.sort_by(|a, b| {
         match (&a.action[..], &b.action[..]) {
@@ -315,9 +396,234 @@ pub fn preview_rebalance(
         actions,
         deviation_before,
         deviation_after,
+        tax_estimate: estimate_sale_tax(&sales, freistellung_remaining, settings.kirchensteuer_rate),
     })
 }
 
+/// Security row of a rebalancing preview
+struct RebalanceRow {
+    security_id: i64,
+    name: String,
+    isin: Option<String>,
+    price: f64,
+    current_weight: f64,
+    target_weight: f64,
+    current_value: f64,
+    target_value: f64,
+}
+
+/// Open FIFO lot (oldest first) available for a sale
+#[derive(Debug, Clone)]
+struct SaleLot {
+    shares: f64,
+    /// Cost per share including fees
+    cost_per_share: f64,
+}
+
+/// Position data needed to plan and tax sells
+struct RebalancePosition {
+    current_value: f64,
+    target_value: f64,
+    price: f64,
+    lots: Vec<SaleLot>,
+    teilfreistellung: f64,
+    is_stock: bool,
+}
+
+/// Gain of a planned sale
+#[derive(Debug, Clone)]
+struct SaleGain {
+    /// FIFO gain before Teilfreistellung
+    gain: f64,
+    /// Taxable part after Teilfreistellung
+    taxable: f64,
+    is_stock: bool,
+}
+
+impl RebalancePosition {
+    /// Gain of selling `shares`, consuming the oldest lots first (FIFO is
+    /// mandatory within a German depot, lots cannot be picked)
+    fn sale_gain(&self, shares: f64) -> SaleGain {
+        let mut remaining = shares;
+        let mut cost = 0.0;
+        for lot in &self.lots {
+            if remaining <= 0.0 {
+                break;
+            }
+            let used = remaining.min(lot.shares);
+            cost += used * lot.cost_per_share;
+            remaining -= used;
+        }
+        let sold = shares - remaining.max(0.0);
+        let gain = sold * self.price - cost;
+        SaleGain {
+            gain,
+            taxable: gain * (1.0 - self.teilfreistellung),
+            is_stock: self.is_stock,
+        }
+    }
+
+    fn sale_gain_for_value(&self, value: f64) -> SaleGain {
+        let shares = if self.price > 0.0 { value / self.price } else { 0.0 };
+        self.sale_gain(shares)
+    }
+}
+
+/// Load the open FIFO lots of a security in a portfolio (oldest first)
+fn load_sale_lots(conn: &rusqlite::Connection, portfolio_id: i64, security_id: i64) -> Result<Vec<SaleLot>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT remaining_shares, original_shares, gross_amount
+             FROM pp_fifo_lot
+             WHERE portfolio_id = ?1 AND security_id = ?2 AND remaining_shares > 0 AND original_shares > 0
+             ORDER BY purchase_date, id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let lots = stmt
+        .query_map([portfolio_id, security_id], |row| {
+            let remaining: i64 = row.get(0)?;
+            let original: i64 = row.get(1)?;
+            let gross: i64 = row.get(2)?;
+            Ok(SaleLot {
+                shares: shares::to_decimal(remaining),
+                cost_per_share: gross as f64 / 100.0 / shares::to_decimal(original),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(lots)
+}
+
+/// Estimate the tax of a set of sales
+///
+/// Stock losses only offset stock gains, other losses offset everything;
+/// the remaining Sparer-Pauschbetrag is applied last.
+fn estimate_sale_tax(
+    sales: &[SaleGain],
+    freistellung_remaining: f64,
+    kirchensteuer_rate: Option<f64>,
+) -> RebalanceTaxEstimate {
+    let aktien: f64 = sales.iter().filter(|s| s.is_stock).map(|s| s.taxable).sum();
+    let sonstige: f64 = sales.iter().filter(|s| !s.is_stock).map(|s| s.taxable).sum();
+
+    let before_freistellung = (aktien.max(0.0) + sonstige).max(0.0);
+    let freistellung_used = before_freistellung.min(freistellung_remaining);
+    let taxable_gain = before_freistellung - freistellung_used;
+    let (abgeltungssteuer, soli, kirchensteuer) =
+        crate::tax::calculate_abgeltungssteuer(taxable_gain, kirchensteuer_rate);
+
+    RebalanceTaxEstimate {
+        realized_gain: sales.iter().map(|s| s.gain).sum(),
+        taxable_gain,
+        freistellung_used,
+        estimated_tax: abgeltungssteuer + soli + kirchensteuer,
+    }
+}
+
+/// Plan buys from new cash only (no sells)
+///
+/// Water-filling over the underweights: the largest deficit is reduced until
+/// it matches the next one, then both are reduced together, and so on until
+/// the cash is used up.
+fn plan_buy_only_trades(positions: &[RebalancePosition], cash: f64) -> Vec<f64> {
+    let deficits: Vec<f64> = positions
+        .iter()
+        .map(|p| (p.target_value - p.current_value).max(0.0))
+        .collect();
+    let total_deficit: f64 = deficits.iter().sum();
+    if total_deficit <= cash {
+        return deficits;
+    }
+
+    // Find the remaining deficit level all underweights are reduced to
+    let mut sorted: Vec<f64> = deficits.iter().copied().filter(|d| *d > 0.0).collect();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut level = 0.0;
+    let mut covered = 0.0;
+    for (k, deficit) in sorted.iter().enumerate() {
+        let next = sorted.get(k + 1).copied().unwrap_or(0.0);
+        let cost = (deficit - next) * (k + 1) as f64;
+        if covered + cost >= cash {
+            level = deficit - (cash - covered) / (k + 1) as f64;
+            break;
+        }
+        covered += cost;
+    }
+
+    deficits.iter().map(|d| (d - level).max(0.0)).collect()
+}
+
+/// Plan tax-aware trades (positive = buy, negative = sell amount)
+fn plan_tax_aware_trades(
+    positions: &[RebalancePosition],
+    new_cash: f64,
+    band: f64,
+    freistellung_remaining: f64,
+) -> Vec<f64> {
+    let mut trades = vec![0.0; positions.len()];
+    let mut sales: Vec<SaleGain> = Vec::new();
+
+    let surplus = |p: &RebalancePosition| (p.current_value - p.target_value).max(0.0);
+    let mut winners: Vec<(usize, f64)> = Vec::new();
+
+    // Losses and gain-free sells first: always sold down to target
+    for (i, position) in positions.iter().enumerate() {
+        let amount = surplus(position);
+        if amount <= 1.0 {
+            continue;
+        }
+        let sale = position.sale_gain_for_value(amount);
+        if sale.taxable <= 0.0 {
+            trades[i] = -amount;
+            sales.push(sale);
+        } else {
+            winners.push((i, sale.taxable / amount));
+        }
+    }
+
+    // Winners with the lowest gain per sold euro first, fully while tax-free
+    winners.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    for (i, _) in winners {
+        let position = &positions[i];
+        let full = surplus(position);
+        let full_sale = position.sale_gain_for_value(full);
+
+        let mut trial = sales.clone();
+        trial.push(full_sale.clone());
+        let amount = if estimate_sale_tax(&trial, freistellung_remaining, None).taxable_gain <= 0.0 {
+            full
+        } else {
+            (full - band).max(0.0)
+        };
+
+        if amount > 0.0 {
+            trades[i] = -amount;
+            sales.push(position.sale_gain_for_value(amount));
+        }
+    }
+
+    // Fund the buys from new cash and the sells
+    let funds = new_cash + trades.iter().map(|t| -t).sum::<f64>();
+    let deficits: Vec<f64> = positions
+        .iter()
+        .map(|p| (p.target_value - p.current_value).max(0.0))
+        .collect();
+    let total_deficit: f64 = deficits.iter().sum();
+    let scale = if total_deficit > 0.0 { (funds / total_deficit).min(1.0) } else { 0.0 };
+
+    for (trade, deficit) in trades.iter_mut().zip(deficits) {
+        if deficit > 0.0 {
+            *trade = deficit * scale;
+        }
+    }
+
+    trades
+}
+
 /// Execute rebalancing by creating transactions
 #[command]
 pub fn execute_rebalance(
@@ -326,7 +632,14 @@ pub fn execute_rebalance(
     account_id: i64,
     actions: Vec<RebalanceAction>,
     date: Option<String>,
+    buy_only: Option<bool>,
 ) -> Result<RebalanceResult, String> {
+    if buy_only.unwrap_or(false) {
+        if let Some(sell) = actions.iter().find(|a| a.action != "BUY") {
+            return Err(format!("Buy-only rebalancing cannot {} {}", sell.action, sell.security_name));
+        }
+    }
+
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
@@ -334,6 +647,9 @@ pub fn execute_rebalance(
 
     let exec_date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
 
+    // All trades and the audit entry are committed together
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
     // Get import_id
     let import_id: i64 = conn.query_row(
         "SELECT import_id FROM pp_portfolio WHERE id = ?1",
@@ -425,6 +741,18 @@ pub fn execute_rebalance(
         log::info!("Rebalancing: Rebuilt FIFO lots for {} securities", affected_security_ids.len());
     }
 
+    record_audit(
+        conn,
+        "execute_rebalance",
+        "portfolio",
+        Some(portfolio_id),
+        &format!(
+            "Rebalancing zum {}: {} Buchungen, {:.2} gekauft, {:.2} verkauft",
+            exec_date, transactions_created, total_bought, total_sold
+        ),
+    );
+    tx.commit().map_err(|e| e.to_string())?;
+
     // Emit data changed event for frontend refresh
     emit_data_changed(
         &app,
@@ -445,7 +773,7 @@ pub fn calculate_deviation(
     portfolio_id: i64,
     targets: Vec<RebalanceTarget>,
 ) -> Result<f64, String> {
-    let preview = preview_rebalance(portfolio_id, targets, None)?;
+    let preview = preview_rebalance(portfolio_id, targets, None, None, None, None)?;
     Ok(preview.deviation_before)
 }
 
@@ -766,3 +1094,97 @@ pub async fn suggest_rebalance_with_ai(
     // Parse the AI response
     parse_rebalance_response(&response_text, &holdings)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(current: f64, target: f64, price: f64, lots: &[(f64, f64)]) -> RebalancePosition {
+        RebalancePosition {
+            current_value: current,
+            target_value: target,
+            price,
+            lots: lots
+                .iter()
+                .map(|(shares, cost)| SaleLot { shares: *shares, cost_per_share: *cost })
+                .collect(),
+            teilfreistellung: 0.0,
+            is_stock: true,
+        }
+    }
+
+    #[test]
+    fn test_sale_gain_consumes_oldest_lots() {
+        // 10 @ 50 then 10 @ 150, price 100
+        let p = position(2000.0, 1000.0, 100.0, &[(10.0, 50.0), (10.0, 150.0)]);
+        assert_eq!(p.sale_gain(10.0).gain, 500.0);
+        assert_eq!(p.sale_gain(15.0).gain, 250.0);
+
+        let mut fund = position(2000.0, 1000.0, 100.0, &[(10.0, 50.0)]);
+        fund.teilfreistellung = 0.30;
+        assert!((fund.sale_gain(10.0).taxable - 350.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_sale_tax() {
+        let sale = |gain: f64, is_stock: bool| SaleGain { gain, taxable: gain, is_stock };
+
+        // Stock losses do not offset fund gains
+        let estimate = estimate_sale_tax(&[sale(-500.0, true), sale(1500.0, false)], 1000.0, None);
+        assert_eq!(estimate.realized_gain, 1000.0);
+        assert_eq!(estimate.freistellung_used, 1000.0);
+        assert_eq!(estimate.taxable_gain, 500.0);
+        assert!((estimate.estimated_tax - 500.0 * 0.25 * 1.055).abs() < 1e-9);
+
+        // Other losses offset stock gains
+        let estimate = estimate_sale_tax(&[sale(800.0, true), sale(-300.0, false)], 0.0, None);
+        assert_eq!(estimate.taxable_gain, 500.0);
+    }
+
+    #[test]
+    fn test_tax_aware_prefers_losses_and_freistellung() {
+        let positions = vec![
+            // Loser: 500 over target, bought at 200 -> sold fully
+            position(1500.0, 1000.0, 100.0, &[(15.0, 200.0)]),
+            // Small winner: 500 over target, gain 250 fits the Pauschbetrag
+            position(1500.0, 1000.0, 100.0, &[(15.0, 50.0)]),
+            // Big winner: 1000 over target, gain 900 -> keeps the band
+            position(2000.0, 1000.0, 100.0, &[(20.0, 10.0)]),
+            // Underweight: 2000 to buy
+            position(0.0, 2000.0, 100.0, &[]),
+        ];
+
+        let trades = plan_tax_aware_trades(&positions, 0.0, 200.0, 300.0);
+        assert_eq!(trades[0], -500.0);
+        assert_eq!(trades[1], -500.0);
+        assert_eq!(trades[2], -800.0);
+        // Only 1800 of 2000 funded
+        assert!((trades[3] - 1800.0).abs() < 1e-9);
+
+        // New cash tops up the buys
+        let trades = plan_tax_aware_trades(&positions, 200.0, 200.0, 300.0);
+        assert!((trades[3] - 2000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buy_only_fills_largest_underweight_first() {
+        let positions = vec![
+            position(1000.0, 1600.0, 100.0, &[]), // 600 under
+            position(1000.0, 1200.0, 100.0, &[]), // 200 under
+            position(2000.0, 1200.0, 100.0, &[]), // overweight, never sold
+        ];
+
+        // 500: first 400 reduce the largest gap to 200, then 50 each
+        let trades = plan_buy_only_trades(&positions, 500.0);
+        assert!((trades[0] - 450.0).abs() < 1e-9);
+        assert!((trades[1] - 50.0).abs() < 1e-9);
+        assert_eq!(trades[2], 0.0);
+
+        // Enough cash: buy all deficits, leftover stays cash
+        let trades = plan_buy_only_trades(&positions, 1000.0);
+        assert_eq!(trades, vec![600.0, 200.0, 0.0]);
+
+        let trades = plan_buy_only_trades(&positions, 300.0);
+        assert_eq!(trades, vec![300.0, 0.0, 0.0]);
+    }
+}
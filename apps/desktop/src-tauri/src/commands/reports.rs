@@ -4,9 +4,12 @@
 //! - Dividend Report: All dividends received in a period
 //! - Tax Report: Taxable events (dividends, realized gains)
 //! - Realized Gains Report: Gains/losses from sales
+//! - Cash Flow Report: Deposits, removals, income, fees and taxes per month/year
+//! - FIRE Projection: Date the net worth reaches 25× the annual spend
 
 use crate::db;
 use crate::pp::common::{prices, shares};
+use crate::tax::withholding;
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
@@ -34,6 +37,16 @@ pub struct DividendEntry {
     pub shares: Option<f64>,
     /// Dividend per share (if calculable)
     pub per_share: Option<f64>,
+    /// Country used for the withholding tax (domicile or ISIN prefix)
+    pub country: Option<String>,
+    /// Expected foreign withholding tax rate
+    pub withholding_rate: f64,
+    /// Expected foreign withholding tax on the gross amount
+    pub expected_withholding: f64,
+    /// Expected net dividend after source withholding
+    pub expected_net: f64,
+    /// Part of the withholding that can be reclaimed abroad
+    pub reclaimable: f64,
 }
 
 /// Dividend report summary
@@ -46,11 +59,17 @@ pub struct DividendReport {
     pub total_taxes: f64,
     pub total_net: f64,
     pub currency: String,
+    /// Expected foreign withholding tax (source tax per country)
+    pub total_expected_withholding: f64,
+    /// Reclaimable foreign withholding tax
+    pub total_reclaimable: f64,
     pub entries: Vec<DividendEntry>,
     /// Grouped by security
     pub by_security: Vec<DividendBySecurity>,
     /// Grouped by month
     pub by_month: Vec<DividendByMonth>,
+    /// Grouped by country (withholding tax / reclaim)
+    pub by_country: Vec<DividendByCountry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +81,7 @@ pub struct DividendBySecurity {
     pub total_gross: f64,
     pub total_taxes: f64,
     pub total_net: f64,
+    pub total_reclaimable: f64,
     pub payment_count: i32,
 }
 
@@ -74,6 +94,17 @@ pub struct DividendByMonth {
     pub total_net: f64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DividendByCountry {
+    /// ISO country code (None = unknown, default rate applied)
+    pub country: Option<String>,
+    pub withholding_rate: f64,
+    pub total_gross: f64,
+    pub expected_withholding: f64,
+    pub reclaimable: f64,
+}
+
 /// Realized gain/loss from a sale
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -102,6 +133,10 @@ pub struct RealizedGain {
     pub fees: f64,
     /// Taxes paid on sale
     pub taxes: f64,
+    /// Currency-driven part of the gain in base currency (foreign-currency sales only)
+    pub fx_gain: Option<f64>,
+    /// Price-driven part of the gain in base currency (foreign-currency sales only)
+    pub price_gain: Option<f64>,
 }
 
 /// Realized gains report summary
@@ -115,14 +150,38 @@ pub struct RealizedGainsReport {
     pub total_gain: f64,
     pub total_fees: f64,
     pub total_taxes: f64,
+    /// Sum of `fx_gain` over all foreign-currency sales (base currency)
+    pub total_fx_gain: f64,
     pub currency: String,
     /// All individual gains
     pub entries: Vec<RealizedGain>,
     /// Gains grouped by security
     pub by_security: Vec<GainBySecurity>,
-    /// Short-term vs long-term summary
+    /// Short-term vs long-term summary (per FIFO lot, see `by_holding_period`)
     pub short_term_gain: f64,
     pub long_term_gain: f64,
+    /// Gains split by holding period of the consumed FIFO lots
+    pub by_holding_period: Vec<GainByHoldingPeriod>,
+}
+
+/// Realized gains of all lots held up to / more than one year
+///
+/// A lot counts as long-term if it was sold after the one-year anniversary of
+/// its purchase (German Spekulationsfrist, § 23 EStG). Proceeds and fees of a
+/// sale are split across its lots by shares.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GainByHoldingPeriod {
+    /// "short_term" or "long_term"
+    pub bucket: String,
+    pub label: String,
+    pub shares: f64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub gain: f64,
+    /// Number of consumed lots
+    pub lot_count: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,7 +259,8 @@ pub fn generate_dividend_report(
                 t.amount as gross_amount,
                 t.currency,
                 COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0) as taxes,
-                t.shares
+                t.shares,
+                s.domicile
             FROM pp_txn t
             LEFT JOIN pp_security s ON s.id = t.security_id
             WHERE t.txn_type = 'DIVIDENDS'
@@ -229,7 +289,8 @@ pub fn generate_dividend_report(
                 t.amount as gross_amount,
                 t.currency,
                 COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0) as taxes,
-                t.shares
+                t.shares,
+                s.domicile
             FROM pp_txn t
             LEFT JOIN pp_security s ON s.id = t.security_id
             WHERE t.txn_type = 'DIVIDENDS'
@@ -257,6 +318,7 @@ pub fn generate_dividend_report(
                 row.get::<_, String>(6)?,
                 row.get::<_, i64>(7)?,
                 row.get::<_, Option<i64>>(8)?,
+                row.get::<_, Option<String>>(9)?,
             ))
         })
         .map_err(|e| e.to_string())?;
@@ -266,7 +328,7 @@ pub fn generate_dividend_report(
     let mut total_taxes = 0.0;
 
     for row in rows.flatten() {
-        let (date, security_id, security_name, isin, portfolio_name, gross_raw, currency, taxes_raw, shares_raw) = row;
+        let (date, security_id, security_name, isin, portfolio_name, gross_raw, currency, taxes_raw, shares_raw, domicile) = row;
 
         let gross = gross_raw as f64 / 100.0;
         let taxes = taxes_raw as f64 / 100.0;
@@ -274,6 +336,11 @@ pub fn generate_dividend_report(
         let shares_val = shares_raw.map(|s| shares::to_decimal(s));
         let per_share = shares_val.filter(|s| *s > 0.0).map(|s| gross / s);
 
+        // Expected foreign source tax by issuer country
+        let country = withholding::security_country(domicile.as_deref(), isin.as_deref());
+        let wht = withholding::withholding_rate_for(country.as_deref());
+        let expected_withholding = gross * wht.rate;
+
         total_gross += gross;
         total_taxes += taxes;
 
@@ -289,6 +356,11 @@ pub fn generate_dividend_report(
             net_amount: net,
             shares: shares_val,
             per_share,
+            country: if wht.is_known { country } else { None },
+            withholding_rate: wht.rate,
+            expected_withholding,
+            expected_net: gross - expected_withholding,
+            reclaimable: gross * wht.reclaimable_rate,
         });
     }
 
@@ -302,11 +374,13 @@ pub fn generate_dividend_report(
             total_gross: 0.0,
             total_taxes: 0.0,
             total_net: 0.0,
+            total_reclaimable: 0.0,
             payment_count: 0,
         });
         sec.total_gross += entry.gross_amount;
         sec.total_taxes += entry.taxes;
         sec.total_net += entry.net_amount;
+        sec.total_reclaimable += entry.reclaimable;
         sec.payment_count += 1;
     }
     let mut by_security: Vec<DividendBySecurity> = by_security_map.into_values().collect();
@@ -329,6 +403,26 @@ pub fn generate_dividend_report(
     let mut by_month: Vec<DividendByMonth> = by_month_map.into_values().collect();
     by_month.sort_by(|a, b| a.month.cmp(&b.month));
 
+    // Group by country
+    let mut by_country_map: std::collections::HashMap<Option<String>, DividendByCountry> = std::collections::HashMap::new();
+    for entry in &entries {
+        let c = by_country_map.entry(entry.country.clone()).or_insert(DividendByCountry {
+            country: entry.country.clone(),
+            withholding_rate: entry.withholding_rate,
+            total_gross: 0.0,
+            expected_withholding: 0.0,
+            reclaimable: 0.0,
+        });
+        c.total_gross += entry.gross_amount;
+        c.expected_withholding += entry.expected_withholding;
+        c.reclaimable += entry.reclaimable;
+    }
+    let mut by_country: Vec<DividendByCountry> = by_country_map.into_values().collect();
+    by_country.sort_by(|a, b| b.reclaimable.partial_cmp(&a.reclaimable).unwrap_or(std::cmp::Ordering::Equal));
+
+    let total_expected_withholding = entries.iter().map(|e| e.expected_withholding).sum();
+    let total_reclaimable = entries.iter().map(|e| e.reclaimable).sum();
+
     Ok(DividendReport {
         start_date,
         end_date,
@@ -336,9 +430,12 @@ pub fn generate_dividend_report(
         total_taxes,
         total_net: total_gross - total_taxes,
         currency: base_currency,
+        total_expected_withholding,
+        total_reclaimable,
         entries,
         by_security,
         by_month,
+        by_country,
     })
 }
 
@@ -354,6 +451,22 @@ pub fn generate_realized_gains_report(
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    build_realized_gains_report(conn, start_date, end_date, portfolio_id)
+}
+
+/// Whether a lot sold at `sale_date` was held more than one year
+fn is_long_term_holding(purchase_date: chrono::NaiveDate, sale_date: chrono::NaiveDate) -> bool {
+    purchase_date
+        .checked_add_months(chrono::Months::new(12))
+        .is_some_and(|anniversary| sale_date > anniversary)
+}
+
+fn build_realized_gains_report(
+    conn: &rusqlite::Connection,
+    start_date: String,
+    end_date: String,
+    portfolio_id: Option<i64>,
+) -> Result<RealizedGainsReport, String> {
     let base_currency: String = conn
         .query_row(
             "SELECT base_currency FROM pp_import ORDER BY id DESC LIMIT 1",
@@ -448,8 +561,27 @@ pub fn generate_realized_gains_report(
     let mut total_cost_basis = 0.0;
     let mut total_fees = 0.0;
     let mut total_taxes = 0.0;
-    let mut short_term_gain = 0.0;
-    let mut long_term_gain = 0.0;
+    let mut total_fx_gain = 0.0;
+    let mut short_term = GainByHoldingPeriod {
+        bucket: "short_term".to_string(),
+        label: "Bis 1 Jahr".to_string(),
+        ..Default::default()
+    };
+    let mut long_term = GainByHoldingPeriod {
+        bucket: "long_term".to_string(),
+        label: "Über 1 Jahr".to_string(),
+        ..Default::default()
+    };
+    let mut lot_stmt = conn
+        .prepare(
+            r#"
+            SELECT date(fl.purchase_date), fc.shares_consumed, fc.gross_amount
+            FROM pp_fifo_consumption fc
+            JOIN pp_fifo_lot fl ON fl.id = fc.lot_id
+            WHERE fc.sale_txn_id = ?1
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
 
     for row in rows.flatten() {
         let (txn_id, date, security_id, security_name, isin, portfolio_name, shares_raw, proceeds_raw, currency, fees_raw, taxes_raw) = row;
@@ -461,20 +593,30 @@ pub fn generate_realized_gains_report(
 
         // Get FIFO cost basis for this sale from consumption records
         // Use gross_amount from consumption which is the proportional cost basis (INCLUDING fees/taxes)
-        let (cost_basis, avg_holding_days): (f64, i32) = conn
+        // FX/price split is stored per consumption by the FIFO builder (NULL for base currency)
+        let (cost_basis, avg_holding_days, fx_gain, price_gain): (f64, i32, Option<f64>, Option<f64>) = conn
             .query_row(
                 r#"
                 SELECT
                     COALESCE(SUM(fc.gross_amount) / 100.0, 0),
-                    COALESCE(AVG(julianday(?) - julianday(fl.purchase_date)), 0)
+                    COALESCE(AVG(julianday(?) - julianday(fl.purchase_date)), 0),
+                    SUM(fc.fx_gain) / 100.0,
+                    SUM(fc.price_gain) / 100.0
                 FROM pp_fifo_consumption fc
                 JOIN pp_fifo_lot fl ON fl.id = fc.lot_id
                 WHERE fc.sale_txn_id = ?
                 "#,
                 rusqlite::params![date, txn_id],
-                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, f64>(1)? as i32)),
+                |row| {
+                    Ok((
+                        row.get::<_, f64>(0)?,
+                        row.get::<_, f64>(1)? as i32,
+                        row.get(2)?,
+                        row.get(3)?,
+                    ))
+                },
             )
-            .unwrap_or((0.0, 0));
+            .unwrap_or((0.0, 0, None, None));
 
         let gain = proceeds - cost_basis - fees;
         let gain_percent = if cost_basis > 0.0 { (gain / cost_basis) * 100.0 } else { 0.0 };
@@ -484,11 +626,37 @@ pub fn generate_realized_gains_report(
         total_cost_basis += cost_basis;
         total_fees += fees;
         total_taxes += taxes;
-
-        if is_long_term {
-            long_term_gain += gain;
+        total_fx_gain += fx_gain.unwrap_or(0.0);
+
+        // Split the sale across its lots; without FIFO data it counts as short-term
+        let sale_date = chrono::NaiveDate::parse_from_str(date.get(..10).unwrap_or(&date), "%Y-%m-%d").ok();
+        let lots: Vec<(String, i64, i64)> = lot_stmt
+            .query_map([txn_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        let lots = if lots.is_empty() {
+            vec![(date.clone(), shares_raw, 0)]
         } else {
-            short_term_gain += gain;
+            lots
+        };
+        for (purchase_date, shares_consumed, lot_cost_raw) in lots {
+            let share = if shares_raw > 0 { shares_consumed as f64 / shares_raw as f64 } else { 1.0 };
+            let lot_long_term = match (
+                chrono::NaiveDate::parse_from_str(&purchase_date, "%Y-%m-%d").ok(),
+                sale_date,
+            ) {
+                (Some(purchase), Some(sale)) => is_long_term_holding(purchase, sale),
+                _ => false,
+            };
+            let bucket = if lot_long_term { &mut long_term } else { &mut short_term };
+            let lot_cost = lot_cost_raw as f64 / 100.0;
+            bucket.shares += shares::to_decimal(shares_consumed);
+            bucket.proceeds += proceeds * share;
+            bucket.cost_basis += lot_cost;
+            bucket.fees += fees * share;
+            bucket.gain += (proceeds - fees) * share - lot_cost;
+            bucket.lot_count += 1;
         }
 
         entries.push(RealizedGain {
@@ -507,6 +675,8 @@ pub fn generate_realized_gains_report(
             currency,
             fees,
             taxes,
+            fx_gain,
+            price_gain,
         });
     }
 
@@ -538,11 +708,13 @@ pub fn generate_realized_gains_report(
         total_gain: total_proceeds - total_cost_basis - total_fees,
         total_fees,
         total_taxes,
+        total_fx_gain,
         currency: base_currency,
         entries,
         by_security,
-        short_term_gain,
-        long_term_gain,
+        short_term_gain: short_term.gain,
+        long_term_gain: long_term.gain,
+        by_holding_period: vec![short_term, long_term],
     })
 }
 
@@ -637,6 +809,284 @@ pub fn get_dividend_yield(security_id: i64) -> Result<f64, String> {
     Ok(dividend_yield)
 }
 
+// ============================================================================
+// Cash Flow Report
+// ============================================================================
+
+/// Cash flows of one period (or the whole range), in base currency
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CashFlowPeriod {
+    /// YYYY-MM or YYYY, depending on the grouping
+    pub period: String,
+    pub deposits: f64,
+    pub removals: f64,
+    pub dividends: f64,
+    /// Interest received minus interest charged
+    pub interest: f64,
+    /// Fees minus fee refunds
+    pub fees: f64,
+    /// Taxes minus tax refunds
+    pub taxes: f64,
+    /// Deposits minus removals
+    pub net_contributions: f64,
+    /// Dividends + interest - fees - taxes
+    pub net_income: f64,
+}
+
+/// Cash flow statement across all accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CashFlowReport {
+    pub start_date: String,
+    pub end_date: String,
+    /// "month" or "year"
+    pub grouping: String,
+    pub base_currency: String,
+    pub periods: Vec<CashFlowPeriod>,
+    pub totals: CashFlowPeriod,
+}
+
+impl CashFlowPeriod {
+    fn add(&mut self, txn_type: &str, amount: f64) {
+        match txn_type {
+            "DEPOSIT" => self.deposits += amount,
+            "REMOVAL" => self.removals += amount,
+            "DIVIDENDS" => self.dividends += amount,
+            "INTEREST" => self.interest += amount,
+            "INTEREST_CHARGE" => self.interest -= amount,
+            "FEES" => self.fees += amount,
+            "FEES_REFUND" => self.fees -= amount,
+            "TAXES" => self.taxes += amount,
+            "TAX_REFUND" => self.taxes -= amount,
+            _ => return,
+        }
+        self.net_contributions = self.deposits - self.removals;
+        self.net_income = self.dividends + self.interest - self.fees - self.taxes;
+    }
+}
+
+/// Generate a cash flow statement: deposits, removals, dividends, interest,
+/// fees and taxes of all accounts per month or year, converted to base currency
+#[command]
+pub fn generate_cash_flow_report(
+    start_date: String,
+    end_date: String,
+    grouping: Option<String>,
+) -> Result<CashFlowReport, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    build_cash_flow_report(conn, &start_date, &end_date, grouping.as_deref().unwrap_or("month"))
+}
+
+fn build_cash_flow_report(
+    conn: &rusqlite::Connection,
+    start_date: &str,
+    end_date: &str,
+    grouping: &str,
+) -> Result<CashFlowReport, String> {
+    let period_len = match grouping {
+        "month" => 7,
+        "year" => 4,
+        _ => return Err(format!("Ungültige Gruppierung: {} (erlaubt: month, year)", grouping)),
+    };
+
+    let base_currency =
+        crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT date(t.date), t.txn_type, t.amount, t.currency
+            FROM pp_txn t
+            WHERE t.owner_type = 'account'
+              AND t.txn_type IN ('DEPOSIT', 'REMOVAL', 'DIVIDENDS', 'INTEREST', 'INTEREST_CHARGE',
+                                 'FEES', 'FEES_REFUND', 'TAXES', 'TAX_REFUND')
+              AND date(t.date) >= date(?1) AND date(t.date) <= date(?2)
+            ORDER BY t.date
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([start_date, end_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut periods: std::collections::BTreeMap<String, CashFlowPeriod> =
+        std::collections::BTreeMap::new();
+    let mut totals = CashFlowPeriod {
+        period: "total".to_string(),
+        ..Default::default()
+    };
+
+    for (date, txn_type, amount_raw, currency) in rows {
+        let amount = amount_raw as f64 / 100.0;
+        let amount = match chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            Ok(d) => crate::currency::convert(conn, amount, &currency, &base_currency, d)
+                .unwrap_or(amount),
+            Err(_) => amount,
+        };
+
+        let key = date.get(..period_len).unwrap_or(&date).to_string();
+        periods
+            .entry(key.clone())
+            .or_insert_with(|| CashFlowPeriod {
+                period: key,
+                ..Default::default()
+            })
+            .add(&txn_type, amount);
+        totals.add(&txn_type, amount);
+    }
+
+    Ok(CashFlowReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        grouping: grouping.to_string(),
+        base_currency,
+        periods: periods.into_values().collect(),
+        totals,
+    })
+}
+
+// ============================================================================
+// FIRE Projection
+// ============================================================================
+
+/// Target net worth as a multiple of the annual spend (4% rule)
+const FIRE_SPEND_MULTIPLE: f64 = 25.0;
+
+/// Projection stops after this many months (100 years)
+const FIRE_MAX_MONTHS: u32 = 1200;
+
+/// Projected date of financial independence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FireProjection {
+    pub base_currency: String,
+    pub annual_spend: f64,
+    /// Expected annual return as decimal (0.05 = 5%)
+    pub expected_return: f64,
+    /// 25 × annual spend
+    pub target_value: f64,
+    pub current_net_worth: f64,
+    /// Average monthly deposits minus removals since the first deposit/removal
+    pub monthly_contribution: f64,
+    /// Date of the first deposit/removal the average is based on
+    pub history_start: Option<String>,
+    /// None if the target is not reached within 100 years
+    pub months_to_target: Option<u32>,
+    pub projected_date: Option<String>,
+    /// Net worth at the projected date (or after 100 years)
+    pub projected_value: f64,
+    /// Contributions added until then
+    pub projected_contributions: f64,
+    /// Returns earned until then
+    pub projected_returns: f64,
+}
+
+/// Estimate when the net worth reaches 25× the annual spend
+///
+/// Starts from the current net worth (all portfolios and accounts), adds the
+/// historical average monthly net contribution and compounds monthly with
+/// `expected_return` (annual, as decimal).
+#[command]
+pub fn calculate_fire_projection(
+    annual_spend: f64,
+    expected_return: f64,
+) -> Result<FireProjection, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    build_fire_projection(conn, chrono::Utc::now().date_naive(), annual_spend, expected_return)
+}
+
+fn build_fire_projection(
+    conn: &rusqlite::Connection,
+    today: chrono::NaiveDate,
+    annual_spend: f64,
+    expected_return: f64,
+) -> Result<FireProjection, String> {
+    if !annual_spend.is_finite() || annual_spend <= 0.0 {
+        return Err("Jährliche Ausgaben müssen größer als 0 sein".to_string());
+    }
+    if !expected_return.is_finite() || expected_return <= -1.0 || expected_return >= 1.0 {
+        return Err(format!(
+            "Ungültige erwartete Rendite: {} (Dezimalwert, z.B. 0.05 für 5%)",
+            expected_return
+        ));
+    }
+
+    let base_currency =
+        crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let current_net_worth = crate::performance::calculate_net_worth(conn, today, &base_currency)
+        .map_err(|e| e.to_string())?
+        .total;
+
+    let history_start: Option<String> = conn
+        .query_row(
+            "SELECT MIN(date(date)) FROM pp_txn WHERE owner_type = 'account' AND txn_type IN ('DEPOSIT', 'REMOVAL')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let monthly_contribution = match &history_start {
+        Some(start) => {
+            let cash_flows = build_cash_flow_report(conn, start, &today.to_string(), "year")?;
+            let months = chrono::NaiveDate::parse_from_str(start, "%Y-%m-%d")
+                .map(|d| (today - d).num_days() as f64 / 30.4375)
+                .unwrap_or(1.0)
+                .max(1.0);
+            cash_flows.totals.net_contributions / months
+        }
+        None => 0.0,
+    };
+
+    let target_value = annual_spend * FIRE_SPEND_MULTIPLE;
+    let monthly_return = (1.0 + expected_return).powf(1.0 / 12.0) - 1.0;
+
+    let mut value = current_net_worth;
+    let mut contributions = 0.0;
+    let mut months = 0;
+    while value < target_value && months < FIRE_MAX_MONTHS {
+        value = value * (1.0 + monthly_return) + monthly_contribution;
+        contributions += monthly_contribution;
+        months += 1;
+    }
+
+    let months_to_target = (value >= target_value).then_some(months);
+    let projected_date = months_to_target
+        .and_then(|m| today.checked_add_months(chrono::Months::new(m)))
+        .map(|d| d.to_string());
+
+    Ok(FireProjection {
+        base_currency,
+        annual_spend,
+        expected_return,
+        target_value,
+        current_net_worth,
+        monthly_contribution,
+        history_start,
+        months_to_target,
+        projected_date,
+        projected_value: value,
+        projected_contributions: contributions,
+        projected_returns: value - current_net_worth - contributions,
+    })
+}
+
 // ============================================================================
 // Monthly & Yearly Returns (Heatmap / Year Returns Widget)
 // ============================================================================
@@ -832,3 +1282,169 @@ pub fn get_yearly_returns(_portfolio_id: Option<i64>) -> Result<Vec<YearlyReturn
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_cash_flow_report() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR'), (2, 'a2', 'USD-Konto', 'USD');
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate) VALUES ('EUR', 'USD', '2024-01-01', '2.0');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency)
+             VALUES ('t1', 'account', 1, 'DEPOSIT', '2024-01-05', 100000, 'EUR'),
+                    ('t2', 'account', 1, 'FEES', '2024-01-20', 500, 'EUR'),
+                    ('t3', 'account', 2, 'DIVIDENDS', '2024-02-10', 2000, 'USD'),
+                    ('t4', 'account', 1, 'REMOVAL', '2024-02-15', 30000, 'EUR'),
+                    ('t5', 'account', 1, 'TAXES', '2024-02-15', 300, 'EUR'),
+                    ('t6', 'account', 1, 'TAX_REFUND', '2024-02-20', 100, 'EUR'),
+                    ('t7', 'account', 1, 'DEPOSIT', '2025-01-05', 100000, 'EUR');",
+        )
+        .unwrap();
+
+        let report = build_cash_flow_report(&conn, "2024-01-01", "2024-12-31", "month").unwrap();
+        assert_eq!(report.periods.len(), 2);
+        assert_eq!(report.periods[0].period, "2024-01");
+        assert!((report.periods[0].deposits - 1000.0).abs() < 1e-9);
+        assert!((report.periods[0].fees - 5.0).abs() < 1e-9);
+
+        // 20 USD dividend at EUR/USD 2.0 = 10 EUR
+        let feb = &report.periods[1];
+        assert!((feb.dividends - 10.0).abs() < 1e-9);
+        assert!((feb.taxes - 2.0).abs() < 1e-9);
+        assert!((feb.net_contributions + 300.0).abs() < 1e-9);
+
+        assert!((report.totals.net_contributions - 700.0).abs() < 1e-9);
+        assert!((report.totals.net_income - 3.0).abs() < 1e-9);
+
+        let yearly = build_cash_flow_report(&conn, "2024-01-01", "2025-12-31", "year").unwrap();
+        let years: Vec<&str> = yearly.periods.iter().map(|p| p.period.as_str()).collect();
+        assert_eq!(years, vec!["2024", "2025"]);
+
+        assert!(build_cash_flow_report(&conn, "2024-01-01", "2024-12-31", "week").is_err());
+    }
+
+    #[test]
+    fn test_realized_gains_by_holding_period() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 'b1', 'portfolio', 1, 'BUY', '2023-01-15', 60000, 'EUR', 1, 600000000),
+                    (2, 'b2', 'portfolio', 1, 'BUY', '2024-01-10', 48000, 'EUR', 1, 400000000),
+                    (3, 's1', 'portfolio', 1, 'SELL', '2024-06-01', 150000, 'EUR', 1, 1000000000);
+             INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency) VALUES (3, 'FEE', 1000, 'EUR');
+             INSERT INTO pp_fifo_lot (id, security_id, portfolio_id, purchase_txn_id, purchase_date, original_shares, remaining_shares, gross_amount, net_amount, currency)
+             VALUES (1, 1, 1, 1, '2023-01-15', 600000000, 0, 60000, 60000, 'EUR'),
+                    (2, 1, 1, 2, '2024-01-10', 400000000, 0, 48000, 48000, 'EUR');
+             INSERT INTO pp_fifo_consumption (lot_id, sale_txn_id, shares_consumed, gross_amount, net_amount)
+             VALUES (1, 3, 600000000, 60000, 60000), (2, 3, 400000000, 48000, 48000);",
+        )
+        .unwrap();
+
+        let report =
+            build_realized_gains_report(&conn, "2024-01-01".into(), "2024-12-31".into(), None)
+                .unwrap();
+        assert!((report.total_gain - 410.0).abs() < 1e-9);
+
+        let short = &report.by_holding_period[0];
+        assert_eq!(short.bucket, "short_term");
+        assert_eq!(short.lot_count, 1);
+        assert!((short.shares - 4.0).abs() < 1e-9);
+        assert!((short.proceeds - 600.0).abs() < 1e-9);
+        assert!((short.gain - 116.0).abs() < 1e-9);
+
+        let long = &report.by_holding_period[1];
+        assert_eq!(long.bucket, "long_term");
+        assert!((long.cost_basis - 600.0).abs() < 1e-9);
+        assert!((long.fees - 6.0).abs() < 1e-9);
+        assert!((long.gain - 294.0).abs() < 1e-9);
+
+        assert!((report.short_term_gain + report.long_term_gain - report.total_gain).abs() < 1e-9);
+
+        // Sold on the anniversary is still within the one-year period
+        let date = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert!(!is_long_term_holding(date("2023-06-01"), date("2024-06-01")));
+        assert!(is_long_term_holding(date("2023-06-01"), date("2024-06-02")));
+    }
+
+    #[test]
+    fn test_realized_gains_fx_component() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_import (file_path, version, base_currency) VALUES ('test', 68, 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'US-Aktie', 'USD'), (2, 's2', 'Aktie', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 'b1', 'portfolio', 1, 'BUY', '2024-01-10', 100000, 'USD', 1, 1000000000),
+                    (2, 's1', 'portfolio', 1, 'SELL', '2024-06-01', 120000, 'USD', 1, 1000000000),
+                    (3, 'b2', 'portfolio', 1, 'BUY', '2024-01-10', 50000, 'EUR', 2, 500000000),
+                    (4, 's2', 'portfolio', 1, 'SELL', '2024-06-01', 60000, 'EUR', 2, 500000000);
+             INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency) VALUES (2, 'FEE', 1000, 'USD');
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES ('EUR', 'USD', '2024-01-10', '1.25'), ('EUR', 'USD', '2024-06-01', '1.0');",
+        )
+        .unwrap();
+        crate::fifo::build_all_fifo_lots(&conn).unwrap();
+
+        let report =
+            build_realized_gains_report(&conn, "2024-01-01".into(), "2024-12-31".into(), None)
+                .unwrap();
+        let usd = report.entries.iter().find(|e| e.security_id == 1).unwrap();
+        // Net proceeds 1190 USD: at 0.80 EUR bought, at 1.00 EUR sold
+        assert!((usd.fx_gain.unwrap() - 238.0).abs() < 0.01);
+        assert!((usd.price_gain.unwrap() - 152.0).abs() < 0.01);
+
+        let eur = report.entries.iter().find(|e| e.security_id == 2).unwrap();
+        assert!(eur.fx_gain.is_none());
+        assert!(eur.price_gain.is_none());
+        assert!((report.total_fx_gain - 238.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_build_fire_projection() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency)
+             VALUES ('t1', 'account', 1, 'DEPOSIT', '2023-01-01', 2500000, 'EUR'),
+                    ('t2', 'account', 1, 'REMOVAL', '2023-06-01', 100000, 'EUR');",
+        )
+        .unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        // 24.000 EUR net over 731 days ≈ 1.000 EUR/month, no return
+        let p = build_fire_projection(&conn, today, 2000.0, 0.0).unwrap();
+        assert!((p.current_net_worth - 24000.0).abs() < 1e-6);
+        assert!((p.target_value - 50000.0).abs() < 1e-9);
+        assert!((p.monthly_contribution - 999.3).abs() < 0.1);
+        assert_eq!(p.history_start.as_deref(), Some("2023-01-01"));
+        assert_eq!(p.months_to_target, Some(27));
+        assert_eq!(p.projected_date.as_deref(), Some("2027-04-01"));
+        assert!(p.projected_returns.abs() < 1e-6);
+
+        // Returns shorten the time to target and show up in the breakdown
+        let with_return = build_fire_projection(&conn, today, 2000.0, 0.07).unwrap();
+        assert!(with_return.months_to_target.unwrap() < 27);
+        assert!(with_return.projected_returns > 0.0);
+        let sum = with_return.current_net_worth
+            + with_return.projected_contributions
+            + with_return.projected_returns;
+        assert!((sum - with_return.projected_value).abs() < 1e-6);
+
+        // Already reached
+        let reached = build_fire_projection(&conn, today, 500.0, 0.05).unwrap();
+        assert_eq!(reached.months_to_target, Some(0));
+        assert_eq!(reached.projected_date.as_deref(), Some("2025-01-01"));
+
+        assert!(build_fire_projection(&conn, today, 0.0, 0.05).is_err());
+        assert!(build_fire_projection(&conn, today, 2000.0, 5.0).is_err());
+    }
+}
@@ -3,9 +3,17 @@
 //! Taxonomies provide hierarchical classification of securities for analysis.
 
 use crate::db;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tauri::command;
 
+/// Full assignment weight in basis points
+const FULL_WEIGHT: i64 = 10000;
+
+/// Name of the allocation entry for the unassigned part of the holdings
+const UNCLASSIFIED_NAME: &str = "Nicht klassifiziert";
+
 // ============================================================================
 // Types
 // ============================================================================
@@ -50,6 +58,9 @@ pub struct ClassificationAssignmentData {
     pub rank: Option<i32>,
 }
 
+/// Allocation of one classification
+///
+/// The unassigned remainder of the holdings is reported with `classification_id` 0.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaxonomyAllocation {
@@ -63,6 +74,38 @@ pub struct TaxonomyAllocation {
     pub percentage: f64,
 }
 
+/// Dimension by which fund positions are decomposed into their constituents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LookThrough {
+    Region,
+    Sector,
+}
+
+impl LookThrough {
+    fn column(self) -> &'static str {
+        match self {
+            LookThrough::Region => "region",
+            LookThrough::Sector => "sector",
+        }
+    }
+}
+
+/// Constituent of a fund (from an imported factsheet)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundHolding {
+    pub id: i64,
+    pub fund_security_id: i64,
+    pub name: String,
+    pub isin: Option<String>,
+    pub region: Option<String>,
+    pub sector: Option<String>,
+    /// Percent of fund assets (0.0 - 100.0)
+    pub weight: f64,
+    pub imported_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTaxonomyRequest {
@@ -640,6 +683,14 @@ pub fn assign_security(data: AssignSecurityRequest) -> Result<ClassificationAssi
         )
         .map_err(|e| e.to_string())?;
 
+    validate_assignment_weight(
+        conn,
+        &security_uuid,
+        &security_name,
+        data.classification_id,
+        data.weight,
+    )?;
+
     // Check if assignment already exists
     let existing: Option<i64> = conn
         .query_row(
@@ -693,6 +744,44 @@ pub fn assign_security(data: AssignSecurityRequest) -> Result<ClassificationAssi
     })
 }
 
+/// Check that the security's weights across the classifications of a taxonomy stay within 100%
+fn validate_assignment_weight(
+    conn: &Connection,
+    security_uuid: &str,
+    security_name: &str,
+    classification_id: i64,
+    weight: i32,
+) -> Result<(), String> {
+    if weight <= 0 || i64::from(weight) > FULL_WEIGHT {
+        return Err("Gewichtung muss zwischen 0,01 % und 100 % liegen".to_string());
+    }
+
+    let assigned_elsewhere: i64 = conn
+        .query_row(
+            r#"
+            SELECT COALESCE(SUM(a.weight), 0)
+            FROM pp_classification_assignment a
+            JOIN pp_classification c ON c.id = a.classification_id
+            WHERE a.vehicle_type = 'security' AND a.vehicle_uuid = ?1
+              AND a.classification_id != ?2
+              AND c.taxonomy_id = (SELECT taxonomy_id FROM pp_classification WHERE id = ?2)
+            "#,
+            rusqlite::params![security_uuid, classification_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if assigned_elsewhere + i64::from(weight) > FULL_WEIGHT {
+        return Err(format!(
+            "Gewichtung zu hoch: {} ist in dieser Taxonomie bereits zu {:.2} % zugeordnet, maximal {:.2} % möglich",
+            security_name,
+            assigned_elsewhere as f64 / 100.0,
+            (FULL_WEIGHT - assigned_elsewhere).max(0) as f64 / 100.0
+        ));
+    }
+
+    Ok(())
+}
+
 /// Remove a security assignment
 #[command]
 pub fn remove_assignment(id: i64) -> Result<(), String> {
@@ -712,13 +801,33 @@ pub fn remove_assignment(id: i64) -> Result<(), String> {
 // ============================================================================
 
 /// Calculate portfolio allocation by taxonomy
+///
+/// Holdings are split across classifications by their assignment weights; the
+/// unassigned part is reported as "Nicht klassifiziert".
+///
+/// With `look_through`, funds with imported constituents are split by the region or
+/// sector of their constituents instead. Values are matched to classifications by name;
+/// regions/sectors without a matching classification are reported with `classification_id` 0.
 #[command]
-pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> Result<Vec<TaxonomyAllocation>, String> {
+pub fn get_taxonomy_allocation(
+    taxonomy_id: i64,
+    portfolio_id: Option<i64>,
+    look_through: Option<LookThrough>,
+) -> Result<Vec<TaxonomyAllocation>, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
         .as_ref()
         .ok_or_else(|| "Database not initialized".to_string())?;
 
+    compute_taxonomy_allocation(conn, taxonomy_id, portfolio_id, look_through)
+}
+
+fn compute_taxonomy_allocation(
+    conn: &Connection,
+    taxonomy_id: i64,
+    portfolio_id: Option<i64>,
+    look_through: Option<LookThrough>,
+) -> Result<Vec<TaxonomyAllocation>, String> {
     // Build holdings query with optional portfolio filter
     let holdings_query = if let Some(pid) = portfolio_id {
         format!(
@@ -755,7 +864,7 @@ pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> R
     };
 
     // Get holdings with values
-    let mut holdings: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    let mut holdings: HashMap<String, i64> = HashMap::new();
     let mut total_value: i64 = 0;
 
     {
@@ -786,6 +895,29 @@ pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> R
         return Ok(Vec::new());
     }
 
+    // Split fund positions into their constituents (lowercase key -> display name, value)
+    let mut look_through_values: HashMap<String, (String, i64)> = HashMap::new();
+    let mut look_through_unclassified: i64 = 0;
+    if let Some(dimension) = look_through {
+        for (uuid, exposures) in load_fund_exposures(conn, dimension)? {
+            let Some(holding_value) = holdings.remove(&uuid) else {
+                continue;
+            };
+            let mut covered = 0.0;
+            for (key, weight) in exposures {
+                let value = (holding_value as f64 * weight / 100.0) as i64;
+                covered += weight;
+                look_through_values
+                    .entry(key.to_lowercase())
+                    .or_insert_with(|| (key, 0))
+                    .1 += value;
+            }
+            // Constituents without region/sector and missing weight (e.g. cash)
+            look_through_unclassified +=
+                (holding_value as f64 * (100.0 - covered).max(0.0) / 100.0) as i64;
+        }
+    }
+
     // Get classifications with paths
     let mut stmt = conn
         .prepare(
@@ -815,6 +947,8 @@ pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> R
 
     // Calculate allocation per classification
     let mut allocations: Vec<TaxonomyAllocation> = Vec::new();
+    // Assigned weight per held security across the taxonomy
+    let mut assigned_weights: HashMap<&str, i64> = HashMap::new();
 
     for (class_id, class_name, color, path) in classifications {
         // Get assignments for this classification
@@ -830,11 +964,15 @@ pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> R
             .filter_map(|r| r.ok())
             .collect();
 
-        let mut class_value: i64 = 0;
+        let mut class_value: i64 = look_through_values
+            .remove(&class_name.to_lowercase())
+            .map(|(_, value)| value)
+            .unwrap_or(0);
         for (uuid, weight) in assignments {
-            if let Some(&holding_value) = holdings.get(&uuid) {
+            if let Some((held_uuid, &holding_value)) = holdings.get_key_value(&uuid) {
                 // Apply weight (10000 = 100%)
-                class_value += (holding_value as f64 * weight as f64 / 10000.0) as i64;
+                class_value += (holding_value as f64 * weight as f64 / FULL_WEIGHT as f64) as i64;
+                *assigned_weights.entry(held_uuid.as_str()).or_insert(0) += i64::from(weight);
             }
         }
 
@@ -853,12 +991,144 @@ pub fn get_taxonomy_allocation(taxonomy_id: i64, portfolio_id: Option<i64>) -> R
         }
     }
 
+    // Constituent regions/sectors without a matching classification
+    for (name, value) in look_through_values.into_values() {
+        if value > 0 {
+            allocations.push(TaxonomyAllocation {
+                classification_id: 0,
+                classification_name: name.clone(),
+                color: None,
+                path: vec![name],
+                value,
+                percentage: (value as f64 / total_value as f64) * 100.0,
+            });
+        }
+    }
+
+    // Unassigned part of each holding (imported data may exceed 100%, which leaves nothing)
+    let unclassified_value: i64 = look_through_unclassified
+        + holdings
+        .iter()
+        .map(|(uuid, &holding_value)| {
+            let assigned = assigned_weights.get(uuid.as_str()).copied().unwrap_or(0);
+            let remainder = (FULL_WEIGHT - assigned).max(0);
+            (holding_value as f64 * remainder as f64 / FULL_WEIGHT as f64) as i64
+        })
+        .sum::<i64>();
+
+    if unclassified_value > 0 {
+        allocations.push(TaxonomyAllocation {
+            classification_id: 0,
+            classification_name: UNCLASSIFIED_NAME.to_string(),
+            color: None,
+            path: vec![UNCLASSIFIED_NAME.to_string()],
+            value: unclassified_value,
+            percentage: (unclassified_value as f64 / total_value as f64) * 100.0,
+        });
+    }
+
     // Sort by value descending
     allocations.sort_by(|a, b| b.value.cmp(&a.value));
 
     Ok(allocations)
 }
 
+/// Weight per region/sector (in percent) of each fund with imported constituents, by security UUID
+///
+/// Constituents without a region/sector are left out, so their weight ends up unclassified.
+fn load_fund_exposures(
+    conn: &Connection,
+    dimension: LookThrough,
+) -> Result<HashMap<String, Vec<(String, f64)>>, String> {
+    let column = dimension.column();
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"
+            SELECT s.uuid, TRIM(COALESCE(f.{column}, '')) as key, SUM(f.weight)
+            FROM pp_fund_holdings f
+            JOIN pp_security s ON s.id = f.fund_security_id
+            GROUP BY s.uuid, key
+            "#
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut exposures: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+    for row in rows {
+        let (uuid, key, weight) = row.map_err(|e| e.to_string())?;
+        let entry = exposures.entry(uuid).or_default();
+        if !key.is_empty() {
+            entry.push((key, weight));
+        }
+    }
+    Ok(exposures)
+}
+
+// ============================================================================
+// Fund Look-Through
+// ============================================================================
+
+/// Get the imported constituents of a fund, largest weight first
+#[command]
+pub fn get_fund_holdings(security_id: i64) -> Result<Vec<FundHolding>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, fund_security_id, name, isin, region, sector, weight, imported_at
+            FROM pp_fund_holdings
+            WHERE fund_security_id = ?
+            ORDER BY weight DESC
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([security_id], |row| {
+            Ok(FundHolding {
+                id: row.get(0)?,
+                fund_security_id: row.get(1)?,
+                name: row.get(2)?,
+                isin: row.get(3)?,
+                region: row.get(4)?,
+                sector: row.get(5)?,
+                weight: row.get(6)?,
+                imported_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Delete the imported constituents of a fund
+#[command]
+pub fn delete_fund_holdings(security_id: i64) -> Result<usize, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    conn.execute(
+        "DELETE FROM pp_fund_holdings WHERE fund_security_id = ?",
+        [security_id],
+    )
+    .map_err(|e| e.to_string())
+}
+
 // ============================================================================
 // Bulk Queries for Grouping
 // ============================================================================
@@ -1038,3 +1308,86 @@ pub fn create_standard_taxonomies() -> Result<Vec<TaxonomyData>, String> {
 
     Ok(created)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_allocation_with_unclassified_remainder() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 'etf', 'World ETF', 'EUR'), (2, 'bond', 'Anleihe', 'EUR');
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-01-02', 10000000000), (2, '2024-01-02', 10000000000);
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 0, 'EUR', 1, 1000000000),
+                    ('t2', 'portfolio', 1, 'BUY', '2024-01-01', 0, 'EUR', 2, 1000000000);
+             INSERT INTO pp_taxonomy (id, uuid, name) VALUES (1, 'tax', 'Regionen');
+             INSERT INTO pp_classification (id, uuid, taxonomy_id, name) VALUES (1, 'us', 1, 'USA'), (2, 'eu', 1, 'Europa');
+             INSERT INTO pp_classification_assignment (classification_id, vehicle_type, vehicle_uuid, weight)
+             VALUES (1, 'security', 'etf', 6000), (2, 'security', 'etf', 4000), (2, 'security', 'bond', 5000);",
+        )
+        .unwrap();
+
+        // ETF 1000 EUR split 60/40, bond 1000 EUR half assigned
+        let allocation = compute_taxonomy_allocation(&conn, 1, None, None).unwrap();
+        let value = |name: &str| {
+            allocation
+                .iter()
+                .find(|a| a.classification_name == name)
+                .map(|a| a.value)
+        };
+        assert_eq!(value("Europa"), Some(90_000));
+        assert_eq!(value("USA"), Some(60_000));
+        assert_eq!(value(UNCLASSIFIED_NAME), Some(50_000));
+        let total: f64 = allocation.iter().map(|a| a.percentage).sum();
+        assert!((total - 100.0).abs() < 1e-9);
+
+        // ETF is fully assigned: raising USA above 60% is rejected, replacing it is fine
+        assert!(validate_assignment_weight(&conn, "etf", "World ETF", 1, 6001).is_err());
+        assert!(validate_assignment_weight(&conn, "etf", "World ETF", 1, 6000).is_ok());
+        assert!(validate_assignment_weight(&conn, "bond", "Anleihe", 1, 5000).is_ok());
+        assert!(validate_assignment_weight(&conn, "bond", "Anleihe", 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_fund_look_through_allocation() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 'etf', 'World ETF', 'EUR');
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-01-02', 10000000000);
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 0, 'EUR', 1, 1000000000);
+             INSERT INTO pp_taxonomy (id, uuid, name) VALUES (1, 'tax', 'Regionen');
+             INSERT INTO pp_classification (id, uuid, taxonomy_id, name) VALUES (1, 'us', 1, 'USA'), (2, 'eu', 1, 'Europa');
+             INSERT INTO pp_classification_assignment (classification_id, vehicle_type, vehicle_uuid, weight)
+             VALUES (1, 'security', 'etf', 10000);
+             INSERT INTO pp_fund_holdings (fund_security_id, name, region, weight)
+             VALUES (1, 'Apple', 'usa', 40.0), (1, 'Microsoft', 'USA', 20.0),
+                    (1, 'Nestle', 'Europa', 25.0), (1, 'Toyota', 'Japan', 10.0), (1, 'Cash', NULL, 5.0);",
+        )
+        .unwrap();
+
+        // Without look-through the ETF counts fully as USA
+        let plain = compute_taxonomy_allocation(&conn, 1, None, None).unwrap();
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].value, 100_000);
+
+        let allocation =
+            compute_taxonomy_allocation(&conn, 1, None, Some(LookThrough::Region)).unwrap();
+        let value = |name: &str| {
+            allocation
+                .iter()
+                .find(|a| a.classification_name == name)
+                .map(|a| (a.classification_id, a.value))
+        };
+        assert_eq!(value("USA"), Some((1, 60_000)));
+        assert_eq!(value("Europa"), Some((2, 25_000)));
+        assert_eq!(value("Japan"), Some((0, 10_000)));
+        assert_eq!(value(UNCLASSIFIED_NAME), Some((0, 5_000)));
+    }
+}
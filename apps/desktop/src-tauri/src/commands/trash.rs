@@ -0,0 +1,465 @@
+//! Trash for deleted transactions
+//!
+//! `delete_transaction` and `delete_transactions_bulk` store a snapshot of the
+//! deleted rows (transaction, linked counterpart, units, cross-entry) in
+//! `pp_txn_trash` before removing them. Every query, FIFO and valuation sees
+//! trashed transactions as absent; `restore_transaction` puts the rows back
+//! with their original IDs and `empty_trash` drops the snapshots for good.
+
+use crate::commands::audit::record_audit;
+use crate::commands::crud::{transactions_changed, AffectedByTransactions};
+use crate::db;
+use crate::events::{emit_data_changed, DataChangedPayload};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{command, AppHandle};
+
+/// One table row by column name (BLOBs as `{"blob": "<base64>"}`)
+type RowSnapshot = serde_json::Map<String, serde_json::Value>;
+
+/// Rows removed together by one delete (transactions linked by a cross-entry)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrashSnapshot {
+    transactions: Vec<RowSnapshot>,
+    units: Vec<RowSnapshot>,
+    cross_entries: Vec<RowSnapshot>,
+}
+
+/// Deleted transaction in the trash
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedTransaction {
+    pub trash_id: i64,
+    pub deleted_at: String,
+    /// First deleted transaction of the entry
+    pub txn_id: i64,
+    pub owner_type: String,
+    pub owner_id: i64,
+    pub txn_type: String,
+    pub date: String,
+    pub amount: f64,
+    pub currency: String,
+    pub security_id: Option<i64>,
+    pub security_name: Option<String>,
+    pub note: Option<String>,
+    /// Linked transactions restored together with this one (e.g. account side of a BUY)
+    pub linked_txn_ids: Vec<i64>,
+}
+
+/// Result of `empty_trash`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmptyTrashResult {
+    pub entries_deleted: usize,
+    pub transactions_deleted: usize,
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(",")
+}
+
+/// Read whole rows of `table` where `column` is one of `ids`
+fn snapshot_rows(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    ids: &[i64],
+) -> Result<Vec<RowSnapshot>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let sql = format!(
+        "SELECT * FROM {} WHERE {} IN ({}) ORDER BY id",
+        table,
+        column,
+        placeholders(ids.len())
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(ids), |row| {
+            let mut snapshot = RowSnapshot::new();
+            for (i, name) in names.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Integer(n) => serde_json::Value::from(n),
+                    ValueRef::Real(f) => serde_json::Value::from(f),
+                    ValueRef::Text(t) => serde_json::Value::from(String::from_utf8_lossy(t).into_owned()),
+                    ValueRef::Blob(b) => serde_json::json!({ "blob": BASE64.encode(b) }),
+                    ValueRef::Null => serde_json::Value::Null,
+                };
+                snapshot.insert(name.clone(), value);
+            }
+            Ok(snapshot)
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// Insert snapshot rows back into `table` (original IDs included)
+fn restore_rows(conn: &rusqlite::Connection, table: &str, rows: &[RowSnapshot]) -> Result<(), String> {
+    for row in rows {
+        let columns: Vec<&str> = row.keys().map(|k| k.as_str()).collect();
+        let values: Vec<Value> = row
+            .values()
+            .map(|v| match v {
+                serde_json::Value::Number(n) => n
+                    .as_i64()
+                    .map(Value::Integer)
+                    .unwrap_or_else(|| Value::Real(n.as_f64().unwrap_or(0.0))),
+                serde_json::Value::String(s) => Value::Text(s.clone()),
+                serde_json::Value::Object(o) => o
+                    .get("blob")
+                    .and_then(|b| b.as_str())
+                    .and_then(|b| BASE64.decode(b).ok())
+                    .map(Value::Blob)
+                    .unwrap_or(Value::Null),
+                _ => Value::Null,
+            })
+            .collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            placeholders(columns.len())
+        );
+        conn.execute(&sql, rusqlite::params_from_iter(values))
+            .map_err(|e| format!("Wiederherstellung fehlgeschlagen ({}): {}", table, e))?;
+    }
+    Ok(())
+}
+
+/// Store snapshots of transactions that are about to be deleted
+///
+/// `txn_ids` must include the linked counterparts; transactions sharing a
+/// cross-entry end up in the same trash entry so they are restored together.
+/// Returns the number of trash entries created.
+pub(crate) fn store_in_trash(conn: &rusqlite::Connection, txn_ids: &[i64]) -> Result<usize, String> {
+    if txn_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let sql = format!(
+        "SELECT id, cross_entry_id FROM pp_txn WHERE id IN ({}) ORDER BY id",
+        placeholders(txn_ids.len())
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows: Vec<(i64, Option<i64>)> = stmt
+        .query_map(rusqlite::params_from_iter(txn_ids), |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Group by cross-entry; transactions without one are an entry of their own
+    let mut groups: BTreeMap<(bool, i64), (Vec<i64>, Option<i64>)> = BTreeMap::new();
+    for (id, cross_entry_id) in rows {
+        let key = match cross_entry_id {
+            Some(ce_id) => (true, ce_id),
+            None => (false, id),
+        };
+        let group = groups.entry(key).or_insert((Vec::new(), cross_entry_id));
+        group.0.push(id);
+    }
+
+    for (ids, cross_entry_id) in groups.values() {
+        let snapshot = TrashSnapshot {
+            transactions: snapshot_rows(conn, "pp_txn", "id", ids)?,
+            units: snapshot_rows(conn, "pp_txn_unit", "txn_id", ids)?,
+            cross_entries: snapshot_rows(conn, "pp_cross_entry", "id", &cross_entry_id.iter().copied().collect::<Vec<_>>())?,
+        };
+        conn.execute(
+            "INSERT INTO pp_txn_trash (txn_ids, snapshot) VALUES (?1, ?2)",
+            rusqlite::params![
+                serde_json::to_string(ids).map_err(|e| e.to_string())?,
+                serde_json::to_string(&snapshot).map_err(|e| e.to_string())?
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(groups.len())
+}
+
+/// List the trash, most recently deleted first
+fn load_trash(conn: &rusqlite::Connection) -> Result<Vec<TrashedTransaction>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, deleted_at, txn_ids, snapshot FROM pp_txn_trash ORDER BY deleted_at DESC, id DESC")
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(i64, String, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for (trash_id, deleted_at, txn_ids, snapshot) in rows {
+        let txn_ids: Vec<i64> = serde_json::from_str(&txn_ids).map_err(|e| e.to_string())?;
+        let snapshot: TrashSnapshot = serde_json::from_str(&snapshot).map_err(|e| e.to_string())?;
+        let Some(txn) = snapshot.transactions.first() else {
+            continue;
+        };
+        let text = |key: &str| txn.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let int = |key: &str| txn.get(key).and_then(|v| v.as_i64());
+
+        let txn_id = int("id").unwrap_or_default();
+        let security_id = int("security_id");
+        let security_name = match security_id {
+            Some(id) => conn
+                .query_row("SELECT name FROM pp_security WHERE id = ?1", [id], |row| row.get(0))
+                .optional()
+                .map_err(|e| e.to_string())?,
+            None => None,
+        };
+
+        entries.push(TrashedTransaction {
+            trash_id,
+            deleted_at,
+            txn_id,
+            owner_type: text("owner_type").unwrap_or_default(),
+            owner_id: int("owner_id").unwrap_or_default(),
+            txn_type: text("txn_type").unwrap_or_default(),
+            date: text("date").unwrap_or_default(),
+            amount: int("amount").unwrap_or_default() as f64 / 100.0,
+            currency: text("currency").unwrap_or_default(),
+            security_id,
+            security_name,
+            note: text("note"),
+            linked_txn_ids: txn_ids.into_iter().filter(|id| *id != txn_id).collect(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Put the trash entry containing `txn_id` back; returns the restored transaction IDs
+fn restore_from_trash(conn: &rusqlite::Connection, txn_id: i64) -> Result<Vec<i64>, String> {
+    let (trash_id, txn_ids, snapshot): (i64, String, String) = conn
+        .query_row(
+            r#"
+            SELECT t.id, t.txn_ids, t.snapshot FROM pp_txn_trash t
+            WHERE EXISTS (SELECT 1 FROM json_each(t.txn_ids) WHERE value = ?1)
+            "#,
+            [txn_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Buchung {} ist nicht im Papierkorb", txn_id))?;
+    let txn_ids: Vec<i64> = serde_json::from_str(&txn_ids).map_err(|e| e.to_string())?;
+    let snapshot: TrashSnapshot = serde_json::from_str(&snapshot).map_err(|e| e.to_string())?;
+
+    // Transactions and cross-entry reference each other; check once at commit
+    conn.execute_batch("PRAGMA defer_foreign_keys = ON")
+        .map_err(|e| e.to_string())?;
+    restore_rows(conn, "pp_txn", &snapshot.transactions)?;
+    restore_rows(conn, "pp_cross_entry", &snapshot.cross_entries)?;
+    restore_rows(conn, "pp_txn_unit", &snapshot.units)?;
+
+    conn.execute("DELETE FROM pp_txn_trash WHERE id = ?1", [trash_id])
+        .map_err(|e| e.to_string())?;
+    Ok(txn_ids)
+}
+
+/// Delete all trash entries; returns (entries, transactions)
+fn clear_trash(conn: &rusqlite::Connection) -> Result<(usize, usize), String> {
+    let transactions: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(json_array_length(txn_ids)), 0) FROM pp_txn_trash",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let entries = conn
+        .execute("DELETE FROM pp_txn_trash", [])
+        .map_err(|e| e.to_string())?;
+    Ok((entries, transactions as usize))
+}
+
+/// List deleted transactions that can still be restored
+#[command]
+pub fn get_deleted_transactions() -> Result<Vec<TrashedTransaction>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    load_trash(conn)
+}
+
+/// Restore a deleted transaction (with its linked counterpart) from the trash
+///
+/// Rebuilds the FIFO lots of the affected securities like a create would.
+/// Returns the restored transaction IDs.
+#[command]
+pub fn restore_transaction(app: AppHandle, id: i64) -> Result<Vec<i64>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let txn_ids = restore_from_trash(&tx, id)?;
+
+    let mut affected = AffectedByTransactions::default();
+    affected.add(&tx, &txn_ids)?;
+    let security_ids: Vec<i64> = affected.security_ids.iter().copied().collect();
+    record_audit(
+        &tx,
+        "restore_transaction",
+        "transaction",
+        txn_ids.first().copied(),
+        &format!("{} Buchung(en) aus dem Papierkorb wiederhergestellt", txn_ids.len()),
+    );
+    transactions_changed(&app, tx, "restored", &affected, true)
+        .map_err(|e| format!("Wiederherstellung fehlgeschlagen: {}", e))?;
+
+    emit_data_changed(
+        &app,
+        DataChangedPayload {
+            entity: "transaction".to_string(),
+            action: "restored".to_string(),
+            security_ids: (!security_ids.is_empty()).then_some(security_ids),
+            portfolio_ids: None,
+        },
+    );
+
+    Ok(txn_ids)
+}
+
+/// Permanently delete everything in the trash
+#[command]
+pub fn empty_trash() -> Result<EmptyTrashResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let (entries_deleted, transactions_deleted) = clear_trash(conn)?;
+    log::info!("Trash emptied: {} transactions", transactions_deleted);
+    record_audit(
+        conn,
+        "empty_trash",
+        "transaction",
+        None,
+        &format!("Papierkorb geleert ({} Buchungen endgültig gelöscht)", transactions_deleted),
+    );
+
+    Ok(EmptyTrashResult {
+        entries_deleted,
+        transactions_deleted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(conn: &rusqlite::Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// All rows of `table` as raw SQLite values, in rowid order
+    fn raw_rows(conn: &rusqlite::Connection, table: &str) -> Vec<Vec<Value>> {
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {} ORDER BY rowid", table)).unwrap();
+        let columns = stmt.column_count();
+        stmt.query_map([], |row| (0..columns).map(|i| row.get::<_, Value>(i)).collect())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_cross_entry (id, uuid, entry_type) VALUES (7, 'ce7', 'BUY_SELL');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares, cross_entry_id, note)
+             VALUES (1, 't1', 'portfolio', 1, 'BUY', '2024-01-02', 100050, 'EUR', 1, 1000000000, 7, 'Kauf'),
+                    (2, 't2', 'account', 1, 'BUY', '2024-01-02', 100050, 'EUR', 1, 1000000000, 7, NULL),
+                    (3, 't3', 'account', 1, 'DEPOSIT', '2024-01-01', 500000, 'EUR', NULL, NULL, NULL, NULL);
+             UPDATE pp_cross_entry SET portfolio_txn_id = 1, account_txn_id = 2 WHERE id = 7;
+             INSERT INTO pp_txn_unit (txn_id, unit_type, amount, currency, exchange_rate) VALUES (1, 'FEE', 50, 'EUR', 1.5);",
+        )
+        .unwrap();
+
+        let original: Vec<_> = ["pp_txn", "pp_txn_unit", "pp_cross_entry"]
+            .iter()
+            .map(|table| raw_rows(&conn, table))
+            .collect();
+
+        assert_eq!(store_in_trash(&conn, &[1, 2, 3]).unwrap(), 2);
+        conn.execute_batch(
+            "DELETE FROM pp_txn_unit; DELETE FROM pp_cross_entry; DELETE FROM pp_txn;",
+        )
+        .unwrap();
+
+        let trash = load_trash(&conn).unwrap();
+        assert_eq!(trash.len(), 2);
+        let buy = trash.iter().find(|t| t.txn_id == 1).unwrap();
+        assert_eq!(buy.linked_txn_ids, vec![2]);
+        assert_eq!(buy.security_name.as_deref(), Some("Aktie"));
+        assert!((buy.amount - 1000.5).abs() < 1e-9);
+
+        // Restoring the account side brings back the whole BUY
+        let tx = conn.unchecked_transaction().unwrap();
+        assert_eq!(restore_from_trash(&tx, 2).unwrap(), vec![1, 2]);
+        tx.commit().unwrap();
+        assert_eq!(count(&conn, "pp_txn"), 2);
+        let (note, cross_entry_id): (String, i64) = conn
+            .query_row("SELECT note, cross_entry_id FROM pp_txn WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(note, "Kauf");
+        assert_eq!(cross_entry_id, 7);
+        let (portfolio_txn, rate): (i64, f64) = conn
+            .query_row(
+                "SELECT ce.portfolio_txn_id, u.exchange_rate FROM pp_cross_entry ce, pp_txn_unit u WHERE ce.id = 7",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(portfolio_txn, 1);
+        assert!((rate - 1.5).abs() < 1e-9);
+
+        assert!(restore_from_trash(&conn, 1).is_err());
+
+        // Restored rows match the originals field by field
+        let tx = conn.unchecked_transaction().unwrap();
+        restore_from_trash(&tx, 3).unwrap();
+        tx.commit().unwrap();
+        let restored: Vec<_> = ["pp_txn", "pp_txn_unit", "pp_cross_entry"]
+            .iter()
+            .map(|table| raw_rows(&conn, table))
+            .collect();
+        assert_eq!(restored, original);
+
+        assert_eq!(clear_trash(&conn).unwrap(), (0, 0));
+        assert_eq!(count(&conn, "pp_txn_trash"), 0);
+    }
+
+    #[test]
+    fn test_snapshot_keeps_blobs() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, data BLOB, rate REAL, label TEXT);
+             INSERT INTO t VALUES (1, x'00ff10', 2.0, 'a'), (2, NULL, 0.5, NULL);",
+        )
+        .unwrap();
+        let original = raw_rows(&conn, "t");
+
+        let rows = snapshot_rows(&conn, "t", "id", &[1, 2]).unwrap();
+        let json = serde_json::to_string(&rows).unwrap();
+        conn.execute("DELETE FROM t", []).unwrap();
+        let rows: Vec<RowSnapshot> = serde_json::from_str(&json).unwrap();
+        restore_rows(&conn, "t", &rows).unwrap();
+
+        assert_eq!(raw_rows(&conn, "t"), original);
+    }
+}
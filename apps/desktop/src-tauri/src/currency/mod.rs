@@ -231,10 +231,105 @@ pub fn get_base_currency(conn: &Connection) -> Result<String> {
         .map_err(|_| anyhow!("No base currency configured"))
 }
 
+/// Normalize and validate an ISO 4217 currency code (three letters)
+pub fn normalize_currency_code(code: &str) -> Result<String> {
+    let code = code.trim().to_uppercase();
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(anyhow!("Invalid currency code: {}", code));
+    }
+    Ok(code)
+}
+
+/// Currencies used by securities, accounts and transactions that cannot be
+/// converted to `base` on `date` (forward-filled rates from before `date` count)
+///
+/// GBX/GBp (pence) are checked as GBP, the way values are converted.
+pub fn currencies_missing_rates(conn: &Connection, base: &str, date: NaiveDate) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT currency FROM pp_security WHERE currency IS NOT NULL
+        UNION SELECT currency FROM pp_account WHERE currency IS NOT NULL
+        UNION SELECT currency FROM pp_txn WHERE currency IS NOT NULL
+        UNION SELECT base_currency FROM pp_import
+        "#,
+    )?;
+    let currencies: std::collections::BTreeSet<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|currency| {
+            currency.map(|c| if c == "GBX" || c == "GBp" { "GBP".to_string() } else { c })
+        })
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(currencies
+        .into_iter()
+        .filter(|currency| currency != base && get_exchange_rate(conn, currency, base, date).is_err())
+        .collect())
+}
+
+/// Change the base currency of the client
+///
+/// The base currency is stored with the latest import (see `get_base_currency`).
+pub fn set_base_currency(conn: &Connection, currency: &str) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE pp_import SET base_currency = ?1 WHERE id = (SELECT MAX(id) FROM pp_import)",
+        params![currency],
+    )?;
+    if updated == 0 {
+        return Err(anyhow!("No base currency configured"));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_set_base_currency() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        assert!(set_base_currency(&conn, "USD").is_err());
+
+        conn.execute_batch(
+            "INSERT INTO pp_import (file_path, version, base_currency) VALUES ('a.portfolio', 1, 'EUR');
+             INSERT INTO pp_account (uuid, name, currency) VALUES ('a1', 'Konto', 'EUR'), ('a2', 'Depot CHF', 'CHF');
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES ('EUR', 'USD', '2024-01-02', '1.10');",
+        )
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+        // EUR converts via EUR/USD, CHF has no rate
+        assert_eq!(currencies_missing_rates(&conn, "USD", date).unwrap(), ["CHF"]);
+        conn.execute(
+            "INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES ('EUR', 'CHF', '2024-01-02', '0.95')",
+            [],
+        )
+        .unwrap();
+        assert!(currencies_missing_rates(&conn, "USD", date).unwrap().is_empty());
+
+        // Pence-quoted securities need a GBP rate
+        conn.execute("INSERT INTO pp_security (uuid, name, currency) VALUES ('s1', 'Vodafone', 'GBX')", [])
+            .unwrap();
+        assert_eq!(currencies_missing_rates(&conn, "USD", date).unwrap(), ["GBP"]);
+        conn.execute(
+            "INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES ('EUR', 'GBP', '2024-01-02', '0.86')",
+            [],
+        )
+        .unwrap();
+        assert!(currencies_missing_rates(&conn, "USD", date).unwrap().is_empty());
+        assert!(!currencies_missing_rates(&conn, "USD", NaiveDate::from_ymd_opt(2023, 1, 1).unwrap())
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(normalize_currency_code(" usd ").unwrap(), "USD");
+        assert!(normalize_currency_code("US").is_err());
+        set_base_currency(&conn, "USD").unwrap();
+        assert_eq!(get_base_currency(&conn).unwrap(), "USD");
+    }
+
     #[test]
     fn test_same_currency() {
         // Same currency should return 1.0
@@ -6,12 +6,255 @@ use std::sync::Mutex;
 pub static DB: once_cell::sync::Lazy<Mutex<Option<Connection>>> =
     once_cell::sync::Lazy::new(|| Mutex::new(None));
 
+/// A numbered schema migration, applied once and recorded in `pp_schema_version`
+struct Migration {
+    version: i32,
+    description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// All schema migrations in order
+///
+/// New schema changes are appended here as the next version number instead of
+/// being added to the `CREATE TABLE` batch or probed by column existence.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Schema changes from before versioned migrations",
+        up: migrate_legacy_schema,
+    },
+    Migration {
+        version: 2,
+        description: "FX and price components of realized gains per FIFO consumption",
+        up: migrate_fifo_fx_gains,
+    },
+    Migration {
+        version: 3,
+        description: "Idempotency keys for transaction creation and imports",
+        up: migrate_txn_idempotency_key,
+    },
+    Migration {
+        version: 4,
+        description: "Trash for deleted transactions",
+        up: migrate_txn_trash,
+    },
+    Migration {
+        version: 5,
+        description: "Audit log of mutating commands",
+        up: migrate_audit_log,
+    },
+    Migration {
+        version: 6,
+        description: "Explicit share flag for the stock loss pot",
+        up: migrate_security_is_stock,
+    },
+    Migration {
+        version: 7,
+        description: "Original purchase date of lots re-delivered by corporate actions",
+        up: migrate_txn_acquisition_date,
+    },
+];
+
+/// Schema version of a fully migrated database (latest migration)
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Tables a database must contain to be restored
+const REQUIRED_TABLES: &[&str] = &["pp_security", "pp_account", "pp_portfolio", "pp_txn"];
+
 pub fn init_database(path: &Path) -> Result<()> {
+    let conn = open_database(path)?;
+    *DB.lock().unwrap() = Some(conn);
+    Ok(())
+}
+
+fn open_database(path: &Path) -> Result<Connection> {
     let conn = Connection::open(path)?;
 
     // Enable WAL mode for better concurrent access
     conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
 
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Write a consistent snapshot of the database to `path`
+///
+/// Uses `VACUUM INTO`, which reads within one transaction and is safe with WAL.
+/// An existing file at `path` is replaced.
+pub fn backup_database(conn: &Connection, path: &Path) -> Result<()> {
+    if conn.path().map(Path::new) == Some(path) {
+        anyhow::bail!("Backup path is the database itself");
+    }
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let target = path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid backup path"))?;
+    conn.execute("VACUUM INTO ?1", [target])?;
+    Ok(())
+}
+
+/// On-disk size of the database and its WAL file
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseSizes {
+    pub db_bytes: u64,
+    pub wal_bytes: u64,
+}
+
+impl DatabaseSizes {
+    pub fn total(&self) -> u64 {
+        self.db_bytes + self.wal_bytes
+    }
+}
+
+/// Current file sizes of an on-disk database (zero for in-memory databases)
+pub fn database_sizes(conn: &Connection) -> DatabaseSizes {
+    let Some(path) = conn.path().filter(|p| !p.is_empty()).map(Path::new) else {
+        return DatabaseSizes::default();
+    };
+    let size = |p: &Path| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+    DatabaseSizes {
+        db_bytes: size(path),
+        wal_bytes: size(&sidecar_path(path, "-wal")),
+    }
+}
+
+/// Checkpoint the WAL into the database and compact it with `VACUUM`
+///
+/// Returns the file sizes before and after. VACUUM itself writes through the
+/// WAL, so the WAL is truncated again afterwards.
+pub fn optimize_database(conn: &Connection) -> Result<(DatabaseSizes, DatabaseSizes)> {
+    let before = database_sizes(conn);
+
+    checkpoint_wal(conn)?;
+    conn.execute_batch("VACUUM")?;
+    checkpoint_wal(conn)?;
+
+    Ok((before, database_sizes(conn)))
+}
+
+/// Run `optimize_database` after bulk deletes or imports; failures are only logged
+pub fn optimize_after_bulk_change(conn: &Connection, reason: &str) {
+    match optimize_database(conn) {
+        Ok((before, after)) => log::info!(
+            "Optimized database after {}: {} -> {} bytes",
+            reason,
+            before.total(),
+            after.total()
+        ),
+        Err(e) => log::warn!("Failed to optimize database after {}: {}", reason, e),
+    }
+}
+
+fn checkpoint_wal(conn: &Connection) -> Result<()> {
+    let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+    if busy != 0 {
+        log::warn!("WAL checkpoint incomplete: database busy");
+    }
+    Ok(())
+}
+
+/// Check that `path` is a portfolio database this app version can open
+///
+/// Returns the schema version of the file. Older versions are accepted since
+/// the migrations upgrade them on open.
+pub fn validate_database_file(path: &Path) -> Result<i32> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .map_err(|e| anyhow::anyhow!("Not a SQLite database: {}", e))?;
+
+    let check: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| anyhow::anyhow!("Not a SQLite database: {}", e))?;
+    if check != "ok" {
+        anyhow::bail!("Database file is corrupt: {}", check);
+    }
+
+    for table in REQUIRED_TABLES {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type='table' AND name=?1)",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            anyhow::bail!("Not a portfolio database (missing table {})", table);
+        }
+    }
+
+    let version = schema_version(&conn)?;
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Database was created by a newer app version (schema {} > {})",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(version)
+}
+
+/// Replace the open database with the file at `source`
+///
+/// The file is copied and validated before the current connection is closed,
+/// so an invalid backup leaves the database untouched.
+pub fn restore_database(slot: &mut Option<Connection>, source: &Path) -> Result<()> {
+    let db_path = slot
+        .as_ref()
+        .and_then(|conn| conn.path())
+        .filter(|p| !p.is_empty())
+        .map(std::path::PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("Database not initialized"))?;
+    if source == db_path {
+        anyhow::bail!("Backup path is the database itself");
+    }
+
+    let staged = sidecar_path(&db_path, "-restore");
+    std::fs::copy(source, &staged)?;
+    if let Err(e) = validate_database_file(&staged) {
+        remove_database_files(&staged);
+        return Err(e);
+    }
+    remove_sidecars(&staged);
+
+    if let Some(conn) = slot.take() {
+        if let Err((conn, e)) = conn.close() {
+            *slot = Some(conn);
+            remove_database_files(&staged);
+            return Err(e.into());
+        }
+    }
+    remove_sidecars(&db_path);
+
+    if let Err(e) = std::fs::rename(&staged, &db_path) {
+        remove_database_files(&staged);
+        *slot = Some(open_database(&db_path)?);
+        return Err(e.into());
+    }
+
+    *slot = Some(open_database(&db_path)?);
+    Ok(())
+}
+
+fn sidecar_path(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    std::path::PathBuf::from(name)
+}
+
+/// Remove WAL and shared-memory files of a closed database
+fn remove_sidecars(path: &Path) {
+    for suffix in ["-wal", "-shm"] {
+        std::fs::remove_file(sidecar_path(path, suffix)).ok();
+    }
+}
+
+fn remove_database_files(path: &Path) {
+    std::fs::remove_file(path).ok();
+    remove_sidecars(path);
+}
+
+/// Create all tables and run migrations on an open connection
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         r#"
         -- =============================================================================
@@ -461,14 +704,65 @@ pub fn init_database(path: &Path) -> Result<()> {
     )?;
 
     // Run migrations for existing databases
-    run_migrations(&conn)?;
+    run_migrations(conn)
+}
 
-    *DB.lock().unwrap() = Some(conn);
-    Ok(())
+/// Highest applied migration version (0 for databases from before versioning)
+pub fn schema_version(conn: &Connection) -> Result<i32> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type='table' AND name='pp_schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_table {
+        return Ok(0);
+    }
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM pp_schema_version",
+        [],
+        |row| row.get(0),
+    )?)
 }
 
-/// Run database migrations to add missing columns to existing tables
+/// Apply all migrations newer than the recorded schema version
+///
+/// Each migration runs in its own transaction together with its version record,
+/// so an interrupted startup resumes at the failed step.
 fn run_migrations(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pp_schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )?;
+
+    let current = schema_version(conn)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        tx.execute(
+            "INSERT INTO pp_schema_version (version, description) VALUES (?1, ?2)",
+            rusqlite::params![migration.version, migration.description],
+        )?;
+        tx.commit()?;
+        log::info!(
+            "Migration {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(())
+}
+
+/// Migration 1: column/table probes that ran on every startup before versioning
+///
+/// Every step checks the current schema, so it is safe on fresh databases and on
+/// databases where only some of the steps were applied.
+fn migrate_legacy_schema(conn: &Connection) -> Result<()> {
     // Helper to check if a column exists in a table
     fn column_exists(conn: &Connection, table: &str, column: &str) -> bool {
         let sql = format!("PRAGMA table_info({})", table);
@@ -774,10 +1068,13 @@ fn run_migrations(conn: &Connection) -> Result<()> {
                     'price_above', 'price_below', 'price_crosses',
                     'rsi_above', 'rsi_below',
                     'volume_spike', 'divergence',
-                    'pattern_detected', 'support_break', 'resistance_break'
+                    'pattern_detected', 'support_break', 'resistance_break',
+                    'price_pct_change', 'trailing_stop'
                 )),
                 target_value REAL NOT NULL,
                 target_value_2 REAL,
+                reference_price REAL,
+                high_water_mark REAL,
                 is_active INTEGER NOT NULL DEFAULT 1,
                 is_triggered INTEGER NOT NULL DEFAULT 0,
                 trigger_count INTEGER NOT NULL DEFAULT 0,
@@ -795,6 +1092,61 @@ fn run_migrations(conn: &Connection) -> Result<()> {
         log::info!("Migration: Created pp_price_alert table");
     }
 
+    // Migration: Reference price for percentage-based price alerts
+    if !column_exists(conn, "pp_price_alert", "reference_price") {
+        conn.execute("ALTER TABLE pp_price_alert ADD COLUMN reference_price REAL", [])?;
+        log::info!("Migration: Added reference_price column to pp_price_alert");
+    }
+
+    // Migration: Percentage and trailing-stop alerts need a relaxed alert_type CHECK
+    // constraint, so the table has to be rebuilt
+    if !column_exists(conn, "pp_price_alert", "high_water_mark") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_price_alert_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                uuid TEXT UNIQUE NOT NULL,
+                security_id INTEGER NOT NULL,
+                alert_type TEXT NOT NULL CHECK(alert_type IN (
+                    'price_above', 'price_below', 'price_crosses',
+                    'rsi_above', 'rsi_below',
+                    'volume_spike', 'divergence',
+                    'pattern_detected', 'support_break', 'resistance_break',
+                    'price_pct_change', 'trailing_stop'
+                )),
+                target_value REAL NOT NULL,
+                target_value_2 REAL,
+                reference_price REAL,
+                high_water_mark REAL,
+                is_active INTEGER NOT NULL DEFAULT 1,
+                is_triggered INTEGER NOT NULL DEFAULT 0,
+                trigger_count INTEGER NOT NULL DEFAULT 0,
+                last_triggered_at TEXT,
+                last_triggered_price REAL,
+                note TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (security_id) REFERENCES pp_security(id) ON DELETE CASCADE
+            );
+            INSERT INTO pp_price_alert_new (
+                id, uuid, security_id, alert_type, target_value, target_value_2, reference_price,
+                is_active, is_triggered, trigger_count, last_triggered_at,
+                last_triggered_price, note, created_at
+            )
+            SELECT
+                id, uuid, security_id, alert_type, target_value, target_value_2, reference_price,
+                is_active, is_triggered, trigger_count, last_triggered_at,
+                last_triggered_price, note, created_at
+            FROM pp_price_alert;
+            DROP TABLE pp_price_alert;
+            ALTER TABLE pp_price_alert_new RENAME TO pp_price_alert;
+            CREATE INDEX idx_pp_price_alert_security ON pp_price_alert(security_id);
+            CREATE INDEX idx_pp_price_alert_active ON pp_price_alert(is_active);
+            CREATE INDEX idx_pp_price_alert_security_active ON pp_price_alert(security_id, is_active);
+            "#,
+        )?;
+        log::info!("Migration: Rebuilt pp_price_alert with high_water_mark for trailing-stop alerts");
+    }
+
     // Migration: Create pp_pattern_history table for tracking pattern success rates
     if !table_exists(conn, "pp_pattern_history") {
         conn.execute_batch(
@@ -891,6 +1243,63 @@ fn run_migrations(conn: &Connection) -> Result<()> {
         log::info!("Migration: Created pp_allocation_target table");
     }
 
+    // Migration: Last reported breach per allocation target, so drift checks only
+    // notify again after the breach cleared or worsened
+    if !table_exists(conn, "pp_allocation_alert_state") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_allocation_alert_state (
+                target_id INTEGER PRIMARY KEY,
+                deviation REAL NOT NULL,
+                severity TEXT NOT NULL,
+                alerted_at TEXT NOT NULL,
+                FOREIGN KEY (target_id) REFERENCES pp_allocation_target(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_allocation_alert_state table");
+    }
+
+    // Migration: Constituents of funds (from factsheet CSVs) for look-through allocation
+    if !table_exists(conn, "pp_fund_holdings") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_fund_holdings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                fund_security_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                isin TEXT,
+                region TEXT,
+                sector TEXT,
+                weight REAL NOT NULL,  -- percent of fund assets (0-100)
+                imported_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (fund_security_id) REFERENCES pp_security(id) ON DELETE CASCADE
+            );
+            CREATE INDEX idx_pp_fund_holdings_fund ON pp_fund_holdings(fund_security_id);
+            "#,
+        )?;
+        log::info!("Migration: Created pp_fund_holdings table");
+    }
+
+    // Migration: Benchmark weights (and optional returns) per classification for
+    // performance attribution
+    if !table_exists(conn, "pp_attribution_weight") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_attribution_weight (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                benchmark_id INTEGER NOT NULL,
+                classification_id INTEGER NOT NULL,
+                weight REAL NOT NULL,       -- decimal (0.0 - 1.0)
+                benchmark_return REAL,      -- percent, NULL = total benchmark return
+                UNIQUE(benchmark_id, classification_id),
+                FOREIGN KEY (classification_id) REFERENCES pp_classification(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_attribution_weight table");
+    }
+
     // Migration: Create pp_attribute_type table for custom attribute definitions
     if !table_exists(conn, "pp_attribute_type") {
         conn.execute_batch(
@@ -1186,9 +1595,337 @@ fn run_migrations(conn: &Connection) -> Result<()> {
         log::info!("Migration: Created ai_user_template_param table");
     }
 
+    // Migration: Create pp_ai_usage table for AI token accounting
+    if !table_exists(conn, "pp_ai_usage") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_ai_usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                command TEXT NOT NULL,
+                prompt_tokens INTEGER,
+                completion_tokens INTEGER,
+                total_tokens INTEGER,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            CREATE INDEX idx_pp_ai_usage_provider_date ON pp_ai_usage(provider, created_at);
+            "#,
+        )?;
+        log::info!("Migration: Created pp_ai_usage table");
+    }
+
+    // Migration: Create pp_ai_budget table for monthly token budgets per provider
+    if !table_exists(conn, "pp_ai_budget") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_ai_budget (
+                provider TEXT PRIMARY KEY,
+                monthly_token_limit INTEGER NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_ai_budget table");
+    }
+
+    // Migration: Create pp_quote_sync_error table for the last failed quote fetch per security
+    if !table_exists(conn, "pp_quote_sync_error") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_quote_sync_error (
+                security_id INTEGER PRIMARY KEY,
+                provider TEXT NOT NULL,
+                error TEXT NOT NULL,
+                failed_at TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (security_id) REFERENCES pp_security(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_quote_sync_error table");
+    }
+
+    // Migration: Create pp_intraday_price table (several points per day, live chart only)
+    if !table_exists(conn, "pp_intraday_price") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_intraday_price (
+                security_id INTEGER NOT NULL,
+                timestamp TEXT NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (security_id, timestamp),
+                FOREIGN KEY (security_id) REFERENCES pp_security(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_intraday_price table");
+    }
+
+    // Migration: Create pp_invested_capital_cache table (unscaled invested capital per date)
+    if !table_exists(conn, "pp_invested_capital_cache") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_invested_capital_cache (
+                date TEXT PRIMARY KEY,
+                value REAL NOT NULL
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_invested_capital_cache table");
+    }
+
+    // Migration: Add domicile column to pp_security (ISO country for withholding tax)
+    if !column_exists(conn, "pp_security", "domicile") {
+        conn.execute("ALTER TABLE pp_security ADD COLUMN domicile TEXT", [])?;
+        log::info!("Migration: Added domicile column to pp_security");
+    }
+
+    // Migration: Add fund_type column to pp_security (Teilfreistellung for Vorabpauschale)
+    if !column_exists(conn, "pp_security", "fund_type") {
+        conn.execute("ALTER TABLE pp_security ADD COLUMN fund_type TEXT", [])?;
+        log::info!("Migration: Added fund_type column to pp_security");
+    }
+
+    // Migration: Track Vorabpauschale already offset against the Freistellungsauftrag
+    if !column_exists(conn, "pp_tax_settings", "vorabpauschale_applied") {
+        conn.execute(
+            "ALTER TABLE pp_tax_settings ADD COLUMN vorabpauschale_applied REAL NOT NULL DEFAULT 0",
+            [],
+        )?;
+        log::info!("Migration: Added vorabpauschale_applied column to pp_tax_settings");
+    }
+
+    // Migration: Loss pots (Verlustverrechnungstöpfe) at year end
+    if !column_exists(conn, "pp_tax_settings", "loss_pot_aktien") {
+        conn.execute(
+            "ALTER TABLE pp_tax_settings ADD COLUMN loss_pot_aktien REAL NOT NULL DEFAULT 0",
+            [],
+        )?;
+        conn.execute(
+            "ALTER TABLE pp_tax_settings ADD COLUMN loss_pot_sonstige REAL NOT NULL DEFAULT 0",
+            [],
+        )?;
+        log::info!("Migration: Added loss pot columns to pp_tax_settings");
+    }
+
+    // Migration: Mark years whose loss pots were saved (the pot columns default to 0)
+    if !column_exists(conn, "pp_tax_settings", "loss_pots_saved_at") {
+        conn.execute("ALTER TABLE pp_tax_settings ADD COLUMN loss_pots_saved_at TEXT", [])?;
+        log::info!("Migration: Added loss_pots_saved_at column to pp_tax_settings");
+    }
+
+    // Migration: Snapshots of applied mergers/spin-offs for undo
+    if !table_exists(conn, "pp_corporate_action_snapshot") {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_corporate_action_snapshot (
+                action_id INTEGER PRIMARY KEY,
+                txn_ids TEXT NOT NULL,  -- JSON array of transactions created by the action
+                lots TEXT NOT NULL,     -- JSON array of the open lots before the action
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (action_id) REFERENCES pp_corporate_action(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        log::info!("Migration: Created pp_corporate_action_snapshot table");
+    }
+
+    // Migration: Full-text index over transaction notes and security identifiers
+    // (rowid = pp_txn.id, kept in sync by triggers)
+    if !table_exists(conn, "pp_txn_fts") {
+        conn.execute_batch(
+            r#"
+            CREATE VIRTUAL TABLE pp_txn_fts USING fts5(
+                note, security_name, isin, wkn, ticker,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            CREATE TRIGGER pp_txn_fts_insert AFTER INSERT ON pp_txn BEGIN
+                INSERT INTO pp_txn_fts (rowid, note, security_name, isin, wkn, ticker)
+                SELECT NEW.id, NEW.note, s.name, s.isin, s.wkn, s.ticker
+                FROM (SELECT 1) LEFT JOIN pp_security s ON s.id = NEW.security_id;
+            END;
+
+            CREATE TRIGGER pp_txn_fts_update AFTER UPDATE OF note, security_id ON pp_txn BEGIN
+                DELETE FROM pp_txn_fts WHERE rowid = OLD.id;
+                INSERT INTO pp_txn_fts (rowid, note, security_name, isin, wkn, ticker)
+                SELECT NEW.id, NEW.note, s.name, s.isin, s.wkn, s.ticker
+                FROM (SELECT 1) LEFT JOIN pp_security s ON s.id = NEW.security_id;
+            END;
+
+            CREATE TRIGGER pp_txn_fts_delete AFTER DELETE ON pp_txn BEGIN
+                DELETE FROM pp_txn_fts WHERE rowid = OLD.id;
+            END;
+
+            CREATE TRIGGER pp_security_fts_update AFTER UPDATE OF name, isin, wkn, ticker ON pp_security BEGIN
+                UPDATE pp_txn_fts
+                SET security_name = NEW.name, isin = NEW.isin, wkn = NEW.wkn, ticker = NEW.ticker
+                WHERE rowid IN (SELECT id FROM pp_txn WHERE security_id = NEW.id);
+            END;
+
+            INSERT INTO pp_txn_fts (rowid, note, security_name, isin, wkn, ticker)
+            SELECT t.id, t.note, s.name, s.isin, s.wkn, s.ticker
+            FROM pp_txn t LEFT JOIN pp_security s ON s.id = t.security_id;
+            "#,
+        )?;
+        log::info!("Migration: Created pp_txn_fts full-text index");
+    }
+
+    // Migration: Mark synthetic prices (e.g. 'interpolated' for backfilled gaps)
+    if !column_exists(conn, "pp_price", "source") {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE pp_price ADD COLUMN source TEXT;
+
+            -- A real price always replaces an interpolated one, even with INSERT OR IGNORE
+            CREATE TRIGGER IF NOT EXISTS pp_price_replace_interpolated
+            BEFORE INSERT ON pp_price WHEN NEW.source IS NULL BEGIN
+                DELETE FROM pp_price
+                WHERE security_id = NEW.security_id AND date = NEW.date AND source = 'interpolated';
+            END;
+            "#,
+        )?;
+        log::info!("Migration: Added source column to pp_price");
+    }
+
+    // Migration: Daily volume for volume-spike alerts
+    if !column_exists(conn, "pp_price", "volume") {
+        conn.execute("ALTER TABLE pp_price ADD COLUMN volume INTEGER", [])?;
+        log::info!("Migration: Added volume column to pp_price");
+    }
+
+    Ok(())
+}
+
+/// Migration 2: base-currency FX/price split of each FIFO consumption
+///
+/// Fills the new columns for existing sales; later FIFO rebuilds keep them current.
+fn migrate_fifo_fx_gains(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE pp_fifo_consumption ADD COLUMN fx_gain INTEGER;     -- scale: 10^2, base currency
+        ALTER TABLE pp_fifo_consumption ADD COLUMN price_gain INTEGER;  -- scale: 10^2, base currency
+        "#,
+    )?;
+
+    let security_ids: Vec<i64> = conn
+        .prepare("SELECT DISTINCT security_id FROM pp_fifo_lot")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for security_id in security_ids {
+        crate::fifo::store_fx_gains(conn, security_id)?;
+    }
+    Ok(())
+}
+
+/// Migration 3: client-supplied or content-derived key per transaction
+///
+/// A repeated create or import with the same key returns or skips the existing
+/// transaction instead of inserting a duplicate.
+fn migrate_txn_idempotency_key(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE pp_txn ADD COLUMN idempotency_key TEXT;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_pp_txn_idempotency_key
+            ON pp_txn(idempotency_key) WHERE idempotency_key IS NOT NULL;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Migration 4: snapshots of deleted transactions for `restore_transaction`
+fn migrate_txn_trash(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pp_txn_trash (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            deleted_at TEXT NOT NULL DEFAULT (datetime('now')),
+            txn_ids TEXT NOT NULL,   -- JSON array of the deleted transactions (incl. linked)
+            snapshot TEXT NOT NULL   -- JSON rows of pp_txn, pp_txn_unit and pp_cross_entry
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Migration 5: one row per change made by CRUD, corporate-action and import commands
+fn migrate_audit_log(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pp_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL DEFAULT (datetime('now')),
+            command TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER,
+            summary TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_pp_audit_log_entity ON pp_audit_log(entity_type, entity_id);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Migration 6: securities explicitly marked as shares (Aktientopf, § 20 Abs. 6 EStG)
+///
+/// Defaults to 0: bonds, certificates and ETCs must not be netted in the stock loss pot.
+fn migrate_security_is_stock(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE pp_security ADD COLUMN is_stock INTEGER NOT NULL DEFAULT 0",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Migration 7: purchase date a DELIVERY_INBOUND lot carries over from the
+/// lot it replaces (spin-offs and mergers), so holding periods survive the action
+fn migrate_txn_acquisition_date(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE pp_txn ADD COLUMN acquisition_date TEXT", [])?;
     Ok(())
 }
 
 pub fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>> {
     Ok(DB.lock().map_err(|e| anyhow::anyhow!("Failed to lock database: {}", e))?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_migrations_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        init_schema(&conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        let applied: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_database() {
+        // Database from before versioning, missing a column added by a probe
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "DROP TABLE pp_schema_version;
+             ALTER TABLE pp_price DROP COLUMN volume;
+             ALTER TABLE pp_fifo_consumption DROP COLUMN fx_gain;
+             ALTER TABLE pp_fifo_consumption DROP COLUMN price_gain;
+             DROP INDEX idx_pp_txn_idempotency_key;
+             ALTER TABLE pp_txn DROP COLUMN idempotency_key;
+             ALTER TABLE pp_security DROP COLUMN is_stock;
+             ALTER TABLE pp_txn DROP COLUMN acquisition_date;",
+        )
+        .unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        init_schema(&conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        conn.execute("UPDATE pp_price SET volume = 0", []).unwrap();
+    }
+}
@@ -3,12 +3,34 @@
 //! When backend operations modify data (transactions, imports, etc.),
 //! emit these events to trigger frontend cache invalidation.
 
+use crate::commands::alerts::AllocationAlert;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter};
 
 /// Event name constant
 pub const DATA_CHANGED_EVENT: &str = "data_changed";
 
+/// Streamed AI chat text fragment
+pub const AI_CHAT_TOKEN_EVENT: &str = "ai-chat-token";
+
+/// Streamed AI chat finished (successfully or with an error)
+pub const AI_CHAT_DONE_EVENT: &str = "ai-chat-done";
+
+/// Transactions were created, updated or deleted
+pub const TRANSACTIONS_CHANGED_EVENT: &str = "transactions-changed";
+
+/// New latest price of a security was stored by the quote sync
+pub const PRICE_UPDATED_EVENT: &str = "price-updated";
+
+/// An allocation target was breached (new or worsened)
+pub const ALLOCATION_ALERT_EVENT: &str = "allocation-alert";
+
+/// Due investment plans were executed automatically
+pub const INVESTMENT_PLANS_EXECUTED_EVENT: &str = "investment-plans-executed";
+
+/// Progress of a long-running file export
+pub const EXPORT_PROGRESS_EVENT: &str = "export-progress";
+
 /// Payload for data change events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -75,9 +97,147 @@ impl DataChangedPayload {
     }
 }
 
+/// Payload for streamed AI chat text fragments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiChatTokenPayload {
+    /// Client-supplied ID to correlate events with the request
+    pub stream_id: String,
+    /// Text fragment to append
+    pub delta: String,
+}
+
+/// Payload for the final event of a streamed AI chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiChatDonePayload {
+    pub stream_id: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_used: Option<u32>,
+    /// Set if the stream failed; JSON-serialized AiError
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload for transaction change events
+///
+/// Sent after derived data (FIFO lots, invested capital cache) was refreshed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsChangedPayload {
+    /// "created", "updated", "deleted" or "bulk_deleted"
+    pub action: String,
+    /// Portfolios owning one of the changed transactions
+    pub portfolio_ids: Vec<i64>,
+    /// Securities whose FIFO lots were rebuilt
+    pub security_ids: Vec<i64>,
+}
+
+/// Payload for price update events (one per security)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceUpdatedPayload {
+    pub security_id: i64,
+    /// Price date (YYYY-MM-DD)
+    pub date: String,
+    /// Price in security currency
+    pub price: f64,
+    pub currency: String,
+    /// Price converted to the base currency (None if no exchange rate is available)
+    pub price_base: Option<f64>,
+    pub base_currency: String,
+}
+
+/// Executions of one investment plan in an automatic run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutedPlanSummary {
+    pub plan_id: i64,
+    pub name: String,
+    pub security_id: i64,
+    /// Execution dates (YYYY-MM-DD), more than one if missed periods were caught up
+    pub dates: Vec<String>,
+    /// Total invested amount in cents
+    pub amount: i64,
+}
+
+/// Payload for automatic investment plan runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvestmentPlansExecutedPayload {
+    pub execution_count: usize,
+    pub plans: Vec<ExecutedPlanSummary>,
+}
+
+/// Payload for export progress events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgressPayload {
+    /// Export kind (e.g. "transactions-jsonl")
+    pub export: String,
+    pub rows_written: usize,
+    pub total_rows: usize,
+    pub done: bool,
+}
+
 /// Emit a data changed event to the frontend
 pub fn emit_data_changed(app: &AppHandle, payload: DataChangedPayload) {
     if let Err(e) = app.emit(DATA_CHANGED_EVENT, payload) {
         log::warn!("Failed to emit data_changed event: {}", e);
     }
 }
+
+/// Emit a streamed AI chat text fragment to the frontend
+pub fn emit_ai_chat_token(app: &AppHandle, stream_id: &str, delta: &str) {
+    let payload = AiChatTokenPayload {
+        stream_id: stream_id.to_string(),
+        delta: delta.to_string(),
+    };
+    if let Err(e) = app.emit(AI_CHAT_TOKEN_EVENT, payload) {
+        log::warn!("Failed to emit ai-chat-token event: {}", e);
+    }
+}
+
+/// Emit the final event of a streamed AI chat to the frontend
+pub fn emit_ai_chat_done(app: &AppHandle, payload: AiChatDonePayload) {
+    if let Err(e) = app.emit(AI_CHAT_DONE_EVENT, payload) {
+        log::warn!("Failed to emit ai-chat-done event: {}", e);
+    }
+}
+
+/// Emit a price update event to the frontend
+pub fn emit_price_updated(app: &AppHandle, payload: PriceUpdatedPayload) {
+    if let Err(e) = app.emit(PRICE_UPDATED_EVENT, payload) {
+        log::warn!("Failed to emit price-updated event: {}", e);
+    }
+}
+
+/// Emit a transaction change event to the frontend
+pub fn emit_transactions_changed(app: &AppHandle, payload: TransactionsChangedPayload) {
+    if let Err(e) = app.emit(TRANSACTIONS_CHANGED_EVENT, payload) {
+        log::warn!("Failed to emit transactions-changed event: {}", e);
+    }
+}
+
+/// Emit an allocation drift alert to the frontend
+pub fn emit_allocation_alert(app: &AppHandle, alert: &AllocationAlert) {
+    if let Err(e) = app.emit(ALLOCATION_ALERT_EVENT, alert) {
+        log::warn!("Failed to emit allocation-alert event: {}", e);
+    }
+}
+
+/// Emit a summary of automatically executed investment plans to the frontend
+pub fn emit_investment_plans_executed(app: &AppHandle, payload: InvestmentPlansExecutedPayload) {
+    if let Err(e) = app.emit(INVESTMENT_PLANS_EXECUTED_EVENT, payload) {
+        log::warn!("Failed to emit investment-plans-executed event: {}", e);
+    }
+}
+
+/// Emit the progress of a file export to the frontend
+pub fn emit_export_progress(app: &AppHandle, payload: ExportProgressPayload) {
+    if let Err(e) = app.emit(EXPORT_PROGRESS_EVENT, payload) {
+        log::warn!("Failed to emit export-progress event: {}", e);
+    }
+}
@@ -6,6 +6,9 @@
 //! - TRANSFER_IN: Move lots from source portfolio (via cross-entry)
 //! - TRANSFER_OUT: Ignored (handled by TRANSFER_IN)
 //!
+//! A TRANSFER_IN dated before its source lots exist waits until the source
+//! portfolio receives shares and then moves those lots (with a warning).
+//!
 //! Based on: https://github.com/portfolio-performance/portfolio
 //! See: TradeCollector.java, CostCalculation.java
 
@@ -63,6 +66,8 @@ struct TxnData {
     portfolio_id: i64,
     txn_type: String,
     date: String,
+    /// Purchase date carried over by a corporate action (lots only)
+    acquisition_date: Option<String>,
     amount: i64,
     currency: String,
     shares: i64,
@@ -71,6 +76,14 @@ struct TxnData {
     cross_entry_id: Option<i64>,
 }
 
+/// TRANSFER_IN whose source portfolio did not hold enough shares yet
+#[derive(Debug)]
+struct PendingTransfer {
+    txn: TxnData,
+    source_portfolio_id: i64,
+    shares: i64,
+}
+
 /// Build FIFO lots from all portfolio transactions for a security
 /// This implements Portfolio Performance's TradeCollector logic
 pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
@@ -94,7 +107,8 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
             t.id, t.uuid, t.owner_id, t.txn_type, t.date,
             t.amount, t.currency, t.shares, t.cross_entry_id,
             COALESCE(SUM(CASE WHEN u.unit_type = 'FEE' THEN u.amount ELSE 0 END), 0) as fees,
-            COALESCE(SUM(CASE WHEN u.unit_type = 'TAX' THEN u.amount ELSE 0 END), 0) as taxes
+            COALESCE(SUM(CASE WHEN u.unit_type = 'TAX' THEN u.amount ELSE 0 END), 0) as taxes,
+            t.acquisition_date
         FROM pp_txn t
         LEFT JOIN pp_txn_unit u ON u.txn_id = t.id
         WHERE t.security_id = ? AND t.owner_type = 'portfolio' AND t.shares IS NOT NULL
@@ -127,6 +141,7 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
                 cross_entry_id: row.get(8)?,
                 fees: row.get(9)?,
                 taxes: row.get(10)?,
+                acquisition_date: row.get(11)?,
             })
         })?
         .filter_map(|r| r.ok())
@@ -138,6 +153,7 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
     // FIFO lots per portfolio: portfolio_id -> Vec<FifoLot>
     let mut lots_by_portfolio: HashMap<i64, Vec<FifoLot>> = HashMap::new();
     let mut consumptions: Vec<FifoConsumption> = Vec::new();
+    let mut pending_transfers: Vec<PendingTransfer> = Vec::new();
     let mut next_lot_id: i64 = 1;
 
     for txn in transactions {
@@ -155,7 +171,7 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
                     security_id,
                     portfolio_id: txn.portfolio_id,
                     purchase_txn_id: txn.id,
-                    purchase_date: txn.date,
+                    purchase_date: txn.acquisition_date.unwrap_or(txn.date),
                     original_shares: txn.shares,
                     remaining_shares: txn.shares,
                     gross_amount,
@@ -168,6 +184,14 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
                     .entry(txn.portfolio_id)
                     .or_default()
                     .push(lot);
+
+                settle_pending_transfers(
+                    &mut lots_by_portfolio,
+                    &mut pending_transfers,
+                    txn.portfolio_id,
+                    &mut next_lot_id,
+                    security_id,
+                );
             }
 
             "SELL" | "DELIVERY_OUTBOUND" => {
@@ -215,16 +239,33 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
             "TRANSFER_IN" => {
                 // Find source portfolio via cross-entry and move lots
                 if let Some(cross_entry_id) = txn.cross_entry_id {
-                    if let Some(source_portfolio_id) = cross_entry_map.get(&cross_entry_id) {
+                    if let Some(&source_portfolio_id) = cross_entry_map.get(&cross_entry_id) {
                         // Move lots from source to destination
-                        move_lots_between_portfolios(
+                        let missing = move_lots_between_portfolios(
                             &mut lots_by_portfolio,
-                            *source_portfolio_id,
+                            source_portfolio_id,
                             txn.portfolio_id,
                             txn.shares,
                             &mut next_lot_id,
                             security_id,
-                            &txn,
+                        );
+                        if missing > 0 {
+                            log::warn!(
+                                "TRANSFER_IN {}: source portfolio {} lacks {} shares, waiting for later lots",
+                                txn.id, source_portfolio_id, missing
+                            );
+                            pending_transfers.push(PendingTransfer {
+                                txn: txn.clone(),
+                                source_portfolio_id,
+                                shares: missing,
+                            });
+                        }
+                        settle_pending_transfers(
+                            &mut lots_by_portfolio,
+                            &mut pending_transfers,
+                            txn.portfolio_id,
+                            &mut next_lot_id,
+                            security_id,
                         );
                     } else {
                         // No cross-entry found - create new lot as fallback
@@ -248,6 +289,30 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
         }
     }
 
+    // Source lots never appeared: keep the shares with unknown cost
+    for pending in pending_transfers {
+        log::warn!(
+            "TRANSFER_IN {}: no source lots for {} shares, creating lot without cost",
+            pending.txn.id, pending.shares
+        );
+        lots_by_portfolio
+            .entry(pending.txn.portfolio_id)
+            .or_default()
+            .push(FifoLot {
+                id: next_lot_id,
+                security_id,
+                portfolio_id: pending.txn.portfolio_id,
+                purchase_txn_id: pending.txn.id,
+                purchase_date: pending.txn.date.clone(),
+                original_shares: pending.shares,
+                remaining_shares: pending.shares,
+                gross_amount: 0, // Unknown cost
+                net_amount: 0,
+                currency: pending.txn.currency.clone(),
+            });
+        next_lot_id += 1;
+    }
+
     // Insert all lots into database and track the mapping from temp ID to actual DB ID
     let mut lot_id_map: HashMap<i64, i64> = HashMap::new();
 
@@ -296,6 +361,105 @@ pub fn build_fifo_lots(conn: &Connection, security_id: i64) -> Result<()> {
         }
     }
 
+    store_fx_gains(conn, security_id)?;
+
+    Ok(())
+}
+
+/// Recompute the FX/price split of all realized gains (after a base currency change)
+pub fn store_all_fx_gains(conn: &Connection) -> Result<()> {
+    let security_ids: Vec<i64> = conn
+        .prepare("SELECT DISTINCT security_id FROM pp_fifo_lot")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for security_id in security_ids {
+        store_fx_gains(conn, security_id)?;
+    }
+    Ok(())
+}
+
+/// Split realized gains of foreign-currency sales into price and FX components
+///
+/// Per consumption (base currency cents): `fx_gain` is the net proceeds valued
+/// at the sale-date rate minus the same proceeds at the purchase-date rate,
+/// `price_gain` is proceeds minus cost in security currency at the purchase-date
+/// rate. Both stay NULL for base-currency trades or when a rate is missing.
+/// GBX/GBp amounts are converted as GBP/100. The values depend on the base
+/// currency, so changing it must recompute them (see `store_all_fx_gains`).
+pub fn store_fx_gains(conn: &Connection, security_id: i64) -> Result<()> {
+    let base_currency =
+        crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    // Values from an earlier base currency must not survive
+    conn.execute(
+        "UPDATE pp_fifo_consumption SET fx_gain = NULL, price_gain = NULL
+         WHERE lot_id IN (SELECT id FROM pp_fifo_lot WHERE security_id = ?)",
+        [security_id],
+    )?;
+
+    let mut stmt = conn.prepare(r#"
+        SELECT
+            fc.id, fc.shares_consumed, fc.gross_amount,
+            l.currency, date(l.purchase_date),
+            t.currency, date(t.date), t.amount, t.shares,
+            COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'FEE'), 0)
+        FROM pp_fifo_consumption fc
+        JOIN pp_fifo_lot l ON l.id = fc.lot_id
+        JOIN pp_txn t ON t.id = fc.sale_txn_id
+        WHERE l.security_id = ?
+    "#)?;
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(i64, i64, i64, String, String, String, String, i64, i64, i64)> = stmt
+        .query_map([security_id], |row| {
+            Ok((
+                row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?,
+                row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (id, consumed, cost, lot_currency, purchase_date, sale_currency, sale_date, amount, sale_shares, fees) in rows {
+        if lot_currency != sale_currency || sale_currency == base_currency || sale_shares <= 0 {
+            continue;
+        }
+        let (Ok(purchase_date), Ok(sale_date)) = (
+            chrono::NaiveDate::parse_from_str(&purchase_date, "%Y-%m-%d"),
+            chrono::NaiveDate::parse_from_str(&sale_date, "%Y-%m-%d"),
+        ) else {
+            continue;
+        };
+
+        // GBX/GBp amounts are in pence
+        let (rate_currency, factor) = if sale_currency == "GBX" || sale_currency == "GBp" {
+            ("GBP", 0.01)
+        } else {
+            (sale_currency.as_str(), 1.0)
+        };
+        let proceeds = (amount - fees) as f64 * consumed as f64 / sale_shares as f64 * factor;
+        let cost = cost as f64 * factor;
+        let rates = (
+            crate::currency::get_exchange_rate(conn, rate_currency, &base_currency, purchase_date),
+            crate::currency::get_exchange_rate(conn, rate_currency, &base_currency, sale_date),
+        );
+        let (fx_gain, price_gain) = match rates {
+            (Ok(purchase_rate), Ok(sale_rate)) => (
+                Some((proceeds * (sale_rate - purchase_rate)).round() as i64),
+                Some(((proceeds - cost) * purchase_rate).round() as i64),
+            ),
+            _ => {
+                log::warn!(
+                    "FIFO: No {}/{} rate for consumption {}, FX gain not split",
+                    sale_currency, base_currency, id
+                );
+                (None, None)
+            }
+        };
+        conn.execute(
+            "UPDATE pp_fifo_consumption SET fx_gain = ?, price_gain = ? WHERE id = ?",
+            params![fx_gain, price_gain, id],
+        )?;
+    }
+
     Ok(())
 }
 
@@ -326,6 +490,8 @@ fn build_cross_entry_map(conn: &Connection) -> Result<HashMap<i64, i64>> {
 }
 
 /// Move lots from source portfolio to destination portfolio
+///
+/// Returns the number of shares the source portfolio could not provide.
 fn move_lots_between_portfolios(
     lots_by_portfolio: &mut HashMap<i64, Vec<FifoLot>>,
     source_portfolio_id: i64,
@@ -333,8 +499,7 @@ fn move_lots_between_portfolios(
     shares_to_move: i64,
     next_lot_id: &mut i64,
     security_id: i64,
-    txn: &TxnData,
-) {
+) -> i64 {
     let source_lots = lots_by_portfolio.entry(source_portfolio_id).or_default();
     let mut shares_remaining = shares_to_move;
     let mut lots_to_add: Vec<FifoLot> = Vec::new();
@@ -376,33 +541,44 @@ fn move_lots_between_portfolios(
         shares_remaining -= shares_from_lot;
     }
 
-    if shares_remaining > 0 {
-        log::warn!(
-            "TRANSFER_IN: Could not find enough lots to transfer {} shares, {} remaining",
-            shares_to_move, shares_remaining
-        );
-        // Create a new lot for the remaining shares as fallback
-        let new_lot = FifoLot {
-            id: *next_lot_id,
-            security_id,
-            portfolio_id: dest_portfolio_id,
-            purchase_txn_id: txn.id,
-            purchase_date: txn.date.clone(),
-            original_shares: shares_remaining,
-            remaining_shares: shares_remaining,
-            gross_amount: 0, // Unknown cost
-            net_amount: 0,
-            currency: txn.currency.clone(),
-        };
-        *next_lot_id += 1;
-        lots_to_add.push(new_lot);
-    }
-
     // Add moved lots to destination
     lots_by_portfolio
         .entry(dest_portfolio_id)
         .or_default()
         .extend(lots_to_add);
+
+    shares_remaining.max(0)
+}
+
+/// Complete pending transfers out of `portfolio_id` after it received new lots
+fn settle_pending_transfers(
+    lots_by_portfolio: &mut HashMap<i64, Vec<FifoLot>>,
+    pending_transfers: &mut Vec<PendingTransfer>,
+    portfolio_id: i64,
+    next_lot_id: &mut i64,
+    security_id: i64,
+) {
+    for pending in pending_transfers.iter_mut() {
+        if pending.source_portfolio_id != portfolio_id || pending.shares <= 0 {
+            continue;
+        }
+        let missing = move_lots_between_portfolios(
+            lots_by_portfolio,
+            portfolio_id,
+            pending.txn.portfolio_id,
+            pending.shares,
+            next_lot_id,
+            security_id,
+        );
+        if missing < pending.shares {
+            log::warn!(
+                "TRANSFER_IN {} preceded its source lots in portfolio {}: moved {} shares from a later purchase",
+                pending.txn.id, portfolio_id, pending.shares - missing
+            );
+        }
+        pending.shares = missing;
+    }
+    pending_transfers.retain(|p| p.shares > 0);
 }
 
 /// Create a lot for a transfer when no cross-entry exists
@@ -575,9 +751,24 @@ pub fn get_cost_basis_by_security_converted(
     conn: &Connection,
     base_currency: &str,
 ) -> Result<HashMap<String, f64>, String> {
+    let mut result: HashMap<String, f64> = HashMap::new();
+    for ((identifier, _), cost) in get_cost_basis_by_security_portfolio_converted(conn, base_currency)? {
+        *result.entry(identifier).or_insert(0.0) += cost;
+    }
+    Ok(result)
+}
+
+/// Wie get_cost_basis_by_security_converted, aber pro Depot
+///
+/// Gibt HashMap<(identifier, portfolio_id), cost_basis_in_base_currency> zurück.
+pub fn get_cost_basis_by_security_portfolio_converted(
+    conn: &Connection,
+    base_currency: &str,
+) -> Result<HashMap<(String, i64), f64>, String> {
     // KEIN GROUP BY! Jedes Lot einzeln mit Security-Identifier und Kaufdatum
     let sql = r#"
         SELECT COALESCE(s.isin, s.uuid) as identifier,
+               l.portfolio_id,
                l.currency,
                l.purchase_date,
                CASE WHEN l.original_shares > 0 THEN
@@ -588,15 +779,16 @@ pub fn get_cost_basis_by_security_converted(
         WHERE l.remaining_shares > 0
     "#;
 
-    let mut result: HashMap<String, f64> = HashMap::new();
+    let mut result: HashMap<(String, i64), f64> = HashMap::new();
     let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
     let rows = stmt
         .query_map([], |row| {
             Ok((
                 row.get::<_, String>(0)?,
-                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, i64>(1)?,
                 row.get::<_, Option<String>>(2)?.unwrap_or_default(),
-                row.get::<_, i64>(3)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                row.get::<_, i64>(4)?,
             ))
         })
         .map_err(|e| e.to_string())?;
@@ -604,7 +796,7 @@ pub fn get_cost_basis_by_security_converted(
     let today = chrono::Utc::now().date_naive();
 
     for row in rows.flatten() {
-        let (identifier, lot_currency, purchase_date_str, cost_cents) = row;
+        let (identifier, portfolio_id, lot_currency, purchase_date_str, cost_cents) = row;
         let cost = cost_cents as f64 / AMOUNT_SCALE as f64;
 
         // Jedes Lot einzeln konvertieren
@@ -619,7 +811,7 @@ pub fn get_cost_basis_by_security_converted(
             cost
         };
 
-        *result.entry(identifier).or_insert(0.0) += converted;
+        *result.entry((identifier, portfolio_id)).or_insert(0.0) += converted;
     }
 
     Ok(result)
@@ -911,4 +1103,107 @@ mod tests {
         let cost_basis = lot.remaining_cost_basis();
         assert_eq!(cost_basis, 3333); // 3333 cents
     }
+
+    /// Lots of a portfolio as (purchase_date, original, remaining, gross)
+    fn portfolio_lots(conn: &Connection, portfolio_id: i64) -> Vec<(String, i64, i64, i64)> {
+        conn.prepare(
+            "SELECT purchase_date, original_shares, remaining_shares, gross_amount
+             FROM pp_fifo_lot WHERE portfolio_id = ?1 ORDER BY purchase_date, id",
+        )
+        .unwrap()
+        .query_map([portfolio_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap()
+    }
+
+    fn setup_transfer(conn: &Connection, buy_date: &str, transfer_date: &str) {
+        crate::db::init_schema(conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot A'), (2, 'p2', 'Depot B');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 't1', 'portfolio', 1, 'BUY', ?1, 100000, 'EUR', 1, 1000000000),
+                    (2, 't2', 'portfolio', 1, 'TRANSFER_OUT', ?2, 40000, 'EUR', 1, 400000000),
+                    (3, 't3', 'portfolio', 2, 'TRANSFER_IN', ?2, 40000, 'EUR', 1, 400000000),
+                    (4, 't4', 'portfolio', 2, 'SELL', '2024-06-03', 30000, 'EUR', 1, 200000000)",
+            params![buy_date, transfer_date],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_cross_entry (id, uuid, entry_type, from_txn_id, to_txn_id) VALUES (1, 'c1', 'PORTFOLIO_TRANSFER', 2, 3);
+             UPDATE pp_txn SET cross_entry_id = 1 WHERE id IN (2, 3);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cross_portfolio_transfer_keeps_lots() {
+        let conn = Connection::open_in_memory().unwrap();
+        setup_transfer(&conn, "2024-01-02", "2024-03-01");
+        build_fifo_lots(&conn, 1).unwrap();
+
+        assert_eq!(
+            portfolio_lots(&conn, 1),
+            vec![("2024-01-02".to_string(), 10 * SHARES_SCALE, 6 * SHARES_SCALE, 100000)]
+        );
+        // Moved lot keeps purchase date and cost; the sale consumes half of it
+        assert_eq!(
+            portfolio_lots(&conn, 2),
+            vec![("2024-01-02".to_string(), 4 * SHARES_SCALE, 2 * SHARES_SCALE, 40000)]
+        );
+        let consumed: i64 = conn
+            .query_row("SELECT gross_amount FROM pp_fifo_consumption WHERE sale_txn_id = 4", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(consumed, 20000);
+    }
+
+    #[test]
+    fn test_transfer_before_source_lot_is_repaired() {
+        let conn = Connection::open_in_memory().unwrap();
+        // Transfer booked before the purchase it moves
+        setup_transfer(&conn, "2024-03-05", "2024-03-01");
+        build_fifo_lots(&conn, 1).unwrap();
+
+        assert_eq!(portfolio_lots(&conn, 1)[0].2, 6 * SHARES_SCALE);
+        assert_eq!(
+            portfolio_lots(&conn, 2),
+            vec![("2024-03-05".to_string(), 4 * SHARES_SCALE, 2 * SHARES_SCALE, 40000)]
+        );
+    }
+
+    #[test]
+    fn test_fx_gains_for_pence_and_after_base_change() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_import (file_path, version, base_currency) VALUES ('test', 68, 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Vodafone', 'GBX');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 'b1', 'portfolio', 1, 'BUY', '2024-01-10', 100000, 'GBX', 1, 1000000000),
+                    (2, 's1', 'portfolio', 1, 'SELL', '2024-06-01', 120000, 'GBX', 1, 1000000000);
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate)
+             VALUES ('EUR', 'GBP', '2024-01-10', '0.8'), ('EUR', 'GBP', '2024-06-01', '1.0');",
+        )
+        .unwrap();
+        let gains = |conn: &Connection| -> (Option<i64>, Option<i64>) {
+            conn.query_row("SELECT fx_gain, price_gain FROM pp_fifo_consumption", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+        };
+
+        // 1000 GBX = 10 GBP bought at 1.25 EUR, 12 GBP sold at 1.00 EUR
+        build_fifo_lots(&conn, 1).unwrap();
+        assert_eq!(gains(&conn), (Some(-300), Some(250)));
+
+        // In GBP there is no FX component left
+        crate::currency::set_base_currency(&conn, "GBP").unwrap();
+        store_all_fx_gains(&conn).unwrap();
+        assert_eq!(gains(&conn), (Some(0), Some(200)));
+    }
 }
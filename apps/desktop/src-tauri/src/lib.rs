@@ -1,4 +1,5 @@
 pub mod ai;
+pub mod cash;
 mod commands;
 pub mod csv_import;
 pub mod currency;
@@ -66,14 +67,20 @@ pub fn run() {
             commands::quotes::fetch_exchange_rates,
             commands::quotes::fetch_exchange_rate,
             commands::quotes::fetch_historical_exchange_rates,
+            commands::quotes::backfill_exchange_rates,
             commands::quotes::get_available_quote_providers,
             commands::quotes::search_external_securities,
+            commands::quotes::enrich_security_from_isin,
             commands::quotes::get_provider_status,
+            commands::quotes::get_stale_securities,
+            commands::quotes::fetch_intraday_quotes,
+            commands::quotes::get_intraday_prices,
             // Corporate Actions (Stock Splits, etc.)
             commands::quotes::detect_security_splits,
             commands::quotes::detect_all_splits,
             commands::quotes::get_corporate_actions,
             commands::quotes::detect_splits_by_price_heuristic,
+            commands::quotes::detect_isin_changes,
             // Quote Provider Suggestions
             commands::quotes::suggest_quote_providers,
             commands::quotes::apply_quote_suggestion,
@@ -93,13 +100,20 @@ pub fn run() {
             commands::import::get_imports,
             commands::import::delete_import,
             commands::import::rebuild_fifo_lots,
+            // Data integrity check
+            commands::integrity::run_integrity_check,
             // New PP Data query commands
             commands::data::get_securities,
             commands::data::get_accounts,
+            commands::data::reconcile_account,
+            commands::data::get_account_ledger,
             commands::data::get_pp_portfolios,
             commands::data::get_transactions,
+            commands::data::search_transactions,
             commands::data::get_price_history,
             commands::data::get_price_history_with_outliers,
+            commands::data::delete_price_outliers,
+            commands::data::backfill_price_gaps,
             commands::data::get_price_history_filtered,
             commands::data::get_holdings,
             commands::data::get_all_holdings,
@@ -130,35 +144,61 @@ pub fn run() {
             commands::crud::update_transaction,
             commands::crud::delete_transaction,
             commands::crud::delete_transactions_bulk,
+            commands::trash::get_deleted_transactions,
+            commands::trash::restore_transaction,
+            commands::trash::empty_trash,
+            commands::audit::get_audit_log,
             commands::crud::get_transaction,
             // Database Reset
             commands::crud::delete_all_data,
+            // Database backup/restore
+            commands::backup::backup_database,
+            commands::backup::restore_database,
+            commands::backup::optimize_database,
             // Performance
             commands::performance::calculate_performance,
             commands::performance::get_period_returns,
+            commands::performance::get_standard_period_returns,
             commands::performance::calculate_risk_metrics,
+            commands::performance::get_holdings_at_date,
+            commands::performance::get_concentration_metrics,
+            commands::performance::get_total_net_worth,
+            commands::performance::get_net_worth_history,
+            commands::performance::calculate_position_performance,
+            commands::performance::calculate_currency_effect,
+            // Performance Attribution
+            commands::attribution::get_attribution_weights,
+            commands::attribution::set_attribution_weights,
+            commands::attribution::calculate_attribution,
             // Currency
             commands::currency::get_exchange_rate,
             commands::currency::convert_currency,
             commands::currency::get_latest_exchange_rate,
             commands::currency::get_base_currency,
+            commands::currency::set_base_currency,
             commands::currency::get_holdings_in_base_currency,
             // CSV Import/Export
             commands::csv::export_transactions_csv,
+            commands::csv::export_transactions_jsonl,
             commands::csv::export_holdings_csv,
             commands::csv::export_securities_csv,
             commands::csv::export_accounts_csv,
             commands::csv::preview_csv,
             commands::csv::import_transactions_csv,
             commands::csv::import_prices_csv,
+            commands::csv::import_fund_holdings_csv,
             commands::csv::detect_csv_broker,
             commands::csv::get_broker_templates,
             commands::csv::import_csv_with_template,
             commands::csv::analyze_csv_with_ai,
+            // Ghostfolio Export
+            commands::ghostfolio::export_ghostfolio_json,
             // Reports
             commands::reports::generate_dividend_report,
             commands::reports::generate_realized_gains_report,
             commands::reports::generate_tax_report,
+            commands::reports::generate_cash_flow_report,
+            commands::reports::calculate_fire_projection,
             commands::reports::get_dividend_yield,
             commands::reports::get_monthly_returns,
             commands::reports::get_yearly_returns,
@@ -167,6 +207,8 @@ pub fn run() {
             commands::dividends::get_dividend_patterns,
             commands::dividends::estimate_annual_dividends,
             commands::dividends::get_portfolio_dividend_yield,
+            commands::dividends::get_yield_on_cost,
+            commands::dividends::simulate_drip,
             // Ex-Dividend Management
             commands::dividends::get_ex_dividends,
             commands::dividends::create_ex_dividend,
@@ -178,8 +220,12 @@ pub fn run() {
             tax::get_tax_settings,
             tax::save_tax_settings,
             tax::generate_german_tax_report,
+            tax::save_loss_pots,
             tax::get_freistellung_status,
             tax::update_freistellung_used,
+            tax::preview_sale_tax,
+            tax::vorabpauschale::calculate_vorabpauschale,
+            tax::anlage_kap::export_anlage_kap,
             // Taxonomy Management
             commands::taxonomy::get_taxonomies,
             commands::taxonomy::get_taxonomy,
@@ -196,6 +242,8 @@ pub fn run() {
             commands::taxonomy::assign_security,
             commands::taxonomy::remove_assignment,
             commands::taxonomy::get_taxonomy_allocation,
+            commands::taxonomy::get_fund_holdings,
+            commands::taxonomy::delete_fund_holdings,
             commands::taxonomy::get_all_security_classifications,
             commands::taxonomy::create_standard_taxonomies,
             // Corporate Actions
@@ -208,6 +256,9 @@ pub fn run() {
             // Merger & Acquisition
             commands::corporate_actions::preview_merger,
             commands::corporate_actions::apply_merger,
+            commands::corporate_actions::undo_merger,
+            commands::corporate_actions::undo_spin_off,
+            commands::corporate_actions::confirm_isin_change,
             // Watchlist Management
             commands::watchlist::get_watchlists,
             commands::watchlist::get_watchlist,
@@ -226,6 +277,7 @@ pub fn run() {
             commands::ai_helpers::ai_list_watchlists,
             commands::ai_helpers::ai_query_transactions,
             commands::ai_helpers::ai_query_portfolio_value,
+            commands::ai_helpers::ai_answer_portfolio_value_locally,
             commands::ai_helpers::ai_save_api_key,
             // User-defined query templates
             commands::ai_helpers::get_user_templates,
@@ -238,6 +290,7 @@ pub fn run() {
             commands::pdf_import::get_supported_banks,
             commands::pdf_import::preview_pdf_import,
             commands::pdf_import::import_pdf_transactions,
+            commands::pdf_import::import_pdf_batch,
             commands::pdf_import::extract_pdf_raw_text,
             commands::pdf_import::parse_pdf_text,
             commands::pdf_import::detect_pdf_bank,
@@ -253,6 +306,8 @@ pub fn run() {
             commands::investment_plans::delete_investment_plan,
             commands::investment_plans::get_investment_plan_executions,
             commands::investment_plans::execute_investment_plan,
+            commands::investment_plans::run_due_investment_plans,
+            commands::investment_plans::simulate_investment_plan,
             commands::investment_plans::get_plans_due_for_execution,
             // Rebalancing
             commands::rebalancing::preview_rebalance,
@@ -264,6 +319,10 @@ pub fn run() {
             optimization::calculate_correlation_matrix,
             optimization::calculate_efficient_frontier,
             optimization::get_optimal_weights,
+            optimization::get_risk_parity_weights,
+            optimization::monte_carlo_projection,
+            optimization::calculate_correlation_to_holdings,
+            optimization::suggest_diversifier,
             // Benchmark
             commands::benchmark::get_benchmarks,
             commands::benchmark::add_benchmark,
@@ -277,6 +336,9 @@ pub fn run() {
             commands::brandfetch::fetch_logos_batch,
             commands::brandfetch::reload_all_logos,
             commands::brandfetch::is_logo_cached,
+            commands::brandfetch::generate_fallback_logo,
+            commands::brandfetch::get_logo_cache_stats,
+            commands::brandfetch::set_logo_cache_limits,
             commands::brandfetch::get_cached_logo_data,
             commands::brandfetch::save_logo_to_cache,
             // PDF Export
@@ -285,6 +347,7 @@ pub fn run() {
             commands::pdf_export::export_performance_pdf,
             commands::pdf_export::export_dividend_pdf,
             commands::pdf_export::export_tax_report_pdf,
+            commands::pdf_export::export_anlage_kap_pdf,
             // AI Chart Analysis
             commands::ai::analyze_chart_with_ai,
             commands::ai::analyze_chart_with_annotations,
@@ -295,6 +358,10 @@ pub fn run() {
             // AI Portfolio Insights & Chat
             commands::ai::analyze_portfolio_with_ai,
             commands::ai::chat_with_portfolio_assistant,
+            commands::ai::chat_with_portfolio_assistant_stream,
+            commands::ai::get_ai_usage_summary,
+            commands::ai::set_ollama_base_url,
+            commands::ai::set_ai_monthly_budget,
             commands::ai::execute_confirmed_ai_action,
             // AI Transaction Commands
             commands::ai::execute_confirmed_transaction,
@@ -339,6 +406,7 @@ pub fn run() {
             commands::alerts::set_allocation_target,
             commands::alerts::delete_allocation_target,
             commands::alerts::get_allocation_alerts,
+            commands::alerts::check_allocation_drift,
             commands::alerts::get_allocation_alert_count,
             // Pattern Tracking
             commands::patterns::save_pattern_detection,
@@ -350,6 +418,8 @@ pub fn run() {
             commands::drawings::get_chart_drawings,
             commands::drawings::delete_chart_drawing,
             commands::drawings::clear_chart_drawings,
+            commands::duplicates::find_duplicate_securities,
+            commands::duplicates::merge_securities,
             // Dashboard Widget System
             commands::dashboard::get_available_widgets,
             commands::dashboard::get_dashboard_layout,
@@ -357,6 +427,8 @@ pub fn run() {
             commands::dashboard::delete_dashboard_layout,
             commands::dashboard::get_all_dashboard_layouts,
             commands::dashboard::create_default_dashboard_layout,
+            commands::dashboard::export_dashboard_layout,
+            commands::dashboard::import_dashboard_layout,
             // Custom Attributes
             commands::attributes::get_attribute_types,
             commands::attributes::create_attribute_type,
@@ -366,6 +438,8 @@ pub fn run() {
             commands::attributes::set_security_attribute,
             commands::attributes::remove_security_attribute,
             commands::attributes::get_securities_by_attribute,
+            commands::attributes::search_securities_by_attributes,
+            commands::attributes::set_security_attribute_bulk,
             // Consortium (Portfolio Groups)
             commands::consortium::get_consortiums,
             commands::consortium::create_consortium,
@@ -374,9 +448,11 @@ pub fn run() {
             commands::consortium::get_consortium_performance,
             commands::consortium::compare_portfolios,
             commands::consortium::get_consortium_history,
+            commands::consortium::get_consortium_risk_metrics,
             // DivvyDiary Export
             commands::divvydiary::get_divvydiary_portfolios,
             commands::divvydiary::upload_to_divvydiary,
+            commands::divvydiary::import_from_divvydiary,
             // Symbol Validation
             commands::validation::validate_all_securities_cmd,
             commands::validation::validate_security_cmd,
@@ -6,11 +6,15 @@
 //! - Minimum Variance Portfolio
 //! - Maximum Sharpe Ratio Portfolio
 //! - Portfolio Risk/Return analysis
+//! - Risk Parity (equal risk contribution) weights
+//! - Monte Carlo projection of the portfolio value
+//! - Correlation of a candidate security to the current holdings
+//! - Watchlist candidates ranked by their marginal Sharpe ratio effect
 
 use anyhow::Result;
 use chrono::NaiveDate;
 use rusqlite::{params, Connection};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::command;
 
@@ -18,6 +22,9 @@ use crate::db;
 
 const PRICE_SCALE: f64 = 100_000_000.0;
 
+/// Fewer common return days than this give no meaningful correlation
+const MIN_CORRELATION_OBSERVATIONS: usize = 10;
+
 // ============================================================================
 // Data Types
 // ============================================================================
@@ -72,6 +79,110 @@ pub struct EfficientFrontier {
     pub securities: Vec<SecurityInfo>,
 }
 
+/// Weight limits of a single security (fractions, e.g. 0.05 = 5%)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeightConstraint {
+    pub min_weight: Option<f64>,
+    pub max_weight: Option<f64>,
+}
+
+/// Risk parity weight of a single security
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskParityWeight {
+    pub security_id: i64,
+    pub name: String,
+    pub weight: f64,
+    /// Share of the portfolio variance (sums to 1)
+    pub risk_contribution: f64,
+}
+
+/// Risk parity allocation result
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskParityResult {
+    pub weights: Vec<RiskParityWeight>,
+    /// Annualized volatility of the risk parity portfolio
+    pub portfolio_volatility: f64,
+    pub iterations: usize,
+}
+
+/// Percentile band of the projected portfolio value for one year
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectionPoint {
+    pub year: u32,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+/// Correlation of the candidate to one held security
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldingCorrelation {
+    pub security_id: i64,
+    pub name: String,
+    pub ticker: Option<String>,
+    /// Share of the portfolio market value
+    pub weight: f64,
+    /// `None` with fewer than `MIN_CORRELATION_OBSERVATIONS` common days
+    pub correlation: Option<f64>,
+    pub observations: usize,
+}
+
+/// Correlation of a candidate security to the holdings and the portfolio
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrelationToHoldings {
+    pub candidate: SecurityInfo,
+    pub start_date: String,
+    pub end_date: String,
+    /// Correlation to the value-weighted daily portfolio return
+    pub portfolio_correlation: Option<f64>,
+    pub portfolio_observations: usize,
+    /// Average of the holding correlations, weighted by market value
+    pub weighted_average_correlation: Option<f64>,
+    /// Most correlated (redundant) first, most diversifying last
+    pub holdings: Vec<HoldingCorrelation>,
+}
+
+/// Effect of adding a small weight of a watchlist security
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiversifierCandidate {
+    pub security_id: i64,
+    pub name: String,
+    pub ticker: Option<String>,
+    /// Annualized mean return of the candidate
+    pub expected_return: f64,
+    /// Annualized volatility of the candidate
+    pub volatility: f64,
+    /// Correlation to the current portfolio (value-weighted)
+    pub correlation_to_portfolio: f64,
+    /// Sharpe ratio after adding `add_weight` of the candidate
+    pub new_sharpe_ratio: f64,
+    /// Against the current portfolio over the candidate's common dates
+    pub sharpe_change: f64,
+    pub new_volatility: f64,
+}
+
+/// Watchlist securities ranked by their effect on the portfolio Sharpe ratio
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiversifierSuggestion {
+    pub current_return: f64,
+    pub current_volatility: f64,
+    pub current_sharpe_ratio: f64,
+    pub add_weight: f64,
+    pub risk_free_rate: f64,
+    /// Best Sharpe improvement first
+    pub candidates: Vec<DiversifierCandidate>,
+    /// Candidates without price volatility in the period
+    pub skipped: Vec<SecurityInfo>,
+}
+
 /// Security statistics for optimization
 #[derive(Debug, Clone)]
 struct SecurityStats {
@@ -119,6 +230,8 @@ pub fn calculate_efficient_frontier(
     end_date: Option<String>,
     risk_free_rate: Option<f64>,
     num_points: Option<usize>,
+    weight_constraints: Option<HashMap<i64, WeightConstraint>>,
+    long_only: Option<bool>,
 ) -> Result<EfficientFrontier, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
@@ -139,8 +252,17 @@ pub fn calculate_efficient_frontier(
         .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
         .unwrap_or_else(|| chrono::Utc::now().date_naive());
 
-    compute_efficient_frontier(conn, portfolio_id, start, end, rf_rate, points)
-        .map_err(|e| e.to_string())
+    let constraints = weight_constraints.unwrap_or_default();
+    compute_efficient_frontier(
+        conn,
+        portfolio_id,
+        start,
+        end,
+        rf_rate,
+        points,
+        (&constraints, long_only.unwrap_or(true)),
+    )
+    .map_err(|e| e.to_string())
 }
 
 /// Get optimal portfolio weights for target return
@@ -150,6 +272,8 @@ pub fn get_optimal_weights(
     target_return: f64,
     start_date: Option<String>,
     end_date: Option<String>,
+    weight_constraints: Option<HashMap<i64, WeightConstraint>>,
+    long_only: Option<bool>,
 ) -> Result<HashMap<i64, f64>, String> {
     let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
     let conn = conn_guard
@@ -167,10 +291,164 @@ pub fn get_optimal_weights(
         .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
         .unwrap_or_else(|| chrono::Utc::now().date_naive());
 
-    compute_optimal_weights(conn, portfolio_id, target_return, start, end)
+    let constraints = weight_constraints.unwrap_or_default();
+    compute_optimal_weights(
+        conn,
+        portfolio_id,
+        target_return,
+        start,
+        end,
+        (&constraints, long_only.unwrap_or(true)),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Get risk parity (equal risk contribution) weights
+///
+/// Without `security_ids` the held securities of the portfolio are used.
+#[command]
+pub fn get_risk_parity_weights(
+    security_ids: Option<Vec<i64>>,
+    portfolio_id: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<RiskParityResult, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start = start_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| {
+            let now = chrono::Utc::now().date_naive();
+            now - chrono::Duration::days(365)
+        });
+
+    let end = end_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let security_ids = match security_ids {
+        Some(ids) if !ids.is_empty() => ids,
+        _ => get_held_securities(conn, portfolio_id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(id, _, _)| id)
+            .collect(),
+    };
+
+    calculate_risk_parity_weights(conn, &security_ids, start, end).map_err(|e| e.to_string())
+}
+
+/// Simulate future portfolio values (p10/p50/p90 per year)
+///
+/// Uses the historical mean returns and the covariance derived from the
+/// correlation matrix of the held securities (last 3 years of prices).
+#[command]
+pub fn monte_carlo_projection(
+    years: u32,
+    simulations: u32,
+    annual_contribution: f64,
+    portfolio_id: Option<i64>,
+) -> Result<Vec<ProjectionPoint>, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let end = chrono::Utc::now().date_naive();
+    let start = end - chrono::Duration::days(3 * 365);
+
+    compute_monte_carlo_projection(
+        conn,
+        portfolio_id,
+        start,
+        end,
+        years,
+        simulations,
+        annual_contribution,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Correlate a candidate security with each holding and the whole portfolio
+///
+/// Uses daily returns of the last `window_days` (default 365); the candidate
+/// itself is excluded from the holdings.
+#[command]
+pub fn calculate_correlation_to_holdings(
+    candidate_security_id: i64,
+    portfolio_id: Option<i64>,
+    window_days: Option<i64>,
+) -> Result<CorrelationToHoldings, String> {
+    let window_days = window_days.unwrap_or(365);
+    if window_days < 2 * MIN_CORRELATION_OBSERVATIONS as i64 {
+        return Err(format!(
+            "Window must be at least {} days",
+            2 * MIN_CORRELATION_OBSERVATIONS
+        ));
+    }
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let end = chrono::Utc::now().date_naive();
+    let start = end - chrono::Duration::days(window_days);
+
+    compute_correlation_to_holdings(conn, candidate_security_id, portfolio_id, start, end)
         .map_err(|e| e.to_string())
 }
 
+/// Rank watchlist securities by the Sharpe ratio change of a small addition
+///
+/// Each candidate gets `add_weight` (default 5%) of the portfolio, the current
+/// holdings are scaled down proportionally. Without `watchlist_id` all
+/// watchlists are used; securities already held are skipped.
+#[command]
+pub fn suggest_diversifier(
+    watchlist_id: Option<i64>,
+    portfolio_id: Option<i64>,
+    add_weight: Option<f64>,
+    risk_free_rate: Option<f64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<DiversifierSuggestion, String> {
+    let add_weight = add_weight.unwrap_or(0.05);
+    if !(add_weight > 0.0 && add_weight < 1.0) {
+        return Err("Add weight must be between 0 and 1".to_string());
+    }
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let start = start_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| {
+            let now = chrono::Utc::now().date_naive();
+            now - chrono::Duration::days(365)
+        });
+
+    let end = end_date
+        .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    compute_diversifier_suggestion(
+        conn,
+        watchlist_id,
+        portfolio_id,
+        add_weight,
+        risk_free_rate.unwrap_or(0.03),
+        start,
+        end,
+    )
+    .map_err(|e| e.to_string())
+}
+
 // ============================================================================
 // Implementation
 // ============================================================================
@@ -307,11 +585,21 @@ fn compute_correlation_matrix(
         });
     }
 
+    correlation_for_securities(conn, &held_securities, start_date, end_date)
+}
+
+/// Compute the correlation matrix for a list of (id, name, ticker)
+fn correlation_for_securities(
+    conn: &Connection,
+    security_list: &[(i64, String, Option<String>)],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<CorrelationMatrix> {
     // Get returns for each security
     let mut all_returns: HashMap<i64, HashMap<String, f64>> = HashMap::new();
     let mut securities: Vec<SecurityInfo> = Vec::new();
 
-    for (id, name, ticker) in &held_securities {
+    for (id, name, ticker) in security_list {
         let returns = get_security_returns(conn, *id, start_date, end_date)?;
         let return_map: HashMap<String, f64> = returns.into_iter().collect();
         all_returns.insert(*id, return_map);
@@ -385,8 +673,260 @@ fn compute_correlation_matrix(
     })
 }
 
+/// Correlation of `candidate_id` to each held security and to the portfolio
+fn compute_correlation_to_holdings(
+    conn: &Connection,
+    candidate_id: i64,
+    portfolio_id: Option<i64>,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<CorrelationToHoldings> {
+    let candidate = conn
+        .query_row(
+            "SELECT id, name, ticker FROM pp_security WHERE id = ?",
+            [candidate_id],
+            |row| {
+                Ok(SecurityInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    ticker: row.get(2)?,
+                })
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("Security {} not found", candidate_id))?;
+    let candidate_returns: HashMap<String, f64> =
+        get_security_returns(conn, candidate_id, start_date, end_date)?
+            .into_iter()
+            .collect();
+
+    let held: Vec<(i64, String, Option<String>)> = get_held_securities(conn, portfolio_id)?
+        .into_iter()
+        .filter(|(id, _, _)| *id != candidate_id)
+        .collect();
+    let held_ids: Vec<i64> = held.iter().map(|(id, _, _)| *id).collect();
+    let values = get_current_values(conn, portfolio_id, &held_ids)?;
+    let total_value: f64 = values.values().sum();
+    let weight_of = |id: i64| {
+        if total_value > 0.0 {
+            values.get(&id).copied().unwrap_or(0.0) / total_value
+        } else {
+            0.0
+        }
+    };
+
+    // Correlation per holding on the days both have a return
+    let mut holdings = Vec::with_capacity(held.len());
+    let mut holding_returns: Vec<(f64, HashMap<String, f64>)> = Vec::with_capacity(held.len());
+    for (id, name, ticker) in held {
+        let returns: HashMap<String, f64> = get_security_returns(conn, id, start_date, end_date)?
+            .into_iter()
+            .collect();
+        let (candidate_series, holding_series): (Vec<f64>, Vec<f64>) = candidate_returns
+            .iter()
+            .filter_map(|(date, r)| returns.get(date).map(|h| (*r, *h)))
+            .unzip();
+        let observations = candidate_series.len();
+
+        holdings.push(HoldingCorrelation {
+            security_id: id,
+            name,
+            ticker,
+            weight: weight_of(id),
+            correlation: (observations >= MIN_CORRELATION_OBSERVATIONS)
+                .then(|| calculate_correlation(&candidate_series, &holding_series)),
+            observations,
+        });
+        holding_returns.push((weight_of(id), returns));
+    }
+
+    // Portfolio return per day: weighted over the holdings priced that day
+    let (candidate_series, portfolio_series): (Vec<f64>, Vec<f64>) = candidate_returns
+        .iter()
+        .filter_map(|(date, r)| {
+            let (weighted, weight) = holding_returns
+                .iter()
+                .filter_map(|(w, returns)| returns.get(date).map(|h| (w * h, *w)))
+                .fold((0.0, 0.0), |(sum, total), (wr, w)| (sum + wr, total + w));
+            (weight > 0.0).then(|| (*r, weighted / weight))
+        })
+        .unzip();
+    let portfolio_observations = candidate_series.len();
+    let portfolio_correlation = (portfolio_observations >= MIN_CORRELATION_OBSERVATIONS)
+        .then(|| calculate_correlation(&candidate_series, &portfolio_series));
+
+    let (weighted_sum, weight_total) = holdings
+        .iter()
+        .filter_map(|h| h.correlation.map(|c| (c * h.weight, h.weight)))
+        .fold((0.0, 0.0), |(sum, total), (cw, w)| (sum + cw, total + w));
+    let weighted_average_correlation = (weight_total > 0.0).then(|| weighted_sum / weight_total);
+
+    holdings.sort_by(|a, b| match (a.correlation, b.correlation) {
+        (Some(x), Some(y)) => y.partial_cmp(&x).unwrap_or(std::cmp::Ordering::Equal),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.name.cmp(&b.name),
+    });
+
+    Ok(CorrelationToHoldings {
+        candidate,
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        portfolio_correlation,
+        portfolio_observations,
+        weighted_average_correlation,
+        holdings,
+    })
+}
+
+/// Annualized return and volatility of a weighted portfolio
+fn portfolio_return_volatility(weights: &[f64], stats: &[SecurityStats], cov_matrix: &[Vec<f64>]) -> (f64, f64) {
+    let port_return: f64 = weights.iter().zip(stats).map(|(w, s)| w * s.expected_return).sum();
+    let mut port_variance = 0.0;
+    for i in 0..weights.len() {
+        for j in 0..weights.len() {
+            port_variance += weights[i] * weights[j] * cov_matrix[i][j];
+        }
+    }
+    (port_return, port_variance.max(0.0).sqrt())
+}
+
+fn sharpe_ratio(port_return: f64, volatility: f64, risk_free_rate: f64) -> f64 {
+    if volatility > 0.0 {
+        (port_return - risk_free_rate) / volatility
+    } else {
+        0.0
+    }
+}
+
+/// Marginal Sharpe ratio effect of each watchlist security
+///
+/// Per candidate the correlation matrix of holdings + candidate is computed
+/// separately, so a candidate with a short price history does not shrink the
+/// common dates of the others.
+fn compute_diversifier_suggestion(
+    conn: &Connection,
+    watchlist_id: Option<i64>,
+    portfolio_id: Option<i64>,
+    add_weight: f64,
+    risk_free_rate: f64,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<DiversifierSuggestion> {
+    let held = get_held_securities(conn, portfolio_id)?;
+    if held.is_empty() {
+        return Err(anyhow::anyhow!("No held securities"));
+    }
+    let held_ids: Vec<i64> = held.iter().map(|(id, _, _)| *id).collect();
+
+    let values = get_current_values(conn, portfolio_id, &held_ids)?;
+    let total_value: f64 = values.values().sum();
+    let current_weights: Vec<f64> = held_ids
+        .iter()
+        .map(|id| {
+            if total_value > 0.0 {
+                values.get(id).copied().unwrap_or(0.0) / total_value
+            } else {
+                1.0 / held_ids.len() as f64
+            }
+        })
+        .collect();
+
+    let held_correlation = correlation_for_securities(conn, &held, start_date, end_date)?;
+    let (held_stats, held_cov) = covariance_matrix(conn, &held_correlation, start_date, end_date)?;
+    let (current_return, current_volatility) =
+        portfolio_return_volatility(&current_weights, &held_stats, &held_cov);
+    if current_volatility <= 0.0 {
+        return Err(anyhow::anyhow!("No price volatility for the holdings in the selected period"));
+    }
+    let current_sharpe = sharpe_ratio(current_return, current_volatility, risk_free_rate);
+
+    let watchlist_filter = watchlist_id
+        .map(|id| format!("AND ws.watchlist_id = {}", id))
+        .unwrap_or_default();
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT DISTINCT s.id, s.name, s.ticker
+        FROM pp_watchlist_security ws
+        JOIN pp_security s ON s.id = ws.security_id
+        WHERE 1 = 1 {}
+        ORDER BY s.name
+        "#,
+        watchlist_filter
+    ))?;
+    let watched: Vec<(i64, String, Option<String>)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut new_weights: Vec<f64> = current_weights.iter().map(|w| w * (1.0 - add_weight)).collect();
+    new_weights.push(add_weight);
+
+    let mut candidates = Vec::new();
+    let mut skipped = Vec::new();
+    for candidate in watched.into_iter().filter(|(id, _, _)| !held_ids.contains(id)) {
+        let mut securities = held.clone();
+        securities.push(candidate.clone());
+        let correlation = correlation_for_securities(conn, &securities, start_date, end_date)?;
+        let (stats, cov) = covariance_matrix(conn, &correlation, start_date, end_date)?;
+
+        let c = stats.len() - 1;
+        let (id, name, ticker) = candidate;
+        if cov[c][c] <= 0.0 {
+            skipped.push(SecurityInfo { id, name, ticker });
+            continue;
+        }
+
+        let (new_return, new_volatility) = portfolio_return_volatility(&new_weights, &stats, &cov);
+        let new_sharpe = sharpe_ratio(new_return, new_volatility, risk_free_rate);
+
+        // Baseline from the same dates as the candidate, so only the addition differs
+        let (held_return, held_volatility) =
+            portfolio_return_volatility(&current_weights, &stats[..c], &cov);
+        let baseline_sharpe = sharpe_ratio(held_return, held_volatility, risk_free_rate);
+
+        // Cov(candidate, portfolio) = Σ w_i Cov(candidate, i)
+        let cov_to_portfolio: f64 = current_weights.iter().enumerate().map(|(i, w)| w * cov[c][i]).sum();
+        let volatility = cov[c][c].sqrt();
+        let correlation_to_portfolio = if held_volatility > 0.0 {
+            cov_to_portfolio / (volatility * held_volatility)
+        } else {
+            0.0
+        };
+
+        candidates.push(DiversifierCandidate {
+            security_id: id,
+            name,
+            ticker,
+            expected_return: stats[c].expected_return,
+            volatility,
+            correlation_to_portfolio,
+            new_sharpe_ratio: new_sharpe,
+            sharpe_change: new_sharpe - baseline_sharpe,
+            new_volatility,
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.sharpe_change
+            .partial_cmp(&a.sharpe_change)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(DiversifierSuggestion {
+        current_return,
+        current_volatility,
+        current_sharpe_ratio: current_sharpe,
+        add_weight,
+        risk_free_rate,
+        candidates,
+        skipped,
+    })
+}
+
 /// Compute efficient frontier using Monte Carlo simulation
 /// (Simplified approach without matrix operations library)
+///
+/// Random weights respect the per-security `constraints`; without an entry a
+/// security may take 0-100% (long-only) or -100-100% (shorts allowed).
 fn compute_efficient_frontier(
     conn: &Connection,
     portfolio_id: Option<i64>,
@@ -394,6 +934,7 @@ fn compute_efficient_frontier(
     end_date: NaiveDate,
     risk_free_rate: f64,
     num_points: usize,
+    (constraints, long_only): (&HashMap<i64, WeightConstraint>, bool),
 ) -> Result<EfficientFrontier> {
     let held_securities = get_held_securities(conn, portfolio_id)?;
 
@@ -470,17 +1011,15 @@ fn compute_efficient_frontier(
         }
     }
 
+    let bounds = weight_bounds(&stats, constraints, long_only)?;
+
     // Monte Carlo simulation to generate efficient frontier
     let mut portfolios: Vec<EfficientFrontierPoint> = Vec::new();
     let num_simulations = num_points * 100;
 
     for _ in 0..num_simulations {
-        // Generate random weights
-        let mut weights: Vec<f64> = (0..n).map(|_| rand_simple()).collect();
-        let sum: f64 = weights.iter().sum();
-        for w in &mut weights {
-            *w /= sum;
-        }
+        // Generate random weights within the bounds
+        let weights = random_weights(&bounds);
 
         // Calculate portfolio return
         let port_return: f64 = weights
@@ -607,6 +1146,32 @@ fn get_current_weights(
     conn: &Connection,
     portfolio_id: Option<i64>,
     stats: &[SecurityStats],
+) -> Result<HashMap<i64, f64>> {
+    let security_ids: Vec<i64> = stats.iter().map(|s| s.id).collect();
+    let values = get_current_values(conn, portfolio_id, &security_ids)?;
+    let total_value: f64 = values.values().sum();
+
+    // Convert to weights
+    let weights: HashMap<i64, f64> = if total_value > 0.0 {
+        values
+            .into_iter()
+            .map(|(id, v)| (id, v / total_value))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    Ok(weights)
+}
+
+/// Get current market values of the holdings in the base currency
+///
+/// Converted per security (GBX/GBp as GBP) like `get_all_holdings`, so
+/// weights and the projection start value are comparable across currencies.
+fn get_current_values(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    security_ids: &[i64],
 ) -> Result<HashMap<i64, f64>> {
     let portfolio_filter = portfolio_id
         .map(|id| format!("AND t.owner_id = {}", id))
@@ -622,8 +1187,10 @@ fn get_current_weights(
                 WHEN t.txn_type IN ('SELL', 'TRANSFER_OUT', 'DELIVERY_OUTBOUND') THEN -t.shares
                 ELSE 0
             END) as net_shares,
-            lp.value as price
+            lp.value as price,
+            s.currency
         FROM pp_txn t
+        JOIN pp_security s ON s.id = t.security_id
         LEFT JOIN pp_latest_price lp ON lp.security_id = t.security_id
         WHERE t.owner_type = 'portfolio'
           AND t.shares IS NOT NULL
@@ -640,40 +1207,38 @@ fn get_current_weights(
             row.get::<_, i64>(0)?,
             row.get::<_, i64>(1)?,
             row.get::<_, Option<i64>>(2)?,
+            row.get::<_, String>(3)?,
         ))
     })?;
 
+    let base_currency = crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let today = chrono::Utc::now().date_naive();
     let mut values: HashMap<i64, f64> = HashMap::new();
-    let mut total_value = 0.0;
 
     for row in rows.flatten() {
-        let (security_id, shares, price_opt) = row;
+        let (security_id, shares, price_opt, security_currency) = row;
 
-        // Only include securities in our stats list
-        if !stats.iter().any(|s| s.id == security_id) {
+        // Only include the requested securities
+        if !security_ids.contains(&security_id) {
             continue;
         }
 
         if let Some(price) = price_opt {
             let shares_f = shares as f64 / 100_000_000.0;
             let price_f = price as f64 / PRICE_SCALE;
-            let value = shares_f * price_f;
-            values.insert(security_id, value);
-            total_value += value;
+            // GBX/GBp prices are in pence
+            let (value, currency) = if security_currency == "GBX" || security_currency == "GBp" {
+                (shares_f * price_f / 100.0, "GBP")
+            } else {
+                (shares_f * price_f, security_currency.as_str())
+            };
+            let value_in_base = crate::currency::convert(conn, value, currency, &base_currency, today)
+                .unwrap_or(value);
+            values.insert(security_id, value_in_base);
         }
     }
 
-    // Convert to weights
-    let weights: HashMap<i64, f64> = if total_value > 0.0 {
-        values
-            .into_iter()
-            .map(|(id, v)| (id, v / total_value))
-            .collect()
-    } else {
-        HashMap::new()
-    };
-
-    Ok(weights)
+    Ok(values)
 }
 
 /// Compute optimal weights for a target return
@@ -683,10 +1248,18 @@ fn compute_optimal_weights(
     target_return: f64,
     start_date: NaiveDate,
     end_date: NaiveDate,
+    constraints: (&HashMap<i64, WeightConstraint>, bool),
 ) -> Result<HashMap<i64, f64>> {
     // Use efficient frontier to find closest portfolio
-    let frontier =
-        compute_efficient_frontier(conn, portfolio_id, start_date, end_date, 0.03, 100)?;
+    let frontier = compute_efficient_frontier(
+        conn,
+        portfolio_id,
+        start_date,
+        end_date,
+        0.03,
+        100,
+        constraints,
+    )?;
 
     // Find point closest to target return
     let closest = frontier
@@ -704,6 +1277,365 @@ fn compute_optimal_weights(
     })
 }
 
+/// Resolve the (min, max) weight of every security and check feasibility
+fn weight_bounds(
+    stats: &[SecurityStats],
+    constraints: &HashMap<i64, WeightConstraint>,
+    long_only: bool,
+) -> Result<Vec<(f64, f64)>> {
+    let default_min = if long_only { 0.0 } else { -1.0 };
+
+    let bounds: Vec<(f64, f64)> = stats
+        .iter()
+        .map(|s| {
+            let c = constraints.get(&s.id).copied().unwrap_or_default();
+            let mut min = c.min_weight.unwrap_or(default_min);
+            if long_only {
+                min = min.max(0.0);
+            }
+            (min, c.max_weight.unwrap_or(1.0))
+        })
+        .collect();
+
+    if let Some((s, _)) = stats.iter().zip(&bounds).find(|(_, (min, max))| min > max) {
+        return Err(anyhow::anyhow!(
+            "Invalid weight constraint for {}: minimum above maximum",
+            s.name
+        ));
+    }
+
+    let min_sum: f64 = bounds.iter().map(|(min, _)| min).sum();
+    let max_sum: f64 = bounds.iter().map(|(_, max)| max).sum();
+    if min_sum > 1.0 + 1e-9 || max_sum < 1.0 - 1e-9 {
+        return Err(anyhow::anyhow!(
+            "Weight constraints are infeasible: minimums sum to {:.1}%, maximums to {:.1}%",
+            min_sum * 100.0,
+            max_sum * 100.0
+        ));
+    }
+
+    Ok(bounds)
+}
+
+/// Random weights within the bounds that sum to 1
+fn random_weights(bounds: &[(f64, f64)]) -> Vec<f64> {
+    let raw: Vec<f64> = bounds
+        .iter()
+        .map(|(min, max)| min + (max - min) * rand_simple())
+        .collect();
+    project_to_bounds(&raw, bounds)
+}
+
+/// Shift weights by a common offset and clamp them so they sum to 1
+///
+/// The sum of the clamped weights grows with the offset, so the offset is
+/// found by bisection. Bounds must be feasible (see `weight_bounds`).
+fn project_to_bounds(weights: &[f64], bounds: &[(f64, f64)]) -> Vec<f64> {
+    let clamped = |shift: f64| -> Vec<f64> {
+        weights
+            .iter()
+            .zip(bounds)
+            .map(|(w, (min, max))| (w + shift).clamp(*min, *max))
+            .collect()
+    };
+
+    let mut low = -2.0;
+    let mut high = 2.0;
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if clamped(mid).iter().sum::<f64>() < 1.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    clamped(high)
+}
+
+/// Annualized statistics and covariance matrix (same order as the correlation matrix)
+///
+/// Covariance = correlation × volatility_i × volatility_j, with each
+/// volatility taken from the full return series of the security.
+fn covariance_matrix(
+    conn: &Connection,
+    correlation: &CorrelationMatrix,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<(Vec<SecurityStats>, Vec<Vec<f64>>)> {
+    let mut stats: Vec<SecurityStats> = Vec::new();
+    let mut volatilities: Vec<f64> = Vec::new();
+    for security in &correlation.securities {
+        let returns: Vec<f64> = get_security_returns(conn, security.id, start_date, end_date)?
+            .into_iter()
+            .map(|(_, r)| r)
+            .collect();
+        let (mean, variance) = if returns.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let n = returns.len() as f64;
+            let mean = returns.iter().sum::<f64>() / n;
+            (mean, returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n)
+        };
+        volatilities.push((variance * 252.0).sqrt());
+        stats.push(SecurityStats {
+            id: security.id,
+            name: security.name.clone(),
+            expected_return: mean * 252.0,
+            returns,
+        });
+    }
+
+    let n = stats.len();
+    let mut cov_matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            cov_matrix[i][j] = correlation.matrix[i][j] * volatilities[i] * volatilities[j];
+        }
+    }
+
+    Ok((stats, cov_matrix))
+}
+
+/// Calculate risk parity weights for the given securities
+pub(crate) fn calculate_risk_parity_weights(
+    conn: &Connection,
+    security_ids: &[i64],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<RiskParityResult> {
+    if security_ids.is_empty() {
+        return Err(anyhow::anyhow!("No securities for risk parity"));
+    }
+
+    let mut securities = Vec::new();
+    for id in security_ids {
+        let security = conn.query_row(
+            "SELECT id, name, ticker FROM pp_security WHERE id = ?",
+            params![id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)),
+        )?;
+        securities.push(security);
+    }
+
+    let correlation = correlation_for_securities(conn, &securities, start_date, end_date)?;
+    let (stats, cov_matrix) = covariance_matrix(conn, &correlation, start_date, end_date)?;
+
+    if let Some(i) = (0..stats.len()).find(|i| cov_matrix[*i][*i] <= 0.0) {
+        return Err(anyhow::anyhow!("No price volatility for {} in the selected period", stats[i].name));
+    }
+
+    let (weights, iterations) = risk_parity(&cov_matrix);
+    let contributions = risk_contributions(&weights, &cov_matrix);
+    let variance: f64 = contributions.iter().sum();
+
+    Ok(RiskParityResult {
+        weights: stats
+            .iter()
+            .zip(weights.iter().zip(&contributions))
+            .map(|(s, (w, rc))| RiskParityWeight {
+                security_id: s.id,
+                name: s.name.clone(),
+                weight: *w,
+                risk_contribution: if variance > 0.0 { rc / variance } else { 0.0 },
+            })
+            .collect(),
+        portfolio_volatility: variance.sqrt(),
+        iterations,
+    })
+}
+
+/// Variance contribution of each asset: w_i × (Σw)_i
+fn risk_contributions(weights: &[f64], cov_matrix: &[Vec<f64>]) -> Vec<f64> {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| w * (0..weights.len()).map(|j| cov_matrix[i][j] * weights[j]).sum::<f64>())
+        .collect()
+}
+
+/// Equal risk contribution weights (long-only, sum to 1)
+///
+/// Multiplicative fixed-point iteration: every weight is scaled by
+/// sqrt(target / contribution) and renormalized until all contributions are
+/// equal. Starts from inverse volatility weights. Returns the iterations used.
+fn risk_parity(cov_matrix: &[Vec<f64>]) -> (Vec<f64>, usize) {
+    const MAX_ITERATIONS: usize = 10_000;
+    const TOLERANCE: f64 = 1e-10;
+
+    let n = cov_matrix.len();
+    let inverse_vol: Vec<f64> = (0..n).map(|i| 1.0 / cov_matrix[i][i].sqrt()).collect();
+    let total: f64 = inverse_vol.iter().sum();
+    let mut weights: Vec<f64> = inverse_vol.iter().map(|v| v / total).collect();
+
+    for iteration in 0..MAX_ITERATIONS {
+        let contributions = risk_contributions(&weights, cov_matrix);
+        let target = contributions.iter().sum::<f64>() / n as f64;
+
+        let deviation = contributions
+            .iter()
+            .map(|rc| ((rc - target) / target).abs())
+            .fold(0.0, f64::max);
+        if deviation < TOLERANCE {
+            return (weights, iteration);
+        }
+
+        for (w, rc) in weights.iter_mut().zip(&contributions) {
+            if *rc > 0.0 {
+                *w *= (target / rc).sqrt();
+            }
+        }
+        let sum: f64 = weights.iter().sum();
+        for w in &mut weights {
+            *w /= sum;
+        }
+    }
+
+    (weights, MAX_ITERATIONS)
+}
+
+/// Run the Monte Carlo projection for the held securities
+fn compute_monte_carlo_projection(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    years: u32,
+    simulations: u32,
+    annual_contribution: f64,
+) -> Result<Vec<ProjectionPoint>> {
+    if years == 0 || simulations == 0 {
+        return Err(anyhow::anyhow!("Years and simulations must be greater than 0"));
+    }
+
+    let correlation = compute_correlation_matrix(conn, portfolio_id, start_date, end_date)?;
+    if correlation.securities.is_empty() {
+        return Err(anyhow::anyhow!("No held securities with price data"));
+    }
+
+    let (stats, cov_matrix) = covariance_matrix(conn, &correlation, start_date, end_date)?;
+    let n = stats.len();
+
+    let security_ids: Vec<i64> = stats.iter().map(|s| s.id).collect();
+    let values = get_current_values(conn, portfolio_id, &security_ids)?;
+    let start_value: f64 = values.values().sum();
+    let weights: Vec<f64> = stats
+        .iter()
+        .map(|s| {
+            if start_value > 0.0 {
+                values.get(&s.id).copied().unwrap_or(0.0) / start_value
+            } else {
+                1.0 / n as f64
+            }
+        })
+        .collect();
+    let means: Vec<f64> = stats.iter().map(|s| s.expected_return).collect();
+
+    Ok(simulate_projection(
+        start_value,
+        &weights,
+        &means,
+        &cov_matrix,
+        (years, simulations),
+        annual_contribution,
+        rand_normal,
+    ))
+}
+
+/// Simulate yearly portfolio values and return the percentile bands
+///
+/// Each year every security draws a correlated normal return (Cholesky of the
+/// annual covariance), the portfolio is rebalanced to the start weights and
+/// the contribution is added at year end. Year 0 is the start value.
+fn simulate_projection(
+    start_value: f64,
+    weights: &[f64],
+    means: &[f64],
+    cov_matrix: &[Vec<f64>],
+    (years, simulations): (u32, u32),
+    annual_contribution: f64,
+    mut normal: impl FnMut() -> f64,
+) -> Vec<ProjectionPoint> {
+    let n = weights.len();
+    let cholesky = cholesky_decomposition(cov_matrix);
+
+    // values[year][simulation]
+    let mut values = vec![Vec::with_capacity(simulations as usize); years as usize + 1];
+
+    for _ in 0..simulations {
+        let mut value = start_value;
+        values[0].push(value);
+
+        for year_values in values.iter_mut().skip(1) {
+            let shocks: Vec<f64> = (0..n).map(|_| normal()).collect();
+            let portfolio_return: f64 = (0..n)
+                .map(|i| {
+                    let shock: f64 = (0..=i).map(|k| cholesky[i][k] * shocks[k]).sum();
+                    weights[i] * (means[i] + shock)
+                })
+                .sum();
+
+            // A loss cannot exceed the invested value
+            value = (value * (1.0 + portfolio_return)).max(0.0) + annual_contribution;
+            year_values.push(value);
+        }
+    }
+
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(year, mut year_values)| {
+            year_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            ProjectionPoint {
+                year: year as u32,
+                p10: percentile(&year_values, 0.10),
+                p50: percentile(&year_values, 0.50),
+                p90: percentile(&year_values, 0.90),
+            }
+        })
+        .collect()
+}
+
+/// Cholesky decomposition (lower triangle) of a covariance matrix
+///
+/// Matrices that are not positive definite (correlations from differently
+/// aligned series) get zeroed columns instead of failing.
+fn cholesky_decomposition(matrix: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                lower[i][j] = (matrix[i][i] - sum).max(0.0).sqrt();
+            } else if lower[j][j] > 0.0 {
+                lower[i][j] = (matrix[i][j] - sum) / lower[j][j];
+            }
+        }
+    }
+
+    lower
+}
+
+/// Linear interpolated percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Standard normal random number (Box-Muller on `rand_simple`)
+fn rand_normal() -> f64 {
+    let u1 = rand_simple().max(1e-6);
+    let u2 = rand_simple();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
 /// Simple pseudo-random number generator (0 to 1)
 /// Used for Monte Carlo simulation
 fn rand_simple() -> f64 {
@@ -976,6 +1908,286 @@ mod tests {
         assert!((mean - 0.5).abs() < 0.1, "Mean should be near 0.5, got {}", mean);
     }
 
+    // -------------------------------------------------------------------------
+    // Correlation To Holdings Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_correlation_to_holdings() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES
+                 (1, 's1', 'Candidate', 'EUR'), (2, 's2', 'Twin', 'EUR'),
+                 (3, 's3', 'Hedge', 'EUR'), (4, 's4', 'Sparse', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares) VALUES
+                 ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'EUR', 2, 300000000),
+                 ('t2', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'EUR', 3, 100000000),
+                 ('t3', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'EUR', 4, 100000000);
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES
+                 (2, '2024-01-31', 10000000000), (3, '2024-01-31', 10000000000), (4, '2024-01-31', 10000000000);",
+        )
+        .unwrap();
+
+        // Candidate and twin move together, the hedge moves the other way
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let (mut candidate, mut hedge) = (100.0, 100.0);
+        for day in 0..30 {
+            let date = (start + chrono::Duration::days(day)).to_string();
+            let change = if day % 3 == 0 { 0.02 } else { -0.01 + day as f64 * 0.0005 };
+            candidate *= 1.0 + change;
+            hedge *= 1.0 - change;
+            for (id, price) in [(1, candidate), (2, candidate * 2.0), (3, hedge)] {
+                conn.execute(
+                    "INSERT INTO pp_price (security_id, date, value) VALUES (?, ?, ?)",
+                    params![id, date, (price * PRICE_SCALE) as i64],
+                )
+                .unwrap();
+            }
+        }
+        conn.execute("INSERT INTO pp_price (security_id, date, value) VALUES (4, '2024-01-05', 10000000000)", [])
+            .unwrap();
+
+        let end = start + chrono::Duration::days(30);
+        let result = compute_correlation_to_holdings(&conn, 1, Some(1), start, end).unwrap();
+
+        let ids: Vec<i64> = result.holdings.iter().map(|h| h.security_id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert!((result.holdings[0].correlation.unwrap() - 1.0).abs() < 1e-9);
+        assert!((result.holdings[1].correlation.unwrap() + 1.0).abs() < 1e-9);
+        assert!(result.holdings[2].correlation.is_none());
+        assert!((result.holdings[0].weight - 0.6).abs() < 1e-9);
+
+        // 60% twin + 20% hedge (+ unpriced sparse) still tracks the candidate
+        assert_eq!(result.portfolio_observations, 29);
+        assert!(result.portfolio_correlation.unwrap() > 0.9);
+        assert!((result.weighted_average_correlation.unwrap() - 0.5).abs() < 1e-9);
+
+        assert!(compute_correlation_to_holdings(&conn, 99, None, start, end).is_err());
+    }
+
+    #[test]
+    fn test_current_values_in_base_currency() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_import (file_path, version, base_currency) VALUES ('test', 68, 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'US', 'USD'), (2, 's2', 'DE', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares) VALUES
+                 ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'USD', 1, 1000000000),
+                 ('t2', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'EUR', 2, 1000000000);
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES
+                 (1, '2024-01-31', 12500000000), (2, '2024-01-31', 10000000000);
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate) VALUES ('EUR', 'USD', '2024-01-01', '1.25');",
+        )
+        .unwrap();
+
+        // 10 x 125 USD at 1.25 USD/EUR = 1000 EUR, same as 10 x 100 EUR
+        let values = get_current_values(&conn, Some(1), &[1, 2]).unwrap();
+        assert!((values[&1] - 1000.0).abs() < 1e-9);
+        assert!((values[&2] - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_suggest_diversifier() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency) VALUES
+                 (1, 's1', 'Held', 'EUR'), (2, 's2', 'Clone', 'EUR'),
+                 (3, 's3', 'Hedge', 'EUR'), (4, 's4', 'No Prices', 'EUR');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 10000, 'EUR', 1, 100000000);
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-02-29', 10000000000);
+             INSERT INTO pp_watchlist (id, name) VALUES (1, 'Ideen');
+             INSERT INTO pp_watchlist_security (watchlist_id, security_id) VALUES (1, 1), (1, 2), (1, 3), (1, 4);",
+        )
+        .unwrap();
+
+        // Held and clone: same upward-drifting returns; hedge: mirrored noise, same drift
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let (mut held, mut hedge) = (100.0, 100.0);
+        for day in 0..60 {
+            let noise = if day % 2 == 0 { 0.02 } else { -0.018 };
+            held *= 1.0 + 0.001 + noise;
+            hedge *= 1.0 + 0.001 - noise;
+            let date = (start + chrono::Duration::days(day)).to_string();
+            for (id, price) in [(1, held), (2, held), (3, hedge)] {
+                conn.execute(
+                    "INSERT INTO pp_price (security_id, date, value) VALUES (?, ?, ?)",
+                    params![id, date, (price * PRICE_SCALE) as i64],
+                )
+                .unwrap();
+            }
+        }
+
+        let end = start + chrono::Duration::days(60);
+        let result = compute_diversifier_suggestion(&conn, Some(1), Some(1), 0.1, 0.0, start, end).unwrap();
+
+        // Held security is no candidate; the hedge cuts volatility, the clone changes nothing
+        let ids: Vec<i64> = result.candidates.iter().map(|c| c.security_id).collect();
+        assert_eq!(ids, vec![3, 2]);
+        assert!(result.candidates[0].sharpe_change > 0.0);
+        assert!(result.candidates[0].correlation_to_portfolio < -0.9);
+        assert!(result.candidates[0].new_volatility < result.current_volatility);
+        assert!(result.candidates[1].sharpe_change.abs() < 1e-9);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].id, 4);
+    }
+
+    // -------------------------------------------------------------------------
+    // Weight Constraint Tests
+    // -------------------------------------------------------------------------
+
+    fn stats_for(ids: &[i64]) -> Vec<SecurityStats> {
+        ids.iter()
+            .map(|id| SecurityStats {
+                id: *id,
+                name: format!("Security {}", id),
+                expected_return: 0.0,
+                returns: vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_random_weights_respect_bounds() {
+        let stats = stats_for(&[1, 2, 3]);
+        let mut constraints = HashMap::new();
+        constraints.insert(1, WeightConstraint { min_weight: Some(0.2), max_weight: Some(0.3) });
+        constraints.insert(3, WeightConstraint { min_weight: None, max_weight: Some(0.4) });
+
+        let bounds = weight_bounds(&stats, &constraints, true).unwrap();
+        assert_eq!(bounds, vec![(0.2, 0.3), (0.0, 1.0), (0.0, 0.4)]);
+
+        for _ in 0..200 {
+            let weights = random_weights(&bounds);
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            for (w, (min, max)) in weights.iter().zip(&bounds) {
+                assert!(*w >= *min - 1e-12 && *w <= *max + 1e-12, "{} outside [{}, {}]", w, min, max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weight_bounds_long_only_and_shorts() {
+        let stats = stats_for(&[1, 2]);
+        let mut constraints = HashMap::new();
+        constraints.insert(1, WeightConstraint { min_weight: Some(-0.5), max_weight: Some(1.5) });
+
+        // Long-only clips negative minimums
+        assert_eq!(weight_bounds(&stats, &constraints, true).unwrap()[0], (0.0, 1.5));
+        assert_eq!(
+            weight_bounds(&stats, &constraints, false).unwrap(),
+            vec![(-0.5, 1.5), (-1.0, 1.0)]
+        );
+
+        let projected = project_to_bounds(&[1.2, 0.6], &[(-0.5, 1.5), (-1.0, 1.0)]);
+        assert!((projected[0] - 0.8).abs() < 1e-9);
+        assert!((projected[1] - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weight_bounds_infeasible() {
+        let stats = stats_for(&[1, 2]);
+        let mut constraints = HashMap::new();
+        constraints.insert(1, WeightConstraint { min_weight: None, max_weight: Some(0.3) });
+        constraints.insert(2, WeightConstraint { min_weight: None, max_weight: Some(0.3) });
+        assert!(weight_bounds(&stats, &constraints, true).is_err());
+
+        constraints.insert(1, WeightConstraint { min_weight: Some(0.5), max_weight: Some(0.4) });
+        assert!(weight_bounds(&stats, &constraints, true).is_err());
+    }
+
+    // -------------------------------------------------------------------------
+    // Risk Parity Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_risk_parity_uncorrelated_is_inverse_volatility() {
+        // σ = 10% and 20%, uncorrelated -> weights 2/3 and 1/3
+        let cov = vec![vec![0.01, 0.0], vec![0.0, 0.04]];
+        let (weights, _) = risk_parity(&cov);
+
+        assert!((weights[0] - 2.0 / 3.0).abs() < 1e-6);
+        assert!((weights[1] - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_risk_parity_equalizes_contributions() {
+        let cov = vec![
+            vec![0.04, 0.012, 0.002],
+            vec![0.012, 0.09, 0.018],
+            vec![0.002, 0.018, 0.01],
+        ];
+        let (weights, iterations) = risk_parity(&cov);
+        assert!(iterations < 10_000);
+        assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let contributions = risk_contributions(&weights, &cov);
+        let total: f64 = contributions.iter().sum();
+        for rc in contributions {
+            assert!((rc / total - 1.0 / 3.0).abs() < 1e-6);
+        }
+        // The least volatile asset gets the largest weight
+        assert!(weights[2] > weights[0] && weights[0] > weights[1]);
+    }
+
+    // -------------------------------------------------------------------------
+    // Monte Carlo Projection Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_cholesky_decomposition() {
+        // σ1 = 20%, σ2 = 30%, ρ = 0.5
+        let cov = vec![vec![0.04, 0.03], vec![0.03, 0.09]];
+        let lower = cholesky_decomposition(&cov);
+
+        for i in 0..2 {
+            for j in 0..2 {
+                let product: f64 = (0..2).map(|k| lower[i][k] * lower[j][k]).sum();
+                assert!((product - cov[i][j]).abs() < 1e-12);
+            }
+        }
+        assert_eq!(lower[0][1], 0.0);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&values, 0.5), 3.0);
+        assert_eq!(percentile(&values, 0.0), 1.0);
+        assert!((percentile(&values, 0.9) - 4.6).abs() < 1e-12);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_projection_without_volatility() {
+        // Deterministic: 5% return and 1000 contribution per year
+        let cov = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        let points = simulate_projection(10_000.0, &[0.5, 0.5], &[0.04, 0.06], &cov, (2, 10), 1000.0, || 1.0);
+
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].p50, 10_000.0);
+        assert!((points[1].p50 - 11_500.0).abs() < 1e-9);
+        assert!((points[2].p10 - 13_075.0).abs() < 1e-9);
+        assert_eq!(points[2].p10, points[2].p90);
+    }
+
+    #[test]
+    fn test_projection_bands_are_ordered() {
+        let cov = vec![vec![0.04]];
+        let points = simulate_projection(10_000.0, &[1.0], &[0.07], &cov, (10, 500), 0.0, rand_normal);
+
+        for point in &points[1..] {
+            assert!(point.p10 < point.p50 && point.p50 < point.p90);
+            assert!(point.p10 >= 0.0);
+        }
+    }
+
     // -------------------------------------------------------------------------
     // Constants and Scale Tests
     // -------------------------------------------------------------------------
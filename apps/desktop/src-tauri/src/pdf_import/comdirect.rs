@@ -3,8 +3,8 @@
 //! Parses broker statements from Comdirect.
 
 use super::{
-    parse_german_date, parse_german_decimal, BankParser, ParseContext,
-    ParsedTransaction, ParsedTransactionType,
+    extract_isin, extract_withheld_taxes, parse_german_date, parse_german_decimal, parse_time,
+    BankParser, ParseContext, ParsedTransaction, ParsedTransactionType,
 };
 use regex::Regex;
 
@@ -37,13 +37,26 @@ impl ComdirectParser {
         }
 
         let date_re = Regex::new(r"Gesch(?:ä|a)ftstag\s*:\s*(\d{2}\.\d{2}\.\d{4})").ok();
+        let time_re = Regex::new(r"Handelszeit\s*:\s*(\d{2}:\d{2}(?::\d{2})?)").ok();
         let isin_re = Regex::new(r"ISIN\s*:\s*([A-Z]{2}[A-Z0-9]{10})").ok();
         let wkn_re = Regex::new(r"WKN\s*:\s*([A-Z0-9]{6})").ok();
+        // Wertpapierabrechnung layout: name and WKN on the line below "WPKNR/ISIN",
+        // then "St. 5,000 EUR 80,61"
+        let name_wkn_re = Regex::new(r"WPKNR/ISIN\s*\n\s*(.+?)\s+([A-Z0-9]{6})\s*\n").ok();
+        let shares_price_re = Regex::new(r"St\.\s+([\d.,]+)\s+([A-Z]{3})\s+([\d.,]+)").ok();
         let shares_re = Regex::new(r"St(?:ü|u)ck\s*:\s*([\d.,]+)").ok();
         let price_re = Regex::new(r"Kurs\s*:\s*([\d.,]+)\s*EUR").ok();
         let amount_re = Regex::new(r"Kurswert\s*:\s*EUR\s*([\d.,]+)").ok();
-        let provision_re = Regex::new(r"Provision\s*:\s*EUR\s*([\d.,]+)").ok();
+        let fee_re = Regex::new(
+            r"(Provision|B(?:ö|o)rsenplatzgeb(?:ü|u)hr|Fremde Spesen|(?:Ü|U)bertragungs-/Liefergeb(?:ü|u)hr|Variable B(?:ö|o)rsenspesen)\s*:\s*EUR\s*([\d.,]+)",
+        )
+        .ok();
         let total_re = Regex::new(r"Zu Ihren (?:Lasten|Gunsten)\s*:\s*EUR\s*([\d.,]+)").ok();
+        // "Zu Ihren Lasten vor Steuern" followed by a line "<IBAN> EUR <Valuta> EUR 409,92"
+        let total_block_re = Regex::new(
+            r"Zu Ihren (?:Lasten|Gunsten) vor Steuern\s*\n[^\n]*?EUR\s+\d{2}\.\d{2}\.\d{4}\s+EUR\s+([\d.,]+)",
+        )
+        .ok();
 
         let mut txn = ParsedTransaction {
             date: chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
@@ -64,7 +77,7 @@ impl ComdirectParser {
             forex_currency: None,
         };
 
-        // Extract date
+        // Extract date and time
         if let Some(re) = &date_re {
             if let Some(caps) = re.captures(content) {
                 if let Some(date) = parse_german_date(&caps[1]) {
@@ -72,32 +85,55 @@ impl ComdirectParser {
                 }
             }
         }
+        if let Some(re) = &time_re {
+            if let Some(caps) = re.captures(content) {
+                txn.time = parse_time(&caps[1]);
+            }
+        }
 
-        // Extract ISIN
+        // Extract ISIN (labelled or standalone after the security name)
         if let Some(re) = &isin_re {
             if let Some(caps) = re.captures(content) {
                 txn.isin = Some(caps[1].to_string());
             }
         }
+        if txn.isin.is_none() {
+            txn.isin = extract_isin(content);
+        }
 
-        // Extract WKN
+        // Extract security name and WKN
+        if let Some(re) = &name_wkn_re {
+            if let Some(caps) = re.captures(content) {
+                txn.security_name = Some(caps[1].trim().to_string());
+                txn.wkn = Some(caps[2].to_string());
+            }
+        }
         if let Some(re) = &wkn_re {
             if let Some(caps) = re.captures(content) {
                 txn.wkn = Some(caps[1].to_string());
             }
         }
 
-        // Extract shares
-        if let Some(re) = &shares_re {
+        // Extract shares and price
+        if let Some(re) = &shares_price_re {
             if let Some(caps) = re.captures(content) {
                 txn.shares = parse_german_decimal(&caps[1]);
+                txn.price_per_share = parse_german_decimal(&caps[3]);
+                txn.currency = caps[2].to_string();
             }
         }
-
-        // Extract price
-        if let Some(re) = &price_re {
-            if let Some(caps) = re.captures(content) {
-                txn.price_per_share = parse_german_decimal(&caps[1]);
+        if txn.shares.is_none() {
+            if let Some(re) = &shares_re {
+                if let Some(caps) = re.captures(content) {
+                    txn.shares = parse_german_decimal(&caps[1]);
+                }
+            }
+        }
+        if txn.price_per_share.is_none() {
+            if let Some(re) = &price_re {
+                if let Some(caps) = re.captures(content) {
+                    txn.price_per_share = parse_german_decimal(&caps[1]);
+                }
             }
         }
 
@@ -108,25 +144,39 @@ impl ComdirectParser {
             }
         }
 
-        // Extract fees
-        if let Some(re) = &provision_re {
-            if let Some(caps) = re.captures(content) {
-                txn.fees = ctx.parse_amount("fees", &caps[1]);
+        // Extract fees (provision, exchange and third-party fees)
+        if let Some(re) = &fee_re {
+            for caps in re.captures_iter(content) {
+                txn.fees += ctx.parse_amount("fees", &caps[2]);
             }
         }
 
+        // Extract taxes (only on sales with gains)
+        txn.taxes = extract_withheld_taxes(content, ctx);
+
         // Extract total
         if let Some(re) = &total_re {
             if let Some(caps) = re.captures(content) {
                 txn.net_amount = ctx.parse_amount("net_amount", &caps[1]);
             }
         }
+        if txn.net_amount == 0.0 {
+            if let Some(re) = &total_block_re {
+                if let Some(caps) = re.captures(content) {
+                    // Amount before taxes: taxes reduce the proceeds of a sale
+                    let before_taxes = ctx.parse_amount("net_amount", &caps[1]);
+                    txn.net_amount = if is_sell { before_taxes - txn.taxes } else { before_taxes + txn.taxes };
+                }
+            }
+        }
 
         // Extract security name
-        let name_re = Regex::new(r"Wertpapier-Bezeichnung\s*:\s*(.+?)(?:\n|ISIN)").ok();
-        if let Some(re) = name_re {
-            if let Some(caps) = re.captures(content) {
-                txn.security_name = Some(caps[1].trim().to_string());
+        if txn.security_name.is_none() {
+            let name_re = Regex::new(r"Wertpapier-Bezeichnung\s*:\s*(.+?)(?:\n|ISIN)").ok();
+            if let Some(re) = name_re {
+                if let Some(caps) = re.captures(content) {
+                    txn.security_name = Some(caps[1].trim().to_string());
+                }
             }
         }
 
@@ -323,3 +373,91 @@ impl BankParser for ComdirectParser {
         "Comdirect"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let parser = ComdirectParser::new();
+        assert!(parser.detect("comdirect bank AG\n25449 Quickborn"));
+        assert!(!parser.detect("Deutsche Kreditbank AG"));
+    }
+
+    #[test]
+    fn test_parse_buy() {
+        let parser = ComdirectParser::new();
+        let mut ctx = ParseContext::new();
+
+        let content = r#"
+comdirect bank AG
+Wertpapierkauf
+Geschäftstag : 14.06.2019 Ausführungsplatz : XETRA
+Wertpapier-Bezeichnung WPKNR/ISIN
+Vanguard FTSE All-World U.ETF A1JX52
+Registered Shares USD Dis.oN IE00B3RBWM25
+St. 5,000 EUR 80,61
+Handelszeit : 09:04 Uhr (MEZ/MESZ)
+Kurswert : EUR 403,05
+Provision : EUR 4,90
+Börsenplatzgebühr : EUR 1,50
+Fremde Spesen : EUR 0,47
+IBAN Valuta Zu Ihren Lasten vor Steuern
+DE00 0000 0000 0000 0000 00 EUR 18.06.2019 EUR 409,92
+"#;
+
+        let txns = parser.parse(content, &mut ctx).unwrap();
+        assert_eq!(txns.len(), 1);
+
+        let txn = &txns[0];
+        assert_eq!(txn.txn_type, ParsedTransactionType::Buy);
+        assert_eq!(txn.date, chrono::NaiveDate::from_ymd_opt(2019, 6, 14).unwrap());
+        assert_eq!(txn.time, chrono::NaiveTime::from_hms_opt(9, 4, 0));
+        assert_eq!(txn.isin, Some("IE00B3RBWM25".to_string()));
+        assert_eq!(txn.wkn, Some("A1JX52".to_string()));
+        assert_eq!(txn.security_name, Some("Vanguard FTSE All-World U.ETF".to_string()));
+        assert!((txn.shares.unwrap() - 5.0).abs() < 0.001);
+        assert!((txn.price_per_share.unwrap() - 80.61).abs() < 0.001);
+        assert!((txn.gross_amount - 403.05).abs() < 0.01);
+        assert!((txn.fees - 6.87).abs() < 0.01);
+        assert_eq!(txn.taxes, 0.0);
+        assert!((txn.net_amount - 409.92).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_sell_with_taxes() {
+        let parser = ComdirectParser::new();
+        let mut ctx = ParseContext::new();
+
+        let content = r#"
+comdirect bank AG
+Wertpapierverkauf
+Geschäftstag : 03.09.2019 Ausführungsplatz : XETRA
+Wertpapier-Bezeichnung WPKNR/ISIN
+Muster AG 000000
+Namens-Aktien o.N. DE0000000001
+St. 20,000 EUR 55,00
+Kurswert : EUR 1.100,00
+Provision : EUR 9,90
+Börsenplatzgebühr : EUR 1,50
+IBAN Valuta Zu Ihren Gunsten vor Steuern
+DE00 0000 0000 0000 0000 00 EUR 05.09.2019 EUR 1.088,60
+Steuerliche Behandlung: Wertpapierverkauf
+Kapitalertragsteuer 25,00 % auf 120,00 EUR 30,00- EUR
+Solidaritätszuschlag 5,50 % auf 30,00 EUR 1,65- EUR
+"#;
+
+        let txns = parser.parse(content, &mut ctx).unwrap();
+        assert_eq!(txns.len(), 1);
+
+        let txn = &txns[0];
+        assert_eq!(txn.txn_type, ParsedTransactionType::Sell);
+        assert_eq!(txn.isin, Some("DE0000000001".to_string()));
+        assert!((txn.shares.unwrap() - 20.0).abs() < 0.001);
+        assert!((txn.gross_amount - 1100.0).abs() < 0.01);
+        assert!((txn.fees - 11.40).abs() < 0.01);
+        assert!((txn.taxes - 31.65).abs() < 0.01);
+        assert!((txn.net_amount - 1056.95).abs() < 0.01);
+    }
+}
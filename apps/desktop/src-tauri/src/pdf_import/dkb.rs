@@ -3,8 +3,8 @@
 //! Parses broker statements from DKB.
 
 use super::{
-    extract_isin, parse_german_date, parse_german_decimal, BankParser, ParseContext,
-    ParsedTransaction, ParsedTransactionType,
+    extract_isin, extract_withheld_taxes, parse_german_date, parse_german_decimal, parse_time,
+    BankParser, ParseContext, ParsedTransaction, ParsedTransactionType,
 };
 use regex::Regex;
 
@@ -31,13 +31,19 @@ impl DkbParser {
 
         // Pattern for buy/sell orders
         // Example: "Wertpapier Abrechnung Kauf" or "Wertpapier Abrechnung Verkauf"
-        let txn_type_re = Regex::new(r"Wertpapier\s+Abrechnung\s+(Kauf|Verkauf)").ok();
-        let date_re = Regex::new(r"Schlusstag\s+(\d{2}\.\d{2}\.\d{4})").ok();
+        // (the sections below start right after "Wertpapier Abrechnung")
+        let txn_type_re = Regex::new(r"^\s*(Kauf|Verkauf)").ok();
+        let date_re = Regex::new(r"Schlusstag(?:/-Zeit)?\s+(\d{2}\.\d{2}\.\d{4})(?:\s+(\d{2}:\d{2}:\d{2}))?").ok();
+        // "Stück 50 ISHSIII-CORE MSCI WORLD U.ETF IE00B4L5Y983 (A0RPWH)"
+        let position_re = Regex::new(r"St(?:ü|u)ck\s+([\d.,]+)\s+(.+?)\s+([A-Z]{2}[A-Z0-9]{10})\s*\(([A-Z0-9]{6})\)").ok();
         let shares_re = Regex::new(r"St(?:ü|u)ck\s+([\d.,]+)").ok();
-        let price_re = Regex::new(r"Ausf(?:ü|u)hrungskurs\s+([\d.,]+)\s*EUR").ok();
-        let amount_re = Regex::new(r"Kurswert\s+([\d.,]+)\s*EUR").ok();
-        let provision_re = Regex::new(r"Provision\s+([\d.,]+)\s*EUR").ok();
-        let total_re = Regex::new(r"Ausmachender Betrag\s+([\d.,]+)\s*EUR").ok();
+        let price_re = Regex::new(r"Ausf(?:ü|u)hrungskurs\s+([\d.,]+)\s*([A-Z]{3})").ok();
+        let amount_re = Regex::new(r"Kurswert\s+([\d.,]+)-?\s*EUR").ok();
+        let fee_re = Regex::new(
+            r"(Provision|Transaktionsentgelt B(?:ö|o)rse|Handelsentgelt|(?:Ü|U)bertragungs-/Liefergeb(?:ü|u)hr|Fremde Spesen)\s+([\d.,]+)-?\s*EUR",
+        )
+        .ok();
+        let total_re = Regex::new(r"Ausmachender Betrag\s+([\d.,]+)-?\s*EUR").ok();
 
         // Split into sections by page or transaction
         let sections: Vec<&str> = content.split("Wertpapier Abrechnung").collect();
@@ -73,22 +79,35 @@ impl DkbParser {
                 }
             }
 
-            // Extract date
+            // Extract date and time
             if let Some(re) = &date_re {
                 if let Some(caps) = re.captures(section) {
                     if let Some(date) = parse_german_date(&caps[1]) {
                         txn.date = date;
                     }
+                    txn.time = caps.get(2).and_then(|m| parse_time(m.as_str()));
                 }
             }
 
-            // Extract ISIN
-            txn.isin = extract_isin(section);
-
-            // Extract shares
-            if let Some(re) = &shares_re {
+            // Extract shares, name, ISIN and WKN from the position line
+            if let Some(re) = &position_re {
                 if let Some(caps) = re.captures(section) {
                     txn.shares = parse_german_decimal(&caps[1]);
+                    txn.security_name = Some(caps[2].trim().to_string());
+                    txn.isin = Some(caps[3].to_string());
+                    txn.wkn = Some(caps[4].to_string());
+                }
+            }
+
+            if txn.isin.is_none() {
+                txn.isin = extract_isin(section);
+            }
+
+            if txn.shares.is_none() {
+                if let Some(re) = &shares_re {
+                    if let Some(caps) = re.captures(section) {
+                        txn.shares = parse_german_decimal(&caps[1]);
+                    }
                 }
             }
 
@@ -96,6 +115,7 @@ impl DkbParser {
             if let Some(re) = &price_re {
                 if let Some(caps) = re.captures(section) {
                     txn.price_per_share = parse_german_decimal(&caps[1]);
+                    txn.currency = caps[2].to_string();
                 }
             }
 
@@ -106,13 +126,16 @@ impl DkbParser {
                 }
             }
 
-            // Extract fees (Provision)
-            if let Some(re) = &provision_re {
-                if let Some(caps) = re.captures(section) {
-                    txn.fees = ctx.parse_amount("fees", &caps[1]);
+            // Extract fees (Provision, exchange fees)
+            if let Some(re) = &fee_re {
+                for caps in re.captures_iter(section) {
+                    txn.fees += ctx.parse_amount("fees", &caps[2]);
                 }
             }
 
+            // Extract withheld taxes (sales with gains)
+            txn.taxes = extract_withheld_taxes(section, ctx);
+
             // Extract total (Ausmachender Betrag)
             if let Some(re) = &total_re {
                 if let Some(caps) = re.captures(section) {
@@ -120,8 +143,8 @@ impl DkbParser {
                 }
             }
 
-            // Extract security name (line after ISIN usually)
-            if let Some(isin) = &txn.isin {
+            // Fall back to the line after the ISIN for the security name
+            if let (None, Some(isin)) = (&txn.security_name, &txn.isin) {
                 let name_re = Regex::new(&format!(r"{}\s*\n\s*(.+)", isin)).ok();
                 if let Some(re) = name_re {
                     if let Some(caps) = re.captures(section) {
@@ -146,8 +169,6 @@ impl DkbParser {
         let date_re = Regex::new(r"Zahlbarkeitstag\s+(\d{2}\.\d{2}\.\d{4})").ok();
         let shares_re = Regex::new(r"St(?:ü|u)ck\s+([\d.,]+)").ok();
         let gross_re = Regex::new(r"Brutto\s+([\d.,]+)\s*EUR").ok();
-        let tax_re = Regex::new(r"Kapitalertragsteuer\s+([\d.,]+)\s*EUR").ok();
-        let soli_re = Regex::new(r"Solidarit(?:ä|a)tszuschlag\s+([\d.,]+)\s*EUR").ok();
         let net_re = Regex::new(r"Ausmachender Betrag\s+([\d.,]+)\s*EUR").ok();
 
         // Find dividend sections
@@ -206,18 +227,7 @@ impl DkbParser {
                 }
 
                 // Extract taxes
-                let mut total_tax = 0.0;
-                if let Some(re) = &tax_re {
-                    if let Some(caps) = re.captures(&full_section) {
-                        total_tax += ctx.parse_amount("tax", &caps[1]);
-                    }
-                }
-                if let Some(re) = &soli_re {
-                    if let Some(caps) = re.captures(&full_section) {
-                        total_tax += ctx.parse_amount("soli", &caps[1]);
-                    }
-                }
-                txn.taxes = total_tax;
+                txn.taxes = extract_withheld_taxes(&full_section, ctx);
 
                 // Extract net amount
                 if let Some(re) = &net_re {
@@ -274,4 +284,76 @@ mod tests {
         assert!(parser.detect("DKB AG\nBerlin"));
         assert!(!parser.detect("ING-DiBa AG"));
     }
+
+    #[test]
+    fn test_parse_buy() {
+        let parser = DkbParser::new();
+        let mut ctx = ParseContext::new();
+
+        let content = r#"
+DKB AG
+Wertpapier Abrechnung Kauf
+Nominale Wertpapierbezeichnung ISIN (WKN)
+Stück 50 ISHSIII-CORE MSCI WORLD U.ETF IE00B4L5Y983 (A0RPWH)
+REGISTERED SHS USD (ACC) O.N.
+Handels-/Ausführungsplatz Tradegate (Best Execution)
+Schlusstag/-Zeit 12.02.2021 09:30:12 Auftraggeber Max Mustermann
+Ausführungskurs 69,53 EUR Auftragserteilung/ -ort Online-Banking
+Girosammelverwahrung Mitteilungsart Wertpapier-Abrechnung
+Kurswert 3.476,50- EUR
+Provision 10,00- EUR
+Ausmachender Betrag 3.486,50- EUR
+"#;
+
+        let txns = parser.parse(content, &mut ctx).unwrap();
+        assert_eq!(txns.len(), 1);
+
+        let txn = &txns[0];
+        assert_eq!(txn.txn_type, ParsedTransactionType::Buy);
+        assert_eq!(txn.date, chrono::NaiveDate::from_ymd_opt(2021, 2, 12).unwrap());
+        assert_eq!(txn.time, chrono::NaiveTime::from_hms_opt(9, 30, 12));
+        assert_eq!(txn.isin, Some("IE00B4L5Y983".to_string()));
+        assert_eq!(txn.wkn, Some("A0RPWH".to_string()));
+        assert_eq!(txn.security_name, Some("ISHSIII-CORE MSCI WORLD U.ETF".to_string()));
+        assert!((txn.shares.unwrap() - 50.0).abs() < 0.001);
+        assert!((txn.price_per_share.unwrap() - 69.53).abs() < 0.001);
+        assert!((txn.gross_amount - 3476.50).abs() < 0.01);
+        assert!((txn.fees - 10.0).abs() < 0.01);
+        assert_eq!(txn.taxes, 0.0);
+        assert!((txn.net_amount - 3486.50).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_sell_with_taxes() {
+        let parser = DkbParser::new();
+        let mut ctx = ParseContext::new();
+
+        let content = r#"
+DKB AG
+Wertpapier Abrechnung Verkauf
+Nominale Wertpapierbezeichnung ISIN (WKN)
+Stück 10 MUSTER AG DE0000000001 (000000)
+NAMENS-AKTIEN O.N.
+Schlusstag/-Zeit 04.05.2023 15:02:44 Auftraggeber Max Mustermann
+Ausführungskurs 120,00 EUR Auftragserteilung/ -ort Online-Banking
+Kurswert 1.200,00 EUR
+Provision 10,00- EUR
+Transaktionsentgelt Börse 0,71- EUR
+Kapitalertragsteuer 25,00 % auf 180,00 EUR 45,00- EUR
+Solidaritätszuschlag 5,50 % auf 45,00 EUR 2,47- EUR
+Ausmachender Betrag 1.141,82 EUR
+"#;
+
+        let txns = parser.parse(content, &mut ctx).unwrap();
+        assert_eq!(txns.len(), 1);
+
+        let txn = &txns[0];
+        assert_eq!(txn.txn_type, ParsedTransactionType::Sell);
+        assert_eq!(txn.isin, Some("DE0000000001".to_string()));
+        assert!((txn.shares.unwrap() - 10.0).abs() < 0.001);
+        assert!((txn.gross_amount - 1200.0).abs() < 0.01);
+        assert!((txn.fees - 10.71).abs() < 0.01);
+        assert!((txn.taxes - 47.47).abs() < 0.01);
+        assert!((txn.net_amount - 1141.82).abs() < 0.01);
+    }
 }
@@ -0,0 +1,313 @@
+//! Generic PDF Parser
+//!
+//! Best-effort fallback for statements of brokers without a dedicated parser.
+//! Scans the text for ISINs, share counts, amounts and dates with label-based
+//! regexes. Results are candidates only: `parse_pdf_content` marks all fields
+//! as low confidence so the user reviews them before importing.
+
+use super::{
+    extract_withheld_taxes, parse_german_date, parse_german_decimal, BankParser, ParseContext,
+    ParsedTransaction, ParsedTransactionType,
+};
+use regex::Regex;
+
+/// Type keywords (lowercase); sell first because "verkauf" contains "kauf"
+const TYPE_KEYWORDS: &[(&str, ParsedTransactionType)] = &[
+    ("verkauf", ParsedTransactionType::Sell),
+    ("sell", ParsedTransactionType::Sell),
+    ("sale", ParsedTransactionType::Sell),
+    ("kauf", ParsedTransactionType::Buy),
+    ("buy", ParsedTransactionType::Buy),
+    ("purchase", ParsedTransactionType::Buy),
+    ("dividend", ParsedTransactionType::Dividend),
+    ("ausschüttung", ParsedTransactionType::Dividend),
+    ("ertragsgutschrift", ParsedTransactionType::Dividend),
+    ("zinsen", ParsedTransactionType::Interest),
+    ("interest", ParsedTransactionType::Interest),
+];
+
+#[derive(Default)]
+pub struct GenericParser;
+
+impl GenericParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Split the text at the first occurrence of each distinct valid ISIN.
+    ///
+    /// A segment starts at the paragraph (blank line) containing its ISIN, so
+    /// headers like "Kauf" or the trade date above the ISIN belong to it. The
+    /// first segment also contains the document header.
+    fn segments(content: &str) -> Vec<(String, &str)> {
+        let isin_re = Regex::new(r"\b([A-Z]{2}[A-Z0-9]{9}[0-9])\b").unwrap();
+
+        let mut starts: Vec<(usize, String)> = Vec::new();
+        for m in isin_re.find_iter(content) {
+            let isin = m.as_str();
+            if !crate::commands::crud::validate_isin(isin) || starts.iter().any(|(_, i)| i == isin) {
+                continue;
+            }
+            let previous = starts.last().map_or(0, |(start, _)| *start);
+            let before = &content[previous..m.start()];
+            let start = before
+                .rfind("\n\n")
+                .or_else(|| before.rfind('\x0c'))
+                .map(|pos| previous + pos + 1)
+                .unwrap_or_else(|| content[..m.start()].rfind('\n').map_or(0, |p| p + 1));
+            starts.push((start, isin.to_string()));
+        }
+
+        let mut segments = Vec::new();
+        for (idx, (start, isin)) in starts.iter().enumerate() {
+            let from = if idx == 0 { 0 } else { *start };
+            let to = starts.get(idx + 1).map_or(content.len(), |(next, _)| *next);
+            segments.push((isin.clone(), &content[from..to.max(from)]));
+        }
+        segments
+    }
+
+    fn detect_type(text: &str) -> Option<ParsedTransactionType> {
+        let lower = text.to_lowercase();
+        TYPE_KEYWORDS
+            .iter()
+            .filter_map(|(keyword, txn_type)| lower.find(keyword).map(|pos| (pos, *txn_type)))
+            .min_by_key(|(pos, _)| *pos)
+            .map(|(_, txn_type)| txn_type)
+    }
+
+    /// Parse an amount in German ("1.234,56") or English ("1,234.56") notation
+    fn parse_amount(raw: &str) -> Option<f64> {
+        let raw = raw.trim().trim_end_matches(['-', '.', ',']);
+        let english = Regex::new(r"^\d{1,3}(,\d{3})*\.\d+$|^\d+\.\d{1,2}$").unwrap();
+        if english.is_match(raw) {
+            raw.replace(',', "").parse().ok()
+        } else {
+            parse_german_decimal(raw)
+        }
+    }
+
+    fn first_amount(re: &Regex, text: &str) -> Option<(f64, Option<String>)> {
+        re.captures_iter(text).find_map(|caps| {
+            let amount = Self::parse_amount(&caps["amount"])?;
+            let currency = caps.name("currency").map(|c| c.as_str().to_string());
+            Some((amount, currency))
+        })
+    }
+
+    fn parse_segment(
+        &self,
+        isin: String,
+        segment: &str,
+        fallback_type: Option<ParsedTransactionType>,
+        ctx: &mut ParseContext,
+    ) -> Option<ParsedTransaction> {
+        let labelled_date_re = Regex::new(
+            r"(?i)(?:schlusstag|handelstag|gesch(?:ä|a)ftstag|ausf(?:ü|u)hrung\w*|datum|valuta|zahltag|trade date|date)\W{0,20}(\d{2}\.\d{2}\.\d{4})",
+        )
+        .unwrap();
+        let date_re = Regex::new(r"\b(\d{2}\.\d{2}\.\d{4})\b").unwrap();
+        let iso_date_re = Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap();
+        let shares_re = Regex::new(
+            r"(?i)(?:st(?:ü|u)ck|stk\.?|st\.|anteile|menge|nominale|quantity|shares)\s*:?\s*(\d[\d.,']*)",
+        )
+        .unwrap();
+        let price_re = Regex::new(
+            r"(?i)\b(?:ausf(?:ü|u)hrungskurs|kurs|preis|price)\b\s*:?\s*(?P<currency>(?-i:[A-Z]{3}))?\s*(?P<amount>\d[\d.,]*)",
+        )
+        .unwrap();
+        let gross_re = Regex::new(
+            r"(?i)(?:kurswert|bruttobetrag|brutto|gross amount)\s*:?\s*(?P<currency>(?-i:[A-Z]{3}))?\s*(?P<amount>\d[\d.,]*)",
+        )
+        .unwrap();
+        let fee_re = Regex::new(
+            r"(?i)(?:provision|geb(?:ü|u)hr(?:en)?|entgelt|spesen|commission|fee)\s*:?\s*(?P<currency>(?-i:[A-Z]{3}))?\s*(?P<amount>\d[\d.,]*)",
+        )
+        .unwrap();
+        let net_re = Regex::new(
+            r"(?i)(?:ausmachender betrag|zu ihren (?:lasten|gunsten)|endbetrag|gesamtbetrag|net amount|total)\s*:?\s*(?P<currency>(?-i:[A-Z]{3}))?\s*(?P<amount>\d[\d.,]*)",
+        )
+        .unwrap();
+
+        let txn_type = match Self::detect_type(segment).or(fallback_type) {
+            Some(t) => t,
+            None => {
+                ctx.warn("txn_type", &isin, "Transaktionstyp nicht erkannt, bitte prüfen");
+                ParsedTransactionType::Buy
+            }
+        };
+
+        // Date: labelled date first, then any date in the segment
+        let date = labelled_date_re
+            .captures(segment)
+            .or_else(|| date_re.captures(segment))
+            .and_then(|caps| parse_german_date(&caps[1]))
+            .or_else(|| {
+                iso_date_re.captures(segment).and_then(|caps| {
+                    chrono::NaiveDate::from_ymd_opt(
+                        caps[1].parse().ok()?,
+                        caps[2].parse().ok()?,
+                        caps[3].parse().ok()?,
+                    )
+                })
+            });
+        let date = match date {
+            Some(d) => d,
+            None => {
+                ctx.warn("date", &isin, "Kein Datum gefunden, bitte prüfen");
+                chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+            }
+        };
+
+        let shares = shares_re
+            .captures(segment)
+            .and_then(|caps| Self::parse_amount(&caps[1]));
+        let price = Self::first_amount(&price_re, segment);
+        let gross = Self::first_amount(&gross_re, segment);
+        let net = Self::first_amount(&net_re, segment);
+        let fees: f64 = fee_re
+            .captures_iter(segment)
+            .filter_map(|caps| Self::parse_amount(&caps["amount"]))
+            .sum();
+        let taxes = extract_withheld_taxes(segment, ctx);
+
+        let currency = [&net, &gross, &price]
+            .iter()
+            .find_map(|v| v.as_ref().and_then(|(_, c)| c.clone()))
+            .unwrap_or_else(|| "EUR".to_string());
+
+        // Derive missing amounts from the others
+        let sign = match txn_type {
+            ParsedTransactionType::Buy => 1.0,
+            _ => -1.0,
+        };
+        let mut gross_amount = gross.map(|(v, _)| v).unwrap_or(0.0);
+        let mut net_amount = net.map(|(v, _)| v).unwrap_or(0.0);
+        if gross_amount == 0.0 {
+            if let (Some(s), Some((p, _))) = (shares, &price) {
+                gross_amount = s * p;
+            } else if net_amount > 0.0 {
+                gross_amount = net_amount - sign * (fees + taxes);
+            }
+        }
+        if net_amount == 0.0 && gross_amount > 0.0 {
+            net_amount = gross_amount + sign * (fees + taxes);
+        }
+
+        if gross_amount <= 0.0 && net_amount <= 0.0 && shares.is_none() {
+            return None;
+        }
+
+        Some(ParsedTransaction {
+            date,
+            time: None,
+            txn_type,
+            security_name: None,
+            isin: Some(isin),
+            wkn: None,
+            shares,
+            price_per_share: price.map(|(v, _)| v),
+            gross_amount,
+            fees,
+            taxes,
+            net_amount,
+            currency,
+            note: Some("Generischer Import (unbekannte Bank)".to_string()),
+            exchange_rate: None,
+            forex_currency: None,
+        })
+    }
+}
+
+impl BankParser for GenericParser {
+    /// Never auto-detected; only used when no bank-specific parser matches
+    fn detect(&self, _content: &str) -> bool {
+        false
+    }
+
+    fn parse(&self, content: &str, ctx: &mut ParseContext) -> Result<Vec<ParsedTransaction>, String> {
+        let document_type = Self::detect_type(content);
+
+        let mut transactions: Vec<ParsedTransaction> = Self::segments(content)
+            .into_iter()
+            .filter_map(|(isin, segment)| self.parse_segment(isin, segment, document_type, ctx))
+            .collect();
+
+        transactions.sort_by_key(|t| t.date);
+
+        Ok(transactions)
+    }
+
+    fn bank_name(&self) -> &'static str {
+        "Unbekannte Bank"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(GenericParser::parse_amount("1.234,56"), Some(1234.56));
+        assert_eq!(GenericParser::parse_amount("1,234.56"), Some(1234.56));
+        assert_eq!(GenericParser::parse_amount("99.90"), Some(99.90));
+        assert_eq!(GenericParser::parse_amount("10,00-"), Some(10.0));
+        assert_eq!(GenericParser::parse_amount("50"), Some(50.0));
+    }
+
+    #[test]
+    fn test_parse_unknown_broker_statement() {
+        let parser = GenericParser::new();
+        let mut ctx = ParseContext::new();
+
+        let content = r#"
+Musterbroker AG - Wertpapierabrechnung
+Verkauf
+Handelstag: 04.05.2023
+Apple Inc. US0378331005
+Stück: 10
+Kurs: USD 170.00
+Kurswert: USD 1,700.00
+Provision: USD 5.00
+Total: USD 1,695.00
+
+Kauf
+Handelstag 05.05.2023
+iShares Core MSCI World IE00B4L5Y983
+Anteile 20
+Kurs 80,00 EUR
+Kurswert 1.600,00 EUR
+"#;
+
+        let txns = parser.parse(content, &mut ctx).unwrap();
+        assert_eq!(txns.len(), 2);
+
+        let sell = &txns[0];
+        assert_eq!(sell.txn_type, ParsedTransactionType::Sell);
+        assert_eq!(sell.isin, Some("US0378331005".to_string()));
+        assert_eq!(sell.date, chrono::NaiveDate::from_ymd_opt(2023, 5, 4).unwrap());
+        assert_eq!(sell.shares, Some(10.0));
+        assert_eq!(sell.price_per_share, Some(170.0));
+        assert_eq!(sell.currency, "USD");
+        assert!((sell.gross_amount - 1700.0).abs() < 0.01);
+        assert!((sell.fees - 5.0).abs() < 0.01);
+        assert!((sell.net_amount - 1695.0).abs() < 0.01);
+
+        let buy = &txns[1];
+        assert_eq!(buy.txn_type, ParsedTransactionType::Buy);
+        assert_eq!(buy.isin, Some("IE00B4L5Y983".to_string()));
+        assert_eq!(buy.shares, Some(20.0));
+        assert!((buy.gross_amount - 1600.0).abs() < 0.01);
+        // No total on the statement: derived from the gross amount
+        assert!((buy.net_amount - 1600.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_without_isin_yields_nothing() {
+        let parser = GenericParser::new();
+        let mut ctx = ParseContext::new();
+        let txns = parser.parse("Kontoauszug\nSaldo 1.000,00 EUR", &mut ctx).unwrap();
+        assert!(txns.is_empty());
+    }
+}
@@ -37,7 +37,9 @@ pub mod olb;
 pub mod santander;
 pub mod raiffeisen;
 pub mod quirinbank;
+pub mod generic;
 pub mod ocr;
+pub mod provenance;
 
 use chrono::NaiveDate;
 use regex::Regex;
@@ -244,7 +246,7 @@ pub struct ParsedTransaction {
 }
 
 /// Transaction type parsed from PDF
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ParsedTransactionType {
     Buy,
     Sell,
@@ -295,6 +297,8 @@ pub struct ParseResult {
     pub transactions: Vec<ParsedTransaction>,
     pub warnings: Vec<ParseWarning>,
     pub raw_text: Option<String>,
+    /// Per-transaction field provenance, same order as `transactions`
+    pub provenance: Vec<provenance::TransactionProvenance>,
 }
 
 /// Bank parser trait
@@ -457,15 +461,53 @@ pub fn parse_pdf_content(content: &str) -> Result<ParseResult, String> {
     for parser in &parsers {
         if parser.detect(content) {
             let transactions = parser.parse(content, &mut ctx)?;
+            let provenance = transactions
+                .iter()
+                .enumerate()
+                .map(|(idx, txn)| provenance::build_provenance(content, idx, txn))
+                .collect();
             return Ok(ParseResult {
                 bank: parser.bank_name().to_string(),
                 transactions,
                 warnings: ctx.warnings,
                 raw_text: Some(content.to_string()),
+                provenance,
             });
         }
     }
 
+    // Unknown bank: best-effort candidates, every field needs a manual check
+    let generic = generic::GenericParser::new();
+    let transactions = generic.parse(content, &mut ctx)?;
+    if !transactions.is_empty() {
+        ctx.warnings.insert(
+            0,
+            ParseWarning {
+                field: "bank".to_string(),
+                raw_value: String::new(),
+                message: "Bank nicht erkannt, Transaktionen wurden generisch erkannt. Bitte alle Werte prüfen."
+                    .to_string(),
+                severity: WarningSeverity::Warning,
+            },
+        );
+        let provenance = transactions
+            .iter()
+            .enumerate()
+            .map(|(idx, txn)| {
+                let mut prov = provenance::build_provenance(content, idx, txn);
+                prov.cap_confidence(provenance::GENERIC_CONFIDENCE);
+                prov
+            })
+            .collect();
+        return Ok(ParseResult {
+            bank: generic.bank_name().to_string(),
+            transactions,
+            warnings: ctx.warnings,
+            raw_text: Some(content.to_string()),
+            provenance,
+        });
+    }
+
     Err("Could not detect bank from PDF content. Supported banks: DKB, ING, Comdirect, Consorsbank, Trade Republic, Scalable Capital, Commerzbank, Deutsche Bank, Postbank, Onvista, S-Broker, Targobank, 1822direkt, DAB BNP Paribas, Baader Bank, GENO Broker, flatex, ebase, DZ Bank, MLP Bank, Merkur Privatbank, OLB, Santander, Quirin Privatbank, Swissquote, UBS, Saxo Bank, DEGIRO, Revolut, PostFinance, ZKB, Credit Suisse, LGT Bank, Erste Bank, Raiffeisen".to_string())
 }
 
@@ -533,6 +575,24 @@ pub fn extract_wkn(text: &str) -> Option<String> {
     None
 }
 
+/// Sum the withheld taxes (KapESt, Soli, KiSt) listed in a statement
+///
+/// Takes the last amount on each tax line, so both "Kapitalertragsteuer : EUR 25,00"
+/// and "Kapitalertragsteuer 25,00 % auf 100,00 EUR 25,00- EUR" are handled.
+pub fn extract_withheld_taxes(text: &str, ctx: &mut ParseContext) -> f64 {
+    let tax_re = Regex::new(
+        r"(?m)^\s*(?:abzuf(?:ü|u)hrende\s+)?(Kapitalertrags?teuer|Solidarit(?:ä|a)tszuschlag|Kirchensteuer)\b[^\n]*?([\d.]+,\d{2})-?\s*(?:EUR)?\s*$",
+    );
+
+    match tax_re {
+        Ok(re) => re
+            .captures_iter(text)
+            .map(|caps| ctx.parse_amount(&caps[1], &caps[2]))
+            .sum(),
+        Err(_) => 0.0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -586,6 +646,20 @@ mod tests {
         assert_eq!(extract_wkn("ABC12"), None); // Too short
     }
 
+    #[test]
+    fn test_parse_pdf_content_generic_fallback() {
+        let content = "Musterbroker AG\nWertpapierkauf\nHandelstag 05.05.2023\n\
+                       iShares Core MSCI World IE00B4L5Y983\nAnteile 20\nKurswert 1.600,00 EUR";
+        let result = parse_pdf_content(content).unwrap();
+
+        assert_eq!(result.bank, "Unbekannte Bank");
+        assert_eq!(result.transactions.len(), 1);
+        assert_eq!(result.warnings[0].field, "bank");
+        assert!(result.provenance[0].confidence <= provenance::GENERIC_CONFIDENCE);
+
+        assert!(parse_pdf_content("Kontoauszug ohne Wertpapiere").is_err());
+    }
+
     #[test]
     fn test_parse_context_strict_mode_date() {
         // Strict mode (default) - should return Err for invalid date
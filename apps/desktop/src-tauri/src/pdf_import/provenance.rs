@@ -0,0 +1,365 @@
+//! Field-level provenance for parsed PDF transactions
+//!
+//! The bank parsers only return values. This module locates each value in the
+//! extracted text afterwards and rates how reliable it is, so the import review
+//! can highlight guessed or defaulted fields before anything is imported.
+//!
+//! Confidence levels:
+//! - 1.0: found verbatim on a line with a matching label (e.g. "Kurswert")
+//! - 0.8: found verbatim, but only on an unlabelled line
+//! - 0.5: not found in the text (derived value) or inconsistent with other fields
+//! - 0.3: ISIN with invalid check digit (typical OCR noise)
+//! - 0.0: missing or fallback value
+
+use super::{parse_german_decimal, ParsedTransaction, ParsedTransactionType};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Confidence below which the review should ask for a manual check
+pub const LOW_CONFIDENCE: f64 = 0.6;
+
+/// Upper bound for fields found by the generic parser (unknown bank layout)
+pub const GENERIC_CONFIDENCE: f64 = 0.5;
+
+/// Maximum length of the quoted source line
+const MAX_SOURCE_LEN: usize = 120;
+
+/// Where a single field came from and how reliable it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldProvenance {
+    /// Field name as serialized in `ParsedTransaction` (e.g. "isin", "grossAmount")
+    pub field: String,
+    pub confidence: f64,
+    /// Line of the extracted text the value was found on
+    pub source: Option<String>,
+    /// 1-based line number of `source`
+    pub line: Option<usize>,
+}
+
+/// Provenance of all fields of one parsed transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionProvenance {
+    pub transaction_index: usize,
+    /// Lowest field confidence
+    pub confidence: f64,
+    pub fields: Vec<FieldProvenance>,
+}
+
+impl TransactionProvenance {
+    /// Fields the user should double-check
+    pub fn low_confidence_fields(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|f| f.confidence < LOW_CONFIDENCE)
+            .map(|f| f.field.as_str())
+            .collect()
+    }
+
+    /// Limit all field confidences, e.g. for results of a heuristic parser
+    pub fn cap_confidence(&mut self, max: f64) {
+        for f in self.fields.iter_mut() {
+            f.confidence = f.confidence.min(max);
+        }
+        self.confidence = self.confidence.min(max);
+    }
+}
+
+/// Labels that typically precede a field on the statement (lowercase)
+fn field_labels(field: &str) -> &'static [&'static str] {
+    match field {
+        "date" => &["tag", "datum", "valuta", "zahlbar", "ausführung", "schluss", "date"],
+        "shares" => &["st.", "stk", "stück", "anteile", "nominale", "shares", "quantity"],
+        "pricePerShare" => &["kurs", "preis", "price"],
+        "grossAmount" => &["kurswert", "brutto", "betrag", "gross"],
+        "fees" => &["provision", "gebühr", "entgelt", "spesen", "fee"],
+        "taxes" => &["steuer", "solidarit", "tax"],
+        "netAmount" => &["betrag", "lasten", "gunsten", "netto", "total", "gutschrift"],
+        _ => &[],
+    }
+}
+
+/// Value located in the text
+struct Located {
+    line: usize,
+    text: String,
+    labelled: bool,
+}
+
+fn locate<F>(lines: &[&str], field: &str, mut matches: F) -> Option<Located>
+where
+    F: FnMut(&str) -> bool,
+{
+    let labels = field_labels(field);
+    let mut first: Option<Located> = None;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if !matches(line) {
+            continue;
+        }
+        // Identifiers (ISIN, WKN) are unambiguous on their own
+        let lower = line.to_lowercase();
+        let labelled = labels.is_empty() || labels.iter().any(|label| lower.contains(label));
+        let located = Located {
+            line: idx + 1,
+            text: line.trim().chars().take(MAX_SOURCE_LEN).collect(),
+            labelled,
+        };
+        if labelled {
+            return Some(located);
+        }
+        if first.is_none() {
+            first = Some(located);
+        }
+    }
+
+    first
+}
+
+/// Parse a numeric token in German ("1.234,56") or English ("1,234.56") notation
+fn parse_number_token(token: &str) -> Vec<f64> {
+    let mut values = Vec::new();
+    if let Some(v) = parse_german_decimal(token) {
+        values.push(v);
+    }
+    if let Ok(v) = token.replace([',', '\''], "").parse::<f64>() {
+        values.push(v);
+    }
+    values
+}
+
+fn line_contains_number(number_re: &Regex, line: &str, value: f64) -> bool {
+    let tolerance = (value.abs() * 1e-6).max(0.005);
+    number_re
+        .find_iter(line)
+        .flat_map(|m| parse_number_token(m.as_str()))
+        .any(|v| (v - value).abs() <= tolerance)
+}
+
+fn provenance_from(field: &str, located: Option<Located>) -> FieldProvenance {
+    match located {
+        Some(loc) => FieldProvenance {
+            field: field.to_string(),
+            confidence: if loc.labelled { 1.0 } else { 0.8 },
+            source: Some(loc.text),
+            line: Some(loc.line),
+        },
+        None => FieldProvenance {
+            field: field.to_string(),
+            confidence: 0.5,
+            source: None,
+            line: None,
+        },
+    }
+}
+
+fn missing(field: &str) -> FieldProvenance {
+    FieldProvenance {
+        field: field.to_string(),
+        confidence: 0.0,
+        source: None,
+        line: None,
+    }
+}
+
+fn cap(fields: &mut [FieldProvenance], names: &[&str], max: f64) {
+    for f in fields.iter_mut().filter(|f| names.contains(&f.field.as_str())) {
+        f.confidence = f.confidence.min(max);
+    }
+}
+
+/// Build the provenance for one transaction from the extracted text
+pub fn build_provenance(
+    content: &str,
+    transaction_index: usize,
+    txn: &ParsedTransaction,
+) -> TransactionProvenance {
+    let lines: Vec<&str> = content.lines().collect();
+    let number_re = Regex::new(r"\d[\d.,']*\d|\d").unwrap();
+    let mut fields = Vec::new();
+
+    // Date: parsers fall back to 2000-01-01 if no date was found
+    let fallback_date = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    if txn.date == fallback_date {
+        fields.push(missing("date"));
+    } else {
+        let german = txn.date.format("%d.%m.%Y").to_string();
+        let iso = txn.date.format("%Y-%m-%d").to_string();
+        let located = locate(&lines, "date", |l| l.contains(&german) || l.contains(&iso));
+        fields.push(provenance_from("date", located));
+    }
+
+    // Identifiers
+    match &txn.isin {
+        Some(isin) => {
+            let mut prov = provenance_from("isin", locate(&lines, "isin", |l| l.contains(isin.as_str())));
+            if !crate::commands::crud::validate_isin(isin) {
+                prov.confidence = prov.confidence.min(0.3);
+            }
+            fields.push(prov);
+        }
+        None if txn.wkn.is_none() && txn.txn_type.to_portfolio_type().is_some() => {
+            fields.push(missing("isin"));
+        }
+        None => {}
+    }
+    if let Some(wkn) = &txn.wkn {
+        fields.push(provenance_from("wkn", locate(&lines, "wkn", |l| l.contains(wkn.as_str()))));
+    }
+
+    // Numbers
+    let optional_numbers = [("shares", txn.shares), ("pricePerShare", txn.price_per_share)];
+    for (field, value) in optional_numbers {
+        match value {
+            Some(v) => fields.push(provenance_from(
+                field,
+                locate(&lines, field, |l| line_contains_number(&number_re, l, v)),
+            )),
+            None if field == "shares" && txn.txn_type.to_portfolio_type().is_some() => {
+                fields.push(missing(field))
+            }
+            None => {}
+        }
+    }
+
+    let amounts = [
+        ("grossAmount", txn.gross_amount),
+        ("fees", txn.fees),
+        ("taxes", txn.taxes),
+        ("netAmount", txn.net_amount),
+    ];
+    for (field, value) in amounts {
+        if value == 0.0 {
+            // Zero fees/taxes are normal; zero totals are suspicious
+            if field == "netAmount" {
+                fields.push(missing(field));
+            }
+            continue;
+        }
+        fields.push(provenance_from(
+            field,
+            locate(&lines, field, |l| line_contains_number(&number_re, l, value)),
+        ));
+    }
+
+    check_consistency(txn, &mut fields);
+
+    let confidence = fields.iter().map(|f| f.confidence).fold(1.0, f64::min);
+    TransactionProvenance {
+        transaction_index,
+        confidence,
+        fields,
+    }
+}
+
+/// Cross-check shares × price against the gross amount and the gross amount
+/// against the total. Summed fees or taxes that are not on any single line
+/// become trustworthy if the total adds up.
+fn check_consistency(txn: &ParsedTransaction, fields: &mut [FieldProvenance]) {
+    if let (Some(shares), Some(price)) = (txn.shares, txn.price_per_share) {
+        let same_currency = txn.exchange_rate.is_none() && txn.forex_currency.is_none();
+        if same_currency && txn.gross_amount > 0.0 {
+            let deviation = (shares * price - txn.gross_amount).abs() / txn.gross_amount;
+            if deviation > 0.01 {
+                cap(fields, &["shares", "pricePerShare", "grossAmount"], 0.5);
+            }
+        }
+    }
+
+    if txn.gross_amount <= 0.0 || txn.net_amount == 0.0 {
+        return;
+    }
+    let expected_net = match txn.txn_type {
+        ParsedTransactionType::Buy => txn.gross_amount + txn.fees + txn.taxes,
+        ParsedTransactionType::Sell | ParsedTransactionType::Dividend | ParsedTransactionType::Interest => {
+            txn.gross_amount - txn.fees - txn.taxes
+        }
+        _ => return,
+    };
+
+    if (expected_net - txn.net_amount.abs()).abs() <= 0.02 {
+        for f in fields.iter_mut().filter(|f| matches!(f.field.as_str(), "fees" | "taxes")) {
+            if f.source.is_none() {
+                f.confidence = 0.9;
+            }
+        }
+    } else {
+        cap(fields, &["fees", "taxes", "netAmount"], 0.5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy() -> ParsedTransaction {
+        ParsedTransaction {
+            date: chrono::NaiveDate::from_ymd_opt(2021, 2, 12).unwrap(),
+            time: None,
+            txn_type: ParsedTransactionType::Buy,
+            security_name: None,
+            isin: Some("IE00B4L5Y983".to_string()),
+            wkn: None,
+            shares: Some(50.0),
+            price_per_share: Some(69.53),
+            gross_amount: 3476.50,
+            fees: 10.0,
+            taxes: 0.0,
+            net_amount: 3486.50,
+            currency: "EUR".to_string(),
+            note: None,
+            exchange_rate: None,
+            forex_currency: None,
+        }
+    }
+
+    const CONTENT: &str = "Wertpapier Abrechnung Kauf
+Stück 50 ISHSIII-CORE MSCI WORLD U.ETF IE00B4L5Y983 (A0RPWH)
+Schlusstag/-Zeit 12.02.2021 09:30:12
+Ausführungskurs 69,53 EUR
+Kurswert 3.476,50- EUR
+Provision 10,00- EUR
+Ausmachender Betrag 3.486,50- EUR";
+
+    fn field<'a>(prov: &'a TransactionProvenance, name: &str) -> &'a FieldProvenance {
+        prov.fields.iter().find(|f| f.field == name).unwrap()
+    }
+
+    #[test]
+    fn test_build_provenance_locates_fields() {
+        let prov = build_provenance(CONTENT, 0, &buy());
+
+        let isin = field(&prov, "isin");
+        assert_eq!(isin.line, Some(2));
+        assert_eq!(isin.confidence, 1.0);
+        assert_eq!(field(&prov, "date").line, Some(3));
+        assert_eq!(field(&prov, "pricePerShare").line, Some(4));
+        assert_eq!(field(&prov, "grossAmount").source.as_deref(), Some("Kurswert 3.476,50- EUR"));
+        assert_eq!(field(&prov, "netAmount").line, Some(7));
+        assert_eq!(prov.confidence, 1.0);
+        assert!(prov.low_confidence_fields().is_empty());
+    }
+
+    #[test]
+    fn test_build_provenance_flags_noise() {
+        let mut txn = buy();
+        // OCR misread: 8 instead of B, and a default date
+        txn.isin = Some("IE0084L5Y983".to_string());
+        txn.date = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        txn.net_amount = 3468.50;
+
+        let prov = build_provenance(CONTENT, 3, &txn);
+        assert_eq!(prov.transaction_index, 3);
+        assert_eq!(field(&prov, "date").confidence, 0.0);
+        assert!(field(&prov, "isin").confidence <= 0.3);
+        assert!(field(&prov, "netAmount").source.is_none());
+
+        let low = prov.low_confidence_fields();
+        assert!(low.contains(&"date"));
+        assert!(low.contains(&"isin"));
+        assert!(low.contains(&"fees"));
+        assert!(low.contains(&"netAmount"));
+        assert!(!low.contains(&"grossAmount"));
+    }
+}
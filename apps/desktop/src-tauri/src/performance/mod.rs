@@ -85,6 +85,12 @@ use rusqlite::{params, Connection};
 // Use centralized date parsing from pp::common (SSOT)
 use crate::pp::parse_date_flexible;
 
+// Cash balances of accounts (shared module)
+use crate::cash::{
+    get_account_balance_converted, get_linked_account_ids, get_total_cash_balance_at_rates,
+    get_total_cash_balance_converted,
+};
+
 /// Scale factors
 const SHARES_SCALE: f64 = 100_000_000.0;
 const AMOUNT_SCALE: f64 = 100.0;
@@ -170,7 +176,7 @@ pub fn calculate_ttwror(
     }
 
     // Get portfolio value history (daily values where we have price data)
-    let valuations = get_ttwror_portfolio_values(conn, portfolio_id, start_date, end_date)?;
+    let valuations = get_ttwror_portfolio_values(conn, portfolio_id, start_date, end_date, None)?;
 
     if valuations.len() < 2 {
         log::warn!("TTWROR: Not enough valuation data points ({}), falling back to simple return", valuations.len());
@@ -178,7 +184,7 @@ pub fn calculate_ttwror(
     }
 
     // Get external cash flows (DEPOSIT/REMOVAL only)
-    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date)?;
+    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
 
     log::info!(
         "TTWROR: {} valuations, {} cash flows, {} days",
@@ -232,12 +238,12 @@ pub fn calculate_ttwror_for_portfolios(
     let mut cash_flows: Vec<CashFlow> = Vec::new();
 
     for pid in portfolio_ids {
-        let values = get_ttwror_portfolio_values(conn, Some(*pid), start_date, end_date)?;
+        let values = get_ttwror_portfolio_values(conn, Some(*pid), start_date, end_date, None)?;
         for (date, value) in values {
             *aggregated_values.entry(date).or_insert(0.0) += value;
         }
 
-        let flows = get_cash_flows(conn, Some(*pid), start_date, end_date)?;
+        let flows = get_cash_flows(conn, Some(*pid), start_date, end_date, None)?;
         cash_flows.extend(flows);
     }
 
@@ -296,6 +302,157 @@ pub fn calculate_ttwror_for_portfolios(
     })
 }
 
+/// Portfolio TTWROR with and without currency effects
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyEffect {
+    pub start_date: String,
+    pub end_date: String,
+    pub base_currency: String,
+    /// TTWROR with daily exchange rates (as in calculate_ttwror) as percentage
+    pub ttwror: f64,
+    /// TTWROR with exchange rates frozen at the start date as percentage
+    pub ttwror_constant_fx: f64,
+    /// Difference in percentage points (ttwror - ttwror_constant_fx)
+    pub currency_effect: f64,
+}
+
+/// Split the portfolio TTWROR into security performance and currency effect
+///
+/// The TTWROR is calculated twice on the same valuation dates: once with the
+/// exchange rates of each day and once with all values and cash flows converted
+/// at the rates of the start date. The difference is the currency contribution.
+pub fn calculate_currency_effect(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<CurrencyEffect> {
+    use crate::currency;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    let valuations = get_ttwror_portfolio_values(conn, portfolio_id, start_date, end_date, None)?;
+    if valuations.len() < 2 {
+        anyhow::bail!("Nicht genügend Bewertungsdaten im Zeitraum");
+    }
+    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
+    let (total_return, _) = calculate_ttwror_from_data(&valuations, &cash_flows);
+
+    let fx_date = Some(valuations[0].0);
+    let valuations_constant =
+        get_ttwror_portfolio_values(conn, portfolio_id, start_date, end_date, fx_date)?;
+    let cash_flows_constant = get_cash_flows(conn, portfolio_id, start_date, end_date, fx_date)?;
+    let (total_return_constant, _) =
+        calculate_ttwror_from_data(&valuations_constant, &cash_flows_constant);
+
+    Ok(CurrencyEffect {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        base_currency,
+        ttwror: total_return * 100.0,
+        ttwror_constant_fx: total_return_constant * 100.0,
+        currency_effect: (total_return - total_return_constant) * 100.0,
+    })
+}
+
+/// TTWROR over one of the standard fact-sheet periods
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardPeriodReturn {
+    /// "YTD", "1M", "3M", "6M", "1Y", "3Y", "5Y" or "MAX" (since inception)
+    pub period: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// TTWROR as percentage
+    pub ttwror: f64,
+    /// Annualized TTWROR as percentage (only for periods longer than one year)
+    pub ttwror_annualized: Option<f64>,
+    /// Portfolio is younger than the period, start clamped to inception
+    pub partial_period: bool,
+}
+
+/// Calculate the standard return table (YTD, 1M, 3M, 6M, 1Y, 3Y, 5Y, since inception)
+///
+/// All windows end at the latest valuation date (last price date up to `today`)
+/// and start at the same day one period earlier; YTD starts at the last day of the
+/// previous year. Windows reaching back before the first portfolio transaction are
+/// clamped to it and flagged as partial.
+pub fn calculate_standard_period_returns(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    today: NaiveDate,
+) -> Result<Vec<StandardPeriodReturn>> {
+    use chrono::{Datelike, Months};
+
+    let portfolio_filter = portfolio_id
+        .map(|id| format!("AND owner_id = {}", id))
+        .unwrap_or_default();
+
+    let inception: Option<String> = conn.query_row(
+        &format!(
+            "SELECT MIN(date(date)) FROM pp_txn WHERE owner_type = 'portfolio' {}",
+            portfolio_filter
+        ),
+        [],
+        |row| row.get(0),
+    )?;
+    let Some(inception) = inception.as_deref().and_then(parse_date_flexible) else {
+        return Ok(Vec::new());
+    };
+
+    let latest: Option<String> = conn.query_row(
+        &format!(
+            r#"
+            SELECT MAX(date(date)) FROM pp_price
+            WHERE date(date) <= ?1
+              AND security_id IN (SELECT security_id FROM pp_txn WHERE owner_type = 'portfolio' {})
+            "#,
+            portfolio_filter
+        ),
+        [today.to_string()],
+        |row| row.get(0),
+    )?;
+    let end_date = latest
+        .as_deref()
+        .and_then(parse_date_flexible)
+        .unwrap_or(today);
+    if end_date <= inception {
+        return Ok(Vec::new());
+    }
+
+    let months_back = |months: u32| end_date.checked_sub_months(Months::new(months));
+    let windows = [
+        ("YTD", NaiveDate::from_ymd_opt(end_date.year() - 1, 12, 31)),
+        ("1M", months_back(1)),
+        ("3M", months_back(3)),
+        ("6M", months_back(6)),
+        ("1Y", months_back(12)),
+        ("3Y", months_back(36)),
+        ("5Y", months_back(60)),
+        ("MAX", Some(inception)),
+    ];
+
+    let mut returns = Vec::with_capacity(windows.len());
+    for (period, start) in windows {
+        let Some(start) = start else { continue };
+        let partial_period = start < inception;
+        let start_date = start.max(inception);
+
+        let result = calculate_ttwror(conn, portfolio_id, start_date, end_date)?;
+        returns.push(StandardPeriodReturn {
+            period: period.to_string(),
+            start_date: start_date.to_string(),
+            end_date: end_date.to_string(),
+            ttwror: result.total_return * 100.0,
+            ttwror_annualized: (result.days > 365).then_some(result.annualized_return * 100.0),
+            partial_period,
+        });
+    }
+
+    Ok(returns)
+}
+
 /// Calculate TTWROR from valuation and cash flow data
 ///
 /// Algorithm:
@@ -488,11 +645,15 @@ fn calculate_ttwror_simple_fallback(
 }
 
 /// Get portfolio values for TTWROR calculation with currency conversion
+///
+/// With `fx_date`, all values are converted at the rates of that date instead of
+/// the rates of each valuation date (constant FX, no currency effect).
 fn get_ttwror_portfolio_values(
     conn: &Connection,
     portfolio_id: Option<i64>,
     start_date: NaiveDate,
     end_date: NaiveDate,
+    fx_date: Option<NaiveDate>,
 ) -> Result<Vec<(NaiveDate, f64)>> {
     use crate::currency;
 
@@ -590,7 +751,7 @@ fn get_ttwror_portfolio_values(
 
                     // Convert to base currency using the same date for consistency
                     let value_base = if !convert_currency.is_empty() && convert_currency != base_currency {
-                        currency::convert(conn, value, convert_currency, &base_currency, date)
+                        currency::convert(conn, value, convert_currency, &base_currency, fx_date.unwrap_or(date))
                             .unwrap_or(value)
                     } else {
                         value
@@ -603,7 +764,7 @@ fn get_ttwror_portfolio_values(
 
         // Add cash balance from linked accounts (Phase 2: NAV inkl. Cash)
         if let Some(pid) = portfolio_id {
-            if let Ok(cash) = get_total_cash_balance_converted(conn, pid, date, &base_currency) {
+            if let Ok(cash) = get_total_cash_balance_at_rates(conn, pid, date, fx_date.unwrap_or(date), &base_currency) {
                 total_value += cash;
             }
         }
@@ -617,31 +778,49 @@ fn get_ttwror_portfolio_values(
     Ok(values)
 }
 
-/// Get portfolio value at a specific date with currency conversion to base currency
+/// Holding of a security at a specific date
+#[derive(Debug, Clone)]
+pub struct HoldingAtDate {
+    pub security_id: i64,
+    pub name: String,
+    pub isin: Option<String>,
+    /// Security currency
+    pub currency: String,
+    pub shares: f64,
+    /// Price in security currency (at or before the date, else latest price)
+    pub price: Option<f64>,
+    pub price_date: Option<NaiveDate>,
+    /// Value in security currency
+    pub value: Option<f64>,
+    /// Value converted to base currency using the FX rate of the date
+    pub value_base: Option<f64>,
+}
+
+/// Get holdings (net shares per security) as of a specific date
 ///
-/// Fix: Now takes valuation_date parameter instead of always using today.
-/// This enables correct historical TTWROR calculations.
-pub fn get_portfolio_value_at_date_with_currency(
+/// Shares are summed from portfolio transactions up to and including the date,
+/// valued with the last known price at or before that date.
+pub fn get_holdings_at_date(
     conn: &Connection,
     portfolio_id: Option<i64>,
-    valuation_date: NaiveDate,
-) -> Result<f64> {
+    date: NaiveDate,
+    base_currency: &str,
+) -> Result<Vec<HoldingAtDate>> {
     use crate::currency;
 
-    // Get base currency
-    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
-
     let portfolio_filter = portfolio_id
         .map(|id| format!("AND t.owner_id = {}", id))
         .unwrap_or_default();
 
-    let date_str = valuation_date.to_string();
+    let date_str = date.to_string();
 
-    // Get holdings as of valuation_date with security currency
+    // Get holdings as of date with security currency
     let holdings_sql = format!(
         r#"
         SELECT
             t.security_id,
+            s.name,
+            s.isin,
             s.currency,
             SUM(CASE
                 WHEN t.txn_type IN ('BUY', 'TRANSFER_IN', 'DELIVERY_INBOUND') THEN t.shares
@@ -656,72 +835,108 @@ pub fn get_portfolio_value_at_date_with_currency(
           {}
         GROUP BY t.security_id
         HAVING net_shares > 0
+        ORDER BY s.name
         "#,
         portfolio_filter
     );
 
-    let mut total_value = 0.0;
-
-    {
-        let mut stmt = conn.prepare(&holdings_sql)?;
-        let rows = stmt.query_map([&date_str], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,                                // security_id
-                row.get::<_, Option<String>>(1)?.unwrap_or_default(), // currency
-                row.get::<_, i64>(2)?,                                // net_shares
-            ))
-        })?;
+    let mut stmt = conn.prepare(&holdings_sql)?;
+    let rows = stmt.query_map([&date_str], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,                                // security_id
+            row.get::<_, Option<String>>(1)?.unwrap_or_default(), // name
+            row.get::<_, Option<String>>(2)?,                     // isin
+            row.get::<_, Option<String>>(3)?.unwrap_or_default(), // currency
+            row.get::<_, i64>(4)?,                                // net_shares
+        ))
+    })?;
 
-        for row in rows.flatten() {
-            let (security_id, security_currency, share_count) = row;
+    let mut holdings = Vec::new();
+    for row in rows.flatten() {
+        let (security_id, name, isin, security_currency, share_count) = row;
 
-            // Get price at or before valuation_date (not always latest!)
-            let price_sql = r#"
-                SELECT value FROM pp_price
-                WHERE security_id = ?1 AND date(date) <= ?2
-                ORDER BY date DESC LIMIT 1
-            "#;
+        // Get price at or before date (not always latest!)
+        let price_sql = r#"
+            SELECT value, date FROM pp_price
+            WHERE security_id = ?1 AND date(date) <= ?2
+            ORDER BY date DESC LIMIT 1
+        "#;
 
-            let price: Option<i64> = conn
-                .query_row(price_sql, params![security_id, date_str], |row| row.get(0))
+        let price: Option<(i64, Option<String>)> = conn
+            .query_row(price_sql, params![security_id, date_str], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .ok()
+            .or_else(|| {
+                // Fallback to latest_price if no historical price found
+                conn.query_row(
+                    "SELECT value, date FROM pp_latest_price WHERE security_id = ?1",
+                    [security_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
                 .ok()
-                .or_else(|| {
-                    // Fallback to latest_price if no historical price found
-                    conn.query_row(
-                        "SELECT value FROM pp_latest_price WHERE security_id = ?1",
-                        [security_id],
-                        |row| row.get(0),
-                    )
-                    .ok()
-                });
-
-            if let Some(p) = price {
-                let shares_f = share_count as f64 / SHARES_SCALE;
-                let mut price_f = p as f64 / 100_000_000.0;
+            });
 
-                // GBX/GBp correction
-                let convert_currency = if security_currency == "GBX" || security_currency == "GBp" {
-                    price_f /= 100.0;
-                    "GBP"
+        let shares_f = share_count as f64 / SHARES_SCALE;
+        let price_f = price.as_ref().map(|(p, _)| *p as f64 / 100_000_000.0);
+        let price_date = price
+            .and_then(|(_, d)| d)
+            .and_then(|d| parse_date_flexible(&d));
+        let value = price_f.map(|p| shares_f * p);
+
+        let value_base = value.map(|value| {
+            // GBX/GBp correction
+            let (value, convert_currency) =
+                if security_currency == "GBX" || security_currency == "GBp" {
+                    (value / 100.0, "GBP")
                 } else {
-                    security_currency.as_str()
+                    (value, security_currency.as_str())
                 };
 
-                let value_in_security_currency = shares_f * price_f;
-
-                // Convert to base currency using valuation_date for FX rate
-                let value_in_base = if !convert_currency.is_empty() && convert_currency != base_currency.as_str() {
-                    currency::convert(conn, value_in_security_currency, convert_currency, &base_currency, valuation_date)
-                        .unwrap_or(value_in_security_currency)
-                } else {
-                    value_in_security_currency
-                };
-
-                total_value += value_in_base;
+            // Convert to base currency using the date for FX rate
+            if !convert_currency.is_empty() && convert_currency != base_currency {
+                currency::convert(conn, value, convert_currency, base_currency, date)
+                    .unwrap_or(value)
+            } else {
+                value
             }
-        }
+        });
+
+        holdings.push(HoldingAtDate {
+            security_id,
+            name,
+            isin,
+            currency: security_currency,
+            shares: shares_f,
+            price: price_f,
+            price_date,
+            value,
+            value_base,
+        });
     }
 
+    Ok(holdings)
+}
+
+/// Get portfolio value at a specific date with currency conversion to base currency
+///
+/// Fix: Now takes valuation_date parameter instead of always using today.
+/// This enables correct historical TTWROR calculations.
+pub fn get_portfolio_value_at_date_with_currency(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    valuation_date: NaiveDate,
+) -> Result<f64> {
+    use crate::currency;
+
+    // Get base currency
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+
+    let mut total_value: f64 = get_holdings_at_date(conn, portfolio_id, valuation_date, &base_currency)?
+        .iter()
+        .filter_map(|h| h.value_base)
+        .sum();
+
     // Add cash balance from linked accounts
     if let Some(pid) = portfolio_id {
         if let Ok(cash) = get_total_cash_balance_converted(conn, pid, valuation_date, &base_currency) {
@@ -1127,6 +1342,7 @@ fn get_cash_flows(
     portfolio_id: Option<i64>,
     start_date: NaiveDate,
     end_date: NaiveDate,
+    fx_date: Option<NaiveDate>,
 ) -> Result<Vec<CashFlow>> {
     use crate::currency;
 
@@ -1199,7 +1415,7 @@ fn get_cash_flows(
                 if cf_amount != 0.0 {
                     // Convert to base currency (Phase 3 fix)
                     let cf_base = if account_currency != base_currency && !account_currency.is_empty() {
-                        currency::convert(conn, cf_amount, &account_currency, &base_currency, date)
+                        currency::convert(conn, cf_amount, &account_currency, &base_currency, fx_date.unwrap_or(date))
                             .unwrap_or(cf_amount)
                     } else {
                         cf_amount
@@ -1254,7 +1470,7 @@ fn get_cash_flows(
                 if cf_amount != 0.0 {
                     // Convert to base currency (Phase 3 fix)
                     let cf_base = if account_currency != base_currency && !account_currency.is_empty() {
-                        currency::convert(conn, cf_amount, &account_currency, &base_currency, date)
+                        currency::convert(conn, cf_amount, &account_currency, &base_currency, fx_date.unwrap_or(date))
                             .unwrap_or(cf_amount)
                     } else {
                         cf_amount
@@ -1268,7 +1484,7 @@ fn get_cash_flows(
 
     // Include DELIVERY_INBOUND/OUTBOUND as external cash flows for TTWROR
     // (security transfers in/out should not count as investment performance)
-    let delivery_flows = get_delivery_cash_flows(conn, portfolio_id, start_date, end_date)?;
+    let delivery_flows = get_delivery_cash_flows(conn, portfolio_id, start_date, end_date, fx_date)?;
     let delivery_count = delivery_flows.len();
     cash_flows.extend(delivery_flows);
 
@@ -1304,12 +1520,12 @@ pub fn get_cash_flows_with_fallback(
     end_date: NaiveDate,
 ) -> Result<Vec<CashFlow>> {
     // Start with DEPOSIT/REMOVAL
-    let mut cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date)?;
+    let mut cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
     let deposit_removal_count = cash_flows.len();
 
     // ALWAYS add DELIVERY_INBOUND/OUTBOUND - these are external asset flows with monetary value
     // (like receiving/sending securities from/to another broker)
-    let delivery_flows = get_delivery_cash_flows(conn, portfolio_id, start_date, end_date)?;
+    let delivery_flows = get_delivery_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
     let delivery_count = delivery_flows.len();
     cash_flows.extend(delivery_flows);
 
@@ -1420,6 +1636,7 @@ fn get_delivery_cash_flows(
     portfolio_id: Option<i64>,
     start_date: NaiveDate,
     end_date: NaiveDate,
+    fx_date: Option<NaiveDate>,
 ) -> Result<Vec<CashFlow>> {
     use crate::currency;
 
@@ -1479,7 +1696,7 @@ fn get_delivery_cash_flows(
             if cf_amount != 0.0 {
                 // Convert to base currency
                 let cf_base = if txn_currency != base_currency && !txn_currency.is_empty() {
-                    currency::convert(conn, cf_amount, &txn_currency, &base_currency, date)
+                    currency::convert(conn, cf_amount, &txn_currency, &base_currency, fx_date.unwrap_or(date))
                         .unwrap_or(cf_amount)
                 } else {
                     cf_amount
@@ -1677,10 +1894,300 @@ fn get_current_portfolio_value(conn: &Connection, portfolio_id: Option<i64>) ->
     Ok(total_value)
 }
 
+// =====================================================
+// Position Performance: TTWROR and IRR of a single security
+// =====================================================
+
+/// Performance of a single position (all values in base currency)
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionPerformance {
+    pub security_id: i64,
+    pub portfolio_id: Option<i64>,
+    /// Date of the first transaction
+    pub start_date: String,
+    pub end_date: String,
+    pub days: i64,
+    pub shares: f64,
+    pub current_value: f64,
+    /// Purchases and inbound deliveries
+    pub total_invested: f64,
+    /// Sale proceeds, outbound deliveries and dividends
+    pub total_returned: f64,
+    pub dividends: f64,
+    /// current value + returned - invested
+    pub absolute_gain: f64,
+    /// TTWROR as percentage
+    pub ttwror: f64,
+    /// Annualized TTWROR as percentage
+    pub ttwror_annualized: f64,
+    /// IRR as percentage
+    pub irr: f64,
+    pub irr_converged: bool,
+}
+
+/// Calculate TTWROR and IRR for one security, optionally limited to one portfolio
+///
+/// The position is treated like a portfolio of its own: purchases are inflows,
+/// sales and dividends are outflows, and the value is shares × price of each
+/// day with a price (converted to base currency). Dividends are taken from the
+/// accounts linked to the portfolio when a portfolio is given.
+pub fn calculate_position_performance(
+    conn: &Connection,
+    security_id: i64,
+    portfolio_id: Option<i64>,
+    end_date: NaiveDate,
+) -> Result<PositionPerformance> {
+    use crate::currency;
+
+    let base_currency = currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let security_currency: String = conn
+        .query_row(
+            "SELECT currency FROM pp_security WHERE id = ?1",
+            [security_id],
+            |row| row.get::<_, Option<String>>(0),
+        )?
+        .unwrap_or_else(|| base_currency.clone());
+    // GBX/GBp prices are quoted in pence
+    let (price_currency, price_divisor) = match security_currency.as_str() {
+        "GBX" | "GBp" => ("GBP".to_string(), 100.0),
+        _ => (security_currency.clone(), 1.0),
+    };
+    let to_base = |amount: f64, from: &str, date: NaiveDate| {
+        if from.is_empty() || from == base_currency {
+            amount
+        } else {
+            currency::convert(conn, amount, from, &base_currency, date).unwrap_or(amount)
+        }
+    };
+
+    // Share changes and cash flows from portfolio transactions
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT date(t.date), t.txn_type, COALESCE(t.shares, 0), t.amount, t.currency
+        FROM pp_txn t
+        WHERE t.owner_type = 'portfolio'
+          AND t.security_id = ?1
+          AND (?2 IS NULL OR t.owner_id = ?2)
+          AND t.txn_type IN ('BUY', 'SELL', 'TRANSFER_IN', 'TRANSFER_OUT',
+                             'DELIVERY_INBOUND', 'DELIVERY_OUTBOUND')
+          AND date(t.date) <= ?3
+        ORDER BY t.date, t.id
+        "#,
+    )?;
+    let txns: Vec<(String, String, i64, i64, Option<String>)> = stmt
+        .query_map(params![security_id, portfolio_id, end_date.to_string()], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut share_changes: Vec<(NaiveDate, i64)> = Vec::new();
+    let mut cash_flows: Vec<CashFlow> = Vec::new();
+    let mut total_invested = 0.0;
+    let mut total_returned = 0.0;
+    for (date_str, txn_type, shares, amount, txn_currency) in txns {
+        let Some(date) = parse_date_flexible(&date_str) else {
+            continue;
+        };
+        let amount = to_base(
+            amount as f64 / AMOUNT_SCALE,
+            txn_currency.as_deref().unwrap_or(&base_currency),
+            date,
+        );
+        let inbound = matches!(txn_type.as_str(), "BUY" | "TRANSFER_IN" | "DELIVERY_INBOUND");
+        if inbound {
+            share_changes.push((date, shares));
+            total_invested += amount;
+            cash_flows.push(CashFlow { date, amount });
+        } else {
+            share_changes.push((date, -shares));
+            total_returned += amount;
+            cash_flows.push(CashFlow { date, amount: -amount });
+        }
+    }
+
+    let Some(start_date) = share_changes.first().map(|(d, _)| *d) else {
+        anyhow::bail!("Keine Transaktionen für dieses Wertpapier vorhanden");
+    };
+
+    // Dividends paid for the position are outflows from its point of view
+    let account_filter = match portfolio_id {
+        Some(pid) => {
+            let ids = get_linked_account_ids(conn, pid)?;
+            if ids.is_empty() {
+                Some("AND 0".to_string())
+            } else {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                Some(format!("AND t.owner_id IN ({})", ids.join(",")))
+            }
+        }
+        None => None,
+    };
+    let dividends_sql = format!(
+        r#"
+        SELECT date(t.date), t.amount, t.currency
+        FROM pp_txn t
+        WHERE t.owner_type = 'account'
+          AND t.txn_type = 'DIVIDENDS'
+          AND t.security_id = ?1
+          AND date(t.date) >= ?2 AND date(t.date) <= ?3
+          {}
+        "#,
+        account_filter.unwrap_or_default()
+    );
+    let mut stmt = conn.prepare(&dividends_sql)?;
+    let dividend_rows: Vec<(String, i64, Option<String>)> = stmt
+        .query_map(
+            params![security_id, start_date.to_string(), end_date.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut dividends = 0.0;
+    for (date_str, amount, txn_currency) in dividend_rows {
+        let Some(date) = parse_date_flexible(&date_str) else {
+            continue;
+        };
+        let amount = to_base(
+            amount as f64 / AMOUNT_SCALE,
+            txn_currency.as_deref().unwrap_or(&base_currency),
+            date,
+        );
+        dividends += amount;
+        cash_flows.push(CashFlow { date, amount: -amount });
+    }
+    total_returned += dividends;
+    cash_flows.sort_by_key(|cf| cf.date);
+
+    // Prices up to the end date, the latest price fills in a newer quote
+    let mut stmt = conn.prepare(
+        "SELECT date(date), value FROM pp_price WHERE security_id = ?1 AND date(date) <= ?2 ORDER BY date",
+    )?;
+    let mut prices: Vec<(NaiveDate, f64)> = stmt
+        .query_map(params![security_id, end_date.to_string()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .filter_map(|r| r.ok())
+        .filter_map(|(d, v)| parse_date_flexible(&d).map(|d| (d, v as f64 / 100_000_000.0 / price_divisor)))
+        .collect();
+    let latest: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT date(date), value FROM pp_latest_price WHERE security_id = ?1",
+            [security_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+    if let Some((date, value)) = latest.and_then(|(d, v)| parse_date_flexible(&d).map(|d| (d, v))) {
+        if date <= end_date && prices.last().map_or(true, |(last, _)| date > *last) {
+            prices.push((date, value as f64 / 100_000_000.0 / price_divisor));
+        }
+    }
+
+    // Value the position on every price date and every cash flow date
+    let mut dates: std::collections::BTreeSet<NaiveDate> = prices
+        .iter()
+        .map(|(d, _)| *d)
+        .filter(|d| *d >= start_date)
+        .collect();
+    dates.extend(cash_flows.iter().map(|cf| cf.date));
+    dates.insert(end_date);
+
+    let mut valuations: Vec<(NaiveDate, f64)> = Vec::new();
+    let mut shares: i64 = 0;
+    let mut change_idx = 0;
+    let mut price_idx = 0;
+    let mut price: Option<f64> = None;
+    for date in dates {
+        while change_idx < share_changes.len() && share_changes[change_idx].0 <= date {
+            shares += share_changes[change_idx].1;
+            change_idx += 1;
+        }
+        while price_idx < prices.len() && prices[price_idx].0 <= date {
+            price = Some(prices[price_idx].1);
+            price_idx += 1;
+        }
+        if let Some(price) = price {
+            let value = shares.max(0) as f64 / SHARES_SCALE * price;
+            valuations.push((date, to_base(value, &price_currency, date)));
+        }
+    }
+
+    let current_value = valuations
+        .last()
+        .filter(|(d, _)| *d == end_date)
+        .map_or(0.0, |(_, v)| *v);
+
+    let days = (end_date - start_date).num_days();
+    let (total_return, _) = calculate_ttwror_from_data(&valuations, &cash_flows);
+    let annualized_return = if days > 0 && total_return > -1.0 {
+        (1.0 + total_return).powf(365.0 / days as f64) - 1.0
+    } else {
+        0.0
+    };
+    let irr = calculate_irr(&cash_flows, current_value, end_date)?;
+
+    Ok(PositionPerformance {
+        security_id,
+        portfolio_id,
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        days,
+        shares: shares.max(0) as f64 / SHARES_SCALE,
+        current_value,
+        total_invested,
+        total_returned,
+        dividends,
+        absolute_gain: current_value + total_returned - total_invested,
+        ttwror: total_return * 100.0,
+        ttwror_annualized: annualized_return * 100.0,
+        irr: irr.irr * 100.0,
+        irr_converged: irr.converged,
+    })
+}
+
 // =====================================================
 // Risk Metrics: Sharpe, Sortino, Drawdown, Volatility, Beta
 // =====================================================
 
+/// Concentration of a portfolio across its positions
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcentrationMetrics {
+    /// Number of positions with a positive value
+    pub positions: usize,
+    /// Herfindahl-Hirschman Index of the position weights (0-1, 1 = single position)
+    pub hhi: f64,
+    /// Effective number of holdings (1 / HHI)
+    pub effective_holdings: f64,
+    /// Cumulative weight of the 5 largest positions in percent
+    pub top5_weight: f64,
+    /// Cumulative weight of the 10 largest positions in percent
+    pub top10_weight: f64,
+}
+
+/// Calculate concentration metrics from position values (in a common currency)
+///
+/// Positions without a positive value are ignored.
+pub fn calculate_concentration_metrics(values: &[f64]) -> ConcentrationMetrics {
+    let mut values: Vec<f64> = values.iter().copied().filter(|v| *v > 0.0).collect();
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return ConcentrationMetrics::default();
+    }
+
+    values.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let weights: Vec<f64> = values.iter().map(|v| v / total).collect();
+    let hhi: f64 = weights.iter().map(|w| w * w).sum();
+
+    ConcentrationMetrics {
+        positions: weights.len(),
+        hhi,
+        effective_holdings: 1.0 / hhi,
+        top5_weight: weights.iter().take(5).sum::<f64>() * 100.0,
+        top10_weight: weights.iter().take(10).sum::<f64>() * 100.0,
+    }
+}
+
 /// Risk metrics result
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -1701,16 +2208,35 @@ pub struct RiskMetrics {
     pub beta: Option<f64>,
     /// Alpha vs benchmark (if provided)
     pub alpha: Option<f64>,
+    /// Upside capture vs benchmark (mean return on benchmark up-days / benchmark mean)
+    pub upside_capture: Option<f64>,
+    /// Downside capture vs benchmark (mean return on benchmark down-days / benchmark mean)
+    pub downside_capture: Option<f64>,
     /// Calmar Ratio (annualized return / max drawdown)
     pub calmar_ratio: Option<f64>,
+    /// Historical Value-at-Risk (95%): 5th percentile of daily returns, scaled to the horizon
+    pub var_95_historical: Option<f64>,
+    /// Parametric Value-at-Risk (95%): mean - 1.645 * sigma of daily returns, scaled to the horizon
+    pub var_95_parametric: Option<f64>,
+    /// Horizon in trading days the VaR values refer to
+    pub var_horizon_days: u32,
     /// Number of data points used
     pub data_points: usize,
 }
 
+/// Comparison of the portfolio returns with a benchmark
+#[derive(Debug, Clone, Copy, Default)]
+struct BenchmarkComparison {
+    beta: Option<f64>,
+    alpha: Option<f64>,
+    upside_capture: Option<f64>,
+    downside_capture: Option<f64>,
+}
+
 /// Calculate risk metrics for a portfolio
 ///
 /// Phase 4 fix: Uses flow-adjusted daily returns to prevent cash flow distortion.
-/// Risk-free rate default: 3% (typical for EUR savings)
+/// Risk-free rate default: 3% (typical for EUR savings), VaR horizon default: 1 day
 pub fn calculate_risk_metrics(
     conn: &Connection,
     portfolio_id: Option<i64>,
@@ -1718,46 +2244,117 @@ pub fn calculate_risk_metrics(
     end_date: NaiveDate,
     benchmark_id: Option<i64>,
     risk_free_rate: Option<f64>,
+    horizon_days: Option<u32>,
 ) -> Result<RiskMetrics> {
-    let rf_rate = risk_free_rate.unwrap_or(0.03); // 3% default
-
     // Get portfolio value history
     let portfolio_values = get_portfolio_value_history(conn, portfolio_id, start_date, end_date)?;
 
     if portfolio_values.len() < 2 {
-        return Ok(RiskMetrics {
-            sharpe_ratio: 0.0,
-            sortino_ratio: 0.0,
-            max_drawdown: 0.0,
-            max_drawdown_start: None,
-            max_drawdown_end: None,
-            volatility: 0.0,
-            beta: None,
-            alpha: None,
-            calmar_ratio: None,
-            data_points: portfolio_values.len(),
-        });
+        return Ok(empty_risk_metrics(portfolio_values.len(), horizon_days));
     }
 
     // Get cash flows for flow-adjusted returns (Phase 4 fix)
-    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date)?;
+    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
+
+    calculate_risk_metrics_from_series(
+        conn,
+        &portfolio_values,
+        &cash_flows,
+        start_date,
+        end_date,
+        benchmark_id,
+        risk_free_rate,
+        horizon_days,
+    )
+}
+
+/// Calculate risk metrics for multiple portfolios (e.g. a consortium).
+///
+/// Valuations are summed per date and cash flows combined, like
+/// `calculate_ttwror_for_portfolios`, so the group is treated as one portfolio.
+pub fn calculate_risk_metrics_for_portfolios(
+    conn: &Connection,
+    portfolio_ids: &[i64],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    benchmark_id: Option<i64>,
+    risk_free_rate: Option<f64>,
+    horizon_days: Option<u32>,
+) -> Result<RiskMetrics> {
+    let mut aggregated_values: std::collections::BTreeMap<NaiveDate, f64> = std::collections::BTreeMap::new();
+    let mut cash_flows: Vec<CashFlow> = Vec::new();
+
+    for pid in portfolio_ids {
+        let values = get_portfolio_value_history(conn, Some(*pid), start_date, end_date)?;
+        for (date, value) in values {
+            *aggregated_values.entry(date).or_insert(0.0) += value;
+        }
+
+        let flows = get_cash_flows(conn, Some(*pid), start_date, end_date, None)?;
+        cash_flows.extend(flows);
+    }
+
+    let portfolio_values: Vec<(NaiveDate, f64)> = aggregated_values.into_iter().collect();
+
+    if portfolio_values.len() < 2 {
+        return Ok(empty_risk_metrics(portfolio_values.len(), horizon_days));
+    }
+
+    cash_flows.sort_by_key(|cf| cf.date);
+
+    calculate_risk_metrics_from_series(
+        conn,
+        &portfolio_values,
+        &cash_flows,
+        start_date,
+        end_date,
+        benchmark_id,
+        risk_free_rate,
+        horizon_days,
+    )
+}
+
+/// Risk metrics without enough data
+fn empty_risk_metrics(data_points: usize, horizon_days: Option<u32>) -> RiskMetrics {
+    RiskMetrics {
+        sharpe_ratio: 0.0,
+        sortino_ratio: 0.0,
+        max_drawdown: 0.0,
+        max_drawdown_start: None,
+        max_drawdown_end: None,
+        volatility: 0.0,
+        beta: None,
+        alpha: None,
+        upside_capture: None,
+        downside_capture: None,
+        calmar_ratio: None,
+        var_95_historical: None,
+        var_95_parametric: None,
+        var_horizon_days: horizon_days.unwrap_or(1).max(1),
+        data_points,
+    }
+}
+
+/// Calculate risk metrics from a valuation series and its external cash flows
+#[allow(clippy::too_many_arguments)]
+fn calculate_risk_metrics_from_series(
+    conn: &Connection,
+    portfolio_values: &[(NaiveDate, f64)],
+    cash_flows: &[CashFlow],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    benchmark_id: Option<i64>,
+    risk_free_rate: Option<f64>,
+    horizon_days: Option<u32>,
+) -> Result<RiskMetrics> {
+    let rf_rate = risk_free_rate.unwrap_or(0.03); // 3% default
+    let horizon_days = horizon_days.unwrap_or(1).max(1); // 1 day default
 
     // Calculate flow-adjusted daily returns (removes cash flow distortion)
-    let returns = calculate_flow_adjusted_returns(&portfolio_values, &cash_flows);
+    let returns = calculate_flow_adjusted_returns(portfolio_values, cash_flows);
 
     if returns.is_empty() {
-        return Ok(RiskMetrics {
-            sharpe_ratio: 0.0,
-            sortino_ratio: 0.0,
-            max_drawdown: 0.0,
-            max_drawdown_start: None,
-            max_drawdown_end: None,
-            volatility: 0.0,
-            beta: None,
-            alpha: None,
-            calmar_ratio: None,
-            data_points: 0,
-        });
+        return Ok(empty_risk_metrics(0, Some(horizon_days)));
     }
 
     // Calculate volatility (annualized standard deviation)
@@ -1786,7 +2383,7 @@ pub fn calculate_risk_metrics(
     };
 
     // Calculate Maximum Drawdown
-    let (max_drawdown, dd_start, dd_end) = calculate_max_drawdown(&portfolio_values);
+    let (max_drawdown, dd_start, dd_end) = calculate_max_drawdown(portfolio_values);
 
     // Calmar Ratio = Annualized Return / Max Drawdown
     let calmar_ratio = if max_drawdown > 0.0 {
@@ -1795,12 +2392,14 @@ pub fn calculate_risk_metrics(
         None
     };
 
-    // Calculate Beta and Alpha if benchmark provided
-    // Fix: Now passes portfolio_id to calculate for specific portfolio
-    let (beta, alpha) = if let Some(bench_id) = benchmark_id {
-        calculate_beta_alpha(conn, portfolio_id, bench_id, start_date, end_date, rf_rate)?
+    // Value-at-Risk (95%) for the requested horizon
+    let (var_95_historical, var_95_parametric) = calculate_value_at_risk(&returns, horizon_days);
+
+    // Calculate Beta, Alpha and capture ratios if benchmark provided (same valuation series)
+    let comparison = if let Some(bench_id) = benchmark_id {
+        calculate_beta_alpha(conn, portfolio_values, cash_flows, bench_id, start_date, end_date, rf_rate)?
     } else {
-        (None, None)
+        BenchmarkComparison::default()
     };
 
     Ok(RiskMetrics {
@@ -1810,9 +2409,14 @@ pub fn calculate_risk_metrics(
         max_drawdown_start: dd_start,
         max_drawdown_end: dd_end,
         volatility,
-        beta,
-        alpha,
+        beta: comparison.beta,
+        alpha: comparison.alpha,
+        upside_capture: comparison.upside_capture,
+        downside_capture: comparison.downside_capture,
         calmar_ratio,
+        var_95_historical,
+        var_95_parametric,
+        var_horizon_days: horizon_days,
         data_points: returns.len(),
     })
 }
@@ -2032,6 +2636,35 @@ fn calculate_volatility(returns: &[f64]) -> f64 {
     daily_std * (252.0_f64).sqrt()
 }
 
+/// z-score of the 5% quantile of the standard normal distribution
+const VAR_95_Z_SCORE: f64 = 1.645;
+
+/// Calculate 95% Value-at-Risk from daily returns as (historical, parametric).
+///
+/// Both are returns (negative = loss) over `horizon_days`. Historical uses the
+/// nearest-rank 5th percentile scaled with the square-root-of-time rule,
+/// parametric assumes normally distributed returns: mean * h - 1.645 * sigma * sqrt(h).
+fn calculate_value_at_risk(returns: &[f64], horizon_days: u32) -> (Option<f64>, Option<f64>) {
+    if returns.is_empty() {
+        return (None, None);
+    }
+
+    let horizon = horizon_days.max(1) as f64;
+    let scale = horizon.sqrt();
+
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let index = ((sorted.len() as f64 * 0.05).ceil() as usize).saturating_sub(1);
+    let historical = sorted[index] * scale;
+
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let parametric = mean * horizon - VAR_95_Z_SCORE * std_dev * scale;
+
+    (Some(historical), Some(parametric))
+}
+
 /// Calculate downside deviation (only negative returns below target)
 fn calculate_downside_deviation(returns: &[f64], target: f64) -> f64 {
     let downside_returns: Vec<f64> = returns
@@ -2088,21 +2721,17 @@ fn calculate_max_drawdown(values: &[(NaiveDate, f64)]) -> (f64, Option<String>,
     )
 }
 
-/// Calculate Beta and Alpha vs benchmark
-///
-/// Phase 5 fix: Uses date-based matching instead of length truncation,
-/// and converts benchmark prices to base currency.
+/// Flow-adjusted portfolio and benchmark daily returns on their common dates
 ///
-/// Fix from review: Now accepts portfolio_id to calculate Beta/Alpha for specific portfolio
-/// instead of all portfolios combined.
-fn calculate_beta_alpha(
+/// Benchmark prices are converted to base currency. Returns (date, portfolio, benchmark).
+fn aligned_benchmark_returns(
     conn: &Connection,
-    portfolio_id: Option<i64>,  // Now uses portfolio_id instead of unused _portfolio_returns
+    portfolio_values: &[(NaiveDate, f64)],
+    cash_flows: &[CashFlow],
     benchmark_id: i64,
     start_date: NaiveDate,
     end_date: NaiveDate,
-    risk_free_rate: f64,
-) -> Result<(Option<f64>, Option<f64>)> {
+) -> Result<Vec<(NaiveDate, f64, f64)>> {
     use crate::currency;
     use std::collections::HashMap;
 
@@ -2160,7 +2789,7 @@ fn calculate_beta_alpha(
     }
 
     if bench_values.len() < 2 {
-        return Ok((None, None));
+        return Ok(Vec::new());
     }
 
     // Calculate benchmark returns WITH dates
@@ -2183,10 +2812,6 @@ fn calculate_beta_alpha(
         .cloned()
         .collect();
 
-    // Get portfolio values to build dated returns (now uses specific portfolio_id!)
-    let portfolio_values = get_portfolio_value_history(conn, portfolio_id, start_date, end_date)?;
-    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date)?;
-
     // Build portfolio returns map by date (flow-adjusted)
     let cf_by_date: HashMap<NaiveDate, f64> = cash_flows
         .iter()
@@ -2221,17 +2846,80 @@ fn calculate_beta_alpha(
         .collect();
     common_dates.sort();
 
-    if common_dates.len() < 10 {
-        log::warn!("Beta/Alpha: Only {} common dates, need at least 10", common_dates.len());
-        return Ok((None, None));
+    Ok(common_dates
+        .into_iter()
+        .map(|d| (d, port_map[&d], bench_map[&d]))
+        .collect())
+}
+
+/// Portfolio and benchmark rebased to 100 on the first common date
+///
+/// Chains the aligned daily returns also used for Beta/Alpha, so cash flows do not
+/// show up as performance. Returns (date, portfolio index, benchmark index).
+pub fn calculate_rebased_benchmark_series(
+    conn: &Connection,
+    portfolio_id: Option<i64>,
+    benchmark_id: i64,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<(NaiveDate, f64, f64)>> {
+    let portfolio_values = get_portfolio_value_history(conn, portfolio_id, start_date, end_date)?;
+    let cash_flows = get_cash_flows(conn, portfolio_id, start_date, end_date, None)?;
+
+    let aligned = aligned_benchmark_returns(conn, &portfolio_values, &cash_flows, benchmark_id, start_date, end_date)?;
+
+    Ok(rebase_returns(&aligned))
+}
+
+/// Chain aligned returns to index series starting at 100 on the first date
+fn rebase_returns(aligned: &[(NaiveDate, f64, f64)]) -> Vec<(NaiveDate, f64, f64)> {
+    let mut portfolio_index = 100.0;
+    let mut benchmark_index = 100.0;
+
+    aligned
+        .iter()
+        .enumerate()
+        .map(|(i, (date, port_ret, bench_ret))| {
+            if i > 0 {
+                portfolio_index *= 1.0 + port_ret;
+                benchmark_index *= 1.0 + bench_ret;
+            }
+            (*date, portfolio_index, benchmark_index)
+        })
+        .collect()
+}
+
+/// Calculate Beta, Alpha and capture ratios vs benchmark
+///
+/// Phase 5 fix: Uses date-based matching instead of length truncation,
+/// and converts benchmark prices to base currency.
+///
+/// Takes the portfolio valuation series and cash flows of the caller, so Beta/Alpha
+/// refer to the same portfolio (or portfolio group) as the other risk metrics.
+fn calculate_beta_alpha(
+    conn: &Connection,
+    portfolio_values: &[(NaiveDate, f64)],
+    cash_flows: &[CashFlow],
+    benchmark_id: i64,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    risk_free_rate: f64,
+) -> Result<BenchmarkComparison> {
+    let aligned = aligned_benchmark_returns(conn, portfolio_values, cash_flows, benchmark_id, start_date, end_date)?;
+
+    if aligned.len() < 10 {
+        log::warn!("Beta/Alpha: Only {} common dates, need at least 10", aligned.len());
+        return Ok(BenchmarkComparison::default());
     }
 
     // Extract aligned returns
-    let port_ret: Vec<f64> = common_dates.iter().map(|d| port_map[d]).collect();
-    let bench_ret: Vec<f64> = common_dates.iter().map(|d| bench_map[d]).collect();
+    let port_ret: Vec<f64> = aligned.iter().map(|(_, p, _)| *p).collect();
+    let bench_ret: Vec<f64> = aligned.iter().map(|(_, _, b)| *b).collect();
+
+    let (upside_capture, downside_capture) = calculate_capture_ratios(&port_ret, &bench_ret);
 
     // Simple linear regression: portfolio = alpha + beta * benchmark
-    let n = common_dates.len() as f64;
+    let n = aligned.len() as f64;
     let sum_x: f64 = bench_ret.iter().sum();
     let sum_y: f64 = port_ret.iter().sum();
     let sum_xy: f64 = port_ret.iter().zip(bench_ret.iter()).map(|(y, x)| x * y).sum();
@@ -2239,7 +2927,11 @@ fn calculate_beta_alpha(
 
     let denom = n * sum_xx - sum_x * sum_x;
     if denom.abs() < 1e-10 {
-        return Ok((None, None));
+        return Ok(BenchmarkComparison {
+            upside_capture,
+            downside_capture,
+            ..Default::default()
+        });
     }
 
     let beta = (n * sum_xy - sum_x * sum_y) / denom;
@@ -2252,133 +2944,208 @@ fn calculate_beta_alpha(
     // Jensen's Alpha (annualized)
     let alpha = (mean_port - daily_rf - beta * (mean_bench - daily_rf)) * 252.0;
 
-    log::info!("Beta/Alpha: {} common dates, beta={:.3}, alpha={:.4}%", common_dates.len(), beta, alpha * 100.0);
-    Ok((Some(beta), Some(alpha)))
+    log::info!("Beta/Alpha: {} common dates, beta={:.3}, alpha={:.4}%", aligned.len(), beta, alpha * 100.0);
+    Ok(BenchmarkComparison {
+        beta: Some(beta),
+        alpha: Some(alpha),
+        upside_capture,
+        downside_capture,
+    })
 }
 
-// =====================================================
-// Cash Balance Functions (Phase 1: NAV inkl. Cash)
-// =====================================================
-
-/// Get all account IDs linked to a portfolio
+/// Upside/downside capture ratios from aligned daily returns.
 ///
-/// Includes:
-/// - The portfolio's reference_account_id
-/// - Any accounts connected via CrossEntry (Buy/Sell transactions)
-fn get_linked_account_ids(conn: &Connection, portfolio_id: i64) -> Result<Vec<i64>> {
-    let sql = r#"
-        SELECT DISTINCT account_id FROM (
-            -- Reference Account
-            SELECT reference_account_id as account_id
-            FROM pp_portfolio
-            WHERE id = ?1 AND reference_account_id IS NOT NULL
-
-            UNION
-
-            -- Accounts via CrossEntry (Buy/Sell link portfolio txn to account txn)
-            SELECT at.owner_id as account_id
-            FROM pp_txn pt
-            JOIN pp_cross_entry ce ON ce.portfolio_txn_id = pt.id
-            JOIN pp_txn at ON at.id = ce.account_txn_id
-            WHERE pt.owner_type = 'portfolio'
-              AND pt.owner_id = ?1
-              AND at.owner_type = 'account'
-        )
-        WHERE account_id IS NOT NULL
-    "#;
+/// Upside capture = mean portfolio return on days the benchmark rose / mean benchmark
+/// return on those days (downside analogously for falling days). Values above 1.0 mean
+/// the portfolio moved more than the benchmark, e.g. downside 0.8 = 80% of the losses.
+fn calculate_capture_ratios(port_ret: &[f64], bench_ret: &[f64]) -> (Option<f64>, Option<f64>) {
+    let capture = |is_match: fn(f64) -> bool| {
+        let days: Vec<(f64, f64)> = port_ret
+            .iter()
+            .zip(bench_ret.iter())
+            .filter(|(_, b)| is_match(**b))
+            .map(|(p, b)| (*p, *b))
+            .collect();
 
-    let mut stmt = conn.prepare(sql)?;
-    let rows = stmt.query_map([portfolio_id], |row| row.get::<_, i64>(0))?;
+        if days.is_empty() {
+            return None;
+        }
 
-    let mut account_ids = Vec::new();
-    for row in rows.flatten() {
-        account_ids.push(row);
-    }
+        let n = days.len() as f64;
+        let mean_port = days.iter().map(|(p, _)| p).sum::<f64>() / n;
+        let mean_bench = days.iter().map(|(_, b)| b).sum::<f64>() / n;
+
+        if mean_bench.abs() < 1e-12 {
+            None
+        } else {
+            Some(mean_port / mean_bench)
+        }
+    };
 
-    log::debug!("Found {} linked accounts for portfolio {}", account_ids.len(), portfolio_id);
-    Ok(account_ids)
+    (capture(|b| b > 0.0), capture(|b| b < 0.0))
 }
 
-/// Calculate account cash balance at a specific date
-///
-/// Returns (balance in cents, currency)
-/// Balance = sum of all credits minus debits up to the given date
-fn get_account_balance_at_date(
+/// Net worth across all portfolios and accounts
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetWorth {
+    pub date: String,
+    pub base_currency: String,
+    /// Securities value plus cash, in base currency
+    pub total: f64,
+    /// Value of all portfolio holdings in base currency
+    pub securities_value: f64,
+    /// Cash balance of all accounts in base currency
+    pub cash_value: f64,
+    /// Breakdown by the currency the assets are held in
+    pub by_currency: Vec<NetWorthByCurrency>,
+}
+
+/// Part of the net worth held in one currency
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetWorthByCurrency {
+    pub currency: String,
+    /// Securities value in this currency
+    pub securities_value: f64,
+    /// Cash balance in this currency
+    pub cash_value: f64,
+    /// Securities and cash converted to base currency
+    pub value_base: f64,
+    /// Share of the total net worth in percent
+    pub weight: f64,
+}
+
+/// Calculate the net worth at a date: holdings of all portfolios plus the cash
+/// balance of every account, converted to base currency
+pub fn calculate_net_worth(
     conn: &Connection,
-    account_id: i64,
     date: NaiveDate,
-) -> Result<(f64, String)> {
-    let sql = r#"
-        SELECT
-            a.currency,
-            COALESCE(SUM(
-                CASE
-                    -- Credits (money into account)
-                    WHEN t.txn_type IN ('DEPOSIT', 'INTEREST', 'DIVIDENDS', 'TAX_REFUND',
-                                        'FEES_REFUND', 'TRANSFER_IN', 'SELL') THEN t.amount
-                    -- Debits (money out of account)
-                    WHEN t.txn_type IN ('REMOVAL', 'FEES', 'TAXES', 'INTEREST_CHARGE',
-                                        'TRANSFER_OUT', 'BUY') THEN -t.amount
-                    ELSE 0
-                END
-            ), 0) as balance
-        FROM pp_account a
-        LEFT JOIN pp_txn t ON t.owner_type = 'account' AND t.owner_id = a.id
-            AND date(t.date) <= ?2
-        WHERE a.id = ?1
-        GROUP BY a.id
-    "#;
+    base_currency: &str,
+) -> Result<NetWorth> {
+    let mut by_currency: std::collections::BTreeMap<String, NetWorthByCurrency> =
+        std::collections::BTreeMap::new();
+    let mut add = |currency: &str, securities: f64, cash: f64, value_base: f64| {
+        let currency = if currency.is_empty() { base_currency } else { currency };
+        let e = by_currency
+            .entry(currency.to_string())
+            .or_insert_with(|| NetWorthByCurrency {
+                currency: currency.to_string(),
+                ..Default::default()
+            });
+        e.securities_value += securities;
+        e.cash_value += cash;
+        e.value_base += value_base;
+    };
 
-    let result: (String, i64) = conn.query_row(sql, params![account_id, date.to_string()], |row| {
-        Ok((
-            row.get::<_, Option<String>>(0)?.unwrap_or_else(|| "EUR".to_string()),
-            row.get::<_, i64>(1)?,
-        ))
-    }).map_err(|e| anyhow::anyhow!("Failed to get account balance: {}", e))?;
+    let mut securities_value = 0.0;
+    for holding in get_holdings_at_date(conn, None, date, base_currency)? {
+        let value_base = holding.value_base.unwrap_or(0.0);
+        add(&holding.currency, holding.value.unwrap_or(0.0), 0.0, value_base);
+        securities_value += value_base;
+    }
+
+    let mut stmt = conn.prepare("SELECT id FROM pp_account")?;
+    let account_ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut cash_value = 0.0;
+    for account_id in account_ids {
+        let (balance, account_currency, balance_base) =
+            get_account_balance_converted(conn, account_id, date, base_currency)?;
+        if balance == 0.0 {
+            continue;
+        }
+        add(&account_currency, 0.0, balance, balance_base);
+        cash_value += balance_base;
+    }
 
-    let balance_f = result.1 as f64 / AMOUNT_SCALE;
-    log::debug!("Account {} balance at {}: {:.2} {}", account_id, date, balance_f, result.0);
+    let total = securities_value + cash_value;
+    let mut by_currency: Vec<NetWorthByCurrency> = by_currency.into_values().collect();
+    for e in &mut by_currency {
+        if total != 0.0 {
+            e.weight = e.value_base / total * 100.0;
+        }
+    }
+    by_currency.sort_by(|a, b| {
+        b.value_base
+            .partial_cmp(&a.value_base)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(NetWorth {
+        date: date.to_string(),
+        base_currency: base_currency.to_string(),
+        total,
+        securities_value,
+        cash_value,
+        by_currency,
+    })
+}
 
-    Ok((balance_f, result.0))
+/// Net worth at one date of the history
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetWorthPoint {
+    pub date: String,
+    /// Holdings of all portfolios in base currency
+    pub securities: f64,
+    /// Cash of all accounts in base currency
+    pub cash: f64,
+    pub total: f64,
 }
 
-/// Get total cash balance from all linked accounts, converted to base currency
+/// Calculate the net worth for every price date between start and end
 ///
-/// This is the sum of all account cash balances at a given date,
-/// with each account's balance converted to the portfolio's base currency.
-fn get_total_cash_balance_converted(
+/// Each point sums the converted holdings of all portfolios and the cash
+/// balance of every account at that date. The end date is always included.
+pub fn calculate_net_worth_history(
     conn: &Connection,
-    portfolio_id: i64,
-    date: NaiveDate,
-    base_currency: &str,
-) -> Result<f64> {
-    use crate::currency;
-
-    let account_ids = get_linked_account_ids(conn, portfolio_id)?;
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<NetWorthPoint>> {
+    let base_currency =
+        crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
 
-    if account_ids.is_empty() {
-        return Ok(0.0);
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT date(date) AS d FROM pp_price WHERE date(date) >= ?1 AND date(date) <= ?2 ORDER BY d",
+    )?;
+    let mut dates: Vec<NaiveDate> = stmt
+        .query_map(params![start_date.to_string(), end_date.to_string()], |row| {
+            row.get::<_, String>(0)
+        })?
+        .filter_map(|d| d.ok().and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()))
+        .collect();
+    if dates.last() != Some(&end_date) {
+        dates.push(end_date);
     }
 
-    let mut total_cash = 0.0;
+    let mut stmt = conn.prepare("SELECT id FROM pp_account")?;
+    let account_ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
 
-    for account_id in account_ids {
-        let (balance, account_currency) = get_account_balance_at_date(conn, account_id, date)?;
+    let mut points = Vec::with_capacity(dates.len());
+    for date in dates {
+        let securities = get_portfolio_value_at_date_with_currency(conn, None, date)?;
 
-        // Convert to base currency if needed
-        let balance_base = if account_currency != base_currency && !account_currency.is_empty() {
-            currency::convert(conn, balance, &account_currency, base_currency, date)
-                .unwrap_or(balance)
-        } else {
-            balance
-        };
+        let mut cash = 0.0;
+        for account_id in &account_ids {
+            let (_, _, balance_base) =
+                get_account_balance_converted(conn, *account_id, date, &base_currency)?;
+            cash += balance_base;
+        }
 
-        total_cash += balance_base;
+        points.push(NetWorthPoint {
+            date: date.to_string(),
+            securities,
+            cash,
+            total: securities + cash,
+        });
     }
 
-    log::debug!("Total cash balance for portfolio {} at {}: {:.2} {}",
-                portfolio_id, date, total_cash, base_currency);
-    Ok(total_cash)
+    Ok(points)
 }
 
 #[cfg(test)]
@@ -2714,56 +3481,310 @@ mod tests {
     }
 
     #[test]
-    fn test_e2e_account_balance_calculation() {
+    fn test_e2e_holdings_at_date() {
         let conn = create_test_db();
 
-        // Create account
-        conn.execute(
-            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'acc-1', 'Test Account', 'EUR')",
-            []
-        ).unwrap();
+        conn.execute_batch(r#"
+            INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot 1'), (2, 'p2', 'Depot 2');
+            INSERT INTO pp_security (id, uuid, name, currency, isin) VALUES (1, 's1', 'Aktie', 'EUR', 'DE0001');
 
-        // Add transactions: DEPOSIT 1000, BUY 500, DIVIDENDS 50
-        conn.execute(
-            "INSERT INTO pp_txn (id, owner_type, owner_id, txn_type, date, amount) VALUES (1, 'account', 1, 'DEPOSIT', '2024-01-01', 100000)",
-            []
-        ).unwrap();
-        conn.execute(
-            "INSERT INTO pp_txn (id, owner_type, owner_id, txn_type, date, amount) VALUES (2, 'account', 1, 'BUY', '2024-01-15', 50000)",
-            []
-        ).unwrap();
-        conn.execute(
-            "INSERT INTO pp_txn (id, owner_type, owner_id, txn_type, date, amount) VALUES (3, 'account', 1, 'DIVIDENDS', '2024-06-01', 5000)",
-            []
-        ).unwrap();
+            -- Depot 1: buy 10, sell 4 later; Depot 2: buy 5
+            INSERT INTO pp_txn (id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares)
+            VALUES (1, 't1', 'portfolio', 1, 1, 'BUY', '2024-01-10', 100000, 'EUR', 1000000000),
+                   (2, 't2', 'portfolio', 1, 1, 'SELL', '2024-03-01', 48000, 'EUR', 400000000),
+                   (3, 't3', 'portfolio', 2, 1, 'BUY', '2024-02-01', 55000, 'EUR', 500000000);
 
-        // Test balance: 1000 - 500 + 50 = 550 EUR
-        let (balance, currency) = get_account_balance_at_date(
-            &conn, 1, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
-        ).unwrap();
+            INSERT INTO pp_price (security_id, date, value) VALUES
+                (1, '2024-01-31', 11000000000), (1, '2024-02-29', 12000000000);
+        "#).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+        let holdings = get_holdings_at_date(&conn, Some(1), date, "EUR").unwrap();
+        assert_eq!(holdings.len(), 1);
+        assert!((holdings[0].shares - 10.0).abs() < 1e-9);
+        assert_eq!(holdings[0].price, Some(110.0));
+        assert_eq!(holdings[0].price_date, NaiveDate::from_ymd_opt(2024, 1, 31));
+        assert_eq!(holdings[0].value_base, Some(1100.0));
+
+        // All portfolios after the sale: 6 + 5 shares
+        let date = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let holdings = get_holdings_at_date(&conn, None, date, "EUR").unwrap();
+        assert!((holdings[0].shares - 11.0).abs() < 1e-9);
+        assert_eq!(holdings[0].value, Some(1320.0));
 
-        assert_eq!(currency, "EUR");
-        assert!((balance - 550.0).abs() < 0.01, "Expected 550, got {}", balance);
+        // Nothing held before the first purchase
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert!(get_holdings_at_date(&conn, None, date, "EUR").unwrap().is_empty());
     }
 
     #[test]
-    fn test_e2e_linked_accounts() {
+    fn test_e2e_risk_metrics_for_portfolios_sums_valuations() {
         let conn = create_test_db();
 
-        // Create account and portfolio with reference
-        conn.execute(
-            "INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'acc-1', 'Test Account', 'EUR')",
-            []
-        ).unwrap();
-        conn.execute(
-            "INSERT INTO pp_portfolio (id, uuid, name, reference_account_id) VALUES (1, 'port-1', 'Test Portfolio', 1)",
-            []
-        ).unwrap();
+        conn.execute_batch(r#"
+            INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot 1'), (2, 'p2', 'Depot 2');
+            INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Volatile', 'EUR'), (2, 's2', 'Stable', 'EUR');
+
+            -- 10 shares each, bought before the range
+            INSERT INTO pp_txn (id, uuid, owner_type, owner_id, security_id, txn_type, date, amount, currency, shares)
+            VALUES (1, 't1', 'portfolio', 1, 1, 'BUY', '2023-12-01', 100000, 'EUR', 1000000000),
+                   (2, 't2', 'portfolio', 2, 2, 'BUY', '2023-12-01', 100000, 'EUR', 1000000000);
+
+            -- Security 1: 100 -> 110 -> 99, security 2 constant at 100
+            INSERT INTO pp_price (security_id, date, value) VALUES
+                (1, '2024-01-02', 10000000000), (1, '2024-01-03', 11000000000), (1, '2024-01-04', 9900000000),
+                (2, '2024-01-02', 10000000000), (2, '2024-01-03', 10000000000), (2, '2024-01-04', 10000000000);
+        "#).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+
+        let single = calculate_risk_metrics(&conn, Some(1), start, end, None, None, None).unwrap();
+        let combined = calculate_risk_metrics_for_portfolios(&conn, &[1, 2], start, end, None, None, None).unwrap();
+
+        // Depot 1 alone: 1100 -> 990 = 10% drawdown
+        assert_eq!(single.data_points, 2);
+        assert!((single.max_drawdown - 0.10).abs() < 1e-9);
+
+        // Combined: 2000 -> 2100 -> 1990, the stable depot dampens the drawdown
+        assert_eq!(combined.data_points, 2);
+        assert!((combined.max_drawdown - 110.0 / 2100.0).abs() < 1e-9);
+        assert!(combined.volatility < single.volatility);
+        assert_eq!(combined.max_drawdown_start.as_deref(), Some("2024-01-03"));
+    }
+
+    #[test]
+    fn test_value_at_risk() {
+        // -4.9%, -4.8%, ..., +5.0% (100 returns, mean 0.05%)
+        let returns: Vec<f64> = (1..=100).map(|i| (i as f64 - 50.0) / 1000.0).collect();
+
+        let (historical, parametric) = calculate_value_at_risk(&returns, 1);
+        // 5th smallest return
+        assert!((historical.unwrap() - (-0.045)).abs() < 1e-9);
+        // sigma of a uniform grid with step 0.001: sqrt((100^2 - 1) / 12) / 1000
+        let sigma = ((100.0_f64 * 100.0 - 1.0) / 12.0).sqrt() / 1000.0;
+        assert!((parametric.unwrap() - (0.0005 - 1.645 * sigma)).abs() < 1e-9);
+
+        // 4-day horizon: historical doubles (sqrt(4)), the mean scales with h
+        let (historical_4d, parametric_4d) = calculate_value_at_risk(&returns, 4);
+        assert!((historical_4d.unwrap() - 2.0 * historical.unwrap()).abs() < 1e-9);
+        assert!((parametric_4d.unwrap() - (4.0 * 0.0005 - 2.0 * 1.645 * sigma)).abs() < 1e-9);
+
+        // mean 0.5%, sigma sqrt(0.000125) = 1.118%:
+        // 4 * 0.005 - 1.645 * 0.0111803 * 2 = 0.02 - 0.0367833 = -1.678%
+        let (_, parametric_small) = calculate_value_at_risk(&[0.01, -0.01, 0.02, 0.0], 4);
+        assert!((parametric_small.unwrap() - (-0.016_783_318_23)).abs() < 1e-9);
+
+        // Shuffled input gives the same historical VaR
+        let mut shuffled = returns.clone();
+        shuffled.reverse();
+        assert_eq!(calculate_value_at_risk(&shuffled, 1).0, historical);
+
+        assert_eq!(calculate_value_at_risk(&[], 1), (None, None));
+    }
+
+    #[test]
+    fn test_capture_ratios() {
+        // Benchmark up-days: +2%, +4% (mean 3%), portfolio +1.5%, +3% (mean 2.25%)
+        // Benchmark down-days: -2%, -2% (mean -2%), portfolio -1%, -2% (mean -1.5%)
+        let bench = vec![0.02, -0.02, 0.04, 0.0, -0.02];
+        let port = vec![0.015, -0.01, 0.03, 0.005, -0.02];
+
+        let (upside, downside) = calculate_capture_ratios(&port, &bench);
+        assert!((upside.unwrap() - 0.75).abs() < 1e-9);
+        assert!((downside.unwrap() - 0.75).abs() < 1e-9);
+
+        // Benchmark never fell: no downside capture
+        let (upside, downside) = calculate_capture_ratios(&[0.01, 0.02], &[0.01, 0.01]);
+        assert!((upside.unwrap() - 1.5).abs() < 1e-9);
+        assert_eq!(downside, None);
+    }
+
+    #[test]
+    fn test_concentration_metrics() {
+        // Weights 50/30/20: HHI 0.25 + 0.09 + 0.04
+        let metrics = calculate_concentration_metrics(&[300.0, 500.0, 0.0, 200.0]);
+        assert_eq!(metrics.positions, 3);
+        assert!((metrics.hhi - 0.38).abs() < 1e-9);
+        assert!((metrics.effective_holdings - 1.0 / 0.38).abs() < 1e-9);
+        assert!((metrics.top5_weight - 100.0).abs() < 1e-9);
+
+        // 20 equal positions
+        let metrics = calculate_concentration_metrics(&[1.0; 20]);
+        assert!((metrics.effective_holdings - 20.0).abs() < 1e-9);
+        assert!((metrics.top5_weight - 25.0).abs() < 1e-9);
+        assert!((metrics.top10_weight - 50.0).abs() < 1e-9);
+
+        assert_eq!(calculate_concentration_metrics(&[]), ConcentrationMetrics::default());
+    }
+
+    #[test]
+    fn test_calculate_net_worth() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot A'), (2, 'p2', 'Depot B');
+             INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR'), (2, 'a2', 'USD-Konto', 'USD');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_price (security_id, date, value) VALUES (1, '2024-01-02', 10000000000);
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate) VALUES ('EUR', 'USD', '2024-01-01', '1.25');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 50000, 'EUR', 1, 500000000),
+                    ('t2', 'portfolio', 2, 'BUY', '2024-01-01', 50000, 'EUR', 1, 500000000),
+                    ('t3', 'account', 1, 'DEPOSIT', '2024-01-01', 20000, 'EUR', NULL, NULL),
+                    ('t4', 'account', 2, 'DEPOSIT', '2024-01-01', 50000, 'USD', NULL, NULL);",
+        )
+        .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let net_worth = calculate_net_worth(&conn, date, "EUR").unwrap();
+
+        // 10 shares at 100 EUR + 200 EUR + 500 USD (= 400 EUR)
+        assert!((net_worth.securities_value - 1000.0).abs() < 1e-6);
+        assert!((net_worth.cash_value - 600.0).abs() < 1e-6);
+        assert!((net_worth.total - 1600.0).abs() < 1e-6);
+
+        assert_eq!(net_worth.by_currency.len(), 2);
+        let eur = &net_worth.by_currency[0];
+        assert_eq!(eur.currency, "EUR");
+        assert!((eur.value_base - 1200.0).abs() < 1e-6);
+        assert!((eur.weight - 75.0).abs() < 1e-6);
+        let usd = &net_worth.by_currency[1];
+        assert!((usd.cash_value - 500.0).abs() < 1e-6);
+        assert!((usd.value_base - 400.0).abs() < 1e-6);
+
+        // Before the deposits and buys only the later point has value
+        let history = calculate_net_worth_history(
+            &conn,
+            NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            date,
+        )
+        .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].date, "2024-01-02");
+        assert_eq!(history[1].date, "2024-01-31");
+        assert!((history[1].securities - 1000.0).abs() < 1e-6);
+        assert!((history[1].cash - 600.0).abs() < 1e-6);
+        assert!((history[1].total - 1600.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_calculate_position_performance() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_account (id, uuid, name, currency) VALUES (1, 'a1', 'Giro', 'EUR');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                (1, '2024-01-01', 10000000000), (1, '2024-07-01', 11000000000), (1, '2025-01-01', 12000000000);
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-01', 100000, 'EUR', 1, 1000000000),
+                    ('t2', 'account', 1, 'DIVIDENDS', '2024-07-01', 2000, 'EUR', 1, NULL);",
+        )
+        .unwrap();
 
-        // Test linked accounts
-        let linked = get_linked_account_ids(&conn, 1).unwrap();
-        assert_eq!(linked.len(), 1);
-        assert_eq!(linked[0], 1);
+        let end = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let perf = calculate_position_performance(&conn, 1, None, end).unwrap();
+
+        assert_eq!(perf.start_date, "2024-01-01");
+        assert!((perf.shares - 10.0).abs() < 1e-9);
+        assert!((perf.current_value - 1200.0).abs() < 1e-6);
+        assert!((perf.dividends - 20.0).abs() < 1e-6);
+        assert!((perf.absolute_gain - 220.0).abs() < 1e-6);
+
+        // (1100 + 20) / 1000 = 1.12, then 1200 / 1100
+        let expected = (1.12 * 1200.0 / 1100.0 - 1.0) * 100.0;
+        assert!((perf.ttwror - expected).abs() < 1e-6);
+        assert!(perf.irr_converged);
+        assert!(perf.irr > 20.0 && perf.irr < 23.0);
+
+        assert!(calculate_position_performance(&conn, 2, None, end).is_err());
+    }
+
+    #[test]
+    fn test_calculate_currency_effect() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'US-Aktie', 'USD');
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                (1, '2024-01-02', 10000000000), (1, '2024-02-01', 11000000000);
+             INSERT INTO pp_exchange_rate (base_currency, term_currency, date, rate) VALUES
+                ('EUR', 'USD', '2024-01-01', '1.25'), ('EUR', 'USD', '2024-02-01', '1.1');
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2024-01-02', 100000, 'USD', 1, 1000000000);",
+        )
+        .unwrap();
+
+        let effect = calculate_currency_effect(
+            &conn,
+            None,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .unwrap();
+
+        // Price +10% in USD; in EUR 800 -> 1000 (+25%)
+        assert!((effect.ttwror_constant_fx - 10.0).abs() < 1e-6);
+        assert!((effect.ttwror - 25.0).abs() < 1e-6);
+        assert!((effect.currency_effect - 15.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_calculate_standard_period_returns() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_security (id, uuid, name, currency) VALUES (1, 's1', 'Aktie', 'EUR');
+             INSERT INTO pp_price (security_id, date, value) VALUES
+                (1, '2022-06-01', 10000000000), (1, '2023-12-31', 11000000000),
+                (1, '2024-05-01', 12000000000), (1, '2024-06-01', 13200000000);
+             INSERT INTO pp_txn (uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES ('t1', 'portfolio', 1, 'BUY', '2022-06-01', 100000, 'EUR', 1, 1000000000);",
+        )
+        .unwrap();
+
+        // Anchored to the last price date, not to today
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let returns = calculate_standard_period_returns(&conn, Some(1), today).unwrap();
+        let periods: Vec<&str> = returns.iter().map(|r| r.period.as_str()).collect();
+        assert_eq!(periods, ["YTD", "1M", "3M", "6M", "1Y", "3Y", "5Y", "MAX"]);
+        assert!(returns.iter().all(|r| r.end_date == "2024-06-01"));
+
+        let get = |period: &str| returns.iter().find(|r| r.period == period).unwrap();
+        // 110 -> 132
+        assert!((get("YTD").ttwror - 20.0).abs() < 1e-6);
+        assert!(get("YTD").ttwror_annualized.is_none());
+        // 120 -> 132
+        assert_eq!(get("1M").start_date, "2024-05-01");
+        assert!((get("1M").ttwror - 10.0).abs() < 1e-6);
+        assert!(!get("1Y").partial_period);
+
+        // Younger than three years: clamped to inception
+        let three_years = get("3Y");
+        assert!(three_years.partial_period);
+        assert_eq!(three_years.start_date, "2022-06-01");
+        assert!((three_years.ttwror - 32.0).abs() < 1e-6);
+        assert!(three_years.ttwror_annualized.is_some());
+        assert!(!get("MAX").partial_period);
+    }
+
+    #[test]
+    fn test_rebase_returns() {
+        let d = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let aligned = vec![(d(1), 0.5, 0.5), (d(2), 0.10, 0.05), (d(3), -0.10, 0.0)];
+
+        let rebased = rebase_returns(&aligned);
+        // First common date is the base, its return is ignored
+        assert_eq!(rebased[0], (d(1), 100.0, 100.0));
+        assert!((rebased[1].1 - 110.0).abs() < 1e-9);
+        assert!((rebased[1].2 - 105.0).abs() < 1e-9);
+        assert!((rebased[2].1 - 99.0).abs() < 1e-9);
+        assert!((rebased[2].2 - 105.0).abs() < 1e-9);
+        assert!(rebase_returns(&[]).is_empty());
     }
 
     #[test]
@@ -10,6 +10,7 @@ pub mod portfolio;
 pub mod security;
 pub mod taxonomy;
 pub mod transaction;
+pub mod xml;
 
 // Re-export main types for convenience
 pub use account::Account;
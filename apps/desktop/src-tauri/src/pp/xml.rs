@@ -0,0 +1,696 @@
+//! XML serializer for Portfolio Performance files.
+//!
+//! Older PP builds and some tools only read the (zipped) XML format. PP reads it
+//! through XStream, so the output follows XStream's object graph conventions:
+//! every object is written in full where it is reached first and referenced by
+//! XPath afterwards. References are absolute (e.g. `/client/securities/security[2]`),
+//! which XStream resolves just like the relative paths PP writes itself.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, Timelike};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use super::{
+    taxonomy::Classification, Account, AccountTransaction, Client, CrossEntry, CrossEntryType,
+    Portfolio, PortfolioTransaction, Security, TransactionUnit,
+};
+
+/// Write a Client as zipped XML (`data.xml` inside a .portfolio archive)
+pub fn write_xml_file(path: &Path, client: &Client) -> Result<()> {
+    let xml = serialize_client_xml(client);
+
+    let file = std::fs::File::create(path).context("Failed to create file")?;
+    let mut zip = ZipWriter::new(file);
+
+    let options = FileOptions::<()>::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    zip.start_file("data.xml", options)
+        .context("Failed to start ZIP entry")?;
+
+    zip.write_all(xml.as_bytes())
+        .context("Failed to write data to ZIP")?;
+
+    zip.finish().context("Failed to finish ZIP archive")?;
+
+    Ok(())
+}
+
+/// Serialize a Client to PP's XML format
+pub fn serialize_client_xml(client: &Client) -> String {
+    let mut serializer = Serializer::new(client);
+    serializer.write_client();
+    serializer.w.out
+}
+
+/// Open element with its XPath and the number of children per name
+struct Frame {
+    name: String,
+    path: String,
+    children: HashMap<String, usize>,
+}
+
+/// Minimal XML writer that tracks the XPath of every element
+struct XmlWriter {
+    out: String,
+    stack: Vec<Frame>,
+    /// Object key -> XPath of its full serialization
+    refs: HashMap<String, String>,
+}
+
+impl XmlWriter {
+    fn new() -> Self {
+        Self {
+            out: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            stack: Vec::new(),
+            refs: HashMap::new(),
+        }
+    }
+
+    /// XPath of the next child called `name`; XStream omits the index for the first one
+    fn child_path(&mut self, name: &str) -> String {
+        match self.stack.last_mut() {
+            Some(parent) => {
+                let count = parent.children.entry(name.to_string()).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    format!("{}/{}", parent.path, name)
+                } else {
+                    format!("{}/{}[{}]", parent.path, name, count)
+                }
+            }
+            None => format!("/{}", name),
+        }
+    }
+
+    fn start_tag(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.out.push_str(&"  ".repeat(self.stack.len()));
+        self.out.push('<');
+        self.out.push_str(name);
+        for (key, value) in attrs {
+            self.out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+    }
+
+    fn open(&mut self, name: &str, attrs: &[(&str, &str)]) -> String {
+        let path = self.child_path(name);
+        self.start_tag(name, attrs);
+        self.out.push_str(">\n");
+        self.stack.push(Frame {
+            name: name.to_string(),
+            path: path.clone(),
+            children: HashMap::new(),
+        });
+        path
+    }
+
+    fn close(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            self.out.push_str(&"  ".repeat(self.stack.len()));
+            self.out.push_str(&format!("</{}>\n", frame.name));
+        }
+    }
+
+    fn empty(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.child_path(name);
+        self.start_tag(name, attrs);
+        self.out.push_str("/>\n");
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.child_path(name);
+        self.start_tag(name, &[]);
+        self.out.push_str(&format!(">{}</{}>\n", escape(value), name));
+    }
+
+    fn opt_text(&mut self, name: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.text(name, value);
+        }
+    }
+
+    /// Write a reference if the object was serialized before
+    fn reference(&mut self, name: &str, key: &str) -> bool {
+        match self.refs.get(key).cloned() {
+            Some(path) => {
+                self.empty(name, &[("reference", &path)]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn register(&mut self, key: String, path: String) {
+        self.refs.insert(key, path);
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// LocalDateTime as written by PP (seconds only if present)
+fn format_datetime(date: NaiveDateTime) -> String {
+    if date.second() == 0 {
+        date.format("%Y-%m-%dT%H:%M").to_string()
+    } else {
+        date.format("%Y-%m-%dT%H:%M:%S").to_string()
+    }
+}
+
+/// Walks the client graph in PP's field order
+struct Serializer<'a> {
+    client: &'a Client,
+    w: XmlWriter,
+    accounts: HashMap<&'a str, &'a Account>,
+    account_txns: HashMap<&'a str, (&'a Account, &'a AccountTransaction)>,
+    portfolio_txns: HashMap<&'a str, (&'a Portfolio, &'a PortfolioTransaction)>,
+}
+
+impl<'a> Serializer<'a> {
+    fn new(client: &'a Client) -> Self {
+        let mut account_txns = HashMap::new();
+        for acc in &client.accounts {
+            for txn in &acc.transactions {
+                account_txns.insert(txn.uuid.as_str(), (acc, txn));
+            }
+        }
+        let mut portfolio_txns = HashMap::new();
+        for port in &client.portfolios {
+            for txn in &port.transactions {
+                portfolio_txns.insert(txn.uuid.as_str(), (port, txn));
+            }
+        }
+
+        Self {
+            client,
+            w: XmlWriter::new(),
+            accounts: client.accounts.iter().map(|a| (a.uuid.as_str(), a)).collect(),
+            account_txns,
+            portfolio_txns,
+        }
+    }
+
+    fn write_client(&mut self) {
+        let client = self.client;
+        self.w.open("client", &[]);
+        self.w.text("version", &client.version.to_string());
+        self.w.text("baseCurrency", &client.base_currency);
+
+        self.w.open("securities", &[]);
+        for sec in &client.securities {
+            self.security(sec);
+        }
+        self.w.close();
+
+        self.w.open("watchlists", &[]);
+        for wl in &client.watchlists {
+            self.w.open("watchlist", &[]);
+            self.w.text("name", &wl.name);
+            self.w.open("securities", &[]);
+            for uuid in &wl.security_uuids {
+                self.w.reference("security", &format!("security:{}", uuid));
+            }
+            self.w.close();
+            self.w.close();
+        }
+        self.w.close();
+
+        self.w.open("accounts", &[]);
+        for acc in &client.accounts {
+            self.account("account", acc);
+        }
+        self.w.close();
+
+        self.w.open("portfolios", &[]);
+        for port in &client.portfolios {
+            self.portfolio("portfolio", port);
+        }
+        self.w.close();
+
+        self.w.open("taxonomies", &[]);
+        for tax in &client.taxonomies {
+            self.w.open("taxonomy", &[]);
+            self.w.text("id", &tax.id);
+            self.w.text("name", &tax.name);
+            self.w.opt_text("source", tax.source.as_deref());
+            if !tax.dimensions.is_empty() {
+                self.w.open("dimensions", &[]);
+                for dim in &tax.dimensions {
+                    self.w.text("string", dim);
+                }
+                self.w.close();
+            }
+            if let Some(ref root) = tax.root {
+                self.classification("root", root, None);
+            }
+            self.w.close();
+        }
+        self.w.close();
+
+        self.w.close();
+    }
+
+    fn security(&mut self, sec: &Security) {
+        let path = self.w.open("security", &[]);
+        self.w.register(format!("security:{}", sec.uuid), path);
+
+        self.w.text("uuid", &sec.uuid);
+        self.w.opt_text("onlineId", sec.online_id.as_deref());
+        self.w.text("name", &sec.name);
+        self.w.text("currencyCode", &sec.currency);
+        self.w.opt_text("targetCurrencyCode", sec.target_currency.as_deref());
+        self.w.opt_text("note", sec.note.as_deref());
+        self.w.opt_text("isin", sec.isin.as_deref());
+        self.w.opt_text("tickerSymbol", sec.ticker.as_deref());
+        self.w.opt_text("wkn", sec.wkn.as_deref());
+        self.w.opt_text("calendar", sec.calendar.as_deref());
+        self.w.opt_text("feed", sec.feed.as_deref());
+        self.w.opt_text("feedURL", sec.feed_url.as_deref());
+
+        self.w.open("prices", &[]);
+        for price in &sec.prices {
+            let date = price.date.format("%Y-%m-%d").to_string();
+            self.w.empty("price", &[("t", &date), ("v", &price.value.to_string())]);
+        }
+        self.w.close();
+
+        self.w.opt_text("latestFeed", sec.latest_feed.as_deref());
+        self.w.opt_text("latestFeedURL", sec.latest_feed_url.as_deref());
+        if let Some(ref latest) = sec.latest {
+            if let (Some(date), Some(value)) = (latest.date, latest.value) {
+                let date = date.format("%Y-%m-%d").to_string();
+                self.w.open("latest", &[("t", &date), ("v", &value.to_string())]);
+                self.w.text("high", &latest.high.unwrap_or(-1).to_string());
+                self.w.text("low", &latest.low.unwrap_or(-1).to_string());
+                self.w.text("volume", &latest.volume.unwrap_or(-1).to_string());
+                self.w.close();
+            }
+        }
+
+        self.w.text("isRetired", &sec.is_retired.to_string());
+        self.w.opt_text("updatedAt", sec.updated_at.as_deref());
+        self.w.close();
+    }
+
+    fn account(&mut self, name: &str, acc: &'a Account) {
+        let key = format!("account:{}", acc.uuid);
+        if self.w.reference(name, &key) {
+            return;
+        }
+        let path = self.w.open(name, &[]);
+        self.w.register(key, path);
+
+        self.w.text("uuid", &acc.uuid);
+        self.w.text("name", &acc.name);
+        self.w.text("currencyCode", &acc.currency);
+        self.w.opt_text("note", acc.note.as_deref());
+        self.w.text("isRetired", &acc.is_retired.to_string());
+
+        self.w.open("transactions", &[]);
+        for txn in &acc.transactions {
+            self.account_transaction("account-transaction", txn);
+        }
+        self.w.close();
+
+        self.w.opt_text("updatedAt", acc.updated_at.as_deref());
+        self.w.close();
+    }
+
+    fn portfolio(&mut self, name: &str, port: &'a Portfolio) {
+        let key = format!("portfolio:{}", port.uuid);
+        if self.w.reference(name, &key) {
+            return;
+        }
+        let path = self.w.open(name, &[]);
+        self.w.register(key, path);
+
+        self.w.text("uuid", &port.uuid);
+        self.w.text("name", &port.name);
+        self.w.opt_text("note", port.note.as_deref());
+        self.w.text("isRetired", &port.is_retired.to_string());
+
+        let reference_account = port
+            .reference_account_uuid
+            .as_deref()
+            .and_then(|uuid| self.accounts.get(uuid).copied());
+        if let Some(acc) = reference_account {
+            self.account("referenceAccount", acc);
+        }
+
+        self.w.open("transactions", &[]);
+        for txn in &port.transactions {
+            self.portfolio_transaction("portfolio-transaction", txn);
+        }
+        self.w.close();
+
+        self.w.opt_text("updatedAt", port.updated_at.as_deref());
+        self.w.close();
+    }
+
+    fn account_transaction(&mut self, name: &str, txn: &'a AccountTransaction) {
+        let key = format!("txn:{}", txn.uuid);
+        if self.w.reference(name, &key) {
+            return;
+        }
+        let path = self.w.open(name, &[]);
+        self.w.register(key, path);
+
+        self.w.text("uuid", &txn.uuid);
+        self.w.text("date", &format_datetime(txn.date));
+        self.w.text("currencyCode", &txn.amount.currency);
+        self.w.text("amount", &txn.amount.amount.to_string());
+        self.security_reference(txn.security_uuid.as_deref());
+        if let Some(ref cross_entry) = txn.cross_entry {
+            self.cross_entry(cross_entry);
+        }
+        self.w.text("shares", &txn.shares.unwrap_or(0).to_string());
+        self.w.opt_text("note", txn.note.as_deref());
+        self.w.opt_text("source", txn.source.as_deref());
+        self.units(&txn.units);
+        self.w.opt_text("updatedAt", txn.updated_at.as_deref());
+        self.w.text("type", txn.transaction_type.as_str());
+        self.w.close();
+    }
+
+    fn portfolio_transaction(&mut self, name: &str, txn: &'a PortfolioTransaction) {
+        let key = format!("txn:{}", txn.uuid);
+        if self.w.reference(name, &key) {
+            return;
+        }
+        let path = self.w.open(name, &[]);
+        self.w.register(key, path);
+
+        self.w.text("uuid", &txn.uuid);
+        self.w.text("date", &format_datetime(txn.date));
+        self.w.text("currencyCode", &txn.amount.currency);
+        self.w.text("amount", &txn.amount.amount.to_string());
+        self.security_reference(txn.security_uuid.as_deref());
+        if let Some(ref cross_entry) = txn.cross_entry {
+            self.cross_entry(cross_entry);
+        }
+        self.w.text("shares", &txn.shares.to_string());
+        self.w.opt_text("note", txn.note.as_deref());
+        self.w.opt_text("source", txn.source.as_deref());
+        self.units(&txn.units);
+        self.w.opt_text("updatedAt", txn.updated_at.as_deref());
+        self.w.text("type", txn.transaction_type.as_str());
+        self.w.close();
+    }
+
+    fn security_reference(&mut self, uuid: Option<&str>) {
+        if let Some(uuid) = uuid {
+            self.w.reference("security", &format!("security:{}", uuid));
+        }
+    }
+
+    fn units(&mut self, units: &[TransactionUnit]) {
+        if units.is_empty() {
+            return;
+        }
+        self.w.open("units", &[]);
+        for unit in units {
+            self.w.open("unit", &[("type", unit.unit_type.as_str())]);
+            self.w.empty(
+                "amount",
+                &[
+                    ("currency", &unit.amount.currency),
+                    ("amount", &unit.amount.amount.to_string()),
+                ],
+            );
+            if let Some(ref forex) = unit.forex {
+                self.w.empty(
+                    "forex",
+                    &[
+                        ("currency", &forex.amount.currency),
+                        ("amount", &forex.amount.amount.to_string()),
+                    ],
+                );
+                self.w.text("exchangeRate", &forex.exchange_rate.to_string());
+            }
+            self.w.close();
+        }
+        self.w.close();
+    }
+
+    /// Cross-entries hold both owners and both transactions. Entries whose
+    /// other side is missing are left out, PP would reject them.
+    fn cross_entry(&mut self, cross_entry: &CrossEntry) {
+        let source = cross_entry.source_uuid.as_str();
+        let target = cross_entry.target_uuid.as_str();
+
+        match cross_entry.entry_type {
+            CrossEntryType::BuySell => {
+                let portfolio_side = self
+                    .portfolio_txns
+                    .get(source)
+                    .or_else(|| self.portfolio_txns.get(target))
+                    .copied();
+                let account_side = self
+                    .account_txns
+                    .get(source)
+                    .or_else(|| self.account_txns.get(target))
+                    .copied();
+                let (Some((port, ptxn)), Some((acc, atxn))) = (portfolio_side, account_side) else {
+                    return;
+                };
+
+                self.w.open("crossEntry", &[("class", "buysell")]);
+                self.portfolio("portfolio", port);
+                self.portfolio_transaction("portfolioTransaction", ptxn);
+                self.account("account", acc);
+                self.account_transaction("accountTransaction", atxn);
+                self.w.close();
+            }
+            CrossEntryType::AccountTransfer => {
+                let (Some(&(from, from_txn)), Some(&(to, to_txn))) =
+                    (self.account_txns.get(source), self.account_txns.get(target))
+                else {
+                    return;
+                };
+
+                self.w.open("crossEntry", &[("class", "account-transfer")]);
+                self.account("accountFrom", from);
+                self.account_transaction("transactionFrom", from_txn);
+                self.account("accountTo", to);
+                self.account_transaction("transactionTo", to_txn);
+                self.w.close();
+            }
+            CrossEntryType::PortfolioTransfer => {
+                let (Some(&(from, from_txn)), Some(&(to, to_txn))) =
+                    (self.portfolio_txns.get(source), self.portfolio_txns.get(target))
+                else {
+                    return;
+                };
+
+                self.w.open("crossEntry", &[("class", "portfolio-transfer")]);
+                self.portfolio("portfolioFrom", from);
+                self.portfolio_transaction("transactionFrom", from_txn);
+                self.portfolio("portfolioTo", to);
+                self.portfolio_transaction("transactionTo", to_txn);
+                self.w.close();
+            }
+        }
+    }
+
+    fn classification(&mut self, name: &str, class: &Classification, parent_path: Option<&str>) {
+        let path = self.w.open(name, &[]);
+
+        self.w.text("id", &class.id);
+        self.w.text("name", &class.name);
+        self.w.opt_text("color", class.color.as_deref());
+        if let Some(parent) = parent_path {
+            self.w.empty("parent", &[("reference", parent)]);
+        }
+
+        self.w.open("children", &[]);
+        for child in &class.children {
+            self.classification("classification", child, Some(&path));
+        }
+        self.w.close();
+
+        self.w.open("assignments", &[]);
+        for assignment in &class.assignments {
+            let (class_attr, key) = if assignment.vehicle_class == "account" {
+                ("account", format!("account:{}", assignment.vehicle_uuid))
+            } else {
+                ("security", format!("security:{}", assignment.vehicle_uuid))
+            };
+            let Some(vehicle) = self.w.refs.get(&key).cloned() else {
+                continue;
+            };
+            self.w.open("assignment", &[]);
+            self.w.empty("investmentVehicle", &[("class", class_attr), ("reference", &vehicle)]);
+            self.w.text("weight", &assignment.weight.to_string());
+            self.w.text("rank", &assignment.rank.unwrap_or(0).to_string());
+            self.w.close();
+        }
+        self.w.close();
+
+        self.w.text("weight", &class.weight.unwrap_or(10000).to_string());
+        self.w.text("rank", &class.rank.unwrap_or(0).to_string());
+        self.w.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pp::{
+        AccountTransactionType, ClassificationAssignment, Money, PortfolioTransactionType,
+        PriceEntry, Taxonomy, Watchlist,
+    };
+    use chrono::NaiveDate;
+    use std::collections::HashSet;
+
+    fn sample_client() -> Client {
+        let mut client = Client::new("EUR");
+        let date = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+
+        let mut sec = Security::new("sec-1".into(), "Müller & Söhne".into(), "EUR".into());
+        sec.isin = Some("DE0007164600".into());
+        sec.prices.push(PriceEntry::new(date, 12_000_000_000));
+        client.securities.push(sec);
+
+        let mut acc = Account::new("acc-1".into(), "Konto".into(), "EUR".into());
+        let mut port = Portfolio::new("port-1".into(), "Depot".into());
+        port.reference_account_uuid = Some("acc-1".into());
+
+        let mut deposit = AccountTransaction::new(
+            "dep-1".into(),
+            date.and_hms_opt(0, 0, 0).unwrap(),
+            AccountTransactionType::Deposit,
+            Money::new(500_000, "EUR"),
+        );
+        deposit.note = Some("Start <1>".into());
+        acc.transactions.push(deposit);
+
+        let mut buy_cash = AccountTransaction::new(
+            "buy-a".into(),
+            date.and_hms_opt(9, 30, 0).unwrap(),
+            AccountTransactionType::Buy,
+            Money::new(120_500, "EUR"),
+        );
+        buy_cash.security_uuid = Some("sec-1".into());
+        buy_cash.cross_entry = Some(CrossEntry::buy_sell("buy-a".into(), "buy-p".into()));
+        acc.transactions.push(buy_cash);
+
+        let mut buy = PortfolioTransaction::new(
+            "buy-p".into(),
+            date.and_hms_opt(9, 30, 0).unwrap(),
+            PortfolioTransactionType::Buy,
+            Money::new(120_500, "EUR"),
+            1_000_000_000,
+        );
+        buy.security_uuid = Some("sec-1".into());
+        buy.units.push(TransactionUnit::fee(Money::new(500, "EUR")));
+        buy.cross_entry = Some(CrossEntry::buy_sell("buy-p".into(), "buy-a".into()));
+        port.transactions.push(buy);
+
+        client.accounts.push(acc);
+        client.portfolios.push(port);
+
+        let mut root = Classification::new("root".into(), "Anlageklassen".into());
+        let mut equity = Classification::new("equity".into(), "Aktien".into());
+        equity.assignments.push(ClassificationAssignment::security("sec-1".into(), 10000));
+        root.children.push(equity);
+        let mut tax = Taxonomy::new("tax-1".into(), "Anlageklassen".into());
+        tax.root = Some(root);
+        client.taxonomies.push(tax);
+
+        let mut wl = Watchlist::new("Favoriten");
+        wl.security_uuids.push("sec-1".into());
+        client.watchlists.push(wl);
+
+        client
+    }
+
+    /// Check well-formedness and that every reference points to an earlier element
+    fn check_references(xml: &str) -> usize {
+        let mut stack: Vec<(String, HashMap<String, usize>)> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut references = 0;
+
+        let body = xml.split_once("?>").unwrap().1;
+        for tag in body.split('<').skip(1) {
+            let tag = tag.split('>').next().unwrap();
+            if let Some(name) = tag.strip_prefix('/') {
+                let (path, _) = stack.pop().unwrap();
+                assert!(path.ends_with(name) || path.contains(&format!("/{}[", name)));
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            let name = tag.trim_end_matches('/').split_whitespace().next().unwrap();
+            let path = match stack.last_mut() {
+                Some((parent, children)) => {
+                    let count = children.entry(name.to_string()).or_insert(0);
+                    *count += 1;
+                    if *count == 1 {
+                        format!("{}/{}", parent, name)
+                    } else {
+                        format!("{}/{}[{}]", parent, name, count)
+                    }
+                }
+                None => format!("/{}", name),
+            };
+            if let Some(reference) = tag.split("reference=\"").nth(1) {
+                let target = reference.split('"').next().unwrap();
+                assert!(seen.contains(target), "unresolved reference {}", target);
+                references += 1;
+            }
+            seen.insert(path.clone());
+            if !self_closing {
+                stack.push((path, HashMap::new()));
+            }
+        }
+
+        assert!(stack.is_empty());
+        references
+    }
+
+    #[test]
+    fn test_serialize_client_xml() {
+        let xml = serialize_client_xml(&sample_client());
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<client>"));
+        assert!(xml.contains(&format!("<version>{}</version>", crate::pp::CURRENT_VERSION)));
+        assert!(xml.contains("<name>Müller &amp; Söhne</name>"));
+        assert!(xml.contains("<note>Start &lt;1&gt;</note>"));
+        assert!(xml.contains("<price t=\"2024-01-10\" v=\"12000000000\"/>"));
+        assert!(xml.contains("<date>2024-01-10T09:30</date>"));
+        assert!(xml.contains("<crossEntry class=\"buysell\">"));
+        assert!(xml.contains("<amount currency=\"EUR\" amount=\"500\"/>"));
+        assert!(xml.contains(
+            "<investmentVehicle class=\"security\" reference=\"/client/securities/security\"/>"
+        ));
+
+        // The portfolio is first reached through the buy's cross-entry
+        let inline = xml.find("<portfolio>").unwrap();
+        assert!(inline < xml.find("<portfolios>").unwrap());
+        assert!(xml.contains("<portfolio reference=\"/client/accounts/account/transactions/account-transaction[2]/crossEntry/portfolio\"/>"));
+
+        // Watchlist, security refs (2), cross-entry refs, reference account, parent, assignment
+        assert!(check_references(&xml) >= 8);
+    }
+
+    #[test]
+    fn test_cross_entry_without_counterpart_is_skipped() {
+        let mut client = sample_client();
+        client.portfolios[0].transactions.clear();
+
+        let xml = serialize_client_xml(&client);
+        assert!(!xml.contains("crossEntry"));
+        check_references(&xml);
+    }
+}
@@ -0,0 +1,175 @@
+//! Decryption of password-protected Portfolio Performance files.
+//!
+//! Encrypted files use the following layout (as written by PP):
+//! - Bytes 0-8: Signature "PORTFOLIO"
+//! - Byte 9: Method (0 = AES-128, 1 = AES-256)
+//! - Bytes 10-25: AES-CBC initialization vector
+//! - Rest: AES/CBC/PKCS5 encrypted stream
+//!
+//! The decrypted stream starts with the major version and the file version
+//! (big-endian i32 each), followed by a ZIP archive with the actual content.
+//! The key is derived with PBKDF2-HMAC-SHA1 from the password and a fixed salt.
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use anyhow::{bail, Result};
+use sha1::Sha1;
+
+/// Signature of password-protected PP files
+pub const SIGNATURE: &[u8] = b"PORTFOLIO";
+
+/// Error prefix when a password is needed to read the file
+pub const PASSWORD_REQUIRED: &str = "PASSWORD_REQUIRED";
+
+/// Error prefix when the given password does not decrypt the file
+pub const INCORRECT_PASSWORD: &str = "INCORRECT_PASSWORD";
+
+/// Fixed salt used by PP for key derivation
+const SALT: [u8; 16] = [
+    112, 67, 103, 107, 164, 131, 144, 161, 159, 142, 117, 200, 203, 187, 231, 228,
+];
+
+const ITERATION_COUNT: u32 = 65536;
+const IV_LENGTH: usize = 16;
+const HEADER_LEN: usize = SIGNATURE.len() + 1 + IV_LENGTH;
+
+/// Check whether the data is a password-protected PP file
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(SIGNATURE)
+}
+
+/// Name of the encryption method stored in the file header
+pub fn encryption_method(data: &[u8]) -> Option<&'static str> {
+    match data.get(SIGNATURE.len()) {
+        Some(0) => Some("AES-128"),
+        Some(1) => Some("AES-256"),
+        _ => None,
+    }
+}
+
+/// Decrypt a password-protected PP file
+///
+/// Returns the ZIP archive contained in the encrypted stream.
+pub fn decrypt(data: &[u8], password: Option<&str>) -> Result<Vec<u8>> {
+    let Some(password) = password.filter(|p| !p.is_empty()) else {
+        bail!("{}: File is password-protected", PASSWORD_REQUIRED);
+    };
+
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        bail!("Not an encrypted portfolio file");
+    }
+
+    let iv = &data[SIGNATURE.len() + 1..HEADER_LEN];
+    let mut buffer = data[HEADER_LEN..].to_vec();
+
+    let plain_len = match data[SIGNATURE.len()] {
+        0 => {
+            let mut key = [0u8; 16];
+            pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), &SALT, ITERATION_COUNT, &mut key);
+            cbc::Decryptor::<aes::Aes128>::new_from_slices(&key, iv)?
+                .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+                .map(|plain| plain.len())
+        }
+        1 => {
+            let mut key = [0u8; 32];
+            pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), &SALT, ITERATION_COUNT, &mut key);
+            cbc::Decryptor::<aes::Aes256>::new_from_slices(&key, iv)?
+                .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+                .map(|plain| plain.len())
+        }
+        method => bail!("Unsupported encryption method: {}", method),
+    };
+
+    // A wrong key almost always produces invalid padding
+    let Ok(plain_len) = plain_len else {
+        bail!("{}: Incorrect password", INCORRECT_PASSWORD);
+    };
+    buffer.truncate(plain_len);
+
+    if buffer.len() < 8 {
+        bail!("{}: Incorrect password", INCORRECT_PASSWORD);
+    }
+    let major_version = i32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+    let version = i32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+
+    // Same sanity check as PP to detect a wrong password
+    if !(1..=10).contains(&major_version) || !(1..=100).contains(&version) {
+        bail!("{}: Incorrect password", INCORRECT_PASSWORD);
+    }
+
+    // The ZIP archive follows the version information
+    let Some(start) = buffer.windows(4).take(64).position(|w| w == b"PK\x03\x04") else {
+        bail!("Encrypted file contains no ZIP archive");
+    };
+
+    Ok(buffer.split_off(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+
+    /// Encrypt a ZIP archive the way PP does (AES-128)
+    fn encrypt(zip: &[u8], password: &str) -> Vec<u8> {
+        let mut key = [0u8; 16];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), &SALT, ITERATION_COUNT, &mut key);
+        let iv = [7u8; IV_LENGTH];
+
+        let mut plain = Vec::new();
+        plain.extend_from_slice(&1i32.to_be_bytes());
+        plain.extend_from_slice(&crate::pp::CURRENT_VERSION.to_be_bytes());
+        plain.extend_from_slice(zip);
+        let len = plain.len();
+        plain.resize(len + 16, 0);
+        let encrypted_len = cbc::Encryptor::<aes::Aes128>::new_from_slices(&key, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<Pkcs7>(&mut plain, len)
+            .unwrap()
+            .len();
+        plain.truncate(encrypted_len);
+
+        let mut data = SIGNATURE.to_vec();
+        data.push(0);
+        data.extend_from_slice(&iv);
+        data.extend_from_slice(&plain);
+        data
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let zip = b"PK\x03\x04 archive content";
+        let data = encrypt(zip, "geheim");
+
+        assert!(is_encrypted(&data));
+        assert_eq!(encryption_method(&data), Some("AES-128"));
+        assert_eq!(decrypt(&data, Some("geheim")).unwrap(), zip.to_vec());
+
+        let missing = decrypt(&data, None).unwrap_err().to_string();
+        assert!(missing.starts_with(PASSWORD_REQUIRED));
+        let wrong = decrypt(&data, Some("falsch")).unwrap_err().to_string();
+        assert!(wrong.starts_with(INCORRECT_PASSWORD));
+    }
+
+    #[test]
+    fn test_parse_encrypted_file() {
+        let dir = std::env::temp_dir().join(format!("pp_encrypted_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain_path = dir.join("plain.portfolio");
+        let encrypted_path = dir.join("encrypted.portfolio");
+
+        super::super::write_portfolio_file(&plain_path, &crate::pp::Client::new("CHF")).unwrap();
+        let zip = std::fs::read(&plain_path).unwrap();
+        std::fs::write(&encrypted_path, encrypt(&zip, "geheim")).unwrap();
+
+        let client =
+            super::super::parse_portfolio_file_with_password(&encrypted_path, Some("geheim"));
+        let without = super::super::parse_portfolio_file(&encrypted_path);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(client.unwrap().base_currency, "CHF");
+        assert!(without
+            .unwrap_err()
+            .to_string()
+            .starts_with(PASSWORD_REQUIRED));
+    }
+}
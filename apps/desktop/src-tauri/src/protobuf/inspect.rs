@@ -0,0 +1,355 @@
+//! Lightweight inspection of Portfolio Performance files.
+//!
+//! Detects the container and payload format, the file version and encryption
+//! without converting the data, so problems can be reported before an import.
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+use super::encryption;
+use crate::pp::CURRENT_VERSION;
+
+/// ZIP local file header
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+/// Only the beginning of XML files is scanned for version and currency
+const XML_HEADER_BYTES: u64 = 64 * 1024;
+
+/// Highest top-level field of the Client message that is imported (1-12)
+const LAST_CLIENT_FIELD: u32 = 12;
+
+/// Payload format of a PP file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PpFileFormat {
+    Protobuf,
+    Xml,
+    Unknown,
+}
+
+/// Result of inspecting a PP file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PpFileInfo {
+    pub format: PpFileFormat,
+    /// Payload is stored in a ZIP archive
+    pub compressed: bool,
+    /// File is password-protected, format and version are unknown until decrypted
+    pub encrypted: bool,
+    pub encryption_method: Option<String>,
+    pub version: Option<i32>,
+    /// Newest file version known to this build
+    pub supported_version: i32,
+    pub base_currency: Option<String>,
+    /// Data blocks that are skipped during import
+    pub unsupported_features: Vec<String>,
+    /// Reasons the file cannot be imported
+    pub issues: Vec<String>,
+    pub importable: bool,
+}
+
+impl PpFileInfo {
+    fn new(format: PpFileFormat) -> Self {
+        Self {
+            format,
+            compressed: false,
+            encrypted: false,
+            encryption_method: None,
+            version: None,
+            supported_version: CURRENT_VERSION,
+            base_currency: None,
+            unsupported_features: Vec::new(),
+            issues: Vec::new(),
+            importable: false,
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        if let Some(version) = self.version {
+            if version > CURRENT_VERSION {
+                self.unsupported_features.push(format!(
+                    "File version {} is newer than the supported version {}; newer data may be skipped",
+                    version, CURRENT_VERSION
+                ));
+            }
+        }
+        self.importable =
+            (self.format == PpFileFormat::Protobuf || self.encrypted) && self.issues.is_empty();
+        self
+    }
+}
+
+/// Inspect a .portfolio file without converting its content
+pub fn inspect_portfolio_file(path: &Path) -> Result<PpFileInfo> {
+    let mut file = std::fs::File::open(path).context("Failed to open file")?;
+    let mut signature = [0u8; 10];
+    let read = file.read(&mut signature).context("Failed to read file")?;
+    let signature = &signature[..read];
+
+    if signature.starts_with(ZIP_SIGNATURE) {
+        let file = std::fs::File::open(path).context("Failed to open file")?;
+        let mut archive = ZipArchive::new(file).context("Failed to read ZIP archive")?;
+        return inspect_archive(&mut archive);
+    }
+
+    // Content is only readable after decryption, the import asks for the password
+    if encryption::is_encrypted(signature) {
+        let mut info = PpFileInfo::new(PpFileFormat::Unknown);
+        info.encrypted = true;
+        info.encryption_method = encryption::encryption_method(signature).map(str::to_string);
+        return Ok(info.finish());
+    }
+
+    if signature.starts_with(super::HEADER) {
+        let mut data = Vec::new();
+        std::fs::File::open(path)
+            .context("Failed to open file")?
+            .take(super::parser::MAX_UNCOMPRESSED_SIZE)
+            .read_to_end(&mut data)
+            .context("Failed to read file")?;
+        return Ok(inspect_protobuf(&data, false));
+    }
+
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .context("Failed to open file")?
+        .take(XML_HEADER_BYTES)
+        .read_to_end(&mut head)
+        .context("Failed to read file")?;
+    Ok(inspect_xml(&head, false))
+}
+
+fn inspect_archive<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<PpFileInfo> {
+    if let Ok(entry) = archive.by_name("data.portfolio") {
+        if entry.size() > super::parser::MAX_UNCOMPRESSED_SIZE {
+            let mut info = PpFileInfo::new(PpFileFormat::Protobuf);
+            info.compressed = true;
+            info.issues.push(format!(
+                "Portfolio file too large: {} MB",
+                entry.size() / (1024 * 1024)
+            ));
+            return Ok(info.finish());
+        }
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry
+            .take(super::parser::MAX_UNCOMPRESSED_SIZE)
+            .read_to_end(&mut data)
+            .context("Failed to read data.portfolio")?;
+        return Ok(inspect_protobuf(&data, true));
+    }
+
+    if let Ok(entry) = archive.by_name("data.xml") {
+        let mut head = Vec::new();
+        entry
+            .take(XML_HEADER_BYTES)
+            .read_to_end(&mut head)
+            .context("Failed to read data.xml")?;
+        return Ok(inspect_xml(&head, true));
+    }
+
+    let mut info = PpFileInfo::new(PpFileFormat::Unknown);
+    info.compressed = true;
+    info.issues
+        .push("ZIP archive contains neither data.portfolio nor data.xml".to_string());
+    Ok(info.finish())
+}
+
+/// Inspect the header and the top-level fields of the protobuf Client message
+fn inspect_protobuf(data: &[u8], compressed: bool) -> PpFileInfo {
+    let mut info = PpFileInfo::new(PpFileFormat::Protobuf);
+    info.compressed = compressed;
+
+    if !data.starts_with(super::HEADER) {
+        info.issues.push(format!(
+            "Invalid header, expected PPPBV1, got {:?}",
+            String::from_utf8_lossy(&data[..data.len().min(super::HEADER_LEN)])
+        ));
+        return info.finish();
+    }
+
+    let mut pos = super::HEADER_LEN;
+    let mut unknown_fields: Vec<u32> = Vec::new();
+    while pos < data.len() {
+        let Some(key) = read_varint(data, &mut pos) else {
+            info.issues.push("Corrupted protobuf data".to_string());
+            break;
+        };
+        let tag = (key >> 3) as u32;
+        let wire_type = key & 0x7;
+
+        match (tag, wire_type) {
+            (1, 0) => match read_varint(data, &mut pos) {
+                Some(version) => info.version = Some(version as i32),
+                None => {
+                    info.issues.push("Corrupted protobuf data".to_string());
+                    break;
+                }
+            },
+            (12, 2) => match read_length_delimited(data, &mut pos) {
+                Some(bytes) => {
+                    info.base_currency = Some(String::from_utf8_lossy(bytes).into_owned())
+                }
+                None => {
+                    info.issues.push("Corrupted protobuf data".to_string());
+                    break;
+                }
+            },
+            _ => {
+                if !skip_field(data, &mut pos, wire_type) {
+                    info.issues.push("Corrupted protobuf data".to_string());
+                    break;
+                }
+                if !(1..=LAST_CLIENT_FIELD).contains(&tag) && !unknown_fields.contains(&tag) {
+                    unknown_fields.push(tag);
+                }
+            }
+        }
+    }
+
+    for tag in unknown_fields {
+        info.unsupported_features.push(match tag {
+            99 => "Extensions (plugin data)".to_string(),
+            _ => format!("Unknown data block (field {})", tag),
+        });
+    }
+
+    info.finish()
+}
+
+/// Scan the beginning of an XML file for version and base currency
+fn inspect_xml(head: &[u8], compressed: bool) -> PpFileInfo {
+    let text = String::from_utf8_lossy(head);
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+
+    if !trimmed.starts_with("<?xml") && !trimmed.starts_with("<client") {
+        let mut info = PpFileInfo::new(PpFileFormat::Unknown);
+        info.compressed = compressed;
+        info.issues
+            .push("Unknown file format, not a Portfolio Performance file".to_string());
+        return info.finish();
+    }
+
+    let mut info = PpFileInfo::new(PpFileFormat::Xml);
+    info.compressed = compressed;
+    info.version = xml_element(trimmed, "version").and_then(|v| v.parse().ok());
+    info.base_currency = xml_element(trimmed, "baseCurrency").map(str::to_string);
+    info.issues.push(
+        "XML files cannot be imported. Save the file in Portfolio Performance in the binary format"
+            .to_string(),
+    );
+    info.finish()
+}
+
+/// Text of the first `<name>...</name>` element
+fn xml_element<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let start = text.find(&open)? + open.len();
+    let end = start + text[start..].find('<')?;
+    Some(text[start..end].trim())
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_length_delimited<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let len = usize::try_from(read_varint(data, pos)?).ok()?;
+    let end = pos.checked_add(len)?;
+    let bytes = data.get(*pos..end)?;
+    *pos = end;
+    Some(bytes)
+}
+
+fn skip_field(data: &[u8], pos: &mut usize, wire_type: u64) -> bool {
+    let skip = |pos: &mut usize, len: usize| match pos.checked_add(len) {
+        Some(end) if end <= data.len() => {
+            *pos = end;
+            true
+        }
+        _ => false,
+    };
+    match wire_type {
+        0 => read_varint(data, pos).is_some(),
+        1 => skip(pos, 8),
+        2 => read_length_delimited(data, pos).is_some(),
+        5 => skip(pos, 4),
+        // Groups are not used by PP
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pp::Client;
+
+    #[test]
+    fn test_inspect_protobuf() {
+        let client = Client::new("EUR");
+        let mut data = super::super::serialize_client(&client).unwrap();
+
+        let info = inspect_protobuf(&data, true);
+        assert_eq!(info.format, PpFileFormat::Protobuf);
+        assert_eq!(info.version, Some(CURRENT_VERSION));
+        assert_eq!(info.base_currency.as_deref(), Some("EUR"));
+        assert!(info.importable);
+        assert!(info.unsupported_features.is_empty());
+
+        // Extension block (field 99, empty message)
+        data.extend_from_slice(&[0x9a, 0x06, 0x00]);
+        let info = inspect_protobuf(&data, true);
+        assert!(info.importable);
+        assert_eq!(
+            info.unsupported_features,
+            vec!["Extensions (plugin data)".to_string()]
+        );
+
+        // Truncated length-delimited field
+        data.extend_from_slice(&[0x12, 0x05, 0x01]);
+        let info = inspect_protobuf(&data, true);
+        assert!(!info.importable);
+    }
+
+    #[test]
+    fn test_inspect_files() {
+        let dir = std::env::temp_dir().join(format!("pp_inspect_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let client = Client::new("CHF");
+
+        let protobuf_path = dir.join("protobuf.portfolio");
+        super::super::write_portfolio_file(&protobuf_path, &client).unwrap();
+        let xml_path = dir.join("xml.portfolio");
+        crate::pp::xml::write_xml_file(&xml_path, &client).unwrap();
+        let encrypted_path = dir.join("encrypted.portfolio");
+        std::fs::write(&encrypted_path, b"PORTFOLIO\x01\x00\x00\x00").unwrap();
+
+        let protobuf = inspect_portfolio_file(&protobuf_path).unwrap();
+        let xml = inspect_portfolio_file(&xml_path).unwrap();
+        let encrypted = inspect_portfolio_file(&encrypted_path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(protobuf.compressed && protobuf.importable);
+        assert_eq!(protobuf.base_currency.as_deref(), Some("CHF"));
+
+        assert_eq!(xml.format, PpFileFormat::Xml);
+        assert_eq!(xml.version, Some(CURRENT_VERSION));
+        assert_eq!(xml.base_currency.as_deref(), Some("CHF"));
+        assert!(!xml.importable);
+
+        assert!(encrypted.encrypted && encrypted.importable);
+        assert_eq!(encrypted.encryption_method.as_deref(), Some("AES-256"));
+    }
+}
@@ -3,12 +3,18 @@
 //! PP files (.portfolio) are ZIP archives containing `data.portfolio` with format:
 //! - Bytes 0-5: Header "PPPBV1" (Portfolio Performance Protobuf Version 1)
 //! - Bytes 6+: Protobuf Client message
+//!
+//! Password-protected files wrap this archive in an AES-encrypted stream
+//! (see [`encryption`]).
 
+pub mod encryption;
+pub mod inspect;
 pub mod parser;
 mod schema;
 pub mod writer;
 
-pub use parser::{parse_portfolio_file, parse_to_client};
+pub use inspect::{inspect_portfolio_file, PpFileFormat, PpFileInfo};
+pub use parser::{parse_portfolio_file, parse_portfolio_file_with_password, parse_to_client};
 pub use writer::{serialize_client, write_portfolio_file};
 
 /// Magic header for PP protobuf format
@@ -1,6 +1,6 @@
 //! Parser for Portfolio Performance protobuf binary files.
 
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
@@ -8,12 +8,13 @@ use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use prost::Message;
 use zip::ZipArchive;
 
+use super::encryption;
 use super::schema::{self, PClient};
 
 /// Maximum uncompressed size for portfolio files (500 MB)
 /// This protects against ZIP bomb attacks where a small compressed file
 /// expands to consume all available memory
-const MAX_UNCOMPRESSED_SIZE: u64 = 500 * 1024 * 1024;
+pub(super) const MAX_UNCOMPRESSED_SIZE: u64 = 500 * 1024 * 1024;
 use crate::pp::{
     common::{ForexInfo, Money},
     security::{DividendEvent, SecurityEvent, SecurityEventKind, SecurityEventType},
@@ -28,8 +29,36 @@ use crate::pp::{
 
 /// Parse a .portfolio file (ZIP archive) into Client
 pub fn parse_portfolio_file(path: &Path) -> Result<Client> {
+    parse_portfolio_file_with_password(path, None)
+}
+
+/// Parse a .portfolio file that may be password-protected
+///
+/// Encrypted files without password fail with an error starting with
+/// [`PASSWORD_REQUIRED`](super::encryption::PASSWORD_REQUIRED).
+pub fn parse_portfolio_file_with_password(path: &Path, password: Option<&str>) -> Result<Client> {
+    let mut file = std::fs::File::open(path).context("Failed to open file")?;
+    let mut signature = [0u8; 9];
+    let is_encrypted = file.read_exact(&mut signature).is_ok() && encryption::is_encrypted(&signature);
+
+    if is_encrypted {
+        let data = std::fs::read(path).context("Failed to read file")?;
+        let zip = encryption::decrypt(&data, password)?;
+        let mut archive =
+            ZipArchive::new(Cursor::new(zip)).context("Failed to read decrypted ZIP archive")?;
+        return parse_archive(&mut archive);
+    }
+
     let file = std::fs::File::open(path).context("Failed to open file")?;
     let mut archive = ZipArchive::new(file).context("Failed to read ZIP archive")?;
+    parse_archive(&mut archive)
+}
+
+/// Parse the data.portfolio entry of a .portfolio archive
+fn parse_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Client> {
+    if archive.by_name("data.portfolio").is_err() && archive.by_name("data.xml").is_ok() {
+        bail!("XML files cannot be imported. Save the file in Portfolio Performance in the binary format");
+    }
 
     let data_file = archive
         .by_name("data.portfolio")
@@ -210,6 +239,8 @@ fn convert_client(pb: &PClient) -> Result<Client> {
                         target_tx.uuid = transfer_in_uuid.clone();
 
                         if let Some(mut tx) = convert_account_transaction(&target_tx)? {
+                            // other_account is set on the copy, which would make it TRANSFER_OUT
+                            tx.transaction_type = AccountTransactionType::TransferIn;
                             // Set cross-entry to link TRANSFER_OUT -> TRANSFER_IN
                             tx.cross_entry = Some(CrossEntry::account_transfer(
                                 transfer_out_uuid.clone(),
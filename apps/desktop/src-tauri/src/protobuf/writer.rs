@@ -2,6 +2,7 @@
 //!
 //! Creates .portfolio files compatible with Portfolio Performance.
 
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
 
@@ -19,8 +20,8 @@ use super::schema::{
 use crate::pp::{
     taxonomy::Classification,
     transaction::{
-        AccountTransaction, AccountTransactionType, PortfolioTransaction, PortfolioTransactionType,
-        TransactionUnit, UnitType,
+        AccountTransaction, AccountTransactionType, CrossEntry, PortfolioTransaction,
+        PortfolioTransactionType, TransactionUnit, UnitType,
     },
     Account, Client, Portfolio, Security,
 };
@@ -97,11 +98,23 @@ fn convert_to_protobuf(client: &Client) -> Result<PClient> {
         pb.portfolios.push(convert_portfolio(port));
     }
 
+    // Owner of each transaction, needed to resolve the other side of transfers
+    let portfolio_of_txn: HashMap<&str, &str> = client
+        .portfolios
+        .iter()
+        .flat_map(|p| p.transactions.iter().map(move |t| (t.uuid.as_str(), p.uuid.as_str())))
+        .collect();
+    let account_of_txn: HashMap<&str, &str> = client
+        .accounts
+        .iter()
+        .flat_map(|a| a.transactions.iter().map(move |t| (t.uuid.as_str(), a.uuid.as_str())))
+        .collect();
+
     // Collect all transactions into a unified list
     // Portfolio transactions
     for portfolio in &client.portfolios {
         for tx in &portfolio.transactions {
-            if let Some(pb_tx) = convert_portfolio_transaction(tx, &portfolio.uuid) {
+            if let Some(pb_tx) = convert_portfolio_transaction(tx, &portfolio.uuid, &portfolio_of_txn) {
                 pb.transactions.push(pb_tx);
             }
         }
@@ -110,7 +123,7 @@ fn convert_to_protobuf(client: &Client) -> Result<PClient> {
     // Account transactions
     for account in &client.accounts {
         for tx in &account.transactions {
-            if let Some(pb_tx) = convert_account_transaction(tx, &account.uuid) {
+            if let Some(pb_tx) = convert_account_transaction(tx, &account.uuid, &account_of_txn) {
                 pb.transactions.push(pb_tx);
             }
         }
@@ -231,8 +244,24 @@ fn convert_portfolio(port: &Portfolio) -> PPortfolio {
     }
 }
 
+/// UUID of the other transaction of a cross-entry
+fn counterpart_uuid(cross_entry: &CrossEntry, own_uuid: &str) -> String {
+    if cross_entry.source_uuid == own_uuid {
+        cross_entry.target_uuid.clone()
+    } else {
+        cross_entry.source_uuid.clone()
+    }
+}
+
 /// Convert pp::PortfolioTransaction to PTransaction
-fn convert_portfolio_transaction(tx: &PortfolioTransaction, portfolio_uuid: &str) -> Option<PTransaction> {
+///
+/// A security transfer is written once from the TRANSFER_IN side, with the
+/// source portfolio in `portfolio` and the destination in `other_portfolio`.
+fn convert_portfolio_transaction(
+    tx: &PortfolioTransaction,
+    portfolio_uuid: &str,
+    portfolio_of_txn: &HashMap<&str, &str>,
+) -> Option<PTransaction> {
     use schema::transaction_type::*;
 
     let transaction_type = match tx.transaction_type {
@@ -247,14 +276,29 @@ fn convert_portfolio_transaction(tx: &PortfolioTransaction, portfolio_uuid: &str
         }
     };
 
+    let other_uuid = tx.cross_entry.as_ref().map(|ce| counterpart_uuid(ce, &tx.uuid));
+
+    // Transfers: the source portfolio owns the TRANSFER_OUT counterpart
+    let source_portfolio = match tx.transaction_type {
+        PortfolioTransactionType::TransferIn => other_uuid
+            .as_deref()
+            .and_then(|uuid| portfolio_of_txn.get(uuid))
+            .map(|p| p.to_string()),
+        _ => None,
+    };
+    let (portfolio, other_portfolio) = match source_portfolio {
+        Some(source) => (source, Some(portfolio_uuid.to_string())),
+        None => (portfolio_uuid.to_string(), None),
+    };
+
     let mut pb = PTransaction {
         uuid: tx.uuid.clone(),
         transaction_type,
-        portfolio: Some(portfolio_uuid.to_string()),
+        portfolio: Some(portfolio),
         account: None,
         other_account: None,
-        other_portfolio: None,
-        other_uuid: tx.cross_entry.as_ref().map(|ce| ce.target_uuid.clone()),
+        other_portfolio,
+        other_uuid,
         other_updated_at: tx.other_updated_at.as_ref().and_then(|s| parse_timestamp(s)),
         date: Some(datetime_to_timestamp(tx.date)),
         currency_code: tx.amount.currency.clone(),
@@ -276,7 +320,14 @@ fn convert_portfolio_transaction(tx: &PortfolioTransaction, portfolio_uuid: &str
 }
 
 /// Convert pp::AccountTransaction to PTransaction
-fn convert_account_transaction(tx: &AccountTransaction, account_uuid: &str) -> Option<PTransaction> {
+///
+/// A cash transfer is written once from the TRANSFER_OUT side, with the
+/// target account in `other_account`.
+fn convert_account_transaction(
+    tx: &AccountTransaction,
+    account_uuid: &str,
+    account_of_txn: &HashMap<&str, &str>,
+) -> Option<PTransaction> {
     use schema::transaction_type::*;
 
     let transaction_type = match tx.transaction_type {
@@ -298,14 +349,25 @@ fn convert_account_transaction(tx: &AccountTransaction, account_uuid: &str) -> O
         AccountTransactionType::TransferOut => CASH_TRANSFER,
     };
 
+    let other_uuid = tx.cross_entry.as_ref().map(|ce| counterpart_uuid(ce, &tx.uuid));
+
+    let other_account = match tx.transaction_type {
+        AccountTransactionType::TransferOut => other_uuid
+            .as_deref()
+            .and_then(|uuid| account_of_txn.get(uuid))
+            .map(|a| a.to_string())
+            .or_else(|| tx.other_account_uuid.clone()),
+        _ => None,
+    };
+
     let mut pb = PTransaction {
         uuid: tx.uuid.clone(),
         transaction_type,
         account: Some(account_uuid.to_string()),
         portfolio: None,
-        other_account: tx.cross_entry.as_ref().map(|ce| ce.target_uuid.clone()),
+        other_account,
         other_portfolio: None,
-        other_uuid: tx.cross_entry.as_ref().map(|ce| ce.target_uuid.clone()),
+        other_uuid,
         other_updated_at: tx.other_updated_at.as_ref().and_then(|s| parse_timestamp(s)),
         date: Some(datetime_to_timestamp(tx.date)),
         currency_code: tx.amount.currency.clone(),
@@ -4,7 +4,7 @@
 //! Free tier: 25 API calls/day, Premium plans available.
 //! API key required - get one at https://www.alphavantage.co/support/#api-key
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use reqwest::Client;
@@ -126,7 +126,7 @@ pub async fn fetch_quote(symbol: &str, api_key: &str) -> Result<LatestQuote> {
         BASE_URL, symbol, api_key
     );
 
-    let response = client.get(&url).send().await?;
+    let response = send_with_retry(client.get(&url)).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("Alpha Vantage API error: {}", response.status()));
@@ -202,7 +202,7 @@ pub async fn fetch_historical(
         BASE_URL, symbol, output_size, api_key
     );
 
-    let response = client.get(&url).send().await?;
+    let response = send_with_retry(client.get(&url)).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("Alpha Vantage API error: {}", response.status()));
@@ -266,7 +266,7 @@ pub async fn search(query: &str, api_key: &str) -> Result<Vec<SearchMatch>> {
         BASE_URL, query, api_key
     );
 
-    let response = client.get(&url).send().await?;
+    let response = send_with_retry(client.get(&url)).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("Alpha Vantage search error: {}", response.status()));
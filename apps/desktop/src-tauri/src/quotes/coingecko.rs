@@ -7,7 +7,7 @@
 //!
 //! API documentation: https://docs.coingecko.com/
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::{NaiveDate, TimeZone, Utc};
 use reqwest::Client;
@@ -95,7 +95,7 @@ pub async fn fetch_quote(coin_id: &str, currency: &str, api_key: Option<&str>) -
         }
     }
 
-    let response = request.send().await?;
+    let response = send_with_retry(request).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!(
@@ -181,7 +181,7 @@ pub async fn fetch_historical(
         }
     }
 
-    let response = request.send().await?;
+    let response = send_with_retry(request).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!(
@@ -255,7 +255,7 @@ pub async fn search_coin(query: &str, api_key: Option<&str>) -> Result<Vec<CoinI
         }
     }
 
-    let response = request.send().await?;
+    let response = send_with_retry(request).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("CoinGecko search error: {}", response.status()));
@@ -6,7 +6,7 @@
 //!
 //! API: https://data.ecb.europa.eu/
 
-use super::ExchangeRate;
+use super::{retry::send_with_retry, ExchangeRate};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use std::collections::HashMap;
@@ -22,7 +22,7 @@ const ECB_HIST_ALL_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofx
 
 /// Aktuelle Wechselkurse abrufen (EUR-Basis)
 pub async fn fetch_latest_rates() -> Result<Vec<ExchangeRate>> {
-    let response = reqwest::get(ECB_DAILY_URL)
+    let response = send_with_retry(reqwest::Client::new().get(ECB_DAILY_URL))
         .await
         .map_err(|e| anyhow!("Request failed: {}", e))?;
 
@@ -53,7 +53,7 @@ pub async fn fetch_historical_rates(
         ECB_HIST_ALL_URL
     };
 
-    let response = reqwest::get(url)
+    let response = send_with_retry(reqwest::Client::new().get(url))
         .await
         .map_err(|e| anyhow!("Request failed: {}", e))?;
 
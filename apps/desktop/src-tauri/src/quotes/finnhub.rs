@@ -6,7 +6,7 @@
 //!
 //! API-Dokumentation: https://finnhub.io/docs/api
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use reqwest::header::{HeaderMap, HeaderValue};
@@ -96,9 +96,7 @@ pub async fn fetch_quote(symbol: &str, api_key: &str) -> Result<LatestQuote> {
     log::info!("Fetching Finnhub quote for {} (normalized: {}) from {}", symbol, normalized_symbol, url);
 
     let client = create_client(api_key)?;
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", symbol, e))?;
 
@@ -176,9 +174,7 @@ pub async fn fetch_historical(
     log::info!("Fetching Finnhub historical for {} (normalized: {}) from {}", symbol, normalized_symbol, url);
 
     let client = create_client(api_key)?;
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", symbol, e))?;
 
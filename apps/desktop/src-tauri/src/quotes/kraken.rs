@@ -11,7 +11,7 @@
 //! - Fiat currencies: Z prefix (ZEUR = Euro, ZUSD = USD)
 //! - Examples: BTC/EUR = XXBTZEUR, ETH/USD = XETHZUSD
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::{NaiveDate, TimeZone, Utc};
 use reqwest::Client;
@@ -72,10 +72,7 @@ pub async fn fetch_quote(pair: &str, currency: &str) -> Result<LatestQuote> {
     let url = format!("{}/Ticker?pair={}", BASE_URL, kraken_pair);
     log::debug!("Fetching Kraken ticker for {} from {}", kraken_pair, url);
 
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
+    let response = send_with_retry(client.get(&url).header("Accept", "application/json"))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", kraken_pair, e))?;
 
@@ -161,10 +158,7 @@ pub async fn fetch_historical(
     );
     log::debug!("Fetching Kraken OHLC for {} from {}", kraken_pair, url);
 
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
+    let response = send_with_retry(client.get(&url).header("Accept", "application/json"))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", kraken_pair, e))?;
 
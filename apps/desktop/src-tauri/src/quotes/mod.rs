@@ -8,6 +8,7 @@
 //! - Alpha Vantage (Aktien, ETFs - API-Key erforderlich)
 //! - Twelve Data (Schweizer Aktien, internationale Märkte - API-Key erforderlich)
 //! - TradingView (Globale Märkte - inoffizielle API)
+//! - Stooq (Tagesschlusskurse, europäische Börsen - kein API-Key)
 
 pub mod alphavantage;
 pub mod assistant;
@@ -15,6 +16,8 @@ pub mod coingecko;
 pub mod ecb;
 pub mod finnhub;
 pub mod kraken;
+pub mod retry;
+pub mod stooq;
 pub mod suggestion;
 pub mod tradingview;
 pub mod twelvedata;
@@ -22,7 +25,14 @@ pub mod yahoo;
 
 use anyhow::Result;
 use chrono::NaiveDate;
+use futures::stream::{FuturesUnordered, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Maximale Anzahl gleichzeitiger Kursabfragen bei der Synchronisation
+pub const MAX_CONCURRENT_QUOTE_REQUESTS: usize = 8;
 
 /// Einzelner Kursdatenpunkt
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,8 +107,17 @@ pub struct HistoricalDataWithSplits {
     pub splits: Vec<SplitEvent>,
 }
 
+/// Intraday-Kurspunkt (nur für den Live-Chart, EOD-Kurse bleiben in `pp_price`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntradayQuote {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub price: f64,
+    pub volume: Option<i64>,
+}
+
 /// Provider-Typ für die Konfiguration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ProviderType {
     Yahoo,
@@ -110,6 +129,7 @@ pub enum ProviderType {
     CoinGecko,
     Kraken,
     Finnhub,
+    Stooq,
     Manual,
 }
 
@@ -125,6 +145,7 @@ impl ProviderType {
             "COINGECKO" => Some(Self::CoinGecko),
             "KRAKEN" => Some(Self::Kraken),
             "FINNHUB" => Some(Self::Finnhub),
+            "STOOQ" => Some(Self::Stooq),
             "MANUAL" => Some(Self::Manual),
             _ => None,
         }
@@ -141,9 +162,20 @@ impl ProviderType {
             Self::CoinGecko => "COINGECKO",
             Self::Kraken => "KRAKEN",
             Self::Finnhub => "FINNHUB",
+            Self::Stooq => "STOOQ",
             Self::Manual => "MANUAL",
         }
     }
+
+    /// Maximale parallele Anfragen an diesen Provider.
+    /// API-Key-Provider haben im Free Tier strenge Limits pro Minute.
+    pub fn max_concurrent_requests(&self) -> usize {
+        match self {
+            Self::AlphaVantage | Self::TwelveData => 1,
+            Self::Finnhub | Self::CoinGecko | Self::TradingView | Self::Stooq => 2,
+            _ => MAX_CONCURRENT_QUOTE_REQUESTS,
+        }
+    }
 }
 
 /// Ergebnis einer Kursabfrage für eine Security
@@ -214,6 +246,47 @@ pub async fn fetch_all_quotes(
     results
 }
 
+/// Kurse für mehrere Securities parallel abrufen.
+///
+/// Es laufen höchstens `MAX_CONCURRENT_QUOTE_REQUESTS` Anfragen gleichzeitig,
+/// zusätzlich begrenzt durch `ProviderType::max_concurrent_requests`.
+/// `on_result` wird für jedes Ergebnis aufgerufen, sobald es vorliegt
+/// (z.B. für Fortschritts-Events). Die Ergebnisse kommen in Abschlussreihenfolge.
+pub async fn fetch_all_quotes_concurrent<F>(
+    securities: Vec<SecurityQuoteRequest>,
+    mut on_result: F,
+) -> Vec<QuoteResult>
+where
+    F: FnMut(&QuoteResult),
+{
+    let global = Arc::new(Semaphore::new(MAX_CONCURRENT_QUOTE_REQUESTS));
+    let mut provider_limits: HashMap<ProviderType, Arc<Semaphore>> = HashMap::new();
+
+    let mut pending = FuturesUnordered::new();
+    for sec in securities {
+        let provider_limit = provider_limits
+            .entry(sec.provider)
+            .or_insert_with(|| Arc::new(Semaphore::new(sec.provider.max_concurrent_requests())))
+            .clone();
+        let global = global.clone();
+
+        pending.push(async move {
+            // Provider-Slot zuerst, damit gedrosselte Provider keine globalen Slots blockieren
+            let _provider_permit = provider_limit.acquire().await;
+            let _permit = global.acquire().await;
+            fetch_quote_for_security(&sec).await
+        });
+    }
+
+    let mut results = Vec::with_capacity(pending.len());
+    while let Some(result) = pending.next().await {
+        on_result(&result);
+        results.push(result);
+    }
+
+    results
+}
+
 /// Security-Anfrage für Kursabfrage
 #[derive(Debug, Clone)]
 pub struct SecurityQuoteRequest {
@@ -364,6 +437,13 @@ async fn fetch_quote_for_security(sec: &SecurityQuoteRequest) -> QuoteResult {
                 Err(e) => QuoteResult::error(sec.id, sec.symbol.clone(), sec.provider.as_str(), e.to_string()),
             }
         }
+        ProviderType::Stooq => {
+            // feed_url may carry a Yahoo-style exchange suffix (e.g., ".DE")
+            match stooq::fetch_quote(&sec.symbol, sec.feed_url.as_deref()).await {
+                Ok(quote) => QuoteResult::success(sec.id, sec.symbol.clone(), sec.provider.as_str(), quote),
+                Err(e) => QuoteResult::error(sec.id, sec.symbol.clone(), sec.provider.as_str(), e.to_string()),
+            }
+        }
         ProviderType::Manual => {
             QuoteResult::error(sec.id, sec.symbol.clone(), "MANUAL", "Manual quotes not fetched automatically".to_string())
         }
@@ -491,12 +571,34 @@ pub async fn fetch_historical_quotes_with_options(
             };
             tradingview::fetch_historical(&full_symbol, from, to).await
         }
+        ProviderType::Stooq => stooq::fetch_historical(symbol, exchange_suffix, from, to).await,
         _ => {
             anyhow::bail!("Historical quotes not supported for provider {:?}", provider)
         }
     }
 }
 
+/// Intraday-Kurse des aktuellen Handelstags abrufen (derzeit nur Yahoo)
+pub async fn fetch_intraday_quotes(
+    symbol: &str,
+    provider: ProviderType,
+    exchange_suffix: Option<&str>,
+) -> Result<Vec<IntradayQuote>> {
+    match provider {
+        ProviderType::Yahoo | ProviderType::YahooAdjustedClose => {
+            // Apply exchange suffix if provided and symbol doesn't already have one
+            let full_symbol = match exchange_suffix {
+                Some(suffix) if suffix.starts_with('.') && !symbol.contains('.') => {
+                    format!("{}{}", symbol, suffix)
+                }
+                _ => symbol.to_string(),
+            };
+            yahoo::fetch_intraday(&full_symbol).await
+        }
+        _ => anyhow::bail!("Intraday quotes not supported for provider {:?}", provider),
+    }
+}
+
 /// Extract base crypto symbol from various formats
 ///
 /// Examples:
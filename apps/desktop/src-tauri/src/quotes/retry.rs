@@ -0,0 +1,118 @@
+//! Retry für vorübergehende HTTP-Fehler der Kursquellen
+//!
+//! Einzelne 429- oder 5xx-Antworten (oder abgebrochene Verbindungen) sollen
+//! eine Synchronisation nicht sofort scheitern lassen. Anfragen werden bis zu
+//! `MAX_RETRIES` mal mit exponentiellem Backoff wiederholt (siehe
+//! `ai::calculate_backoff_delay`); ein `Retry-After` Header hat Vorrang.
+
+use crate::ai::{calculate_backoff_delay, parse_retry_after_headers};
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+/// Maximale Anzahl Wiederholungen nach dem ersten Versuch
+pub const MAX_RETRIES: u32 = 3;
+
+/// Obergrenze für `Retry-After`, damit eine Synchronisation nicht minutenlang hängt
+const MAX_RETRY_AFTER_SECS: u64 = 30;
+
+/// Anfrage senden und bei vorübergehenden Fehlern wiederholen.
+///
+/// Nach dem letzten Versuch wird die Antwort (z.B. 429) bzw. der Fehler
+/// unverändert zurückgegeben, damit der Provider seine Fehlermeldung bildet.
+pub async fn send_with_retry(request: RequestBuilder) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+
+    loop {
+        // Requests mit Streaming-Body lassen sich nicht klonen - dann nur ein Versuch
+        let Some(current) = request.try_clone() else {
+            return request.send().await;
+        };
+
+        let delay = match current.send().await {
+            Ok(response) if attempt < MAX_RETRIES && is_retryable_status(response.status()) => {
+                log::warn!(
+                    "Quote request to {} failed with {} (attempt {}/{})",
+                    response.url().host_str().unwrap_or("?"),
+                    response.status(),
+                    attempt + 1,
+                    MAX_RETRIES + 1
+                );
+                retry_delay(response.headers(), attempt)
+            }
+            Err(e) if attempt < MAX_RETRIES && is_transient_error(&e) => {
+                log::warn!("Quote request failed: {} (attempt {}/{})", e, attempt + 1, MAX_RETRIES + 1);
+                calculate_backoff_delay(attempt)
+            }
+            result => return result,
+        };
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// 429 (Rate Limit) und Serverfehler sind vorübergehend
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Verbindungsfehler, Timeouts und zurückgesetzte Verbindungen
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    if error.is_connect() || error.is_timeout() {
+        return true;
+    }
+
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io_err.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Wartezeit vor dem nächsten Versuch: `Retry-After` falls vorhanden, sonst Backoff
+fn retry_delay(headers: &HeaderMap, attempt: u32) -> Duration {
+    match parse_retry_after_headers(headers) {
+        Some(secs) => Duration::from_secs(u64::from(secs).min(MAX_RETRY_AFTER_SECS)),
+        None => calculate_backoff_delay(attempt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_retry_delay_honors_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_delay(&headers, 0), calculate_backoff_delay(0));
+        assert_eq!(retry_delay(&headers, 2), calculate_backoff_delay(2));
+
+        headers.insert("retry-after", HeaderValue::from_static("5"));
+        assert_eq!(retry_delay(&headers, 0), Duration::from_secs(5));
+
+        // Very long waits are capped
+        headers.insert("retry-after", HeaderValue::from_static("3600"));
+        assert_eq!(retry_delay(&headers, 0), Duration::from_secs(MAX_RETRY_AFTER_SECS));
+    }
+}
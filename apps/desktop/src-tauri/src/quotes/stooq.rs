@@ -0,0 +1,219 @@
+//! Stooq Quote Provider
+//!
+//! Fetches daily end-of-day prices from stooq.com as CSV.
+//! - No API key required
+//! - Good coverage of European exchanges (XETRA, London, Warsaw, Budapest)
+//!
+//! Endpoint: `https://stooq.com/q/d/l/?s=SYMBOL&i=d[&d1=YYYYMMDD&d2=YYYYMMDD]`
+//! Response: `Date,Open,High,Low,Close,Volume` (volume missing for indices/funds)
+//!
+//! Stooq symbol conventions (lowercase, market suffix):
+//! - `sap.de` (XETRA), `vod.uk` (London), `aapl.us` (US), `7203.jp` (Tokyo)
+//! - Warsaw stocks use `.pl` (`pko.pl`)
+
+use super::{retry::send_with_retry, LatestQuote, Quote};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate, Utc};
+use reqwest::Client;
+
+const BASE_URL: &str = "https://stooq.com/q/d/l/";
+
+/// Days to look back when fetching the latest quote (covers weekends and holidays)
+const LATEST_LOOKBACK_DAYS: i64 = 14;
+
+/// Yahoo exchange suffix -> Stooq market suffix
+const YAHOO_TO_STOOQ_SUFFIX: &[(&str, &str)] = &[
+    (".DE", ".de"),
+    (".F", ".de"),
+    (".L", ".uk"),
+    (".T", ".jp"),
+    (".HK", ".hk"),
+    (".BD", ".hu"),
+    (".WA", ".pl"),
+];
+
+/// Create HTTP client
+fn create_client() -> Result<Client> {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))
+}
+
+/// Convert a ticker to Stooq format.
+///
+/// Yahoo-style suffixes (`SAP.DE`, `VOD.L`) are mapped to Stooq markets,
+/// an explicit `exchange_suffix` (e.g. ".DE" from feed_url) is applied if the
+/// ticker has none, and plain US-style tickers get `.us`.
+pub fn convert_symbol(symbol: &str, exchange_suffix: Option<&str>) -> String {
+    let symbol = symbol.trim();
+
+    let (base, suffix) = match symbol.rfind('.') {
+        Some(pos) => (&symbol[..pos], Some(&symbol[pos..])),
+        None => (symbol, exchange_suffix.filter(|s| s.starts_with('.'))),
+    };
+
+    let stooq_suffix = match suffix {
+        Some(suffix) => YAHOO_TO_STOOQ_SUFFIX
+            .iter()
+            .find(|(yahoo, _)| yahoo.eq_ignore_ascii_case(suffix))
+            .map(|(_, stooq)| stooq.to_string())
+            // Already a Stooq suffix (e.g. "sap.de") or unknown market - keep it
+            .unwrap_or_else(|| suffix.to_lowercase()),
+        None => ".us".to_string(),
+    };
+
+    format!("{}{}", base.to_lowercase(), stooq_suffix)
+}
+
+/// Fetch current (last available EOD) quote
+///
+/// # Arguments
+/// * `symbol` - Ticker (Yahoo or Stooq format, e.g. "SAP.DE", "sap.de")
+/// * `exchange_suffix` - Optional exchange suffix from feed_url (e.g. ".DE")
+pub async fn fetch_quote(symbol: &str, exchange_suffix: Option<&str>) -> Result<LatestQuote> {
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(LATEST_LOOKBACK_DAYS);
+    let stooq_symbol = convert_symbol(symbol, exchange_suffix);
+
+    let quotes = fetch_csv(&stooq_symbol, Some((from, to))).await?;
+    let quote = quotes
+        .into_iter()
+        .last()
+        .ok_or_else(|| anyhow!("No recent data for {} on Stooq", stooq_symbol))?;
+
+    Ok(LatestQuote {
+        symbol: stooq_symbol.to_uppercase(),
+        name: None,
+        currency: None,
+        quote,
+    })
+}
+
+/// Fetch historical daily quotes
+pub async fn fetch_historical(
+    symbol: &str,
+    exchange_suffix: Option<&str>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Quote>> {
+    let stooq_symbol = convert_symbol(symbol, exchange_suffix);
+    fetch_csv(&stooq_symbol, Some((from, to))).await
+}
+
+async fn fetch_csv(stooq_symbol: &str, range: Option<(NaiveDate, NaiveDate)>) -> Result<Vec<Quote>> {
+    let client = create_client()?;
+
+    let mut url = format!("{}?s={}&i=d", BASE_URL, urlencoding::encode(stooq_symbol));
+    if let Some((from, to)) = range {
+        url.push_str(&format!("&d1={}&d2={}", from.format("%Y%m%d"), to.format("%Y%m%d")));
+    }
+    log::debug!("Fetching Stooq CSV for {} from {}", stooq_symbol, url);
+
+    let response = send_with_retry(client.get(&url))
+        .await
+        .map_err(|e| anyhow!("Request failed for {}: {}", stooq_symbol, e))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Stooq API error: {}", response.status()));
+    }
+
+    let body = response.text().await?;
+    parse_csv(&body).map_err(|e| anyhow!("{} ({})", e, stooq_symbol))
+}
+
+/// Parse Stooq daily CSV into quotes (sorted by date ascending)
+fn parse_csv(body: &str) -> Result<Vec<Quote>> {
+    let mut lines = body.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    let header = lines.next().ok_or_else(|| anyhow!("Empty response from Stooq"))?;
+    // Unknown symbols return "No data" (or an HTML page) instead of a CSV header
+    if !header.to_lowercase().starts_with("date,") {
+        return Err(anyhow!("No data from Stooq"));
+    }
+
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_lowercase()).collect();
+    let index_of = |name: &str| columns.iter().position(|c| c == name);
+    let close_idx = index_of("close").ok_or_else(|| anyhow!("Stooq CSV without Close column"))?;
+    let (open_idx, high_idx, low_idx, volume_idx) =
+        (index_of("open"), index_of("high"), index_of("low"), index_of("volume"));
+
+    let mut quotes = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let field = |idx: Option<usize>| idx.and_then(|i| fields.get(i)).copied();
+        let number = |idx: Option<usize>| field(idx).and_then(|v| v.parse::<f64>().ok());
+
+        let Some(date) = field(Some(0)).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) else {
+            continue;
+        };
+        let Some(close) = number(Some(close_idx)).filter(|c| *c > 0.0) else {
+            continue;
+        };
+
+        quotes.push(Quote {
+            date,
+            close,
+            high: number(high_idx),
+            low: number(low_idx),
+            open: number(open_idx),
+            volume: number(volume_idx).map(|v| v as i64),
+        });
+    }
+
+    if quotes.is_empty() {
+        return Err(anyhow!("No data from Stooq"));
+    }
+
+    quotes.sort_by_key(|q| q.date);
+    Ok(quotes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quotes::price_to_db;
+
+    #[test]
+    fn test_convert_symbol() {
+        assert_eq!(convert_symbol("SAP.DE", None), "sap.de");
+        assert_eq!(convert_symbol("SAP", Some(".DE")), "sap.de");
+        assert_eq!(convert_symbol("VOD.L", None), "vod.uk");
+        assert_eq!(convert_symbol("AAPL", None), "aapl.us");
+        assert_eq!(convert_symbol("PKO.WA", None), "pko.pl");
+        assert_eq!(convert_symbol("PKO", Some(".pl")), "pko.pl");
+        assert_eq!(convert_symbol("sap.de", None), "sap.de");
+        assert_eq!(convert_symbol("7203.T", None), "7203.jp");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "Date,Open,High,Low,Close,Volume\n\
+                   2024-01-03,156.5,157.2,155.1,156.78,1234567\n\
+                   2024-01-02,155.0,156.9,154.3,155.92,987654\n";
+        let quotes = parse_csv(csv).unwrap();
+
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(quotes[1].close, 156.78);
+        assert_eq!(quotes[1].volume, Some(1234567));
+        // Scaled for pp_price (value × 10^8)
+        assert_eq!(price_to_db(quotes[1].close), 15_678_000_000);
+    }
+
+    #[test]
+    fn test_parse_csv_without_volume() {
+        let csv = "Date,Open,High,Low,Close\r\n2024-01-02,10,11,9,10.5\r\n";
+        let quotes = parse_csv(csv).unwrap();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].volume, None);
+        assert_eq!(quotes[0].high, Some(11.0));
+    }
+
+    #[test]
+    fn test_parse_csv_no_data() {
+        assert!(parse_csv("No data").is_err());
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("Date,Open,High,Low,Close,Volume\n").is_err());
+    }
+}
@@ -59,6 +59,13 @@ const ISIN_YAHOO_MAPPINGS: &[(&str, &str, &str)] = &[
     ("US", "", "US exchanges (no suffix)"),
 ];
 
+/// Markets that Yahoo covers poorly but Stooq serves as free EOD data
+const ISIN_STOOQ_MAPPINGS: &[(&str, &str, &str)] = &[
+    // (ISIN prefix, Stooq suffix, description)
+    ("PL", ".pl", "Warschauer Börse (GPW)"),
+    ("HU", ".hu", "Budapester Börse"),
+];
+
 /// Known crypto symbols
 const CRYPTO_SYMBOLS: &[&str] = &[
     "BTC", "ETH", "SOL", "ADA", "DOT", "AVAX", "MATIC", "LINK", "UNI", "ATOM",
@@ -316,6 +323,25 @@ fn suggest_by_isin(security: &SecurityForSuggestion) -> Option<QuoteSuggestion>
 
     let country_code = &isin[0..2].to_uppercase();
 
+    // Markets with better coverage on Stooq (needs an existing ticker)
+    if let Some(ticker) = security.ticker.as_ref() {
+        for (prefix, suffix, description) in ISIN_STOOQ_MAPPINGS {
+            if country_code == *prefix {
+                return Some(QuoteSuggestion {
+                    security_id: security.id,
+                    security_name: security.name.clone(),
+                    isin: security.isin.clone(),
+                    ticker: security.ticker.clone(),
+                    suggested_feed: "STOOQ".to_string(),
+                    suggested_feed_url: if suffix.is_empty() { None } else { Some(suffix.to_string()) },
+                    suggested_ticker: None,
+                    confidence: 0.80,
+                    reason: format!("ISIN {} → {} (Stooq {}{})", country_code, description, ticker, suffix),
+                });
+            }
+        }
+    }
+
     // Find matching country
     for (prefix, suffix, description) in ISIN_YAHOO_MAPPINGS {
         if country_code == *prefix {
@@ -640,4 +666,22 @@ mod tests {
         assert_eq!(suggestion.suggested_feed, "YAHOO");
         assert_eq!(suggestion.suggested_feed_url, Some("GC=F".to_string()));
     }
+
+    #[test]
+    fn test_polish_isin_suggests_stooq() {
+        let security = SecurityForSuggestion {
+            id: 1,
+            name: "PKO Bank Polski".to_string(),
+            isin: Some("PLPKO0000016".to_string()),
+            ticker: Some("PKO".to_string()),
+            currency: Some("PLN".to_string()),
+        };
+        let suggestion = suggest_quote_provider(&security).unwrap();
+        assert_eq!(suggestion.suggested_feed, "STOOQ");
+        assert_eq!(suggestion.suggested_feed_url.as_deref(), Some(".pl"));
+        assert_eq!(
+            crate::quotes::stooq::convert_symbol("PKO", suggestion.suggested_feed_url.as_deref()),
+            "pko.pl"
+        );
+    }
 }
@@ -8,7 +8,7 @@
 //! - Historical data (Daily OHLCV)
 //! - Global markets (Stocks, ETFs, Crypto, Forex)
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::{NaiveDate, Utc};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT};
@@ -98,10 +98,7 @@ pub async fn fetch_quote(symbol: &str) -> Result<LatestQuote> {
         ],
     };
 
-    let response = client
-        .post(SCANNER_URL)
-        .json(&request_body)
-        .send()
+    let response = send_with_retry(client.post(SCANNER_URL).json(&request_body))
         .await
         .map_err(|e| anyhow!("TradingView request failed for {}: {}", symbol, e))?;
 
@@ -182,9 +179,7 @@ pub async fn fetch_historical(
         to.and_hms_opt(23, 59, 59).map(|dt| dt.and_utc().timestamp()).unwrap_or(0)
     );
 
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("TradingView history request failed: {}", e))?;
 
@@ -250,9 +245,7 @@ pub async fn search_symbols(query: &str, limit: usize) -> Result<Vec<SymbolSearc
         urlencoding::encode(query)
     );
 
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("TradingView search failed: {}", e))?;
 
@@ -282,6 +275,52 @@ pub struct SymbolSearchResult {
     pub security_type: Option<String>,
     pub currency_code: Option<String>,
     pub provider_id: Option<String>,
+    pub isin: Option<String>,
+}
+
+/// Look up the current ISIN of a symbol via the symbol search
+///
+/// Returns the ISIN together with a confidence: an exact exchange match is
+/// trusted more than a match by ticker only. Ambiguous results return `None`.
+pub async fn fetch_isin(symbol: &str) -> Result<Option<(String, f64)>> {
+    let tv_symbol = normalize_symbol(symbol);
+    let (exchange, ticker) = match tv_symbol.split_once(':') {
+        Some((exchange, ticker)) => (Some(exchange.to_string()), ticker.to_string()),
+        None => (None, tv_symbol.clone()),
+    };
+
+    let results = search_symbols(&ticker, 30).await?;
+    Ok(select_isin(&results, &ticker, exchange.as_deref()))
+}
+
+/// Pick the ISIN of the search result matching ticker (and exchange)
+fn select_isin(
+    results: &[SymbolSearchResult],
+    ticker: &str,
+    exchange: Option<&str>,
+) -> Option<(String, f64)> {
+    let clean = |s: &str| s.replace("<em>", "").replace("</em>", "").to_uppercase();
+    let matches: Vec<&SymbolSearchResult> = results
+        .iter()
+        .filter(|r| clean(&r.symbol) == ticker && r.isin.as_deref().is_some_and(|i| !i.is_empty()))
+        .collect();
+
+    if let Some(exchange) = exchange {
+        if let Some(hit) = matches
+            .iter()
+            .find(|r| r.exchange.as_deref().map(clean).as_deref() == Some(exchange))
+        {
+            return Some((clean(hit.isin.as_deref()?), 0.9));
+        }
+    }
+
+    // Ticker only: accept if all listings agree on the ISIN
+    let first = clean(matches.first()?.isin.as_deref()?);
+    if matches.iter().all(|r| r.isin.as_deref().map(clean).as_deref() == Some(first.as_str())) {
+        Some((first, 0.7))
+    } else {
+        None
+    }
 }
 
 /// Normalize symbol to TradingView format
@@ -340,6 +379,38 @@ pub fn get_chart_url(symbol: &str) -> String {
 mod tests {
     use super::*;
 
+    fn search_result(symbol: &str, exchange: &str, isin: &str) -> SymbolSearchResult {
+        SymbolSearchResult {
+            symbol: symbol.to_string(),
+            description: None,
+            exchange: Some(exchange.to_string()),
+            security_type: Some("stock".to_string()),
+            currency_code: None,
+            provider_id: None,
+            isin: Some(isin.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_select_isin() {
+        let results = vec![
+            search_result("<em>SAP</em>", "XETR", "DE0007164600"),
+            search_result("SAP", "NYSE", "US8030542042"),
+        ];
+
+        assert_eq!(
+            select_isin(&results, "SAP", Some("XETR")),
+            Some(("DE0007164600".to_string(), 0.9))
+        );
+        // Listings disagree without an exchange
+        assert_eq!(select_isin(&results, "SAP", None), None);
+        assert_eq!(
+            select_isin(&results[..1], "SAP", None),
+            Some(("DE0007164600".to_string(), 0.7))
+        );
+        assert_eq!(select_isin(&results, "SAPX", None), None);
+    }
+
     #[test]
     fn test_normalize_symbol() {
         assert_eq!(normalize_symbol("AAPL"), "AAPL");
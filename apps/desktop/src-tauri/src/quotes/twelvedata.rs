@@ -7,7 +7,7 @@
 //!
 //! Documentation: https://twelvedata.com/docs
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use reqwest::Client;
@@ -86,7 +86,7 @@ pub async fn fetch_quote(symbol: &str, api_key: &str) -> Result<LatestQuote> {
         BASE_URL, symbol, api_key
     );
 
-    let response = client.get(&url).send().await?;
+    let response = send_with_retry(client.get(&url)).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("Twelve Data API error: {}", response.status()));
@@ -155,7 +155,7 @@ pub async fn fetch_historical(
         api_key
     );
 
-    let response = client.get(&url).send().await?;
+    let response = send_with_retry(client.get(&url)).await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("Twelve Data API error: {}", response.status()));
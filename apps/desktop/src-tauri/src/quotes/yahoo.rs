@@ -3,15 +3,19 @@
 //! Unterstützt:
 //! - Aktuelle Kurse (Latest)
 //! - Historische Kurse (Daily)
+//! - Intraday-Kurse (5-Minuten-Intervall, aktueller Handelstag)
 //! - Adjusted Close (Dividenden-bereinigt)
 
-use super::{LatestQuote, Quote};
+use super::{retry::send_with_retry, IntradayQuote, LatestQuote, Quote};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 
 const BASE_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
 
+/// Intervall für Intraday-Kurse
+const INTRADAY_INTERVAL: &str = "5m";
+
 /// HTTP Client mit korrekten Headers erstellen
 fn create_client() -> Result<reqwest::Client> {
     let mut headers = HeaderMap::new();
@@ -32,9 +36,7 @@ pub async fn fetch_quote(symbol: &str, _adjusted: bool) -> Result<LatestQuote> {
     log::debug!("Fetching Yahoo quote for {} from {}", symbol, url);
 
     let client = create_client()?;
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", symbol, e))?;
 
@@ -61,6 +63,31 @@ pub async fn fetch_quote(symbol: &str, _adjusted: bool) -> Result<LatestQuote> {
     parse_latest_quote(symbol, &data)
 }
 
+/// Intraday-Kurse des aktuellen Handelstags abrufen
+pub async fn fetch_intraday(symbol: &str) -> Result<Vec<IntradayQuote>> {
+    let url = format!("{}?interval={}&range=1d", symbol_url(symbol), INTRADAY_INTERVAL);
+    log::debug!("Fetching Yahoo intraday for {} from {}", symbol, url);
+
+    let client = create_client()?;
+    let response = send_with_retry(client.get(&url))
+        .await
+        .map_err(|e| anyhow!("Request failed for {}: {}", symbol, e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        log::error!("Yahoo API error for {}: {} - {}", symbol, status, body);
+        return Err(anyhow!("HTTP error for {}: {} - {}", symbol, status, body));
+    }
+
+    let data: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Failed to parse JSON for {}: {}", symbol, e))?;
+
+    parse_intraday_quotes(&data)
+}
+
 /// Historische Kurse abrufen
 pub async fn fetch_historical(
     symbol: &str,
@@ -102,9 +129,7 @@ pub async fn fetch_historical_with_splits(
     log::debug!("Fetching Yahoo historical with splits for {} from {}", symbol, url);
 
     let client = create_client()?;
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("Request failed for {}: {}", symbol, e))?;
 
@@ -213,6 +238,45 @@ fn parse_split_events(data: &serde_json::Value) -> Vec<super::SplitEvent> {
     splits
 }
 
+/// Intraday-Kurse aus Yahoo Response parsen (Lücken ohne Kurs werden übersprungen)
+fn parse_intraday_quotes(data: &serde_json::Value) -> Result<Vec<IntradayQuote>> {
+    let chart = data
+        .get("chart")
+        .and_then(|c| c.get("result"))
+        .and_then(|r| r.get(0))
+        .ok_or_else(|| anyhow!("Invalid response format"))?;
+
+    let timestamps = chart
+        .get("timestamp")
+        .and_then(|t| t.as_array())
+        .ok_or_else(|| anyhow!("Missing timestamps"))?;
+
+    let quote_data = chart
+        .get("indicators")
+        .and_then(|i| i.get("quote"))
+        .and_then(|q| q.get(0))
+        .ok_or_else(|| anyhow!("Missing quote data"))?;
+
+    let closes = quote_data
+        .get("close")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| anyhow!("Missing close prices"))?;
+    let volumes = quote_data.get("volume").and_then(|v| v.as_array());
+
+    let quotes = timestamps
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ts)| {
+            let timestamp = chrono::DateTime::from_timestamp(ts.as_i64()?, 0)?;
+            let price = closes.get(i).and_then(|v| v.as_f64())?;
+            let volume = volumes.and_then(|v| v.get(i)).and_then(|v| v.as_i64());
+            Some(IntradayQuote { timestamp, price, volume })
+        })
+        .collect();
+
+    Ok(quotes)
+}
+
 /// Symbol URL erstellen (encoded)
 fn symbol_url(symbol: &str) -> String {
     let encoded = urlencoding::encode(symbol);
@@ -430,9 +494,7 @@ pub async fn search(query: &str) -> Result<Vec<YahooSearchResult>> {
 
     log::debug!("Yahoo search for: {}", query);
 
-    let response = client
-        .get(&url)
-        .send()
+    let response = send_with_retry(client.get(&url))
         .await
         .map_err(|e| anyhow!("Yahoo search request failed: {}", e))?;
 
@@ -465,6 +527,29 @@ mod tests {
     use super::*;
     use chrono::Datelike;
 
+    #[test]
+    fn test_parse_intraday_quotes() {
+        let data = serde_json::json!({
+            "chart": {
+                "result": [{
+                    "timestamp": [1704700800, 1704701100, 1704701400],
+                    "indicators": {
+                        "quote": [{
+                            "close": [185.5, null, 186.25],
+                            "volume": [1000, 0, 1500]
+                        }]
+                    }
+                }]
+            }
+        });
+
+        let quotes = parse_intraday_quotes(&data).unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].timestamp.timestamp(), 1704700800);
+        assert_eq!(quotes[1].price, 186.25);
+        assert_eq!(quotes[1].volume, Some(1500));
+    }
+
     #[tokio::test]
     async fn test_search() {
         let results = search("apple").await;
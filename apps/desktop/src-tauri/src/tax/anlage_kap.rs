@@ -0,0 +1,230 @@
+//! Export of the German tax report as "Anlage KAP" line items
+//!
+//! Maps the figures of `GermanTaxReport` to the lines of the tax form so they
+//! can be copied into ELSTER. The export is returned as JSON and printed via
+//! `commands::pdf_export::export_anlage_kap_pdf`.
+
+use super::{build_german_tax_report, GermanTaxReport};
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Zeile 7: Kapitalerträge
+pub const ZEILE_KAPITALERTRAEGE: u32 = 7;
+/// Zeile 8: In Zeile 7 enthaltene Gewinne aus Aktienveräußerungen
+pub const ZEILE_AKTIENGEWINNE: u32 = 8;
+/// Zeile 12: Nicht ausgeglichene Verluste ohne Verluste aus Aktienveräußerungen
+pub const ZEILE_VERLUSTE_SONSTIGE: u32 = 12;
+/// Zeile 13: Nicht ausgeglichene Verluste aus Aktienveräußerungen
+pub const ZEILE_VERLUSTE_AKTIEN: u32 = 13;
+/// Zeile 16: In Anspruch genommener Sparer-Pauschbetrag
+pub const ZEILE_SPARER_PAUSCHBETRAG: u32 = 16;
+/// Zeile 19: Ausländische Kapitalerträge
+pub const ZEILE_AUSLAENDISCHE_ERTRAEGE: u32 = 19;
+/// Zeile 37: Anrechenbare ausländische Quellensteuer
+pub const ZEILE_ANRECHENBARE_QUELLENSTEUER: u32 = 37;
+
+/// Single line of the Anlage KAP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnlageKapLine {
+    pub zeile: u32,
+    pub label: String,
+    /// Amount in EUR, rounded to cents
+    pub amount: f64,
+}
+
+/// Anlage KAP export for one year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnlageKapExport {
+    pub year: i32,
+    pub currency: String,
+    pub lines: Vec<AnlageKapLine>,
+    /// Already paid taxes for information (not part of the form lines above)
+    pub tax_already_paid: f64,
+    pub remaining_tax_liability: f64,
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Map a computed tax report to the Anlage KAP lines
+pub fn build_anlage_kap_export(report: &GermanTaxReport) -> AnlageKapExport {
+    let pots = &report.loss_pots;
+
+    let lines = [
+        (
+            ZEILE_KAPITALERTRAEGE,
+            "Kapitalerträge",
+            (report.total_taxable_income - pots.aktien_used - pots.sonstige_used).max(0.0),
+        ),
+        (
+            ZEILE_AKTIENGEWINNE,
+            "Darin enthaltene Gewinne aus Aktienveräußerungen",
+            pots.aktien_gains - pots.aktien_used,
+        ),
+        (
+            ZEILE_VERLUSTE_SONSTIGE,
+            "Nicht ausgeglichene Verluste ohne Aktienveräußerungen",
+            pots.sonstige_carryforward,
+        ),
+        (
+            ZEILE_VERLUSTE_AKTIEN,
+            "Nicht ausgeglichene Verluste aus Aktienveräußerungen",
+            pots.aktien_carryforward,
+        ),
+        (
+            ZEILE_SPARER_PAUSCHBETRAG,
+            "In Anspruch genommener Sparer-Pauschbetrag",
+            report.freistellung_used,
+        ),
+        (
+            ZEILE_AUSLAENDISCHE_ERTRAEGE,
+            "Ausländische Kapitalerträge",
+            report.anlage_kap.zeile_8_ausland_dividenden,
+        ),
+        (
+            ZEILE_ANRECHENBARE_QUELLENSTEUER,
+            "Anrechenbare ausländische Quellensteuer",
+            report.creditable_foreign_tax,
+        ),
+    ]
+    .into_iter()
+    .map(|(zeile, label, amount)| AnlageKapLine {
+        zeile,
+        label: label.to_string(),
+        amount: round_cents(amount),
+    })
+    .collect();
+
+    AnlageKapExport {
+        year: report.year,
+        currency: report.currency.clone(),
+        lines,
+        tax_already_paid: round_cents(report.tax_already_paid),
+        remaining_tax_liability: round_cents(report.remaining_tax_liability),
+    }
+}
+
+/// Load the Anlage KAP export for a year on an existing connection
+pub(crate) fn load_anlage_kap_export(
+    conn: &rusqlite::Connection,
+    year: i32,
+) -> Result<AnlageKapExport, String> {
+    let report = build_german_tax_report(conn, year)?;
+    Ok(build_anlage_kap_export(&report))
+}
+
+/// Export the German tax report as Anlage KAP line items (JSON)
+#[command]
+pub fn export_anlage_kap(year: i32) -> Result<AnlageKapExport, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    load_anlage_kap_export(conn, year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tax::{AnlageKapData, LossPotStatus, TaxSettings};
+
+    fn report() -> GermanTaxReport {
+        GermanTaxReport {
+            year: 2024,
+            currency: "EUR".to_string(),
+            settings: TaxSettings {
+                year: 2024,
+                is_married: false,
+                kirchensteuer_rate: None,
+                bundesland: None,
+                freistellung_limit: 1000.0,
+                freistellung_used: 0.0,
+            },
+            dividend_income_gross: 800.0,
+            interest_income_gross: 100.0,
+            realized_gains: 500.0,
+            realized_losses: 200.0,
+            teilfreistellung_exempt: 0.0,
+            total_taxable_income: 1400.0,
+            freistellung_available: 1000.0,
+            freistellung_used: 1000.0,
+            loss_carryforward: 0.0,
+            loss_pots: LossPotStatus {
+                aktien_gains: 500.0,
+                aktien_losses: 200.0,
+                aktien_used: 200.0,
+                ..Default::default()
+            },
+            taxable_after_deductions: 200.0,
+            foreign_withholding_tax: 90.0,
+            creditable_foreign_tax: 90.0,
+            abgeltungssteuer: 50.0,
+            solidaritaetszuschlag: 2.75,
+            kirchensteuer: 0.0,
+            total_german_tax: 0.0,
+            tax_already_paid: 0.0,
+            remaining_tax_liability: 0.0,
+            dividend_details: Vec::new(),
+            gains_details: Vec::new(),
+            losses_details: Vec::new(),
+            anlage_kap: AnlageKapData {
+                zeile_7_inland_dividenden: 200.0,
+                zeile_8_ausland_dividenden: 600.004,
+                zeile_14_zinsen: 100.0,
+                zeile_15_veraeusserungsgewinne: 500.0,
+                zeile_16_veraeusserungsverluste: 200.0,
+                zeile_47_auslaendische_steuern: 90.0,
+                zeile_48_kapest: 0.0,
+                zeile_49_soli: 2.75,
+                zeile_50_kist: 0.0,
+            },
+        }
+    }
+
+    fn amount(export: &AnlageKapExport, zeile: u32) -> f64 {
+        export.lines.iter().find(|l| l.zeile == zeile).unwrap().amount
+    }
+
+    #[test]
+    fn test_build_anlage_kap_export() {
+        let export = build_anlage_kap_export(&report());
+
+        // Dividends + interest + gains after loss offset
+        assert_eq!(amount(&export, ZEILE_KAPITALERTRAEGE), 1200.0);
+        assert_eq!(amount(&export, ZEILE_AKTIENGEWINNE), 300.0);
+        assert_eq!(amount(&export, ZEILE_VERLUSTE_SONSTIGE), 0.0);
+        assert_eq!(amount(&export, ZEILE_VERLUSTE_AKTIEN), 0.0);
+        assert_eq!(amount(&export, ZEILE_SPARER_PAUSCHBETRAG), 1000.0);
+        assert_eq!(amount(&export, ZEILE_AUSLAENDISCHE_ERTRAEGE), 600.0);
+        assert_eq!(amount(&export, ZEILE_ANRECHENBARE_QUELLENSTEUER), 90.0);
+
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["lines"][0]["zeile"], 7);
+        assert_eq!(json["lines"][0]["label"], "Kapitalerträge");
+    }
+
+    #[test]
+    fn test_anlage_kap_unbalanced_losses() {
+        let mut report = report();
+        report.total_taxable_income = 1000.0;
+        report.loss_pots = LossPotStatus {
+            aktien_gains: 100.0,
+            aktien_losses: 350.0,
+            aktien_used: 100.0,
+            aktien_carryforward: 250.0,
+            sonstige_carryforward: 40.0,
+            ..Default::default()
+        };
+        let export = build_anlage_kap_export(&report);
+
+        assert_eq!(amount(&export, ZEILE_AKTIENGEWINNE), 0.0);
+        assert_eq!(amount(&export, ZEILE_VERLUSTE_AKTIEN), 250.0);
+        assert_eq!(amount(&export, ZEILE_VERLUSTE_SONSTIGE), 40.0);
+        assert_eq!(amount(&export, ZEILE_KAPITALERTRAEGE), 900.0);
+    }
+}
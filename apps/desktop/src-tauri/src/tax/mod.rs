@@ -8,8 +8,19 @@
 //! Freistellungsauftrag (tax-free allowance):
 //! - Since 2023: 1000€ (single) / 2000€ (married)
 //! - Before 2023: 801€ (single) / 1602€ (married)
+//!
+//! Verlustverrechnungstöpfe (loss pots):
+//! - Aktientopf: losses from selling shares, only offset against share gains
+//! - Allgemeiner Topf (Sonstige): all other losses, offset against any income
+//! - Unused losses are carried forward to the next year
+
+pub mod anlage_kap;
+pub mod vorabpauschale;
+pub mod withholding;
 
 use crate::db;
+use chrono::Datelike;
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
@@ -61,12 +72,16 @@ pub struct GermanTaxReport {
     pub interest_income_gross: f64,
     pub realized_gains: f64,
     pub realized_losses: f64,
+    /// Net gain exempt via Teilfreistellung (already deducted from gains/losses)
+    pub teilfreistellung_exempt: f64,
     pub total_taxable_income: f64,
 
     // Deductions
     pub freistellung_available: f64,
     pub freistellung_used: f64,
+    /// Unused losses of both pots carried forward to the next year
     pub loss_carryforward: f64,
+    pub loss_pots: LossPotStatus,
 
     // After deductions
     pub taxable_after_deductions: f64,
@@ -105,6 +120,33 @@ pub struct TaxableItem {
     pub withholding_tax: f64,
     pub net_amount: f64,
     pub item_type: String, // DIVIDEND, INTEREST, GAIN, LOSS
+    /// Teilfreistellung rate (0.3 for equity funds, 0 for stocks)
+    pub teilfreistellung: f64,
+    /// Amount relevant for tax after Teilfreistellung
+    pub taxable_amount: f64,
+    /// Sale of shares (Aktientopf); funds and other securities use the general pot
+    pub is_stock: bool,
+}
+
+/// Balances of the two loss pots (Verlustverrechnungstöpfe) for a year
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LossPotStatus {
+    /// Stock losses carried over from previous years
+    pub aktien_carry_in: f64,
+    /// Other losses carried over from previous years
+    pub sonstige_carry_in: f64,
+    /// Gains from selling shares in this year
+    pub aktien_gains: f64,
+    /// Losses realized in this year
+    pub aktien_losses: f64,
+    pub sonstige_losses: f64,
+    /// Losses offset against income in this year
+    pub aktien_used: f64,
+    pub sonstige_used: f64,
+    /// Balances carried forward to the next year
+    pub aktien_carryforward: f64,
+    pub sonstige_carryforward: f64,
 }
 
 /// Data for German tax form "Anlage KAP"
@@ -143,12 +185,54 @@ pub struct FreistellungStatus {
     pub usage_percent: f64,
 }
 
+/// Lot consumed by a simulated sale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaleTaxLot {
+    pub purchase_date: String,
+    pub shares: f64,
+    pub cost_basis: f64,
+    pub gain: f64,
+}
+
+/// Estimated tax and net proceeds of selling shares at the latest price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaleTaxPreview {
+    pub security_id: i64,
+    pub portfolio_id: i64,
+    pub shares: f64,
+    /// Latest price in base currency
+    pub price: f64,
+    pub price_date: String,
+    pub currency: String,
+    pub proceeds: f64,
+    /// FIFO cost basis of the sold shares
+    pub cost_basis: f64,
+    pub realized_gain: f64,
+    /// Teilfreistellung rate (0.3 for equity funds, 0 for stocks)
+    pub teilfreistellung: f64,
+    /// Gain after Teilfreistellung
+    pub taxable_gain: f64,
+    pub freistellung_available: f64,
+    pub freistellung_used: f64,
+    pub taxable_after_freistellung: f64,
+    pub abgeltungssteuer: f64,
+    pub solidaritaetszuschlag: f64,
+    pub kirchensteuer: f64,
+    pub total_tax: f64,
+    /// Proceeds minus estimated tax (fees not included)
+    pub net_proceeds: f64,
+    /// Consumed lots, oldest first
+    pub lots: Vec<SaleTaxLot>,
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
 /// Calculate Abgeltungssteuer with optional Kirchensteuer
-fn calculate_abgeltungssteuer(
+pub(crate) fn calculate_abgeltungssteuer(
     taxable_amount: f64,
     kirchensteuer_rate: Option<f64>,
 ) -> (f64, f64, f64) {
@@ -181,244 +265,469 @@ fn calculate_creditable_wht(foreign_tax: f64, gross_income: f64) -> f64 {
     foreign_tax.min(max_credit)
 }
 
-// ============================================================================
-// Commands
-// ============================================================================
-
-/// Get or create tax settings for a year
-#[command]
-pub fn get_tax_settings(year: i32) -> Result<TaxSettings, String> {
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard
-        .as_ref()
-        .ok_or_else(|| "Database not initialized".to_string())?;
-
-    // Try to get from database
-    let result: Option<(bool, Option<f64>, Option<String>, f64)> = conn
-        .query_row(
-            "SELECT is_married, kirchensteuer_rate, bundesland, freistellung_used FROM pp_tax_settings WHERE year = ?",
-            [year],
-            |row| {
-                Ok((
-                    row.get::<_, i32>(0)? == 1,
-                    row.get::<_, Option<f64>>(1)?,
-                    row.get::<_, Option<String>>(2)?,
-                    row.get::<_, f64>(3)?,
-                ))
-            },
-        )
-        .ok();
-
-    match result {
-        Some((is_married, kirchensteuer_rate, bundesland, freistellung_used)) => {
-            Ok(TaxSettings {
-                year,
-                is_married,
-                kirchensteuer_rate,
-                bundesland,
-                freistellung_limit: get_freistellung_limit(year, is_married),
-                freistellung_used,
-            })
-        }
-        None => {
-            // Return default settings
-            Ok(TaxSettings {
-                year,
-                is_married: false,
-                kirchensteuer_rate: None,
-                bundesland: None,
-                freistellung_limit: get_freistellung_limit(year, false),
-                freistellung_used: 0.0,
-            })
-        }
-    }
+/// Teilfreistellung rate of a security (0% for individual stocks)
+pub(crate) fn teilfreistellung_for(fund_type: Option<&str>) -> f64 {
+    fund_type
+        .and_then(vorabpauschale::teilfreistellung_rate)
+        .unwrap_or(0.0)
 }
 
-/// Save tax settings for a year
-#[command]
-pub fn save_tax_settings(settings: TaxSettings) -> Result<(), String> {
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard
-        .as_ref()
-        .ok_or_else(|| "Database not initialized".to_string())?;
-
-    conn.execute(
-        r#"
-        INSERT OR REPLACE INTO pp_tax_settings (year, is_married, kirchensteuer_rate, bundesland, freistellung_used)
-        VALUES (?1, ?2, ?3, ?4, ?5)
-        "#,
-        rusqlite::params![
-            settings.year,
-            if settings.is_married { 1 } else { 0 },
-            settings.kirchensteuer_rate,
-            settings.bundesland,
-            settings.freistellung_used,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+/// Whether sales belong to the Aktientopf (§ 20 Abs. 6 EStG).
+///
+/// Only securities explicitly marked as shares; funds, bonds, certificates
+/// and ETCs go to the general pot.
+pub(crate) fn is_stock_for(is_stock: bool, fund_type: Option<&str>) -> bool {
+    is_stock && fund_type.map_or(true, |t| t.trim().is_empty())
 }
 
-/// Generate detailed German tax report
-#[command]
-pub fn generate_german_tax_report(year: i32) -> Result<GermanTaxReport, String> {
-    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
-    let conn = conn_guard
-        .as_ref()
-        .ok_or_else(|| "Database not initialized".to_string())?;
-
-    let settings = get_tax_settings(year)?;
-
-    let base_currency: String = conn
-        .query_row(
-            "SELECT base_currency FROM pp_import ORDER BY id DESC LIMIT 1",
-            [],
-            |r| r.get(0),
-        )
-        .unwrap_or_else(|_| "EUR".to_string());
-
-    let start_date = format!("{}-01-01", year);
-    let end_date = format!("{}-12-31", year);
-
-    // Get dividends
-    let mut dividend_details: Vec<TaxableItem> = Vec::new();
-    let mut total_dividend_gross = 0.0;
-    let mut total_dividend_wht = 0.0;
+/// Realized gains and losses of sales in a period (FIFO cost basis).
+///
+/// The Teilfreistellung of funds is applied to gains and losses alike.
+fn load_realized_gains(
+    conn: &rusqlite::Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(Vec<TaxableItem>, Vec<TaxableItem>), String> {
+    let mut gains_details: Vec<TaxableItem> = Vec::new();
+    let mut losses_details: Vec<TaxableItem> = Vec::new();
 
-    let mut div_stmt = conn
+    let mut gains_stmt = conn
         .prepare(
             r#"
             SELECT
                 t.date,
                 COALESCE(s.name, 'Unbekannt') as name,
                 s.isin,
-                t.amount / 100.0 as gross,
-                COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0) / 100.0 as tax
+                t.amount / 100.0 as proceeds,
+                COALESCE(SUM(fc.gross_amount), 0) / 100.0 as cost_basis,
+                s.fund_type,
+                COALESCE(s.is_stock, 0)
             FROM pp_txn t
             LEFT JOIN pp_security s ON s.id = t.security_id
-            WHERE t.txn_type = 'DIVIDENDS'
+            LEFT JOIN pp_fifo_consumption fc ON fc.sale_txn_id = t.id
+            WHERE t.txn_type = 'SELL'
+              AND t.owner_type = 'portfolio'
               AND t.date >= ?1 AND t.date <= ?2
+            GROUP BY t.id
             ORDER BY t.date
             "#,
         )
         .map_err(|e| e.to_string())?;
 
-    let div_rows = div_stmt
-        .query_map([&start_date, &end_date], |row| {
+    let gains_rows = gains_stmt
+        .query_map([start_date, end_date], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, Option<String>>(2)?,
                 row.get::<_, f64>(3)?,
                 row.get::<_, f64>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, bool>(6)?,
             ))
         })
         .map_err(|e| e.to_string())?;
 
-    for row in div_rows.flatten() {
-        let (date, name, isin, gross, tax) = row;
-        total_dividend_gross += gross;
-        total_dividend_wht += tax;
-        dividend_details.push(TaxableItem {
+    for row in gains_rows.flatten() {
+        let (date, name, isin, proceeds, cost_basis, fund_type, is_stock) = row;
+        let gain = proceeds - cost_basis;
+        let teilfreistellung = teilfreistellung_for(fund_type.as_deref());
+
+        let item = TaxableItem {
             date,
             security_name: name,
             security_isin: isin,
-            gross_amount: gross,
-            withholding_tax: tax,
-            net_amount: gross - tax,
-            item_type: "DIVIDEND".to_string(),
-        });
+            gross_amount: proceeds,
+            withholding_tax: 0.0,
+            net_amount: gain,
+            item_type: if gain >= 0.0 { "GAIN" } else { "LOSS" }.to_string(),
+            teilfreistellung,
+            taxable_amount: gain * (1.0 - teilfreistellung),
+            is_stock: is_stock_for(is_stock, fund_type.as_deref()),
+        };
+
+        if gain >= 0.0 {
+            gains_details.push(item);
+        } else {
+            losses_details.push(item);
+        }
     }
 
-    // Get interest
-    let total_interest: f64 = conn
-        .query_row(
-            r#"
-            SELECT COALESCE(SUM(amount), 0) / 100.0
-            FROM pp_txn
-            WHERE txn_type = 'INTEREST' AND date >= ?1 AND date <= ?2
-            "#,
-            [&start_date, &end_date],
-            |row| row.get(0),
-        )
-        .unwrap_or(0.0);
+    Ok((gains_details, losses_details))
+}
 
-    // Get realized gains from FIFO consumption
-    let mut gains_details: Vec<TaxableItem> = Vec::new();
-    let mut losses_details: Vec<TaxableItem> = Vec::new();
-    let mut total_gains = 0.0;
-    let mut total_losses = 0.0;
+/// Income of a year split by loss pot
+#[derive(Debug, Clone, Copy, Default)]
+struct LossPotInput {
+    aktien_gains: f64,
+    aktien_losses: f64,
+    /// Dividends, interest and gains from funds or other securities
+    sonstige_income: f64,
+    sonstige_losses: f64,
+}
 
-    let mut gains_stmt = conn
+impl LossPotInput {
+    fn from_items(other_income: f64, gains: &[TaxableItem], losses: &[TaxableItem]) -> Self {
+        let mut input = LossPotInput {
+            sonstige_income: other_income,
+            ..Default::default()
+        };
+        for item in gains {
+            if item.is_stock {
+                input.aktien_gains += item.taxable_amount;
+            } else {
+                input.sonstige_income += item.taxable_amount;
+            }
+        }
+        for item in losses {
+            if item.is_stock {
+                input.aktien_losses += item.taxable_amount.abs();
+            } else {
+                input.sonstige_losses += item.taxable_amount.abs();
+            }
+        }
+        input
+    }
+}
+
+/// Offset losses against income: stock losses only against stock gains,
+/// other losses against everything that remains.
+fn apply_loss_pots(aktien_carry_in: f64, sonstige_carry_in: f64, input: &LossPotInput) -> LossPotStatus {
+    let aktien_pot = aktien_carry_in + input.aktien_losses;
+    let aktien_used = aktien_pot.min(input.aktien_gains).max(0.0);
+
+    let remaining_income = input.aktien_gains - aktien_used + input.sonstige_income;
+    let sonstige_pot = sonstige_carry_in + input.sonstige_losses;
+    let sonstige_used = sonstige_pot.min(remaining_income).max(0.0);
+
+    LossPotStatus {
+        aktien_carry_in,
+        sonstige_carry_in,
+        aktien_gains: input.aktien_gains,
+        aktien_losses: input.aktien_losses,
+        sonstige_losses: input.sonstige_losses,
+        aktien_used,
+        sonstige_used,
+        aktien_carryforward: aktien_pot - aktien_used,
+        sonstige_carryforward: sonstige_pot - sonstige_used,
+    }
+}
+
+/// Dividend income of a period
+struct DividendIncome {
+    details: Vec<TaxableItem>,
+    gross: f64,
+    /// Gross dividends of non-German issuers
+    foreign: f64,
+    withholding_tax: f64,
+}
+
+/// Dividends of a period with withheld taxes
+fn load_dividends(
+    conn: &rusqlite::Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<DividendIncome, String> {
+    let mut income = DividendIncome {
+        details: Vec::new(),
+        gross: 0.0,
+        foreign: 0.0,
+        withholding_tax: 0.0,
+    };
+
+    let mut div_stmt = conn
         .prepare(
             r#"
             SELECT
                 t.date,
                 COALESCE(s.name, 'Unbekannt') as name,
                 s.isin,
-                t.amount / 100.0 as proceeds,
-                COALESCE(SUM(fc.gross_amount), 0) / 100.0 as cost_basis
+                t.amount / 100.0 as gross,
+                COALESCE((SELECT SUM(amount) FROM pp_txn_unit WHERE txn_id = t.id AND unit_type = 'TAX'), 0) / 100.0 as tax,
+                s.domicile
             FROM pp_txn t
             LEFT JOIN pp_security s ON s.id = t.security_id
-            LEFT JOIN pp_fifo_consumption fc ON fc.sale_txn_id = t.id
-            WHERE t.txn_type = 'SELL'
-              AND t.owner_type = 'portfolio'
+            WHERE t.txn_type = 'DIVIDENDS'
               AND t.date >= ?1 AND t.date <= ?2
-            GROUP BY t.id
             ORDER BY t.date
             "#,
         )
         .map_err(|e| e.to_string())?;
 
-    let gains_rows = gains_stmt
-        .query_map([&start_date, &end_date], |row| {
+    let div_rows = div_stmt
+        .query_map([start_date, end_date], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, Option<String>>(2)?,
                 row.get::<_, f64>(3)?,
                 row.get::<_, f64>(4)?,
+                row.get::<_, Option<String>>(5)?,
             ))
         })
         .map_err(|e| e.to_string())?;
 
-    for row in gains_rows.flatten() {
-        let (date, name, isin, proceeds, cost_basis) = row;
-        let gain = proceeds - cost_basis;
-
-        let item = TaxableItem {
-            date: date.clone(),
+    for row in div_rows.flatten() {
+        let (date, name, isin, gross, tax, domicile) = row;
+        income.gross += gross;
+        // Securities without known country are treated as foreign
+        if withholding::security_country(domicile.as_deref(), isin.as_deref()).as_deref() != Some("DE") {
+            income.foreign += gross;
+        }
+        income.withholding_tax += tax;
+        income.details.push(TaxableItem {
+            date,
             security_name: name,
             security_isin: isin,
-            gross_amount: proceeds,
-            withholding_tax: 0.0,
-            net_amount: gain,
-            item_type: if gain >= 0.0 { "GAIN" } else { "LOSS" }.to_string(),
-        };
+            gross_amount: gross,
+            withholding_tax: tax,
+            net_amount: gross - tax,
+            item_type: "DIVIDEND".to_string(),
+            teilfreistellung: 0.0,
+            taxable_amount: gross,
+            is_stock: false,
+        });
+    }
 
-        if gain >= 0.0 {
-            total_gains += gain;
-            gains_details.push(item);
-        } else {
-            total_losses += gain.abs();
-            losses_details.push(item);
+    Ok(income)
+}
+
+/// Interest of a period
+fn load_interest(conn: &rusqlite::Connection, start_date: &str, end_date: &str) -> f64 {
+    conn.query_row(
+        r#"
+        SELECT COALESCE(SUM(amount), 0) / 100.0
+        FROM pp_txn
+        WHERE txn_type = 'INTEREST' AND date >= ?1 AND date <= ?2
+        "#,
+        [start_date, end_date],
+        |row| row.get(0),
+    )
+    .unwrap_or(0.0)
+}
+
+/// Dividends (gross, as in the report) and interest of a period
+fn load_other_income(conn: &rusqlite::Connection, start_date: &str, end_date: &str) -> Result<f64, String> {
+    Ok(load_dividends(conn, start_date, end_date)?.gross + load_interest(conn, start_date, end_date))
+}
+
+/// Store the loss pot balances at the end of a year
+fn store_loss_pots(conn: &rusqlite::Connection, year: i32, pots: &LossPotStatus) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT INTO pp_tax_settings (year, loss_pot_aktien, loss_pot_sonstige, loss_pots_saved_at)
+        VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+        ON CONFLICT(year) DO UPDATE SET
+            loss_pot_aktien = excluded.loss_pot_aktien,
+            loss_pot_sonstige = excluded.loss_pot_sonstige,
+            loss_pots_saved_at = excluded.loss_pots_saved_at
+        "#,
+        rusqlite::params![year, pots.aktien_carryforward, pots.sonstige_carryforward],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Loss pot balances carried into `year`.
+///
+/// Starts from the latest year-end balance saved with `save_loss_pots`
+/// before `year` and rolls the pots forward in memory from there (without
+/// one, from the first year with a sale). Computing the carry-in stores
+/// nothing.
+fn load_loss_carry_in(conn: &rusqlite::Connection, year: i32) -> Result<(f64, f64), String> {
+    let saved: Option<(i32, f64, f64)> = conn
+        .query_row(
+            "SELECT year, loss_pot_aktien, loss_pot_sonstige FROM pp_tax_settings
+             WHERE year < ?1 AND loss_pots_saved_at IS NOT NULL
+             ORDER BY year DESC LIMIT 1",
+            [year],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let (start_year, mut aktien, mut sonstige) = match saved {
+        Some((saved_year, aktien, sonstige)) => (saved_year + 1, aktien, sonstige),
+        None => {
+            let first_year: Option<i32> = conn
+                .query_row(
+                    "SELECT CAST(MIN(substr(date, 1, 4)) AS INTEGER) FROM pp_txn WHERE txn_type = 'SELL' AND owner_type = 'portfolio'",
+                    [],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            (first_year.unwrap_or(year), 0.0, 0.0)
         }
+    };
+    for y in start_year..year {
+        let start_date = format!("{}-01-01", y);
+        let end_date = format!("{}-12-31", y);
+        let (gains, losses) = load_realized_gains(conn, &start_date, &end_date)?;
+        let input = LossPotInput::from_items(load_other_income(conn, &start_date, &end_date)?, &gains, &losses);
+
+        let pots = apply_loss_pots(aktien, sonstige, &input);
+        aktien = pots.aktien_carryforward;
+        sonstige = pots.sonstige_carryforward;
+    }
+
+    Ok((aktien, sonstige))
+}
+
+/// Load tax settings for a year (defaults if none are stored)
+pub(crate) fn load_tax_settings(conn: &rusqlite::Connection, year: i32) -> TaxSettings {
+    let result: Option<(bool, Option<f64>, Option<String>, f64)> = conn
+        .query_row(
+            "SELECT is_married, kirchensteuer_rate, bundesland, freistellung_used FROM pp_tax_settings WHERE year = ?",
+            [year],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)? == 1,
+                    row.get::<_, Option<f64>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            },
+        )
+        .ok();
+
+    match result {
+        Some((is_married, kirchensteuer_rate, bundesland, freistellung_used)) => TaxSettings {
+            year,
+            is_married,
+            kirchensteuer_rate,
+            bundesland,
+            freistellung_limit: get_freistellung_limit(year, is_married),
+            freistellung_used,
+        },
+        // Default settings
+        None => TaxSettings {
+            year,
+            is_married: false,
+            kirchensteuer_rate: None,
+            bundesland: None,
+            freistellung_limit: get_freistellung_limit(year, false),
+            freistellung_used: 0.0,
+        },
     }
+}
+
+/// Store tax settings for a year
+fn store_tax_settings(conn: &rusqlite::Connection, settings: &TaxSettings) -> Result<(), String> {
+    // Upsert keeps columns not part of TaxSettings (e.g. vorabpauschale_applied)
+    conn.execute(
+        r#"
+        INSERT INTO pp_tax_settings (year, is_married, kirchensteuer_rate, bundesland, freistellung_used)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(year) DO UPDATE SET
+            is_married = excluded.is_married,
+            kirchensteuer_rate = excluded.kirchensteuer_rate,
+            bundesland = excluded.bundesland,
+            freistellung_used = excluded.freistellung_used
+        "#,
+        rusqlite::params![
+            settings.year,
+            if settings.is_married { 1 } else { 0 },
+            settings.kirchensteuer_rate,
+            settings.bundesland,
+            settings.freistellung_used,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get or create tax settings for a year
+#[command]
+pub fn get_tax_settings(year: i32) -> Result<TaxSettings, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    Ok(load_tax_settings(conn, year))
+}
+
+/// Save tax settings for a year
+#[command]
+pub fn save_tax_settings(settings: TaxSettings) -> Result<(), String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    store_tax_settings(conn, &settings)
+}
+
+/// Generate detailed German tax report
+#[command]
+pub fn generate_german_tax_report(year: i32) -> Result<GermanTaxReport, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    build_german_tax_report(conn, year)
+}
+
+/// Build the German tax report for a year on an existing connection
+pub(crate) fn build_german_tax_report(
+    conn: &rusqlite::Connection,
+    year: i32,
+) -> Result<GermanTaxReport, String> {
+    let settings = load_tax_settings(conn, year);
+
+    let base_currency: String = conn
+        .query_row(
+            "SELECT base_currency FROM pp_import ORDER BY id DESC LIMIT 1",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap_or_else(|_| "EUR".to_string());
+
+    let start_date = format!("{}-01-01", year);
+    let end_date = format!("{}-12-31", year);
+
+    // Get dividends and interest
+    let DividendIncome {
+        details: dividend_details,
+        gross: total_dividend_gross,
+        foreign: total_dividend_foreign,
+        withholding_tax: total_dividend_wht,
+    } = load_dividends(conn, &start_date, &end_date)?;
+    let total_interest = load_interest(conn, &start_date, &end_date);
+
+    // Get realized gains from FIFO consumption (after Teilfreistellung)
+    let (gains_details, losses_details) = load_realized_gains(conn, &start_date, &end_date)?;
+    let total_gains: f64 = gains_details.iter().map(|i| i.taxable_amount).sum();
+    let total_losses: f64 = losses_details.iter().map(|i| i.taxable_amount.abs()).sum();
+    let teilfreistellung_exempt: f64 = gains_details
+        .iter()
+        .chain(losses_details.iter())
+        .map(|i| i.net_amount - i.taxable_amount)
+        .sum();
 
     // Calculate totals
     let total_taxable_income = total_dividend_gross + total_interest + total_gains;
 
+    // Offset losses via the loss pots (Aktien / Sonstige), including carry-forward
+    let (aktien_carry_in, sonstige_carry_in) = load_loss_carry_in(conn, year)?;
+    let loss_input = LossPotInput::from_items(total_dividend_gross + total_interest, &gains_details, &losses_details);
+    let loss_pots = apply_loss_pots(aktien_carry_in, sonstige_carry_in, &loss_input);
+    let income_after_losses =
+        (total_taxable_income - loss_pots.aktien_used - loss_pots.sonstige_used).max(0.0);
+
     // Apply Freistellung
     let freistellung_available = settings.freistellung_limit - settings.freistellung_used;
-    let freistellung_used_now = freistellung_available.min(total_taxable_income).max(0.0);
-
-    // Apply losses (against gains only in Germany)
-    let net_gains_after_losses = (total_gains - total_losses).max(0.0);
+    let freistellung_used_now = freistellung_available.min(income_after_losses).max(0.0);
 
     // Taxable amount after deductions
-    let taxable_after_deductions =
-        (total_dividend_gross + total_interest + net_gains_after_losses - freistellung_used_now).max(0.0);
+    let taxable_after_deductions = (income_after_losses - freistellung_used_now).max(0.0);
 
     // Foreign tax credit
     let creditable_foreign_tax = calculate_creditable_wht(total_dividend_wht, total_dividend_gross);
@@ -445,10 +754,10 @@ pub fn generate_german_tax_report(year: i32) -> Result<GermanTaxReport, String>
         .unwrap_or(0.0);
 
     // Anlage KAP data
-    // Separate inland vs. ausland dividends (simplified: assume all are foreign for now)
+    // Separate inland vs. ausland dividends by security domicile / ISIN prefix
     let anlage_kap = AnlageKapData {
-        zeile_7_inland_dividenden: 0.0, // Would need ISIN prefix check for German stocks
-        zeile_8_ausland_dividenden: total_dividend_gross,
+        zeile_7_inland_dividenden: total_dividend_gross - total_dividend_foreign,
+        zeile_8_ausland_dividenden: total_dividend_foreign,
         zeile_14_zinsen: total_interest,
         zeile_15_veraeusserungsgewinne: total_gains,
         zeile_16_veraeusserungsverluste: total_losses,
@@ -466,10 +775,12 @@ pub fn generate_german_tax_report(year: i32) -> Result<GermanTaxReport, String>
         interest_income_gross: total_interest,
         realized_gains: total_gains,
         realized_losses: total_losses,
+        teilfreistellung_exempt,
         total_taxable_income,
         freistellung_available,
         freistellung_used: freistellung_used_now,
-        loss_carryforward: 0.0, // Would need tracking across years
+        loss_carryforward: loss_pots.aktien_carryforward + loss_pots.sonstige_carryforward,
+        loss_pots,
         taxable_after_deductions,
         foreign_withholding_tax: total_dividend_wht,
         creditable_foreign_tax,
@@ -486,6 +797,27 @@ pub fn generate_german_tax_report(year: i32) -> Result<GermanTaxReport, String>
     })
 }
 
+/// Store the year-end loss pot balances of the report (Verlustvortrag)
+fn persist_loss_pots(conn: &rusqlite::Connection, year: i32) -> Result<LossPotStatus, String> {
+    let report = build_german_tax_report(conn, year)?;
+    store_loss_pots(conn, year, &report.loss_pots)?;
+    Ok(report.loss_pots)
+}
+
+/// Save the loss carry-forward at the end of a year
+///
+/// The tax report itself is read-only; balances are only stored here and
+/// are the starting point of the carry-in for later years.
+#[command]
+pub fn save_loss_pots(year: i32) -> Result<LossPotStatus, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    persist_loss_pots(conn, year)
+}
+
 /// Get Freistellung status for a year
 #[command]
 pub fn get_freistellung_status(year: i32) -> Result<FreistellungStatus, String> {
@@ -516,6 +848,165 @@ pub fn update_freistellung_used(year: i32, amount: f64) -> Result<(), String> {
     save_tax_settings(settings)
 }
 
+/// Preview the tax of selling `shares` of a security today
+///
+/// Simulates the FIFO consumption of the open lots in memory; nothing is
+/// written. Losses are not offset against the loss pots.
+#[command]
+pub fn preview_sale_tax(
+    security_id: i64,
+    portfolio_id: i64,
+    shares: f64,
+) -> Result<SaleTaxPreview, String> {
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    calculate_sale_tax_preview(conn, security_id, portfolio_id, shares)
+}
+
+fn calculate_sale_tax_preview(
+    conn: &rusqlite::Connection,
+    security_id: i64,
+    portfolio_id: i64,
+    shares: f64,
+) -> Result<SaleTaxPreview, String> {
+    if !shares.is_finite() || shares <= 0.0 {
+        return Err("Stückzahl muss größer als 0 sein".to_string());
+    }
+
+    let base_currency =
+        crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let (security_currency, fund_type): (String, Option<String>) = conn
+        .query_row(
+            "SELECT currency, fund_type FROM pp_security WHERE id = ?1",
+            [security_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| format!("Wertpapier {} nicht gefunden", security_id))?;
+
+    let (price_date, price_raw): (String, i64) = conn
+        .query_row(
+            "SELECT date(date), value FROM pp_latest_price WHERE security_id = ?1 AND value IS NOT NULL",
+            [security_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .or_else(|_| {
+            conn.query_row(
+                "SELECT date(date), value FROM pp_price WHERE security_id = ?1 ORDER BY date DESC LIMIT 1",
+                [security_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+        })
+        .map_err(|_| "Kein aktueller Kurs vorhanden".to_string())?;
+    let today = chrono::Local::now().date_naive();
+    let price = crate::currency::convert(
+        conn,
+        crate::pp::common::prices::to_decimal(price_raw),
+        &security_currency,
+        &base_currency,
+        today,
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Open lots, oldest first (FIFO is mandatory within a German depot)
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT date(purchase_date), remaining_shares, original_shares, gross_amount, currency
+            FROM pp_fifo_lot
+            WHERE security_id = ?1 AND portfolio_id = ?2 AND remaining_shares > 0 AND original_shares > 0
+            ORDER BY purchase_date, id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let open_lots = stmt
+        .query_map([security_id, portfolio_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let available: f64 = open_lots
+        .iter()
+        .map(|(_, remaining, _, _, _)| crate::pp::common::shares::to_decimal(*remaining))
+        .sum();
+    if shares > available + 1e-9 {
+        return Err(format!(
+            "Nur {:.4} Stück im Bestand, {:.4} Stück angefragt",
+            available, shares
+        ));
+    }
+
+    let mut lots = Vec::new();
+    let mut remaining = shares;
+    for (purchase_date, remaining_raw, original_raw, gross_raw, lot_currency) in open_lots {
+        if remaining <= 1e-9 {
+            break;
+        }
+        let lot_shares = crate::pp::common::shares::to_decimal(remaining_raw);
+        let used = remaining.min(lot_shares);
+        let mut cost = gross_raw as f64 / 100.0 * used
+            / crate::pp::common::shares::to_decimal(original_raw);
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(&purchase_date, "%Y-%m-%d") {
+            cost = crate::currency::convert(conn, cost, &lot_currency, &base_currency, date)
+                .unwrap_or(cost);
+        }
+        lots.push(SaleTaxLot {
+            purchase_date,
+            shares: used,
+            cost_basis: cost,
+            gain: used * price - cost,
+        });
+        remaining -= used;
+    }
+
+    let proceeds = shares * price;
+    let cost_basis: f64 = lots.iter().map(|l| l.cost_basis).sum();
+    let realized_gain = proceeds - cost_basis;
+    let teilfreistellung = teilfreistellung_for(fund_type.as_deref());
+    let taxable_gain = realized_gain * (1.0 - teilfreistellung);
+
+    let settings = load_tax_settings(conn, today.year());
+    let freistellung_available = (settings.freistellung_limit - settings.freistellung_used).max(0.0);
+    let freistellung_used = taxable_gain.max(0.0).min(freistellung_available);
+    let taxable_after_freistellung = taxable_gain.max(0.0) - freistellung_used;
+    let (abgeltungssteuer, solidaritaetszuschlag, kirchensteuer) =
+        calculate_abgeltungssteuer(taxable_after_freistellung, settings.kirchensteuer_rate);
+    let total_tax = abgeltungssteuer + solidaritaetszuschlag + kirchensteuer;
+
+    Ok(SaleTaxPreview {
+        security_id,
+        portfolio_id,
+        shares,
+        price,
+        price_date,
+        currency: base_currency,
+        proceeds,
+        cost_basis,
+        realized_gain,
+        teilfreistellung,
+        taxable_gain,
+        freistellung_available,
+        freistellung_used,
+        taxable_after_freistellung,
+        abgeltungssteuer,
+        solidaritaetszuschlag,
+        kirchensteuer,
+        total_tax,
+        net_proceeds: proceeds - total_tax,
+        lots,
+    })
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -728,4 +1219,219 @@ mod tests {
         assert_eq!(SOLI_RATE, 0.055);
         assert_eq!(MAX_CREDITABLE_WHT, 0.15);
     }
+
+    // -------------------------------------------------------------------------
+    // Teilfreistellung Tests
+    // -------------------------------------------------------------------------
+
+    fn setup_gains_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_security (id INTEGER PRIMARY KEY, name TEXT, isin TEXT, fund_type TEXT, is_stock INTEGER);
+            CREATE TABLE pp_txn (
+                id INTEGER PRIMARY KEY, owner_type TEXT, security_id INTEGER,
+                txn_type TEXT, date TEXT, amount INTEGER
+            );
+            CREATE TABLE pp_fifo_consumption (sale_txn_id INTEGER, gross_amount INTEGER);
+
+            INSERT INTO pp_security VALUES (1, 'MSCI World ETF', 'IE00B4L5Y983', 'EQUITY', 0);
+            INSERT INTO pp_security VALUES (2, 'Einzelaktie', 'DE0007164600', NULL, 1);
+
+            -- ETF: sold for 1500 €, cost 1000 € -> gain 500 €
+            INSERT INTO pp_txn VALUES (1, 'portfolio', 1, 'SELL', '2024-03-01', 150000);
+            INSERT INTO pp_fifo_consumption VALUES (1, 100000);
+            -- Stock: sold for 800 €, cost 1000 € -> loss 200 €
+            INSERT INTO pp_txn VALUES (2, 'portfolio', 2, 'SELL', '2024-04-01', 80000);
+            INSERT INTO pp_fifo_consumption VALUES (2, 100000);
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_teilfreistellung_equity_fund_gain() {
+        let conn = setup_gains_db();
+        let (gains, losses) = load_realized_gains(&conn, "2024-01-01", "2024-12-31").unwrap();
+
+        assert_eq!(gains.len(), 1);
+        assert!((gains[0].net_amount - 500.0).abs() < 1e-9);
+        assert_eq!(gains[0].teilfreistellung, 0.30);
+        // 30% of the equity fund gain is tax-free
+        assert!((gains[0].taxable_amount - 350.0).abs() < 1e-9);
+
+        // Individual stocks have no Teilfreistellung
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].teilfreistellung, 0.0);
+        assert!((losses[0].taxable_amount + 200.0).abs() < 1e-9);
+        assert!(losses[0].is_stock);
+        assert!(!gains[0].is_stock);
+    }
+
+    #[test]
+    fn test_only_explicit_shares_are_stock_sales() {
+        let conn = setup_gains_db();
+        conn.execute_batch(
+            "INSERT INTO pp_security VALUES (3, 'Bundesanleihe', 'DE0001102580', NULL, 0);
+             INSERT INTO pp_txn VALUES (3, 'portfolio', 3, 'SELL', '2024-05-01', 90000);
+             INSERT INTO pp_fifo_consumption VALUES (3, 100000);",
+        )
+        .unwrap();
+        let (_, losses) = load_realized_gains(&conn, "2024-01-01", "2024-12-31").unwrap();
+
+        // Bond without fund type goes to the general pot
+        let bond = losses.iter().find(|l| l.security_name == "Bundesanleihe").unwrap();
+        assert!(!bond.is_stock);
+        assert!(is_stock_for(true, None));
+        assert!(is_stock_for(true, Some("")));
+        assert!(!is_stock_for(true, Some("EQUITY")));
+        assert!(!is_stock_for(false, None));
+    }
+
+    #[test]
+    fn test_teilfreistellung_for() {
+        assert_eq!(teilfreistellung_for(Some("EQUITY")), 0.30);
+        assert_eq!(teilfreistellung_for(Some("MIXED")), 0.15);
+        assert_eq!(teilfreistellung_for(Some("REAL_ESTATE")), 0.60);
+        assert_eq!(teilfreistellung_for(None), 0.0);
+    }
+
+    // -------------------------------------------------------------------------
+    // Loss Pot Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_stock_losses_only_offset_stock_gains() {
+        let input = LossPotInput {
+            aktien_gains: 0.0,
+            aktien_losses: 500.0,
+            sonstige_income: 1000.0,
+            sonstige_losses: 0.0,
+        };
+        let pots = apply_loss_pots(0.0, 0.0, &input);
+
+        // Stock losses must not reduce dividends or fund gains
+        assert_eq!(pots.aktien_used, 0.0);
+        assert_eq!(pots.aktien_carryforward, 500.0);
+        assert_eq!(pots.sonstige_used, 0.0);
+    }
+
+    #[test]
+    fn test_sonstige_losses_offset_remaining_income() {
+        let input = LossPotInput {
+            aktien_gains: 300.0,
+            aktien_losses: 100.0,
+            sonstige_income: 50.0,
+            sonstige_losses: 400.0,
+        };
+        let pots = apply_loss_pots(50.0, 20.0, &input);
+
+        // Stock pot 150 against 300 stock gains
+        assert_eq!(pots.aktien_used, 150.0);
+        assert_eq!(pots.aktien_carryforward, 0.0);
+        // General pot 420 against remaining 150 stock gains + 50 other income
+        assert_eq!(pots.sonstige_used, 200.0);
+        assert_eq!(pots.sonstige_carryforward, 220.0);
+    }
+
+    #[test]
+    fn test_loss_carry_in_across_years() {
+        let conn = setup_gains_db();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE pp_tax_settings (
+                year INTEGER PRIMARY KEY,
+                is_married INTEGER NOT NULL DEFAULT 0,
+                kirchensteuer_rate REAL,
+                bundesland TEXT,
+                freistellung_used REAL NOT NULL DEFAULT 0,
+                loss_pot_aktien REAL NOT NULL DEFAULT 0,
+                loss_pot_sonstige REAL NOT NULL DEFAULT 0,
+                loss_pots_saved_at TEXT
+            );
+            CREATE TABLE pp_txn_unit (txn_id INTEGER, unit_type TEXT, amount INTEGER);
+            ALTER TABLE pp_security ADD COLUMN domicile TEXT;
+            "#,
+        )
+        .unwrap();
+
+        // 2024: stock loss of 200 € is not offset against the ETF gain
+        let (aktien, sonstige) = load_loss_carry_in(&conn, 2025).unwrap();
+        assert!((aktien - 200.0).abs() < 1e-9);
+        assert_eq!(sonstige, 0.0);
+
+        // Computing the carry-in stores nothing
+        let stored: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pp_tax_settings", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(stored, 0);
+
+        // Only the explicit save stores the year-end balances
+        let pots = persist_loss_pots(&conn, 2024).unwrap();
+        assert!((pots.aktien_carryforward - 200.0).abs() < 1e-9);
+        let stored: f64 = conn
+            .query_row("SELECT loss_pot_aktien FROM pp_tax_settings WHERE year = 2024", [], |r| r.get(0))
+            .unwrap();
+        assert!((stored - 200.0).abs() < 1e-9);
+
+        // First year has nothing to carry in
+        assert_eq!(load_loss_carry_in(&conn, 2024).unwrap(), (0.0, 0.0));
+
+        // A saved year-end balance is the starting point for later years
+        conn.execute("UPDATE pp_tax_settings SET loss_pot_aktien = 150 WHERE year = 2024", [])
+            .unwrap();
+        let (aktien, _) = load_loss_carry_in(&conn, 2025).unwrap();
+        assert!((aktien - 150.0).abs() < 1e-9);
+
+        // Settings without saved pots are ignored
+        conn.execute("INSERT INTO pp_tax_settings (year, is_married) VALUES (2023, 1)", [])
+            .unwrap();
+        conn.execute("DELETE FROM pp_tax_settings WHERE year = 2024", []).unwrap();
+        let (aktien, _) = load_loss_carry_in(&conn, 2025).unwrap();
+        assert!((aktien - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sale_tax_preview() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pp_security (id, uuid, name, currency, fund_type) VALUES (1, 's1', 'Aktie', 'EUR', NULL), (2, 's2', 'ETF', 'EUR', 'EQUITY');
+             INSERT INTO pp_portfolio (id, uuid, name) VALUES (1, 'p1', 'Depot');
+             INSERT INTO pp_txn (id, uuid, owner_type, owner_id, txn_type, date, amount, currency, security_id, shares)
+             VALUES (1, 'b1', 'portfolio', 1, 'BUY', '2020-01-02', 100000, 'EUR', 1, 1000000000),
+                    (2, 'b2', 'portfolio', 1, 'BUY', '2021-01-04', 300000, 'EUR', 1, 1000000000),
+                    (3, 'b3', 'portfolio', 1, 'BUY', '2021-01-04', 100000, 'EUR', 2, 1000000000);
+             INSERT INTO pp_fifo_lot (security_id, portfolio_id, purchase_txn_id, purchase_date, original_shares, remaining_shares, gross_amount, net_amount, currency)
+             VALUES (1, 1, 1, '2020-01-02', 1000000000, 500000000, 100000, 100000, 'EUR'),
+                    (1, 1, 2, '2021-01-04', 1000000000, 1000000000, 300000, 300000, 'EUR'),
+                    (2, 1, 3, '2021-01-04', 1000000000, 1000000000, 100000, 100000, 'EUR');
+             INSERT INTO pp_latest_price (security_id, date, value) VALUES (1, '2024-06-28', 40000000000), (2, '2024-06-28', 50000000000);",
+        )
+        .unwrap();
+
+        // 5 of the first lot (100/share) + 5 of the second (300/share) at 400
+        let preview = calculate_sale_tax_preview(&conn, 1, 1, 10.0).unwrap();
+        assert_eq!(preview.lots.len(), 2);
+        assert!((preview.proceeds - 4000.0).abs() < 1e-6);
+        assert!((preview.cost_basis - 2000.0).abs() < 1e-6);
+        assert!((preview.realized_gain - 2000.0).abs() < 1e-6);
+        assert_eq!(preview.freistellung_used, 1000.0);
+        assert!((preview.total_tax - 1000.0 * 0.26375).abs() < 1e-6);
+        assert!((preview.net_proceeds - (4000.0 - 263.75)).abs() < 1e-6);
+
+        // Teilfreistellung of an equity fund: 30% of 4000 gain is tax-free
+        let fund = calculate_sale_tax_preview(&conn, 2, 1, 10.0).unwrap();
+        assert!((fund.taxable_gain - 2800.0).abs() < 1e-6);
+
+        // Lots are not touched
+        let remaining: i64 = conn
+            .query_row("SELECT SUM(remaining_shares) FROM pp_fifo_lot WHERE security_id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1500000000);
+
+        assert!(calculate_sale_tax_preview(&conn, 1, 1, 20.0).is_err());
+        assert!(calculate_sale_tax_preview(&conn, 1, 1, 0.0).is_err());
+    }
 }
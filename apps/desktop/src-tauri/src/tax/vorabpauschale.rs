@@ -0,0 +1,448 @@
+//! Vorabpauschale for investment funds (§ 18 InvStG)
+//!
+//! Accumulating funds are taxed on a minimum yield every year:
+//! - Basisertrag = Rücknahmepreis at the start of the year × Basiszins × 70%
+//! - capped at the actual value increase of the year (incl. distributions)
+//! - reduced by the distributions of the year
+//! - reduced by 1/12 for every full month before the purchase in the purchase year
+//!
+//! The Teilfreistellung (30% for equity funds) applies to the result. The
+//! Vorabpauschale for year X is deemed received on the first working day of
+//! X+1 and is offset against that year's Freistellungsauftrag.
+
+use super::{calculate_abgeltungssteuer, load_tax_settings, store_tax_settings};
+use crate::db;
+use crate::pp::common::{prices, shares};
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Part of the Basiszins used for the Basisertrag
+const BASISERTRAG_FACTOR: f64 = 0.7;
+
+/// Basiszins published by the BMF (negative values count as 0)
+const BASISZINS: &[(i32, f64)] = &[
+    (2018, 0.0087),
+    (2019, 0.0052),
+    (2020, 0.0007),
+    (2021, -0.0045),
+    (2022, -0.0005),
+    (2023, 0.0255),
+    (2024, 0.0229),
+    (2025, 0.0253),
+];
+
+/// Basiszins for a year, if known
+pub fn basiszins_for(year: i32) -> Option<f64> {
+    BASISZINS.iter().find(|(y, _)| *y == year).map(|(_, rate)| *rate)
+}
+
+/// Teilfreistellung rate for private investors by fund type (§ 20 InvStG)
+pub fn teilfreistellung_rate(fund_type: &str) -> Option<f64> {
+    match fund_type {
+        "EQUITY" => Some(0.30),
+        "MIXED" => Some(0.15),
+        "REAL_ESTATE" => Some(0.60),
+        "FOREIGN_REAL_ESTATE" => Some(0.80),
+        "OTHER" => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Vorabpauschale per share as (Basisertrag, Vorabpauschale)
+fn vorabpauschale_per_share(price_start: f64, price_end: f64, distributions: f64, basiszins: f64) -> (f64, f64) {
+    let basisertrag = price_start * basiszins.max(0.0) * BASISERTRAG_FACTOR;
+    // Capped at the actual increase in value incl. distributions
+    let value_increase = price_end - price_start + distributions;
+    let vorabpauschale = (basisertrag.min(value_increase) - distributions).max(0.0);
+    (basisertrag, vorabpauschale)
+}
+
+/// Shares held at year end, weighted by the months held in the year.
+///
+/// Sales consume the oldest shares first (FIFO), so the shares at year end are
+/// the latest purchases. A purchase in month m counts (13 - m) / 12.
+fn weighted_year_end_shares(shares_start: f64, purchases: &[(u32, f64)], shares_end: f64) -> f64 {
+    let mut remaining = shares_end.max(0.0);
+    let mut weighted = 0.0;
+
+    for &(month, bought) in purchases.iter().rev() {
+        let take = bought.min(remaining);
+        weighted += take * (13 - month) as f64 / 12.0;
+        remaining -= take;
+        if remaining <= 0.0 {
+            return weighted;
+        }
+    }
+
+    weighted + remaining.min(shares_start.max(0.0))
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Vorabpauschale of a single fund
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VorabpauschaleItem {
+    pub security_id: i64,
+    pub security_name: String,
+    pub security_isin: Option<String>,
+    pub fund_type: String,
+    pub teilfreistellung: f64,
+    /// Shares held at year end
+    pub shares: f64,
+    /// Shares weighted by months held (purchases during the year)
+    pub weighted_shares: f64,
+    pub price_start: f64,
+    pub price_end: f64,
+    pub distributions_per_share: f64,
+    pub basisertrag_per_share: f64,
+    pub vorabpauschale_per_share: f64,
+    /// Vorabpauschale before Teilfreistellung (base currency)
+    pub vorabpauschale: f64,
+    /// Taxable amount after Teilfreistellung (base currency)
+    pub taxable_amount: f64,
+    /// Currency of the fund prices
+    pub currency: String,
+}
+
+/// Vorabpauschale for all funds of a year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VorabpauschaleReport {
+    pub year: i32,
+    /// Year the Vorabpauschale is deemed received (year + 1)
+    pub inflow_year: i32,
+    pub basiszins: f64,
+    pub currency: String,
+    pub items: Vec<VorabpauschaleItem>,
+    pub total_vorabpauschale: f64,
+    pub total_taxable: f64,
+    /// Freistellungsauftrag of the inflow year still available
+    pub freistellung_available: f64,
+    pub freistellung_used: f64,
+    pub taxable_after_freistellung: f64,
+    pub abgeltungssteuer: f64,
+    pub solidaritaetszuschlag: f64,
+    pub kirchensteuer: f64,
+    pub total_tax: f64,
+    /// True if the used Freistellung was stored in the tax settings of the inflow year
+    pub applied_to_freistellung: bool,
+}
+
+// ============================================================================
+// Database helpers
+// ============================================================================
+
+/// Total shares held in all portfolios at a date
+fn shares_held_at(conn: &Connection, security_id: i64, date: &str) -> f64 {
+    let sql = format!(
+        "SELECT COALESCE({}, 0) FROM pp_txn t WHERE t.owner_type = 'portfolio' AND t.security_id = ?1 AND date(t.date) <= ?2",
+        crate::pp::HOLDINGS_SUM_SQL
+    );
+    conn.query_row(&sql, params![security_id, date], |row| row.get::<_, i64>(0))
+        .map(shares::to_decimal)
+        .unwrap_or(0.0)
+}
+
+/// Purchases during the year as (month, shares), chronologically
+fn purchases_in_year(conn: &Connection, security_id: i64, year: i32) -> Result<Vec<(u32, f64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT date(date), shares FROM pp_txn
+            WHERE owner_type = 'portfolio' AND security_id = ?1
+              AND txn_type IN ('BUY', 'DELIVERY_INBOUND')
+              AND shares IS NOT NULL
+              AND date(date) >= ?2 AND date(date) <= ?3
+            ORDER BY date
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(
+            params![security_id, format!("{}-01-01", year), format!("{}-12-31", year)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .flatten()
+        .filter_map(|(date, s)| {
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .ok()
+                .map(|d| (d.month(), shares::to_decimal(s)))
+        })
+        .collect())
+}
+
+/// Last price on or before a date, or the first price after it (funds launched during the year)
+fn fund_price(conn: &Connection, security_id: i64, date: &str, fallback_until: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT value FROM pp_price WHERE security_id = ?1 AND date(date) <= ?2 ORDER BY date DESC LIMIT 1",
+        params![security_id, date],
+        |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .or_else(|| {
+        conn.query_row(
+            "SELECT value FROM pp_price WHERE security_id = ?1 AND date(date) > ?2 AND date(date) <= ?3 ORDER BY date LIMIT 1",
+            params![security_id, date, fallback_until],
+            |row| row.get::<_, i64>(0),
+        )
+        .ok()
+    })
+    .map(prices::to_decimal)
+}
+
+/// Distributions per share during the year (in the fund currency)
+fn distributions_per_share(conn: &Connection, security_id: i64, year: i32, currency: &str) -> f64 {
+    let mut stmt = match conn.prepare(
+        r#"
+        SELECT date(date), amount, currency, shares FROM pp_txn
+        WHERE txn_type = 'DIVIDENDS' AND security_id = ?1
+          AND shares IS NOT NULL AND shares > 0
+          AND date(date) >= ?2 AND date(date) <= ?3
+        "#,
+    ) {
+        Ok(stmt) => stmt,
+        Err(_) => return 0.0,
+    };
+
+    let rows = match stmt.query_map(
+        params![security_id, format!("{}-01-01", year), format!("{}-12-31", year)],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        },
+    ) {
+        Ok(rows) => rows,
+        Err(_) => return 0.0,
+    };
+
+    rows.flatten()
+        .map(|(date, amount, txn_currency, paid_shares)| {
+            let amount = amount as f64 / crate::pp::AMOUNT_FACTOR as f64;
+            let amount = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| crate::currency::convert(conn, amount, &txn_currency, currency, d).ok())
+                .unwrap_or(amount);
+            amount / shares::to_decimal(paid_shares)
+        })
+        .sum()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Calculate the Vorabpauschale of all funds (securities with a fund type) for a year.
+///
+/// `basiszins` overrides the built-in table (required for years not yet in it).
+/// With `apply_to_freistellung`, the used Freistellung is stored in the tax
+/// settings of the inflow year (like `update_freistellung_used`); repeated runs
+/// replace the previously applied amount instead of adding it again.
+#[command]
+pub fn calculate_vorabpauschale(
+    year: i32,
+    basiszins: Option<f64>,
+    apply_to_freistellung: Option<bool>,
+) -> Result<VorabpauschaleReport, String> {
+    let basiszins = basiszins
+        .or_else(|| basiszins_for(year))
+        .ok_or_else(|| format!("Basiszins für {} unbekannt – bitte manuell angeben", year))?;
+
+    let conn_guard = db::get_connection().map_err(|e| e.to_string())?;
+    let conn = conn_guard
+        .as_ref()
+        .ok_or_else(|| "Database not initialized".to_string())?;
+
+    let base_currency = crate::currency::get_base_currency(conn).unwrap_or_else(|_| "EUR".to_string());
+    let start_date = format!("{}-12-31", year - 1);
+    let end_date = format!("{}-12-31", year);
+    let year_end = NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| format!("Ungültiges Jahr: {}", year))?;
+
+    let funds: Vec<(i64, String, Option<String>, String, String)> = {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT id, name, isin, fund_type, COALESCE(currency, ?1)
+                FROM pp_security
+                WHERE fund_type IS NOT NULL
+                ORDER BY name
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&base_currency], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+
+    let mut items = Vec::new();
+    for (security_id, name, isin, fund_type, currency) in funds {
+        let Some(teilfreistellung) = teilfreistellung_rate(&fund_type) else {
+            continue;
+        };
+
+        let shares_end = shares_held_at(conn, security_id, &end_date);
+        if shares_end <= 0.0 {
+            continue;
+        }
+        let shares_start = shares_held_at(conn, security_id, &start_date);
+        let purchases = purchases_in_year(conn, security_id, year)?;
+        let weighted_shares = weighted_year_end_shares(shares_start, &purchases, shares_end);
+
+        let (Some(mut price_start), Some(mut price_end)) = (
+            fund_price(conn, security_id, &start_date, &end_date),
+            fund_price(conn, security_id, &end_date, &end_date),
+        ) else {
+            log::warn!("Vorabpauschale: no prices for {} in {}", name, year);
+            continue;
+        };
+
+        // GBX/GBp prices are quoted in pence
+        let price_currency = if currency == "GBX" || currency == "GBp" {
+            price_start /= 100.0;
+            price_end /= 100.0;
+            "GBP".to_string()
+        } else {
+            currency
+        };
+
+        let distributions = distributions_per_share(conn, security_id, year, &price_currency);
+        let (basisertrag, vap_per_share) =
+            vorabpauschale_per_share(price_start, price_end, distributions, basiszins);
+
+        let vorabpauschale = weighted_shares * vap_per_share;
+        let vorabpauschale = crate::currency::convert(conn, vorabpauschale, &price_currency, &base_currency, year_end)
+            .unwrap_or(vorabpauschale);
+
+        items.push(VorabpauschaleItem {
+            security_id,
+            security_name: name,
+            security_isin: isin,
+            fund_type,
+            teilfreistellung,
+            shares: shares_end,
+            weighted_shares,
+            price_start,
+            price_end,
+            distributions_per_share: distributions,
+            basisertrag_per_share: basisertrag,
+            vorabpauschale_per_share: vap_per_share,
+            vorabpauschale,
+            taxable_amount: vorabpauschale * (1.0 - teilfreistellung),
+            currency: price_currency,
+        });
+    }
+
+    let total_vorabpauschale: f64 = items.iter().map(|i| i.vorabpauschale).sum();
+    let total_taxable: f64 = items.iter().map(|i| i.taxable_amount).sum();
+
+    // Offset against the Freistellungsauftrag of the inflow year
+    let inflow_year = year + 1;
+    let mut settings = load_tax_settings(conn, inflow_year);
+    let previously_applied: f64 = conn
+        .query_row(
+            "SELECT vorabpauschale_applied FROM pp_tax_settings WHERE year = ?1",
+            [inflow_year],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.0);
+    let used_without_vap = (settings.freistellung_used - previously_applied).max(0.0);
+    let freistellung_available = (settings.freistellung_limit - used_without_vap).max(0.0);
+    let freistellung_used = freistellung_available.min(total_taxable).max(0.0);
+    let taxable_after_freistellung = (total_taxable - freistellung_used).max(0.0);
+
+    let applied_to_freistellung = apply_to_freistellung.unwrap_or(false);
+    if applied_to_freistellung {
+        settings.freistellung_used = (used_without_vap + freistellung_used).min(settings.freistellung_limit);
+        store_tax_settings(conn, &settings)?;
+        conn.execute(
+            "UPDATE pp_tax_settings SET vorabpauschale_applied = ?1 WHERE year = ?2",
+            params![freistellung_used, inflow_year],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let (abgeltungssteuer, soli, kirchensteuer) =
+        calculate_abgeltungssteuer(taxable_after_freistellung, settings.kirchensteuer_rate);
+
+    Ok(VorabpauschaleReport {
+        year,
+        inflow_year,
+        basiszins,
+        currency: base_currency,
+        items,
+        total_vorabpauschale,
+        total_taxable,
+        freistellung_available,
+        freistellung_used,
+        taxable_after_freistellung,
+        abgeltungssteuer,
+        solidaritaetszuschlag: soli,
+        kirchensteuer,
+        total_tax: abgeltungssteuer + soli + kirchensteuer,
+        applied_to_freistellung,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vorabpauschale_per_share() {
+        // 100 € at the start of 2023, Basiszins 2.55%: Basisertrag = 100 × 0.0255 × 0.7 = 1.785
+        let (basisertrag, vap) = vorabpauschale_per_share(100.0, 110.0, 0.0, 0.0255);
+        assert!((basisertrag - 1.785).abs() < 1e-9);
+        assert!((vap - 1.785).abs() < 1e-9);
+
+        // Capped at the actual value increase
+        let (_, vap) = vorabpauschale_per_share(100.0, 101.0, 0.0, 0.0255);
+        assert!((vap - 1.0).abs() < 1e-9);
+
+        // No Vorabpauschale in a losing year or with a negative Basiszins
+        assert_eq!(vorabpauschale_per_share(100.0, 90.0, 0.0, 0.0255).1, 0.0);
+        assert_eq!(vorabpauschale_per_share(100.0, 110.0, 0.0, -0.0045).1, 0.0);
+
+        // Distributions reduce the Vorabpauschale
+        let (_, vap) = vorabpauschale_per_share(100.0, 110.0, 1.0, 0.0255);
+        assert!((vap - 0.785).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_year_end_shares() {
+        // Held all year
+        assert_eq!(weighted_year_end_shares(10.0, &[], 10.0), 10.0);
+
+        // 12 shares bought in April count 9/12
+        assert!((weighted_year_end_shares(0.0, &[(4, 12.0)], 12.0) - 9.0).abs() < 1e-9);
+
+        // 10 old shares, 5 bought in July, 8 sold: the 7 remaining are the 5 new + 2 old
+        let weighted = weighted_year_end_shares(10.0, &[(7, 5.0)], 7.0);
+        assert!((weighted - (5.0 * 6.0 / 12.0 + 2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_teilfreistellung_and_basiszins() {
+        assert_eq!(teilfreistellung_rate("EQUITY"), Some(0.30));
+        assert_eq!(teilfreistellung_rate("MIXED"), Some(0.15));
+        assert_eq!(teilfreistellung_rate("BOND"), None);
+        assert_eq!(basiszins_for(2024), Some(0.0229));
+        assert_eq!(basiszins_for(1999), None);
+    }
+}
@@ -23,7 +23,7 @@ import { setGlobalErrorHandler } from './lib/errors';
 import { toast } from './store';
 
 // API
-import { validateAllSecurities } from './lib/api';
+import { checkAllocationDrift, runDueInvestmentPlans, validateAllSecurities } from './lib/api';
 
 // Store
 import {
@@ -81,6 +81,7 @@ import {
 
 // Types
 import type { AggregatedHolding, PortfolioData } from './views';
+import type { AllocationAlert, ImportResult, InvestmentPlansExecutedEvent, PriceUpdatedEvent } from './lib/types';
 
 // ============================================================================
 // Main App Component
@@ -427,6 +428,66 @@ function App() {
     };
   }, [loadDbData]);
 
+  // Listen for price-updated events from quote syncs; a sync emits one event
+  // per security, so the reload is coalesced
+  useEffect(() => {
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    const unlisten = listen<PriceUpdatedEvent>('price-updated', () => {
+      clearTimeout(timer);
+      timer = setTimeout(() => {
+        invalidateAllQueries();
+        loadDbData();
+        checkAllocationDrift().catch((err) => console.warn('Allocation drift check failed:', err));
+      }, 300);
+    });
+
+    return () => {
+      clearTimeout(timer);
+      unlisten.then((fn) => fn());
+    };
+  }, [loadDbData]);
+
+  // Execute due investment plans on start (including missed periods)
+  useEffect(() => {
+    const unlisten = listen<InvestmentPlansExecutedEvent>('investment-plans-executed', (event) => {
+      const { executionCount, plans } = event.payload;
+      toast.success(
+        `Sparpläne ausgeführt: ${executionCount} Ausführung${executionCount !== 1 ? 'en' : ''} (${plans.map((p) => p.name).join(', ')})`
+      );
+    });
+
+    unlisten.then(() =>
+      runDueInvestmentPlans().catch((err) => console.warn('Investment plan run failed:', err))
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Check allocation targets on start and every 15 minutes; the backend only
+  // reports breaches that are new or worsened
+  useEffect(() => {
+    const unlisten = listen<AllocationAlert>('allocation-alert', (event) => {
+      const { entityName, currentWeight, targetWeight, alertType } = event.payload;
+      const direction = alertType === 'over_weight' ? 'übergewichtet' : 'untergewichtet';
+      toast.warning(
+        `Allokation: ${entityName} ist ${direction} (${(currentWeight * 100).toFixed(1)} % statt ${(targetWeight * 100).toFixed(1)} %)`
+      );
+    });
+
+    const check = () => {
+      checkAllocationDrift().catch((err) => console.warn('Allocation drift check failed:', err));
+    };
+    check();
+    const interval = setInterval(check, 15 * 60 * 1000);
+
+    return () => {
+      clearInterval(interval);
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Set up global error handler
   useEffect(() => {
     setGlobalErrorHandler((error) => {
@@ -469,10 +530,38 @@ function App() {
       });
 
       if (selected) {
+        // With existing data, offer to merge instead of importing everything again
+        const merge =
+          dbPortfolios.length > 0 &&
+          confirm(
+            'Es sind bereits Daten vorhanden. Sollen nur neue Buchungen und Kurse übernommen werden (Zusammenführen)?\n\n' +
+              'Abbrechen importiert die Datei vollständig.'
+          );
+
         setLoading(true);
         setError(null);
 
-        await invoke('import_pp_file', { path: selected });
+        // Password-protected files: ask for the password until it is correct or cancelled
+        let password: string | null = null;
+        let result: ImportResult | null = null;
+        while (!result) {
+          try {
+            result = await invoke<ImportResult>('import_pp_file', { path: selected, merge, password });
+          } catch (err) {
+            const message = String(err);
+            if (!message.startsWith('PASSWORD_REQUIRED') && !message.startsWith('INCORRECT_PASSWORD')) {
+              throw err;
+            }
+            password = prompt(
+              message.startsWith('INCORRECT_PASSWORD')
+                ? 'Falsches Passwort. Bitte erneut eingeben:'
+                : 'Die Datei ist passwortgeschützt. Bitte Passwort eingeben:'
+            );
+            if (!password) {
+              return;
+            }
+          }
+        }
 
         // Invalidate all TanStack Query caches
         invalidateAllQueries();
@@ -480,14 +569,22 @@ function App() {
         // Reload data
         await loadDbData();
 
-        toast.success('Import erfolgreich abgeschlossen');
+        if (result.merge) {
+          const { transactionsAdded, transactionsSkipped, pricesAdded, securitiesAdded } = result.merge;
+          toast.success(
+            `Zusammenführung abgeschlossen: ${transactionsAdded} neue Buchungen, ${transactionsSkipped} übersprungen, ` +
+              `${securitiesAdded} neue Wertpapiere, ${pricesAdded} neue Kurse`
+          );
+        } else {
+          toast.success('Import erfolgreich abgeschlossen');
+        }
       }
     } catch (err) {
       setError(`Fehler beim Import: ${err}`);
     } finally {
       setLoading(false);
     }
-  }, [loadDbData, setLoading, setError]);
+  }, [dbPortfolios, loadDbData, setLoading, setError]);
 
   // ============================================================================
   // View Router
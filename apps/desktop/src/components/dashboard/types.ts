@@ -14,7 +14,8 @@ export type WidgetType =
   | 'year_returns'
   | 'alerts'
   | 'chart'
-  | 'benchmark';
+  | 'benchmark'
+  | 'yield_on_cost';
 
 export interface Position {
   x: number;
@@ -54,8 +54,8 @@ export function RecentTransactionsWidget({
     setLoading(true);
     setError(null);
     try {
-      const data = await getTransactions({ limit });
-      setTransactions(data);
+      const page = await getTransactions({ limit });
+      setTransactions(page.transactions);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Fehler beim Laden');
     } finally {
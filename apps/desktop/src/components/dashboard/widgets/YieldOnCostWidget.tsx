@@ -0,0 +1,87 @@
+/**
+ * Yield on Cost Widget - Annual dividends relative to the cost basis
+ */
+
+import { useEffect, useState } from 'react';
+import { RefreshCw } from 'lucide-react';
+import { getYieldOnCost, type YieldOnCost } from '../../../lib/api';
+import type { WidgetProps } from '../types';
+
+export function YieldOnCostWidget({ config }: WidgetProps) {
+  const portfolioId = config.settings?.portfolioId as number | undefined;
+  const [data, setData] = useState<YieldOnCost | null>(null);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadYield = async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setData(await getYieldOnCost(portfolioId));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Fehler beim Laden');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    loadYield();
+  }, [portfolioId]);
+
+  const formatCurrency = (value: number, curr: string) => {
+    return new Intl.NumberFormat('de-DE', {
+      style: 'currency',
+      currency: curr,
+      minimumFractionDigits: 0,
+      maximumFractionDigits: 0,
+    }).format(value);
+  };
+
+  if (loading) {
+    return (
+      <div className="h-full flex items-center justify-center">
+        <RefreshCw className="h-5 w-5 animate-spin text-muted-foreground" />
+      </div>
+    );
+  }
+
+  if (error || !data) {
+    return (
+      <div className="h-full flex flex-col p-4">
+        <div className="text-xs text-muted-foreground uppercase tracking-wide mb-3">
+          Yield on Cost
+        </div>
+        <div className="flex-1 flex items-center justify-center">
+          <div className="text-center text-sm text-muted-foreground">
+            <p>{error ?? 'Keine Daten'}</p>
+            <button
+              onClick={loadYield}
+              className="mt-2 text-primary hover:underline"
+            >
+              Erneut versuchen
+            </button>
+          </div>
+        </div>
+      </div>
+    );
+  }
+
+  return (
+    <div className="h-full flex flex-col p-4">
+      <div className="text-xs text-muted-foreground uppercase tracking-wide mb-3">
+        Yield on Cost
+      </div>
+
+      <div className="flex-1 flex flex-col justify-center text-center">
+        <div className="text-2xl font-bold text-green-600">
+          {data.yieldOnCost !== null ? `${data.yieldOnCost.toFixed(2)} %` : '–'}
+        </div>
+        <div className="text-xs text-muted-foreground mt-1">
+          {formatCurrency(data.annualDividends, data.currency)} p.a. /{' '}
+          {formatCurrency(data.costBasis, data.currency)} Einstand
+        </div>
+      </div>
+    </div>
+  );
+}
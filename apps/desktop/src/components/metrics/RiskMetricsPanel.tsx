@@ -6,8 +6,9 @@
  * - Sortino Ratio
  * - Maximum Drawdown
  * - Volatility
- * - Beta / Alpha (vs benchmark)
+ * - Beta / Alpha / Upside & Downside Capture (vs benchmark)
  * - Calmar Ratio
+ * - Value-at-Risk (95%)
  */
 
 import { useState, useEffect } from 'react';
@@ -192,6 +193,17 @@ export function RiskMetricsPanel({
               tooltip="Annualisierte Rendite / Max Drawdown. Höher ist besser."
             />
           )}
+          {metrics.var95Historical !== null && (
+            <MetricCard
+              label="Value-at-Risk (95%)"
+              value={formatPercent(metrics.var95Historical)}
+              color={getRatioColor(metrics.var95Historical, { good: -0.01, bad: -0.03 })}
+              icon={<AlertTriangle size={16} />}
+              tooltip={`Verlust, der an ${metrics.varHorizonDays === 1 ? 'einem Tag' : `${metrics.varHorizonDays} Tagen`} mit 95% Wahrscheinlichkeit nicht überschritten wird (historisch). Parametrisch (Normalverteilung): ${
+                metrics.var95Parametric !== null ? formatPercent(metrics.var95Parametric) : '–'
+              }.`}
+            />
+          )}
         </div>
 
         {/* Drawdown Section */}
@@ -224,7 +236,8 @@ export function RiskMetricsPanel({
         </div>
 
         {/* Beta/Alpha Section (if benchmark provided) */}
-        {(metrics.beta !== null || metrics.alpha !== null) && (
+        {(metrics.beta !== null || metrics.alpha !== null ||
+          metrics.upsideCapture !== null || metrics.downsideCapture !== null) && (
           <div className="bg-muted/30 rounded-lg p-4">
             <div className="flex items-center gap-2 mb-3">
               <Target size={18} className="text-blue-500" />
@@ -265,6 +278,32 @@ export function RiskMetricsPanel({
                   </div>
                 </div>
               )}
+              {metrics.upsideCapture !== null && (
+                <div>
+                  <div className="text-sm text-muted-foreground flex items-center gap-1">
+                    Upside Capture
+                    <Tooltip text="Anteil der Benchmark-Gewinne an steigenden Tagen, den das Portfolio mitnimmt. >100% = stärker gestiegen als der Markt." />
+                  </div>
+                  <div className={`text-2xl font-bold ${
+                    metrics.upsideCapture >= 1 ? 'text-green-600' : ''
+                  }`}>
+                    {formatPercent(metrics.upsideCapture)}
+                  </div>
+                </div>
+              )}
+              {metrics.downsideCapture !== null && (
+                <div>
+                  <div className="text-sm text-muted-foreground flex items-center gap-1">
+                    Downside Capture
+                    <Tooltip text="Anteil der Benchmark-Verluste an fallenden Tagen, den das Portfolio mitmacht. <100% = weniger verloren als der Markt." />
+                  </div>
+                  <div className={`text-2xl font-bold ${
+                    metrics.downsideCapture < 1 ? 'text-green-600' : 'text-red-600'
+                  }`}>
+                    {formatPercent(metrics.downsideCapture)}
+                  </div>
+                </div>
+              )}
             </div>
           </div>
         )}
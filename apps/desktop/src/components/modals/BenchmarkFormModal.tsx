@@ -5,7 +5,7 @@
 import { useState, useEffect, useCallback } from 'react';
 import { X, Search, Loader2, Globe, Database } from 'lucide-react';
 import type { SecurityData } from '../../lib/types';
-import { getSecurities, addBenchmark, searchExternalSecurities, createSecurity } from '../../lib/api';
+import { getSecurities, addBenchmark, addBenchmarkBySymbol, searchExternalSecurities } from '../../lib/api';
 import { useEscapeKey } from '../../lib/hooks';
 
 interface ExternalSecurityResult {
@@ -129,26 +129,16 @@ export function BenchmarkFormModal({
     setIsSubmitting(true);
 
     try {
-      let securityId: number;
-
       if (selectedSecurity) {
         // Use existing internal security
-        securityId = selectedSecurity.id;
+        await addBenchmark(selectedSecurity.id, startDate);
       } else if (selectedExternal) {
-        // Create new security from external search result
-        const result = await createSecurity({
-          name: selectedExternal.name,
-          isin: selectedExternal.isin || undefined,
-          ticker: selectedExternal.symbol,
-          currency: 'EUR', // Default, will be updated by quote sync
-          feed: 'YAHOO',
-        });
-        securityId = result.id;
+        // Backend creates the security and fetches its price history
+        await addBenchmarkBySymbol(selectedExternal.symbol, startDate);
       } else {
         throw new Error('Bitte ein Wertpapier auswählen');
       }
 
-      await addBenchmark(securityId, startDate);
       onSuccess();
       onClose();
     } catch (err) {
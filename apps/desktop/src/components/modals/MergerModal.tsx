@@ -87,14 +87,12 @@ export function MergerModal({
     setIsLoadingPreview(true);
 
     try {
-      const cashInCents = Math.round(parseFloat(formData.cashPerShare) * 100);
       const result = await previewMerger(
         parseInt(formData.sourceSecurityId),
         parseInt(formData.targetSecurityId),
         formData.effectiveDate,
         parseFloat(formData.shareRatio),
-        cashInCents,
-        formData.cashCurrency || undefined
+        parseFloat(formData.cashPerShare) || 0
       );
       setPreview(result);
       setStep('preview');
@@ -110,13 +108,12 @@ export function MergerModal({
     setIsSubmitting(true);
 
     try {
-      const cashInCents = Math.round(parseFloat(formData.cashPerShare) * 100);
       const request: ApplyMergerRequest = {
         sourceSecurityId: parseInt(formData.sourceSecurityId),
         targetSecurityId: parseInt(formData.targetSecurityId),
         effectiveDate: formData.effectiveDate,
         shareRatio: parseFloat(formData.shareRatio),
-        cashPerShare: cashInCents,
+        cashPerShare: parseFloat(formData.cashPerShare) || 0,
         cashCurrency: formData.cashCurrency || undefined,
         note: formData.note || undefined,
       };
@@ -134,8 +131,8 @@ export function MergerModal({
     }
   };
 
-  const formatCurrency = (cents: number, currency: string) => {
-    return (cents / 100).toLocaleString('de-DE', {
+  const formatCurrency = (amount: number, currency: string) => {
+    return amount.toLocaleString('de-DE', {
       style: 'currency',
       currency,
       minimumFractionDigits: 2,
@@ -144,7 +141,7 @@ export function MergerModal({
   };
 
   const formatShares = (shares: number) => {
-    return (shares / 100_000_000).toLocaleString('de-DE', {
+    return shares.toLocaleString('de-DE', {
       minimumFractionDigits: 2,
       maximumFractionDigits: 8,
     });
@@ -394,7 +391,7 @@ export function MergerModal({
                   <div className="flex justify-between text-sm">
                     <span className="text-muted-foreground">Barabfindung/Aktie:</span>
                     <span className="font-medium">
-                      {formatCurrency(preview.cashPerShare, preview.cashCurrency)}
+                      {formatCurrency(preview.cashPerShare, formData.cashCurrency)}
                     </span>
                   </div>
                 )}
@@ -411,23 +408,51 @@ export function MergerModal({
                     + {formatShares(preview.totalTargetShares)}
                   </span>
                 </div>
-                {preview.totalCashAmount > 0 && (
+                {preview.totalCash > 0 && (
                   <div className="flex justify-between text-sm">
                     <span className="text-muted-foreground">Barabfindung gesamt:</span>
                     <span className="font-medium text-green-600">
-                      + {formatCurrency(preview.totalCashAmount, preview.cashCurrency)}
+                      + {formatCurrency(preview.totalCash, formData.cashCurrency)}
+                    </span>
+                  </div>
+                )}
+                {preview.totalCashInLieu > 0 && (
+                  <div className="flex justify-between text-sm">
+                    <span className="text-muted-foreground">Barausgleich Spitzen:</span>
+                    <span className="font-medium text-green-600">
+                      + {formatCurrency(preview.totalCashInLieu, preview.lots[0]?.currency ?? 'EUR')}
                     </span>
                   </div>
                 )}
-                <div className="h-px bg-border" />
-                <div className="flex justify-between text-sm">
-                  <span className="text-muted-foreground">Einstandswert übertragen:</span>
-                  <span className="font-medium">
-                    {formatCurrency(preview.totalCostBasisTransferred, 'EUR')}
-                  </span>
-                </div>
               </div>
 
+              {/* Lot Conversion */}
+              {preview.lots.length > 0 && (
+                <div className="mt-4">
+                  <h4 className="text-sm font-medium mb-2">Umtausch je Kauflos (FIFO):</h4>
+                  <table className="w-full text-xs">
+                    <thead>
+                      <tr className="border-b border-border text-muted-foreground">
+                        <th className="py-1 text-left font-normal">Kaufdatum</th>
+                        <th className="py-1 text-right font-normal">Alt</th>
+                        <th className="py-1 text-right font-normal">Neu</th>
+                        <th className="py-1 text-right font-normal">Einstandswert</th>
+                      </tr>
+                    </thead>
+                    <tbody>
+                      {preview.lots.map((lot) => (
+                        <tr key={lot.lotId} className="border-b border-border">
+                          <td className="py-1">{formatDate(lot.purchaseDate)}</td>
+                          <td className="py-1 text-right">{formatShares(lot.sourceShares)}</td>
+                          <td className="py-1 text-right">{formatShares(lot.targetShares)}</td>
+                          <td className="py-1 text-right">{formatCurrency(lot.grossCostBasis, lot.currency)}</td>
+                        </tr>
+                      ))}
+                    </tbody>
+                  </table>
+                </div>
+              )}
+
               {/* Affected Portfolios */}
               {preview.affectedPortfolios.length > 0 && (
                 <div className="mt-4">
@@ -447,11 +472,23 @@ export function MergerModal({
                           <span className="text-muted-foreground">Ziel:</span>
                           <span className="text-green-600">+ {formatShares(p.targetShares)}</span>
                         </div>
-                        {p.cashAmount > 0 && (
+                        {p.cashReceived > 0 && (
                           <div className="flex justify-between">
                             <span className="text-muted-foreground">Bar:</span>
                             <span className="text-green-600">
-                              + {formatCurrency(p.cashAmount, preview.cashCurrency)}
+                              + {formatCurrency(p.cashReceived, formData.cashCurrency)}
+                            </span>
+                          </div>
+                        )}
+                        {p.fractionalShares > 0 && (
+                          <div className="flex justify-between">
+                            <span className="text-muted-foreground">
+                              Spitze ({formatShares(p.fractionalShares)} Stk.):
+                            </span>
+                            <span className="text-green-600">
+                              {preview.targetPrice != null
+                                ? `+ ${formatCurrency(p.cashInLieu, preview.lots[0]?.currency ?? 'EUR')}`
+                                : 'kein Kurs'}
                             </span>
                           </div>
                         )}
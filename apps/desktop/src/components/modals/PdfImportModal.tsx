@@ -25,9 +25,13 @@ import type {
   PdfImportPreview,
   PortfolioData,
   AccountData,
+  FieldProvenance,
 } from '../../lib/types';
 import { formatDate } from '../../lib/types';
 
+/** Field confidence below which the review highlights a value (see pdf_import::provenance) */
+const LOW_CONFIDENCE = 0.6;
+
 interface PdfImportModalProps {
   isOpen: boolean;
   onClose: () => void;
@@ -142,6 +146,20 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
     return key && key.trim().length > 0;
   };
 
+  // Regular preview; falls back to OCR automatically for scanned PDFs if possible
+  const previewWithAutoOcr = (filePath: string) => {
+    const key = getOcrApiKey();
+    if (aiEnabled && key && key.trim().length > 0 && ocrConsentGiven) {
+      return previewPdfImport(filePath, {
+        provider: aiProvider,
+        model: aiModel,
+        apiKey: key,
+        consentGiven: true,
+      });
+    }
+    return previewPdfImport(filePath);
+  };
+
   useEffect(() => {
     if (isOpen) {
       loadInitialData();
@@ -378,7 +396,7 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
           } else {
             // Regular preview
             console.log(`[PDF Import D&D] Using regular preview for ${fileName}`);
-            previewData = await previewPdfImport(filePath);
+            previewData = await previewWithAutoOcr(filePath);
             console.log(`[PDF Import D&D] Received preview for ${fileName}:`, previewData.transactions.length, 'transactions');
           }
 
@@ -438,6 +456,12 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
           p.warnings.map(w => `[${p.fileName}] ${w}`)
         ),
         potentialDuplicates: allPreviews.flatMap(p => p.potentialDuplicates || []),
+        ocrUsed: allPreviews.some(p => p.ocrUsed),
+        // Shift per-file transaction indices to the combined list
+        provenance: allPreviews.flatMap((p, fileIdx) => {
+          const offset = allPreviews.slice(0, fileIdx).reduce((n, prev) => n + prev.transactions.length, 0);
+          return (p.provenance || []).map(prov => ({ ...prov, transactionIndex: prov.transactionIndex + offset }));
+        }),
       };
 
       // Remove duplicate securities (by ISIN)
@@ -556,7 +580,7 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
             } else {
               // Regular preview
               console.log(`[PDF Import] Using regular preview for ${fileName}`);
-              previewData = await previewPdfImport(filePath);
+              previewData = await previewWithAutoOcr(filePath);
               console.log(`[PDF Import] Received preview for ${fileName}:`, previewData.transactions.length, 'transactions');
             }
 
@@ -616,6 +640,12 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
             p.warnings.map(w => `[${p.fileName}] ${w}`)
           ),
           potentialDuplicates: allPreviews.flatMap(p => p.potentialDuplicates || []),
+          ocrUsed: allPreviews.some(p => p.ocrUsed),
+          // Shift per-file transaction indices to the combined list
+          provenance: allPreviews.flatMap((p, fileIdx) => {
+            const offset = allPreviews.slice(0, fileIdx).reduce((n, prev) => n + prev.transactions.length, 0);
+            return (p.provenance || []).map(prov => ({ ...prov, transactionIndex: prov.transactionIndex + offset }));
+          }),
         };
 
         // Remove duplicate securities (by ISIN)
@@ -751,6 +781,24 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
     }
   };
 
+  // Highlight fields the parser could not verify in the PDF text
+  const lowConfidenceProps = (idx: number, fields: string[]) => {
+    const provenance = combinedPreview?.provenance?.find(p => p.transactionIndex === idx);
+    const low = (provenance?.fields || []).filter(
+      (f: FieldProvenance) => fields.includes(f.field) && f.confidence < LOW_CONFIDENCE
+    );
+    if (low.length === 0) {
+      return { className: '', title: undefined };
+    }
+    const details = low.map(f =>
+      f.source ? `${f.field}: „${f.source}" (Zeile ${f.line})` : `${f.field}: nicht im Text gefunden`
+    );
+    return {
+      className: 'bg-amber-500/10',
+      title: `Bitte prüfen:\n${details.join('\n')}`,
+    };
+  };
+
   const formatCurrency = (amount: number | undefined | null, currency: string) => {
     try {
       if (amount === undefined || amount === null || isNaN(amount)) {
@@ -1181,9 +1229,13 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
                       <tbody>
                         {combinedPreview.transactions.map((txn, idx) => {
                           const effectiveType = getEffectiveTxnType(idx, txn.txnType);
+                          const dateCheck = lowConfidenceProps(idx, ['date']);
+                          const securityCheck = lowConfidenceProps(idx, ['isin', 'wkn', 'shares', 'pricePerShare']);
+                          const amountCheck = lowConfidenceProps(idx, ['grossAmount', 'taxes', 'netAmount']);
+                          const feeCheck = lowConfidenceProps(idx, ['fees']);
                           return (
                           <tr key={idx} className="border-t border-border">
-                            <td className="py-1.5 px-3 text-xs">{formatDate(txn.date)}</td>
+                            <td className={`py-1.5 px-3 text-xs ${dateCheck.className}`} title={dateCheck.title}>{formatDate(txn.date)}</td>
                             <td className="py-1.5 px-3">
                               <select
                                 value={effectiveType}
@@ -1200,14 +1252,14 @@ export function PdfImportModal({ isOpen, onClose, onSuccess, initialFilePath }:
                                 ))}
                               </select>
                             </td>
-                            <td className="py-1.5 px-3">
+                            <td className={`py-1.5 px-3 ${securityCheck.className}`} title={securityCheck.title}>
                               <span className="font-medium text-sm">{txn.securityName || '-'}</span>
                               {txn.isin && <span className="text-xs text-muted-foreground ml-1">{txn.isin}</span>}
                             </td>
-                            <td className="py-1.5 px-3 text-right font-medium text-sm">
+                            <td className={`py-1.5 px-3 text-right font-medium text-sm ${amountCheck.className}`} title={amountCheck.title}>
                               {formatCurrency(txn.netAmount, txn.currency)}
                             </td>
-                            <td className="py-1.5 px-3 text-right">
+                            <td className={`py-1.5 px-3 text-right ${feeCheck.className}`} title={feeCheck.title}>
                               <input
                                 type="number"
                                 step="0.01"
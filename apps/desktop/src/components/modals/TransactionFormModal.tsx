@@ -94,6 +94,8 @@ export function TransactionFormModal({ isOpen, onClose, onSuccess, defaultSecuri
   const [forexExpanded, setForexExpanded] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [dataLoaded, setDataLoaded] = useState(false);
+  // One key per opened form: a retried or double-submitted create is stored once
+  const [idempotencyKey, setIdempotencyKey] = useState('');
 
   // ESC key to close
   useEscapeKey(isOpen, onClose);
@@ -101,6 +103,7 @@ export function TransactionFormModal({ isOpen, onClose, onSuccess, defaultSecuri
   // Load data when modal opens
   useEffect(() => {
     if (isOpen) {
+      setIdempotencyKey(crypto.randomUUID());
       setIsLoadingData(true);
       setDataLoaded(false);
       Promise.all([getAccounts(), getPortfolios(), getSecurities()])
@@ -282,6 +285,7 @@ export function TransactionFormModal({ isOpen, onClose, onSuccess, defaultSecuri
           // Transfer fields
           otherAccountId: formData.otherAccountId ? parseInt(formData.otherAccountId) : undefined,
           otherPortfolioId: formData.otherPortfolioId ? parseInt(formData.otherPortfolioId) : undefined,
+          idempotencyKey,
         };
 
         // Add fee unit if specified
@@ -7,12 +7,14 @@ import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
   ImportProgress,
   ImportResult,
+  PpFileInfo,
   GoImportResult,
   ImportInfo,
   SecurityData,
   AccountData,
   PortfolioData,
-  TransactionData,
+  TransactionFilters,
+  TransactionPage,
   PriceData,
   HoldingData,
   SecurityChartData,
@@ -44,6 +46,8 @@ import type {
   ClassificationAssignmentData,
   SecurityClassification,
   TaxonomyAllocation,
+  LookThrough,
+  FundHolding,
   CreateTaxonomyRequest,
   UpdateTaxonomyRequest,
   CreateClassificationRequest,
@@ -52,12 +56,15 @@ import type {
   DividendReport,
   RealizedGainsReport,
   TaxReport,
+  CashFlowReport,
+  FireProjection,
   WatchlistData,
   QuoteSyncResult,
   WatchlistSecurityData,
   WatchlistWithSecurities,
   ExternalSecuritySearchResult,
   ExternalSearchResponse,
+  SecurityEnrichment,
   StockSplitPreview,
   ApplyStockSplitRequest,
   ApplySpinOffRequest,
@@ -66,11 +73,15 @@ import type {
   ParseResult,
   PdfImportPreview,
   PdfImportResult,
+  PdfOcrOptions,
+  PdfBatchImportResult,
   SupportedBank,
   // Investment Plans
   InvestmentPlanData,
   CreateInvestmentPlanRequest,
   InvestmentPlanExecution,
+  SimulatePlanRequest,
+  PlanSimulation,
   // Rebalancing
   RebalanceTarget,
   RebalanceAction,
@@ -93,6 +104,9 @@ import type {
   AttributeValue,
   SetAttributeValueRequest,
   SecurityWithAttribute,
+  AttributeFilter,
+  AttributeMatchMode,
+  SecurityAttributeMatch,
 } from './types';
 
 // ============================================================================
@@ -138,13 +152,22 @@ export async function importPPFile(
   }
 }
 
+/**
+ * Inspect a .portfolio file before importing it (format, version, encryption).
+ */
+export async function validatePortfolioFile(path: string): Promise<PpFileInfo> {
+  return invoke<PpFileInfo>('validate_portfolio_file', { path });
+}
+
 /**
  * Import a Portfolio Performance XML file using the Rust parser (legacy).
  * @deprecated Use importPPFile instead (Go sidecar)
  */
 export async function importPPFileRust(
   path: string,
-  onProgress?: (progress: ImportProgress) => void
+  onProgress?: (progress: ImportProgress) => void,
+  merge?: boolean,
+  password?: string
 ): Promise<ImportResult> {
   let unlisten: UnlistenFn | null = null;
 
@@ -155,7 +178,7 @@ export async function importPPFileRust(
       });
     }
 
-    const result = await invoke<ImportResult>('import_pp_file', { path });
+    const result = await invoke<ImportResult>('import_pp_file', { path, merge, password });
     return result;
   } finally {
     if (unlisten) {
@@ -180,10 +203,13 @@ export async function deleteImport(importId: number): Promise<void> {
 
 /**
  * Rebuild FIFO cost basis lots from transactions.
- * Call this after fixing FIFO calculation logic.
+ * Call this after fixing FIFO calculation logic, or with `securityIds` once after
+ * a bulk import that used `skipFifoRebuild`.
  */
-export async function rebuildFifoLots(): Promise<{ securitiesProcessed: number; lotsCreated: number }> {
-  return invoke('rebuild_fifo_lots');
+export async function rebuildFifoLots(
+  securityIds?: number[]
+): Promise<{ securitiesProcessed: number; lotsCreated: number }> {
+  return invoke('rebuild_fifo_lots', { securityIds });
 }
 
 // ============================================================================
@@ -282,6 +308,14 @@ export interface QuoteSyncStatus {
   outdatedSecurities: OutdatedQuoteInfo[];
 }
 
+export interface QuoteSyncError {
+  id: number;
+  name: string;
+  provider: string;
+  error: string;
+  failedAt: string;
+}
+
 export interface ProviderStatus {
   totalSecurities: number;
   configuredCount: number;
@@ -291,6 +325,8 @@ export interface ProviderStatus {
   byProvider: ProviderSecurityCount[];
   cannotSync: SecurityProviderInfo[];
   quoteStatus: QuoteSyncStatus;
+  /** Securities whose last quote fetch failed (after all retries) */
+  syncErrors: QuoteSyncError[];
 }
 
 /**
@@ -302,6 +338,46 @@ export async function getProviderStatus(apiKeys?: ApiKeys): Promise<ProviderStat
   return invoke<ProviderStatus>('get_provider_status', { apiKeys });
 }
 
+export interface IntradayPrice {
+  timestamp: string;
+  price: number;
+}
+
+/**
+ * Fetch intraday quotes (5 min) and store them for the live chart.
+ * Without securityIds all securities on watchlists are fetched.
+ * Intraday rows older than 7 days are pruned.
+ */
+export async function fetchIntradayQuotes(securityIds?: number[]): Promise<QuoteSyncResult> {
+  return invoke<QuoteSyncResult>('fetch_intraday_quotes', { securityIds });
+}
+
+/**
+ * Get stored intraday prices of a security (oldest first).
+ */
+export async function getIntradayPrices(securityId: number): Promise<IntradayPrice[]> {
+  return invoke<IntradayPrice[]>('get_intraday_prices', { securityId });
+}
+
+export interface StaleSecurity {
+  id: number;
+  name: string;
+  isin: string | null;
+  ticker: string | null;
+  feed: string | null;
+  lastQuoteDate: string | null;
+  /** Days since the latest price, null if there is no price at all */
+  daysStale: number | null;
+}
+
+/**
+ * Get held securities whose latest price is older than maxAgeDays (or missing).
+ * @param maxAgeDays Maximum accepted age of the latest price in days
+ */
+export async function getStaleSecurities(maxAgeDays: number): Promise<StaleSecurity[]> {
+  return invoke<StaleSecurity[]>('get_stale_securities', { maxAgeDays });
+}
+
 // ============================================================================
 // Quote Provider Suggestions
 // ============================================================================
@@ -619,16 +695,27 @@ export async function getPortfolios(importId?: number): Promise<PortfolioData[]>
 }
 
 /**
- * Get transactions with optional filters.
+ * Get a page of transactions with optional filters.
  */
-export async function getTransactions(options?: {
-  ownerType?: string;
-  ownerId?: number;
-  securityId?: number;
-  limit?: number;
-  offset?: number;
-}): Promise<TransactionData[]> {
-  return invoke<TransactionData[]>('get_transactions', options ?? {});
+export async function getTransactions(
+  options?: TransactionFilters & {
+    limit?: number;
+    offset?: number;
+  }
+): Promise<TransactionPage> {
+  return invoke<TransactionPage>('get_transactions', options ?? {});
+}
+
+/**
+ * Full-text search over transaction notes and security name, ISIN, WKN and ticker.
+ * Every word must match as word prefix (e.g. "nestle divi").
+ */
+export async function searchTransactions(
+  query: string,
+  limit?: number,
+  offset?: number
+): Promise<TransactionPage> {
+  return invoke<TransactionPage>('search_transactions', { query, limit, offset });
 }
 
 /**
@@ -654,8 +741,15 @@ export interface OutlierInfo {
   value: number;
   previousValue: number;
   changePercent: number;
+  reason: OutlierReason;
 }
 
+/**
+ * Why a price was flagged: single-day spike away from both neighbors,
+ * or a daily move above the threshold.
+ */
+export type OutlierReason = 'spike' | 'daily_move';
+
 /**
  * Summary of outliers detected in price data.
  */
@@ -676,9 +770,10 @@ export interface OutlierSummary {
 export interface PriceDataWithOutliers {
   date: string;
   value: number;
-  /** Whether this price is detected as an outlier (>75% daily change) */
+  /** Whether this price is detected as an outlier */
   isOutlier: boolean;
-  /** Percentage change from previous day */
+  reason: OutlierReason | null;
+  /** Percentage change from previous day (or from the day before a spike) */
   changePercent: number | null;
 }
 
@@ -705,17 +800,20 @@ export interface FilteredPriceHistory {
  * @param securityId The security ID
  * @param startDate Optional start date (ISO format)
  * @param endDate Optional end date (ISO format)
+ * @param percentJumpThreshold Daily change in percent that counts as outlier (default 30)
  * @returns Prices with outlier flags and a summary
  */
 export async function getPriceHistoryWithOutliers(
   securityId: number,
   startDate?: string,
-  endDate?: string
+  endDate?: string,
+  percentJumpThreshold?: number
 ): Promise<PriceHistoryWithOutliers> {
   return invoke<PriceHistoryWithOutliers>('get_price_history_with_outliers', {
     securityId,
     startDate,
     endDate,
+    percentJumpThreshold,
   });
 }
 
@@ -726,20 +824,64 @@ export async function getPriceHistoryWithOutliers(
  * @param securityId The security ID
  * @param startDate Optional start date (ISO format)
  * @param endDate Optional end date (ISO format)
+ * @param percentJumpThreshold Daily change in percent that counts as outlier (default 30)
  * @returns Filtered prices plus a summary of what was removed
  */
 export async function getPriceHistoryFiltered(
   securityId: number,
   startDate?: string,
-  endDate?: string
+  endDate?: string,
+  percentJumpThreshold?: number
 ): Promise<FilteredPriceHistory> {
   return invoke<FilteredPriceHistory>('get_price_history_filtered', {
     securityId,
     startDate,
     endDate,
+    percentJumpThreshold,
+  });
+}
+
+/**
+ * Delete detected outliers from the stored price history of a security.
+ *
+ * @param securityId The security ID
+ * @param percentJumpThreshold Daily change in percent that counts as outlier (default 30)
+ * @returns The deleted prices
+ */
+export async function deletePriceOutliers(
+  securityId: number,
+  percentJumpThreshold?: number
+): Promise<OutlierInfo[]> {
+  return invoke<OutlierInfo[]>('delete_price_outliers', {
+    securityId,
+    percentJumpThreshold,
   });
 }
 
+/**
+ * Result of backfilling price gaps.
+ */
+export interface BackfillResult {
+  /** Number of interpolated prices inserted */
+  inserted: number;
+  /** Number of previously interpolated prices that were replaced */
+  removed: number;
+}
+
+/**
+ * Fill missing trading days (Mon-Fri) between the first and last real price.
+ * Interpolated prices are marked, excluded from exports and recomputed on every call.
+ *
+ * @param securityId The security ID
+ * @param method 'last_value' carries the last price forward, 'linear' interpolates
+ */
+export async function backfillPriceGaps(
+  securityId: number,
+  method: 'last_value' | 'linear'
+): Promise<BackfillResult> {
+  return invoke<BackfillResult>('backfill_price_gaps', { securityId, method });
+}
+
 /**
  * Get FIFO cost basis history and trade data for a security.
  * Used for the security detail chart showing:
@@ -859,6 +1001,46 @@ export async function deleteSecurity(id: number): Promise<void> {
   return invoke('delete_security', { id });
 }
 
+export interface DuplicateSecurity {
+  id: number;
+  uuid: string;
+  name: string;
+  currency: string;
+  ticker: string | null;
+  isRetired: boolean;
+  transactionCount: number;
+  priceCount: number;
+}
+
+export interface DuplicateSecurityCluster {
+  isin: string;
+  suggestedKeepId: number;
+  securities: DuplicateSecurity[];
+}
+
+export interface MergeSecuritiesResult {
+  keptId: number;
+  mergedIds: number[];
+  transactionsMoved: number;
+  pricesMoved: number;
+  fifoLots: number;
+}
+
+/**
+ * Find securities sharing the same ISIN (e.g. after repeated imports).
+ */
+export async function findDuplicateSecurities(): Promise<DuplicateSecurityCluster[]> {
+  return invoke<DuplicateSecurityCluster[]>('find_duplicate_securities');
+}
+
+/**
+ * Merge duplicate securities into keepId (transactions, prices, watchlists,
+ * classifications) and delete them. FIFO lots are rebuilt.
+ */
+export async function mergeSecurities(keepId: number, mergeIds: number[]): Promise<MergeSecuritiesResult> {
+  return invoke<MergeSecuritiesResult>('merge_securities', { keepId, mergeIds });
+}
+
 /**
  * Search securities by name, ISIN, WKN, or ticker.
  */
@@ -934,16 +1116,25 @@ export async function deletePPPortfolio(id: number): Promise<void> {
 /**
  * Create a new transaction.
  * For portfolio BUY/SELL, also creates a matching account transaction via cross-entry.
+ * FIFO lots of the security are rebuilt automatically unless `skipFifoRebuild` is set.
  */
-export async function createTransaction(data: CreateTransactionRequest): Promise<TransactionResult> {
-  return invoke<TransactionResult>('create_transaction', { data });
+export async function createTransaction(
+  data: CreateTransactionRequest,
+  skipFifoRebuild?: boolean
+): Promise<TransactionResult> {
+  return invoke<TransactionResult>('create_transaction', { data, skipFifoRebuild });
 }
 
 /**
  * Delete a transaction (also deletes linked cross-entry and account transaction if applicable).
+ * Moves it to the trash unless `permanent` is set.
  */
-export async function deleteTransaction(id: number): Promise<void> {
-  return invoke('delete_transaction', { id });
+export async function deleteTransaction(
+  id: number,
+  skipFifoRebuild?: boolean,
+  permanent?: boolean
+): Promise<void> {
+  return invoke('delete_transaction', { id, skipFifoRebuild, permanent });
 }
 
 /**
@@ -962,9 +1153,90 @@ export interface BulkDeleteResult {
  * Delete multiple transactions at once.
  * Automatically deletes linked cross-entry transactions (e.g., account side of BUY/SELL).
  * Rebuilds FIFO cost basis for affected securities.
+ * Moves them to the trash unless `permanent` is set.
+ */
+export async function deleteTransactionsBulk(
+  ids: number[],
+  permanent?: boolean
+): Promise<BulkDeleteResult> {
+  return invoke<BulkDeleteResult>('delete_transactions_bulk', { ids, permanent });
+}
+
+/**
+ * Deleted transaction in the trash.
+ */
+export interface TrashedTransaction {
+  trashId: number;
+  deletedAt: string;
+  txnId: number;
+  ownerType: string;
+  ownerId: number;
+  txnType: string;
+  date: string;
+  amount: number;
+  currency: string;
+  securityId?: number;
+  securityName?: string;
+  note?: string;
+  /** Linked transactions restored together with this one */
+  linkedTxnIds: number[];
+}
+
+export interface EmptyTrashResult {
+  entriesDeleted: number;
+  transactionsDeleted: number;
+}
+
+/**
+ * List deleted transactions that can still be restored.
  */
-export async function deleteTransactionsBulk(ids: number[]): Promise<BulkDeleteResult> {
-  return invoke<BulkDeleteResult>('delete_transactions_bulk', { ids });
+export async function getDeletedTransactions(): Promise<TrashedTransaction[]> {
+  return invoke<TrashedTransaction[]>('get_deleted_transactions');
+}
+
+/**
+ * Restore a deleted transaction (with its linked counterpart). Returns the restored IDs.
+ */
+export async function restoreTransaction(id: number): Promise<number[]> {
+  return invoke<number[]>('restore_transaction', { id });
+}
+
+/**
+ * Permanently delete everything in the trash.
+ */
+export async function emptyTrash(): Promise<EmptyTrashResult> {
+  return invoke<EmptyTrashResult>('empty_trash');
+}
+
+export interface AuditLogEntry {
+  id: number;
+  timestamp: string;
+  command: string;
+  /** security, account, portfolio, transaction, corporate_action, import */
+  entityType: string;
+  entityId: number | null;
+  summary: string;
+}
+
+export interface AuditLogFilter {
+  entityType?: string;
+  entityId?: number;
+  command?: string;
+  /** Inclusive, YYYY-MM-DD */
+  from?: string;
+  /** Inclusive, YYYY-MM-DD */
+  to?: string;
+  /** Substring of the summary */
+  search?: string;
+  /** Default 200, max 5000 */
+  limit?: number;
+}
+
+/**
+ * Browse the audit log of mutating commands, newest first.
+ */
+export async function getAuditLog(filter?: AuditLogFilter): Promise<AuditLogEntry[]> {
+  return invoke<AuditLogEntry[]>('get_audit_log', { filter });
 }
 
 /**
@@ -983,8 +1255,8 @@ export async function updateTransaction(id: number, data: {
   txnType?: string;
   securityId?: number;
   currency?: string;
-}): Promise<void> {
-  return invoke('update_transaction', { id, data });
+}, skipFifoRebuild?: boolean): Promise<void> {
+  return invoke('update_transaction', { id, data, skipFifoRebuild });
 }
 
 /**
@@ -1020,6 +1292,25 @@ export async function getPeriodReturns(options?: {
   return invoke<PeriodReturnData[]>('get_period_returns', options ?? {});
 }
 
+export interface StandardPeriodReturn {
+  period: 'YTD' | '1M' | '3M' | '6M' | '1Y' | '3Y' | '5Y' | 'MAX';
+  startDate: string;
+  endDate: string;
+  /** TTWROR in percent */
+  ttwror: number;
+  /** Annualized TTWROR in percent (periods longer than one year) */
+  ttwrorAnnualized?: number;
+  /** Portfolio is younger than the period, start clamped to inception */
+  partialPeriod: boolean;
+}
+
+/**
+ * Get the standard return table (YTD, 1M ... 5Y, since inception).
+ */
+export async function getStandardPeriodReturns(portfolioId?: number): Promise<StandardPeriodReturn[]> {
+  return invoke<StandardPeriodReturn[]>('get_standard_period_returns', { portfolioId });
+}
+
 /**
  * Risk metrics (Sharpe, Sortino, Drawdown, Volatility, Beta/Alpha)
  */
@@ -1032,7 +1323,13 @@ export interface RiskMetrics {
   volatility: number;
   beta: number | null;
   alpha: number | null;
+  upsideCapture: number | null;
+  downsideCapture: number | null;
   calmarRatio: number | null;
+  /** 95% VaR as return over varHorizonDays (negative = loss) */
+  var95Historical: number | null;
+  var95Parametric: number | null;
+  varHorizonDays: number;
   dataPoints: number;
 }
 
@@ -1045,10 +1342,366 @@ export async function calculateRiskMetrics(options?: {
   endDate?: string;
   benchmarkId?: number;
   riskFreeRate?: number;
+  /** VaR horizon in trading days (default 1) */
+  horizonDays?: number;
 }): Promise<RiskMetrics> {
   return invoke<RiskMetrics>('calculate_risk_metrics', options ?? {});
 }
 
+/**
+ * Holding of a security at a specific date
+ */
+export interface HoldingAtDate {
+  securityId: number;
+  name: string;
+  isin: string | null;
+  currency: string;
+  shares: number;
+  /** Price in security currency at or before the date (else latest price) */
+  price: number | null;
+  priceDate: string | null;
+  /** Value in security currency */
+  value: number | null;
+  /** Value converted to base currency */
+  valueBase: number | null;
+  baseCurrency: string;
+}
+
+/**
+ * Get holdings as of a specific date (point-in-time snapshot).
+ * Without portfolioId, holdings of all portfolios are summed per security.
+ */
+export async function getHoldingsAtDate(
+  date: string,
+  portfolioId?: number
+): Promise<HoldingAtDate[]> {
+  return invoke<HoldingAtDate[]>('get_holdings_at_date', { portfolioId, date });
+}
+
+export interface ConcentrationMetrics {
+  /** Number of positions with a positive value */
+  positions: number;
+  /** Herfindahl-Hirschman Index of the position weights (0-1, 1 = single position) */
+  hhi: number;
+  /** Effective number of holdings (1 / HHI) */
+  effectiveHoldings: number;
+  /** Cumulative weight of the 5 largest positions in percent */
+  top5Weight: number;
+  /** Cumulative weight of the 10 largest positions in percent */
+  top10Weight: number;
+}
+
+/**
+ * Get concentration metrics of the current holdings (HHI, top-5/top-10 weight, effective number of holdings).
+ */
+export async function getConcentrationMetrics(portfolioId?: number): Promise<ConcentrationMetrics> {
+  return invoke<ConcentrationMetrics>('get_concentration_metrics', { portfolioId });
+}
+
+export interface NetWorthByCurrency {
+  currency: string;
+  /** Securities value in this currency */
+  securitiesValue: number;
+  /** Cash balance in this currency */
+  cashValue: number;
+  /** Securities and cash converted to base currency */
+  valueBase: number;
+  /** Share of the total net worth in percent */
+  weight: number;
+}
+
+export interface NetWorth {
+  date: string;
+  baseCurrency: string;
+  total: number;
+  securitiesValue: number;
+  cashValue: number;
+  byCurrency: NetWorthByCurrency[];
+}
+
+/**
+ * Get the total net worth across all portfolios and cash accounts in base currency.
+ */
+export async function getTotalNetWorth(): Promise<NetWorth> {
+  return invoke<NetWorth>('get_total_net_worth');
+}
+
+export interface NetWorthPoint {
+  date: string;
+  securities: number;
+  cash: number;
+  total: number;
+}
+
+/**
+ * Get the net worth (all portfolios + all cash accounts) for each price date in a range.
+ */
+export async function getNetWorthHistory(start: string, end: string): Promise<NetWorthPoint[]> {
+  return invoke<NetWorthPoint[]>('get_net_worth_history', { start, end });
+}
+
+export interface PositionPerformance {
+  securityId: number;
+  portfolioId?: number;
+  /** Date of the first transaction */
+  startDate: string;
+  endDate: string;
+  days: number;
+  shares: number;
+  currentValue: number;
+  /** Purchases and inbound deliveries */
+  totalInvested: number;
+  /** Sale proceeds, outbound deliveries and dividends */
+  totalReturned: number;
+  dividends: number;
+  absoluteGain: number;
+  /** TTWROR in percent */
+  ttwror: number;
+  ttwrorAnnualized: number;
+  /** IRR in percent */
+  irr: number;
+  irrConverged: boolean;
+}
+
+/**
+ * Calculate TTWROR and IRR of a single position from its own purchases, sales and dividends.
+ */
+export async function calculatePositionPerformance(
+  securityId: number,
+  portfolioId?: number
+): Promise<PositionPerformance> {
+  return invoke<PositionPerformance>('calculate_position_performance', { securityId, portfolioId });
+}
+
+export interface CurrencyEffect {
+  startDate: string;
+  endDate: string;
+  baseCurrency: string;
+  /** TTWROR with daily exchange rates in percent */
+  ttwror: number;
+  /** TTWROR with exchange rates frozen at the start date in percent */
+  ttwrorConstantFx: number;
+  /** Currency contribution in percentage points */
+  currencyEffect: number;
+}
+
+export async function calculateCurrencyEffect(
+  portfolioId?: number,
+  startDate?: string,
+  endDate?: string
+): Promise<CurrencyEffect> {
+  return invoke<CurrencyEffect>('calculate_currency_effect', { portfolioId, startDate, endDate });
+}
+
+// ============================================================================
+// Account Reconciliation
+// ============================================================================
+
+export interface ReconciliationTransaction {
+  id: number;
+  date: string;
+  txnType: string;
+  /** Signed effect on the balance */
+  amount: number;
+  securityName?: string;
+  note?: string;
+  /** Included in the balance at the reconciliation date */
+  included: boolean;
+}
+
+export interface AccountReconciliation {
+  accountId: number;
+  currency: string;
+  asOfDate: string;
+  computedBalance: number;
+  expectedBalance: number;
+  /** computed - expected */
+  difference: number;
+  reconciled: boolean;
+  transactions: ReconciliationTransaction[];
+}
+
+/**
+ * Compare the computed account balance with a statement balance.
+ */
+export async function reconcileAccount(
+  accountId: number,
+  expectedBalance: number,
+  asOfDate: string,
+  windowDays?: number
+): Promise<AccountReconciliation> {
+  return invoke<AccountReconciliation>('reconcile_account', {
+    accountId,
+    expectedBalance,
+    asOfDate,
+    windowDays,
+  });
+}
+
+export interface AccountLedgerEntry {
+  id: number;
+  date: string;
+  txnType: string;
+  /** Signed effect on the balance (credits positive, debits negative) */
+  amount: number;
+  /** Running balance after this transaction */
+  balance: number;
+  securityName?: string;
+  note?: string;
+}
+
+export interface AccountLedger {
+  accountId: number;
+  accountName: string;
+  currency: string;
+  entries: AccountLedgerEntry[];
+  closingBalance: number;
+}
+
+/**
+ * Get all transactions of an account in date order with running balance.
+ */
+export async function getAccountLedger(accountId: number): Promise<AccountLedger> {
+  return invoke<AccountLedger>('get_account_ledger', { accountId });
+}
+
+// ============================================================================
+// Database Backup / Restore
+// ============================================================================
+
+export interface DatabaseBackupResult {
+  path: string;
+  sizeBytes: number;
+  schemaVersion: number;
+}
+
+/**
+ * Save a snapshot of the database (.db, .sqlite, .sqlite3).
+ */
+export async function backupDatabase(path: string): Promise<DatabaseBackupResult> {
+  return invoke<DatabaseBackupResult>('backup_database', { path });
+}
+
+/**
+ * Replace the database with a backup. Invalid or newer files are rejected.
+ */
+export async function restoreDatabase(path: string): Promise<DatabaseBackupResult> {
+  return invoke<DatabaseBackupResult>('restore_database', { path });
+}
+
+export interface DatabaseSizes {
+  dbBytes: number;
+  walBytes: number;
+}
+
+export interface DatabaseOptimizationResult {
+  before: DatabaseSizes;
+  after: DatabaseSizes;
+  savedBytes: number;
+}
+
+/**
+ * Checkpoint the WAL file and compact the database (VACUUM).
+ */
+export async function optimizeDatabase(): Promise<DatabaseOptimizationResult> {
+  return invoke<DatabaseOptimizationResult>('optimize_database');
+}
+
+// ============================================================================
+// Data Integrity Check
+// ============================================================================
+
+export interface IntegrityIssue {
+  /** transaction, cross_entry, account, fifo_lot or price */
+  entityType: string;
+  entityId: number;
+  message: string;
+}
+
+export interface IntegrityCategory {
+  category: string;
+  description: string;
+  severity: 'error' | 'warning';
+  /** Total issues (issues list is capped at 100) */
+  count: number;
+  issues: IntegrityIssue[];
+}
+
+export interface IntegrityReport {
+  checkedAt: string;
+  issueCount: number;
+  categories: IntegrityCategory[];
+}
+
+/**
+ * Check the database for orphans, broken cross-entries and negative positions.
+ */
+export async function runIntegrityCheck(): Promise<IntegrityReport> {
+  return invoke<IntegrityReport>('run_integrity_check');
+}
+
+// ============================================================================
+// Performance Attribution (Brinson)
+// ============================================================================
+
+export interface AttributionWeight {
+  classificationId: number;
+  classificationName?: string;
+  /** Weight as decimal (0-1) */
+  weight: number;
+  /** Benchmark return of the classification in percent (empty = total benchmark return) */
+  benchmarkReturn?: number;
+}
+
+export interface AttributionBucket {
+  classificationId: number;
+  classificationName: string;
+  portfolioWeight: number;
+  benchmarkWeight: number;
+  portfolioReturn: number;
+  benchmarkReturn: number;
+  allocationEffect: number;
+  selectionEffect: number;
+  interactionEffect: number;
+  totalEffect: number;
+}
+
+export interface AttributionResult {
+  portfolioReturn: number;
+  benchmarkReturn: number;
+  activeReturn: number;
+  allocationEffect: number;
+  selectionEffect: number;
+  interactionEffect: number;
+  weightSource: 'manual' | 'assignments';
+  startDate: string;
+  endDate: string;
+  buckets: AttributionBucket[];
+}
+
+export async function getAttributionWeights(
+  benchmarkId: number,
+  taxonomyId: number
+): Promise<AttributionWeight[]> {
+  return invoke<AttributionWeight[]>('get_attribution_weights', { benchmarkId, taxonomyId });
+}
+
+export async function setAttributionWeights(
+  benchmarkId: number,
+  taxonomyId: number,
+  weights: AttributionWeight[]
+): Promise<void> {
+  return invoke('set_attribution_weights', { request: { benchmarkId, taxonomyId, weights } });
+}
+
+export async function calculateAttribution(
+  benchmarkId: number,
+  taxonomyId: number,
+  portfolioId?: number
+): Promise<AttributionResult> {
+  return invoke<AttributionResult>('calculate_attribution', { portfolioId, benchmarkId, taxonomyId });
+}
+
 // ============================================================================
 // Portfolio Optimization API (Markowitz)
 // ============================================================================
@@ -1114,8 +1767,17 @@ export async function calculateCorrelationMatrix(options?: {
   return invoke<CorrelationMatrix>('calculate_correlation_matrix', options ?? {});
 }
 
+/**
+ * Weight limits of a security (fractions, e.g. 0.05 = 5%)
+ */
+export interface WeightConstraint {
+  minWeight?: number;
+  maxWeight?: number;
+}
+
 /**
  * Calculate efficient frontier for portfolio.
+ * Weights respect the per-security constraints; longOnly defaults to true.
  */
 export async function calculateEfficientFrontier(options?: {
   portfolioId?: number;
@@ -1123,6 +1785,8 @@ export async function calculateEfficientFrontier(options?: {
   endDate?: string;
   riskFreeRate?: number;
   numPoints?: number;
+  weightConstraints?: Record<number, WeightConstraint>;
+  longOnly?: boolean;
 }): Promise<EfficientFrontier> {
   return invoke<EfficientFrontier>('calculate_efficient_frontier', options ?? {});
 }
@@ -1135,10 +1799,149 @@ export async function getOptimalWeights(options: {
   portfolioId?: number;
   startDate?: string;
   endDate?: string;
+  weightConstraints?: Record<number, WeightConstraint>;
+  longOnly?: boolean;
 }): Promise<Record<number, number>> {
   return invoke<Record<number, number>>('get_optimal_weights', options);
 }
 
+/**
+ * Risk parity weight of a security
+ */
+export interface RiskParityWeight {
+  securityId: number;
+  name: string;
+  weight: number;
+  /** Share of the portfolio variance (sums to 1) */
+  riskContribution: number;
+}
+
+/**
+ * Risk parity (equal risk contribution) allocation
+ */
+export interface RiskParityResult {
+  weights: RiskParityWeight[];
+  portfolioVolatility: number;
+  iterations: number;
+}
+
+/**
+ * Get risk parity weights. Without securityIds the held securities are used.
+ */
+export async function getRiskParityWeights(options?: {
+  securityIds?: number[];
+  portfolioId?: number;
+  startDate?: string;
+  endDate?: string;
+}): Promise<RiskParityResult> {
+  return invoke<RiskParityResult>('get_risk_parity_weights', options ?? {});
+}
+
+/**
+ * Projected portfolio value band for one year (Monte Carlo)
+ */
+export interface ProjectionPoint {
+  year: number;
+  p10: number;
+  p50: number;
+  p90: number;
+}
+
+/**
+ * Monte Carlo projection of the portfolio value (p10/p50/p90 per year).
+ */
+export async function monteCarloProjection(options: {
+  years: number;
+  simulations: number;
+  annualContribution: number;
+  portfolioId?: number;
+}): Promise<ProjectionPoint[]> {
+  return invoke<ProjectionPoint[]>('monte_carlo_projection', options);
+}
+
+/**
+ * Correlation of a candidate security to one holding
+ */
+export interface HoldingCorrelation {
+  securityId: number;
+  name: string;
+  ticker: string | null;
+  weight: number;
+  /** null with fewer than 10 common return days */
+  correlation: number | null;
+  observations: number;
+}
+
+/**
+ * Correlation of a candidate security to the holdings and the portfolio
+ */
+export interface CorrelationToHoldings {
+  candidate: OptimizationSecurityInfo;
+  startDate: string;
+  endDate: string;
+  portfolioCorrelation: number | null;
+  portfolioObservations: number;
+  weightedAverageCorrelation: number | null;
+  /** Most correlated (redundant) first */
+  holdings: HoldingCorrelation[];
+}
+
+/**
+ * Correlate a candidate security with each holding and the portfolio
+ * (daily returns, default window 365 days).
+ */
+export async function calculateCorrelationToHoldings(options: {
+  candidateSecurityId: number;
+  portfolioId?: number;
+  windowDays?: number;
+}): Promise<CorrelationToHoldings> {
+  return invoke<CorrelationToHoldings>('calculate_correlation_to_holdings', options);
+}
+
+/**
+ * Effect of adding a small weight of a watchlist security
+ */
+export interface DiversifierCandidate {
+  securityId: number;
+  name: string;
+  ticker: string | null;
+  expectedReturn: number;
+  volatility: number;
+  correlationToPortfolio: number;
+  newSharpeRatio: number;
+  sharpeChange: number;
+  newVolatility: number;
+}
+
+/**
+ * Watchlist securities ranked by their effect on the portfolio Sharpe ratio
+ */
+export interface DiversifierSuggestion {
+  currentReturn: number;
+  currentVolatility: number;
+  currentSharpeRatio: number;
+  addWeight: number;
+  riskFreeRate: number;
+  /** Best Sharpe improvement first */
+  candidates: DiversifierCandidate[];
+  skipped: OptimizationSecurityInfo[];
+}
+
+/**
+ * Rank watchlist securities by the Sharpe ratio change of adding addWeight
+ * (default 5%). Without watchlistId all watchlists are used.
+ */
+export async function suggestDiversifier(options?: {
+  watchlistId?: number;
+  portfolioId?: number;
+  addWeight?: number;
+  riskFreeRate?: number;
+  startDate?: string;
+  endDate?: string;
+}): Promise<DiversifierSuggestion> {
+  return invoke<DiversifierSuggestion>('suggest_diversifier', options ?? {});
+}
+
 // ============================================================================
 // Currency API
 // ============================================================================
@@ -1177,11 +1980,44 @@ export async function getLatestExchangeRate(
   return invoke<ExchangeRateResult>('get_latest_exchange_rate', { base, target });
 }
 
+export interface ExchangeRateBackfillResult {
+  /** Foreign currencies of securities and accounts */
+  currencies: string[];
+  from: string;
+  to: string;
+  fetched: number;
+  /** Newly stored rates (existing days are kept) */
+  inserted: number;
+  /** Currencies the ECB has no rates for */
+  unsupported: string[];
+}
+
+/**
+ * Fill missing daily ECB rates for all used currencies since the first transaction.
+ */
+export async function backfillExchangeRates(): Promise<ExchangeRateBackfillResult> {
+  return invoke<ExchangeRateBackfillResult>('backfill_exchange_rates');
+}
+
+/**
+ * Get the configured base currency from client settings.
+ */
+export async function getBaseCurrency(): Promise<string> {
+  return invoke<string>('get_base_currency');
+}
+
+export interface BaseCurrencyChange {
+  oldCurrency: string;
+  newCurrency: string;
+  /** Exchange rates fetched from the ECB to cover the new base */
+  fetchedRates: number;
+}
+
 /**
- * Get the configured base currency from client settings.
+ * Change the base currency. Fails (unchanged) if rates to the new base are missing.
  */
-export async function getBaseCurrency(): Promise<string> {
-  return invoke<string>('get_base_currency');
+export async function setBaseCurrency(currency: string): Promise<BaseCurrencyChange> {
+  return invoke<BaseCurrencyChange>('set_base_currency', { currency });
 }
 
 /**
@@ -1200,13 +2036,18 @@ export interface ExportResult {
   securitiesCount: number;
   accountsCount: number;
   portfoliosCount: number;
+  transactionsCount: number;
 }
 
 /**
- * Export the database to a .portfolio file (PP-compatible binary format).
+ * Export the database to a .portfolio file.
+ * 'protobuf' is PP's current binary format, 'xml' the zipped XML for older PP builds.
  */
-export async function exportDatabaseToPortfolio(path: string): Promise<ExportResult> {
-  return invoke<ExportResult>('export_database_to_portfolio', { path });
+export async function exportDatabaseToPortfolio(
+  path: string,
+  format?: 'xml' | 'protobuf'
+): Promise<ExportResult> {
+  return invoke<ExportResult>('export_database_to_portfolio', { path, format });
 }
 
 // ============================================================================
@@ -1227,6 +2068,29 @@ export async function exportTransactionsCsv(
   return invoke<CsvExportResult>('export_transactions_csv', { path, ownerType, ownerId });
 }
 
+/** Payload of the `export-progress` event */
+export interface ExportProgress {
+  export: string;
+  rowsWritten: number;
+  totalRows: number;
+  done: boolean;
+}
+
+/**
+ * Export transactions as JSON Lines (one object per line), streamed to the file.
+ * Progress is reported via the `export-progress` event.
+ * @param path Output file path (.jsonl)
+ * @param ownerType Optional filter by "account" or "portfolio"
+ * @param ownerId Optional filter by specific owner ID
+ */
+export async function exportTransactionsJsonl(
+  path: string,
+  ownerType?: string,
+  ownerId?: number
+): Promise<CsvExportResult> {
+  return invoke<CsvExportResult>('export_transactions_jsonl', { path, ownerType, ownerId });
+}
+
 /**
  * Export current holdings to CSV file.
  * @param path Output file path
@@ -1251,6 +2115,20 @@ export async function exportAccountsCsv(path: string): Promise<CsvExportResult>
   return invoke<CsvExportResult>('export_accounts_csv', { path });
 }
 
+export interface GhostfolioExportResult {
+  path: string;
+  accountsExported: number;
+  activitiesExported: number;
+}
+
+/**
+ * Export accounts and transactions in Ghostfolio's JSON import format.
+ * @param path Output file path (.json)
+ */
+export async function exportGhostfolioJson(path: string): Promise<GhostfolioExportResult> {
+  return invoke<GhostfolioExportResult>('export_ghostfolio_json', { path });
+}
+
 /**
  * Preview a CSV file for import.
  * Returns column info and sample values for mapping.
@@ -1305,6 +2183,21 @@ export async function importPricesCsv(
   });
 }
 
+/**
+ * Import the constituents of a fund from a factsheet CSV (replaces previous constituents).
+ * Columns (name, ISIN, region/country, sector, weight in %) are detected by their header.
+ * @param path CSV file path
+ * @param securityId Fund security ID
+ * @param delimiter Optional delimiter character (auto-detected if not provided)
+ */
+export async function importFundHoldingsCsv(
+  path: string,
+  securityId: number,
+  delimiter?: string
+): Promise<CsvImportResult> {
+  return invoke<CsvImportResult>('import_fund_holdings_csv', { path, securityId, delimiter });
+}
+
 /**
  * Detect broker format from CSV file headers.
  * @param path CSV file path
@@ -1466,12 +2359,28 @@ export async function removeAssignment(id: number): Promise<void> {
  * Calculate portfolio allocation by taxonomy.
  * @param taxonomyId The taxonomy to analyze
  * @param portfolioId Optional portfolio filter (all portfolios if not specified)
+ * @param lookThrough Split funds with imported constituents by region or sector
  */
 export async function getTaxonomyAllocation(
   taxonomyId: number,
-  portfolioId?: number
+  portfolioId?: number,
+  lookThrough?: LookThrough
 ): Promise<TaxonomyAllocation[]> {
-  return invoke<TaxonomyAllocation[]>('get_taxonomy_allocation', { taxonomyId, portfolioId });
+  return invoke<TaxonomyAllocation[]>('get_taxonomy_allocation', { taxonomyId, portfolioId, lookThrough });
+}
+
+/**
+ * Get the imported constituents of a fund (largest weight first).
+ */
+export async function getFundHoldings(securityId: number): Promise<FundHolding[]> {
+  return invoke<FundHolding[]>('get_fund_holdings', { securityId });
+}
+
+/**
+ * Delete the imported constituents of a fund. Returns the number of deleted rows.
+ */
+export async function deleteFundHoldings(securityId: number): Promise<number> {
+  return invoke<number>('delete_fund_holdings', { securityId });
 }
 
 /**
@@ -1530,6 +2439,31 @@ export async function generateTaxReport(year: number): Promise<TaxReport> {
   return invoke<TaxReport>('generate_tax_report', { year });
 }
 
+/**
+ * Generate a cash flow statement (deposits, removals, dividends, interest, fees, taxes)
+ * across all accounts, converted to base currency.
+ * @param grouping 'month' (default) or 'year'
+ */
+export async function generateCashFlowReport(
+  startDate: string,
+  endDate: string,
+  grouping?: 'month' | 'year'
+): Promise<CashFlowReport> {
+  return invoke<CashFlowReport>('generate_cash_flow_report', { startDate, endDate, grouping });
+}
+
+/**
+ * Estimate when the net worth reaches 25× the annual spend, based on the
+ * average monthly net contribution so far and monthly compounding.
+ * @param expectedReturn Annual return as decimal (0.05 = 5%)
+ */
+export async function calculateFireProjection(
+  annualSpend: number,
+  expectedReturn: number
+): Promise<FireProjection> {
+  return invoke<FireProjection>('calculate_fire_projection', { annualSpend, expectedReturn });
+}
+
 /**
  * Get dividend yield for a security (trailing 12 months).
  * @param securityId Security ID
@@ -1684,6 +2618,73 @@ export async function getPortfolioDividendYield(): Promise<number> {
   return invoke<number>('get_portfolio_dividend_yield');
 }
 
+export interface YieldOnCost {
+  portfolioId?: number;
+  currency: string;
+  /** Expected dividends for a full year at current holdings */
+  annualDividends: number;
+  /** FIFO cost basis of the open positions */
+  costBasis: number;
+  /** In percent, null without cost basis */
+  yieldOnCost: number | null;
+}
+
+/**
+ * Get yield on cost (expected annual dividends / cost basis).
+ */
+export async function getYieldOnCost(portfolioId?: number): Promise<YieldOnCost> {
+  return invoke<YieldOnCost>('get_yield_on_cost', { portfolioId });
+}
+
+/** A dividend reinvested in the DRIP simulation */
+export interface DripReinvestment {
+  date: string;
+  securityId: number;
+  securityName: string;
+  dividendAmount: number;
+  price: number;
+  sharesBought: number;
+  currency: string;
+}
+
+/** Actual vs. DRIP position of a security */
+export interface DripSecurityResult {
+  securityId: number;
+  securityName: string;
+  actualShares: number;
+  additionalShares: number;
+  latestPrice: number | null;
+  currency: string;
+  actualValue: number;
+  dripValue: number;
+}
+
+/** Result of the dividend reinvestment simulation */
+export interface DripSimulationResult {
+  portfolioId: number | null;
+  startDate: string;
+  currency: string;
+  actualValue: number;
+  dripValue: number;
+  difference: number;
+  differencePercent: number;
+  skippedDividends: number;
+  securities: DripSecurityResult[];
+  reinvestments: DripReinvestment[];
+}
+
+/**
+ * Simulate reinvesting all dividends since startDate (what-if DRIP).
+ * @param portfolioId Optional portfolio (all portfolios if omitted)
+ * @param startDate Start date (YYYY-MM-DD)
+ */
+export async function simulateDrip(
+  startDate: string,
+  portfolioId?: number
+): Promise<DripSimulationResult> {
+  return invoke<DripSimulationResult>('simulate_drip', { portfolioId, startDate });
+}
+
 // ============================================================================
 // Ex-Dividend Management API
 // ============================================================================
@@ -1825,6 +2826,24 @@ export interface TaxableItem {
   withholdingTax: number;
   netAmount: number;
   itemType: string;
+  /** Teilfreistellung rate (0.3 for equity funds, 0 for stocks) */
+  teilfreistellung: number;
+  taxableAmount: number;
+  /** Sale of shares (Aktientopf) */
+  isStock: boolean;
+}
+
+/** Loss pots (Verlustverrechnungstöpfe) of a year */
+export interface LossPotStatus {
+  aktienCarryIn: number;
+  sonstigeCarryIn: number;
+  aktienGains: number;
+  aktienLosses: number;
+  sonstigeLosses: number;
+  aktienUsed: number;
+  sonstigeUsed: number;
+  aktienCarryforward: number;
+  sonstigeCarryforward: number;
 }
 
 /** Data for German tax form "Anlage KAP" */
@@ -1849,10 +2868,12 @@ export interface GermanTaxReport {
   interestIncomeGross: number;
   realizedGains: number;
   realizedLosses: number;
+  teilfreistellungExempt: number;
   totalTaxableIncome: number;
   freistellungAvailable: number;
   freistellungUsed: number;
   lossCarryforward: number;
+  lossPots: LossPotStatus;
   taxableAfterDeductions: number;
   foreignWithholdingTax: number;
   creditableForeignTax: number;
@@ -1899,6 +2920,14 @@ export async function generateGermanTaxReport(year: number): Promise<GermanTaxRe
   return invoke<GermanTaxReport>('generate_german_tax_report', { year });
 }
 
+/**
+ * Store the loss carry-forward (Verlusttöpfe) at the end of a year.
+ * Generating the report never writes; only this call persists the balances.
+ */
+export async function saveLossPots(year: number): Promise<LossPotStatus> {
+  return invoke<LossPotStatus>('save_loss_pots', { year });
+}
+
 /**
  * Get Freistellung status for a year.
  */
@@ -1913,6 +2942,123 @@ export async function updateFreistellungUsed(year: number, amount: number): Prom
   return invoke('update_freistellung_used', { year, amount });
 }
 
+/** Lot consumed by a simulated sale */
+export interface SaleTaxLot {
+  purchaseDate: string;
+  shares: number;
+  costBasis: number;
+  gain: number;
+}
+
+/** Estimated tax and net proceeds of a sale at the latest price */
+export interface SaleTaxPreview {
+  securityId: number;
+  portfolioId: number;
+  shares: number;
+  price: number;
+  priceDate: string;
+  currency: string;
+  proceeds: number;
+  costBasis: number;
+  realizedGain: number;
+  teilfreistellung: number;
+  taxableGain: number;
+  freistellungAvailable: number;
+  freistellungUsed: number;
+  taxableAfterFreistellung: number;
+  abgeltungssteuer: number;
+  solidaritaetszuschlag: number;
+  kirchensteuer: number;
+  totalTax: number;
+  /** Proceeds minus estimated tax (fees not included) */
+  netProceeds: number;
+  lots: SaleTaxLot[];
+}
+
+/**
+ * Preview the tax of selling shares today (FIFO simulated in memory, nothing is booked).
+ */
+export async function previewSaleTax(
+  securityId: number,
+  portfolioId: number,
+  shares: number
+): Promise<SaleTaxPreview> {
+  return invoke<SaleTaxPreview>('preview_sale_tax', { securityId, portfolioId, shares });
+}
+
+/** Vorabpauschale of a single fund */
+export interface VorabpauschaleItem {
+  securityId: number;
+  securityName: string;
+  securityIsin?: string;
+  fundType: string;
+  teilfreistellung: number;
+  shares: number;
+  weightedShares: number;
+  priceStart: number;
+  priceEnd: number;
+  distributionsPerShare: number;
+  basisertragPerShare: number;
+  vorabpauschalePerShare: number;
+  vorabpauschale: number;
+  taxableAmount: number;
+  currency: string;
+}
+
+/** Vorabpauschale for all funds of a year (deemed received in inflowYear) */
+export interface VorabpauschaleReport {
+  year: number;
+  inflowYear: number;
+  basiszins: number;
+  currency: string;
+  items: VorabpauschaleItem[];
+  totalVorabpauschale: number;
+  totalTaxable: number;
+  freistellungAvailable: number;
+  freistellungUsed: number;
+  taxableAfterFreistellung: number;
+  abgeltungssteuer: number;
+  solidaritaetszuschlag: number;
+  kirchensteuer: number;
+  totalTax: number;
+  appliedToFreistellung: boolean;
+}
+
+/**
+ * Calculate the Vorabpauschale for funds (securities with a fund type).
+ * @param basiszins Override for years without a known Basiszins
+ * @param applyToFreistellung Store the used Freistellung for the inflow year
+ */
+export async function calculateVorabpauschale(
+  year: number,
+  options?: { basiszins?: number; applyToFreistellung?: boolean }
+): Promise<VorabpauschaleReport> {
+  return invoke<VorabpauschaleReport>('calculate_vorabpauschale', { year, ...options });
+}
+
+/** Single line of the Anlage KAP */
+export interface AnlageKapLine {
+  zeile: number;
+  label: string;
+  amount: number;
+}
+
+/** German tax report mapped to the Anlage KAP lines */
+export interface AnlageKapExport {
+  year: number;
+  currency: string;
+  lines: AnlageKapLine[];
+  taxAlreadyPaid: number;
+  remainingTaxLiability: number;
+}
+
+/**
+ * Export the German tax report as Anlage KAP line items.
+ */
+export async function exportAnlageKap(year: number): Promise<AnlageKapExport> {
+  return invoke<AnlageKapExport>('export_anlage_kap', { year });
+}
+
 // ============================================================================
 // Watchlist API
 // ============================================================================
@@ -2009,6 +3155,14 @@ export async function searchExternalSecurities(
   });
 }
 
+/**
+ * Suggest name, ticker, exchange, currency and domicile for an ISIN.
+ * Nothing is saved; apply confirmed values with updateSecurity.
+ */
+export async function enrichSecurityFromIsin(isin: string): Promise<SecurityEnrichment> {
+  return invoke<SecurityEnrichment>('enrich_security_from_isin', { isin });
+}
+
 /**
  * Create a security from external search result and add to watchlist.
  * If security with same ISIN exists, uses existing. Otherwise creates new.
@@ -2117,9 +3271,17 @@ export async function undoStockSplit(
   });
 }
 
+/**
+ * Undo an applied spin-off and restore the parent lots.
+ * @param actionId Corporate action id returned by applySpinOff
+ */
+export async function undoSpinOff(actionId: number): Promise<CorporateActionResult> {
+  return invoke<CorporateActionResult>('undo_spin_off', { actionId });
+}
+
 /**
  * Apply a spin-off corporate action.
- * Creates holdings in a new security based on existing holdings.
+ * Splits the cost basis of each parent lot between parent and new security.
  */
 export async function applySpinOff(request: ApplySpinOffRequest): Promise<CorporateActionResult> {
   return invoke<CorporateActionResult>('apply_spin_off', { request });
@@ -2152,7 +3314,7 @@ export interface ApplyMergerRequest {
   targetSecurityId: number;
   effectiveDate: string;
   shareRatio: number;      // target shares per source share
-  cashPerShare: number;    // cash component per source share (in cents)
+  cashPerShare: number;    // cash component per source share (in cash currency)
   cashCurrency?: string;
   note?: string;
 }
@@ -2163,29 +3325,46 @@ export interface ApplyMergerRequest {
 export interface MergerAffectedPortfolio {
   portfolioId: number;
   portfolioName: string;
-  sourceShares: number;    // scaled 10^8
-  targetShares: number;    // scaled 10^8
-  cashAmount: number;      // cents
-  costBasisTransferred: number;  // cents
+  sourceShares: number;
+  targetShares: number;
+  cashReceived: number;
+  /** Fractional target shares settled in cash */
+  fractionalShares: number;
+  cashInLieu: number;
+}
+
+/**
+ * Conversion of a single FIFO lot under the exchange ratio.
+ */
+export interface MergerLotConversion {
+  lotId: number;
+  portfolioId: number;
+  purchaseDate: string;
+  sourceShares: number;
+  targetShares: number;
+  /** Cost basis carried over to the target security */
+  grossCostBasis: number;
+  netCostBasis: number;
+  currency: string;
 }
 
 /**
  * Preview of a merger's effects.
  */
 export interface MergerPreview {
-  sourceSecurityId: number;
   sourceSecurityName: string;
-  targetSecurityId: number;
   targetSecurityName: string;
   effectiveDate: string;
   shareRatio: number;
   cashPerShare: number;
-  cashCurrency: string;
-  totalSourceShares: number;      // scaled 10^8
-  totalTargetShares: number;      // scaled 10^8
-  totalCashAmount: number;        // cents
-  totalCostBasisTransferred: number;  // cents
   affectedPortfolios: MergerAffectedPortfolio[];
+  lots: MergerLotConversion[];
+  /** Target price used for cash-in-lieu (null if no price is known) */
+  targetPrice: number | null;
+  totalSourceShares: number;
+  totalTargetShares: number;
+  totalCash: number;
+  totalCashInLieu: number;
 }
 
 /**
@@ -2196,8 +3375,7 @@ export async function previewMerger(
   targetSecurityId: number,
   effectiveDate: string,
   shareRatio: number,
-  cashPerShare: number,
-  cashCurrency?: string
+  cashPerShare: number
 ): Promise<MergerPreview> {
   return invoke<MergerPreview>('preview_merger', {
     sourceSecurityId,
@@ -2205,19 +3383,27 @@ export async function previewMerger(
     effectiveDate,
     shareRatio,
     cashPerShare,
-    cashCurrency,
   });
 }
 
 /**
  * Apply a merger/acquisition corporate action.
- * Creates DELIVERY_OUTBOUND for source shares and DELIVERY_INBOUND for target shares.
- * Optionally creates dividend transaction for cash component.
+ * Creates DELIVERY_OUTBOUND for source shares and one DELIVERY_INBOUND per FIFO lot,
+ * carrying over each lot's cost basis. Fractional shares are sold (cash-in-lieu),
+ * a cash component is booked as dividend.
  */
 export async function applyMerger(request: ApplyMergerRequest): Promise<CorporateActionResult> {
   return invoke<CorporateActionResult>('apply_merger', { request });
 }
 
+/**
+ * Undo an applied merger and restore the source lots.
+ * @param actionId Corporate action id returned by applyMerger
+ */
+export async function undoMerger(actionId: number): Promise<CorporateActionResult> {
+  return invoke<CorporateActionResult>('undo_merger', { actionId });
+}
+
 // ============================================================================
 // PDF Import API
 // ============================================================================
@@ -2232,9 +3418,16 @@ export async function getSupportedBanks(): Promise<SupportedBank[]> {
 /**
  * Preview PDF import without making changes.
  * Shows which transactions will be imported and which securities need to be created.
+ * Scanned PDFs without text layer are read via OCR automatically if OCR options are given.
  */
-export async function previewPdfImport(pdfPath: string): Promise<PdfImportPreview> {
-  return invoke<PdfImportPreview>('preview_pdf_import', { pdfPath });
+export async function previewPdfImport(pdfPath: string, ocr?: PdfOcrOptions): Promise<PdfImportPreview> {
+  return invoke<PdfImportPreview>('preview_pdf_import', {
+    pdfPath,
+    ocrProvider: ocr?.provider,
+    ocrModel: ocr?.model,
+    ocrApiKey: ocr?.apiKey,
+    ocrConsentGiven: ocr?.consentGiven,
+  });
 }
 
 /**
@@ -2265,6 +3458,27 @@ export async function importPdfTransactions(
   });
 }
 
+/**
+ * Import several PDF statements at once.
+ * Transactions contained in more than one file are imported only once.
+ * Emits `pdf-batch-progress` after each file.
+ */
+export async function importPdfBatch(
+  paths: string[],
+  portfolioId: number,
+  accountId: number,
+  createMissingSecurities: boolean = true,
+  skipDuplicates: boolean = true
+): Promise<PdfBatchImportResult> {
+  return invoke<PdfBatchImportResult>('import_pdf_batch', {
+    paths,
+    portfolioId,
+    accountId,
+    createMissingSecurities,
+    skipDuplicates,
+  });
+}
+
 /**
  * Extract raw text from a PDF for debugging or custom parsing.
  */
@@ -2335,6 +3549,24 @@ export async function getInvestmentPlanExecutions(planId: number): Promise<Inves
   return invoke<InvestmentPlanExecution[]>('get_investment_plan_executions', { planId });
 }
 
+/**
+ * Execute all active plans whose next execution date has passed, catching up missed periods.
+ * Emits 'investment-plans-executed' if anything was executed.
+ */
+export async function runDueInvestmentPlans(): Promise<InvestmentPlanExecution[]> {
+  return invoke<InvestmentPlanExecution[]>('run_due_investment_plans');
+}
+
+/**
+ * Simulate a savings plan over a number of years without creating transactions.
+ */
+export async function simulateInvestmentPlan(
+  config: SimulatePlanRequest,
+  years: number
+): Promise<PlanSimulation> {
+  return invoke<PlanSimulation>('simulate_investment_plan', { config, years });
+}
+
 /**
  * Manually execute an investment plan.
  * @param planId Plan ID
@@ -2365,13 +3597,18 @@ export async function getPlansDueForExecution(date: string): Promise<InvestmentP
  * @param portfolioId Portfolio to rebalance
  * @param targets Target allocations
  * @param newCash Optional additional cash to invest
+ * @param taxAware Prefer sells that realize losses or stay within the Sparer-Pauschbetrag
+ * @param tolerance Overweight (percentage points) winners may keep in tax-aware mode (default 2)
  */
 export async function previewRebalance(
   portfolioId: number,
   targets: RebalanceTarget[],
-  newCash?: number
+  newCash?: number,
+  taxAware?: boolean,
+  tolerance?: number,
+  buyOnly?: boolean
 ): Promise<RebalancePreview> {
-  return invoke<RebalancePreview>('preview_rebalance', { portfolioId, targets, newCash });
+  return invoke<RebalancePreview>('preview_rebalance', { portfolioId, targets, newCash, taxAware, tolerance, buyOnly });
 }
 
 /**
@@ -2381,9 +3618,10 @@ export async function executeRebalance(
   portfolioId: number,
   accountId: number,
   actions: RebalanceAction[],
-  date?: string
+  date?: string,
+  buyOnly?: boolean
 ): Promise<number> {
-  return invoke<number>('execute_rebalance', { portfolioId, accountId, actions, date });
+  return invoke<number>('execute_rebalance', { portfolioId, accountId, actions, date, buyOnly });
 }
 
 /**
@@ -2440,6 +3678,14 @@ export async function addBenchmark(securityId: number, startDate?: string): Prom
   return invoke<BenchmarkData>('add_benchmark', { securityId, startDate });
 }
 
+/**
+ * Add a benchmark by Yahoo ticker, ISIN or common index (e.g. "^GSPC", "DAX").
+ * Creates the security if needed and fetches its price history from startDate.
+ */
+export async function addBenchmarkBySymbol(symbol: string, startDate?: string): Promise<BenchmarkData> {
+  return invoke<BenchmarkData>('add_benchmark', { symbol, startDate });
+}
+
 /**
  * Remove a benchmark.
  */
@@ -2475,13 +3721,15 @@ export async function getBenchmarkComparisonData(
   portfolioId: number | null,
   benchmarkId: number,
   startDate: string,
-  endDate: string
+  endDate: string,
+  normalize?: boolean
 ): Promise<BenchmarkDataPoint[]> {
   return invoke<BenchmarkDataPoint[]>('get_benchmark_comparison_data', {
     portfolioId,
     benchmarkId,
     startDate,
     endDate,
+    normalize,
   });
 }
 
@@ -2493,13 +3741,27 @@ export interface LogoResult {
   securityId: number;
   logoUrl: string | null;
   domain: string | null;
+  /** Logo already in the local cache */
+  cached: boolean;
 }
 
 export interface ReloadLogosResult {
   cleared: number;
   downloaded: number;
+  /** Skipped because already cached */
+  cached: number;
+  notFound: number;
   failed: number;
   totalDomains: number;
+  failures: Array<{ domain: string; reason: string }>;
+}
+
+/** Payload of the `logo-reload-progress` event */
+export interface LogoReloadProgress {
+  current: number;
+  total: number;
+  domain: string;
+  status: 'downloaded' | 'not_found' | 'failed';
 }
 
 /**
@@ -2542,6 +3804,29 @@ export async function clearLogoCache(): Promise<number> {
   return invoke<number>('clear_logo_cache');
 }
 
+export interface LogoCacheStats {
+  count: number;
+  totalBytes: number;
+  /** null = unlimited */
+  maxBytes: number | null;
+  maxCount: number | null;
+}
+
+/**
+ * Number and total size of cached logos with the configured limits.
+ */
+export async function getLogoCacheStats(): Promise<LogoCacheStats> {
+  return invoke<LogoCacheStats>('get_logo_cache_stats');
+}
+
+/**
+ * Set the logo cache limits (undefined = default, 0 = unlimited).
+ * Least recently used logos are evicted right away.
+ */
+export async function setLogoCacheLimits(maxBytes?: number, maxCount?: number): Promise<LogoCacheStats> {
+  return invoke<LogoCacheStats>('set_logo_cache_limits', { maxBytes, maxCount });
+}
+
 /**
  * Batch get logo URLs for multiple securities.
  * Returns Brandfetch CDN URLs that can be used directly in img src.
@@ -2557,18 +3842,31 @@ export async function fetchLogosBatch(
 }
 
 /**
- * Reload all logos - clears cache and re-downloads everything.
+ * Download logos for all securities, skipping cached ones.
+ * force clears the cache and re-downloads everything.
+ * Emits `logo-reload-progress` events.
  */
 export async function reloadAllLogos(
   clientId: string,
-  securities: Array<{ id: number; ticker?: string; name: string }>
+  securities: Array<{ id: number; ticker?: string; name: string }>,
+  force?: boolean
 ): Promise<ReloadLogosResult> {
   return invoke<ReloadLogosResult>('reload_all_logos', {
     clientId,
     securities: securities.map((s) => [s.id, s.ticker || null, s.name]),
+    force,
   });
 }
 
+/**
+ * Generate a monogram logo (initials on a color derived from the name) for a
+ * security without Brandfetch logo. Returns the SVG data URL; `domain` is the
+ * cache key for getCachedLogoData.
+ */
+export async function generateFallbackLogo(securityId: number): Promise<LogoResult> {
+  return invoke<LogoResult>('generate_fallback_logo', { securityId });
+}
+
 /**
  * Check if a logo is cached locally for a domain.
  */
@@ -2601,9 +3899,12 @@ export async function saveLogoToCache(domain: string, base64Data: string): Promi
 /**
  * Get all holdings aggregated by ISIN across all portfolios.
  * Includes current price, cost basis, and gain/loss calculations.
+ * @param groupBy 'portfolio' returns one row per ISIN and portfolio (default: 'security')
  */
-export async function getAllHoldings(): Promise<AggregatedHolding[]> {
-  return invoke<AggregatedHolding[]>('get_all_holdings');
+export async function getAllHoldings(
+  groupBy?: 'security' | 'portfolio'
+): Promise<AggregatedHolding[]> {
+  return invoke<AggregatedHolding[]>('get_all_holdings', { groupBy });
 }
 
 /**
@@ -2687,6 +3988,15 @@ export async function exportTaxReportPdf(path: string, year: number): Promise<Pd
   return invoke<PdfExportResult>('export_tax_report_pdf', { path, year });
 }
 
+/**
+ * Export the Anlage KAP line items to PDF.
+ * @param path Output file path
+ * @param year Tax year
+ */
+export async function exportAnlageKapPdf(path: string, year: number): Promise<PdfExportResult> {
+  return invoke<PdfExportResult>('export_anlage_kap_pdf', { path, year });
+}
+
 // ============================================================================
 // Stock Split Detection API
 // ============================================================================
@@ -2706,6 +4016,35 @@ export async function detectAllSplits(): Promise<SplitDetectionResult> {
   return invoke<SplitDetectionResult>('detect_all_splits');
 }
 
+/**
+ * A detected ISIN change (old/new security are merged on confirmation)
+ */
+export interface IsinChangeResult {
+  actionId: number;
+  securityId: number;
+  securityName: string;
+  oldIsin: string;
+  newIsin: string;
+  effectiveDate: string;
+  confidence: number;
+  successorSecurityId: number | null;
+  successorName: string | null;
+}
+
+/**
+ * Detect ISIN changes by comparing the stored ISIN with the provider's current ISIN
+ */
+export async function detectIsinChanges(onlyHeld?: boolean): Promise<IsinChangeResult[]> {
+  return invoke<IsinChangeResult[]>('detect_isin_changes', { onlyHeld });
+}
+
+/**
+ * Confirm an ISIN change: merges transactions and price history onto the old security
+ */
+export async function confirmIsinChange(actionId: number): Promise<CorporateActionResult> {
+  return invoke<CorporateActionResult>('confirm_isin_change', { actionId });
+}
+
 // ============================================================================
 // Retire Entity API
 // ============================================================================
@@ -2900,6 +4239,14 @@ export async function getAllocationAlerts(portfolioId?: number): Promise<Allocat
   return invoke<AllocationAlert[]>('get_allocation_alerts', { portfolioId });
 }
 
+/**
+ * Evaluate all allocation targets and emit an 'allocation-alert' event per new or worsened breach.
+ * Returns the reported alerts; breaches already reported are only repeated after they cleared or worsened.
+ */
+export async function checkAllocationDrift(): Promise<AllocationAlert[]> {
+  return invoke<AllocationAlert[]>('check_allocation_drift');
+}
+
 /**
  * Get the count of active allocation alerts (for badge display).
  */
@@ -2958,6 +4305,20 @@ export async function createDefaultDashboardLayout(): Promise<DashboardLayout> {
   return invoke<DashboardLayout>('create_default_dashboard_layout');
 }
 
+/**
+ * Export a dashboard layout as JSON (for import on another machine).
+ */
+export async function exportDashboardLayout(layoutId: number): Promise<string> {
+  return invoke<string>('export_dashboard_layout', { layoutId });
+}
+
+/**
+ * Import a dashboard layout from exported JSON. Creates a new, non-default layout.
+ */
+export async function importDashboardLayout(json: string): Promise<DashboardLayout> {
+  return invoke<DashboardLayout>('import_dashboard_layout', { json });
+}
+
 // ============================================================================
 // Custom Attributes API
 // ============================================================================
@@ -3005,6 +4366,18 @@ export async function setSecurityAttribute(request: SetAttributeValueRequest): P
   return invoke('set_security_attribute', { request });
 }
 
+/**
+ * Set the same attribute value on many securities in one transaction.
+ * Returns the number of updated securities.
+ */
+export async function setSecurityAttributeBulk(
+  attributeTypeId: number,
+  value: string,
+  securityIds: number[]
+): Promise<number> {
+  return invoke<number>('set_security_attribute_bulk', { attributeTypeId, value, securityIds });
+}
+
 /**
  * Remove an attribute value from a security.
  */
@@ -3014,9 +4387,18 @@ export async function removeSecurityAttribute(securityId: number, attributeTypeI
 
 /**
  * Get all securities with their values for a specific attribute type.
- */
-export async function getSecuritiesByAttribute(attributeTypeId: number): Promise<SecurityWithAttribute[]> {
-  const data = await invoke<[number, string, string | null][]>('get_securities_by_attribute', { attributeTypeId });
+ * With `value` only matching securities are returned.
+ */
+export async function getSecuritiesByAttribute(
+  attributeTypeId: number,
+  value?: string,
+  matchMode?: AttributeMatchMode
+): Promise<SecurityWithAttribute[]> {
+  const data = await invoke<[number, string, string | null][]>('get_securities_by_attribute', {
+    attributeTypeId,
+    value,
+    matchMode,
+  });
   return data.map(([securityId, securityName, value]) => ({
     securityId,
     securityName,
@@ -3024,6 +4406,13 @@ export async function getSecuritiesByAttribute(attributeTypeId: number): Promise
   }));
 }
 
+/**
+ * Find securities matching all attribute filters, with all their attribute values.
+ */
+export async function searchSecuritiesByAttributes(filters: AttributeFilter[]): Promise<SecurityAttributeMatch[]> {
+  return invoke<SecurityAttributeMatch[]>('search_securities_by_attributes', { filters });
+}
+
 // ============================================================================
 // Consortium (Portfolio Groups) API
 // ============================================================================
@@ -3090,6 +4479,22 @@ export async function getConsortiumHistory(
   return invoke<ConsortiumHistory>('get_consortium_history', { consortiumId, startDate, endDate });
 }
 
+/**
+ * Get risk metrics for a consortium (all portfolios combined).
+ */
+export async function getConsortiumRiskMetrics(
+  consortiumId: number,
+  options?: {
+    startDate?: string;
+    endDate?: string;
+    benchmarkId?: number;
+    riskFreeRate?: number;
+    horizonDays?: number;
+  }
+): Promise<RiskMetrics> {
+  return invoke<RiskMetrics>('get_consortium_risk_metrics', { consortiumId, ...options });
+}
+
 // ============================================================================
 // Symbol Validation API
 // ============================================================================
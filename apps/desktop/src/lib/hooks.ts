@@ -197,8 +197,8 @@ export function useTransactions(options?: {
     setIsLoading(true);
     setError(null);
     try {
-      const transactions = await api.getTransactions(options);
-      setData(transactions);
+      const page = await api.getTransactions(options);
+      setData(page.transactions);
     } catch (err) {
       setError(err instanceof Error ? err.message : String(err));
     } finally {
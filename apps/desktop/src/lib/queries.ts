@@ -31,6 +31,7 @@ import type {
   CreateSecurityRequest,
   CreateAccountRequest,
   CreatePortfolioRequest,
+  TransactionFilters,
 } from './types';
 
 // ============================================================================
@@ -70,7 +71,7 @@ export const queryKeys = {
   securities: ['securities'] as const,
   accounts: ['accounts'] as const,
   portfolios: ['portfolios'] as const,
-  transactions: (filters?: { ownerType?: string; ownerId?: number; securityId?: number }) =>
+  transactions: (filters?: TransactionFilters & { limit?: number; offset?: number }) =>
     ['transactions', filters] as const,
   holdings: ['holdings'] as const,
   portfolioHistory: ['portfolioHistory'] as const,
@@ -230,10 +231,7 @@ export function useDeletePortfolio() {
 // Transactions Queries
 // ============================================================================
 
-export function useTransactions(filters?: {
-  ownerType?: string;
-  ownerId?: number;
-  securityId?: number;
+export function useTransactions(filters?: TransactionFilters & {
   limit?: number;
   offset?: number;
 }) {
@@ -250,7 +248,7 @@ export function useTransactions(filters?: {
 export function useHoldings() {
   return useQuery({
     queryKey: queryKeys.holdings,
-    queryFn: getAllHoldings,
+    queryFn: () => getAllHoldings(),
   });
 }
 
@@ -26,6 +26,73 @@ export interface ImportResult {
   transactionsCount: number;
   pricesCount: number;
   warnings: string[];
+  /** Set for merge imports */
+  merge?: ImportMergeSummary;
+}
+
+// Payload of the 'transactions-changed' event (sent after FIFO lots and caches were refreshed)
+export interface TransactionsChangedEvent {
+  action: 'created' | 'updated' | 'deleted' | 'bulk_deleted';
+  portfolioIds: number[];
+  securityIds: number[];
+}
+
+// Payload of the 'investment-plans-executed' event (after due plans were executed automatically)
+export interface InvestmentPlansExecutedEvent {
+  executionCount: number;
+  plans: {
+    planId: number;
+    name: string;
+    securityId: number;
+    /** Execution dates (YYYY-MM-DD), several if missed periods were caught up */
+    dates: string[];
+    /** Total invested amount in cents */
+    amount: number;
+  }[];
+}
+
+// Payload of the 'price-updated' event (one per security after a quote sync)
+export interface PriceUpdatedEvent {
+  securityId: number;
+  date: string;
+  /** Price in security currency */
+  price: number;
+  currency: string;
+  /** Price converted to the base currency (missing if no exchange rate is available) */
+  priceBase?: number | null;
+  baseCurrency: string;
+}
+
+// Result of inspecting a .portfolio file before import
+export interface PpFileInfo {
+  format: 'protobuf' | 'xml' | 'unknown';
+  /** Payload is stored in a ZIP archive */
+  compressed: boolean;
+  encrypted: boolean;
+  encryptionMethod?: string;
+  version?: number;
+  /** Newest file version known to this build */
+  supportedVersion: number;
+  baseCurrency?: string;
+  /** Data blocks that are skipped during import */
+  unsupportedFeatures: string[];
+  /** Reasons the file cannot be imported */
+  issues: string[];
+  importable: boolean;
+}
+
+// Counts of a merge import (existing data is kept, only new records are added)
+export interface ImportMergeSummary {
+  securitiesMatched: number;
+  securitiesAdded: number;
+  accountsMatched: number;
+  accountsAdded: number;
+  portfoliosMatched: number;
+  portfoliosAdded: number;
+  transactionsAdded: number;
+  transactionsSkipped: number;
+  pricesAdded: number;
+  pricesSkipped: number;
 }
 
 // Go sidecar import result
@@ -144,6 +211,24 @@ export interface TransactionData {
   otherUpdatedAt?: string;
 }
 
+/** Server-side filters for get_transactions */
+export interface TransactionFilters {
+  ownerType?: string;
+  ownerId?: number;
+  securityId?: number;
+  /** BUY/SELL include deliveries, German names like "Kauf" are accepted */
+  txnType?: string;
+  year?: number;
+  startDate?: string;
+  endDate?: string;
+}
+
+/** A page of transactions with the total number of matching rows */
+export interface TransactionPage {
+  transactions: TransactionData[];
+  totalCount: number;
+}
+
 export interface PriceData {
   date: string;
   value: number;
@@ -428,6 +513,9 @@ export interface UpdateSecurityRequest {
   isRetired?: boolean;
   attributes?: Record<string, string>;
   properties?: Record<string, string>;
+  domicile?: string;       // ISO country code for withholding tax ('' = derive from ISIN)
+  fundType?: 'EQUITY' | 'MIXED' | 'REAL_ESTATE' | 'FOREIGN_REAL_ESTATE' | 'OTHER' | '';  // Teilfreistellung
+  isStock?: boolean;       // Share: sales go to the stock loss pot (Aktientopf)
 }
 
 export interface SecurityResult {
@@ -524,6 +612,7 @@ export interface CreateTransactionRequest {
   referenceAccountId?: number; // For portfolio BUY/SELL
   otherAccountId?: number;     // For TRANSFER_IN/OUT (account transfers)
   otherPortfolioId?: number;   // For TRANSFER_IN/OUT (portfolio transfers)
+  idempotencyKey?: string;     // Retried create returns the existing transaction
 }
 
 export interface TransactionResult {
@@ -735,6 +824,7 @@ export interface SecurityClassification {
   weight: number;
 }
 
+/** Allocation of one classification; the unassigned remainder ("Nicht klassifiziert") has classificationId 0 */
 export interface TaxonomyAllocation {
   classificationId: number;
   classificationName: string;
@@ -746,6 +836,22 @@ export interface TaxonomyAllocation {
   percentage: number;
 }
 
+/** Dimension by which funds are decomposed into their constituents */
+export type LookThrough = 'region' | 'sector';
+
+/** Constituent of a fund (imported from a factsheet CSV) */
+export interface FundHolding {
+  id: number;
+  fundSecurityId: number;
+  name: string;
+  isin?: string;
+  region?: string;
+  sector?: string;
+  /** Percent of fund assets (0.0 - 100.0) */
+  weight: number;
+  importedAt?: string;
+}
+
 export interface CreateTaxonomyRequest {
   name: string;
   source?: string;
@@ -795,6 +901,12 @@ export interface DividendEntry {
   netAmount: number;
   shares?: number;
   perShare?: number;
+  /** Country for withholding tax (undefined = unknown, default rate) */
+  country?: string;
+  withholdingRate: number;
+  expectedWithholding: number;
+  expectedNet: number;
+  reclaimable: number;
 }
 
 export interface DividendBySecurity {
@@ -804,9 +916,18 @@ export interface DividendBySecurity {
   totalGross: number;
   totalTaxes: number;
   totalNet: number;
+  totalReclaimable: number;
   paymentCount: number;
 }
 
+export interface DividendByCountry {
+  country?: string;
+  withholdingRate: number;
+  totalGross: number;
+  expectedWithholding: number;
+  reclaimable: number;
+}
+
 export interface DividendByMonth {
   month: string;
   totalGross: number;
@@ -821,9 +942,12 @@ export interface DividendReport {
   totalTaxes: number;
   totalNet: number;
   currency: string;
+  totalExpectedWithholding: number;
+  totalReclaimable: number;
   entries: DividendEntry[];
   bySecurity: DividendBySecurity[];
   byMonth: DividendByMonth[];
+  byCountry: DividendByCountry[];
 }
 
 export interface RealizedGain {
@@ -842,6 +966,10 @@ export interface RealizedGain {
   currency: string;
   fees: number;
   taxes: number;
+  /** Currency-driven part of the gain in base currency (foreign-currency sales only) */
+  fxGain?: number;
+  /** Price-driven part of the gain in base currency (foreign-currency sales only) */
+  priceGain?: number;
 }
 
 export interface GainBySecurity {
@@ -862,11 +990,26 @@ export interface RealizedGainsReport {
   totalGain: number;
   totalFees: number;
   totalTaxes: number;
+  totalFxGain: number;
   currency: string;
   entries: RealizedGain[];
   bySecurity: GainBySecurity[];
   shortTermGain: number;
   longTermGain: number;
+  /** Split by holding period of the sold FIFO lots */
+  byHoldingPeriod: GainByHoldingPeriod[];
+}
+
+/** Realized gains of lots held up to / more than one year (Spekulationsfrist) */
+export interface GainByHoldingPeriod {
+  bucket: 'short_term' | 'long_term';
+  label: string;
+  shares: number;
+  proceeds: number;
+  costBasis: number;
+  fees: number;
+  gain: number;
+  lotCount: number;
 }
 
 export interface TaxReport {
@@ -883,6 +1026,52 @@ export interface TaxReport {
   realizedGains: RealizedGainsReport;
 }
 
+export interface CashFlowPeriod {
+  /** YYYY-MM or YYYY, depending on the grouping ('total' for the totals) */
+  period: string;
+  deposits: number;
+  removals: number;
+  dividends: number;
+  /** Interest received minus interest charged */
+  interest: number;
+  /** Fees minus fee refunds */
+  fees: number;
+  /** Taxes minus tax refunds */
+  taxes: number;
+  /** Deposits minus removals */
+  netContributions: number;
+  /** Dividends + interest - fees - taxes */
+  netIncome: number;
+}
+
+export interface CashFlowReport {
+  startDate: string;
+  endDate: string;
+  grouping: 'month' | 'year';
+  baseCurrency: string;
+  periods: CashFlowPeriod[];
+  totals: CashFlowPeriod;
+}
+
+/** Projected date the net worth reaches 25× the annual spend */
+export interface FireProjection {
+  baseCurrency: string;
+  annualSpend: number;
+  /** Annual return as decimal (0.05 = 5%) */
+  expectedReturn: number;
+  targetValue: number;
+  currentNetWorth: number;
+  /** Average monthly deposits minus removals */
+  monthlyContribution: number;
+  historyStart: string | null;
+  /** null if not reached within 100 years */
+  monthsToTarget: number | null;
+  projectedDate: string | null;
+  projectedValue: number;
+  projectedContributions: number;
+  projectedReturns: number;
+}
+
 // ============================================================================
 // Monthly/Yearly Returns (Heatmap Widget)
 // ============================================================================
@@ -958,6 +1147,22 @@ export interface ExternalSearchResponse {
   errors: string[];
 }
 
+/** Metadata suggested for an ISIN (not saved until confirmed) */
+export interface SecurityEnrichment {
+  isin: string;
+  name: string | null;
+  ticker: string | null;
+  exchange: string | null;
+  currency: string | null;
+  securityType: string | null;
+  sector: string | null;
+  industry: string | null;
+  domicile: string | null;
+  feed: string | null;
+  candidates: ExternalSecuritySearchResult[];
+  errors: string[];
+}
+
 // ============================================================================
 // Corporate Actions Types
 // ============================================================================
@@ -1003,7 +1208,8 @@ export interface ApplySpinOffRequest {
   sourceSecurityId: number;
   targetSecurityId: number;
   effectiveDate: string;
-  costAllocation: number;
+  /** Cost basis allocated to the new security in percent; defaults to the fair value ratio */
+  costAllocationPercent?: number;
   shareRatio: number;
   note?: string;
 }
@@ -1014,6 +1220,8 @@ export interface CorporateActionResult {
   transactionsAdjusted: number;
   fifoLotsAdjusted: number;
   pricesAdjusted: number;
+  /** Recorded action of a merger/spin-off, used for undo */
+  actionId?: number | null;
 }
 
 // ============================================================================
@@ -1057,6 +1265,7 @@ export interface ParseResult {
   transactions: ParsedTransaction[];
   warnings: string[];
   rawText?: string;
+  provenance: TransactionProvenance[];
 }
 
 export interface SecurityMatch {
@@ -1083,6 +1292,32 @@ export interface PdfImportPreview {
   newSecurities: SecurityMatch[];
   matchedSecurities: SecurityMatch[];
   potentialDuplicates: PotentialDuplicate[];
+  /** True if the text was read via OCR (scanned PDF without text layer) */
+  ocrUsed: boolean;
+  /** Per-transaction field confidence and source lines */
+  provenance: TransactionProvenance[];
+}
+
+/** Where a parsed field came from (confidence 0..1) */
+export interface FieldProvenance {
+  field: string;
+  confidence: number;
+  source?: string;
+  line?: number;
+}
+
+export interface TransactionProvenance {
+  transactionIndex: number;
+  confidence: number;
+  fields: FieldProvenance[];
+}
+
+/** OCR credentials for the automatic fallback on scanned PDFs */
+export interface PdfOcrOptions {
+  provider: string;
+  model: string;
+  apiKey: string;
+  consentGiven: boolean;
 }
 
 export interface PdfImportResult {
@@ -1095,6 +1330,32 @@ export interface PdfImportResult {
   warnings: string[];
 }
 
+export interface PdfBatchFileResult {
+  path: string;
+  /** Detected bank, undefined if the file could not be parsed */
+  bank?: string;
+  transactionsImported: number;
+  transactionsSkipped: number;
+  securitiesCreated: number;
+  errors: string[];
+  warnings: string[];
+}
+
+export interface PdfBatchImportResult {
+  files: PdfBatchFileResult[];
+  transactionsImported: number;
+  transactionsSkipped: number;
+  filesFailed: number;
+}
+
+/** Payload of the `pdf-batch-progress` event */
+export interface PdfBatchProgress {
+  current: number;
+  total: number;
+  path: string;
+  success: boolean;
+}
+
 export interface SupportedBank {
   id: string;
   name: string;
@@ -1152,6 +1413,42 @@ export interface InvestmentPlanExecution {
   transactionId: number;
 }
 
+export interface SimulatePlanRequest {
+  securityId: number;
+  interval: string;
+  /** Contribution in cents */
+  amount: number;
+  dayOfMonth: number;
+  /** First execution (YYYY-MM-DD), default today */
+  startDate?: string;
+  /** Yearly increase of the contribution in percent (omit for flat contributions) */
+  contributionGrowthRate?: number;
+}
+
+export interface SimulatedExecution {
+  date: string;
+  /** Cents */
+  amount: number;
+  /** Scaled ×10^8 */
+  shares: number;
+  /** Cents */
+  totalInvested: number;
+  /** Scaled ×10^8 */
+  totalShares: number;
+}
+
+export interface PlanSimulation {
+  /** Average price of the last 12 months used for all executions (scaled ×10^8) */
+  assumedPrice: number;
+  executions: SimulatedExecution[];
+  /** Cents */
+  totalInvested: number;
+  /** Scaled ×10^8 */
+  totalShares: number;
+  /** Value of the shares at the assumed price (cents) */
+  projectedValue: number;
+}
+
 // ============================================================================
 // Rebalancing Types
 // ============================================================================
@@ -1172,6 +1469,15 @@ export interface RebalanceAction {
   amount: number;
   currentWeight: number;
   targetWeight: number;
+  /** Estimated FIFO gain of a SELL */
+  realizedGain?: number | null;
+}
+
+export interface RebalanceTaxEstimate {
+  realizedGain: number;
+  taxableGain: number;
+  freistellungUsed: number;
+  estimatedTax: number;
 }
 
 export interface RebalancePreview {
@@ -1181,6 +1487,7 @@ export interface RebalancePreview {
   actions: RebalanceAction[];
   deviationBefore: number;
   deviationAfter: number;
+  taxEstimate: RebalanceTaxEstimate;
 }
 
 export interface AiRebalanceTargetSuggestion {
@@ -1225,6 +1532,8 @@ export interface BenchmarkDataPoint {
   portfolioReturn: number;
   benchmarkValue: number;
   benchmarkReturn: number;
+  /** Cumulative outperformance in percentage points */
+  outperformance: number;
 }
 
 // ============================================================================
@@ -1245,6 +1554,9 @@ export interface AggregatedHolding {
   customLogo?: string;
   ticker?: string;
   latestPriceDate?: string;
+  /** Set when grouped by portfolio */
+  portfolioId?: number | null;
+  portfolioName?: string | null;
 }
 
 // ============================================================================
@@ -1545,7 +1857,9 @@ export type AlertType =
   | 'divergence'
   | 'pattern_detected'
   | 'support_break'
-  | 'resistance_break';
+  | 'resistance_break'
+  | 'price_pct_change'
+  | 'trailing_stop';
 
 export interface PriceAlert {
   id: number;
@@ -1563,6 +1877,10 @@ export interface PriceAlert {
   lastTriggeredPrice?: number;
   note?: string;
   createdAt: string;
+  /** Price captured at creation (price_pct_change, targetValue is the percentage) */
+  referencePrice?: number;
+  /** Highest price since creation (trailing_stop, targetValue is the distance in %) */
+  highWaterMark?: number;
 }
 
 export interface CreateAlertRequest {
@@ -1585,6 +1903,7 @@ export interface TriggeredAlert {
   alert: PriceAlert;
   currentPrice: number;
   triggerReason: string;
+  referencePrice?: number;
 }
 
 // ============================================================================
@@ -1626,6 +1945,10 @@ export interface AllocationAlert {
   severity: 'warning' | 'critical';
   securityId?: number;
   classificationId?: number;
+  /** Breached allocation target */
+  targetId: number;
+  /** Threshold of the target as decimal */
+  threshold: number;
 }
 
 export interface AllocationAlertCount {
@@ -1694,6 +2017,24 @@ export interface SecurityWithAttribute {
   value?: string;
 }
 
+/** Attribute comparison (case-insensitive), default 'contains' */
+export type AttributeMatchMode = 'exact' | 'contains';
+
+/** One condition of an attribute search (all conditions must match) */
+export interface AttributeFilter {
+  attributeTypeId: number;
+  value: string;
+  matchMode?: AttributeMatchMode;
+}
+
+/** Security matching an attribute search, with all its attribute values */
+export interface SecurityAttributeMatch {
+  securityId: number;
+  securityName: string;
+  isin?: string;
+  attributes: AttributeValue[];
+}
+
 // ============================================================================
 // Consortium (Portfolio Groups)
 // ============================================================================
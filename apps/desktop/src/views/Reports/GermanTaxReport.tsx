@@ -290,12 +290,14 @@ export function GermanTaxReportView({ year }: Props) {
                 <td className="py-2 text-right">{formatCurrency(report.totalTaxableIncome)}</td>
               </tr>
               <tr className="border-b border-border">
-                <td className="py-2 text-muted-foreground">- Freistellungsauftrag</td>
-                <td className="py-2 text-right text-green-600">-{formatCurrency(report.freistellungUsed)}</td>
+                <td className="py-2 text-muted-foreground">- Verlustverrechnung</td>
+                <td className="py-2 text-right text-green-600">
+                  -{formatCurrency(report.lossPots.aktienUsed + report.lossPots.sonstigeUsed)}
+                </td>
               </tr>
               <tr className="border-b border-border">
-                <td className="py-2 text-muted-foreground">- Verlustverrechnung</td>
-                <td className="py-2 text-right text-green-600">-{formatCurrency(report.realizedLosses)}</td>
+                <td className="py-2 text-muted-foreground">- Freistellungsauftrag</td>
+                <td className="py-2 text-right text-green-600">-{formatCurrency(report.freistellungUsed)}</td>
               </tr>
               <tr className="border-b border-border font-medium">
                 <td className="py-2">= Steuerpflichtige Erträge</td>
@@ -330,6 +332,49 @@ export function GermanTaxReportView({ year }: Props) {
         </div>
       </div>
 
+      {/* Loss Pots */}
+      <div className="bg-card rounded-lg border border-border">
+        <div className="p-4 border-b border-border">
+          <h3 className="font-medium">Verlustverrechnungstöpfe</h3>
+        </div>
+        <div className="p-4">
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="border-b border-border text-muted-foreground">
+                <th className="py-2 text-left font-normal"></th>
+                <th className="py-2 text-right font-normal">Aktien</th>
+                <th className="py-2 text-right font-normal">Sonstige</th>
+              </tr>
+            </thead>
+            <tbody>
+              <tr className="border-b border-border">
+                <td className="py-2 text-muted-foreground">Vortrag aus Vorjahren</td>
+                <td className="py-2 text-right">{formatCurrency(report.lossPots.aktienCarryIn)}</td>
+                <td className="py-2 text-right">{formatCurrency(report.lossPots.sonstigeCarryIn)}</td>
+              </tr>
+              <tr className="border-b border-border">
+                <td className="py-2 text-muted-foreground">+ Verluste {year}</td>
+                <td className="py-2 text-right">{formatCurrency(report.lossPots.aktienLosses)}</td>
+                <td className="py-2 text-right">{formatCurrency(report.lossPots.sonstigeLosses)}</td>
+              </tr>
+              <tr className="border-b border-border">
+                <td className="py-2 text-muted-foreground">- Verrechnet</td>
+                <td className="py-2 text-right text-green-600">-{formatCurrency(report.lossPots.aktienUsed)}</td>
+                <td className="py-2 text-right text-green-600">-{formatCurrency(report.lossPots.sonstigeUsed)}</td>
+              </tr>
+              <tr className="font-medium">
+                <td className="py-2">= Verlustvortrag ins Folgejahr</td>
+                <td className="py-2 text-right text-red-600">{formatCurrency(report.lossPots.aktienCarryforward)}</td>
+                <td className="py-2 text-right text-red-600">{formatCurrency(report.lossPots.sonstigeCarryforward)}</td>
+              </tr>
+            </tbody>
+          </table>
+          <p className="text-xs text-muted-foreground mt-2">
+            Aktienverluste werden nur mit Aktiengewinnen verrechnet.
+          </p>
+        </div>
+      </div>
+
       {/* Anlage KAP */}
       <div className="bg-card rounded-lg border border-border">
         <div className="p-4 border-b border-border flex items-center justify-between">
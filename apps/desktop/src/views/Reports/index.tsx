@@ -293,6 +293,44 @@ export function ReportsView() {
           </div>
         )}
 
+        {/* Withholding Tax by Country */}
+        {dividendData.byCountry.length > 0 && (
+          <div className="bg-card rounded-lg border border-border">
+            <div className="p-4 border-b border-border flex items-center justify-between">
+              <h3 className="font-semibold">Quellensteuer nach Land (erwartet)</h3>
+              <span className="text-sm text-muted-foreground">
+                Erstattungsfähig: {formatCurrency(dividendData.totalReclaimable, dividendData.currency)}
+              </span>
+            </div>
+            <table className="w-full text-sm">
+              <thead className="bg-muted/50">
+                <tr>
+                  <th className="text-left py-2 px-4 font-medium">Land</th>
+                  <th className="text-right py-2 px-4 font-medium">Satz</th>
+                  <th className="text-right py-2 px-4 font-medium">Brutto</th>
+                  <th className="text-right py-2 px-4 font-medium">Quellensteuer</th>
+                  <th className="text-right py-2 px-4 font-medium">Erstattungsfähig</th>
+                </tr>
+              </thead>
+              <tbody>
+                {dividendData.byCountry.map((c) => (
+                  <tr key={c.country ?? 'unknown'} className="border-t border-border">
+                    <td className="py-2 px-4">{c.country ?? 'Unbekannt'}</td>
+                    <td className="py-2 px-4 text-right">{(c.withholdingRate * 100).toFixed(2)}%</td>
+                    <td className="py-2 px-4 text-right">{formatCurrency(c.totalGross, dividendData.currency)}</td>
+                    <td className="py-2 px-4 text-right text-red-600">
+                      -{formatCurrency(c.expectedWithholding, dividendData.currency)}
+                    </td>
+                    <td className="py-2 px-4 text-right text-green-600">
+                      {formatCurrency(c.reclaimable, dividendData.currency)}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          </div>
+        )}
+
         {/* Entries Table */}
         {dividendData.entries.length > 0 && (
           <div className="bg-card rounded-lg border border-border">
@@ -248,8 +248,8 @@ export function SecuritiesView() {
   const loadSecurityTransactions = useCallback(async (securityId: number) => {
     setIsLoadingTransactions(true);
     try {
-      const txns = await getTransactions({ securityId });
-      setSecurityTransactions(txns);
+      const page = await getTransactions({ securityId });
+      setSecurityTransactions(page.transactions);
     } catch (err) {
       console.error('Failed to load transactions:', err);
       setSecurityTransactions([]);
@@ -92,8 +92,8 @@ export function TransactionsView() {
     setIsLoading(true);
     setError(null);
     try {
-      const [transactions, secs, holdings] = await Promise.all([
-        invoke<TransactionData[]>('get_transactions', {
+      const [page, secs, holdings] = await Promise.all([
+        invoke<{ transactions: TransactionData[]; totalCount: number }>('get_transactions', {
           ownerType: null,
           ownerId: null,
           securityId: null,
@@ -103,7 +103,7 @@ export function TransactionsView() {
         getSecurities(),
         getAllHoldings(),
       ]);
-      setDbTransactions(transactions);
+      setDbTransactions(page.transactions);
       setSecurities(secs.map((s) => ({ id: s.id, uuid: s.uuid, name: s.name, ticker: s.ticker })));
       // Build set of security IDs that are in holdings (flatMap because AggregatedHolding has securityIds array)
       const holdingIds = new Set(holdings.filter(h => h.totalShares > 0).flatMap(h => h.securityIds));
@@ -88,9 +88,12 @@ export interface PortfolioData {
 }
 
 export interface PortfolioHolding {
+  portfolioId: number;
   portfolioName: string;
   shares: number;
   value: number | null;
+  /** Einstandswert of the FIFO lots in this portfolio */
+  costBasis: number;
 }
 
 export interface AggregatedHolding {
@@ -112,8 +115,12 @@ export interface AggregatedHolding {
   gainLossPercent: number | null;
   /** ΣDiv Seit (total dividends received for this position) */
   dividendsTotal: number;
+  /** Per-portfolio breakdown (combined view only) */
   portfolios: PortfolioHolding[];
   customLogo?: string; // Base64-encoded custom logo
+  /** Set when grouped by portfolio (groupBy: 'portfolio') */
+  portfolioId: number | null;
+  portfolioName: string | null;
 }
 
 // ============================================================================